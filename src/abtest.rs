@@ -0,0 +1,312 @@
+//! Template A/B testing
+//!
+//! Runs two versions of a template against the same targets and classifies
+//! each target by whether the versions agree. Used by `cxg scan --ab-test` to
+//! check that a refactored template still reports what the original did.
+
+use crate::template::Template;
+use crate::types::{Context, Finding, Target};
+use futures::future::join;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Comparison outcome for a single target
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AbOutcome {
+    /// Both versions fired, or both stayed silent
+    Agreement,
+    /// Only template A fired
+    Disagreement,
+    /// Only template B fired
+    Regression,
+}
+
+/// Per-target comparison between template A and B
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbTargetResult {
+    /// Target address (with port, if any)
+    pub target: String,
+    /// Comparison outcome
+    pub outcome: AbOutcome,
+    /// Findings reported by template A
+    pub findings_a: Vec<Finding>,
+    /// Findings reported by template B
+    pub findings_b: Vec<Finding>,
+    /// Error from template A, if execution failed
+    pub error_a: Option<String>,
+    /// Error from template B, if execution failed
+    pub error_b: Option<String>,
+    /// Line diff of the evidence (`-` only in A, `+` only in B) for non-agreements
+    pub evidence_diff: Vec<String>,
+}
+
+/// Result of comparing two template versions across all targets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbTestResult {
+    /// Template A ID
+    pub template_a: String,
+    /// Template B ID
+    pub template_b: String,
+    /// Per-target results
+    pub targets: Vec<AbTargetResult>,
+}
+
+impl AbTestResult {
+    fn count(&self, outcome: AbOutcome) -> usize {
+        self.targets.iter().filter(|t| t.outcome == outcome).count()
+    }
+
+    /// Targets where both versions agree
+    pub fn agreements(&self) -> usize {
+        self.count(AbOutcome::Agreement)
+    }
+
+    /// Targets where only template A fired
+    pub fn disagreements(&self) -> usize {
+        self.count(AbOutcome::Disagreement)
+    }
+
+    /// Targets where only template B fired
+    pub fn regressions(&self) -> usize {
+        self.count(AbOutcome::Regression)
+    }
+
+    /// Whether both versions behaved the same on every target
+    pub fn is_equivalent(&self) -> bool {
+        self.targets
+            .iter()
+            .all(|t| t.outcome == AbOutcome::Agreement)
+    }
+}
+
+/// Classify the findings of both template versions for one target
+pub fn compare_findings(
+    target: &str,
+    findings_a: &[Finding],
+    findings_b: &[Finding],
+) -> AbTargetResult {
+    let outcome = match (findings_a.is_empty(), findings_b.is_empty()) {
+        (false, true) => AbOutcome::Disagreement,
+        (true, false) => AbOutcome::Regression,
+        _ => AbOutcome::Agreement,
+    };
+
+    let evidence_diff = if outcome == AbOutcome::Agreement {
+        Vec::new()
+    } else {
+        diff_lines(&evidence_lines(findings_a), &evidence_lines(findings_b))
+    };
+
+    AbTargetResult {
+        target: target.to_string(),
+        outcome,
+        findings_a: findings_a.to_vec(),
+        findings_b: findings_b.to_vec(),
+        error_a: None,
+        error_b: None,
+        evidence_diff,
+    }
+}
+
+/// Run both template versions against every target and compare the results
+///
+/// Execution errors are recorded on the target result and treated as "no
+/// findings" for classification.
+pub async fn run_ab_test(
+    template_a: &dyn Template,
+    template_b: &dyn Template,
+    targets: &[Target],
+    context: &Context,
+) -> AbTestResult {
+    let mut results = Vec::with_capacity(targets.len());
+
+    for target in targets {
+        let (result_a, result_b) = join(
+            template_a.execute(target, context),
+            template_b.execute(target, context),
+        )
+        .await;
+
+        let (findings_a, error_a) = split_result(result_a);
+        let (findings_b, error_b) = split_result(result_b);
+
+        let mut result = compare_findings(&target.url(), &findings_a, &findings_b);
+        result.error_a = error_a;
+        result.error_b = error_b;
+        results.push(result);
+    }
+
+    AbTestResult {
+        template_a: template_a.metadata().id.clone(),
+        template_b: template_b.metadata().id.clone(),
+        targets: results,
+    }
+}
+
+fn split_result(result: crate::error::Result<Vec<Finding>>) -> (Vec<Finding>, Option<String>) {
+    match result {
+        Ok(findings) => (findings, None),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    }
+}
+
+/// Render the comparable parts of findings as sorted, de-duplicated lines
+fn evidence_lines(findings: &[Finding]) -> Vec<String> {
+    let mut lines = BTreeSet::new();
+    for finding in findings {
+        lines.insert(format!("title: {}", finding.title));
+        lines.insert(format!("severity: {}", finding.severity));
+        for pattern in &finding.evidence.matched_patterns {
+            lines.insert(format!("matched: {}", pattern));
+        }
+        if let Some(request) = &finding.evidence.request {
+            lines.insert(format!("request: {}", request));
+        }
+        if let Some(response) = &finding.evidence.response {
+            lines.insert(format!("response: {}", response));
+        }
+        for (key, value) in &finding.evidence.data {
+            lines.insert(format!("data.{}: {}", key, value));
+        }
+    }
+    lines.into_iter().collect()
+}
+
+fn diff_lines(a: &[String], b: &[String]) -> Vec<String> {
+    let removed = a
+        .iter()
+        .filter(|l| !b.contains(l))
+        .map(|l| format!("- {}", l));
+    let added = b
+        .iter()
+        .filter(|l| !a.contains(l))
+        .map(|l| format!("+ {}", l));
+    removed.chain(added).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use crate::types::{AuthorInfo, Protocol, Severity, TemplateLanguage, TemplateMetadata};
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+
+    /// Fires when every matcher word appears in the target's canned response
+    struct WordTemplate {
+        metadata: TemplateMetadata,
+        words: Vec<&'static str>,
+        responses: HashMap<&'static str, &'static str>,
+    }
+
+    #[async_trait]
+    impl Template for WordTemplate {
+        fn metadata(&self) -> &TemplateMetadata {
+            &self.metadata
+        }
+
+        async fn execute(&self, target: &Target, _context: &Context) -> Result<Vec<Finding>> {
+            let body = self
+                .responses
+                .get(target.address.as_str())
+                .copied()
+                .unwrap_or("");
+            if !self.words.iter().all(|w| body.contains(w)) {
+                return Ok(Vec::new());
+            }
+            let mut finding = Finding::new(
+                target.address.as_str(),
+                self.metadata.id.as_str(),
+                Severity::High,
+                "Redis exposed",
+                "",
+            );
+            finding.evidence.matched_patterns = self.words.iter().map(|w| w.to_string()).collect();
+            Ok(vec![finding])
+        }
+    }
+
+    fn word_template(id: &str, words: Vec<&'static str>) -> WordTemplate {
+        let responses = HashMap::from([
+            ("both", "redis_version:7.0 role:master"),
+            ("only-version", "redis_version:7.0"),
+            ("only-role", "role:master"),
+            ("none", "HTTP/1.1 404"),
+        ]);
+        WordTemplate {
+            metadata: TemplateMetadata {
+                id: id.to_string(),
+                name: id.to_string(),
+                author: AuthorInfo {
+                    name: "Test".to_string(),
+                    email: None,
+                    github: None,
+                },
+                severity: Severity::High,
+                description: String::new(),
+                cve_ids: Vec::new(),
+                cwe_ids: Vec::new(),
+                references: Vec::new(),
+                cvss_score: None,
+                tags: Vec::new(),
+                language: TemplateLanguage::Yaml,
+                file_path: std::path::PathBuf::from(format!("{}.yaml", id)),
+                created: chrono::Utc::now(),
+                updated: chrono::Utc::now(),
+                version: "1.0".to_string(),
+                confidence: None,
+                rate_limit: None,
+                max_requests: None,
+                deprecated: false,
+                superseded_by: None,
+                created_date: None,
+                depends_on: Vec::new(),
+            },
+            words,
+            responses,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ab_test_classifies_targets() {
+        // B swaps one matcher: "role:master" instead of "redis_version"
+        let a = word_template("redis-a", vec!["redis_version"]);
+        let b = word_template("redis-b", vec!["role:master"]);
+        let targets: Vec<Target> = ["both", "only-version", "only-role", "none"]
+            .iter()
+            .map(|addr| Target::new(*addr, Protocol::Tcp))
+            .collect();
+
+        let result = run_ab_test(&a, &b, &targets, &Context::default()).await;
+        let outcomes: Vec<AbOutcome> = result.targets.iter().map(|t| t.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                AbOutcome::Agreement,
+                AbOutcome::Disagreement,
+                AbOutcome::Regression,
+                AbOutcome::Agreement,
+            ]
+        );
+        assert_eq!(result.agreements(), 2);
+        assert_eq!(result.disagreements(), 1);
+        assert_eq!(result.regressions(), 1);
+        assert!(!result.is_equivalent());
+
+        assert!(result.targets[1]
+            .evidence_diff
+            .contains(&"- matched: redis_version".to_string()));
+        assert!(result.targets[2]
+            .evidence_diff
+            .contains(&"+ matched: role:master".to_string()));
+        assert!(result.targets[0].evidence_diff.is_empty());
+    }
+
+    #[test]
+    fn test_compare_findings_silent_is_agreement() {
+        let result = compare_findings("host", &[], &[]);
+        assert_eq!(result.outcome, AbOutcome::Agreement);
+        assert!(result.evidence_diff.is_empty());
+    }
+}
@@ -3,6 +3,7 @@
 //! Handles loading, saving, and managing AI provider configurations.
 //! Configuration file location: `~/.cert-x-gen/ai-config.yaml`
 
+use crate::secrets::{Secret, SecretKey};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -44,9 +45,9 @@ pub struct ProviderConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub endpoint: Option<String>,
 
-    /// API key (supports environment variable substitution)
+    /// API key (supports environment variable substitution and `age:` encryption)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub api_key: Option<String>,
+    pub api_key: Option<Secret<String>>,
 
     /// Model name to use
     pub model: String,
@@ -153,6 +154,13 @@ impl Default for CacheConfig {
     }
 }
 
+impl ProviderConfig {
+    /// Get the API key as a plain string
+    pub fn api_key_value(&self) -> Option<String> {
+        self.api_key.as_ref().map(|k| k.expose().clone())
+    }
+}
+
 impl AIConfig {
     /// Load configuration from default location
     pub fn load() -> Result<Self> {
@@ -171,6 +179,11 @@ impl AIConfig {
         let mut config: AIConfig =
             serde_yaml::from_str(&content).with_context(|| "Failed to parse AI configuration")?;
 
+        if config.has_encrypted_secrets() {
+            let key = SecretKey::load_or_generate(SecretKey::default_path())?;
+            config.decrypt_secrets(&key)?;
+        }
+
         // Expand environment variables in API keys
         config.expand_env_vars();
 
@@ -215,14 +228,14 @@ impl AIConfig {
     fn expand_env_vars(&mut self) {
         for (name, provider) in self.providers.iter_mut() {
             // Clone the api_key to avoid borrow checker issues
-            let api_key_template = provider.api_key.clone();
+            let api_key_template = provider.api_key.clone().map(Secret::into_inner);
 
             if let Some(api_key) = api_key_template {
                 if api_key.starts_with("${") && api_key.ends_with("}") {
                     let env_var = &api_key[2..api_key.len() - 1];
                     if let Ok(value) = std::env::var(env_var) {
                         if !value.is_empty() {
-                            provider.api_key = Some(value);
+                            provider.api_key = Some(Secret::new(value));
                             // Auto-enable provider when API key is available
                             provider.enabled = true;
                             tracing::debug!(
@@ -237,6 +250,37 @@ impl AIConfig {
         }
     }
 
+    /// Check whether any provider API key is `age:` encrypted
+    pub fn has_encrypted_secrets(&self) -> bool {
+        self.providers
+            .values()
+            .filter_map(|p| p.api_key.as_ref())
+            .any(|k| k.is_encrypted())
+    }
+
+    /// Decrypt all `age:` encrypted provider API keys in place
+    pub fn decrypt_secrets(&mut self, key: &SecretKey) -> Result<()> {
+        for provider in self.providers.values_mut() {
+            if let Some(api_key) = provider.api_key.as_mut() {
+                api_key.decrypt(key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encrypt all plain-text provider API keys in place, returning how many changed
+    pub fn encrypt_secrets(&mut self, key: &SecretKey) -> Result<usize> {
+        let mut count = 0;
+        for provider in self.providers.values_mut() {
+            if let Some(api_key) = provider.api_key.as_mut() {
+                if api_key.encrypt(key)? {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
     /// Get provider configuration by name
     pub fn get_provider(&self, name: &str) -> Option<&ProviderConfig> {
         self.providers.get(name)
@@ -281,7 +325,7 @@ impl AIConfig {
             ProviderConfig {
                 enabled: false,
                 endpoint: None,
-                api_key: Some("${OPENAI_API_KEY}".to_string()),
+                api_key: Some(Secret::from("${OPENAI_API_KEY}")),
                 model: "gpt-4".to_string(),
                 max_tokens: Some(4000),
                 temperature: Some(0.7),
@@ -295,7 +339,7 @@ impl AIConfig {
             ProviderConfig {
                 enabled: false,
                 endpoint: None,
-                api_key: Some("${ANTHROPIC_API_KEY}".to_string()),
+                api_key: Some(Secret::from("${ANTHROPIC_API_KEY}")),
                 model: "claude-3-5-sonnet-20241022".to_string(),
                 max_tokens: Some(4000),
                 temperature: Some(0.7),
@@ -309,7 +353,7 @@ impl AIConfig {
             ProviderConfig {
                 enabled: false,
                 endpoint: None,
-                api_key: Some("${DEEPSEEK_API_KEY}".to_string()),
+                api_key: Some(Secret::from("${DEEPSEEK_API_KEY}")),
                 model: "deepseek-coder".to_string(),
                 max_tokens: Some(4000),
                 temperature: Some(0.7),
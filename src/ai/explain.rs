@@ -0,0 +1,177 @@
+//! LLM explanations for validation diagnostics
+//!
+//! `cxg template validate --explain` (and `cxg ai explain`) send a template's
+//! source and its structured diagnostics to the AI provider and print, per
+//! diagnostic, what it means and a suggested fix as a unified diff. Patches
+//! are only shown, never applied.
+
+use crate::types::TemplateLanguage;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::validator::TemplateDiagnostic;
+
+/// Template characters included in the prompt; longer files are truncated
+pub const MAX_EXPLAIN_CODE_CHARS: usize = 12_000;
+
+/// What the provider had to say about one diagnostic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticExplanation {
+    /// Diagnostic code, e.g. `python.missing_finding_output`
+    pub code: String,
+    /// Plain-language explanation of the problem
+    pub explanation: String,
+    /// Suggested fix as a unified diff, when the provider gave a usable one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+}
+
+/// Build the prompt asking for one explanation per diagnostic
+pub fn build_explain_prompt(
+    code: &str,
+    language: TemplateLanguage,
+    diagnostics: &[TemplateDiagnostic],
+    file_name: &str,
+) -> String {
+    let mut prompt = format!(
+        "You are reviewing a CERT-X-GEN security scanning template written in {}.\n\
+         The template validator reported the diagnostics below. For EACH diagnostic, \
+         explain to a junior template author what it means and why it matters, then \
+         suggest the smallest fix.\n\n\
+         Diagnostics:\n",
+        language
+    );
+    for diagnostic in diagnostics {
+        let location = match (diagnostic.line, diagnostic.column) {
+            (Some(line), Some(column)) => format!(" (line {}, column {})", line, column),
+            (Some(line), None) => format!(" (line {})", line),
+            _ => String::new(),
+        };
+        prompt.push_str(&format!(
+            "- [{:?}] {}{}: {}\n",
+            diagnostic.severity, diagnostic.code, location, diagnostic.message
+        ));
+    }
+
+    let truncated: String = code.chars().take(MAX_EXPLAIN_CODE_CHARS).collect();
+    prompt.push_str(&format!(
+        "\nTemplate `{}` (line numbers added for reference, not part of the file):\n```\n",
+        file_name
+    ));
+    for (n, line) in truncated.lines().enumerate() {
+        prompt.push_str(&format!("{:>4} | {}\n", n + 1, line));
+    }
+    if truncated.len() < code.len() {
+        prompt.push_str("... (truncated)\n");
+    }
+    prompt.push_str("```\n");
+
+    prompt.push_str(&format!(
+        "\nRespond with ONLY a JSON object, no prose around it:\n\
+         {{\"explanations\": [{{\"code\": \"<diagnostic code>\", \
+         \"explanation\": \"<2-4 sentences>\", \
+         \"patch\": \"<unified diff against a/{name} and b/{name}, or empty>\"}}]}}\n\
+         Use the diagnostic codes exactly as listed. Patches must be valid unified \
+         diffs with ---, +++ and @@ headers and must not change unrelated lines.",
+        name = file_name
+    ));
+    prompt
+}
+
+/// Extract explanations from the provider's response
+///
+/// Tolerates markdown fences and text around the JSON object. Explanations
+/// for codes that were not asked about are dropped, as are patches that are
+/// not unified diffs.
+pub fn parse_explanations(
+    response: &str,
+    diagnostics: &[TemplateDiagnostic],
+) -> Result<Vec<DiagnosticExplanation>> {
+    #[derive(Deserialize)]
+    struct Response {
+        explanations: Vec<DiagnosticExplanation>,
+    }
+
+    let start = response
+        .find('{')
+        .context("AI response contains no JSON object")?;
+    let end = response
+        .rfind('}')
+        .filter(|&end| end > start)
+        .context("AI response contains no JSON object")?;
+    let parsed: Response = serde_json::from_str(&response[start..=end])
+        .context("AI response is not valid explanation JSON")?;
+
+    Ok(parsed
+        .explanations
+        .into_iter()
+        .filter(|e| diagnostics.iter().any(|d| d.code == e.code))
+        .map(|mut e| {
+            e.patch = e.patch.filter(|patch| is_unified_diff(patch));
+            e
+        })
+        .collect())
+}
+
+/// Whether `patch` has the file and hunk headers of a unified diff
+fn is_unified_diff(patch: &str) -> bool {
+    let mut lines = patch.lines().map(str::trim_end);
+    lines.clone().any(|l| l.starts_with("--- "))
+        && lines.clone().any(|l| l.starts_with("+++ "))
+        && lines.any(|l| l.starts_with("@@"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn missing_output() -> TemplateDiagnostic {
+        let mut diagnostic = TemplateDiagnostic::error(
+            "python.missing_finding_output",
+            "Template never prints findings as JSON",
+        );
+        diagnostic.line = Some(3);
+        diagnostic
+    }
+
+    #[test]
+    fn test_prompt_lists_diagnostics_and_caps_code() {
+        let code = "x = 1\n".repeat(MAX_EXPLAIN_CODE_CHARS);
+        let prompt = build_explain_prompt(
+            &code,
+            TemplateLanguage::Python,
+            &[missing_output()],
+            "check.py",
+        );
+
+        assert!(prompt.contains("python.missing_finding_output (line 3)"));
+        assert!(prompt.contains("   1 | x = 1"));
+        assert!(prompt.contains("... (truncated)"));
+        assert!(prompt.contains("a/check.py"));
+        assert!(prompt.len() < code.len());
+    }
+
+    #[test]
+    fn test_parse_keeps_known_codes_and_valid_patches() {
+        let response = r#"Here you go:
+```json
+{"explanations": [
+  {"code": "python.missing_finding_output",
+   "explanation": "The engine reads findings from stdout.",
+   "patch": "--- a/check.py\n+++ b/check.py\n@@ -1,1 +1,2 @@\n x = 1\n+print(json.dumps([]))\n"},
+  {"code": "made.up", "explanation": "Not asked for."}
+]}
+```"#;
+        let explanations = parse_explanations(response, &[missing_output()]).unwrap();
+        assert_eq!(explanations.len(), 1);
+        assert_eq!(explanations[0].code, "python.missing_finding_output");
+        assert!(explanations[0].patch.as_deref().unwrap().contains("+print"));
+
+        let response = r#"{"explanations": [{"code": "python.missing_finding_output",
+            "explanation": "Print findings.", "patch": "just add a print call"}]}"#;
+        let explanations = parse_explanations(response, &[missing_output()]).unwrap();
+        assert_eq!(explanations[0].patch, None);
+
+        assert!(parse_explanations("no json here", &[missing_output()]).is_err());
+    }
+}
@@ -10,13 +10,16 @@ use std::path::PathBuf;
 use tracing::{debug, info};
 
 use super::config::AIConfig;
+use super::cve::NvdClient;
+use super::explain::{build_explain_prompt, parse_explanations, DiagnosticExplanation};
 use super::parser::ResponseParser;
 use super::prompt::PromptBuilder;
 use super::providers::{
-    AnthropicProvider, DeepSeekProvider, GenerationOptions, LLMProvider, OllamaProvider,
-    OpenAIProvider, ProviderHealthStatus,
+    count_tokens, AnthropicProvider, DeepSeekProvider, GenerationOptions, LLMProvider,
+    OllamaProvider, OpenAIProvider, ProviderHealthStatus,
 };
-use super::validator::TemplateValidator;
+use super::selection::{choose_model, GenerationTask, DEFAULT_RESPONSE_TOKENS};
+use super::validator::{TemplateDiagnostic, TemplateValidator};
 
 /// Main AI manager for template generation
 #[derive(Debug)]
@@ -35,6 +38,9 @@ pub struct AIManager {
 
     /// Template validator for syntax checking
     validator: TemplateValidator,
+
+    /// NVD client for CVE-driven generation
+    nvd: NvdClient,
 }
 
 impl AIManager {
@@ -65,6 +71,7 @@ impl AIManager {
             prompt_builder,
             parser,
             validator,
+            nvd: NvdClient::new(),
         })
     }
 
@@ -88,6 +95,7 @@ impl AIManager {
             prompt_builder,
             parser,
             validator,
+            nvd: NvdClient::new(),
         })
     }
 
@@ -111,6 +119,11 @@ impl AIManager {
         &self.output_dir
     }
 
+    /// Fetch CVEs with a custom NVD client (another endpoint or cache directory)
+    pub fn set_nvd_client(&mut self, client: NvdClient) {
+        self.nvd = client;
+    }
+
     /// Generate a template from a natural language prompt
     ///
     /// # Arguments
@@ -144,6 +157,21 @@ impl AIManager {
         prompt: &str,
         language: TemplateLanguage,
         provider_name: Option<&str>,
+    ) -> Result<String> {
+        self.generate_template_with_model(prompt, language, provider_name, None)
+            .await
+    }
+
+    /// Generate a template with a specific model instead of the provider's configured one
+    ///
+    /// `model` is typically the result of [`select_model`](Self::select_model)
+    /// or `--model`; `None` uses the configured model.
+    pub async fn generate_template_with_model(
+        &self,
+        prompt: &str,
+        language: TemplateLanguage,
+        provider_name: Option<&str>,
+        model: Option<&str>,
     ) -> Result<String> {
         // Determine which provider to use
         let provider = provider_name.unwrap_or_else(|| self.config.default_provider_name());
@@ -153,6 +181,200 @@ impl AIManager {
             language, prompt, provider
         );
 
+        let llm_provider = self.create_provider(provider, model)?;
+
+        // Check if the provider is actually available
+        if !llm_provider.is_available() {
+            anyhow::bail!(
+                "Provider '{}' is not available. For Ollama, ensure it's running with: ollama serve",
+                provider
+            );
+        }
+
+        // Build a context-aware prompt using the PromptBuilder (Task 1.4)
+        info!("Building context-aware prompt for {} template...", language);
+        let llm_prompt = self
+            .prompt_builder
+            .build_generation_prompt(prompt, language);
+
+        debug!("Generated prompt length: {} chars", llm_prompt.len());
+
+        // Configure generation options from provider config
+        let provider_config = self.config.get_provider(provider).unwrap();
+        let options = GenerationOptions {
+            max_tokens: provider_config.max_tokens,
+            temperature: provider_config.temperature,
+            timeout: provider_config
+                .timeout_secs
+                .map(std::time::Duration::from_secs),
+        };
+
+        // Generate the template
+        info!("Calling LLM provider for generation...");
+        let generated_code = llm_provider.generate(&llm_prompt, options).await?; // Show actual error
+
+        info!(
+            "Template generated successfully ({} chars)",
+            generated_code.len()
+        );
+
+        // Task 1.5: Parse the response to extract clean template code
+        info!("Parsing response to extract clean template code...");
+        let parsed_code = self
+            .parser
+            .parse(&generated_code, language)
+            .context("Failed to parse LLM response")?;
+
+        debug!("Parsed template length: {} chars", parsed_code.len());
+
+        // Task 1.6: Validate the template
+        info!("Validating template syntax and structure...");
+        self.validator
+            .validate(&parsed_code, language)
+            .context("Template validation failed")?;
+
+        info!("Template validation passed successfully!");
+
+        Ok(parsed_code)
+    }
+
+    /// Ask the default provider to explain validation diagnostics
+    ///
+    /// Returns one explanation per diagnostic the provider addressed, each
+    /// with a suggested unified-diff patch when it gave a usable one. Fails
+    /// when the provider is unavailable, so callers can fall back to the
+    /// plain diagnostics.
+    pub async fn explain_diagnostics(
+        &self,
+        code: &str,
+        language: TemplateLanguage,
+        diagnostics: &[TemplateDiagnostic],
+        file_name: &str,
+    ) -> Result<Vec<DiagnosticExplanation>> {
+        if diagnostics.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let provider = self.config.default_provider_name();
+        let llm_provider = self.create_provider(provider, None)?;
+        if !llm_provider.is_available() {
+            anyhow::bail!("Provider '{}' is not available", provider);
+        }
+
+        let prompt = build_explain_prompt(code, language, diagnostics, file_name);
+        debug!("Explain prompt length: {} chars", prompt.len());
+
+        let provider_config = self.config.get_provider(provider).unwrap();
+        let options = GenerationOptions {
+            max_tokens: provider_config.max_tokens,
+            temperature: provider_config.temperature,
+            timeout: provider_config
+                .timeout_secs
+                .map(std::time::Duration::from_secs),
+        };
+
+        info!(
+            "Asking {} to explain {} diagnostic(s)",
+            provider,
+            diagnostics.len()
+        );
+        let response = llm_provider.generate(&prompt, options).await?;
+        parse_explanations(&response, diagnostics)
+    }
+
+    /// Estimate what generating a template would cost with a provider
+    ///
+    /// Builds the same prompt as [`generate_template`](Self::generate_template)
+    /// and asks the provider to price it. Returns `None` for providers without
+    /// per-token pricing (local models such as Ollama).
+    pub fn estimate_generation_cost(
+        &self,
+        prompt: &str,
+        language: TemplateLanguage,
+        provider_name: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<Option<f64>> {
+        let provider = provider_name.unwrap_or_else(|| self.config.default_provider_name());
+        let llm_provider = self.create_provider(provider, model)?;
+        let llm_prompt = self
+            .prompt_builder
+            .build_generation_prompt(prompt, language);
+
+        Ok(llm_provider.estimate_cost(&llm_prompt))
+    }
+
+    /// Generation prompt for a CVE fetched from the NVD, with an optional PoC
+    ///
+    /// The prompt lists the affected components, the CVSS impact and the PoC
+    /// code, which makes for far more precise templates than a free-form
+    /// description.
+    pub async fn cve_prompt(&self, cve_id: &str, poc: Option<&str>) -> Result<String> {
+        let record = self.nvd.fetch(cve_id).await?;
+        Ok(record.to_prompt(poc))
+    }
+
+    /// Generate a template for a CVE, optionally guided by PoC code
+    ///
+    /// Fetches the CVE from the NVD (cached for a day), builds the prompt with
+    /// [`cve_prompt`](Self::cve_prompt) and generates as
+    /// [`generate_template_with_model`](Self::generate_template_with_model) does.
+    pub async fn generate_from_cve(
+        &self,
+        cve_id: &str,
+        poc: Option<&str>,
+        language: TemplateLanguage,
+        provider_name: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<String> {
+        let prompt = self.cve_prompt(cve_id, poc).await?;
+        self.generate_template_with_model(&prompt, language, provider_name, model)
+            .await
+    }
+
+    /// Pick a model for `task` from those its provider offers
+    ///
+    /// See [`choose_model`] for the heuristic. Falls back to the provider's
+    /// configured model when its models cannot be listed or none fits.
+    pub async fn select_model(&self, task: &GenerationTask) -> Result<String> {
+        let provider = task
+            .provider
+            .as_deref()
+            .unwrap_or_else(|| self.config.default_provider_name());
+        let provider_config = self
+            .config
+            .get_provider(provider)
+            .with_context(|| format!("Provider '{}' is not configured", provider))?;
+
+        let models = match self.create_provider(provider, None)?.list_models().await {
+            Ok(models) => models,
+            Err(e) => {
+                debug!(
+                    "Could not list {} models, using configured model: {}",
+                    provider, e
+                );
+                return Ok(provider_config.model.clone());
+            }
+        };
+
+        let llm_prompt = self
+            .prompt_builder
+            .build_generation_prompt(&task.description, task.language);
+        let required_tokens = count_tokens(&llm_prompt)
+            + provider_config
+                .max_tokens
+                .unwrap_or(DEFAULT_RESPONSE_TOKENS);
+
+        let model = choose_model(provider, task.complexity, required_tokens, &models)
+            .unwrap_or_else(|| provider_config.model.clone());
+        info!(
+            "Selected model {} for {} complexity ({} tokens needed)",
+            model, task.complexity, required_tokens
+        );
+        Ok(model)
+    }
+
+    /// Create the configured, enabled provider named `provider`, optionally with another model
+    fn create_provider(&self, provider: &str, model: Option<&str>) -> Result<Box<dyn LLMProvider>> {
         // Check if provider is configured and enabled
         if !self.config.is_provider_enabled(provider) {
             anyhow::bail!(
@@ -175,7 +397,7 @@ impl AIManager {
                     .clone()
                     .unwrap_or_else(|| "http://localhost:11434".to_string());
 
-                let model = provider_config.model.clone();
+                let model = model.map_or_else(|| provider_config.model.clone(), str::to_string);
 
                 Box::new(OllamaProvider::new(endpoint, model))
             }
@@ -185,11 +407,10 @@ impl AIManager {
                     .get_provider("openai")
                     .context("OpenAI provider configuration not found")?;
 
-                let api_key = provider_config.api_key
-                    .clone()
+                let api_key = provider_config.api_key_value()
                     .context("OpenAI API key not configured. Set OPENAI_API_KEY environment variable or add to config.")?;
 
-                let model = provider_config.model.clone();
+                let model = model.map_or_else(|| provider_config.model.clone(), str::to_string);
 
                 Box::new(OpenAIProvider::new(api_key, model))
             }
@@ -199,11 +420,10 @@ impl AIManager {
                     .get_provider("anthropic")
                     .context("Anthropic provider configuration not found")?;
 
-                let api_key = provider_config.api_key
-                    .clone()
+                let api_key = provider_config.api_key_value()
                     .context("Anthropic API key not configured. Set ANTHROPIC_API_KEY environment variable or add to config.")?;
 
-                let model = provider_config.model.clone();
+                let model = model.map_or_else(|| provider_config.model.clone(), str::to_string);
 
                 Box::new(AnthropicProvider::new(api_key, model))
             }
@@ -214,11 +434,10 @@ impl AIManager {
                     .context("DeepSeek provider configuration not found")?;
 
                 let api_key = provider_config
-                    .api_key
-                    .clone()
+                    .api_key_value()
                     .context("DeepSeek API key not configured")?;
 
-                let model = provider_config.model.clone();
+                let model = model.map_or_else(|| provider_config.model.clone(), str::to_string);
 
                 Box::new(DeepSeekProvider::new(api_key, model))
             }
@@ -231,59 +450,7 @@ impl AIManager {
             }
         };
 
-        // Check if the provider is actually available
-        if !llm_provider.is_available() {
-            anyhow::bail!(
-                "Provider '{}' is not available. For Ollama, ensure it's running with: ollama serve",
-                provider
-            );
-        }
-
-        // Build a context-aware prompt using the PromptBuilder (Task 1.4)
-        info!("Building context-aware prompt for {} template...", language);
-        let llm_prompt = self
-            .prompt_builder
-            .build_generation_prompt(prompt, language);
-
-        debug!("Generated prompt length: {} chars", llm_prompt.len());
-
-        // Configure generation options from provider config
-        let provider_config = self.config.get_provider(provider).unwrap();
-        let options = GenerationOptions {
-            max_tokens: provider_config.max_tokens,
-            temperature: provider_config.temperature,
-            timeout: provider_config
-                .timeout_secs
-                .map(std::time::Duration::from_secs),
-        };
-
-        // Generate the template
-        info!("Calling LLM provider for generation...");
-        let generated_code = llm_provider.generate(&llm_prompt, options).await?; // Show actual error
-
-        info!(
-            "Template generated successfully ({} chars)",
-            generated_code.len()
-        );
-
-        // Task 1.5: Parse the response to extract clean template code
-        info!("Parsing response to extract clean template code...");
-        let parsed_code = self
-            .parser
-            .parse(&generated_code, language)
-            .context("Failed to parse LLM response")?;
-
-        debug!("Parsed template length: {} chars", parsed_code.len());
-
-        // Task 1.6: Validate the template
-        info!("Validating template syntax and structure...");
-        self.validator
-            .validate(&parsed_code, language)
-            .context("Template validation failed")?;
-
-        info!("Template validation passed successfully!");
-
-        Ok(parsed_code)
+        Ok(llm_provider)
     }
 
     /// List available providers
@@ -326,7 +493,7 @@ impl AIManager {
                     .get_provider("openai")
                     .context("OpenAI provider configuration not found")?;
 
-                let api_key = provider_config.api_key.clone().unwrap_or_default();
+                let api_key = provider_config.api_key_value().unwrap_or_default();
                 let model = provider_config.model.clone();
 
                 Box::new(OpenAIProvider::new(api_key, model))
@@ -337,7 +504,7 @@ impl AIManager {
                     .get_provider("anthropic")
                     .context("Anthropic provider configuration not found")?;
 
-                let api_key = provider_config.api_key.clone().unwrap_or_default();
+                let api_key = provider_config.api_key_value().unwrap_or_default();
                 let model = provider_config.model.clone();
 
                 Box::new(AnthropicProvider::new(api_key, model))
@@ -348,7 +515,7 @@ impl AIManager {
                     .get_provider("deepseek")
                     .context("DeepSeek provider configuration not found")?;
 
-                let api_key = provider_config.api_key.clone().unwrap_or_default();
+                let api_key = provider_config.api_key_value().unwrap_or_default();
                 let model = provider_config.model.clone();
 
                 Box::new(DeepSeekProvider::new(api_key, model))
@@ -362,6 +529,37 @@ impl AIManager {
         Ok(provider.is_available())
     }
 
+    /// Embed text with the default provider (ollama or openai)
+    pub async fn embed_text(&self, text: &str) -> Result<Vec<f32>> {
+        let provider = self.config.default_provider_name();
+        let provider_config = self
+            .config
+            .get_provider(provider)
+            .with_context(|| format!("Provider '{}' is not configured", provider))?;
+
+        let llm_provider: Box<dyn LLMProvider> = match provider {
+            "ollama" => {
+                let endpoint = provider_config
+                    .endpoint
+                    .clone()
+                    .unwrap_or_else(|| "http://localhost:11434".to_string());
+                Box::new(OllamaProvider::new(endpoint, provider_config.model.clone()))
+            }
+            "openai" => {
+                let api_key = provider_config
+                    .api_key_value()
+                    .context("OpenAI API key not configured. Set OPENAI_API_KEY environment variable or add to config.")?;
+                Box::new(OpenAIProvider::new(api_key, provider_config.model.clone()))
+            }
+            _ => anyhow::bail!(
+                "Provider '{}' does not support embeddings. Use ollama or openai, or TF-IDF similarity offline.",
+                provider
+            ),
+        };
+
+        llm_provider.embed(text).await
+    }
+
     /// Get current configuration
     pub fn config(&self) -> &AIConfig {
         &self.config
@@ -482,6 +680,7 @@ impl AIManager {
             TemplateLanguage::Ruby => "rb",
             TemplateLanguage::Perl => "pl",
             TemplateLanguage::Php => "php",
+            TemplateLanguage::Rhai => "rhai",
         };
 
         format!("{}-{}.{}", safe_name, timestamp, extension)
@@ -555,8 +754,7 @@ impl AIManager {
                     .get_provider("openai")
                     .ok_or_else(|| anyhow::anyhow!("OpenAI provider not configured"))?;
                 let api_key = provider_config
-                    .api_key
-                    .clone()
+                    .api_key_value()
                     .unwrap_or_else(|| "${OPENAI_API_KEY}".to_string());
                 let model = provider_config.model.clone();
 
@@ -569,8 +767,7 @@ impl AIManager {
                     .get_provider("anthropic")
                     .ok_or_else(|| anyhow::anyhow!("Anthropic provider not configured"))?;
                 let api_key = provider_config
-                    .api_key
-                    .clone()
+                    .api_key_value()
                     .unwrap_or_else(|| "${ANTHROPIC_API_KEY}".to_string());
                 let model = provider_config.model.clone();
 
@@ -583,8 +780,7 @@ impl AIManager {
                     .get_provider("deepseek")
                     .ok_or_else(|| anyhow::anyhow!("DeepSeek provider not configured"))?;
                 let api_key = provider_config
-                    .api_key
-                    .clone()
+                    .api_key_value()
                     .unwrap_or_else(|| "${DEEPSEEK_API_KEY}".to_string());
                 let model = provider_config.model.clone();
 
@@ -679,6 +875,7 @@ mod tests {
         assert!(builder.has_skeleton(TemplateLanguage::Ruby));
         assert!(builder.has_skeleton(TemplateLanguage::Perl));
         assert!(builder.has_skeleton(TemplateLanguage::Php));
+        assert!(builder.has_skeleton(TemplateLanguage::Rhai));
         assert!(builder.has_skeleton(TemplateLanguage::Shell));
 
         // YAML doesn't have a skeleton (it's declarative)
@@ -7,9 +7,12 @@
 //!
 //! - `AIManager`: Main orchestrator for template generation
 //! - `AIConfig`: Configuration management for AI providers
+//! - `cost`: `--max-cost` guard over provider cost estimates
+//! - `cve`: NVD lookups and prompts for `--from-cve` generation
 //! - `providers`: LLM provider implementations (Ollama, OpenAI, Anthropic, etc.)
 //! - `prompt`: Prompt engineering system for template generation
 //! - `parser`: Response parsing to extract clean template code
+//! - `selection`: Automatic model choice by task complexity and context window
 //! - `validator`: Template validation before saving
 //!
 //! # Example
@@ -33,18 +36,28 @@
 //! ```
 
 pub mod config;
+pub mod cost;
+pub mod cve;
+pub mod embedder;
+pub mod explain;
 pub mod manager;
 pub mod parser;
 pub mod prompt;
 pub mod providers;
+pub mod selection;
 pub mod validator;
 
 pub use config::AIConfig;
+pub use cost::{CostDecision, CostGuard};
+pub use cve::{CveRecord, NvdClient};
+pub use embedder::TemplateEmbedder;
+pub use explain::DiagnosticExplanation;
 pub use manager::AIManager;
 pub use parser::ResponseParser;
 pub use prompt::PromptBuilder;
 pub use providers::{
-    AuthStatus, ConnectionStatus, DeepSeekProvider, GenerationOptions, LLMProvider, ModelInfo,
-    OllamaProvider, ProviderHealthStatus,
+    count_tokens, AuthStatus, ConnectionStatus, DeepSeekProvider, GenerationOptions, LLMProvider,
+    ModelInfo, OllamaProvider, ProviderHealthStatus,
 };
+pub use selection::{Complexity, GenerationTask};
 pub use validator::TemplateValidator;
@@ -105,6 +105,7 @@ impl ResponseParser {
             TemplateLanguage::Ruby => self.extract_ruby_code(text),
             TemplateLanguage::Perl => self.extract_perl_code(text),
             TemplateLanguage::Php => self.extract_php_code(text),
+            TemplateLanguage::Rhai => self.extract_rhai_code(text),
             TemplateLanguage::Shell => self.extract_shell_code(text),
         }
     }
@@ -425,6 +426,24 @@ impl ResponseParser {
         lines[start..].join("\n")
     }
 
+    /// Extract Rhai code by finding the metadata header or first statement
+    fn extract_rhai_code(&self, text: &str) -> String {
+        let lines: Vec<&str> = text.lines().collect();
+
+        let start = lines
+            .iter()
+            .position(|line| {
+                let trimmed = line.trim();
+                trimmed.starts_with("// @id")
+                    || trimmed.starts_with("// cert-x-gen:")
+                    || trimmed.starts_with("let ")
+                    || trimmed.starts_with("fn ")
+            })
+            .unwrap_or(0);
+
+        lines[start..].join("\n")
+    }
+
     /// Extract Shell/Bash code by finding shebang
     fn extract_shell_code(&self, text: &str) -> String {
         let lines: Vec<&str> = text.lines().collect();
@@ -463,6 +482,7 @@ impl ResponseParser {
             TemplateLanguage::Ruby => self.validate_ruby_structure(code),
             TemplateLanguage::Perl => self.validate_perl_structure(code),
             TemplateLanguage::Php => self.validate_php_structure(code),
+            TemplateLanguage::Rhai => self.validate_rhai_structure(code),
             TemplateLanguage::Shell => self.validate_shell_structure(code),
         }
     }
@@ -614,6 +634,14 @@ impl ResponseParser {
         Ok(())
     }
 
+    /// Validate Rhai template structure
+    fn validate_rhai_structure(&self, code: &str) -> Result<()> {
+        if !code.contains("emit_finding(") {
+            anyhow::bail!("Rhai template never calls emit_finding()");
+        }
+        Ok(())
+    }
+
     /// Validate Shell template structure
     fn validate_shell_structure(&self, code: &str) -> Result<()> {
         if !code.contains("#!/") {
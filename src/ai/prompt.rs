@@ -10,7 +10,7 @@ use std::collections::HashMap;
 /// Skeleton templates embedded at compile-time
 /// These are always available and don't require filesystem access
 mod embedded_skeletons {
-    // Embed all 11 skeleton templates at compile time
+    // Embed all 12 skeleton templates at compile time
     // These will be included in the binary, ensuring they're always available
 
     pub const PYTHON: &str = include_str!("../../templates/skeleton/python-template-skeleton.py");
@@ -25,6 +25,7 @@ mod embedded_skeletons {
     pub const PERL: &str = include_str!("../../templates/skeleton/perl-template-skeleton.pl");
     pub const PHP: &str = include_str!("../../templates/skeleton/php-template-skeleton.php");
     pub const SHELL: &str = include_str!("../../templates/skeleton/shell-template-skeleton.sh");
+    pub const RHAI: &str = include_str!("../../templates/skeleton/rhai-template-skeleton.rhai");
 }
 
 /// YAML example templates for reference in prompts
@@ -189,6 +190,7 @@ impl PromptBuilder {
         skeleton_templates.insert(TemplateLanguage::Perl, embedded_skeletons::PERL);
         skeleton_templates.insert(TemplateLanguage::Php, embedded_skeletons::PHP);
         skeleton_templates.insert(TemplateLanguage::Shell, embedded_skeletons::SHELL);
+        skeleton_templates.insert(TemplateLanguage::Rhai, embedded_skeletons::RHAI);
 
         Self { skeleton_templates }
     }
@@ -537,6 +539,14 @@ Generate the CUSTOMIZED {language_name} template for "{user_request}" now:
                  - Build JSON output manually or use jq\n\
                  - Echo JSON to stdout"
             }
+            TemplateLanguage::Rhai => {
+                "- Use Rhai (embedded interpreter, no imports or external modules)\n\
+                 - Use http_get/http_post for HTTP; paths starting with / are relative to the target\n\
+                 - Use tcp_send(target.host, target.port, data) for raw TCP\n\
+                 - Use regex_match/regex_extract to inspect responses\n\
+                 - Report each issue with emit_finding(#{ title, severity, evidence })\n\
+                 - Do not print JSON; emit_finding is the only output"
+            }
             TemplateLanguage::Yaml => "YAML is declarative - this shouldn't be called",
         }
     }
@@ -555,6 +565,7 @@ Generate the CUSTOMIZED {language_name} template for "{user_request}" now:
             TemplateLanguage::Ruby => "ruby",
             TemplateLanguage::Perl => "perl",
             TemplateLanguage::Php => "php",
+            TemplateLanguage::Rhai => "rust",
             TemplateLanguage::Shell => "bash",
         }
     }
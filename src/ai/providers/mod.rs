@@ -70,6 +70,11 @@ pub use deepseek::DeepSeekProvider;
 pub use ollama::OllamaProvider;
 pub use openai::OpenAIProvider;
 
+/// Approximate token count of `text` (1 token ≈ 4 characters, as the cost estimates assume)
+pub fn count_tokens(text: &str) -> u32 {
+    text.chars().count().div_ceil(4) as u32
+}
+
 /// Options for controlling LLM generation behavior
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenerationOptions {
@@ -553,12 +558,26 @@ pub trait LLMProvider: Send + Sync {
     /// # }
     /// ```
     async fn health_check(&self) -> Result<ProviderHealthStatus>;
+
+    /// Embed text into a vector for similarity comparisons
+    ///
+    /// Providers without an embeddings API return an error.
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        anyhow::bail!("Provider '{}' does not support embeddings", self.name())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_count_tokens() {
+        assert_eq!(count_tokens(""), 0);
+        assert_eq!(count_tokens("abcd"), 1);
+        assert_eq!(count_tokens("abcde"), 2);
+    }
+
     #[test]
     fn test_generation_options_default() {
         let options = GenerationOptions::default();
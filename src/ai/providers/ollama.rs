@@ -279,6 +279,42 @@ impl LLMProvider for OllamaProvider {
         Ok(response_data.response)
     }
 
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        #[derive(Serialize)]
+        struct EmbeddingRequest<'a> {
+            model: &'a str,
+            prompt: &'a str,
+        }
+
+        #[derive(Deserialize)]
+        struct EmbeddingResponse {
+            embedding: Vec<f32>,
+        }
+
+        let response = self
+            .client
+            .post(format!("{}/api/embeddings", self.endpoint))
+            .json(&EmbeddingRequest {
+                model: &self.model,
+                prompt: text,
+            })
+            .send()
+            .await
+            .context("Failed to connect to Ollama. Is it running? Try: ollama serve")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Ollama returned error {}: {}", status, error_text);
+        }
+
+        let response_data: EmbeddingResponse = response
+            .json()
+            .await
+            .context("Failed to parse Ollama embedding response")?;
+        Ok(response_data.embedding)
+    }
+
     async fn list_models(&self) -> Result<Vec<ModelInfo>> {
         debug!("Fetching model list from Ollama");
 
@@ -447,6 +483,31 @@ mod tests {
         assert_eq!(provider.estimate_cost("test prompt"), None);
     }
 
+    #[tokio::test]
+    async fn test_embed() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .and(body_partial_json(
+                serde_json::json!({"model": "nomic-embed-text"}),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"embedding": [0.5, -0.25, 1.0]})),
+            )
+            .mount(&server)
+            .await;
+
+        let provider = OllamaProvider::new(server.uri(), "nomic-embed-text".to_string());
+        assert_eq!(
+            provider.embed("redis").await.unwrap(),
+            vec![0.5, -0.25, 1.0]
+        );
+    }
+
     #[tokio::test]
     async fn test_availability_check() {
         let provider = OllamaProvider::default();
@@ -41,6 +41,9 @@ use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
+/// Model used for embeddings (the configured chat model cannot embed)
+const EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
 /// OpenAI provider for cloud-based LLM execution
 #[derive(Debug, Clone)]
 pub struct OpenAIProvider {
@@ -246,6 +249,59 @@ impl LLMProvider for OpenAIProvider {
         Ok(content.clone())
     }
 
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        if !self.is_api_key_valid() {
+            anyhow::bail!(
+                "Invalid OpenAI API key. Get your key from: https://platform.openai.com/api-keys"
+            );
+        }
+
+        #[derive(Serialize)]
+        struct EmbeddingRequest<'a> {
+            model: &'a str,
+            input: &'a str,
+        }
+
+        #[derive(Deserialize)]
+        struct EmbeddingResponse {
+            data: Vec<EmbeddingData>,
+        }
+
+        #[derive(Deserialize)]
+        struct EmbeddingData {
+            embedding: Vec<f32>,
+        }
+
+        let response = self
+            .client
+            .post(format!("{}/embeddings", self.endpoint))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&EmbeddingRequest {
+                model: EMBEDDING_MODEL,
+                input: text,
+            })
+            .send()
+            .await
+            .context("Failed to connect to OpenAI API. Check your internet connection.")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("OpenAI API error {}: {}", status, error_text);
+        }
+
+        let response_data: EmbeddingResponse = response
+            .json()
+            .await
+            .context("Failed to parse OpenAI embedding response")?;
+        response_data
+            .data
+            .into_iter()
+            .next()
+            .map(|d| d.embedding)
+            .context("OpenAI returned no embedding")
+    }
+
     async fn list_models(&self) -> Result<Vec<ModelInfo>> {
         Ok(vec![
             // GPT-4 Turbo - Most capable, 128K context
@@ -0,0 +1,268 @@
+//! Automatic model selection for template generation
+//!
+//! Without `--model`, `cxg ai generate` picks one of the provider's models:
+//! it must have a context window large enough for the prompt plus the
+//! response, and its size follows the task's [`Complexity`] (a small model
+//! for YAML matchers, the largest for multi-step compiled templates).
+
+use super::providers::ModelInfo;
+use crate::types::TemplateLanguage;
+use serde::{Deserialize, Serialize};
+
+/// Tokens reserved for the generated template when the provider sets no `max_tokens`
+pub const DEFAULT_RESPONSE_TOKENS: u32 = 2048;
+
+/// How demanding a generation task is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Complexity {
+    /// Single-request checks such as YAML matchers
+    Low,
+    /// Scripted checks in interpreted languages
+    Medium,
+    /// Multi-step logic or compiled templates
+    High,
+}
+
+impl Complexity {
+    /// Guess from the template language and the description
+    ///
+    /// YAML is `Low` and compiled languages are `High`; descriptions of
+    /// multi-step checks (authentication, sessions, chained requests) raise
+    /// the guess one level.
+    pub fn infer(language: TemplateLanguage, description: &str) -> Self {
+        const MULTI_STEP: &[&str] = &[
+            "multi-step",
+            "multi step",
+            "chain",
+            "authenticat",
+            "login",
+            "session",
+            "race condition",
+            "deserializ",
+        ];
+        let description = description.to_lowercase();
+        let multi_step = MULTI_STEP.iter().any(|word| description.contains(word));
+
+        match (language, multi_step) {
+            (language, _) if language.is_compiled() => Complexity::High,
+            (TemplateLanguage::Yaml, false) => Complexity::Low,
+            (TemplateLanguage::Yaml, true) | (_, false) => Complexity::Medium,
+            (_, true) => Complexity::High,
+        }
+    }
+}
+
+impl std::fmt::Display for Complexity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Complexity::Low => write!(f, "low"),
+            Complexity::Medium => write!(f, "medium"),
+            Complexity::High => write!(f, "high"),
+        }
+    }
+}
+
+/// A template generation request, as far as model selection is concerned
+#[derive(Debug, Clone)]
+pub struct GenerationTask {
+    /// Natural language description of the check
+    pub description: String,
+    /// Language of the template to generate
+    pub language: TemplateLanguage,
+    /// How demanding the task is
+    pub complexity: Complexity,
+    /// Provider to pick a model from (the default provider when `None`)
+    pub provider: Option<String>,
+}
+
+impl GenerationTask {
+    /// Task with its complexity inferred from the language and description
+    pub fn new(description: impl Into<String>, language: TemplateLanguage) -> Self {
+        let description = description.into();
+        let complexity = Complexity::infer(language, &description);
+        Self {
+            description,
+            language,
+            complexity,
+            provider: None,
+        }
+    }
+
+    /// Override the inferred complexity
+    pub fn with_complexity(mut self, complexity: Complexity) -> Self {
+        self.complexity = complexity;
+        self
+    }
+
+    /// Pick from this provider's models instead of the default provider's
+    pub fn with_provider(mut self, provider: Option<String>) -> Self {
+        self.provider = provider;
+        self
+    }
+}
+
+/// Known good models per provider and complexity, most preferred first (ID prefixes)
+fn preferred_models(provider: &str, complexity: Complexity) -> &'static [&'static str] {
+    match (provider, complexity) {
+        ("ollama", Complexity::High) => &["codellama:34b", "codellama:13b"],
+        ("ollama", Complexity::Medium) => &["codellama:13b"],
+        ("ollama", Complexity::Low) => &["codellama:7b"],
+        ("openai", Complexity::High) => &["gpt-4-turbo", "gpt-4-32k", "gpt-4"],
+        ("openai", Complexity::Medium) => &["gpt-4-turbo", "gpt-4"],
+        ("openai", Complexity::Low) => &["gpt-3.5-turbo"],
+        ("anthropic", Complexity::High) => &["claude-3-opus", "claude-3-5-sonnet"],
+        ("anthropic", Complexity::Medium) => &["claude-3-5-sonnet"],
+        ("anthropic", Complexity::Low) => &["claude-3-haiku"],
+        ("deepseek", _) => &["deepseek-coder"],
+        _ => &[],
+    }
+}
+
+/// Parameter count in billions from IDs such as `codellama:34b` or `llama3-8b-instruct`
+fn parameter_billions(id: &str) -> Option<f64> {
+    id.split([':', '-', '_'])
+        .filter_map(|part| part.strip_suffix('b'))
+        .find_map(|number| number.parse::<f64>().ok())
+}
+
+/// Pick a model for `complexity` whose context window holds `required_tokens`
+///
+/// Known good models for the provider come first. Otherwise the largest
+/// (`High`), median (`Medium`) or smallest (`Low`) fitting model is taken,
+/// preferring code models. `None` when no model fits.
+pub fn choose_model(
+    provider: &str,
+    complexity: Complexity,
+    required_tokens: u32,
+    models: &[ModelInfo],
+) -> Option<String> {
+    let fitting: Vec<&ModelInfo> = models
+        .iter()
+        .filter(|m| {
+            m.context_window
+                .is_none_or(|window| window >= required_tokens)
+        })
+        .collect();
+
+    for prefix in preferred_models(provider, complexity) {
+        if let Some(model) = fitting.iter().find(|m| m.id.starts_with(prefix)) {
+            return Some(model.id.clone());
+        }
+    }
+
+    let code_models: Vec<&ModelInfo> = fitting
+        .iter()
+        .copied()
+        .filter(|m| m.capabilities.iter().any(|c| c == "code-generation"))
+        .collect();
+    let mut candidates = if code_models.is_empty() {
+        fitting
+    } else {
+        code_models
+    };
+    let size = |m: &ModelInfo| {
+        parameter_billions(&m.id)
+            .or(m.size.map(|bytes| bytes as f64 / 1e9))
+            .unwrap_or(0.0)
+    };
+    candidates.sort_by(|a, b| size(a).total_cmp(&size(b)));
+
+    let index = match complexity {
+        Complexity::Low => 0,
+        Complexity::Medium => candidates.len() / 2,
+        Complexity::High => candidates.len().checked_sub(1)?,
+    };
+    candidates.get(index).map(|m| m.id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(id: &str, context_window: u32) -> ModelInfo {
+        ModelInfo::new(id.to_string(), id.to_string(), "test".to_string())
+            .with_context_window(context_window)
+            .with_capability("code-generation")
+    }
+
+    #[test]
+    fn test_infer_complexity() {
+        assert_eq!(
+            Complexity::infer(TemplateLanguage::Yaml, "detect exposed .git directory"),
+            Complexity::Low
+        );
+        assert_eq!(
+            Complexity::infer(TemplateLanguage::Python, "detect Redis without auth"),
+            Complexity::Medium
+        );
+        assert_eq!(
+            Complexity::infer(
+                TemplateLanguage::Python,
+                "login then check session fixation"
+            ),
+            Complexity::High
+        );
+        assert_eq!(
+            Complexity::infer(TemplateLanguage::Rust, "grab the SSH banner"),
+            Complexity::High
+        );
+    }
+
+    #[test]
+    fn test_ollama_prefers_codellama_by_complexity() {
+        let models = vec![
+            model("llama3:70b", 8192),
+            model("codellama:7b", 4096),
+            model("codellama:13b", 4096),
+            model("codellama:34b", 4096),
+        ];
+
+        let pick = |complexity| choose_model("ollama", complexity, 3000, &models);
+        assert_eq!(pick(Complexity::High).as_deref(), Some("codellama:34b"));
+        assert_eq!(pick(Complexity::Medium).as_deref(), Some("codellama:13b"));
+        assert_eq!(pick(Complexity::Low).as_deref(), Some("codellama:7b"));
+    }
+
+    #[test]
+    fn test_context_window_excludes_small_models() {
+        let models = vec![
+            model("gpt-4", 8192),
+            model("gpt-4-turbo-preview", 128000),
+            model("gpt-3.5-turbo", 16385),
+        ];
+
+        assert_eq!(
+            choose_model("openai", Complexity::Low, 2000, &models).as_deref(),
+            Some("gpt-3.5-turbo")
+        );
+        // Too large for GPT-3.5 and GPT-4 8K
+        assert_eq!(
+            choose_model("openai", Complexity::Low, 20000, &models).as_deref(),
+            Some("gpt-4-turbo-preview")
+        );
+        assert_eq!(
+            choose_model("openai", Complexity::High, 200000, &models),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unknown_models_ranked_by_size() {
+        let models = vec![
+            model("qwen2.5-coder:14b", 32768),
+            model("qwen2.5-coder:1.5b", 32768),
+            model("qwen2.5-coder:32b", 32768),
+            ModelInfo::new("llama3:70b".into(), "llama3".into(), "ollama".into())
+                .with_context_window(8192),
+        ];
+
+        let pick = |complexity| choose_model("ollama", complexity, 1000, &models);
+        assert_eq!(pick(Complexity::High).as_deref(), Some("qwen2.5-coder:32b"));
+        assert_eq!(
+            pick(Complexity::Medium).as_deref(),
+            Some("qwen2.5-coder:14b")
+        );
+        assert_eq!(pick(Complexity::Low).as_deref(), Some("qwen2.5-coder:1.5b"));
+    }
+}
@@ -1,7 +1,9 @@
 //! Language detection from filename and content
 
 use crate::types::TemplateLanguage;
+use regex::Regex;
 use std::path::Path;
+use std::sync::OnceLock;
 
 /// Supported file extensions for each language
 const EXTENSIONS: &[(&str, TemplateLanguage)] = &[
@@ -23,6 +25,7 @@ const EXTENSIONS: &[(&str, TemplateLanguage)] = &[
     ("pl", TemplateLanguage::Perl),
     ("pm", TemplateLanguage::Perl),
     ("php", TemplateLanguage::Php),
+    ("rhai", TemplateLanguage::Rhai),
     ("yaml", TemplateLanguage::Yaml),
     ("yml", TemplateLanguage::Yaml),
 ];
@@ -37,135 +40,230 @@ pub fn detect_language_from_filename(path: &Path) -> Option<TemplateLanguage> {
         .map(|(_, lang)| *lang)
 }
 
-/// Detect language from code content using heuristics
-pub fn detect_language_from_content(code: &str) -> Option<TemplateLanguage> {
-    let code_lower = code.to_lowercase();
-    let first_lines: Vec<&str> = code.lines().take(10).collect();
-    let first_10_lines = first_lines.join("\n").to_lowercase();
+/// Minimum confidence for [`detect_language_from_content`] to report a language
+const MIN_CONFIDENCE: f32 = 0.4;
 
-    // Check shebang first
-    if let Some(first_line) = code.lines().next() {
-        if first_line.starts_with("#!") {
-            if first_line.contains("python") {
-                return Some(TemplateLanguage::Python);
-            } else if first_line.contains("node") || first_line.contains("nodejs") {
-                return Some(TemplateLanguage::JavaScript);
-            } else if first_line.contains("bash") || first_line.contains("sh") {
-                return Some(TemplateLanguage::Shell);
-            } else if first_line.contains("ruby") {
-                return Some(TemplateLanguage::Ruby);
-            } else if first_line.contains("perl") {
-                return Some(TemplateLanguage::Perl);
-            } else if first_line.contains("php") {
-                return Some(TemplateLanguage::Php);
-            }
-        }
-    }
-
-    // Python indicators
-    if (code_lower.contains("import ")
-        || code_lower.contains("from ")
-        || code_lower.contains("def "))
-        && (first_10_lines.contains("import json")
-            || first_10_lines.contains("import sys")
-            || first_10_lines.contains("import os")
-            || code_lower.contains("if __name__"))
-    {
-        return Some(TemplateLanguage::Python);
-    }
-
-    // JavaScript/Node indicators
-    if (code_lower.contains("const ") || code_lower.contains("let ") || code_lower.contains("var "))
-        && (first_10_lines.contains("require(")
-            || first_10_lines.contains("import ")
-            || code_lower.contains("console.log")
-            || code_lower.contains("async "))
-    {
-        return Some(TemplateLanguage::JavaScript);
-    }
+/// Score at which evidence for a language is considered conclusive
+const CONCLUSIVE_SCORE: u32 = 10;
 
-    // Rust indicators
-    if first_10_lines.contains("fn main()")
-        || (code_lower.contains("fn ") && code_lower.contains("use "))
-        || code_lower.contains("impl ")
-        || code_lower.contains("struct ")
-        || first_10_lines.contains("extern crate")
-    {
-        return Some(TemplateLanguage::Rust);
-    }
-
-    // Shell indicators
-    if code_lower.contains("#!/bin/bash")
-        || code_lower.contains("#!/bin/sh")
-        || (code_lower.contains("echo ") && code_lower.contains("if ["))
-    {
-        return Some(TemplateLanguage::Shell);
-    }
-
-    // C indicators
-    if (first_10_lines.contains("#include <stdio.h>")
-        || first_10_lines.contains("#include <stdlib.h>"))
-        && code_lower.contains("int main(")
-        && !code_lower.contains("std::")
-    {
-        return Some(TemplateLanguage::C);
-    }
-
-    // C++ indicators
-    if (first_10_lines.contains("#include <iostream>")
-        || first_10_lines.contains("std::")
-        || code_lower.contains("namespace "))
-        && code_lower.contains("int main(")
-    {
-        return Some(TemplateLanguage::Cpp);
-    }
-
-    // Java indicators
-    if (code_lower.contains("public class ") || code_lower.contains("public static void main"))
-        && (first_10_lines.contains("import java.") || code_lower.contains("package "))
-    {
-        return Some(TemplateLanguage::Java);
-    }
-
-    // Go indicators
-    if first_10_lines.contains("package main")
-        || (code_lower.contains("func main()") && code_lower.contains("import ("))
-    {
-        return Some(TemplateLanguage::Go);
-    }
+/// Weighted evidence patterns per language
+///
+/// Each pattern counts once per snippet regardless of how often it matches,
+/// so long files do not drown out distinctive markers like `<?php`.
+const EVIDENCE: &[(TemplateLanguage, &str, u32)] = &[
+    // Python
+    (TemplateLanguage::Python, r"\A#!.*\bpython", 10),
+    (
+        TemplateLanguage::Python,
+        r"(?m)^\s*import (json|sys|os|socket|re|ssl|requests)\b",
+        3,
+    ),
+    (TemplateLanguage::Python, r"(?m)^\s*from [\w.]+ import ", 3),
+    (
+        TemplateLanguage::Python,
+        r"(?m)^\s*def \w+\(.*\)( -> [\w\[\], ]+)?:\s*$",
+        3,
+    ),
+    (
+        TemplateLanguage::Python,
+        r#"if __name__ == ['"]__main__['"]"#,
+        5,
+    ),
+    (TemplateLanguage::Python, r"\bself\.\w+", 2),
+    (
+        TemplateLanguage::Python,
+        r"(?m)^\s*(try|except[^:]*|else|finally):\s*$",
+        2,
+    ),
+    // JavaScript
+    (TemplateLanguage::JavaScript, r"\A#!.*\bnode", 10),
+    (
+        TemplateLanguage::JavaScript,
+        r#"\brequire\(['"][\w./@-]+['"]\)"#,
+        3,
+    ),
+    (
+        TemplateLanguage::JavaScript,
+        r"(?m)^\s*(const|let) \w+\s*=",
+        3,
+    ),
+    (TemplateLanguage::JavaScript, r"\bconsole\.(log|error)\(", 3),
+    (TemplateLanguage::JavaScript, r"\bmodule\.exports\b", 4),
+    (
+        TemplateLanguage::JavaScript,
+        r"\basync function\b|\bawait \w+",
+        2,
+    ),
+    (TemplateLanguage::JavaScript, r"\)\s*=>\s*\{", 2),
+    // Rust
+    (
+        TemplateLanguage::Rust,
+        r"(?m)^\s*use (std|crate|super|serde|tokio|anyhow)::",
+        4,
+    ),
+    (TemplateLanguage::Rust, r"\blet mut \w+", 4),
+    (TemplateLanguage::Rust, r"\bprintln!\(", 4),
+    (TemplateLanguage::Rust, r"\bfn \w+\([^)]*\)\s*(->|\{)", 3),
+    (TemplateLanguage::Rust, r"#\[derive\(", 5),
+    (TemplateLanguage::Rust, r"(?m)^\s*impl\b", 3),
+    // C
+    (
+        TemplateLanguage::C,
+        r"(?m)^#include\s*<(stdio|stdlib|string|unistd|sys/socket|arpa/inet|netinet/in)\.h>",
+        4,
+    ),
+    (TemplateLanguage::C, r"\bint main\s*\(", 2),
+    (
+        TemplateLanguage::C,
+        r"\b(malloc|free|memset|strncpy|snprintf)\s*\(",
+        2,
+    ),
+    (TemplateLanguage::C, r"\bstruct sockaddr(_in)?\b", 3),
+    // C++
+    (
+        TemplateLanguage::Cpp,
+        r"(?m)^#include\s*<(iostream|string|vector|map|memory|sstream)>",
+        5,
+    ),
+    (
+        TemplateLanguage::Cpp,
+        r"\bstd::(cout|cerr|endl|string|vector|map|unique_ptr)\b",
+        5,
+    ),
+    (TemplateLanguage::Cpp, r"\busing namespace std;", 5),
+    (TemplateLanguage::Cpp, r"\bint main\s*\(", 2),
+    // Java
+    (TemplateLanguage::Java, r"(?m)^\s*import java\.", 6),
+    (TemplateLanguage::Java, r"(?m)^\s*package [\w.]+;", 4),
+    (TemplateLanguage::Java, r"\bpublic (final )?class \w+", 4),
+    (
+        TemplateLanguage::Java,
+        r"\bpublic static void main\s*\(String",
+        6,
+    ),
+    (TemplateLanguage::Java, r"\bSystem\.(out|err)\.print", 5),
+    // Go
+    (TemplateLanguage::Go, r"(?m)^package \w+\s*$", 5),
+    (
+        TemplateLanguage::Go,
+        r"(?m)^func (\(\w+ \*?\w+\) )?\w+\(",
+        4,
+    ),
+    (TemplateLanguage::Go, r"(?m)^import \(\s*$", 4),
+    (
+        TemplateLanguage::Go,
+        r"\bfmt\.(Print|Sprint|Fprint|Errorf)",
+        5,
+    ),
+    (TemplateLanguage::Go, r"\w+ := ", 2),
+    // Ruby
+    (TemplateLanguage::Ruby, r"\A#!.*\bruby", 10),
+    (
+        TemplateLanguage::Ruby,
+        r#"(?m)^\s*require ['"][\w/]+['"]"#,
+        4,
+    ),
+    (TemplateLanguage::Ruby, r"(?m)^\s*def \w+(\(.*\))?\s*$", 2),
+    (TemplateLanguage::Ruby, r"(?m)^\s*end\s*$", 2),
+    (TemplateLanguage::Ruby, r"(?m)^\s*puts\b", 3),
+    (
+        TemplateLanguage::Ruby,
+        r"\.each do\b|\bdo \|\w+(, \w+)*\|",
+        4,
+    ),
+    (TemplateLanguage::Ruby, r"#\{[\w.@]+\}", 3),
+    // Perl
+    (TemplateLanguage::Perl, r"\A#!.*\bperl", 10),
+    (TemplateLanguage::Perl, r"(?m)^\s*use strict;", 3),
+    (TemplateLanguage::Perl, r"(?m)^\s*use warnings;", 3),
+    (TemplateLanguage::Perl, r"\bmy [$@%]\w+\s*=", 2),
+    (TemplateLanguage::Perl, r"\$\w+\s*[=!]~", 4),
+    (TemplateLanguage::Perl, r"(?m)^\s*sub \w+\s*\{", 3),
+    (TemplateLanguage::Perl, r"(?m)^\s*use [A-Z]\w*(::\w+)+", 3),
+    // PHP
+    (TemplateLanguage::Php, r"<\?php", 10),
+    (TemplateLanguage::Php, r"\$this->", 4),
+    (TemplateLanguage::Php, r"\bfunction \w+\s*\(\$", 4),
+    (
+        TemplateLanguage::Php,
+        r"\b(json_encode|json_decode|curl_init|curl_setopt|curl_exec)\(",
+        4,
+    ),
+    (TemplateLanguage::Php, r"\$\w+\s*=\s*(array\(|\[)", 2),
+    // Shell
+    (
+        TemplateLanguage::Shell,
+        r"\A#!.*/(env\s+)?(ba|z|da)?sh\b",
+        10,
+    ),
+    (TemplateLanguage::Shell, r"(?m)^\s*if \[\[? ", 4),
+    (TemplateLanguage::Shell, r"(?m)^\s*(fi|done|esac)\s*$", 4),
+    (TemplateLanguage::Shell, r"(?m)^\s*export \w+=", 3),
+    (TemplateLanguage::Shell, r#"\becho ["$]"#, 2),
+    (TemplateLanguage::Shell, r"(?m)^\s*\w+\(\)\s*\{", 2),
+    // Rhai
+    (TemplateLanguage::Rhai, r"\bemit_finding\s*\(", 10),
+    (
+        TemplateLanguage::Rhai,
+        r"\b(http_get|http_post|tcp_send)\s*\(",
+        4,
+    ),
+    (TemplateLanguage::Rhai, r"=\s*#\{", 3),
+    // YAML
+    (TemplateLanguage::Yaml, r"(?m)^id:\s*\S+", 3),
+    (TemplateLanguage::Yaml, r"(?m)^severity:\s*\w+", 3),
+    (
+        TemplateLanguage::Yaml,
+        r"(?m)^(http|header_injection|network|flows|matchers):\s*$",
+        4,
+    ),
+    (
+        TemplateLanguage::Yaml,
+        r"(?m)^\s*- (method|path|matchers|type|port):",
+        3,
+    ),
+    (TemplateLanguage::Yaml, r"(?m)^tags:\s*$", 2),
+];
 
-    // Ruby indicators
-    if code_lower.contains("require ")
-        || (code_lower.contains("def ") && code_lower.contains("end"))
-        || code_lower.contains("puts ")
-    {
-        return Some(TemplateLanguage::Ruby);
-    }
+fn compiled_evidence() -> &'static [(TemplateLanguage, Regex, u32)] {
+    static COMPILED: OnceLock<Vec<(TemplateLanguage, Regex, u32)>> = OnceLock::new();
+    COMPILED.get_or_init(|| {
+        EVIDENCE
+            .iter()
+            .map(|(lang, pattern, weight)| {
+                let re = Regex::new(pattern).expect("invalid language evidence pattern");
+                (*lang, re, *weight)
+            })
+            .collect()
+    })
+}
 
-    // Perl indicators
-    if first_10_lines.contains("use strict;")
-        || first_10_lines.contains("use warnings;")
-        || (code_lower.contains("my ") && code_lower.contains("$"))
-    {
-        return Some(TemplateLanguage::Perl);
+/// Detect language from code content using weighted evidence scoring
+///
+/// Returns the best-scoring language with a confidence in `0.0..=1.0`, or
+/// `None` when no language reaches [`MIN_CONFIDENCE`]. Confidence combines
+/// the winner's share of all evidence with how conclusive its score is, so
+/// both ambiguous snippets and very short ones are rejected.
+pub fn detect_language_from_content(code: &str) -> Option<(TemplateLanguage, f32)> {
+    let mut scores: Vec<(TemplateLanguage, u32)> = Vec::new();
+    for (lang, re, weight) in compiled_evidence() {
+        if !re.is_match(code) {
+            continue;
+        }
+        match scores.iter_mut().find(|(l, _)| l == lang) {
+            Some((_, score)) => *score += weight,
+            None => scores.push((*lang, *weight)),
+        }
     }
 
-    // PHP indicators
-    if code_lower.starts_with("<?php") || code_lower.contains("<?php") {
-        return Some(TemplateLanguage::Php);
-    }
+    let total: u32 = scores.iter().map(|(_, s)| s).sum();
+    let (language, best) = scores.into_iter().max_by_key(|(_, s)| *s)?;
 
-    // YAML indicators
-    if (first_10_lines.contains("id:") || first_10_lines.contains("name:"))
-        && (first_10_lines.contains("severity:") || first_10_lines.contains("description:"))
-        && (code_lower.contains("http:")
-            || code_lower.contains("network:")
-            || code_lower.contains("flows:"))
-    {
-        return Some(TemplateLanguage::Yaml);
-    }
+    let share = best as f32 / total as f32;
+    let strength = (best as f32 / CONCLUSIVE_SCORE as f32).min(1.0);
+    let confidence = share * strength;
 
-    None
+    (confidence >= MIN_CONFIDENCE).then_some((language, confidence))
 }
 
 /// Check if an extension is supported by CERT-X-GEN
@@ -219,7 +317,7 @@ mod tests {
         let python_code =
             "#!/usr/bin/env python3\nimport json\nimport sys\n\ndef main():\n    pass";
         assert_eq!(
-            detect_language_from_content(python_code),
+            detect_language_from_content(python_code).map(|(lang, _)| lang),
             Some(TemplateLanguage::Python)
         );
     }
@@ -228,7 +326,7 @@ mod tests {
     fn test_detect_from_content_javascript() {
         let js_code = "const http = require('http');\nconst fs = require('fs');";
         assert_eq!(
-            detect_language_from_content(js_code),
+            detect_language_from_content(js_code).map(|(lang, _)| lang),
             Some(TemplateLanguage::JavaScript)
         );
     }
@@ -237,7 +335,7 @@ mod tests {
     fn test_detect_from_content_shell() {
         let shell_code = "#!/bin/bash\necho 'test'\nif [ -f /tmp/test ]; then\n  echo 'found'\nfi";
         assert_eq!(
-            detect_language_from_content(shell_code),
+            detect_language_from_content(shell_code).map(|(lang, _)| lang),
             Some(TemplateLanguage::Shell)
         );
     }
@@ -246,11 +344,106 @@ mod tests {
     fn test_detect_from_content_yaml() {
         let yaml_code = "id: test\nname: Test\nseverity: high\nhttp:\n  - method: GET";
         assert_eq!(
-            detect_language_from_content(yaml_code),
+            detect_language_from_content(yaml_code).map(|(lang, _)| lang),
             Some(TemplateLanguage::Yaml)
         );
     }
 
+    fn detected(code: &str) -> Option<TemplateLanguage> {
+        detect_language_from_content(code).map(|(lang, _)| lang)
+    }
+
+    #[test]
+    fn test_detect_from_content_real_snippets() {
+        let snippets = [
+            (
+                TemplateLanguage::Python,
+                "import socket\nimport json\n\nclass Check:\n    def run(self, host):\n        self.host = host\n        try:\n            socket.create_connection((host, 6379))\n        except OSError:\n            return None\n",
+            ),
+            (
+                TemplateLanguage::JavaScript,
+                "const net = require('net');\n\nasync function check(host) {\n  const socket = net.connect(6379, host);\n  console.log(JSON.stringify({ host }));\n}\n\nmodule.exports = { check };\n",
+            ),
+            (
+                TemplateLanguage::Rust,
+                "use std::net::TcpStream;\n\nfn main() {\n    let mut stream = TcpStream::connect(\"127.0.0.1:6379\").unwrap();\n    println!(\"{}\", \"[]\");\n}\n",
+            ),
+            (
+                TemplateLanguage::C,
+                "#include <stdio.h>\n#include <sys/socket.h>\n\nint main(int argc, char **argv) {\n    struct sockaddr_in addr;\n    memset(&addr, 0, sizeof(addr));\n    return 0;\n}\n",
+            ),
+            (
+                TemplateLanguage::Cpp,
+                "#include <iostream>\n#include <string>\n\nusing namespace std;\n\nint main() {\n    std::string host = \"localhost\";\n    std::cout << host << std::endl;\n}\n",
+            ),
+            (
+                TemplateLanguage::Java,
+                "package checks;\n\nimport java.net.Socket;\n\npublic class RedisCheck {\n    public static void main(String[] args) {\n        System.out.println(\"[]\");\n    }\n}\n",
+            ),
+            (
+                TemplateLanguage::Go,
+                "package main\n\nimport (\n\t\"fmt\"\n\t\"net\"\n)\n\nfunc main() {\n\tconn, err := net.Dial(\"tcp\", \"localhost:6379\")\n\tfmt.Println(conn, err)\n}\n",
+            ),
+            (
+                TemplateLanguage::Ruby,
+                "require 'socket'\nrequire 'json'\n\ndef check(host)\n  hosts.each do |h|\n    puts \"checking #{h}\"\n  end\nend\n",
+            ),
+            (
+                TemplateLanguage::Perl,
+                "use strict;\nuse warnings;\nuse IO::Socket::INET;\n\nmy $host = $ENV{'CERT_X_GEN_TARGET_HOST'};\nif ($response =~ /redis_version/) {\n    print \"found\\n\";\n}\n",
+            ),
+            (
+                TemplateLanguage::Php,
+                "<?php\n$host = getenv('CERT_X_GEN_TARGET_HOST');\n$ch = curl_init(\"http://$host/\");\necho json_encode([]);\n",
+            ),
+            (
+                TemplateLanguage::Shell,
+                "#!/usr/bin/env bash\nexport TARGET=\"$1\"\nif [ -z \"$TARGET\" ]; then\n  echo \"missing target\"\nfi\n",
+            ),
+            (
+                TemplateLanguage::Rhai,
+                "let resp = http_get(\"/server-status\");\nif resp.status == 200 {\n    emit_finding(#{ title: \"exposed\", severity: \"low\" });\n}\n",
+            ),
+            (
+                TemplateLanguage::Yaml,
+                "id: redis-info\nname: Redis Info\nseverity: medium\ntags:\n  - redis\nnetwork:\n  - port: 6379\n",
+            ),
+        ];
+
+        for (expected, code) in snippets {
+            let (lang, confidence) = detect_language_from_content(code)
+                .unwrap_or_else(|| panic!("no language detected for {} snippet", expected));
+            assert_eq!(lang, expected, "misdetected {} snippet", expected);
+            assert!((MIN_CONFIDENCE..=1.0).contains(&confidence));
+        }
+    }
+
+    #[test]
+    fn test_detect_from_content_disambiguates_similar_languages() {
+        // Both use `$var`, but only PHP has `<?php`
+        let php = "<?php\nmy_func($var);\n$result = array(1, 2);\n";
+        assert_eq!(detected(php), Some(TemplateLanguage::Php));
+
+        let perl = "#!/usr/bin/perl\nuse strict;\nmy $var = 1;\n";
+        assert_eq!(detected(perl), Some(TemplateLanguage::Perl));
+
+        // Python definitions end with a colon, Ruby ones close with `end`
+        let python =
+            "def check(host):\n    return host\n\nif __name__ == '__main__':\n    check('x')\n";
+        assert_eq!(detected(python), Some(TemplateLanguage::Python));
+
+        let ruby = "def check(host)\n  puts host\nend\n";
+        assert_eq!(detected(ruby), Some(TemplateLanguage::Ruby));
+    }
+
+    #[test]
+    fn test_detect_from_content_low_confidence() {
+        assert_eq!(detect_language_from_content(""), None);
+        assert_eq!(detect_language_from_content("hello world\n"), None);
+        // A single weak hint is not enough
+        assert_eq!(detect_language_from_content("x := 1\n"), None);
+    }
+
     #[test]
     fn test_supported_extension() {
         assert!(is_supported_extension(Path::new("test.py")));
@@ -34,8 +34,8 @@ impl EnhancedValidator {
     ) -> Result<Vec<TemplateDiagnostic>> {
         let mut diagnostics = Vec::new();
 
-        // Skip YAML as it has different validation needs
-        if language == TemplateLanguage::Yaml {
+        // Skip YAML and Rhai, which use the engine API instead of sockets and JSON output
+        if matches!(language, TemplateLanguage::Yaml | TemplateLanguage::Rhai) {
             return Ok(diagnostics);
         }
 
@@ -467,7 +467,8 @@ impl EnhancedValidator {
             | TemplateLanguage::C
             | TemplateLanguage::Cpp
             | TemplateLanguage::Java
-            | TemplateLanguage::Php => {
+            | TemplateLanguage::Php
+            | TemplateLanguage::Rhai => {
                 trimmed.starts_with("//") || trimmed.starts_with("/*") || trimmed.starts_with('*')
             }
         }
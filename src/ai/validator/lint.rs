@@ -0,0 +1,372 @@
+//! Style linting for templates
+//!
+//! Unlike [`TemplateValidator`](super::TemplateValidator), the linter never
+//! reports errors: it only flags style issues as warnings or informational
+//! notes. Rules can be tuned per template directory with a `.cxg-lint.yaml`
+//! file:
+//!
+//! ```yaml
+//! min_description_length: 30
+//! rules:
+//!   missing-author-email: allow
+//!   tags-lowercase: warning
+//! ```
+
+use super::{DiagnosticSeverity, TemplateDiagnostic};
+use crate::engine::common::{parse_template_metadata, ParsedMetadata};
+use crate::types::TemplateLanguage;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Name of the per-directory lint configuration file
+pub const LINT_CONFIG_FILE: &str = ".cxg-lint.yaml";
+
+/// Description shorter than the configured minimum
+pub const RULE_DESCRIPTION_TOO_SHORT: &str = "description-too-short";
+/// Author without a contact email
+pub const RULE_MISSING_AUTHOR_EMAIL: &str = "missing-author-email";
+/// Tags containing uppercase characters
+pub const RULE_TAGS_LOWERCASE: &str = "tags-lowercase";
+/// Severity inconsistent with the declared CVSS score
+pub const RULE_SEVERITY_MATCHES_CVSS: &str = "severity-matches-cvss";
+/// Template id containing characters other than `a-z`, `0-9`, `-` and `_`
+pub const RULE_ID_SPECIAL_CHARS: &str = "id-contains-special-chars";
+
+/// Reporting level for a lint rule
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    /// Rule is disabled
+    Allow,
+    /// Report as informational
+    Info,
+    /// Report as a warning
+    Warning,
+}
+
+/// Lint rule configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LintConfig {
+    /// Minimum description length for `description-too-short`
+    pub min_description_length: usize,
+    /// Per-rule level overrides
+    pub rules: BTreeMap<String, LintLevel>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            min_description_length: 20,
+            rules: BTreeMap::new(),
+        }
+    }
+}
+
+impl LintConfig {
+    /// Load configuration from a `.cxg-lint.yaml` file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_yaml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    /// Find the lint configuration that applies to a template file or directory
+    ///
+    /// Searches the directory itself and then its ancestors for
+    /// `.cxg-lint.yaml`.
+    pub fn find(path: &Path) -> Option<PathBuf> {
+        let start = if path.is_dir() { path } else { path.parent()? };
+        start
+            .ancestors()
+            .map(|dir| dir.join(LINT_CONFIG_FILE))
+            .find(|candidate| candidate.is_file())
+    }
+
+    /// Load the applicable configuration for a path, or defaults if none exists
+    pub fn for_path(path: &Path) -> Result<Self> {
+        match Self::find(path) {
+            Some(config_path) => Self::load(&config_path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Effective level for a rule, falling back to its default
+    pub fn level(&self, rule: &str, default: LintLevel) -> LintLevel {
+        self.rules.get(rule).copied().unwrap_or(default)
+    }
+}
+
+/// Style checker for template metadata
+#[derive(Debug, Default)]
+pub struct TemplateLinter {
+    config: LintConfig,
+}
+
+impl TemplateLinter {
+    /// Create a linter with default rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a linter with the given configuration
+    pub fn with_config(config: LintConfig) -> Self {
+        Self { config }
+    }
+
+    /// Lint template code and return style diagnostics
+    pub fn lint(&self, code: &str, language: TemplateLanguage) -> Vec<TemplateDiagnostic> {
+        let metadata = extract_metadata(code, language);
+        let mut diagnostics = Vec::new();
+
+        let min_len = self.config.min_description_length;
+        let description_len = metadata
+            .description
+            .as_deref()
+            .map(|d| d.trim().chars().count())
+            .unwrap_or(0);
+        if description_len < min_len {
+            self.report(
+                &mut diagnostics,
+                RULE_DESCRIPTION_TOO_SHORT,
+                LintLevel::Warning,
+                format!(
+                    "Description is {} characters; use at least {} to explain what is detected",
+                    description_len, min_len
+                ),
+            );
+        }
+
+        if let Some(author) = &metadata.author {
+            if !contains_email(author) {
+                self.report(
+                    &mut diagnostics,
+                    RULE_MISSING_AUTHOR_EMAIL,
+                    LintLevel::Info,
+                    format!(
+                        "Author '{}' has no contact email (e.g. 'Name <name@example.com>')",
+                        author
+                    ),
+                );
+            }
+        }
+
+        let uppercase_tags: Vec<&str> = metadata
+            .tags
+            .iter()
+            .filter(|t| t.chars().any(char::is_uppercase))
+            .map(String::as_str)
+            .collect();
+        if !uppercase_tags.is_empty() {
+            self.report(
+                &mut diagnostics,
+                RULE_TAGS_LOWERCASE,
+                LintLevel::Warning,
+                format!("Tags should be lowercase: {}", uppercase_tags.join(", ")),
+            );
+        }
+
+        if let (Some(cvss), Some(severity)) = (metadata.cvss, &metadata.severity) {
+            let expected = severity_for_cvss(cvss);
+            if !severity.eq_ignore_ascii_case(expected) {
+                self.report(
+                    &mut diagnostics,
+                    RULE_SEVERITY_MATCHES_CVSS,
+                    LintLevel::Warning,
+                    format!(
+                        "Severity '{}' does not match CVSS {:.1} (expected '{}')",
+                        severity, cvss, expected
+                    ),
+                );
+            }
+        }
+
+        if let Some(id) = &metadata.id {
+            let valid = id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+            if !valid {
+                self.report(
+                    &mut diagnostics,
+                    RULE_ID_SPECIAL_CHARS,
+                    LintLevel::Warning,
+                    format!(
+                        "Template id '{}' should only contain lowercase letters, digits, '-' and '_'",
+                        id
+                    ),
+                );
+            }
+        }
+
+        diagnostics
+    }
+
+    fn report(
+        &self,
+        diagnostics: &mut Vec<TemplateDiagnostic>,
+        rule: &str,
+        default: LintLevel,
+        message: String,
+    ) {
+        let severity = match self.config.level(rule, default) {
+            LintLevel::Allow => return,
+            LintLevel::Info => DiagnosticSeverity::Info,
+            LintLevel::Warning => DiagnosticSeverity::Warning,
+        };
+        diagnostics.push(TemplateDiagnostic {
+            severity,
+            code: format!("lint.{}", rule),
+            message,
+            line: None,
+            column: None,
+        });
+    }
+}
+
+/// Collect metadata from comment headers, preferring YAML fields when present
+fn extract_metadata(code: &str, language: TemplateLanguage) -> ParsedMetadata {
+    let mut metadata = parse_template_metadata(code, language, None);
+
+    // The header parser normalizes tags to lowercase, so re-read them as written
+    if let Some(raw_tags) = code
+        .lines()
+        .take(50)
+        .find_map(|line| line.split_once("@tags:").map(|(_, tags)| tags))
+    {
+        metadata.tags = raw_tags
+            .split(',')
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+    }
+
+    if language != TemplateLanguage::Yaml {
+        return metadata;
+    }
+
+    let Ok(yaml) = serde_yaml::from_str::<serde_yaml::Value>(code) else {
+        return metadata;
+    };
+    let field = |name: &str| yaml.get(name).and_then(|v| v.as_str()).map(str::to_string);
+
+    if let Some(id) = field("id") {
+        metadata.id = Some(id);
+    }
+    if let Some(author) = field("author") {
+        metadata.author = Some(author);
+    }
+    if let Some(severity) = field("severity") {
+        metadata.severity = Some(severity);
+    }
+    if let Some(description) = field("description") {
+        metadata.description = Some(description);
+    }
+    if let Some(tags) = yaml.get("tags").and_then(|v| v.as_sequence()) {
+        metadata.tags = tags
+            .iter()
+            .filter_map(|t| t.as_str().map(str::to_string))
+            .collect();
+    }
+    if let Some(cvss) = yaml
+        .get("cvss")
+        .or_else(|| yaml.get("cvss_score"))
+        .and_then(|v| v.as_f64())
+    {
+        metadata.cvss = Some(cvss as f32);
+    }
+
+    metadata
+}
+
+/// CVSS v3 qualitative severity rating for a base score
+fn severity_for_cvss(score: f32) -> &'static str {
+    match score {
+        s if s >= 9.0 => "critical",
+        s if s >= 7.0 => "high",
+        s if s >= 4.0 => "medium",
+        s if s > 0.0 => "low",
+        _ => "info",
+    }
+}
+
+fn contains_email(author: &str) -> bool {
+    author
+        .split(|c: char| c.is_whitespace() || c == '<' || c == '>' || c == ',')
+        .any(|part| {
+            part.split_once('@')
+                .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PYTHON_TEMPLATE: &str = r#"# @id: Redis_Check!
+# @author: Security Team
+# @severity: low
+# @cvss: 9.8
+# @description: Redis check
+# @tags: Redis, database
+import json
+"#;
+
+    fn codes(diagnostics: &[TemplateDiagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_lint_reports_all_rules() {
+        let diagnostics = TemplateLinter::new().lint(PYTHON_TEMPLATE, TemplateLanguage::Python);
+
+        assert_eq!(
+            codes(&diagnostics),
+            vec![
+                "lint.description-too-short",
+                "lint.missing-author-email",
+                "lint.tags-lowercase",
+                "lint.severity-matches-cvss",
+                "lint.id-contains-special-chars",
+            ]
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.severity != DiagnosticSeverity::Error));
+    }
+
+    #[test]
+    fn test_lint_config_overrides_rules() {
+        let config: LintConfig = serde_yaml::from_str(
+            "min_description_length: 5\nrules:\n  missing-author-email: allow\n  tags-lowercase: info\n",
+        )
+        .unwrap();
+        let diagnostics =
+            TemplateLinter::with_config(config).lint(PYTHON_TEMPLATE, TemplateLanguage::Python);
+
+        assert!(!codes(&diagnostics).contains(&"lint.description-too-short"));
+        assert!(!codes(&diagnostics).contains(&"lint.missing-author-email"));
+        let tags = diagnostics
+            .iter()
+            .find(|d| d.code == "lint.tags-lowercase")
+            .unwrap();
+        assert_eq!(tags.severity, DiagnosticSeverity::Info);
+    }
+
+    #[test]
+    fn test_clean_yaml_template() {
+        let yaml = r#"id: redis-unauth
+author: Jane Doe <jane@example.com>
+severity: critical
+cvss: 9.8
+description: Detects Redis instances that accept commands without authentication
+tags:
+  - redis
+  - database
+"#;
+        assert!(TemplateLinter::new()
+            .lint(yaml, TemplateLanguage::Yaml)
+            .is_empty());
+    }
+}
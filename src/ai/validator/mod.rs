@@ -23,10 +23,13 @@ mod finding_schema;
 mod go_lang;
 mod java;
 mod javascript;
+mod lint;
+mod mutate;
 mod patterns;
 mod perl;
 mod php;
 mod python;
+mod rhai_lang;
 mod ruby;
 mod rust_lang;
 mod shell;
@@ -37,6 +40,10 @@ pub use common::CommonValidator;
 pub use detect::{detect_language_from_content, detect_language_from_filename};
 pub use enhanced::EnhancedValidator;
 pub use finding_schema::FindingSchemaValidator;
+pub use lint::{LintConfig, LintLevel, TemplateLinter, LINT_CONFIG_FILE};
+pub use mutate::{
+    load_pass_response, MockResponse, Mutation, MutationOutcome, MutationReport, TemplateMutator,
+};
 pub use patterns::{PatternCategory, PatternRegistry, ValidationPattern};
 pub use syntax_check::SyntaxChecker;
 
@@ -170,7 +177,7 @@ impl TemplateValidator {
         let mut diagnostics = Vec::new();
 
         // Run common validators
-        diagnostics.extend(self.common.validate(code, language)?);
+        diagnostics.extend(self.common.validate_with_path(code, language, filename)?);
 
         // Detect language mismatch if filename provided
         if let Some(path) = filename {
@@ -197,8 +204,10 @@ impl TemplateValidator {
             diagnostics.extend(self.syntax_checker.check(code, language)?);
         }
 
-        // Run Finding schema validation (skip for YAML which has different structure)
-        if self.schema_validation_enabled && language != TemplateLanguage::Yaml {
+        // Run Finding schema validation (skip YAML and Rhai, which do not print JSON)
+        if self.schema_validation_enabled
+            && !matches!(language, TemplateLanguage::Yaml | TemplateLanguage::Rhai)
+        {
             diagnostics.extend(self.schema_validator.validate(code)?);
         }
 
@@ -215,6 +224,7 @@ impl TemplateValidator {
             TemplateLanguage::Ruby => ruby::validate(code)?,
             TemplateLanguage::Perl => perl::validate(code)?,
             TemplateLanguage::Php => php::validate(code)?,
+            TemplateLanguage::Rhai => rhai_lang::validate(code)?,
             TemplateLanguage::Yaml => yaml::validate(code)?,
         };
         diagnostics.extend(lang_diagnostics);
@@ -222,6 +232,31 @@ impl TemplateValidator {
         Ok(diagnostics)
     }
 
+    /// Decode a template file's bytes for validation
+    ///
+    /// Files saved as UTF-16 or with a BOM are transcoded and get a
+    /// `template.encoding` warning; files in no supported encoding get a
+    /// `template.encoding` error instead of text.
+    pub fn decode_template(
+        bytes: &[u8],
+    ) -> std::result::Result<(String, Option<TemplateDiagnostic>), TemplateDiagnostic> {
+        match crate::template::decode_source(bytes) {
+            Ok(source) => {
+                let warning = source.encoding.is_transcoded().then(|| {
+                    TemplateDiagnostic::warning(
+                        "template.encoding",
+                        format!(
+                            "File is saved as {}; it was transcoded, but save it as UTF-8 without a BOM",
+                            source.encoding
+                        ),
+                    )
+                });
+                Ok((source.text, warning))
+            }
+            Err(e) => Err(TemplateDiagnostic::error("template.encoding", e)),
+        }
+    }
+
     /// Legacy validate method (for backwards compatibility)
     pub fn validate(&self, code: &str, language: TemplateLanguage) -> Result<()> {
         let diagnostics = self.validate_with_diagnostics(code, language, None)?;
@@ -285,7 +320,7 @@ impl TemplateValidator {
         }
 
         // Check content heuristics
-        if let Some(detected_lang) = detect_language_from_content(code) {
+        if let Some((detected_lang, _)) = detect_language_from_content(code) {
             if detected_lang != declared_language {
                 return Some(TemplateDiagnostic::warning(
                     "language.content_mismatch",
@@ -311,6 +346,28 @@ impl Default for TemplateValidator {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_decode_template_reports_encoding() {
+        let (text, warning) = TemplateValidator::decode_template(b"id: plain\n").unwrap();
+        assert_eq!(text, "id: plain\n");
+        assert!(warning.is_none());
+
+        let utf16: Vec<u8> = "\u{feff}id: wide\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let (text, warning) = TemplateValidator::decode_template(&utf16).unwrap();
+        assert_eq!(text, "id: wide\n");
+        let warning = warning.unwrap();
+        assert_eq!(warning.code, "template.encoding");
+        assert_eq!(warning.severity, DiagnosticSeverity::Warning);
+        assert!(warning.message.contains("UTF-16LE"));
+
+        let error = TemplateValidator::decode_template(b"id: caf\xE9\n").unwrap_err();
+        assert_eq!(error.code, "template.encoding");
+        assert_eq!(error.severity, DiagnosticSeverity::Error);
+    }
+
     #[test]
     fn test_validator_creation() {
         let validator = TemplateValidator::new();
@@ -0,0 +1,549 @@
+//! Mutation testing for template matchers
+//!
+//! A template is run against a local mock server that first serves a known
+//! "passing" response and then mutations of it, each designed to break one
+//! matcher (drop a matched word, change the status code, ...). A mutation the
+//! template still fires on "survives" and points at a matcher that never
+//! decides the result.
+
+use crate::matcher::{MatchCondition, MatcherType, ResponsePart};
+use crate::template::Template;
+use crate::types::{Context, Protocol, Target};
+use anyhow::{bail, Context as _, Result};
+use regex::Regex;
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// Status codes tried, in order, when a mutation needs a non-matching status
+const FALLBACK_STATUSES: [u16; 5] = [404, 500, 200, 302, 403];
+
+/// HTTP response served by the mock server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockResponse {
+    /// Parse a raw HTTP response (`HTTP/1.1 200 OK`, headers, blank line, body)
+    ///
+    /// Text without a status line is treated as the body of a `200` response.
+    pub fn parse(raw: &str) -> Result<Self> {
+        let normalized = raw.replace("\r\n", "\n");
+        if !normalized.starts_with("HTTP/") {
+            return Ok(Self {
+                status: 200,
+                headers: Vec::new(),
+                body: raw.to_string(),
+            });
+        }
+
+        let (head, body) = normalized
+            .split_once("\n\n")
+            .unwrap_or((normalized.as_str(), ""));
+        let mut lines = head.lines();
+        let status_line = lines.next().unwrap_or_default();
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|s| s.parse().ok())
+            .with_context(|| format!("Invalid status line: {}", status_line))?;
+
+        let mut headers = Vec::new();
+        for line in lines {
+            let (name, value) = line
+                .split_once(':')
+                .with_context(|| format!("Invalid header line: {}", line))?;
+            // Content-Length is recomputed for every mutation
+            if !name.trim().eq_ignore_ascii_case("content-length") {
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+
+        Ok(Self {
+            status,
+            headers,
+            body: body.to_string(),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = format!("HTTP/1.1 {} Mock\r\n", self.status);
+        for (name, value) in &self.headers {
+            out.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        out.push_str(&format!(
+            "Content-Length: {}\r\nConnection: close\r\n\r\n",
+            self.body.len()
+        ));
+        out.push_str(&self.body);
+        out.into_bytes()
+    }
+}
+
+/// A mutated response and the matcher it is meant to break
+#[derive(Debug, Clone, Serialize)]
+pub struct Mutation {
+    pub description: String,
+    /// Index of the targeted matcher, or `None` for generic mutations
+    pub matcher: Option<usize>,
+    pub response: MockResponse,
+}
+
+/// Result of running the template against one mutation
+#[derive(Debug, Clone, Serialize)]
+pub struct MutationOutcome {
+    pub mutation: Mutation,
+    /// Whether the template still fired (the mutation survived)
+    pub fired: bool,
+    pub error: Option<String>,
+}
+
+/// Mutation test results for a template
+#[derive(Debug, Clone, Serialize)]
+pub struct MutationReport {
+    /// Whether the template fired on the unmodified passing response
+    pub baseline_fired: bool,
+    /// Number of matchers found in the template
+    pub matcher_count: usize,
+    pub outcomes: Vec<MutationOutcome>,
+}
+
+impl MutationReport {
+    /// Mutations the template still fired on
+    pub fn survivors(&self) -> impl Iterator<Item = &MutationOutcome> {
+        self.outcomes.iter().filter(|o| o.fired)
+    }
+
+    /// Indices of matchers whose every targeted mutation survived
+    pub fn dead_matchers(&self) -> Vec<usize> {
+        (0..self.matcher_count)
+            .filter(|&index| {
+                let mut targeted = self
+                    .outcomes
+                    .iter()
+                    .filter(|o| o.mutation.matcher == Some(index))
+                    .peekable();
+                targeted.peek().is_some() && targeted.all(|o| o.fired)
+            })
+            .collect()
+    }
+
+    /// Whether the baseline fired and every mutation was detected
+    pub fn passed(&self) -> bool {
+        self.baseline_fired && self.survivors().next().is_none()
+    }
+}
+
+/// Generates response mutations and checks that a template reacts to them
+#[derive(Debug, Default)]
+pub struct TemplateMutator {
+    matchers: Vec<MatcherType>,
+}
+
+impl TemplateMutator {
+    /// Create a mutator for the given matchers
+    ///
+    /// With no matchers (script templates), only generic mutations are used.
+    pub fn new(matchers: Vec<MatcherType>) -> Self {
+        Self { matchers }
+    }
+
+    /// Create a mutator from YAML template source
+    ///
+    /// Matchers are collected from the top level and from every `http` and
+    /// `network` request, in that order. Unrecognized matchers are skipped.
+    pub fn from_yaml(code: &str) -> Result<Self> {
+        let yaml: serde_yaml::Value =
+            serde_yaml::from_str(code).context("Failed to parse YAML template")?;
+
+        let mut lists = vec![yaml.get("matchers")];
+        for section in ["http", "network"] {
+            if let Some(requests) = yaml.get(section).and_then(|v| v.as_sequence()) {
+                lists.extend(requests.iter().map(|r| r.get("matchers")));
+            }
+        }
+
+        let matchers = lists
+            .into_iter()
+            .flatten()
+            .filter_map(|list| list.as_sequence())
+            .flatten()
+            .filter_map(|m| serde_yaml::from_value(m.clone()).ok())
+            .collect();
+        Ok(Self { matchers })
+    }
+
+    /// Matchers being tested
+    pub fn matchers(&self) -> &[MatcherType] {
+        &self.matchers
+    }
+
+    /// Generate mutations of a passing response
+    pub fn mutations(&self, pass: &MockResponse) -> Vec<Mutation> {
+        let mut mutations = Vec::new();
+
+        for (index, matcher) in self.matchers.iter().enumerate() {
+            let mut push = |description: String, response: MockResponse| {
+                if &response != pass {
+                    mutations.push(Mutation {
+                        description,
+                        matcher: Some(index),
+                        response,
+                    });
+                }
+            };
+
+            match matcher {
+                MatcherType::Status { status } => {
+                    if let Some(code) = FALLBACK_STATUSES.iter().find(|c| !status.contains(c)) {
+                        push(
+                            format!("status {} instead of {:?}", code, status),
+                            MockResponse {
+                                status: *code,
+                                ..pass.clone()
+                            },
+                        );
+                    }
+                }
+                // With `and`, dropping any single word must break the matcher
+                MatcherType::Word {
+                    words,
+                    condition: MatchCondition::And,
+                    part,
+                } => {
+                    for word in words {
+                        push(
+                            format!("without word '{}'", word),
+                            remove_word(pass, word, *part),
+                        );
+                    }
+                }
+                // With `or`, only dropping every word must break it
+                MatcherType::Word { words, part, .. } => {
+                    let response = words
+                        .iter()
+                        .fold(pass.clone(), |r, word| remove_word(&r, word, *part));
+                    push(format!("without words {}", quoted(words)), response);
+                }
+                MatcherType::Regex { regex, .. } => {
+                    let body = regex
+                        .iter()
+                        .filter_map(|pattern| Regex::new(pattern).ok())
+                        .fold(pass.body.clone(), |body, re| {
+                            re.replace_all(&body, "").into_owned()
+                        });
+                    push(
+                        format!("without matches of {}", quoted(regex)),
+                        MockResponse {
+                            body,
+                            ..pass.clone()
+                        },
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        if self.matchers.is_empty() {
+            mutations.extend(generic_mutations(pass));
+        }
+        mutations
+    }
+
+    /// Run the template against the passing response and each mutation
+    pub async fn run(
+        &self,
+        template: &dyn Template,
+        pass: &MockResponse,
+    ) -> Result<MutationReport> {
+        let server = MockServer::start(pass.clone()).await?;
+        let target = Target::with_port("127.0.0.1", server.addr.port(), Protocol::Http);
+        let context = Context::default();
+
+        let baseline_fired = !template
+            .execute(&target, &context)
+            .await
+            .context("Template failed against the passing response")?
+            .is_empty();
+
+        let mut outcomes = Vec::new();
+        if baseline_fired {
+            for mutation in self.mutations(pass) {
+                server.serve(mutation.response.clone());
+                let (fired, error) = match template.execute(&target, &context).await {
+                    Ok(findings) => (!findings.is_empty(), None),
+                    Err(e) => (false, Some(e.to_string())),
+                };
+                outcomes.push(MutationOutcome {
+                    mutation,
+                    fired,
+                    error,
+                });
+            }
+        }
+
+        Ok(MutationReport {
+            baseline_fired,
+            matcher_count: self.matchers.len(),
+            outcomes,
+        })
+    }
+}
+
+/// Remove every occurrence of `word` from the response part a word matcher checks
+fn remove_word(pass: &MockResponse, word: &str, part: ResponsePart) -> MockResponse {
+    let mut response = pass.clone();
+    if matches!(
+        part,
+        ResponsePart::Body | ResponsePart::All | ResponsePart::Data
+    ) {
+        response.body = response.body.replace(word, "");
+    }
+    if matches!(part, ResponsePart::Header | ResponsePart::All) {
+        response.headers = response
+            .headers
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let name = name.replace(word, "");
+                (!name.trim().is_empty()).then(|| (name, value.replace(word, "")))
+            })
+            .collect();
+    }
+    response
+}
+
+fn quoted(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|i| format!("'{}'", i))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Mutations that don't depend on matcher definitions
+fn generic_mutations(pass: &MockResponse) -> Vec<Mutation> {
+    let mut mutations = Vec::new();
+    let status = if pass.status == 404 { 200 } else { 404 };
+    mutations.push(Mutation {
+        description: format!("status {} instead of {}", status, pass.status),
+        matcher: None,
+        response: MockResponse {
+            status,
+            ..pass.clone()
+        },
+    });
+    if !pass.body.is_empty() {
+        mutations.push(Mutation {
+            description: "empty body".to_string(),
+            matcher: None,
+            response: MockResponse {
+                body: String::new(),
+                ..pass.clone()
+            },
+        });
+    }
+    for (index, (name, _)) in pass.headers.iter().enumerate() {
+        let mut response = pass.clone();
+        response.headers.remove(index);
+        mutations.push(Mutation {
+            description: format!("without header '{}'", name),
+            matcher: None,
+            response,
+        });
+    }
+    mutations
+}
+
+/// Minimal HTTP server returning a swappable canned response
+struct MockServer {
+    addr: SocketAddr,
+    response: Arc<Mutex<MockResponse>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockServer {
+    async fn start(response: MockResponse) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("Failed to start mock server")?;
+        let addr = listener.local_addr()?;
+        let response = Arc::new(Mutex::new(response));
+
+        let shared = response.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let bytes = shared.lock().map(|r| r.to_bytes()).unwrap_or_default();
+                tokio::spawn(async move {
+                    read_request(&mut stream).await;
+                    let _ = stream.write_all(&bytes).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+
+        Ok(Self {
+            addr,
+            response,
+            handle,
+        })
+    }
+
+    fn serve(&self, response: MockResponse) {
+        if let Ok(mut current) = self.response.lock() {
+            *current = response;
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Consume the request head and any `Content-Length` body
+async fn read_request(stream: &mut tokio::net::TcpStream) {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    while request.len() < 64 * 1024 {
+        let Ok(n) = stream.read(&mut buf).await else {
+            return;
+        };
+        if n == 0 {
+            return;
+        }
+        request.extend_from_slice(&buf[..n]);
+
+        let text = String::from_utf8_lossy(&request);
+        if let Some(head_end) = text.find("\r\n\r\n") {
+            let content_length = text[..head_end]
+                .lines()
+                .filter_map(|l| l.split_once(':'))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            if request.len() >= head_end + 4 + content_length {
+                return;
+            }
+        }
+    }
+}
+
+/// Load a passing response from a raw HTTP response file
+pub fn load_pass_response(path: &std::path::Path) -> Result<MockResponse> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let response = MockResponse::parse(&raw)?;
+    if response.body.is_empty() && response.headers.is_empty() {
+        bail!("{} has no headers or body to mutate", path.display());
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = r#"
+id: jenkins-panel
+info:
+  name: Jenkins panel
+http:
+  - method: GET
+    path: ["/"]
+    matchers-condition: and
+    matchers:
+      - type: status
+        status: [200]
+      - type: word
+        words: ["Jenkins", "Dashboard"]
+        condition: and
+      - type: word
+        part: header
+        words: ["X-Jenkins"]
+"#;
+
+    fn pass_response() -> MockResponse {
+        MockResponse::parse(
+            "HTTP/1.1 200 OK\r\nX-Jenkins: 2.401\r\nContent-Length: 99\r\n\r\n<title>Dashboard [Jenkins]</title>",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_pass_response() {
+        let response = pass_response();
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            response.headers,
+            vec![("X-Jenkins".to_string(), "2.401".to_string())]
+        );
+        assert_eq!(response.body, "<title>Dashboard [Jenkins]</title>");
+
+        let body_only = MockResponse::parse("just a body").unwrap();
+        assert_eq!(
+            (body_only.status, body_only.body.as_str()),
+            (200, "just a body")
+        );
+    }
+
+    #[test]
+    fn test_matcher_mutations() {
+        let mutator = TemplateMutator::from_yaml(TEMPLATE).unwrap();
+        assert_eq!(mutator.matchers().len(), 3);
+
+        let pass = pass_response();
+        let mutations = mutator.mutations(&pass);
+        let described: Vec<(Option<usize>, &str)> = mutations
+            .iter()
+            .map(|m| (m.matcher, m.description.as_str()))
+            .collect();
+        assert_eq!(
+            described,
+            vec![
+                (Some(0), "status 404 instead of [200]"),
+                (Some(1), "without word 'Jenkins'"),
+                (Some(1), "without word 'Dashboard'"),
+                (Some(2), "without words 'X-Jenkins'"),
+            ]
+        );
+
+        assert_eq!(mutations[1].response.body, "<title>Dashboard []</title>");
+        assert_eq!(mutations[1].response.headers, pass.headers);
+        assert!(mutations[3].response.headers.is_empty());
+        assert_eq!(mutations[3].response.body, pass.body);
+    }
+
+    #[test]
+    fn test_generic_mutations_and_dead_matchers() {
+        let pass = pass_response();
+        let generic = TemplateMutator::default().mutations(&pass);
+        assert_eq!(generic.len(), 3);
+        assert!(generic.iter().all(|m| m.matcher.is_none()));
+
+        // Matcher 1 survives every mutation aimed at it, matcher 0 does not
+        let mutator = TemplateMutator::from_yaml(TEMPLATE).unwrap();
+        let outcomes = mutator
+            .mutations(&pass)
+            .into_iter()
+            .map(|mutation| MutationOutcome {
+                fired: mutation.matcher == Some(1),
+                mutation,
+                error: None,
+            })
+            .collect();
+        let report = MutationReport {
+            baseline_fired: true,
+            matcher_count: 3,
+            outcomes,
+        };
+        assert_eq!(report.dead_matchers(), vec![1]);
+        assert!(!report.passed());
+    }
+}
@@ -0,0 +1,79 @@
+//! Rhai-specific template validation
+//!
+//! Rhai templates are compiled in-process, so syntax errors are reported
+//! here rather than by an external syntax checker.
+
+use super::TemplateDiagnostic;
+use anyhow::Result;
+
+/// Functions that give a template network access
+const NETWORK_FUNCTIONS: &[&str] = &["http_get(", "http_post(", "tcp_send("];
+
+pub fn validate(code: &str) -> Result<Vec<TemplateDiagnostic>> {
+    let mut diagnostics = Vec::new();
+
+    if let Err(e) = crate::engine::rhai::compile(code) {
+        let position = e.position();
+        let mut diagnostic = TemplateDiagnostic::error("rhai.syntax_error", e.to_string());
+        if let Some(line) = position.line() {
+            diagnostic = diagnostic.with_location(line, position.position());
+        }
+        diagnostics.push(diagnostic);
+    }
+
+    if !code.contains("emit_finding(") {
+        diagnostics.push(TemplateDiagnostic::error(
+            "rhai.missing_emit_finding",
+            "Rhai template never calls emit_finding(#{ title: ..., severity: ... }), \
+             so it cannot report findings",
+        ));
+    }
+
+    if !NETWORK_FUNCTIONS.iter().any(|f| code.contains(f)) {
+        diagnostics.push(TemplateDiagnostic::warning(
+            "rhai.missing_network_code",
+            "Rhai template does not call http_get, http_post or tcp_send",
+        ));
+    }
+
+    Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_template() {
+        let code = r#"
+let resp = http_get("/");
+if resp.status == 200 {
+    emit_finding(#{ title: "Reachable", severity: "info" });
+}
+"#;
+        assert!(validate(code).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_skeleton_is_valid() {
+        let skeleton = include_str!("../../../templates/skeleton/rhai-template-skeleton.rhai");
+        assert!(validate(skeleton).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_syntax_error_has_location() {
+        let code = "let resp = http_get(\"/\");\nlet status = ;\nemit_finding(#{});\n";
+        let diags = validate(code).unwrap();
+        let syntax = diags
+            .iter()
+            .find(|d| d.code == "rhai.syntax_error")
+            .expect("syntax error reported");
+        assert_eq!(syntax.line, Some(2));
+    }
+
+    #[test]
+    fn test_missing_emit_finding() {
+        let diags = validate("let resp = tcp_send(\"127.0.0.1\", 6379, \"PING\\r\\n\");").unwrap();
+        assert!(diags.iter().any(|d| d.code == "rhai.missing_emit_finding"));
+    }
+}
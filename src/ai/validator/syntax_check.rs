@@ -55,6 +55,7 @@ impl SyntaxChecker {
             TemplateLanguage::Cpp => self.check_cpp(code),
             TemplateLanguage::Java => self.check_java(code),
             TemplateLanguage::Yaml => Ok(vec![]), // YAML handled elsewhere
+            TemplateLanguage::Rhai => Ok(vec![]), // Compiled in-process by rhai_lang
         }
     }
 
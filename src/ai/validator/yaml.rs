@@ -13,7 +13,19 @@ use super::TemplateDiagnostic;
 use anyhow::Result;
 
 /// Valid matcher types
-const VALID_MATCHER_TYPES: &[&str] = &["word", "regex", "binary", "status", "size", "dsl", "xpath"];
+const VALID_MATCHER_TYPES: &[&str] = &[
+    "word",
+    "regex",
+    "binary",
+    "status",
+    "size",
+    "dsl",
+    "json",
+    "xpath",
+    "diff",
+    "content_sniff",
+    "latency",
+];
 
 /// Valid extractor types
 const VALID_EXTRACTOR_TYPES: &[&str] = &["regex", "kval", "xpath", "json", "dsl"];
@@ -26,6 +38,9 @@ const VALID_HTTP_METHODS: &[&str] = &[
 /// Valid matcher conditions
 const VALID_MATCHER_CONDITIONS: &[&str] = &["and", "or"];
 
+/// Valid `scheme` values of HTTP requests
+const VALID_HTTP_SCHEMES: &[&str] = &["auto", "http", "https", "both"];
+
 pub fn validate(code: &str) -> Result<Vec<TemplateDiagnostic>> {
     let mut diagnostics = Vec::new();
 
@@ -85,6 +100,19 @@ pub fn validate(code: &str) -> Result<Vec<TemplateDiagnostic>> {
     // Validate variable references
     diagnostics.extend(validate_variable_references(code));
 
+    // `wordlist:` payloads must name an embedded or user wordlist
+    diagnostics.extend(validate_wordlist_references(yaml_map, code));
+
+    // Default credential checks must name a supported service
+    if let Some(default_creds) = yaml_map.get("default_creds") {
+        diagnostics.extend(validate_default_creds_section(default_creds, code));
+    }
+
+    // Cloud storage checks must name a provider and known levels
+    if let Some(cloud_storage) = yaml_map.get("cloud_storage") {
+        diagnostics.extend(validate_cloud_storage_section(cloud_storage, code));
+    }
+
     Ok(diagnostics)
 }
 
@@ -185,10 +213,15 @@ fn validate_execution_blocks(
 ) -> Vec<TemplateDiagnostic> {
     let mut diagnostics = Vec::new();
 
-    let has_http = yaml_map.contains_key("http") || yaml_map.contains_key("requests");
+    let has_http = yaml_map.contains_key("http")
+        || yaml_map.contains_key("requests")
+        || yaml_map.contains_key("header_injection")
+        || yaml_map.contains_key("browser");
     let has_network = yaml_map.contains_key("network")
         || yaml_map.contains_key("tcp")
-        || yaml_map.contains_key("udp");
+        || yaml_map.contains_key("udp")
+        || yaml_map.contains_key("default_creds")
+        || yaml_map.contains_key("cloud_storage");
     let has_flows = yaml_map.contains_key("flows") || yaml_map.contains_key("workflow");
     let has_dns = yaml_map.contains_key("dns");
 
@@ -197,7 +230,7 @@ fn validate_execution_blocks(
         diagnostics.push(
             TemplateDiagnostic::error(
                 "yaml.no_execution_block",
-                "YAML template must have at least one of: 'http', 'network', 'dns', or 'flows' sections",
+                "YAML template must have at least one of: 'http', 'browser', 'header_injection', 'network', 'dns', or 'flows' sections",
             )
             .with_location(line, None)
         );
@@ -242,6 +275,28 @@ fn validate_http_section(http: &serde_yaml::Value, code: &str) -> Vec<TemplateDi
                 }
             }
 
+            // Validate the schemes the request is sent over
+            if let Some(scheme) = item_map.get("scheme") {
+                if !scheme
+                    .as_str()
+                    .is_some_and(|s| VALID_HTTP_SCHEMES.contains(&s))
+                {
+                    let line = find_yaml_field_line(code, "scheme");
+                    diagnostics.push(
+                        TemplateDiagnostic::error(
+                            "yaml.invalid_http_scheme",
+                            format!(
+                                "http[{}]: Unknown scheme {:?}. Valid: {}",
+                                idx,
+                                scheme,
+                                VALID_HTTP_SCHEMES.join(", ")
+                            ),
+                        )
+                        .with_location(line.unwrap_or(1), None),
+                    );
+                }
+            }
+
             // Validate path exists
             if !item_map.contains_key("path") && !item_map.contains_key("raw") {
                 diagnostics.push(TemplateDiagnostic::warning(
@@ -250,6 +305,26 @@ fn validate_http_section(http: &serde_yaml::Value, code: &str) -> Vec<TemplateDi
                 ));
             }
 
+            // At most one kind of request body
+            let bodies: Vec<&str> = ["body", "body_form", "body_multipart"]
+                .into_iter()
+                .filter(|field| item_map.contains_key(*field))
+                .collect();
+            if bodies.len() > 1 {
+                diagnostics.push(TemplateDiagnostic::error(
+                    "yaml.http_multiple_bodies",
+                    format!(
+                        "http[{}]: Use only one of {}",
+                        idx,
+                        bodies
+                            .iter()
+                            .map(|b| format!("'{}'", b))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                ));
+            }
+
             // Validate matchers in HTTP item
             if let Some(matchers) = item_map.get("matchers") {
                 diagnostics.extend(validate_matchers(matchers, code, &format!("http[{}]", idx)));
@@ -523,6 +598,37 @@ fn validate_matchers(
                             );
                         }
                     }
+                    kind @ ("json" | "xpath") if !matcher_map.contains_key(kind) => {
+                        diagnostics.push(TemplateDiagnostic::error(
+                            "yaml.matcher_missing_paths",
+                            format!(
+                                "{}.matchers[{}]: '{}' matcher requires a '{}' list of paths",
+                                context, idx, kind, kind
+                            ),
+                        ));
+                    }
+                    "latency" if !matcher_map.contains_key("payload_delay") => {
+                        diagnostics.push(TemplateDiagnostic::error(
+                            "yaml.matcher_latency_missing_delay",
+                            format!(
+                                "{}.matchers[{}]: 'latency' matcher requires 'payload_delay' field (seconds)",
+                                context, idx
+                            ),
+                        ));
+                    }
+                    "diff" => {
+                        for field in ["baseline_request", "detect_request", "min_diff_ratio"] {
+                            if !matcher_map.contains_key(field) {
+                                diagnostics.push(TemplateDiagnostic::error(
+                                    "yaml.matcher_diff_missing_field",
+                                    format!(
+                                        "{}.matchers[{}]: 'diff' matcher requires '{}' field",
+                                        context, idx, field
+                                    ),
+                                ));
+                            }
+                        }
+                    }
                     _ => {}
                 }
             } else {
@@ -786,6 +892,22 @@ fn validate_matchers_in_document(
         diagnostics.extend(validate_matchers(matchers, code, "root"));
     }
 
+    // Sections without their own validation still need compilable matchers
+    for section in ["browser", "grpc", "websocket", "ssh", "snmp"] {
+        let Some(items) = yaml_map.get(section).and_then(|v| v.as_sequence()) else {
+            continue;
+        };
+        for (idx, item) in items.iter().enumerate() {
+            if let Some(matchers) = item.get("matchers") {
+                diagnostics.extend(validate_matchers(
+                    matchers,
+                    code,
+                    &format!("{}[{}]", section, idx),
+                ));
+            }
+        }
+    }
+
     // Check matchers-condition at root
     if let Some(cond) = yaml_map.get("matchers-condition").and_then(|v| v.as_str()) {
         if !VALID_MATCHER_CONDITIONS.contains(&cond.to_lowercase().as_str()) {
@@ -814,6 +936,146 @@ fn validate_extractors_in_document(
     diagnostics
 }
 
+/// Validate `default_creds:` entries
+fn validate_default_creds_section(
+    default_creds: &serde_yaml::Value,
+    code: &str,
+) -> Vec<TemplateDiagnostic> {
+    let mut diagnostics = Vec::new();
+    for (idx, spec) in default_creds
+        .as_sequence()
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        let Some(service) = spec.get("service").and_then(|v| v.as_str()) else {
+            diagnostics.push(TemplateDiagnostic::error(
+                "yaml.missing_service",
+                format!("default_creds[{}] is missing 'service'", idx),
+            ));
+            continue;
+        };
+        if let Err(e) = service.parse::<crate::network::Service>() {
+            let line = code
+                .lines()
+                .position(|line| line.contains("service:") && line.contains(service))
+                .map(|idx| idx + 1);
+            diagnostics.push(
+                TemplateDiagnostic::error("yaml.unknown_service", e.message())
+                    .with_location(line.unwrap_or(1), None),
+            );
+        }
+    }
+    diagnostics
+}
+
+/// Validate `cloud_storage:` entries
+fn validate_cloud_storage_section(
+    cloud_storage: &serde_yaml::Value,
+    code: &str,
+) -> Vec<TemplateDiagnostic> {
+    let line_of = |key: &str, value: &str| {
+        code.lines()
+            .position(|line| line.contains(key) && line.contains(value))
+            .map_or(1, |idx| idx + 1)
+    };
+    let mut diagnostics = Vec::new();
+    for (idx, spec) in cloud_storage
+        .as_sequence()
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        let Some(provider) = spec.get("provider").and_then(|v| v.as_str()) else {
+            diagnostics.push(TemplateDiagnostic::error(
+                "yaml.missing_provider",
+                format!("cloud_storage[{}] is missing 'provider'", idx),
+            ));
+            continue;
+        };
+        match provider.to_lowercase().as_str() {
+            "gcs" | "gcp" | "google" => {}
+            "azure" | "azure-blob" | "blob" => {
+                if spec.get("accounts").is_none() {
+                    diagnostics.push(
+                        TemplateDiagnostic::error(
+                            "yaml.missing_accounts",
+                            format!("cloud_storage[{}] uses Azure and needs 'accounts'", idx),
+                        )
+                        .with_location(line_of("provider:", provider), None),
+                    );
+                }
+            }
+            _ => diagnostics.push(
+                TemplateDiagnostic::error(
+                    "yaml.unknown_provider",
+                    format!(
+                        "Unknown cloud storage provider '{}' (supported: gcs, azure)",
+                        provider
+                    ),
+                )
+                .with_location(line_of("provider:", provider), None),
+            ),
+        }
+        if spec.get("names").is_none() {
+            diagnostics.push(TemplateDiagnostic::error(
+                "yaml.missing_names",
+                format!("cloud_storage[{}] is missing 'names'", idx),
+            ));
+        }
+        for check in spec
+            .get("checks")
+            .and_then(|v| v.as_sequence())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+        {
+            if let Err(e) = check.parse::<crate::network::StorageExposure>() {
+                diagnostics.push(
+                    TemplateDiagnostic::error("yaml.unknown_storage_check", e.message())
+                        .with_location(line_of("checks", check), None),
+                );
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Validate `wordlist:<name>` payload values
+fn validate_wordlist_references(
+    yaml_map: &serde_yaml::Mapping,
+    code: &str,
+) -> Vec<TemplateDiagnostic> {
+    let Some(payloads) = yaml_map.get("payloads").and_then(|p| p.as_mapping()) else {
+        return Vec::new();
+    };
+    let store = crate::wordlist::WordlistStore::new();
+    payloads
+        .values()
+        .flat_map(|values| match values {
+            serde_yaml::Value::Sequence(items) => items.iter().collect(),
+            value => vec![value],
+        })
+        .filter_map(|value| crate::wordlist::reference_name(value.as_str()?))
+        .filter(|name| !store.contains(name))
+        .map(|name| {
+            let line = code
+                .lines()
+                .position(|line| line.contains(&format!("wordlist:{}", name)))
+                .map(|idx| idx + 1);
+            TemplateDiagnostic::error(
+                "yaml.unknown_wordlist",
+                format!(
+                    "Wordlist '{}' is neither embedded nor in {} (see 'cxg wordlist list')",
+                    name,
+                    crate::wordlist::WordlistStore::default_user_dir().display()
+                ),
+            )
+            .with_location(line.unwrap_or(1), None)
+        })
+        .collect()
+}
+
 /// Validate variable references {{variable}}
 fn validate_variable_references(code: &str) -> Vec<TemplateDiagnostic> {
     let mut diagnostics = Vec::new();
@@ -834,6 +1096,8 @@ fn validate_variable_references(code: &str) -> Vec<TemplateDiagnostic> {
         "BaseURL",
         "Hostname",
         "Host",
+        // Organisation label of the host, in `cloud_storage:` names
+        "org",
         "Port",
         "Path",
         "Scheme",
@@ -846,8 +1110,22 @@ fn validate_variable_references(code: &str) -> Vec<TemplateDiagnostic> {
         "rand_text_alphanumeric",
         "timestamp",
         "unix_timestamp",
+        // Built-in payload list of every path traversal encoding
+        crate::network::path_traversal::PATH_TRAVERSAL_PAYLOAD,
     ];
 
+    // Names defined by the template's `variables:` and `payloads:` maps
+    let defined: Vec<String> = serde_yaml::from_str::<serde_yaml::Value>(code)
+        .ok()
+        .map(|doc| {
+            ["variables", "payloads"]
+                .iter()
+                .filter_map(|key| doc.get(key)?.as_mapping())
+                .flat_map(|map| map.keys().filter_map(|k| k.as_str().map(String::from)))
+                .collect()
+        })
+        .unwrap_or_default();
+
     // Check for potentially undefined variables
     for var in &found_vars {
         // Skip built-in variables
@@ -862,7 +1140,7 @@ fn validate_variable_references(code: &str) -> Vec<TemplateDiagnostic> {
             || var.contains('/')
             || var.contains('=');
 
-        if !is_builtin && !is_dsl {
+        if !is_builtin && !is_dsl && !defined.contains(var) {
             // Check if variable is defined as extractor name
             let is_extractor = code.contains(&format!("name: {}", var))
                 || code.contains(&format!("name: \"{}\"", var))
@@ -998,6 +1276,33 @@ http:
         assert!(diags.iter().any(|d| d.code == "yaml.invalid_matcher_type"));
     }
 
+    #[test]
+    fn test_invalid_http_scheme() {
+        let yaml = r#"
+id: test
+name: Test
+author: test
+severity: low
+description: Test
+language: yaml
+
+http:
+  - path: ["/"]
+    scheme: http
+    matchers: [{type: status, status: [200]}]
+  - path: ["/"]
+    scheme: ftp
+    matchers: [{type: status, status: [200]}]
+"#;
+        let diags = validate(yaml).unwrap();
+        let schemes: Vec<_> = diags
+            .iter()
+            .filter(|d| d.code == "yaml.invalid_http_scheme")
+            .collect();
+        assert_eq!(schemes.len(), 1);
+        assert!(schemes[0].message.starts_with("http[1]"));
+    }
+
     #[test]
     fn test_missing_required_fields() {
         let yaml = r#"
@@ -1010,6 +1315,121 @@ http:
         assert!(diags.iter().any(|d| d.code.contains("missing_")));
     }
 
+    #[test]
+    fn test_variables_and_payloads_are_defined() {
+        let yaml = r#"
+id: test
+name: Test
+author: test
+severity: high
+description: Test
+language: yaml
+variables:
+  root: /api
+payloads:
+  param: [url, callback]
+http:
+  - path:
+      - "{{root}}/?{{param}}={{missing}}"
+    matchers:
+      - type: status
+        status: [200]
+"#;
+        let undefined: Vec<_> = validate(yaml)
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.code == "yaml.undefined_variable")
+            .collect();
+        assert_eq!(undefined.len(), 1);
+        assert!(undefined[0].message.contains("{{missing}}"));
+    }
+
+    #[test]
+    fn test_default_creds_services() {
+        let yaml = r#"
+id: test
+name: Test
+author: test
+severity: critical
+description: Test
+language: yaml
+default_creds:
+  - service: grafana
+  - service: couchdb
+"#;
+        let diagnostics = validate(yaml).unwrap();
+        assert!(!diagnostics
+            .iter()
+            .any(|d| d.code == "yaml.no_execution_block"));
+        let unknown: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.code == "yaml.unknown_service")
+            .collect();
+        assert_eq!(unknown.len(), 1);
+        assert!(unknown[0].message.contains("'couchdb'"));
+        assert_eq!(unknown[0].line, Some(10));
+    }
+
+    #[test]
+    fn test_cloud_storage_entries() {
+        let yaml = r#"
+id: test
+name: Test
+author: test
+severity: critical
+description: Test
+language: yaml
+cloud_storage:
+  - provider: gcs
+    names: ["{{org}}-backup"]
+    checks: [read, delete]
+  - provider: azure
+    names: [backups]
+  - provider: s3
+    names: [acme]
+"#;
+        let diagnostics = validate(yaml).unwrap();
+        let codes: Vec<&str> = diagnostics.iter().map(|d| d.code.as_str()).collect();
+        assert!(!codes.contains(&"yaml.no_execution_block"));
+        assert!(!codes.contains(&"yaml.undefined_variable"));
+        let find = |code: &str| diagnostics.iter().find(|d| d.code == code).unwrap();
+        assert_eq!(find("yaml.unknown_storage_check").line, Some(11));
+        assert!(find("yaml.unknown_storage_check")
+            .message
+            .contains("'delete'"));
+        assert_eq!(find("yaml.missing_accounts").line, Some(12));
+        assert_eq!(find("yaml.unknown_provider").line, Some(14));
+    }
+
+    #[test]
+    fn test_unknown_wordlist() {
+        let yaml = r#"
+id: test
+name: Test
+author: test
+severity: high
+description: Test
+language: yaml
+payloads:
+  path: "wordlist:admin-panels"
+  param: [url, "wordlist:no-such-list"]
+http:
+  - path:
+      - "{{path}}?{{param}}=1"
+    matchers:
+      - type: status
+        status: [200]
+"#;
+        let unknown: Vec<_> = validate(yaml)
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.code == "yaml.unknown_wordlist")
+            .collect();
+        assert_eq!(unknown.len(), 1);
+        assert!(unknown[0].message.contains("'no-such-list'"));
+        assert_eq!(unknown[0].line, Some(10));
+    }
+
     #[test]
     fn test_invalid_port() {
         let yaml = r#"
@@ -1049,5 +1469,13 @@ http:
 "#;
         let diags = validate(yaml).unwrap();
         assert!(diags.iter().any(|d| d.code == "yaml.invalid_regex_pattern"));
+
+        let ssh = yaml
+            .replace("http:\n  - path:\n      - \"/\"\n", "ssh:\n  - port: 22\n")
+            .replace("[invalid(regex", "kex: (diffie");
+        let diags = validate(&ssh).unwrap();
+        assert!(diags
+            .iter()
+            .any(|d| d.code == "yaml.invalid_regex_pattern" && d.message.contains("ssh[0]")));
     }
 }
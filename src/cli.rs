@@ -1,6 +1,6 @@
 //! Command-line interface for CERT-X-GEN
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -29,6 +29,7 @@ EXAMPLES:
   # Bulk input
   cxg scan --scope @targets.txt
   cxg scan --scope file://scopes/internal.txt
+  cat hosts.txt | cxg scan --scope -
 
   # Advanced scanning with filters
   cxg scan --scope example.com --template-language python,rust
@@ -85,6 +86,15 @@ pub struct Cli {
     #[arg(short, long, global = true, value_name = "FILE")]
     pub config: Option<PathBuf>,
 
+    /// Configuration profile to merge over the base config
+    #[arg(
+        long,
+        global = true,
+        value_name = "NAME",
+        help = "Use named profile from config. Allows quick switching between scan scenarios"
+    )]
+    pub profile: Option<String>,
+
     /// Update templates (shorthand for 'cxg template update')
     #[arg(
         long = "ut",
@@ -118,6 +128,25 @@ pub struct Cli {
         help = "Force template update on every startup (aggressive)"
     )]
     pub update_templates_on_startup: bool,
+
+    /// How a failing command reports its error on stderr
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = ErrorFormat::Text,
+        help = "Error output: text, or json ({\"code\", \"message\", \"hint\"}) for scripts"
+    )]
+    pub output_errors: ErrorFormat,
+}
+
+/// `--output-errors` formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// `Error: [code] message` followed by a hint line
+    Text,
+    /// One JSON object with code, message and hint
+    Json,
 }
 
 #[derive(Subcommand, Debug)]
@@ -143,11 +172,17 @@ pub enum Commands {
     /// Manage sandbox environment
     Sandbox(SandboxCommand),
 
+    /// Inspect the wordlists available to template payloads
+    Wordlist(WordlistCommand),
+
+    /// Show which interpreter or compiler each template engine resolves to
+    Doctor,
+
     /// Display version information
     Version,
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(
     about = "Run a security scan against targets using vulnerability detection templates",
     long_about = "Execute comprehensive security scans against one or more targets using the multi-language \
@@ -166,6 +201,8 @@ TARGET SPECIFICATION:
       • Single host or URL (example.com, https://api.example.com:8443)
       • Comma-separated lists (example.com,test.com,192.168.1.1)
       • Files via @targets.txt or file://path/to/targets.txt (one entry per line, # for comments)
+        Pin templates to a file entry with: https://api.example.com # templates:api-auth,api-injection
+      • Stdin via - or @- (one entry per line, e.g. subfinder -d example.com | cxg scan --scope -)
       • CIDR ranges (192.168.1.0/24, 10.0.0.0/8)
       • Domains and subdomains (example.com, api.example.com)
       • Mixed entries in a single invocation
@@ -173,6 +210,20 @@ TARGET SPECIFICATION:
     Legacy flags (--target, --targets, --target-file, --domain, --domains, --domain-file, --cidr) remain as aliases.
     The scanner automatically deduplicates and expands entries from files.
 
+  --scope-from <SOURCE>
+    Import scope from another tool's output (repeatable, deduplicated against --scope):
+      • shodan:results.json      Shodan export (JSON lines or array, .gz ok); keeps ip/port/transport
+      • asn:AS13335              Announced IPv4 prefixes via RIPEstat, expanded as CIDRs
+      • csv:hosts.csv?column=ip  One CSV column (header name or index; first column by default)
+    Use --offline to refuse network lookups such as asn: imports.
+
+  --zone-transfer <DOMAIN>
+    Ask DOMAIN's nameservers (NS records) for the whole zone (AXFR) and scan the
+    domain plus every A, AAAA and CNAME host it lists. A refused transfer, the
+    usual outcome, is reported as an Info finding; an allowed one as Medium.
+    Example:
+      cxg scan --zone-transfer example.com --templates http-title
+
 PORT SELECTION:
   Customize which ports to scan.
 
@@ -309,6 +360,38 @@ PERFORMANCE AND CONCURRENCY:
     Recommendation: Lower for fragile targets (5), higher for robust systems (20)
     Example:
       cxg scan --scope example.com --parallel-templates 5
+  
+  --adaptive-concurrency
+    Let the scan find its own pace. Every 20 HTTP requests, per host and overall, the number
+    of templates running at once is halved if more than 10% failed (connection errors,
+    timeouts, 429 or 503) and raised by one if none did. Starts at --max-concurrency.
+    --min-concurrency / --max-concurrency bound it; --stealth caps it at 2, --safe at 5.
+    Example:
+      cxg scan --scope @targets.txt --adaptive-concurrency --max-concurrency 200
+  
+  --randomize-targets, --randomize-templates
+    Shuffle the target order (after port expansion) and/or each target's template order,
+    so one subnet or service is not hit by consecutive checks.
+    Example:
+      cxg scan --cidr 10.0.0.0/16 --randomize-targets --randomize-templates
+  
+  --priority <EXPR>
+    Order in which each target's templates start, so a scan stopped early has already run
+    the checks that matter. By default templates run highest severity first. Boost sets of
+    templates with key:value terms, earliest term first (a value without a key extends the
+    previous term; keys: tags, severity, id); the rest follow by severity. Use none to keep the
+    load order. With the default interleaved scheduling every target in flight still starts
+    its first templates right away. --randomize-templates only shuffles templates of equal
+    priority.
+    Examples:
+      cxg scan --scope @targets.txt --priority tags:rce,sqli,severity:critical
+      cxg scan --scope example.com --priority none
+  
+  --seed <N>
+    Seed for the shuffles. The seed of every randomized run is printed with -v and stored
+    in the results (statistics.seed); pass it back to replay the same order.
+    Example:
+      cxg scan --cidr 10.0.0.0/16 --randomize-targets --seed 8172635
 
 TIMEOUTS AND RETRIES:
   Configure how the scanner handles slow responses and failures.
@@ -392,6 +475,14 @@ NETWORK CONFIGURATION:
     Example:
       cxg scan --scope example.com --cookie 'session=abc123' --cookie 'user=admin'
   
+  --session-cookie <NAME:KEY=VALUE> / --session-header <NAME:KEY: VALUE>
+    Log in as several users at once. Templates send a request as one of them with
+    `session: NAME`, e.g. to check whether user_a can read what user_b owns (IDOR).
+    Each session replaces --cookie with its own cookies and keeps its own cookie jar.
+    Example:
+      cxg scan --scope app.example.com --session-cookie 'user_a:sid=aaa' \\
+        --session-cookie 'user_b:sid=bbb' --templates idor-orders.yaml
+  
   --follow-redirects
     Follow HTTP redirects automatically. Useful for discovering redirect chains.
     Default: Enabled
@@ -403,6 +494,14 @@ NETWORK CONFIGURATION:
     Default: 5
     Example:
       cxg scan --scope example.com --max-redirects 3
+  
+  --detect-scheme / --no-detect-scheme
+    Before scanning, try a TLS handshake and then a plain HTTP request on each
+    host:port target, so HTTPS on 8080 or HTTP on 8443 is scanned over the right
+    scheme without retrying the other. Ports the probe cannot tell use the
+    network.scheme_ports table. On by default, off with --passive.
+    Example:
+      cxg scan --scope admin.example.com:8080 --no-detect-scheme
 
 ADVANCED FEATURES:
   Advanced capabilities for complex scanning scenarios.
@@ -443,7 +542,7 @@ CONFIGURATION FILES:
     Use a named configuration profile from your config file.
     Profiles allow quick switching between different scanning scenarios.
     Example:
-      cxg scan --profile production --scope example.com
+      cxg scan --config cert-x-gen.yaml --profile production --scope example.com
 
 COMMON SCANNING SCENARIOS:
 
@@ -497,10 +596,64 @@ pub struct ScanArgs {
         ],
         value_name = "SCOPE",
         value_delimiter = ',',
-        help = "Smart target selector. Accepts single host, comma lists, files (@file.txt), CIDR blocks (192.168.1.0/24), domains, URLs, or mixed entries"
+        help = "Smart target selector. Accepts single host, comma lists, files (@file.txt), stdin (-), CIDR blocks (192.168.1.0/24), domains, URLs, or mixed entries"
     )]
     pub scope: Vec<String>,
 
+    /// Import scope from third-party exports
+    #[arg(
+        long = "scope-from",
+        value_name = "SOURCE",
+        help = "Import scope from shodan:<export.json>, asn:<AS13335> or csv:<hosts.csv>?column=<name>&labels=<col,col>. Repeatable"
+    )]
+    pub scope_from: Vec<String>,
+
+    /// Add the hosts a DNS zone transfer reveals to the scope
+    #[arg(
+        long = "zone-transfer",
+        value_name = "DOMAIN",
+        help = "Attempt an AXFR of DOMAIN from its nameservers and scan every A, AAAA and CNAME host it reveals. Repeatable"
+    )]
+    pub zone_transfer: Vec<String>,
+
+    /// Labels applied to every target
+    #[arg(
+        long = "label",
+        value_name = "KEY=VALUE",
+        help = "Label every target (e.g. env=prod); copied onto its findings for grouping. Repeatable; labels from CSV scope columns take precedence"
+    )]
+    pub labels: Vec<String>,
+
+    /// Disable scope importers that need network access
+    #[arg(
+        long,
+        help = "Do not contact external services while building scope (disables asn: imports)"
+    )]
+    pub offline: bool,
+
+    /// Keep targets exactly as given
+    #[arg(
+        long,
+        help = "Do not normalize targets (case, default ports, trailing slashes, IP aliases) before deduplication"
+    )]
+    pub no_normalize: bool,
+
+    /// OpenAPI/Swagger spec whose endpoints become targets
+    #[arg(
+        long,
+        value_name = "SPEC",
+        help = "Scan every endpoint of an OpenAPI 2/3 spec (JSON/YAML file or URL). Uses the spec's servers when no --scope is given"
+    )]
+    pub openapi: Option<String>,
+
+    /// Cap on endpoints taken from the OpenAPI spec
+    #[arg(
+        long,
+        default_value_t = cert_x_gen::scope::openapi::DEFAULT_MAX_ENDPOINTS,
+        help = "Maximum number of OpenAPI endpoints to scan"
+    )]
+    pub max_endpoints: usize,
+
     // Port specification
     /// Smart port selector that adds to template defaults
     #[arg(
@@ -524,6 +677,21 @@ pub struct ScanArgs {
     )]
     pub override_ports: Option<String>,
 
+    /// Probe host:port targets for TLS to pick their scheme
+    #[arg(
+        long,
+        help = "Probe host:port targets for TLS or plain HTTP before scanning instead of guessing from the port (default unless --passive)"
+    )]
+    pub detect_scheme: bool,
+
+    /// Guess host:port schemes from the port table only
+    #[arg(
+        long,
+        conflicts_with = "detect_scheme",
+        help = "Skip the scheme probe; host:port targets use the port table (network.scheme_ports)"
+    )]
+    pub no_detect_scheme: bool,
+
     // Protocol specification
     /// Protocol to use for scanning
     #[arg(long, help = "Specify protocol: http, https, tcp, udp, etc.")]
@@ -566,6 +734,24 @@ pub struct ScanArgs {
     )]
     pub severity: Option<Vec<SeverityArg>>,
 
+    /// Leave findings below this severity out of the output
+    #[arg(
+        long,
+        alias = "min-severity",
+        value_enum,
+        help = "Only report findings at or above this severity. Output-only: every selected template still runs"
+    )]
+    pub report_min_severity: Option<SeverityArg>,
+
+    /// Leave findings below this confidence out of the output
+    #[arg(
+        long,
+        value_name = "0-100",
+        value_parser = clap::value_parser!(u8).range(0..=100),
+        help = "Only report findings with at least this confidence (0-100). Output-only: every selected template still runs"
+    )]
+    pub min_confidence: Option<u8>,
+
     /// Exclude templates matching pattern (supports wildcards)
     #[arg(long, help = "Exclude templates. Example: test-*,experimental-*")]
     pub exclude_templates: Option<String>,
@@ -579,6 +765,29 @@ pub struct ScanArgs {
     )]
     pub template_language: Option<Vec<LanguageArg>>,
 
+    /// Run the replacements of deprecated templates named in --templates
+    #[arg(
+        long,
+        help = "Run the template named by superseded-by instead of each deprecated template selected with --templates"
+    )]
+    pub follow_deprecations: bool,
+
+    /// Keep deprecated templates when scanning with all templates
+    #[arg(
+        long,
+        help = "Include deprecated templates in scans that are not limited by --templates (skipped by default)"
+    )]
+    pub include_deprecated: bool,
+
+    /// Try default credentials against these services (comma-separated)
+    #[arg(
+        long,
+        value_name = "SERVICES",
+        value_delimiter = ',',
+        help = "Try the bundled default credentials against these services: jenkins, grafana, elasticsearch, tomcat, rabbitmq, redis, mongo. Without --templates, only these checks run"
+    )]
+    pub test_creds: Vec<String>,
+
     // Execution options
     /// Number of worker threads (default: CPU cores)
     ///
@@ -605,6 +814,60 @@ pub struct ScanArgs {
     )]
     pub parallel_templates: usize,
 
+    /// Tune parallelism from error, timeout and 429 rates
+    #[arg(
+        long,
+        help = "Halve parallelism when targets start failing or throttling, and raise it again while requests succeed"
+    )]
+    pub adaptive_concurrency: bool,
+
+    /// Lowest parallelism adaptive concurrency backs off to
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "adaptive_concurrency",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Lowest number of templates run at once with --adaptive-concurrency (default: 1)"
+    )]
+    pub min_concurrency: Option<usize>,
+
+    /// Highest parallelism adaptive concurrency grows to
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "adaptive_concurrency",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Highest number of templates run at once with --adaptive-concurrency (default: parallel targets × parallel templates)"
+    )]
+    pub max_concurrency: Option<usize>,
+
+    /// Scan targets in random order
+    #[arg(
+        long,
+        help = "Shuffle targets after expansion so hosts of one subnet are not scanned back to back"
+    )]
+    pub randomize_targets: bool,
+
+    /// Run templates in a random order on each target
+    #[arg(long, help = "Shuffle the template order separately for each target")]
+    pub randomize_templates: bool,
+
+    /// Order of each target's templates
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Template order per target: severity (default, highest first), none (load order), or boosted sets like tags:rce,severity:critical"
+    )]
+    pub priority: Option<String>,
+
+    /// Seed for --randomize-targets and --randomize-templates
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Seed the shuffles so a run's order can be replayed (default: random, printed with -v and stored in the results' statistics)"
+    )]
+    pub seed: Option<u64>,
+
     /// Timeout duration (supports: s=seconds, m=minutes, h=hours)
     #[arg(
         long,
@@ -621,6 +884,43 @@ pub struct ScanArgs {
     )]
     pub retry: u32,
 
+    /// Skip the pre-compilation phase for compiled-language templates
+    #[arg(
+        long,
+        help = "Compile Rust/Go/C/C++/Java templates lazily on first use instead of before the scan"
+    )]
+    pub no_precompile: bool,
+
+    /// Time each template's load, compile, network and matcher phases
+    #[arg(
+        long,
+        help = "Print the 10 slowest templates with a per-phase time breakdown after the scan"
+    )]
+    pub profile_templates: bool,
+
+    /// Directory to write every HTTP request and response to
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Write each HTTP request/response as <DIR>/<scan_id>/<target>/<template_id>/<seq>.req.txt and .res.txt"
+    )]
+    pub trace_requests: Option<PathBuf>,
+
+    /// Keep credentials in request traces
+    #[arg(
+        long,
+        requires = "trace_requests",
+        help = "Do not redact Authorization, Cookie and Set-Cookie values in --trace-requests output"
+    )]
+    pub trace_no_redact: bool,
+
+    /// Summarize target liveness seen during the scan
+    #[arg(
+        long,
+        help = "Report per-target liveness (reachable, lowest status, Server, title, TLS CN) from the scan's own requests"
+    )]
+    pub probe_summary: bool,
+
     /// Rate limit in requests per second (prevents overwhelming targets)
     #[arg(
         long,
@@ -628,6 +928,37 @@ pub struct ScanArgs {
     )]
     pub rate_limit: Option<u32>,
 
+    /// Rate limit per target domain in requests per second
+    #[arg(
+        long,
+        help = "Max requests/sec to any single domain, on top of --rate-limit"
+    )]
+    pub domain_rate_limit: Option<u32>,
+
+    /// Random per-request delay as a fraction of the rate-limit interval
+    #[arg(
+        long,
+        value_name = "0.0-1.0",
+        help = "Add up to this fraction of the rate-limit interval as random delay to every request, blurring timing patterns"
+    )]
+    pub jitter_factor: Option<f64>,
+
+    /// Most requests to send to any one host
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Stop sending requests to a host after N; its remaining templates are counted as skipped"
+    )]
+    pub max_requests_per_host: Option<u64>,
+
+    /// Most requests to send in total
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Stop sending requests after N in total; remaining templates are counted as skipped"
+    )]
+    pub max_requests_total: Option<u64>,
+
     // Scanning modes
     /// Enable aggressive mode (WARNING: intrusive, may cause disruption)
     #[arg(
@@ -686,6 +1017,22 @@ pub struct ScanArgs {
     )]
     pub cookie: Option<Vec<String>>,
 
+    /// Cookie of a named session (name:key=value, repeatable)
+    #[arg(
+        long,
+        value_name = "NAME:KEY=VALUE",
+        help = "Add a cookie to a named session that templates send requests as (session: NAME). Example: \"user_a:sid=abc\""
+    )]
+    pub session_cookie: Vec<String>,
+
+    /// Header of a named session (name:key: value, repeatable)
+    #[arg(
+        long,
+        value_name = "NAME:KEY: VALUE",
+        help = "Add a header to a named session. Example: \"user_b:Authorization: Bearer xyz\""
+    )]
+    pub session_header: Vec<String>,
+
     /// Follow HTTP redirects automatically (enabled by default)
     #[arg(
         long,
@@ -711,14 +1058,58 @@ pub struct ScanArgs {
     )]
     pub output: String,
 
-    /// Output formats (comma-separated: json,html,sarif,csv,xml)
+    /// Output formats (comma-separated: json,html,sarif,csv,xml,elasticsearch,inventory)
     #[arg(
         long,
         default_value = "json",
-        help = "Output formats. json=automation, csv=spreadsheet, sarif=CI/CD, html=visual, xml=enterprise"
+        help = "Output formats. json=automation, csv=spreadsheet, sarif=CI/CD, html=visual, xml=enterprise, elasticsearch=index into output.elasticsearch_url, inventory=detected technologies per target"
     )]
     pub output_format: String,
 
+    /// MiniJinja output template
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Render results through a MiniJinja template (.j2). report.csv.j2 writes <basename>.csv; see templates/output-templates/"
+    )]
+    pub output_template: Option<PathBuf>,
+
+    /// Replace existing results files
+    #[arg(
+        long,
+        help = "Replace existing results files. Without it (or --output-timestamp) the scan refuses to overwrite them"
+    )]
+    pub output_overwrite: bool,
+
+    /// Add the scan's start time to the output basename
+    #[arg(
+        long,
+        help = "Add the scan start time to the output basename (scan-results-20240301-142501.json) so earlier results are kept"
+    )]
+    pub output_timestamp: bool,
+
+    /// Break down the scan summary by a target label
+    #[arg(
+        long,
+        value_name = "KEY",
+        help = "Also count findings per value of this target label in the scan summary (e.g. owner)"
+    )]
+    pub summary_by: Option<String>,
+
+    /// Cluster findings of each template by response similarity
+    #[arg(
+        long,
+        help = "Group findings of the same template whose responses are near-identical and report cluster counts in the summary"
+    )]
+    pub cluster: bool,
+
+    /// Report one finding per response cluster
+    #[arg(
+        long,
+        help = "Emit one finding per cluster with a duplicates count and the affected targets (implies --cluster)"
+    )]
+    pub collapse_clusters: bool,
+
     /// Enable real-time streaming output (results shown as found)
     #[arg(
         long,
@@ -726,6 +1117,87 @@ pub struct ScanArgs {
     )]
     pub stream: bool,
 
+    /// Capture screenshots of web findings
+    #[arg(
+        long,
+        help = "Screenshot the page of every HTTP finding in a headless browser; embedded in HTML/SARIF, written to screenshots/ next to JSON"
+    )]
+    pub screenshot: bool,
+
+    /// Keep template working directories and copy referenced artifacts
+    #[arg(
+        long,
+        help = "Keep each template's working directory (~/.cert-x-gen/work/<scan_id>/) and copy artifacts findings reference to artifacts/ next to the results"
+    )]
+    pub keep_artifacts: bool,
+
+    /// Remove kept workspaces older than this many days
+    #[arg(
+        long,
+        value_name = "DAYS",
+        help = "Remove kept scan workspaces older than DAYS when a scan starts (default: 7, 0 keeps them forever)"
+    )]
+    pub workspace_retention_days: Option<u64>,
+
+    /// Do not record template usage statistics
+    #[arg(
+        long,
+        help = "Skip updating per-template run/hit counters in ~/.cert-x-gen/stats/ (also: templates.usage_tracking: false)"
+    )]
+    pub no_usage_tracking: bool,
+
+    /// Watch template files and re-run them on change
+    #[arg(
+        long,
+        help = "Re-run a template against the targets whenever its file changes (requires --templates with file paths)"
+    )]
+    pub watch: bool,
+
+    /// Print the scan plan and exit
+    #[arg(
+        long,
+        help = "Print the templates, targets and estimated requests, then exit without scanning"
+    )]
+    pub dry_run: bool,
+
+    /// Print the scan plan as JSON and exit
+    #[arg(long, help = "Like --dry-run, but print the plan as JSON")]
+    pub dry_run_json: bool,
+
+    /// Jobs file for multi-job scans
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Run every job of a YAML jobs file (own scope, template filters, modes and output each) with templates loaded once"
+    )]
+    pub input: Option<PathBuf>,
+
+    /// Jobs from --input run at once
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_name = "N",
+        requires = "input",
+        help = "Number of --input jobs to run concurrently"
+    )]
+    pub jobs_parallel: usize,
+
+    /// Stop the remaining --input jobs after one fails
+    #[arg(
+        long,
+        requires = "input",
+        help = "Do not start further --input jobs once one has failed"
+    )]
+    pub fail_fast: bool,
+
+    /// A/B comparison of two template versions
+    #[command(flatten)]
+    pub ab_test: AbTestArgs,
+
+    /// Headless browser for `browser:` templates
+    #[command(flatten)]
+    pub browser: BrowserArgs,
+
     /// Quiet mode (suppress non-essential output)
     #[arg(
         short,
@@ -762,13 +1234,61 @@ pub struct ScanArgs {
         help = "Worker identifier in distributed mode. Example: scanner-01, worker-east-1"
     )]
     pub worker_id: Option<String>,
+}
+
+/// Options for comparing two versions of a template during a scan
+#[derive(Args, Debug, Default, Clone)]
+pub struct AbTestArgs {
+    /// Template files to compare (A = current, B = candidate)
+    #[arg(
+        long = "ab-test",
+        num_args = 2,
+        value_names = ["TEMPLATE_A", "TEMPLATE_B"],
+        help = "Run two template versions against every target and report agreements, disagreements (only A fires) and regressions (only B fires)"
+    )]
+    pub ab_test: Option<Vec<PathBuf>>,
+}
 
-    /// Configuration profile name from config file
+/// Headless browser options for templates that render pages
+#[derive(Args, Debug, Default, Clone)]
+pub struct BrowserArgs {
+    /// Chrome or Chromium executable
     #[arg(
         long,
-        help = "Use named profile from config. Allows quick switching between scan scenarios"
+        value_name = "PATH",
+        help = "Chrome/Chromium used by browser templates (default: found on PATH)"
     )]
-    pub profile: Option<String>,
+    pub browser_path: Option<PathBuf>,
+
+    /// Pages rendered at once
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Maximum browser tabs open at once; all tabs share one browser process"
+    )]
+    pub browser_tabs: Option<usize>,
+
+    /// Per-page timeout in seconds
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Give up on a page after this many seconds"
+    )]
+    pub browser_timeout: Option<u64>,
+
+    /// Show the browser window
+    #[arg(
+        long,
+        help = "Run the browser with a visible window (for debugging templates)"
+    )]
+    pub browser_headful: bool,
+
+    /// Disable Chrome's sandbox
+    #[arg(
+        long,
+        help = "Start Chrome with --no-sandbox; needed when running as root, e.g. in containers"
+    )]
+    pub browser_no_sandbox: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -798,15 +1318,33 @@ EXAMPLES:
   cxg template validate ~/.cert-x-gen/templates/
   cxg template validate ./my-templates/ --recursive
   cxg template validate ./redis-check.c
+  cxg template validate ./my-templates/ --strict-lint
+  cxg template validate ./redis-check.py --explain
+
+  # Check template style (rules configurable via .cxg-lint.yaml)
+  cxg template lint ./my-templates/ --recursive
 
   # Update templates from repository
   cxg template update
   cxg template update --force
 
+  # Bundle, install and list template packs
+  cxg template pack create --name acme-corp-pack --templates ./acme-templates/
+  cxg template pack install ./acme-corp-pack.cxgpack
+  cxg template pack install https://example.com/packs/acme-corp-pack.cxgpack
+  cxg template pack list
+
+  # Find templates that detect the same thing
+  cxg template deduplicate --threshold 0.85
+  cxg template deduplicate --offline --json
+
   # Create new template from skeleton
   cxg template create --id my-check --language python --name \"My Check\"
   cxg template create --id redis-test --language c --output ./my-templates/
 
+  # Scaffold a buildable project for a compiled template
+  cxg template init-project --language rust --id my-check --output ./my-check/
+
   # Test a template
   cxg template test --template ./my-template.c --target 192.168.1.100
   cxg template test --template redis-unauthenticated --target localhost --debug"
@@ -831,6 +1369,29 @@ pub enum TemplateAction {
         /// Filter by tags (comma-separated)
         #[arg(long, value_name = "TAG,TAG,...")]
         tags: Option<String>,
+
+        /// Show how often each template ran and how many findings it produced
+        #[arg(long)]
+        usage: bool,
+    },
+
+    /// Summarize the template collection (languages, severities, tags, authors, dates)
+    Stats {
+        /// Rows in the tag and author lists, or templates in each --usage list
+        #[arg(long, default_value_t = 20, value_name = "N")]
+        top: usize,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "table")]
+        format: StatsFormat,
+
+        /// Output as JSON (same as --format json)
+        #[arg(long)]
+        json: bool,
+
+        /// Show the most and least productive templates from local usage instead
+        #[arg(long)]
+        usage: bool,
     },
 
     /// Validate template files
@@ -845,6 +1406,60 @@ pub enum TemplateAction {
         /// Output validation results as JSON
         #[arg(long)]
         json: bool,
+
+        /// Run lint rules and treat their warnings as errors
+        #[arg(long)]
+        strict_lint: bool,
+
+        /// Also run the `tests:` cases of YAML templates
+        #[arg(long)]
+        with_tests: bool,
+
+        /// Ask the AI provider to explain each diagnostic and suggest a patch
+        #[arg(long)]
+        explain: bool,
+    },
+
+    /// Run the `tests:` cases of YAML templates against their matchers (no network)
+    SelfTest {
+        /// YAML template file or directory (searched recursively)
+        path: PathBuf,
+
+        /// Fail templates that have no test cases
+        #[arg(long)]
+        strict: bool,
+
+        /// Output results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check template style (lint rules only, no validation)
+    Lint {
+        /// Template file or directory to lint
+        path: PathBuf,
+
+        /// Recursively lint all templates in subdirectories
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Output lint results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check that every matcher affects the result using mutated mock responses
+    MutateTest {
+        /// Template file to test
+        template: PathBuf,
+
+        /// Raw HTTP response the template should fire on
+        #[arg(long, value_name = "FILE")]
+        mock_pass_response: PathBuf,
+
+        /// Output the mutation report as JSON
+        #[arg(long)]
+        json: bool,
     },
 
     /// Update templates from remote repository
@@ -879,6 +1494,25 @@ pub enum TemplateAction {
         output: PathBuf,
     },
 
+    /// Scaffold a buildable multi-file project for a compiled template
+    InitProject {
+        /// Unique template ID (kebab-case)
+        #[arg(long, value_name = "ID")]
+        id: String,
+
+        /// Template language (rust, go or java)
+        #[arg(long, value_enum, value_name = "LANG")]
+        language: LanguageArg,
+
+        /// Human-readable template name (derived from the ID if omitted)
+        #[arg(long, value_name = "NAME")]
+        name: Option<String>,
+
+        /// Project directory to create (defaults to ./<ID>)
+        #[arg(short, long, value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
+
     /// Test a template against a target
     Test {
         /// Path to template file or template ID
@@ -892,6 +1526,78 @@ pub enum TemplateAction {
         #[arg(long)]
         debug: bool,
     },
+
+    /// Bundle, install and list template packs (.cxgpack)
+    Pack {
+        #[command(subcommand)]
+        action: PackAction,
+    },
+
+    /// Report templates that are semantically equivalent
+    Deduplicate(DeduplicateCommand),
+}
+
+/// Options for finding duplicate templates
+#[derive(Args, Debug)]
+pub struct DeduplicateCommand {
+    /// Similarity (0.0 - 1.0) at or above which templates are reported
+    #[arg(long, default_value_t = 0.85, value_name = "SCORE")]
+    pub threshold: f32,
+
+    /// Use TF-IDF similarity instead of the AI provider's embeddings
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Only compare templates under this directory
+    #[arg(long, value_name = "DIR")]
+    pub path: Option<PathBuf>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PackAction {
+    /// Bundle a template directory into a .cxgpack archive
+    Create {
+        /// Pack name (letters, digits, '-', '_' and '.')
+        #[arg(long, value_name = "NAME")]
+        name: String,
+
+        /// Directory of templates to bundle (searched recursively)
+        #[arg(long, value_name = "DIR")]
+        templates: PathBuf,
+
+        /// Pack version
+        #[arg(long, default_value = "1.0.0", value_name = "VERSION")]
+        version: String,
+
+        /// Pack author
+        #[arg(long, default_value = "", value_name = "NAME")]
+        author: String,
+
+        /// Pack description
+        #[arg(long, default_value = "", value_name = "TEXT")]
+        description: String,
+
+        /// Archive to write (defaults to ./<NAME>.cxgpack)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Verify and install a pack into the user template directory
+    Install {
+        /// Pack archive path or http(s) URL
+        source: String,
+    },
+
+    /// List installed packs
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Parser, Debug)]
@@ -996,6 +1702,10 @@ pub struct SearchArgs {
     #[arg(long, value_name = "CWE-ID")]
     pub cwe: Option<String>,
 
+    /// Only show templates that link to advisories or PoC references
+    #[arg(long)]
+    pub has_references: bool,
+
     /// Search in template content/code (slower but more comprehensive)
     #[arg(long)]
     pub content: bool,
@@ -1008,6 +1718,10 @@ pub struct SearchArgs {
     #[arg(long)]
     pub regex: bool,
 
+    /// Rank templates by meaning rather than matching text (uses embeddings)
+    #[arg(long, conflicts_with_all = ["regex", "content"], requires = "query")]
+    pub semantic: bool,
+
     /// Maximum number of results to return
     #[arg(long, default_value_t = 50, value_name = "N")]
     pub limit: usize,
@@ -1039,6 +1753,10 @@ pub struct SearchArgs {
     /// Show search statistics and summary
     #[arg(long)]
     pub stats: bool,
+
+    /// Rebuild the full-text search index even if it is up to date
+    #[arg(long)]
+    pub rebuild_index: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -1086,6 +1804,57 @@ pub struct ServerArgs {
     pub tls_key: Option<PathBuf>,
 }
 
+#[derive(Parser, Debug)]
+#[command(
+    about = "Inspect built-in and user wordlists",
+    long_about = "List, show and export the wordlists that templates reference from payloads \
+                  with \"wordlist:<name>\". A <name>.txt file in ~/.cert-x-gen/wordlists/ \
+                  takes precedence over the built-in list of the same name.",
+    after_help = "EXAMPLES:
+  # List available wordlists
+  cxg wordlist list
+
+  # Print the entries of a list
+  cxg wordlist show admin-panels
+
+  # Copy a built-in list to customise it
+  cxg wordlist export admin-panels -o ~/.cert-x-gen/wordlists/admin-panels.txt"
+)]
+pub struct WordlistCommand {
+    #[command(subcommand)]
+    pub action: WordlistAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WordlistAction {
+    /// List available wordlists
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print a wordlist's entries
+    Show {
+        /// Wordlist name
+        name: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write a wordlist's raw contents to a file or stdout
+    Export {
+        /// Wordlist name
+        name: String,
+
+        /// Output file (stdout if omitted)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+}
+
 #[derive(Parser, Debug)]
 #[command(
     about = "Generate and manage configuration files",
@@ -1097,12 +1866,23 @@ pub struct ServerArgs {
   cxg config generate --output config.yaml
   cxg config generate --format toml --output config.toml
 
+  # First-time setup wizard, or the same answers as flags for CI
+  cxg config generate --interactive --output ~/.cert-x-gen/config.yaml
+  cxg config generate --no-interactive --timeout 20 --rate-limit 50 --output-format sarif
+
   # Validate configuration
   cxg config validate config.yaml
   cxg config validate production.toml
 
   # Show current/default configuration
-  cxg config show"
+  cxg config show
+
+  # Show the effective configuration with a profile applied
+  cxg config show --config cert-x-gen.yaml --profile prod
+
+  # Encrypt plain-text secrets (proxy URL, AI API keys) with age
+  cxg config encrypt-secrets config.yaml
+  cxg config encrypt-secrets --ai"
 )]
 pub struct ConfigCommand {
     #[command(subcommand)]
@@ -1120,6 +1900,38 @@ pub enum ConfigAction {
         /// Configuration format
         #[arg(short, long, value_enum, default_value = "yaml", value_name = "FORMAT")]
         format: ConfigFormat,
+
+        /// Ask for each setting with a step-by-step wizard
+        #[arg(long, conflicts_with = "no_interactive")]
+        interactive: bool,
+
+        /// Take every setting from flags, without prompting (for CI)
+        #[arg(long)]
+        no_interactive: bool,
+
+        /// Extra template directory
+        #[arg(long, value_name = "DIR")]
+        template_dir: Option<PathBuf>,
+
+        /// Network timeout in seconds
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+
+        /// Requests per second (0 = unlimited)
+        #[arg(long, value_name = "RPS")]
+        rate_limit: Option<u32>,
+
+        /// Preferred AI provider (ollama, openai, anthropic, deepseek)
+        #[arg(long, value_name = "PROVIDER")]
+        ai_provider: Option<String>,
+
+        /// Proxy URL (http, https or socks5)
+        #[arg(long, value_name = "URL")]
+        proxy: Option<String>,
+
+        /// Default output format (json, html, sarif, csv, markdown)
+        #[arg(long, value_name = "FORMAT")]
+        output_format: Option<String>,
     },
 
     /// Validate a configuration file
@@ -1130,6 +1942,18 @@ pub enum ConfigAction {
 
     /// Show current/default configuration
     Show,
+
+    /// Encrypt plain-text secret fields of a configuration file in place
+    #[command(name = "encrypt-secrets")]
+    EncryptSecrets {
+        /// Configuration file to update
+        #[arg(required_unless_present = "ai", value_name = "FILE")]
+        config: Option<PathBuf>,
+
+        /// Also encrypt provider API keys in ~/.cert-x-gen/ai-config.yaml
+        #[arg(long)]
+        ai: bool,
+    },
 }
 
 /// Sandbox management commands
@@ -1253,6 +2077,10 @@ pub enum SandboxAction {
         /// Update specific language only
         #[arg(short, long)]
         language: Option<String>,
+
+        /// List available updates without applying them
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Export sandbox configuration
@@ -1311,6 +2139,10 @@ pub enum SandboxAction {
         /// Auto-start on CLI launch
         #[arg(short, long, default_value = "true")]
         auto_start: bool,
+
+        /// Don't stream docker build output
+        #[arg(short, long)]
+        quiet: bool,
     },
 
     /// Delete a sandbox
@@ -1343,6 +2175,10 @@ pub enum SandboxAction {
         /// Dockerfile path
         #[arg(short, long)]
         dockerfile: Option<PathBuf>,
+
+        /// Don't stream docker build output
+        #[arg(short, long)]
+        quiet: bool,
     },
 }
 
@@ -1372,6 +2208,26 @@ impl From<SeverityArg> for cert_x_gen::types::Severity {
     }
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ComplexityArg {
+    /// Single-request checks such as YAML matchers
+    Low,
+    /// Scripted checks
+    Medium,
+    /// Multi-step or compiled templates
+    High,
+}
+
+impl From<ComplexityArg> for cert_x_gen::ai::Complexity {
+    fn from(arg: ComplexityArg) -> Self {
+        match arg {
+            ComplexityArg::Low => cert_x_gen::ai::Complexity::Low,
+            ComplexityArg::Medium => cert_x_gen::ai::Complexity::Medium,
+            ComplexityArg::High => cert_x_gen::ai::Complexity::High,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum LanguageArg {
     /// YAML declarative templates
@@ -1398,6 +2254,8 @@ pub enum LanguageArg {
     Perl,
     /// PHP interpreted templates
     Php,
+    /// Rhai scripts (embedded interpreter)
+    Rhai,
 }
 
 impl From<LanguageArg> for cert_x_gen::types::TemplateLanguage {
@@ -1415,6 +2273,7 @@ impl From<LanguageArg> for cert_x_gen::types::TemplateLanguage {
             LanguageArg::Ruby => cert_x_gen::types::TemplateLanguage::Ruby,
             LanguageArg::Perl => cert_x_gen::types::TemplateLanguage::Perl,
             LanguageArg::Php => cert_x_gen::types::TemplateLanguage::Php,
+            LanguageArg::Rhai => cert_x_gen::types::TemplateLanguage::Rhai,
         }
     }
 }
@@ -1445,6 +2304,16 @@ pub enum SearchFormat {
     Detailed,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StatsFormat {
+    /// Counts and percentages (default)
+    Table,
+    /// JSON format (machine-readable)
+    Json,
+    /// ASCII bar charts
+    Chart,
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum SearchSort {
     /// Sort by relevance score (default)
@@ -1497,10 +2366,21 @@ EXAMPLES:
   cxg ai generate \"find SSRF\" --provider anthropic --model claude-3-5-sonnet-20241022
   cxg ai generate \"check headers\" --provider ollama --model codellama:13b
 
+  # Without --model, the model is picked by task complexity (inferred or given)
+  cxg ai generate \"login and check session fixation\" --complexity high
+
+  # Generate from a CVE record (NVD), optionally with PoC code
+  cxg ai generate --from-cve CVE-2021-44228
+  cxg ai generate --from-cve CVE-2023-46604 --poc-file poc.py --language python
+
   # Save to specific location
   cxg ai generate \"Redis check\" --language yaml --output templates/redis-test.yaml
   cxg ai generate \"MySQL scan\" --output mysql-check.py
 
+  # Ask before generating when the estimate exceeds $0.10 (abort in scripts)
+  cxg ai generate \"detect RCE\" --provider openai --max-cost 0.10
+  cxg ai generate \"detect RCE\" --provider openai --max-cost 0.10 --no-interactive
+
   # List available providers
   cxg ai providers list
   cxg ai providers list --detailed
@@ -1512,6 +2392,9 @@ EXAMPLES:
   # Show provider status
   cxg ai providers status
 
+  # Explain validation diagnostics and suggest fixes
+  cxg ai explain ./redis-check.py
+
 GETTING STARTED WITH OLLAMA (FREE, LOCAL):
   1. Install Ollama: curl -fsSL https://ollama.com/install.sh | sh
   2. Download model: ollama pull codellama:13b
@@ -1542,7 +2425,25 @@ pub enum AiAction {
         ///   "find SQL injection vulnerabilities"
         ///   "check for exposed Memcached instances"
         ///   "scan for XSS in forms"
-        prompt: String,
+        #[arg(required_unless_present = "from_cve", conflicts_with = "from_cve")]
+        prompt: Option<String>,
+
+        /// Generate from a CVE record fetched from the NVD instead of a description
+        #[arg(
+            long,
+            value_name = "CVE-ID",
+            help = "Build the prompt from the NVD record of a CVE (e.g., CVE-2021-44228), cached for 24 hours"
+        )]
+        from_cve: Option<String>,
+
+        /// Proof-of-concept code to include in the CVE prompt
+        #[arg(
+            long,
+            requires = "from_cve",
+            value_name = "PATH",
+            help = "PoC code or commands that trigger the CVE (requires --from-cve)"
+        )]
+        poc_file: Option<PathBuf>,
 
         /// Programming language for the template
         #[arg(
@@ -1569,10 +2470,19 @@ pub enum AiAction {
             short = 'm',
             long,
             value_name = "MODEL",
-            help = "Model name (e.g., codellama:13b, gpt-4, claude-3-5-sonnet-20241022)"
+            help = "Model name (e.g., codellama:13b, gpt-4, claude-3-5-sonnet-20241022). Auto-selected by task complexity when omitted"
         )]
         model: Option<String>,
 
+        /// Task complexity used to auto-select the model
+        #[arg(
+            long,
+            value_enum,
+            value_name = "LEVEL",
+            help = "Task complexity for model auto-selection (default: inferred from language and prompt)"
+        )]
+        complexity: Option<ComplexityArg>,
+
         /// Output file path (auto-generated if not specified)
         #[arg(
             short = 'o',
@@ -1605,6 +2515,18 @@ pub enum AiAction {
             help = "Estimate and show cost before generating (cloud providers only)"
         )]
         estimate_cost: bool,
+
+        /// Ask before generating when the estimated cost exceeds this many USD
+        #[arg(
+            long,
+            value_name = "USD",
+            help = "Cost limit in USD (e.g., 0.10). Over the limit, ask for confirmation before generating (cloud providers only)"
+        )]
+        max_cost: Option<f64>,
+
+        /// Abort instead of asking when the cost limit is exceeded
+        #[arg(long, requires = "max_cost")]
+        no_interactive: bool,
     },
 
     /// Manage LLM providers
@@ -1647,6 +2569,20 @@ TROUBLESHOOTING:
         #[command(subcommand)]
         action: ProviderAction,
     },
+
+    /// Validate a template and have the AI provider explain its diagnostics
+    ///
+    /// Same as `cxg template validate <TEMPLATE> --explain`: each diagnostic
+    /// gets a plain-language explanation and a suggested patch (unified diff,
+    /// never applied). Without a healthy provider only the diagnostics are shown.
+    Explain {
+        /// Template file to explain
+        template: PathBuf,
+
+        /// Output validation results and explanations as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -0,0 +1,326 @@
+//! First-time setup wizard for `cxg config generate`
+//!
+//! The same answers can come from interactive prompts or from command-line
+//! flags (for CI), so both paths produce identical configuration files.
+
+use super::Config;
+use crate::error::{Error, Result};
+use crate::secrets::Secret;
+use dialoguer::{theme::ColorfulTheme, Input, Select};
+use std::path::PathBuf;
+
+/// AI providers the wizard offers, in order of preference
+pub const AI_PROVIDERS: [&str; 4] = ["ollama", "openai", "anthropic", "deepseek"];
+
+/// Output formats the wizard offers
+pub const OUTPUT_FORMATS: [&str; 5] = ["json", "html", "sarif", "csv", "markdown"];
+
+/// Answers to the wizard's questions
+#[derive(Debug, Clone, PartialEq)]
+pub struct WizardAnswers {
+    /// Extra template directory (`None` uses template discovery only)
+    pub template_dir: Option<PathBuf>,
+    /// Network timeout (seconds)
+    pub timeout_secs: u64,
+    /// Requests per second (`None` is unlimited)
+    pub rate_limit: Option<u32>,
+    /// Preferred AI provider
+    pub ai_provider: String,
+    /// HTTP(S) or SOCKS5 proxy URL
+    pub proxy: Option<String>,
+    /// Output format
+    pub output_format: String,
+}
+
+impl Default for WizardAnswers {
+    fn default() -> Self {
+        let config = Config::default();
+        Self {
+            template_dir: None,
+            timeout_secs: config.network.timeout_secs,
+            rate_limit: config.network.rate_limit,
+            ai_provider: AI_PROVIDERS[0].to_string(),
+            proxy: None,
+            output_format: config.output.formats[0].clone(),
+        }
+    }
+}
+
+/// Builds a configuration from prompts or flags
+#[derive(Debug, Clone)]
+pub struct ConfigWizard {
+    answers: WizardAnswers,
+    detected_providers: Vec<String>,
+}
+
+impl ConfigWizard {
+    /// Wizard pre-filled with defaults and the AI providers found on this machine
+    pub fn new() -> Self {
+        Self::with_detected(detect_ai_providers())
+    }
+
+    /// Wizard pre-filled with defaults and the given detected providers
+    pub fn with_detected(detected_providers: Vec<String>) -> Self {
+        let mut answers = WizardAnswers::default();
+        if let Some(provider) = detected_providers.first() {
+            answers.ai_provider = provider.clone();
+        }
+        Self {
+            answers,
+            detected_providers,
+        }
+    }
+
+    /// Current answers
+    pub fn answers(&self) -> &WizardAnswers {
+        &self.answers
+    }
+
+    /// Mutable answers, e.g. to apply command-line flags
+    pub fn answers_mut(&mut self) -> &mut WizardAnswers {
+        &mut self.answers
+    }
+
+    /// AI providers detected on this machine
+    pub fn detected_providers(&self) -> &[String] {
+        &self.detected_providers
+    }
+
+    /// Ask every question on the terminal, using the current answers as defaults
+    pub fn prompt(&mut self) -> Result<()> {
+        let theme = ColorfulTheme::default();
+        let prompt_error = |e: dialoguer::Error| Error::config(format!("Prompt failed: {}", e));
+
+        let template_dir: String = Input::with_theme(&theme)
+            .with_prompt("Extra template directory (empty = discovered template directories only)")
+            .default(
+                self.answers
+                    .template_dir
+                    .as_ref()
+                    .map(|d| d.display().to_string())
+                    .unwrap_or_default(),
+            )
+            .allow_empty(true)
+            .interact_text()
+            .map_err(prompt_error)?;
+
+        let timeout_secs: u64 = Input::with_theme(&theme)
+            .with_prompt("Network timeout in seconds")
+            .default(self.answers.timeout_secs)
+            .validate_with(|v: &u64| {
+                if *v > 0 {
+                    Ok(())
+                } else {
+                    Err("timeout must be greater than 0")
+                }
+            })
+            .interact_text()
+            .map_err(prompt_error)?;
+
+        let rate_limit: u32 = Input::with_theme(&theme)
+            .with_prompt("Rate limit in requests per second (0 = unlimited)")
+            .default(self.answers.rate_limit.unwrap_or(0))
+            .interact_text()
+            .map_err(prompt_error)?;
+
+        let labels: Vec<String> = AI_PROVIDERS
+            .iter()
+            .map(|p| {
+                if self.detected_providers.iter().any(|d| d == p) {
+                    format!("{} (detected)", p)
+                } else {
+                    p.to_string()
+                }
+            })
+            .collect();
+        let provider = Select::with_theme(&theme)
+            .with_prompt("Preferred AI provider for `cxg ai` commands")
+            .items(&labels)
+            .default(
+                AI_PROVIDERS
+                    .iter()
+                    .position(|p| *p == self.answers.ai_provider)
+                    .unwrap_or(0),
+            )
+            .interact()
+            .map_err(prompt_error)?;
+
+        let proxy: String = Input::with_theme(&theme)
+            .with_prompt("Proxy URL, e.g. http://127.0.0.1:8080 (empty = none)")
+            .default(self.answers.proxy.clone().unwrap_or_default())
+            .allow_empty(true)
+            .interact_text()
+            .map_err(prompt_error)?;
+
+        let format = Select::with_theme(&theme)
+            .with_prompt("Default output format")
+            .items(&OUTPUT_FORMATS)
+            .default(
+                OUTPUT_FORMATS
+                    .iter()
+                    .position(|f| *f == self.answers.output_format)
+                    .unwrap_or(0),
+            )
+            .interact()
+            .map_err(prompt_error)?;
+
+        self.answers = WizardAnswers {
+            template_dir: (!template_dir.trim().is_empty())
+                .then(|| PathBuf::from(template_dir.trim())),
+            timeout_secs,
+            rate_limit: (rate_limit > 0).then_some(rate_limit),
+            ai_provider: AI_PROVIDERS[provider].to_string(),
+            proxy: (!proxy.trim().is_empty()).then(|| proxy.trim().to_string()),
+            output_format: OUTPUT_FORMATS[format].to_string(),
+        };
+        Ok(())
+    }
+
+    /// Configuration reflecting the answers, validated
+    pub fn build(&self) -> Result<Config> {
+        let answers = &self.answers;
+        if !AI_PROVIDERS.contains(&answers.ai_provider.as_str()) {
+            return Err(Error::config(format!(
+                "Unknown AI provider '{}' (expected one of: {})",
+                answers.ai_provider,
+                AI_PROVIDERS.join(", ")
+            )));
+        }
+        if !OUTPUT_FORMATS.contains(&answers.output_format.as_str()) {
+            return Err(Error::config(format!(
+                "Unknown output format '{}' (expected one of: {})",
+                answers.output_format,
+                OUTPUT_FORMATS.join(", ")
+            )));
+        }
+        if let Some(proxy) = &answers.proxy {
+            let url = url::Url::parse(proxy)
+                .map_err(|e| Error::config(format!("Invalid proxy URL '{}': {}", proxy, e)))?;
+            if !matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") {
+                return Err(Error::config(format!(
+                    "Unsupported proxy scheme '{}' (use http, https or socks5)",
+                    url.scheme()
+                )));
+            }
+        }
+
+        let mut config = Config::default();
+        config.templates.directories = answers.template_dir.iter().cloned().collect();
+        config.network.timeout_secs = answers.timeout_secs;
+        config.network.rate_limit = answers.rate_limit;
+        config.network.proxy = answers.proxy.clone().map(Secret::new);
+        config.output.formats = vec![answers.output_format.clone()];
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Build the configuration and write it to `path` (format from the extension)
+    pub fn write(&self, path: &std::path::Path) -> Result<Config> {
+        let config = self.build()?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        config.save(path)?;
+        Ok(config)
+    }
+}
+
+impl Default for ConfigWizard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// AI providers usable on this machine: `ollama` on PATH, or an API key in the environment
+pub fn detect_ai_providers() -> Vec<String> {
+    let mut detected = Vec::new();
+    if crate::engine::runtime::find_in_path("ollama").is_some() {
+        detected.push("ollama".to_string());
+    }
+    for (provider, variable) in [
+        ("openai", "OPENAI_API_KEY"),
+        ("anthropic", "ANTHROPIC_API_KEY"),
+        ("deepseek", "DEEPSEEK_API_KEY"),
+    ] {
+        if std::env::var(variable).is_ok_and(|v| !v.is_empty()) {
+            detected.push(provider.to_string());
+        }
+    }
+    detected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_interactive_writes_loadable_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("config.yaml");
+
+        let mut wizard = ConfigWizard::with_detected(vec!["openai".to_string()]);
+        assert_eq!(wizard.answers().ai_provider, "openai");
+        *wizard.answers_mut() = WizardAnswers {
+            template_dir: Some(PathBuf::from("/opt/templates")),
+            timeout_secs: 25,
+            rate_limit: None,
+            ai_provider: "ollama".to_string(),
+            proxy: Some("socks5://127.0.0.1:9050".to_string()),
+            output_format: "sarif".to_string(),
+        };
+        wizard.write(&path).unwrap();
+
+        let loaded = Config::from_file(&path).unwrap();
+        assert_eq!(
+            loaded.templates.directories,
+            vec![PathBuf::from("/opt/templates")]
+        );
+        assert_eq!(loaded.network.timeout_secs, 25);
+        assert_eq!(loaded.network.rate_limit, None);
+        assert_eq!(
+            loaded.network.proxy.as_ref().map(|p| p.expose().as_str()),
+            Some("socks5://127.0.0.1:9050")
+        );
+        assert_eq!(loaded.output.formats, vec!["sarif".to_string()]);
+        loaded.validate().unwrap();
+    }
+
+    #[test]
+    fn test_defaults_match_default_config() {
+        let wizard = ConfigWizard::with_detected(Vec::new());
+        let config = wizard.build().unwrap();
+        let default = Config::default();
+        assert_eq!(wizard.answers().ai_provider, "ollama");
+        assert_eq!(config.network.timeout_secs, default.network.timeout_secs);
+        assert_eq!(config.network.rate_limit, default.network.rate_limit);
+        assert_eq!(config.output.formats, default.output.formats);
+        assert!(config.templates.directories.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_answers_are_rejected() {
+        let invalid = [
+            WizardAnswers {
+                timeout_secs: 0,
+                ..WizardAnswers::default()
+            },
+            WizardAnswers {
+                ai_provider: "gemini".to_string(),
+                ..WizardAnswers::default()
+            },
+            WizardAnswers {
+                output_format: "pdf".to_string(),
+                ..WizardAnswers::default()
+            },
+            WizardAnswers {
+                proxy: Some("ftp://proxy:21".to_string()),
+                ..WizardAnswers::default()
+            },
+        ];
+        for answers in invalid {
+            let mut wizard = ConfigWizard::with_detected(Vec::new());
+            *wizard.answers_mut() = answers.clone();
+            assert!(wizard.build().is_err(), "{:?} should be rejected", answers);
+        }
+    }
+}
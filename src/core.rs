@@ -1,15 +1,39 @@
 //! Core engine for CERT-X-GEN
 
+pub mod builder;
+
+pub use builder::{PreparedScan, ScanBuilder};
+
 use crate::config::Config;
-use crate::error::Result;
-use crate::executor::Executor;
-use crate::scheduler::Scheduler;
+use crate::error::{Error, Result};
+use crate::executor::{EngineLimits, ExecutionUsage, Executor};
+use crate::metrics::RequestCounter;
+use crate::network::{settle_schemes, ProbeRecorder};
+use crate::scheduler::{JobControl, ScanQueue, Scheduler};
 use crate::template::{Template, TemplateFilter, TemplateLoader, TemplateManager};
 use crate::types::{Context, ScanResults, Target};
+use crate::workspace::ScanWorkspace;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+/// Tags excluded by safe mode
+pub const SAFE_MODE_EXCLUDED_TAGS: &[&str] = &[
+    "dos",
+    "resource-exhaustion",
+    "intrusive",
+    "destructive",
+    "brute-force",
+    "exploit",
+];
+
+/// Tags excluded by passive mode, unless the template is also tagged `passive`
+pub const PASSIVE_MODE_EXCLUDED_TAGS: &[&str] = &["active", "probe", "intrusive", "exploit"];
+
 /// Main CERT-X-GEN engine
 #[allow(missing_debug_implementations)]
 pub struct CertXGen {
@@ -18,15 +42,22 @@ pub struct CertXGen {
     template_manager: Arc<TemplateManager>,
     executor: Arc<Executor>,
     scheduler: Arc<RwLock<Scheduler>>,
+    scan_queue: ScanQueue,
+    browser: Arc<crate::engine::HeadlessBrowserEngine>,
+    yaml_engine: crate::engine::YamlTemplateEngine,
 }
 
 impl CertXGen {
     /// Create a new CERT-X-GEN engine
     pub async fn new(config: Config) -> Result<Self> {
         config.validate()?;
+        // A configured runtime path that does not exist fails here, not once per target
+        crate::engine::runtime::validate_configured(&config.engines).await?;
+
+        crate::sandbox::syscall::set_enabled(config.sandbox.syscall_filtering);
 
         let config = Arc::new(config);
-        let executor = Arc::new(Executor::new(config.clone()).await?);
+        let mut executor = Executor::new(config.clone()).await?;
 
         // Initialize template manager
         let template_manager = Arc::new(TemplateManager::new());
@@ -35,30 +66,68 @@ impl CertXGen {
         // Create template loader with registered engines
         let mut template_loader = TemplateLoader::new();
 
-        // Register YAML engine with network client
+        // Register YAML engine with network client and the shared headless browser
+        let browser = Arc::new(crate::engine::HeadlessBrowserEngine::new(&config));
         let yaml_engine = crate::engine::YamlTemplateEngine::new()
-            .with_network_client(executor.network_client().clone());
-        template_loader.register_engine(Box::new(yaml_engine));
+            .with_network_client(executor.network_client().clone())
+            .with_snippets_dir(config.templates.snippets_dir.clone())
+            .with_browser(browser.clone());
+        template_loader.register_engine(Box::new(yaml_engine.clone()));
 
-        // Register other engines
-        template_loader.register_engine(Box::new(crate::engine::PythonEngine::new()));
-        template_loader.register_engine(Box::new(crate::engine::RustEngine::new()));
-        template_loader.register_engine(Box::new(crate::engine::ShellEngine::new()));
-        template_loader.register_engine(Box::new(crate::engine::JavaScriptEngine::new()));
+        // Register other engines, each with its `engines.<language>` runtime override
+        let runtime = |language: &str| config.engines.get(language).cloned().unwrap_or_default();
+        template_loader.register_engine(Box::new(
+            crate::engine::PythonEngine::new().with_runtime_config(runtime("python")),
+        ));
+        template_loader.register_engine(Box::new(
+            crate::engine::RustEngine::new().with_runtime_config(runtime("rust")),
+        ));
+        template_loader.register_engine(Box::new(
+            crate::engine::ShellEngine::new().with_runtime_config(runtime("shell")),
+        ));
+        template_loader.register_engine(Box::new(
+            crate::engine::JavaScriptEngine::new().with_runtime_config(runtime("javascript")),
+        ));
 
         // Register compiled language engines
-        template_loader.register_engine(Box::new(crate::engine::CEngine::new()));
-        template_loader.register_engine(Box::new(crate::engine::CppEngine::new()));
-        template_loader.register_engine(Box::new(crate::engine::JavaEngine::new()));
-        template_loader.register_engine(Box::new(crate::engine::GoEngine::new()));
+        template_loader.register_engine(Box::new(
+            crate::engine::CEngine::new().with_runtime_config(runtime("c")),
+        ));
+        template_loader.register_engine(Box::new(
+            crate::engine::CppEngine::new().with_runtime_config(runtime("cpp")),
+        ));
+        template_loader.register_engine(Box::new(
+            crate::engine::JavaEngine::new().with_runtime_config(runtime("java")),
+        ));
+        template_loader.register_engine(Box::new(
+            crate::engine::GoEngine::new().with_runtime_config(runtime("go")),
+        ));
 
         // Register interpreted language engines
-        template_loader.register_engine(Box::new(crate::engine::RubyEngine::new()));
-        template_loader.register_engine(Box::new(crate::engine::PerlEngine::new()));
-        template_loader.register_engine(Box::new(crate::engine::PhpEngine::new()));
+        template_loader.register_engine(Box::new(
+            crate::engine::RubyEngine::new().with_runtime_config(runtime("ruby")),
+        ));
+        template_loader.register_engine(Box::new(
+            crate::engine::PerlEngine::new().with_runtime_config(runtime("perl")),
+        ));
+        template_loader.register_engine(Box::new(
+            crate::engine::PhpEngine::new().with_runtime_config(runtime("php")),
+        ));
+
+        // Register the embedded Rhai engine (no external runtime)
+        template_loader.register_engine(Box::new(
+            crate::engine::RhaiEngine::new()
+                .with_network_client(executor.network_client().clone())
+                .with_timeout(std::time::Duration::from_secs(
+                    config.templates.timeout_secs,
+                )),
+        ));
 
+        executor.set_engine_limits(EngineLimits::from_loader(&template_loader, &config.engines));
+        let executor = Arc::new(executor);
         let template_loader = Arc::new(template_loader);
         let scheduler = Arc::new(RwLock::new(Scheduler::new(config.clone())));
+        let scan_queue = ScanQueue::new(executor.clone(), config.execution.concurrent_scans);
 
         Ok(Self {
             config,
@@ -66,12 +135,34 @@ impl CertXGen {
             template_manager,
             executor,
             scheduler,
+            scan_queue,
+            browser,
+            yaml_engine,
         })
     }
 
+    /// Built-in templates trying default credentials against `services` (`--test-creds`)
+    pub fn default_credential_templates(
+        &self,
+        services: &[String],
+    ) -> Result<Vec<Box<dyn Template>>> {
+        let mut parsed = Vec::new();
+        for name in services {
+            let service: crate::network::Service = name.parse()?;
+            if !parsed.contains(&service) {
+                parsed.push(service);
+            }
+        }
+        parsed
+            .into_iter()
+            .map(|service| self.yaml_engine.default_credentials_template(service))
+            .collect()
+    }
+
     /// Load templates from configured directories
     pub async fn load_templates(&self) -> Result<Vec<Box<dyn Template>>> {
         let mut all_templates = Vec::new();
+        let skipped_before = self.template_loader.skipped_count();
 
         // Get directories from template manager
         let directories = if self.config.templates.directories.is_empty() {
@@ -105,13 +196,21 @@ impl CertXGen {
             }
         }
 
+        let skipped = self.template_loader.skipped_count() - skipped_before;
+        if skipped > 0 {
+            tracing::warn!(
+                "Skipped {} template file(s) that failed to load; run 'cxg template validate' for details",
+                skipped
+            );
+        }
+
         // If no templates found, show helpful message
         if all_templates.is_empty() {
             tracing::warn!("No templates found. Run 'cxg template update' to download templates.");
         }
 
         // Deduplicate templates by ID, keeping first occurrence (priority: Local > User > System)
-        let mut seen_ids = std::collections::HashSet::new();
+        let mut seen_ids = HashSet::new();
         let original_count = all_templates.len();
         let deduplicated_templates: Vec<Box<dyn Template>> = all_templates
             .into_iter()
@@ -140,6 +239,119 @@ impl CertXGen {
         Ok(deduplicated_templates)
     }
 
+    /// Load the template files at `paths`, failing on the first that does not load
+    pub async fn load_template_files(&self, paths: &[PathBuf]) -> Result<Vec<Box<dyn Template>>> {
+        tracing::info!("Loading {} template(s) from direct paths", paths.len());
+        let mut direct_templates: Vec<Box<dyn Template>> = Vec::new();
+
+        for path in paths {
+            tracing::debug!("Loading template from: {}", path.display());
+            match self.template_loader.load_template(path).await {
+                Ok(template) => {
+                    tracing::info!(
+                        "Loaded template: {} ({}) from {}",
+                        template.id(),
+                        template.metadata().language,
+                        path.display()
+                    );
+                    direct_templates.push(template);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to load template from {}: {}", path.display(), e);
+                    return Err(Error::config(format!(
+                        "Failed to load template '{}': {}",
+                        path.display(),
+                        e
+                    )));
+                }
+            }
+        }
+
+        if direct_templates.is_empty() {
+            return Err(Error::config(
+                "No templates could be loaded from the specified paths.",
+            ));
+        }
+
+        Ok(direct_templates)
+    }
+
+    /// Search the template directories for templates matching `ids` by ID,
+    /// name, file name or path
+    pub async fn find_templates(&self, ids: &[String]) -> Result<Vec<Box<dyn Template>>> {
+        tracing::info!(
+            "Searching for {} specified template ID(s): {:?}",
+            ids.len(),
+            ids
+        );
+        let mut matched_templates: Vec<Box<dyn Template>> = Vec::new();
+        let mut found_ids: HashSet<String> = HashSet::new();
+
+        // Search in template directories for matching templates
+        for dir in self.template_manager.get_template_dirs() {
+            if !dir.exists() {
+                continue;
+            }
+
+            match self.template_loader.load_templates_from_dir(&dir).await {
+                Ok(templates) => {
+                    for template in templates {
+                        let template_id = template.id().to_string();
+                        let template_name = template.name().to_string();
+                        let file_path = template.metadata().file_path.to_string_lossy().to_string();
+
+                        // Check if this template matches any of the filter IDs
+                        let matches = ids.iter().any(|filter_id| {
+                            template_id.eq_ignore_ascii_case(filter_id)
+                                || template_name.eq_ignore_ascii_case(filter_id)
+                                || file_path.contains(filter_id)
+                                || template
+                                    .metadata()
+                                    .file_path
+                                    .file_stem()
+                                    .and_then(|s| s.to_str())
+                                    .map(|s| s.eq_ignore_ascii_case(filter_id))
+                                    .unwrap_or(false)
+                        });
+
+                        if matches && !found_ids.contains(&template_id) {
+                            tracing::info!(
+                                "Found matching template: {} ({}) in {}",
+                                template_id,
+                                template.metadata().language,
+                                dir.display()
+                            );
+                            found_ids.insert(template_id);
+                            matched_templates.push(template);
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to search templates in {}: {}", dir.display(), e);
+                }
+            }
+        }
+
+        // Report any filter IDs that weren't found
+        for filter_id in ids {
+            if !found_ids
+                .iter()
+                .any(|id| id.eq_ignore_ascii_case(filter_id))
+            {
+                tracing::warn!("Template not found: {}", filter_id);
+            }
+        }
+
+        if matched_templates.is_empty() {
+            return Err(Error::config(format!(
+                "No templates found matching: {:?}. Use 'cxg template list' to see available templates.",
+                ids
+            )));
+        }
+
+        Ok(matched_templates)
+    }
+
     /// Create a new scan job
     pub fn create_scan_job(
         &self,
@@ -149,8 +361,33 @@ impl CertXGen {
         ScanJob::new(targets, templates, self.config.clone())
     }
 
+    /// Create a scan job over templates shared with other jobs
+    pub fn create_shared_scan_job(
+        &self,
+        targets: Vec<Target>,
+        templates: Vec<Arc<dyn Template>>,
+    ) -> ScanJob {
+        ScanJob::with_shared_templates(targets, templates, self.config.clone())
+    }
+
     /// Execute a scan job
-    pub async fn execute_scan(&self, job: ScanJob) -> Result<ScanResults> {
+    ///
+    /// HTTP targets with a port but no scheme first get it settled (see
+    /// [`settle_schemes`]). Script and compiled templates run in the job's
+    /// workspace (see [`ScanJob::workspace`]), removed afterwards unless
+    /// artifacts are kept.
+    pub async fn execute_scan(&self, mut job: ScanJob) -> Result<ScanResults> {
+        let settled = settle_schemes(&mut job.targets, &self.config.network).await;
+        if settled.probed + settled.port_table > 0 {
+            tracing::info!(
+                "Settled the scheme of {} target(s) by probing, {} by the port table",
+                settled.probed,
+                settled.port_table
+            );
+            // Overrides are keyed by target, scheme included
+            job.per_target_overrides.apply_to(&job.targets);
+        }
+
         tracing::info!(
             "Starting scan {} with {} targets and {} templates",
             job.id,
@@ -158,7 +395,11 @@ impl CertXGen {
             job.templates.len()
         );
 
-        let mut results = ScanResults::new(job.id);
+        let results = ScanResults::new(job.id);
+        let workspace = job.workspace();
+        if let Some(on_finding) = &job.on_finding {
+            job.discovery_findings.iter().for_each(|f| on_finding(f));
+        }
 
         // Schedule templates for execution
         let mut scheduler = self.scheduler.write().await;
@@ -166,24 +407,29 @@ impl CertXGen {
         drop(scheduler); // Release lock
 
         // Execute scan using executor
-        let findings = self.executor.execute(&job).await?;
-
-        // Aggregate results
-        for finding in findings {
-            results.add_finding(finding);
-        }
-
-        // Update statistics
-        results.statistics.targets_scanned = job.targets.len();
-        results.statistics.templates_executed = job.templates.len();
-
-        // Calculate success rate
-        let total_checks = job.targets.len() * job.templates.len();
-        if total_checks > 0 {
-            results.statistics.success_rate = results.findings.len() as f64 / total_checks as f64;
+        let mut executed = self.executor.execute_with_usage(&job).await;
+        if let Ok((findings, _)) = &mut executed {
+            if self.config.output.screenshots {
+                crate::engine::ScreenshotCapture::new(self.browser.clone())
+                    .capture(findings)
+                    .await;
+            }
         }
-
-        results.complete();
+        // Close the browser (if a template started it) even when the scan failed
+        self.browser.shutdown().await;
+        job.release_workspace(&workspace);
+        let (findings, usage) = executed?;
+        let results = finish_results(
+            results,
+            &job,
+            findings,
+            usage,
+            self.executor.network_client().requests(),
+            self.executor
+                .network_client()
+                .probe_recorder()
+                .map(Arc::as_ref),
+        );
 
         tracing::info!(
             "Scan {} completed. Found {} findings",
@@ -213,6 +459,135 @@ impl CertXGen {
     pub fn executor(&self) -> &Executor {
         &self.executor
     }
+
+    /// Priority queue for scan jobs submitted concurrently (API server)
+    pub fn scan_queue(&self) -> &ScanQueue {
+        &self.scan_queue
+    }
+}
+
+/// Fill in `results` from what executing `job` returned
+pub(crate) fn finish_results(
+    mut results: ScanResults,
+    job: &ScanJob,
+    findings: Vec<crate::types::Finding>,
+    usage: ExecutionUsage,
+    requests: &RequestCounter,
+    probes: Option<&ProbeRecorder>,
+) -> ScanResults {
+    // Aggregate results
+    for finding in job.discovery_findings.iter().cloned().chain(findings) {
+        results.add_finding(finding);
+    }
+
+    // Update statistics
+    results.statistics.targets_scanned = job.targets.len();
+    results.statistics.templates_executed = job.templates.len();
+    results.statistics.engine_peak_concurrency = usage.engine_peaks;
+    results.statistics.network_requests = requests.total() as usize;
+    results.statistics.requests_per_host = requests.per_host();
+    results.statistics.skipped_work_units = usage.skipped;
+    results.statistics.seed = job.seed;
+    results.statistics.concurrency_timeline = usage.concurrency_timeline;
+    if let Some(probes) = probes {
+        results.targets = job.targets.iter().map(|t| probes.summarize(t)).collect();
+    }
+
+    // Calculate success rate
+    let total_checks = job.total_work_units();
+    if total_checks > 0 {
+        results.statistics.success_rate = results.findings.len() as f64 / total_checks as f64;
+    }
+
+    results.complete();
+    results
+}
+
+/// Template IDs to run on specific targets instead of the job's full set
+///
+/// Filled from `# templates:` annotations in scope files. Targets without an
+/// entry run every template of the job.
+#[derive(Debug, Clone, Default)]
+pub struct PerTargetOverrides {
+    templates: HashMap<Target, Vec<String>>,
+}
+
+impl PerTargetOverrides {
+    /// No overrides: every target runs every template
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run only `template_ids` on `target`, adding to any IDs it already has
+    pub fn insert(&mut self, target: Target, template_ids: Vec<String>) {
+        let ids = self.templates.entry(target).or_default();
+        for id in template_ids {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    /// Template IDs for `target`, if it has an override
+    pub fn get(&self, target: &Target) -> Option<&[String]> {
+        self.templates.get(target).map(Vec::as_slice)
+    }
+
+    /// Whether no target has an override
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    /// Number of targets with an override
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Whether `template` runs on `target`
+    pub fn runs(&self, target: &Target, template: &dyn Template) -> bool {
+        self.get(target)
+            .is_none_or(|ids| ids.iter().any(|id| id == template.id()))
+    }
+
+    /// Every template ID named by an override
+    pub fn template_ids(&self) -> BTreeSet<&str> {
+        self.templates
+            .values()
+            .flatten()
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Re-key the overrides onto `targets` after normalization or expansion
+    ///
+    /// A target keeps its own entry, else inherits the entry it was derived
+    /// from: the same target ID (normalized or port-expanded copies), or the
+    /// same protocol, address and port (OpenAPI endpoints). Entries for targets
+    /// no longer in scope are dropped.
+    pub fn apply_to(&mut self, targets: &[Target]) {
+        if self.templates.is_empty() {
+            return;
+        }
+        let previous = std::mem::take(&mut self.templates);
+        for target in targets {
+            let inherited = previous.get(target).or_else(|| {
+                previous
+                    .iter()
+                    .find(|(origin, _)| origin.id == target.id)
+                    .or_else(|| {
+                        previous.iter().find(|(origin, _)| {
+                            origin.protocol == target.protocol
+                                && origin.address == target.address
+                                && origin.port == target.port
+                        })
+                    })
+                    .map(|(_, ids)| ids)
+            });
+            if let Some(ids) = inherited {
+                self.insert(target.clone(), ids.clone());
+            }
+        }
+    }
 }
 
 /// A scan job containing targets and templates to execute
@@ -222,20 +597,46 @@ pub struct ScanJob {
     pub id: Uuid,
     /// Targets to scan
     pub targets: Vec<Target>,
-    /// Templates to execute
-    pub templates: Vec<Box<dyn Template>>,
+    /// Templates to execute; shared so several jobs can reuse loaded templates
+    pub templates: Vec<Arc<dyn Template>>,
+    /// Templates to run on particular targets instead of all of `templates`
+    pub per_target_overrides: PerTargetOverrides,
     /// Execution context
     pub context: Context,
     /// Configuration
     pub config: Arc<Config>,
+    /// Pause and cancellation, checked before each template starts
+    pub control: JobControl,
+    /// Seed of the run's randomized orders, recorded in the results' statistics
+    pub seed: Option<u64>,
+    /// Shuffle each target's template order with `seed`
+    pub randomize_templates: bool,
+    /// Findings made while gathering the targets (e.g. `--zone-transfer`),
+    /// reported with the scan's own
+    pub discovery_findings: Vec<crate::types::Finding>,
+    /// Called with each finding as soon as its template reports it
+    pub on_finding: Option<FindingCallback>,
 }
 
+/// Callback receiving findings while a scan runs (see [`ScanJob::on_finding`])
+pub type FindingCallback = Arc<dyn Fn(&crate::types::Finding) + Send + Sync>;
+
 impl ScanJob {
     /// Create a new scan job
     pub fn new(
         targets: Vec<Target>,
         templates: Vec<Box<dyn Template>>,
         config: Arc<Config>,
+    ) -> Self {
+        let templates = templates.into_iter().map(Arc::from).collect();
+        Self::with_shared_templates(targets, templates, config)
+    }
+
+    /// Create a scan job over templates that other jobs also use
+    pub fn with_shared_templates(
+        targets: Vec<Target>,
+        templates: Vec<Arc<dyn Template>>,
+        config: Arc<Config>,
     ) -> Self {
         let mut context = Context::default();
         context.aggressive_mode = config.execution.aggressive_mode;
@@ -245,27 +646,268 @@ impl ScanJob {
         context.max_retries = config.execution.max_retries;
         context.headers = config.network.headers.clone();
         context.cookies = config.network.cookies.clone();
+        context.sessions = config.network.sessions.clone();
 
         Self {
             id: Uuid::new_v4(),
             targets,
             templates,
+            per_target_overrides: PerTargetOverrides::new(),
             context,
             config,
+            control: JobControl::default(),
+            seed: None,
+            randomize_templates: false,
+            discovery_findings: Vec::new(),
+            on_finding: None,
         }
     }
 
+    /// Working directories for the job's script and compiled templates
+    ///
+    /// A fresh workspace under [`ScanWorkspace::default_base`] is attached to
+    /// the context unless it already has one.
+    pub fn workspace(&mut self) -> Arc<ScanWorkspace> {
+        let id = self.id;
+        self.context
+            .workspace
+            .get_or_insert_with(|| Arc::new(ScanWorkspace::new(ScanWorkspace::default_base(), id)))
+            .clone()
+    }
+
+    /// Remove `workspace` once the job is done, unless `execution.keep_artifacts` is set
+    pub fn release_workspace(&self, workspace: &ScanWorkspace) {
+        if self.config.execution.keep_artifacts {
+            return;
+        }
+        if let Err(e) = workspace.remove() {
+            tracing::warn!(
+                "Failed to remove scan workspace {}: {}",
+                workspace.dir().display(),
+                e
+            );
+        }
+    }
+
+    /// Whether `template` runs on `target`, given the per-target overrides
+    pub fn runs(&self, template: &dyn Template, target: &Target) -> bool {
+        self.per_target_overrides.runs(target, template)
+    }
+
+    /// Indexes into `templates`, in the order they start on `targets[target_idx]`
+    ///
+    /// Job order, or a per-target shuffle with `randomize_templates`, then
+    /// sorted by `execution.priority`, so shuffles only reorder templates of
+    /// equal priority.
+    pub fn template_order(&self, target_idx: usize) -> Vec<usize> {
+        let seed = self.seed.filter(|_| self.randomize_templates);
+        let mut order = crate::scheduler::template_order(self.templates.len(), seed, target_idx);
+        self.config
+            .execution
+            .priority
+            .sort(&mut order, &self.templates);
+        order
+    }
+
+    /// Templates that run on `target`
+    pub fn templates_for<'a>(
+        &'a self,
+        target: &'a Target,
+    ) -> impl Iterator<Item = &'a Arc<dyn Template>> + 'a {
+        self.templates
+            .iter()
+            .filter(move |t| self.runs(t.as_ref(), target))
+    }
+
     /// Filter templates
     pub fn filter_templates(&mut self, filter: &TemplateFilter) {
         self.templates.retain(|t| filter.matches(t.as_ref()));
     }
 
-    /// Get total work units (targets × templates)
+    /// Drop templates tagged as dangerous; returns how many were removed
+    pub fn apply_safe_mode(&mut self) -> usize {
+        let before = self.templates.len();
+        self.templates
+            .retain(|t| !has_any_tag(t.as_ref(), SAFE_MODE_EXCLUDED_TAGS));
+        before - self.templates.len()
+    }
+
+    /// Drop active probe templates not tagged `passive`; returns how many were removed
+    pub fn apply_passive_mode(&mut self) -> usize {
+        let before = self.templates.len();
+        self.templates.retain(|t| {
+            has_any_tag(t.as_ref(), &["passive"])
+                || !has_any_tag(t.as_ref(), PASSIVE_MODE_EXCLUDED_TAGS)
+        });
+        before - self.templates.len()
+    }
+
+    /// Drop deprecated templates; returns how many were removed
+    pub fn exclude_deprecated(&mut self) -> usize {
+        let before = self.templates.len();
+        self.templates.retain(|t| !t.metadata().deprecated);
+        before - self.templates.len()
+    }
+
+    /// Describe what this job would run, without contacting any target
+    pub fn plan(&self) -> ScanPlan {
+        let templates: Vec<PlannedTemplate> = self
+            .templates
+            .iter()
+            .map(|t| {
+                let metadata = t.metadata();
+                PlannedTemplate {
+                    id: metadata.id.clone(),
+                    language: metadata.language.to_string(),
+                    severity: metadata.severity.to_string(),
+                    path: metadata.file_path.clone(),
+                    estimated_requests: t.estimated_requests(),
+                }
+            })
+            .collect();
+
+        let mut languages = BTreeMap::new();
+        for template in &templates {
+            *languages.entry(template.language.clone()).or_insert(0) += 1;
+        }
+        let requests_per_target = templates.iter().map(|t| t.estimated_requests).sum();
+        let estimated_requests = self
+            .targets
+            .iter()
+            .map(|target| {
+                self.templates_for(target)
+                    .map(|t| t.estimated_requests())
+                    .sum::<usize>()
+            })
+            .sum();
+
+        ScanPlan {
+            targets: self.targets.iter().map(|t| t.url()).collect(),
+            work_units: self.total_work_units(),
+            requests_per_target,
+            estimated_requests,
+            languages,
+            additional_ports: self.context.additional_ports.clone(),
+            override_ports: self.context.override_ports.clone(),
+            excluded: BTreeMap::new(),
+            unexpanded_scope: Vec::new(),
+            templates,
+        }
+    }
+
+    /// Compile all compiled-language templates ahead of execution
+    ///
+    /// Runs up to one compilation per CPU concurrently. Templates that fail to
+    /// compile are removed from the job and returned with their error.
+    /// `on_compiled` is called once per compiled template.
+    pub async fn precompile_templates<F>(&mut self, on_compiled: F) -> Vec<(String, Error)>
+    where
+        F: Fn(&str, bool),
+    {
+        let compiled: Vec<usize> = self
+            .templates
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.metadata().language.is_compiled())
+            .map(|(i, _)| i)
+            .collect();
+
+        let results: Vec<(usize, Result<()>)> = stream::iter(compiled)
+            .map(|idx| {
+                let template = &self.templates[idx];
+                let on_compiled = &on_compiled;
+                async move {
+                    let result = match crate::metrics::profiler::get_profiler() {
+                        Some(profiler) => profiler.prepare(template.id(), template.prepare()).await,
+                        None => template.prepare().await,
+                    };
+                    on_compiled(template.id(), result.is_ok());
+                    (idx, result)
+                }
+            })
+            .buffer_unordered(num_cpus::get().max(1))
+            .collect()
+            .await;
+
+        let mut failed_idx = HashSet::new();
+        let mut failures = Vec::new();
+        for (idx, result) in results {
+            if let Err(e) = result {
+                failed_idx.insert(idx);
+                failures.push((self.templates[idx].id().to_string(), e));
+            }
+        }
+
+        let mut idx = 0;
+        self.templates.retain(|_| {
+            let keep = !failed_idx.contains(&idx);
+            idx += 1;
+            keep
+        });
+
+        failures
+    }
+
+    /// Get total work units (targets × templates, less per-target overrides)
     pub fn total_work_units(&self) -> usize {
-        self.targets.len() * self.templates.len()
+        if self.per_target_overrides.is_empty() {
+            return self.targets.len() * self.templates.len();
+        }
+        self.targets
+            .iter()
+            .map(|target| self.templates_for(target).count())
+            .sum()
     }
 }
 
+fn has_any_tag(template: &dyn Template, tags: &[&str]) -> bool {
+    template
+        .metadata()
+        .tags
+        .iter()
+        .any(|t| tags.iter().any(|tag| t.eq_ignore_ascii_case(tag)))
+}
+
+/// What a scan would do, as printed by `cxg scan --dry-run`
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanPlan {
+    /// Templates left after filtering
+    pub templates: Vec<PlannedTemplate>,
+    /// Targets after expansion, as URLs
+    pub targets: Vec<String>,
+    /// Template × target pairs
+    pub work_units: usize,
+    /// Sum of the templates' request estimates
+    pub requests_per_target: usize,
+    /// `requests_per_target` across all targets
+    pub estimated_requests: usize,
+    /// Template count per language
+    pub languages: BTreeMap<String, usize>,
+    /// Extra ports templates will be tried on
+    pub additional_ports: Vec<u16>,
+    /// Ports replacing template defaults
+    pub override_ports: Option<Vec<u16>>,
+    /// Templates removed per filtering stage
+    pub excluded: BTreeMap<String, usize>,
+    /// Scope expansions left to the scan as they need the network
+    pub unexpanded_scope: Vec<String>,
+}
+
+/// One template in a [`ScanPlan`]
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedTemplate {
+    /// Template ID
+    pub id: String,
+    /// Template language
+    pub language: String,
+    /// Template severity
+    pub severity: String,
+    /// Template file
+    pub path: PathBuf,
+    /// Requests sent to each target
+    pub estimated_requests: usize,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -287,4 +929,247 @@ mod tests {
         let job = ScanJob::new(targets, templates, config);
         assert_eq!(job.targets.len(), 1);
     }
+
+    struct BrokenCompiledTemplate {
+        metadata: crate::types::TemplateMetadata,
+    }
+
+    #[async_trait::async_trait]
+    impl Template for BrokenCompiledTemplate {
+        fn metadata(&self) -> &crate::types::TemplateMetadata {
+            &self.metadata
+        }
+
+        async fn execute(
+            &self,
+            _target: &Target,
+            _context: &Context,
+        ) -> Result<Vec<crate::types::Finding>> {
+            Ok(Vec::new())
+        }
+
+        async fn prepare(&self) -> Result<()> {
+            if self.metadata.language.is_compiled() {
+                Err(Error::Execution("compilation failed".to_string()))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_precompile_removes_failed_templates() {
+        use crate::types::TemplateLanguage;
+
+        let template = |id: &str, language: TemplateLanguage| -> Box<dyn Template> {
+            let path = std::path::Path::new("test.yaml");
+            let mut metadata = crate::engine::common::create_metadata(path, language);
+            metadata.id = id.to_string();
+            Box::new(BrokenCompiledTemplate { metadata })
+        };
+
+        let mut job = ScanJob::new(
+            vec![Target::new("example.com", Protocol::Https)],
+            vec![
+                template("yaml-check", TemplateLanguage::Yaml),
+                template("go-check", TemplateLanguage::Go),
+            ],
+            Arc::new(Config::default()),
+        );
+
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let failures = job
+            .precompile_templates(|_, _| {
+                calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            })
+            .await;
+
+        assert_eq!(calls.into_inner(), 1);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "go-check");
+        assert_eq!(job.templates.len(), 1);
+        assert_eq!(job.templates[0].id(), "yaml-check");
+    }
+
+    fn tagged(id: &str, tags: &[&str]) -> Box<dyn Template> {
+        let path = std::path::Path::new("test.yaml");
+        let mut metadata =
+            crate::engine::common::create_metadata(path, crate::types::TemplateLanguage::Yaml);
+        metadata.id = id.to_string();
+        metadata.tags = tags.iter().map(|t| t.to_string()).collect();
+        Box::new(BrokenCompiledTemplate { metadata })
+    }
+
+    #[test]
+    fn test_safe_and_passive_modes_drop_tagged_templates() {
+        let templates = || {
+            vec![
+                tagged("info-leak", &["exposure"]),
+                tagged("slowloris", &["DoS"]),
+                tagged("banner-probe", &["probe"]),
+                tagged("passive-probe", &["probe", "passive"]),
+            ]
+        };
+        let config = Arc::new(Config::default());
+
+        let mut job = ScanJob::new(Vec::new(), templates(), config.clone());
+        assert_eq!(job.apply_safe_mode(), 1);
+        assert_eq!(job.templates.len(), 3);
+
+        let mut job = ScanJob::new(Vec::new(), templates(), config);
+        assert_eq!(job.apply_passive_mode(), 1);
+        let ids: Vec<&str> = job.templates.iter().map(|t| t.id()).collect();
+        assert_eq!(ids, vec!["info-leak", "slowloris", "passive-probe"]);
+    }
+
+    #[test]
+    fn test_randomized_template_order() {
+        let ids = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let templates = ids.iter().map(|id| tagged(id, &[])).collect();
+        let mut job = ScanJob::new(Vec::new(), templates, Arc::new(Config::default()));
+        job.seed = Some(1234);
+        assert_eq!(job.template_order(0), (0..ids.len()).collect::<Vec<_>>());
+
+        job.randomize_templates = true;
+        let first = job.template_order(0);
+        assert_eq!(first, job.template_order(0));
+        assert_ne!(first, job.template_order(1));
+        let mut sorted = first.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..ids.len()).collect::<Vec<_>>());
+
+        let results = finish_results(
+            ScanResults::new(job.id),
+            &job,
+            Vec::new(),
+            ExecutionUsage::default(),
+            &RequestCounter::default(),
+            None,
+        );
+        assert_eq!(results.statistics.seed, Some(1234));
+    }
+
+    #[test]
+    fn test_discovery_findings_are_reported() {
+        use crate::types::{Finding, Severity};
+
+        let mut job = ScanJob::new(Vec::new(), Vec::new(), Arc::new(Config::default()));
+        job.discovery_findings.push(Finding::new(
+            "example.com",
+            "dns-zone-transfer",
+            Severity::Info,
+            "DNS zone transfer refused",
+            "d",
+        ));
+        let scanned = Finding::new("example.com", "t", Severity::High, "x", "d");
+        let results = finish_results(
+            ScanResults::new(job.id),
+            &job,
+            vec![scanned],
+            ExecutionUsage::default(),
+            &RequestCounter::default(),
+            None,
+        );
+        assert_eq!(results.findings.len(), 2);
+        assert_eq!(results.findings[0].template_id, "dns-zone-transfer");
+        assert_eq!(results.statistics.findings_by_severity[&Severity::Info], 1);
+    }
+
+    #[test]
+    fn test_exclude_deprecated_templates() {
+        let path = std::path::Path::new("test.yaml");
+        let mut metadata =
+            crate::engine::common::create_metadata(path, crate::types::TemplateLanguage::Yaml);
+        metadata.id = "old-check".to_string();
+        metadata.deprecated = true;
+        metadata.superseded_by = Some("new-check".to_string());
+        let templates: Vec<Box<dyn Template>> = vec![
+            Box::new(BrokenCompiledTemplate { metadata }),
+            tagged("new-check", &[]),
+        ];
+
+        let mut job = ScanJob::new(Vec::new(), templates, Arc::new(Config::default()));
+        assert_eq!(job.exclude_deprecated(), 1);
+        assert_eq!(job.templates[0].id(), "new-check");
+    }
+
+    #[test]
+    fn test_plan_summarizes_job() {
+        let mut job = ScanJob::new(
+            vec![
+                Target::new("a.example.com", Protocol::Https),
+                Target::with_port("b.example.com", 8080, Protocol::Http),
+            ],
+            vec![tagged("one", &[]), tagged("two", &[])],
+            Arc::new(Config::default()),
+        );
+        job.context.additional_ports = vec![8443];
+
+        let plan = job.plan();
+        assert_eq!(plan.work_units, 4);
+        assert_eq!(plan.requests_per_target, 2);
+        assert_eq!(plan.estimated_requests, 4);
+        assert_eq!(plan.languages.get("yaml"), Some(&2));
+        assert_eq!(plan.targets[1], "http://b.example.com:8080");
+        assert_eq!(plan.additional_ports, vec![8443]);
+
+        let json = serde_json::to_value(&plan).unwrap();
+        assert_eq!(json["templates"][0]["id"], "one");
+    }
+
+    #[test]
+    fn test_per_target_overrides_narrow_templates() {
+        let api = Target::new("api.example.com", Protocol::Https);
+        let web = Target::new("www.example.com", Protocol::Https);
+        let mut job = ScanJob::new(
+            vec![api.clone(), web.clone()],
+            vec![
+                tagged("api-auth", &[]),
+                tagged("api-injection", &[]),
+                tagged("xss", &[]),
+            ],
+            Arc::new(Config::default()),
+        );
+        job.per_target_overrides.insert(
+            Target::new("api.example.com", Protocol::Https),
+            vec!["api-auth".to_string(), "api-injection".to_string()],
+        );
+
+        let ids = |target: &Target| -> Vec<String> {
+            job.templates_for(target)
+                .map(|t| t.id().to_string())
+                .collect()
+        };
+        assert_eq!(ids(&api), vec!["api-auth", "api-injection"]);
+        assert_eq!(ids(&web).len(), 3);
+        assert_eq!(job.total_work_units(), 5);
+        assert_eq!(job.plan().estimated_requests, 5);
+    }
+
+    #[test]
+    fn test_overrides_follow_expanded_targets() {
+        let origin = Target::new("api.example.com", Protocol::Https);
+        let mut overrides = PerTargetOverrides::new();
+        overrides.insert(origin.clone(), vec!["api-auth".to_string()]);
+
+        // A port-expanded copy keeps the ID, an endpoint keeps the host
+        let mut other_port = origin.clone();
+        other_port.port = Some(8443);
+        let mut endpoint = Target::new("api.example.com", Protocol::Https);
+        endpoint.base_path = Some("/v1/users".to_string());
+        let unrelated = Target::new("www.example.com", Protocol::Https);
+
+        overrides.apply_to(&[other_port.clone(), endpoint.clone(), unrelated.clone()]);
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(
+            overrides.get(&other_port),
+            Some(&["api-auth".to_string()][..])
+        );
+        assert_eq!(
+            overrides.get(&endpoint),
+            Some(&["api-auth".to_string()][..])
+        );
+        assert_eq!(overrides.get(&unrelated), None);
+        assert_eq!(overrides.get(&origin), None);
+    }
 }
@@ -0,0 +1,964 @@
+//! Embedding scans in other tools
+//!
+//! [`ScanBuilder`] goes from scope entries and a template selection to
+//! [`ScanResults`] the way `cxg scan` does: scope files, CIDR blocks and
+//! importers are expanded, targets are deduplicated and multiplied by extra
+//! ports, and templates are loaded, filtered and trimmed by safe or passive
+//! mode. HTTP schemes are settled when the job runs.
+//!
+//! ```no_run
+//! use cert_x_gen::prelude::*;
+//! use cert_x_gen::template::TemplateFilter;
+//! use cert_x_gen::ScanBuilder;
+//!
+//! # async fn scan() -> Result<()> {
+//! let mut filter = TemplateFilter::new();
+//! filter.severities = vec![Severity::Critical, Severity::High];
+//!
+//! let results = ScanBuilder::new(Config::default())
+//!     .targets(["example.com", "10.0.0.0/30", "@more-targets.txt"])
+//!     .templates_from_dirs()
+//!     .filter(filter)
+//!     .safe_mode(true)
+//!     .on_finding(|finding| println!("[{}] {}", finding.severity, finding.title))
+//!     .run()
+//!     .await?;
+//! println!("{} findings", results.findings.len());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Templates do not have to come from files; anything implementing
+//! [`Template`] can be scanned with:
+//!
+//! ```
+//! use cert_x_gen::prelude::*;
+//! use cert_x_gen::ScanBuilder;
+//! use std::path::Path;
+//! use std::sync::{Arc, Mutex};
+//!
+//! /// Records every HTTPS service it is run against
+//! struct HttpsInventory(TemplateMetadata);
+//!
+//! #[async_trait]
+//! impl Template for HttpsInventory {
+//!     fn metadata(&self) -> &TemplateMetadata {
+//!         &self.0
+//!     }
+//!
+//!     async fn execute(&self, target: &Target, _context: &Context) -> Result<Vec<Finding>> {
+//!         Ok(vec![Finding::new(
+//!             target.url(),
+//!             self.0.id.clone(),
+//!             Severity::Info,
+//!             "HTTPS service".to_string(),
+//!             format!("{} serves HTTPS", target.address),
+//!         )])
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let metadata = cert_x_gen::engine::common::create_metadata(
+//!     Path::new("https-inventory.rs"),
+//!     TemplateLanguage::Rust,
+//! );
+//! let streamed = Arc::new(Mutex::new(Vec::new()));
+//! let sink = streamed.clone();
+//!
+//! let results = ScanBuilder::new(Config::default())
+//!     .targets(["https://app.example.com", "https://api.example.com"])
+//!     .with_templates(vec![Arc::new(HttpsInventory(metadata))])
+//!     .on_finding(move |finding| sink.lock().unwrap().push(finding.target.clone()))
+//!     .run()
+//!     .await?;
+//!
+//! assert_eq!(results.findings.len(), 2);
+//! assert_eq!(streamed.lock().unwrap().len(), 2);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{CertXGen, FindingCallback, PerTargetOverrides, ScanJob, ScanPlan};
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::scope::openapi::{ApiSpec, DEFAULT_MAX_ENDPOINTS};
+use crate::scope::zone_transfer::ZoneTransfer;
+use crate::scope::{self, expand_targets_for_ports, parse_target, ScopeEntries, ScopeSource};
+use crate::template::{separate_template_entries, Template, TemplateFilter};
+use crate::types::{Finding, ScanResults, Target, TargetNormalizer};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Where a scan's templates come from
+enum TemplateSelection {
+    /// Every template in the template directories
+    Dirs,
+    /// Template files, IDs and list files, as `--templates` takes them
+    Entries(Vec<String>),
+    /// Templates loaded beforehand
+    Loaded(Vec<Arc<dyn Template>>),
+}
+
+/// Configure a scan, then [`run`](Self::run) it or [`prepare`](Self::prepare)
+/// a job to inspect first
+///
+/// Without a template selection every template of the template directories
+/// runs. Safe, passive, aggressive and stealth mode start as set in the
+/// configuration's `execution` section.
+#[allow(missing_debug_implementations)]
+pub struct ScanBuilder {
+    config: Config,
+    scope: Vec<String>,
+    scope_sources: Vec<ScopeSource>,
+    zone_transfers: Vec<String>,
+    dry_run: bool,
+    labels: Vec<(String, String)>,
+    openapi: Option<ApiSpec>,
+    max_endpoints: usize,
+    offline: bool,
+    normalize: bool,
+    ports: Vec<u16>,
+    top_ports: Option<u16>,
+    override_ports: Option<Vec<u16>>,
+    templates: Option<TemplateSelection>,
+    credential_services: Vec<String>,
+    filter: TemplateFilter,
+    include_deprecated: bool,
+    follow_deprecations: bool,
+    safe_mode: bool,
+    passive_mode: bool,
+    aggressive_mode: bool,
+    stealth_mode: bool,
+    randomize_targets: bool,
+    randomize_templates: bool,
+    seed: Option<u64>,
+    on_finding: Option<FindingCallback>,
+}
+
+/// A scan job built by [`ScanBuilder::prepare`], not yet run
+#[allow(missing_debug_implementations)]
+pub struct PreparedScan {
+    /// Targets, templates and context of the scan
+    pub job: ScanJob,
+    /// Templates removed by each selection stage (`filters`, `deprecated`,
+    /// `safe-mode`, `passive-mode`)
+    pub excluded: BTreeMap<String, usize>,
+    /// Template files picked by path
+    pub template_paths: Vec<PathBuf>,
+    /// Scope a dry run left unexpanded as it needs the network (zone
+    /// transfers, AS prefix lookups)
+    pub unexpanded_scope: Vec<String>,
+}
+
+impl PreparedScan {
+    /// What the scan would do, without sending a request
+    pub fn plan(&self) -> ScanPlan {
+        let mut plan = self.job.plan();
+        plan.excluded = self.excluded.clone();
+        plan.unexpanded_scope = self.unexpanded_scope.clone();
+        plan
+    }
+
+    /// Run the job on `engine`
+    pub async fn run(self, engine: &CertXGen) -> Result<ScanResults> {
+        engine.execute_scan(self.job).await
+    }
+}
+
+impl ScanBuilder {
+    /// Scan with `config`
+    pub fn new(config: Config) -> Self {
+        let execution = &config.execution;
+        Self {
+            safe_mode: execution.safe_mode,
+            passive_mode: execution.passive_mode,
+            aggressive_mode: execution.aggressive_mode,
+            stealth_mode: execution.stealth_mode,
+            config,
+            scope: Vec::new(),
+            scope_sources: Vec::new(),
+            zone_transfers: Vec::new(),
+            dry_run: false,
+            labels: Vec::new(),
+            openapi: None,
+            max_endpoints: DEFAULT_MAX_ENDPOINTS,
+            offline: false,
+            normalize: true,
+            ports: Vec::new(),
+            top_ports: None,
+            override_ports: None,
+            templates: None,
+            credential_services: Vec::new(),
+            filter: TemplateFilter::new(),
+            include_deprecated: false,
+            follow_deprecations: false,
+            randomize_targets: false,
+            randomize_templates: false,
+            seed: None,
+            on_finding: None,
+        }
+    }
+
+    /// Add scope entries: hosts, `host:port`, URLs, CIDR blocks, comma-separated
+    /// lists, or `@file` references to files of entries; `-` reads them from stdin
+    pub fn targets<I>(mut self, entries: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.scope.extend(entries.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add the entries of an importer, labeled as the source describes them
+    pub fn scope_from(mut self, source: ScopeSource) -> Self {
+        self.scope_sources.push(source);
+        self
+    }
+
+    /// Try a zone transfer of `domain`, scanning the domain and the hosts it
+    /// reveals and reporting the attempt as a finding
+    pub fn zone_transfer(mut self, domain: impl Into<String>) -> Self {
+        self.zone_transfers.push(domain.into());
+        self
+    }
+
+    /// Label every target, unless its scope source already set `key`
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.push((key.into(), value.into()));
+        self
+    }
+
+    /// Scan every endpoint of `spec`; its servers are the targets when no
+    /// other scope is given
+    pub fn openapi(mut self, spec: ApiSpec) -> Self {
+        self.openapi = Some(spec);
+        self
+    }
+
+    /// Cap on the endpoints taken from the OpenAPI spec
+    pub fn max_endpoints(mut self, max: usize) -> Self {
+        self.max_endpoints = max;
+        self
+    }
+
+    /// Refuse to fetch scope from the network (importers, zone transfers)
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Only plan the scan: skip the importers and zone transfers that fetch
+    /// scope from the network, listing them in the plan instead
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Drop equivalent targets and hostnames resolving to IPs in scope (default: on)
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Scan each target on these ports too
+    pub fn ports(mut self, ports: impl IntoIterator<Item = u16>) -> Self {
+        self.ports.extend(ports);
+        self
+    }
+
+    /// Scan each target on the `count` most common ports too
+    pub fn top_ports(mut self, count: u16) -> Self {
+        self.top_ports = Some(count);
+        self
+    }
+
+    /// Ports templates use instead of their own defaults
+    pub fn override_ports(mut self, ports: Vec<u16>) -> Self {
+        self.override_ports = Some(ports);
+        self
+    }
+
+    /// Run every template of the template directories
+    pub fn templates_from_dirs(mut self) -> Self {
+        self.templates = Some(TemplateSelection::Dirs);
+        self
+    }
+
+    /// Run the templates named by `entries`: template files, paths relative
+    /// to a template directory, IDs, or list files
+    ///
+    /// When any entry is a template file, only the files run.
+    pub fn templates<I>(mut self, entries: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let entries = entries.into_iter().map(Into::into);
+        match &mut self.templates {
+            Some(TemplateSelection::Entries(selected)) => selected.extend(entries),
+            _ => self.templates = Some(TemplateSelection::Entries(entries.collect())),
+        }
+        self
+    }
+
+    /// Run the templates of the template directories with these IDs (or names)
+    pub fn template_ids<I>(self, ids: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.templates(ids)
+    }
+
+    /// Run the template files at `paths`
+    pub fn template_paths<I>(self, paths: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<PathBuf>,
+    {
+        self.templates(
+            paths
+                .into_iter()
+                .map(|path| path.into().to_string_lossy().into_owned()),
+        )
+    }
+
+    /// Run templates loaded beforehand, e.g. one load shared by several scans
+    ///
+    /// The filter and modes apply to them as to the template directories.
+    pub fn with_templates(mut self, templates: Vec<Arc<dyn Template>>) -> Self {
+        self.templates = Some(TemplateSelection::Loaded(templates));
+        self
+    }
+
+    /// Add the built-in default credential checks for `services` (e.g. `ssh`,
+    /// `redis`); without another selection only they run
+    pub fn test_credentials<I>(mut self, services: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.credential_services
+            .extend(services.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only run templates matching `filter`
+    pub fn filter(mut self, filter: TemplateFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Keep deprecated templates in scans over the template directories
+    pub fn include_deprecated(mut self, include: bool) -> Self {
+        self.include_deprecated = include;
+        self
+    }
+
+    /// Run the templates superseding deprecated ones picked by
+    /// [`templates`](Self::templates) instead
+    pub fn follow_deprecations(mut self, follow: bool) -> Self {
+        self.follow_deprecations = follow;
+        self
+    }
+
+    /// Leave out dangerous templates (DoS, brute force, exploits)
+    pub fn safe_mode(mut self, enabled: bool) -> Self {
+        self.safe_mode = enabled;
+        self
+    }
+
+    /// Leave out templates sending active probes
+    pub fn passive_mode(mut self, enabled: bool) -> Self {
+        self.passive_mode = enabled;
+        self
+    }
+
+    /// Let templates run their aggressive checks
+    pub fn aggressive_mode(mut self, enabled: bool) -> Self {
+        self.aggressive_mode = enabled;
+        self
+    }
+
+    /// Ask templates to keep a low profile
+    pub fn stealth_mode(mut self, enabled: bool) -> Self {
+        self.stealth_mode = enabled;
+        self
+    }
+
+    /// Shuffle the targets once they are expanded
+    pub fn randomize_targets(mut self, enabled: bool) -> Self {
+        self.randomize_targets = enabled;
+        self
+    }
+
+    /// Shuffle each target's templates (within their priority)
+    pub fn randomize_templates(mut self, enabled: bool) -> Self {
+        self.randomize_templates = enabled;
+        self
+    }
+
+    /// Seed of the shuffles, to replay a run's order
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Call `callback` with each finding as soon as its template reports it
+    ///
+    /// Findings arrive from several templates at once, before the results'
+    /// deduplication.
+    pub fn on_finding<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Finding) + Send + Sync + 'static,
+    {
+        self.on_finding = Some(Arc::new(callback));
+        self
+    }
+
+    /// Create an engine from the configuration and run the scan on it
+    pub async fn run(self) -> Result<ScanResults> {
+        let engine = CertXGen::new(self.config.clone()).await?;
+        self.prepare(&engine).await?.run(&engine).await
+    }
+
+    /// Load the templates and gather the targets into a job for `engine`
+    ///
+    /// `engine` should have been created from the builder's configuration.
+    /// Nothing is sent to the targets except zone transfer requests, which a
+    /// [dry run](Self::dry_run) skips; whether they speak HTTP or HTTPS is
+    /// probed when the job runs.
+    pub async fn prepare(self, engine: &CertXGen) -> Result<PreparedScan> {
+        let (templates, template_paths, picked) = self.select_templates(engine).await?;
+        let (mut targets, mut overrides, discovery_findings, unexpanded_scope) =
+            self.scope_targets().await?;
+
+        if let Some(spec) = self.openapi.as_ref().filter(|_| targets.is_empty()) {
+            targets = spec
+                .servers
+                .iter()
+                .map(|server| parse_target(server))
+                .collect();
+            self.apply_labels(&mut targets);
+        }
+        if targets.is_empty() && unexpanded_scope.is_empty() {
+            return Err(Error::config(
+                "No scope provided. Use --scope (aliases: --target, --targets, --target-file, --domain, --cidr, etc.), --zone-transfer or an --openapi spec that declares servers.",
+            ));
+        }
+        tracing::info!("Parsed {} targets", targets.len());
+
+        // Expand targets for additional ports
+        let mut additional_ports = self.ports.clone();
+        additional_ports.sort_unstable();
+        additional_ports.dedup();
+        if !additional_ports.is_empty() {
+            targets = expand_targets_for_ports(targets, &additional_ports);
+            tracing::info!(
+                "Expanded to {} targets with additional ports: {:?}",
+                targets.len(),
+                additional_ports
+            );
+        }
+
+        let top_ports = self
+            .top_ports
+            .map(crate::utils::top_ports)
+            .unwrap_or_default();
+        if !top_ports.is_empty() {
+            targets = expand_targets_for_ports(targets, &top_ports);
+            tracing::info!(
+                "Expanded to {} targets with top ports: {:?}",
+                targets.len(),
+                top_ports
+            );
+        }
+
+        // One sub-target per API endpoint, after port expansion so every port gets them all
+        if let Some(spec) = &self.openapi {
+            targets = spec.endpoint_targets(&targets, self.max_endpoints);
+            tracing::info!(
+                "Expanded to {} targets with {} OpenAPI endpoint(s)",
+                targets.len(),
+                spec.endpoints.len().min(self.max_endpoints)
+            );
+        }
+
+        // Port-expanded copies and endpoints keep their scope entry's templates
+        overrides.apply_to(&targets);
+
+        let filter = &self.filter;
+        if !filter.ids.is_empty() {
+            tracing::info!("Filtering templates by IDs: {:?}", filter.ids);
+        }
+        if !filter.tags.is_empty() {
+            tracing::info!("Filtering templates by tags: {:?}", filter.tags);
+        }
+        if !filter.severities.is_empty() {
+            tracing::info!("Filtering templates by severities: {:?}", filter.severities);
+        }
+        if !filter.languages.is_empty() {
+            tracing::info!("Filtering templates by languages: {:?}", filter.languages);
+        }
+        if !filter.exclude_ids.is_empty() {
+            tracing::info!("Excluding templates: {:?}", filter.exclude_ids);
+        }
+
+        // Shuffle once the target list is final; the seed lets the order be replayed
+        let seed = crate::scheduler::scan_seed(
+            self.randomize_targets || self.randomize_templates,
+            self.seed,
+        );
+        if let Some(seed) = seed.filter(|_| self.randomize_targets) {
+            crate::scheduler::shuffle_seeded(&mut targets, seed);
+        }
+
+        let mut job = engine.create_shared_scan_job(targets, templates);
+        job.seed = seed;
+        job.randomize_templates = self.randomize_templates;
+        job.discovery_findings = discovery_findings;
+        job.on_finding = self.on_finding.clone();
+        job.context.aggressive_mode = self.aggressive_mode;
+        job.context.stealth_mode = self.stealth_mode;
+        job.context.passive_mode = self.passive_mode;
+        job.context.safe_mode = self.safe_mode;
+        let templates_before = job.templates.len();
+        job.filter_templates(filter);
+
+        let mut excluded = BTreeMap::new();
+        excluded.insert(
+            "filters".to_string(),
+            templates_before - job.templates.len(),
+        );
+
+        // Scans over all templates skip deprecated ones unless asked to keep them
+        if !picked && filter.ids.is_empty() && !self.include_deprecated {
+            let removed = job.exclude_deprecated();
+            if removed > 0 {
+                tracing::info!(
+                    "Excluded {} deprecated template(s); use --include-deprecated to run them",
+                    removed
+                );
+            }
+            excluded.insert("deprecated".to_string(), removed);
+        }
+
+        // Apply mode-based template filtering
+        if self.safe_mode {
+            let removed = job.apply_safe_mode();
+            if removed > 0 {
+                tracing::info!(
+                    "Safe mode: Excluded {} dangerous templates (DoS, resource-exhaustion, etc.)",
+                    removed
+                );
+            }
+            excluded.insert("safe-mode".to_string(), removed);
+        }
+
+        if self.passive_mode {
+            let removed = job.apply_passive_mode();
+            if removed > 0 {
+                tracing::info!("Passive mode: Excluded {} active probe templates", removed);
+            }
+            excluded.insert("passive-mode".to_string(), removed);
+        }
+
+        let templates_after = job.templates.len();
+
+        if !overrides.is_empty() {
+            let selected: HashSet<&str> = job.templates.iter().map(|t| t.id()).collect();
+            for id in overrides.template_ids() {
+                if !selected.contains(id) {
+                    tracing::warn!(
+                        "Scope file pins template '{}', which is not among the selected templates",
+                        id
+                    );
+                }
+            }
+            tracing::info!(
+                "{} target(s) run only the templates named in the scope file",
+                overrides.len()
+            );
+            job.per_target_overrides = overrides;
+        }
+
+        tracing::info!(
+            "Templates selected: {} (total available: {})",
+            templates_after,
+            templates_before
+        );
+
+        // List selected templates in verbose mode
+        if templates_after > 0 && templates_after <= 10 {
+            for template in &job.templates {
+                tracing::info!(
+                    "  - {} ({})",
+                    template.metadata().id,
+                    template.metadata().language
+                );
+            }
+        }
+
+        if !additional_ports.is_empty() || !top_ports.is_empty() {
+            let mut combined = additional_ports;
+            combined.extend(top_ports);
+            combined.sort_unstable();
+            combined.dedup();
+            job.context.additional_ports = combined;
+            tracing::info!(
+                "Adding {} additional ports to scan (custom + top): {:?}",
+                job.context.additional_ports.len(),
+                job.context.additional_ports
+            );
+        }
+
+        if let Some(override_ports) = &self.override_ports {
+            job.context.override_ports = Some(override_ports.clone());
+            tracing::info!(
+                "Overriding template default ports with: {:?}",
+                override_ports
+            );
+        }
+
+        Ok(PreparedScan {
+            job,
+            excluded,
+            template_paths,
+            unexpanded_scope,
+        })
+    }
+
+    /// Load the selected templates without gathering targets, e.g. to share
+    /// them between several scans with [`with_templates`](Self::with_templates)
+    pub async fn load_templates(&self, engine: &CertXGen) -> Result<Vec<Arc<dyn Template>>> {
+        Ok(self.select_templates(engine).await?.0)
+    }
+
+    /// The selected templates, the files picked by path, and whether they
+    /// were picked by path or ID rather than taken from a whole set
+    async fn select_templates(
+        &self,
+        engine: &CertXGen,
+    ) -> Result<(Vec<Arc<dyn Template>>, Vec<PathBuf>, bool)> {
+        // Credential checks alone run just the built-in credential templates
+        let credential_templates =
+            engine.default_credential_templates(&self.credential_services)?;
+        let selection = match &self.templates {
+            None if !credential_templates.is_empty() => {
+                let templates = credential_templates.into_iter().map(Arc::from).collect();
+                return Ok((templates, Vec::new(), true));
+            }
+            None => &TemplateSelection::Dirs,
+            Some(selection) => selection,
+        };
+
+        let (templates, paths, picked) = match selection {
+            TemplateSelection::Loaded(templates) => {
+                let mut templates = templates.clone();
+                templates.extend(credential_templates.into_iter().map(Arc::from));
+                return Ok((templates, Vec::new(), false));
+            }
+            TemplateSelection::Dirs => {
+                // No specific templates - load all templates from directories
+                tracing::debug!("No specific templates specified, loading all from directories");
+                let loaded = engine.load_templates().await?;
+                tracing::info!(
+                    "Loaded {} templates from template directories",
+                    loaded.len()
+                );
+                (loaded, Vec::new(), false)
+            }
+            TemplateSelection::Entries(entries) => {
+                tracing::debug!("Processing template entries: {:?}", entries);
+                let (paths, ids) =
+                    separate_template_entries(entries, &engine.config().templates.directories)?;
+                tracing::info!("Loading templates...");
+                // File paths load only those files; IDs are searched for in the template directories
+                let loaded = if !paths.is_empty() {
+                    engine.load_template_files(&paths).await?
+                } else if !ids.is_empty() {
+                    engine.find_templates(&ids).await?
+                } else {
+                    engine.load_templates().await?
+                };
+                let picked = !paths.is_empty() || !ids.is_empty();
+                // Deprecated templates picked explicitly still run unless their replacements should
+                let loaded = if picked {
+                    resolve_deprecated_templates(engine, loaded, self.follow_deprecations).await?
+                } else {
+                    loaded
+                };
+                (loaded, paths, picked)
+            }
+        };
+
+        let mut templates = templates;
+        templates.extend(credential_templates);
+        tracing::info!("Total templates to use: {}", templates.len());
+        for template in &templates {
+            let metadata = template.metadata();
+            tracing::debug!(
+                "Available template: {} ({})",
+                metadata.id,
+                metadata.language
+            );
+        }
+
+        if templates.is_empty() {
+            return Err(Error::config(
+                "No templates loaded. Please add templates to the templates directory.",
+            ));
+        }
+
+        Ok((
+            templates.into_iter().map(Arc::from).collect(),
+            paths,
+            picked,
+        ))
+    }
+
+    /// Targets from the scope entries, importers and zone transfers, with the
+    /// templates scope file annotations pin to some of them, the findings of
+    /// the zone transfers and the network lookups a dry run skipped
+    async fn scope_targets(
+        &self,
+    ) -> Result<(Vec<Target>, PerTargetOverrides, Vec<Finding>, Vec<String>)> {
+        let mut entries = ScopeEntries::new();
+        for entry in &self.scope {
+            if scope::is_stdin_entry(entry) {
+                for line in scope::read_stdin().await? {
+                    entries.expand(&line)?;
+                }
+                continue;
+            }
+            entries.expand(entry)?;
+        }
+
+        let mut unexpanded = Vec::new();
+        let (fetched, sources): (Vec<ScopeSource>, Vec<ScopeSource>) = self
+            .scope_sources
+            .iter()
+            .cloned()
+            .partition(|source| self.dry_run && source.fetches());
+        unexpanded.extend(fetched.iter().map(ScopeSource::to_string));
+        for imported in scope::import_all(&sources, self.offline).await? {
+            entries.expand_labeled(&imported.entry, &imported.labels)?;
+        }
+
+        // The domain itself is scanned whether or not its zone is sent
+        let mut findings = Vec::new();
+        for domain in &self.zone_transfers {
+            if self.offline {
+                return Err(Error::config(format!(
+                    "Cannot transfer the {} zone with --offline",
+                    domain
+                )));
+            }
+            if self.dry_run {
+                entries.push(domain.clone());
+                unexpanded.push(format!("zone transfer of {}", domain));
+                continue;
+            }
+            let transfer = ZoneTransfer::new(domain)?.run().await?;
+            entries.push(transfer.domain.clone());
+            for host in &transfer.hosts {
+                entries.push(host.clone());
+            }
+            findings.push(transfer.finding());
+        }
+
+        let (mut targets, mut overrides) = entries.into_targets();
+        self.apply_labels(&mut targets);
+
+        if !self.normalize {
+            return Ok((targets, overrides, findings, unexpanded));
+        }
+        let before = targets.len();
+        let targets = TargetNormalizer::new()
+            .with_alias_resolution(true)
+            .dedup(targets)
+            .await;
+        if targets.len() < before {
+            tracing::info!(
+                "Normalization removed {} duplicate target(s)",
+                before - targets.len()
+            );
+        }
+        overrides.apply_to(&targets);
+        Ok((targets, overrides, findings, unexpanded))
+    }
+
+    /// Add the builder's labels to every target, keeping labels a scope source already set
+    fn apply_labels(&self, targets: &mut [Target]) {
+        for target in targets {
+            for (key, value) in &self.labels {
+                target
+                    .labels
+                    .entry(key.clone())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+    }
+}
+
+/// Warn about deprecated templates picked by `--templates`, or with `--follow-deprecations` swap in
+/// the templates that supersede them
+async fn resolve_deprecated_templates(
+    engine: &CertXGen,
+    templates: Vec<Box<dyn Template>>,
+    follow: bool,
+) -> Result<Vec<Box<dyn Template>>> {
+    if !templates.iter().any(|t| t.metadata().deprecated) {
+        return Ok(templates);
+    }
+    if follow {
+        let available = engine.load_templates().await?;
+        return crate::template::follow_deprecations(templates, available);
+    }
+
+    for metadata in templates
+        .iter()
+        .map(|t| t.metadata())
+        .filter(|m| m.deprecated)
+    {
+        match &metadata.superseded_by {
+            Some(replacement) => tracing::warn!(
+                "Template '{}' is deprecated and superseded by '{}' (use --follow-deprecations to run the replacement)",
+                metadata.id,
+                replacement
+            ),
+            None => tracing::warn!("Template '{}' is deprecated", metadata.id),
+        }
+    }
+    Ok(templates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Context, TemplateLanguage, TemplateMetadata};
+
+    struct StubTemplate {
+        metadata: TemplateMetadata,
+    }
+
+    #[async_trait::async_trait]
+    impl Template for StubTemplate {
+        fn metadata(&self) -> &TemplateMetadata {
+            &self.metadata
+        }
+
+        async fn execute(&self, _target: &Target, _context: &Context) -> Result<Vec<Finding>> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn template(id: &str, tags: &[&str], deprecated: bool) -> Arc<dyn Template> {
+        let path = std::path::Path::new("test.yaml");
+        let mut metadata = crate::engine::common::create_metadata(path, TemplateLanguage::Yaml);
+        metadata.id = id.to_string();
+        metadata.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        metadata.deprecated = deprecated;
+        Arc::new(StubTemplate { metadata })
+    }
+
+    #[tokio::test]
+    async fn test_prepare_expands_scope_and_trims_templates() {
+        let mut config = Config::default();
+        config.network.detect_scheme = false;
+        let engine = CertXGen::new(config.clone()).await.unwrap();
+
+        let prepared = ScanBuilder::new(config)
+            .targets(["https://10.0.0.1", "10.0.0.2,10.0.0.1"])
+            .label("team", "web")
+            .ports([8080])
+            .override_ports(vec![9443])
+            .with_templates(vec![
+                template("http-check", &["http"], false),
+                template("slowloris", &["dos"], false),
+                template("old-check", &["http"], true),
+            ])
+            .safe_mode(true)
+            .on_finding(|_| {})
+            .prepare(&engine)
+            .await
+            .unwrap();
+
+        let job = &prepared.job;
+        // Schemes are settled when the job runs
+        let urls: Vec<String> = job.targets.iter().map(Target::url).collect();
+        assert_eq!(urls, ["https://10.0.0.1:8080", "https://10.0.0.2:8080"]);
+        assert!(job.targets.iter().all(|t| t.labels["team"] == "web"));
+        let ids: Vec<&str> = job.templates.iter().map(|t| t.id()).collect();
+        assert_eq!(ids, ["http-check"]);
+        assert_eq!(prepared.excluded["deprecated"], 1);
+        assert_eq!(prepared.excluded["safe-mode"], 1);
+        assert!(job.context.safe_mode);
+        assert_eq!(job.context.additional_ports, [8080]);
+        assert_eq!(job.context.override_ports, Some(vec![9443]));
+        assert!(job.on_finding.is_some());
+
+        let empty = ScanBuilder::new(Config::default())
+            .with_templates(Vec::new())
+            .prepare(&engine)
+            .await;
+        assert!(empty.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_plan_sends_nothing_to_targets() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let mut config = Config::default();
+        config.network.detect_scheme = true;
+        let engine = CertXGen::new(config.clone()).await.unwrap();
+        let plan = ScanBuilder::new(config)
+            .targets([format!("127.0.0.1:{}", port)])
+            .with_templates(vec![template("http-check", &["http"], false)])
+            .prepare(&engine)
+            .await
+            .unwrap()
+            .plan();
+
+        assert_eq!(plan.targets.len(), 1);
+        let accepted = listener.accept();
+        assert!(
+            matches!(&accepted, Err(e) if e.kind() == std::io::ErrorKind::WouldBlock),
+            "planning connected to the target: {:?}",
+            accepted
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_leaves_network_scope_unexpanded() {
+        let config = Config::default();
+        let engine = CertXGen::new(config.clone()).await.unwrap();
+        let prepared = ScanBuilder::new(config)
+            .zone_transfer("example.invalid")
+            .scope_from(ScopeSource::Asn(64496))
+            .normalize(false)
+            .dry_run(true)
+            .with_templates(vec![template("http-check", &["http"], false)])
+            .prepare(&engine)
+            .await
+            .unwrap();
+
+        let hosts: Vec<&str> = prepared
+            .job
+            .targets
+            .iter()
+            .map(|t| t.address.as_str())
+            .collect();
+        assert_eq!(hosts, ["example.invalid"]);
+        assert!(prepared.job.discovery_findings.is_empty());
+        assert_eq!(
+            prepared.plan().unexpanded_scope,
+            ["asn:AS64496", "zone transfer of example.invalid"]
+        );
+    }
+}
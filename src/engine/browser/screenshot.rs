@@ -0,0 +1,197 @@
+//! Screenshots of web findings (`--screenshot`)
+//!
+//! Browser templates attach the page they rendered. After the scan,
+//! [`ScreenshotCapture`] loads the page of every other HTTP finding in the
+//! shared headless browser, once per URL and at most
+//! [`MAX_CONCURRENT_SCREENSHOTS`] at a time, and attaches a PNG of the
+//! viewport. The evidence references it as `screenshots/<finding id>.png`;
+//! reports embed it (HTML, SARIF) or write it there (JSON, Markdown).
+
+use super::{HeadlessBrowserEngine, RenderRequest, WaitFor};
+use crate::error::{Error, Result};
+use crate::types::Finding;
+use futures::stream::{self, StreamExt};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Pages screenshotted at once, whatever `browser.max_tabs` allows templates
+pub const MAX_CONCURRENT_SCREENSHOTS: usize = 2;
+
+/// Takes PNG screenshots of web pages with a [`HeadlessBrowserEngine`]
+#[derive(Debug, Clone)]
+pub struct ScreenshotCapture {
+    browser: Arc<HeadlessBrowserEngine>,
+}
+
+impl ScreenshotCapture {
+    /// Capture with `browser`, starting it if no template did
+    pub fn new(browser: Arc<HeadlessBrowserEngine>) -> Self {
+        Self { browser }
+    }
+
+    /// PNG of `url` once its network is idle
+    pub async fn take(&self, url: &str) -> Result<Vec<u8>> {
+        let page = self
+            .browser
+            .render(&RenderRequest {
+                url: url.to_string(),
+                wait_for: WaitFor::NetworkIdle,
+                scripts: Vec::new(),
+                screenshot: true,
+            })
+            .await?;
+        page.screenshot
+            .ok_or_else(|| Error::Execution(format!("No screenshot taken of {}", url)))
+    }
+
+    /// Attach a screenshot to every HTTP finding without one and return how
+    /// many were attached; pages that fail to load are skipped with a warning,
+    /// and everything is skipped when no browser can be started
+    pub async fn capture(&self, findings: &mut [Finding]) -> usize {
+        let urls: HashSet<String> = findings
+            .iter()
+            .filter(|f| f.screenshot.is_none())
+            .filter_map(finding_url)
+            .map(str::to_string)
+            .collect();
+        if urls.is_empty() {
+            return 0;
+        }
+        if let Err(e) = self.browser.start().await {
+            tracing::warn!("Skipping screenshots, no browser available: {}", e);
+            return 0;
+        }
+        tracing::info!("Capturing screenshots of {} page(s)", urls.len());
+
+        let shots: HashMap<String, Vec<u8>> = stream::iter(urls)
+            .map(|url| async move {
+                let shot = self.take(&url).await;
+                (url, shot)
+            })
+            .buffer_unordered(
+                self.browser
+                    .settings
+                    .max_tabs
+                    .clamp(1, MAX_CONCURRENT_SCREENSHOTS),
+            )
+            .filter_map(|(url, shot)| async move {
+                match shot {
+                    Ok(png) => Some((url, png)),
+                    Err(e) => {
+                        tracing::warn!("Failed to screenshot {}: {}", url, e);
+                        None
+                    }
+                }
+            })
+            .collect()
+            .await;
+
+        let mut attached = 0;
+        for finding in findings.iter_mut().filter(|f| f.screenshot.is_none()) {
+            if let Some(png) = finding_url(finding).and_then(|url| shots.get(url)) {
+                finding.attach_screenshot(png.clone());
+                attached += 1;
+            }
+        }
+        attached
+    }
+}
+
+/// Page a finding was reported on: its evidence URL, else an HTTP(S) target
+fn finding_url(finding: &Finding) -> Option<&str> {
+    finding
+        .evidence
+        .data
+        .get("url")
+        .and_then(|url| url.as_str())
+        .into_iter()
+        .chain([finding.target.as_str()])
+        .find(|url| url.starts_with("http://") || url.starts_with("https://"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Severity;
+
+    #[test]
+    fn test_only_http_findings_have_a_page() {
+        let finding = |target: &str| Finding::new(target, "t", Severity::High, "T", "d");
+
+        assert_eq!(
+            finding_url(&finding("https://example.com/admin")),
+            Some("https://example.com/admin")
+        );
+        assert_eq!(finding_url(&finding("10.0.0.1:6379")), None);
+
+        let mut injected = finding("https://example.com");
+        injected
+            .evidence
+            .add_data("url", serde_json::json!("https://example.com/?q=%0d%0a"));
+        assert_eq!(
+            finding_url(&injected),
+            Some("https://example.com/?q=%0d%0a")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_capture_skips_without_a_browser() {
+        let mut config = crate::config::Config::default();
+        config.browser.executable = Some("/nonexistent/chromium".into());
+        let browser = Arc::new(HeadlessBrowserEngine::new(&config));
+        let mut findings = vec![Finding::new(
+            "https://example.com",
+            "a",
+            Severity::High,
+            "A",
+            "d",
+        )];
+
+        let attached = ScreenshotCapture::new(browser).capture(&mut findings).await;
+        assert_eq!(attached, 0);
+        assert!(findings[0].screenshot.is_none());
+        assert!(!findings[0].evidence.data.contains_key("screenshot"));
+    }
+
+    #[tokio::test]
+    #[ignore] // Only run with --ignored when Chrome or Chromium is installed
+    async fn test_capture_attaches_one_shot_per_page() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw("<h1>Tomcat Manager</h1>", "text/html"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let browser = Arc::new(HeadlessBrowserEngine::new(
+            &super::super::tests::local_chrome_config(),
+        ));
+        let mut findings = vec![
+            Finding::new(server.uri().as_str(), "a", Severity::High, "A", "d"),
+            Finding::new(server.uri().as_str(), "b", Severity::Low, "B", "d"),
+            Finding::new("10.0.0.1:6379", "redis", Severity::High, "R", "d"),
+        ];
+        let attached = ScreenshotCapture::new(browser.clone())
+            .capture(&mut findings)
+            .await;
+        browser.shutdown().await;
+
+        assert_eq!(attached, 2);
+        assert!(findings[0]
+            .screenshot
+            .as_ref()
+            .unwrap()
+            .starts_with(b"\x89PNG"));
+        assert_eq!(findings[0].screenshot, findings[1].screenshot);
+        assert_eq!(
+            findings[0].evidence.data["screenshot"],
+            format!("screenshots/{}.png", findings[0].id)
+        );
+        assert!(findings[2].screenshot.is_none());
+    }
+}
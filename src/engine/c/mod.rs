@@ -1,23 +1,25 @@
 //! C template engine implementation
 
+use crate::config::EngineRuntimeConfig;
 use crate::engine::common::{
-    build_env_vars, check_tool_available, create_metadata, execute_command, generate_cache_key,
-    get_cache_dir, parse_findings,
+    build_env_vars, cached_binary_path, create_metadata, execute_command, generate_cache_key,
+    get_cache_dir, parse_findings, prepare_workdir, ToolCommand,
 };
+use crate::engine::runtime::EngineRuntime;
 use crate::error::{Error, Result};
-use crate::template::{Template, TemplateEngine};
+use crate::metrics::profiler::{self, Phase};
+use crate::template::{ExecutionCost, Template, TemplateEngine};
 use crate::types::{Context, Finding, Protocol, Target, TemplateLanguage};
+use crate::workspace::resolve_artifacts;
 use async_trait::async_trait;
 use std::path::Path;
 use std::path::PathBuf;
-use std::process::Stdio;
-use tokio::process::Command;
 
 /// C template engine - compiles and executes C templates
 #[derive(Debug)]
 pub struct CEngine {
     name: String,
-    compiler_path: String,
+    runtime: EngineRuntime,
     cache_dir: PathBuf,
 }
 
@@ -26,39 +28,48 @@ impl CEngine {
     pub fn new() -> Self {
         Self {
             name: "c".to_string(),
-            compiler_path: "gcc".to_string(),
+            runtime: EngineRuntime::new("c"),
             cache_dir: get_cache_dir("c"),
         }
     }
 
-    /// Compile and execute C template
-    async fn execute_c_template(
-        &self,
-        template_path: &Path,
-        target: &Target,
-        context: &Context,
-    ) -> Result<Vec<Finding>> {
+    /// Use an `engines.c` configuration entry instead of the defaults
+    pub fn with_runtime_config(mut self, config: EngineRuntimeConfig) -> Self {
+        self.runtime = self.runtime.with_config(config);
+        self
+    }
+
+    /// Compile the template unless an up-to-date cached binary exists
+    async fn ensure_compiled(&self, template_path: &Path) -> Result<PathBuf> {
         // Ensure cache directory exists
         tokio::fs::create_dir_all(&self.cache_dir).await?;
 
         // Generate cache key and binary path
         let cache_key = generate_cache_key(template_path)?;
-        let binary_name = template_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("template");
-        let binary_path = self
-            .cache_dir
-            .join(format!("{}-{}", binary_name, cache_key));
+        let binary_path = cached_binary_path(&self.cache_dir, template_path, &cache_key);
 
         // Check if binary exists and is newer than source
         if !binary_path.exists() || self.is_source_newer(template_path, &binary_path).await? {
             // Compile C template
+            let _compiling = profiler::enter(Phase::Compile);
             self.compile_template(template_path, &binary_path).await?;
         }
 
+        Ok(binary_path)
+    }
+
+    /// Compile and execute C template
+    async fn execute_c_template(
+        &self,
+        template_path: &Path,
+        target: &Target,
+        context: &Context,
+    ) -> Result<Vec<Finding>> {
+        let binary_path = self.ensure_compiled(template_path).await?;
+
         // Build environment variables
-        let env_vars = build_env_vars(target, context)?;
+        let mut env_vars = build_env_vars(target, context)?;
+        let workdir = prepare_workdir(&mut env_vars, context, template_path, target)?;
 
         // Execute compiled binary
         let stdout = execute_command(
@@ -79,28 +90,22 @@ impl CEngine {
             .unwrap_or("unknown")
             .to_string();
 
-        parse_findings(&stdout, target, &template_id)
+        let mut findings = parse_findings(&stdout, target, &template_id)?;
+        resolve_artifacts(&mut findings, workdir.as_deref());
+        Ok(findings)
     }
 
     /// Compile C template to binary
     async fn compile_template(&self, source_path: &Path, binary_path: &Path) -> Result<()> {
-        // Try gcc first, then clang
-        let compiler = if check_tool_available("gcc").await {
-            "gcc"
-        } else if check_tool_available("clang").await {
-            "clang"
-        } else {
-            return Err(Error::Execution("Neither gcc nor clang found".to_string()));
-        };
-
-        let output = Command::new(compiler)
+        let compiler = self.runtime.program().await?;
+
+        let output = ToolCommand::new(&compiler)
             .arg(source_path)
             .arg("-o")
             .arg(binary_path)
             .arg("-O2")
             .arg("-std=c11")
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
+            .args(self.runtime.compile_flags())
             .output()
             .await
             .map_err(|e| Error::Execution(format!("Failed to compile C template: {}", e)))?;
@@ -129,7 +134,7 @@ impl Clone for CEngine {
     fn clone(&self) -> Self {
         Self {
             name: self.name.clone(),
-            compiler_path: self.compiler_path.clone(),
+            runtime: self.runtime.clone(),
             cache_dir: self.cache_dir.clone(),
         }
     }
@@ -156,6 +161,10 @@ impl Template for CTemplate {
             .await
     }
 
+    async fn prepare(&self) -> Result<()> {
+        self.engine.ensure_compiled(&self.path).await.map(|_| ())
+    }
+
     fn validate(&self) -> Result<()> {
         if !self.path.exists() {
             return Err(Error::Io(std::io::Error::new(
@@ -209,6 +218,10 @@ impl TemplateEngine for CEngine {
         &self.name
     }
 
+    fn cost_hint(&self) -> ExecutionCost {
+        ExecutionCost::Heavy
+    }
+
     fn supports_file(&self, path: &Path) -> bool {
         path.extension()
             .and_then(|s| s.to_str())
@@ -228,6 +241,17 @@ mod tests {
         assert!(!engine.supports_file(Path::new("test.cpp")));
     }
 
+    #[test]
+    fn test_cached_binary_path() {
+        let path = cached_binary_path(Path::new("cache"), Path::new("templates/check.c"), "ab12");
+        assert_eq!(path.parent(), Some(Path::new("cache")));
+
+        #[cfg(windows)]
+        assert_eq!(path.file_name().unwrap(), "check-ab12.exe");
+        #[cfg(not(windows))]
+        assert_eq!(path.file_name().unwrap(), "check-ab12");
+    }
+
     #[test]
     fn test_c_engine_name() {
         let engine = CEngine::new();
@@ -3,32 +3,53 @@
 #![allow(missing_docs)]
 
 use crate::error::{Error, Result};
+use crate::sandbox::docker::ExecRoute;
 use crate::types::{Context, Finding, Severity, Target, TemplateLanguage, TemplateMetadata};
+use crate::workspace::WORKDIR_ENV;
 use regex::Regex;
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::path::Path;
-use std::process::Stdio;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
 use tokio::process::Command;
 
 // ============================================================================
 // METADATA PARSING FROM COMMENT HEADERS
 // ============================================================================
 
-/// Parsed metadata extracted from template comment headers
-#[derive(Debug, Clone, Default)]
+/// Parsed metadata extracted from template comment headers, front-matter or a sidecar file
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
 pub struct ParsedMetadata {
     pub id: Option<String>,
     pub name: Option<String>,
     pub author: Option<String>,
     pub severity: Option<String>,
     pub description: Option<String>,
+    #[serde(deserialize_with = "string_or_list")]
     pub tags: Vec<String>,
+    #[serde(deserialize_with = "string_or_list")]
     pub cwe: Vec<String>,
     pub cvss: Option<f32>,
+    #[serde(deserialize_with = "string_or_list")]
     pub references: Vec<String>,
     pub confidence: Option<u8>,
+    pub rate_limit: Option<u32>,
+    #[serde(rename = "max-requests", alias = "max_requests")]
+    pub max_requests: Option<u64>,
     pub version: Option<String>,
+    pub deprecated: Option<bool>,
+    #[serde(rename = "superseded-by", alias = "superseded_by")]
+    pub superseded_by: Option<String>,
+    #[serde(rename = "created-date", alias = "created_date")]
+    pub created_date: Option<chrono::NaiveDate>,
+    #[serde(
+        rename = "depends-on",
+        alias = "depends_on",
+        deserialize_with = "string_or_list"
+    )]
+    pub depends_on: Vec<String>,
 }
 
 impl ParsedMetadata {
@@ -75,6 +96,45 @@ impl ParsedMetadata {
         }
         missing
     }
+
+    /// Overlay fields present in `other`, keeping current values where it has none
+    pub fn merge(&mut self, other: ParsedMetadata) {
+        macro_rules! overlay {
+            ($($field:ident),*) => {
+                $(if other.$field.is_some() {
+                    self.$field = other.$field;
+                })*
+            };
+        }
+        overlay!(
+            id,
+            name,
+            author,
+            severity,
+            description,
+            cvss,
+            confidence,
+            rate_limit,
+            max_requests,
+            version,
+            deprecated,
+            superseded_by,
+            created_date
+        );
+
+        if !other.tags.is_empty() {
+            self.tags = other.tags;
+        }
+        if !other.cwe.is_empty() {
+            self.cwe = other.cwe;
+        }
+        if !other.references.is_empty() {
+            self.references = other.references;
+        }
+        if !other.depends_on.is_empty() {
+            self.depends_on = other.depends_on;
+        }
+    }
 }
 
 /// Parse metadata from template comment headers
@@ -131,6 +191,32 @@ pub fn parse_metadata_from_comments(content: &str) -> ParsedMetadata {
         metadata.confidence = conf_str.parse::<u8>().ok();
     }
 
+    // Parse per-template rate limit (requests/second)
+    if let Some(rate_str) = extract_metadata_field(&header_content, "rate_limit") {
+        metadata.rate_limit = rate_str.parse::<u32>().ok();
+    }
+
+    // Parse the per-execution request budget
+    if let Some(max_str) = extract_metadata_field(&header_content, "max-requests") {
+        metadata.max_requests = max_str.parse::<u64>().ok();
+    }
+
+    // Parse deprecation and the replacing template ID
+    if let Some(deprecated_str) = extract_metadata_field(&header_content, "deprecated") {
+        metadata.deprecated = deprecated_str.parse::<bool>().ok();
+    }
+    metadata.superseded_by = extract_metadata_field(&header_content, "superseded-by");
+
+    // Parse the IDs of templates this one waits for (comma-separated)
+    if let Some(deps_str) = extract_metadata_field(&header_content, "depends-on") {
+        metadata.depends_on = parse_comma_separated(&deps_str);
+    }
+
+    // Parse the date the template was added (YYYY-MM-DD)
+    if let Some(date_str) = extract_metadata_field(&header_content, "created-date") {
+        metadata.created_date = chrono::NaiveDate::parse_from_str(&date_str, "%Y-%m-%d").ok();
+    }
+
     // If no @tags found, try fallback extraction from code
     if metadata.tags.is_empty() {
         metadata.tags = extract_tags_from_code(content);
@@ -287,6 +373,9 @@ pub fn build_env_vars(target: &Target, context: &Context) -> Result<HashMap<Stri
         "CERT_X_GEN_TARGET_PORT".to_string(),
         target.port.unwrap_or(80).to_string(),
     );
+    if let Some(base_path) = &target.base_path {
+        env_vars.insert("CERT_X_GEN_BASE_PATH".to_string(), base_path.clone());
+    }
 
     // Port configuration
     if !context.additional_ports.is_empty() {
@@ -318,6 +407,43 @@ pub fn build_env_vars(target: &Target, context: &Context) -> Result<HashMap<Stri
     Ok(env_vars)
 }
 
+/// Create the execution's working directory and add it to `env_vars`
+///
+/// The directory is `<workspace>/<template>/<target>` (see
+/// [`crate::workspace`]); [`execute_command`] runs the template in it.
+/// Returns `None` when the scan has no workspace.
+pub fn prepare_workdir(
+    env_vars: &mut HashMap<String, String>,
+    context: &Context,
+    template_path: &Path,
+    target: &Target,
+) -> Result<Option<PathBuf>> {
+    let Some(workspace) = &context.workspace else {
+        return Ok(None);
+    };
+    let template_id = template_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown");
+    let dir = workspace.execution_dir(template_id, target)?;
+    env_vars.insert(WORKDIR_ENV.to_string(), dir.to_string_lossy().into_owned());
+    Ok(Some(dir))
+}
+
+/// Merge a local sandbox's library paths into a template's environment
+///
+/// Lets interpreted templates import gems, Perl modules, Composer packages,
+/// node modules and venv packages installed by `cxg sandbox`. Without a
+/// sandbox `env_vars` is left untouched.
+pub fn merge_sandbox_env(
+    env_vars: &mut HashMap<String, String>,
+    sandbox: Option<&crate::sandbox::Sandbox>,
+) {
+    if let Some(sandbox) = sandbox.filter(|sandbox| sandbox.is_initialized()) {
+        env_vars.extend(sandbox.get_env_vars());
+    }
+}
+
 /// Parse JSON output from templates into Finding structs
 pub fn parse_findings(stdout: &str, target: &Target, template_id: &str) -> Result<Vec<Finding>> {
     if stdout.trim().is_empty() {
@@ -448,7 +574,15 @@ fn parse_simple_findings(
                 Vec::new()
             },
             tags: Vec::new(),
+            technology: simple
+                .get("technology")
+                .and_then(|v| serde_json::from_value(v.clone()).ok()),
             timestamp: chrono::Utc::now(),
+            screenshot: None,
+            target_labels: Default::default(),
+            cluster_id: None,
+            duplicates: None,
+            affected_targets: Vec::new(),
         };
         findings.push(finding);
     }
@@ -456,17 +590,218 @@ fn parse_simple_findings(
     Ok(findings)
 }
 
+// ============================================================================
+// METADATA SIDECAR FILES
+// ============================================================================
+
+/// Suffix of per-template metadata sidecars (`<name>.meta.yaml`)
+pub const METADATA_SIDECAR_SUFFIX: &str = ".meta.yaml";
+
+/// Project-level metadata file written by `cxg template init-project`
+pub const PROJECT_METADATA_FILE: &str = "template.meta.yaml";
+
+/// Build manifests that mark the root of a template project
+const PROJECT_MANIFESTS: &[&str] = &["Cargo.toml", "go.mod", "pom.xml"];
+
+/// Check whether a path is a metadata sidecar rather than a template
+pub fn is_metadata_sidecar(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.ends_with(METADATA_SIDECAR_SUFFIX))
+}
+
+/// Find the metadata sidecar for a template file
+///
+/// Looks for `<name>.meta.yaml` next to the template, then for
+/// `template.meta.yaml` in the template's directory and its parents up to
+/// the project root (the nearest directory with a build manifest).
+pub fn find_metadata_sidecar(path: &Path) -> Option<PathBuf> {
+    let dir = path.parent()?;
+    let stem = path.file_stem()?.to_str()?;
+
+    let beside = dir.join(format!("{}{}", stem, METADATA_SIDECAR_SUFFIX));
+    if beside.is_file() {
+        return Some(beside);
+    }
+
+    // Without a project root, only the template's own directory is searched
+    let project_root = dir
+        .ancestors()
+        .take(6)
+        .find(|d| PROJECT_MANIFESTS.iter().any(|m| d.join(m).is_file()))
+        .unwrap_or(dir);
+
+    for ancestor in dir.ancestors() {
+        let candidate = ancestor.join(PROJECT_METADATA_FILE);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if ancestor == project_root {
+            break;
+        }
+    }
+    None
+}
+
+/// Load metadata from a template's sidecar file, if one exists
+pub fn load_metadata_sidecar(path: &Path) -> Option<ParsedMetadata> {
+    let sidecar = find_metadata_sidecar(path)?;
+    let content = std::fs::read_to_string(&sidecar).ok()?;
+    match serde_yaml::from_str::<ParsedMetadata>(&content) {
+        Ok(metadata) => Some(metadata),
+        Err(e) => {
+            tracing::warn!(
+                "Ignoring invalid metadata sidecar {}: {}",
+                sidecar.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Accept either a YAML list or a comma-separated string
+fn string_or_list<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum StringOrList {
+        String(String),
+        List(Vec<String>),
+    }
+
+    Ok(
+        match <Option<StringOrList> as serde::Deserialize>::deserialize(deserializer)? {
+            Some(StringOrList::String(s)) => s
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            Some(StringOrList::List(list)) => list,
+            None => Vec::new(),
+        },
+    )
+}
+
+// ============================================================================
+// METADATA FRONT-MATTER
+// ============================================================================
+
+/// Comment line that opens a YAML front-matter block in a script template
+pub const FRONT_MATTER_MARKER: &str = "cert-x-gen:";
+
+/// Line comment prefixes for a language, longest first
+fn line_comment_prefixes(language: TemplateLanguage) -> &'static [&'static str] {
+    match language {
+        TemplateLanguage::Python
+        | TemplateLanguage::Shell
+        | TemplateLanguage::Ruby
+        | TemplateLanguage::Perl
+        | TemplateLanguage::Yaml => &["#"],
+        TemplateLanguage::Php => &["//", "#"],
+        TemplateLanguage::Rust => &["//!", "///", "//"],
+        TemplateLanguage::JavaScript
+        | TemplateLanguage::Rhai
+        | TemplateLanguage::C
+        | TemplateLanguage::Cpp
+        | TemplateLanguage::Java
+        | TemplateLanguage::Go => &["//"],
+    }
+}
+
+/// Parse a YAML front-matter block embedded in line comments
+///
+/// The block starts with a `cert-x-gen:` comment line within the first 50
+/// lines and continues over the following indented lines using the same
+/// comment prefix:
+///
+/// ```text
+/// #!/usr/bin/env python3
+/// # cert-x-gen:
+/// #   id: redis-unauthenticated
+/// #   severity: high
+/// #   tags: [redis, database]
+/// ```
+pub fn parse_front_matter(content: &str, language: TemplateLanguage) -> Option<ParsedMetadata> {
+    let prefixes = line_comment_prefixes(language);
+    let strip = |line: &str| -> Option<(&'static str, String)> {
+        let trimmed = line.trim_start();
+        prefixes
+            .iter()
+            .find(|p| trimmed.starts_with(**p))
+            .map(|p| (*p, trimmed[p.len()..].to_string()))
+    };
+
+    let mut lines = content.lines().take(50);
+    let prefix = lines.by_ref().find_map(|line| {
+        strip(line)
+            .and_then(|(prefix, rest)| (rest.trim() == FRONT_MATTER_MARKER).then_some(prefix))
+    })?;
+
+    // Entries are indented under the marker; a plain `# comment` ends the block
+    let block: Vec<String> = lines
+        .map_while(|line| line.trim_start().strip_prefix(prefix).map(str::to_string))
+        .take_while(|rest| {
+            rest.starts_with("  ") || rest.starts_with('\t') || rest.trim().is_empty()
+        })
+        .collect();
+
+    // Dedent so the block parses as a top-level YAML mapping
+    let indent = block
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let yaml = block
+        .iter()
+        .map(|l| l.get(indent..).unwrap_or("").trim_end())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    match serde_yaml::from_str::<ParsedMetadata>(&yaml) {
+        Ok(metadata) => Some(metadata),
+        Err(e) => {
+            tracing::warn!("Ignoring invalid cert-x-gen front-matter: {}", e);
+            None
+        }
+    }
+}
+
+/// Collect template metadata from all supported sources
+///
+/// Sources are applied in increasing precedence: `@field:` comment
+/// annotations, `cert-x-gen:` front-matter, then the metadata sidecar file
+/// (when `path` is given).
+pub fn parse_template_metadata(
+    content: &str,
+    language: TemplateLanguage,
+    path: Option<&Path>,
+) -> ParsedMetadata {
+    let mut parsed = parse_metadata_from_comments(content);
+    if let Some(front_matter) = parse_front_matter(content, language) {
+        parsed.merge(front_matter);
+    }
+    if let Some(sidecar) = path.and_then(load_metadata_sidecar) {
+        parsed.merge(sidecar);
+    }
+    parsed
+}
+
 /// Create template metadata from file path
 ///
-/// This function reads the template file and extracts metadata from comment headers.
-/// It looks for @field: annotations in the first 50 lines.
+/// This function reads the template file and collects metadata from comment
+/// headers, front-matter and sidecar files (see [`parse_template_metadata`]).
 /// Falls back to filename-based defaults if no metadata is found.
 pub fn create_metadata(path: &Path, language: TemplateLanguage) -> TemplateMetadata {
     // Read file content for metadata parsing
-    let content = std::fs::read_to_string(path).unwrap_or_default();
+    let content = crate::template::read_source(path)
+        .map(|source| source.text)
+        .unwrap_or_default();
 
-    // Parse metadata from comment headers
-    let parsed = parse_metadata_from_comments(&content);
+    let parsed = parse_template_metadata(&content, language, Some(path));
 
     // Check if metadata was found before moving fields
     let has_metadata = parsed.has_metadata();
@@ -525,6 +860,7 @@ pub fn create_metadata(path: &Path, language: TemplateLanguage) -> TemplateMetad
         description,
         cve_ids: Vec::new(),
         cwe_ids: parsed.cwe,
+        references: parsed.references,
         cvss_score: parsed.cvss,
         tags,
         language,
@@ -533,6 +869,12 @@ pub fn create_metadata(path: &Path, language: TemplateLanguage) -> TemplateMetad
         updated: chrono::Utc::now(),
         version: parsed.version.unwrap_or_else(|| "1.0.0".to_string()),
         confidence: parsed.confidence.or(Some(50)),
+        rate_limit: parsed.rate_limit,
+        max_requests: parsed.max_requests,
+        deprecated: parsed.deprecated.unwrap_or(false),
+        superseded_by: parsed.superseded_by,
+        created_date: parsed.created_date,
+        depends_on: parsed.depends_on,
     }
 }
 
@@ -554,24 +896,206 @@ pub fn get_ports_to_scan(context: &Context) -> Vec<u16> {
     defaults
 }
 
+/// External program run by an engine (interpreter, compiler or compiled template)
+///
+/// When a default Docker sandbox is running, the program is executed inside
+/// it with `docker exec`, with host paths translated to their mounted
+/// locations. Otherwise it runs directly on the host.
+#[derive(Debug, Clone)]
+pub struct ToolCommand {
+    program: OsString,
+    args: Vec<OsString>,
+    env: Vec<(String, String)>,
+    current_dir: Option<PathBuf>,
+    syscall_filter: bool,
+}
+
+impl ToolCommand {
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Self {
+        Self {
+            program: program.as_ref().to_os_string(),
+            args: Vec::new(),
+            env: Vec::new(),
+            current_dir: None,
+            syscall_filter: false,
+        }
+    }
+
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args
+            .extend(args.into_iter().map(|a| a.as_ref().to_os_string()));
+        self
+    }
+
+    pub fn env<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) -> &mut Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.current_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Confine the process to the template syscall allowlist
+    /// (see [`crate::sandbox::syscall`])
+    pub fn syscall_filter(&mut self, enabled: bool) -> &mut Self {
+        self.syscall_filter = enabled;
+        self
+    }
+
+    pub fn program_name(&self) -> String {
+        self.program.to_string_lossy().into_owned()
+    }
+
+    /// Program to run on the host
+    ///
+    /// A filtered process may only `execve` once, so a bare name is looked
+    /// up in the command's `PATH` here rather than tried entry by entry.
+    fn host_program(&self) -> OsString {
+        let program = Path::new(&self.program);
+        if !self.syscall_filter || program.components().count() > 1 {
+            return self.program.clone();
+        }
+        let path = self
+            .env
+            .iter()
+            .rfind(|(key, _)| key == "PATH")
+            .map(|(_, value)| OsString::from(value))
+            .or_else(|| std::env::var_os("PATH"))
+            .unwrap_or_default();
+        std::env::split_paths(&path)
+            .map(|dir| dir.join(program))
+            .find(|candidate| candidate.is_file())
+            .map_or_else(|| self.program.clone(), PathBuf::into_os_string)
+    }
+
+    /// Run to completion, in the active sandbox if there is one
+    pub async fn output(&self) -> Result<Output> {
+        self.output_via(crate::sandbox::active_exec_route()).await
+    }
+
+    /// Run to completion through `route`, or on the host if `None`
+    pub async fn output_via(&self, route: Option<&ExecRoute>) -> Result<Output> {
+        let mut cmd = match route {
+            Some(route) => {
+                let mut cmd = Command::new("docker");
+                cmd.args(route.exec_args(
+                    &self.program,
+                    &self.args,
+                    &self.env,
+                    self.current_dir.as_deref(),
+                )?);
+                cmd
+            }
+            None => {
+                let mut cmd = Command::new(self.host_program());
+                cmd.args(&self.args).envs(self.env.iter().cloned());
+                if let Some(dir) = &self.current_dir {
+                    cmd.current_dir(dir);
+                }
+                crate::engine::process::isolate(&mut cmd);
+                cmd
+            }
+        };
+
+        let failed = |e: std::io::Error| {
+            Error::Execution(format!(
+                "Failed to run {}: {}",
+                self.program.to_string_lossy(),
+                e
+            ))
+        };
+        // A Docker sandbox applies the container's own seccomp profile
+        let filter = if self.syscall_filter && route.is_none() {
+            Some(crate::sandbox::syscall::confine(&mut cmd).map_err(failed)?)
+        } else {
+            None
+        };
+        let child = cmd
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(failed)?;
+
+        // A template timeout drops this future; take the host process tree down with it
+        let guard = crate::engine::process::TreeGuard::new(child.id().filter(|_| route.is_none()));
+        let watch = filter.map(|filter| filter.watch(child.id()));
+        let output = child.wait_with_output().await.map_err(failed)?;
+        guard.disarm();
+
+        if let Some(syscall) = watch.and_then(|watch| watch.finish()) {
+            return Err(Error::SandboxViolation {
+                template_id: crate::network::rate_limit::current_template()
+                    .unwrap_or_else(|| self.program_name()),
+                syscall,
+            });
+        }
+        Ok(output)
+    }
+}
+
 /// Execute a command with environment variables and return stdout
 pub async fn execute_command(
     command: &str,
     args: &[String],
     env_vars: &HashMap<String, String>,
 ) -> Result<String> {
-    let mut cmd = Command::new(command);
-    cmd.args(args).stdout(Stdio::piped()).stderr(Stdio::piped());
+    let route = crate::sandbox::active_exec_route();
+
+    // The working directory, and its path as seen from inside the sandbox if there is one
+    let workdir = env_vars.get(WORKDIR_ENV).map(Path::new).and_then(|dir| {
+        match route.map(|route| route.container_path(dir)) {
+            None => Some((dir, dir.to_path_buf())),
+            Some(Some(inner)) => Some((dir, inner)),
+            Some(None) => {
+                tracing::debug!(
+                    "Working directory {} is not mounted in the sandbox; not using it",
+                    dir.display()
+                );
+                None
+            }
+        }
+    });
+    // Relative template and binary paths would not resolve from the working directory
+    let anchored = |arg: &str| match workdir {
+        Some(_) if Path::new(arg).is_relative() && Path::new(arg).exists() => {
+            std::path::absolute(arg)
+                .map(|path| path.to_string_lossy().into_owned())
+                .unwrap_or_else(|_| arg.to_string())
+        }
+        _ => arg.to_string(),
+    };
+
+    // A bare program name is looked up on PATH, not in the directory
+    let program = match Path::new(command).components().count() {
+        1 => command.to_string(),
+        _ => anchored(command),
+    };
+    let mut cmd = ToolCommand::new(program);
+    cmd.args(args.iter().map(|arg| anchored(arg)))
+        .syscall_filter(crate::sandbox::syscall::enabled());
 
     // Set environment variables
-    for (key, value) in env_vars {
+    for (key, value) in env_vars.iter().filter(|(key, _)| *key != WORKDIR_ENV) {
         cmd.env(key, value);
     }
+    if let Some((dir, visible)) = &workdir {
+        cmd.env(WORKDIR_ENV, visible.to_string_lossy())
+            .current_dir(dir);
+    }
 
-    let output = cmd
-        .output()
-        .await
-        .map_err(|e| Error::Execution(format!("Failed to execute command: {}", e)))?;
+    let output = cmd.output_via(route).await?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -587,14 +1111,38 @@ pub async fn check_tool_available(tool: &str) -> bool {
     // Go uses "version" instead of "--version"
     let version_arg = if tool == "go" { "version" } else { "--version" };
 
-    let output = Command::new(tool).arg(version_arg).output().await;
-
-    output.is_ok() && output.unwrap().status.success()
+    ToolCommand::new(tool)
+        .arg(version_arg)
+        .output()
+        .await
+        .is_ok_and(|output| output.status.success())
 }
 
 /// Get cache directory for a language
-pub fn get_cache_dir(language: &str) -> std::path::PathBuf {
-    std::path::PathBuf::from("/tmp/cert-x-gen-cache").join(language)
+///
+/// Inside a Docker sandbox route this is the host side of the mounted cache
+/// volume, so compiled templates survive container restarts.
+pub fn get_cache_dir(language: &str) -> PathBuf {
+    match crate::sandbox::active_exec_route().and_then(|route| route.host_cache_dir()) {
+        Some(dir) => dir.join(language),
+        None => std::env::temp_dir().join("cert-x-gen-cache").join(language),
+    }
+}
+
+/// Path of the cached binary compiled from `template_path`
+///
+/// The file name carries the platform's executable suffix (`.exe` on Windows).
+pub fn cached_binary_path(cache_dir: &Path, template_path: &Path, cache_key: &str) -> PathBuf {
+    let binary_name = template_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("template");
+    cache_dir.join(format!(
+        "{}-{}{}",
+        binary_name,
+        cache_key,
+        std::env::consts::EXE_SUFFIX
+    ))
 }
 
 /// Generate cache key from file path and content
@@ -1,23 +1,25 @@
 //! Go template engine implementation
 
+use crate::config::EngineRuntimeConfig;
 use crate::engine::common::{
-    build_env_vars, check_tool_available, create_metadata, execute_command, generate_cache_key,
-    get_cache_dir, parse_findings,
+    build_env_vars, cached_binary_path, create_metadata, execute_command, generate_cache_key,
+    get_cache_dir, parse_findings, prepare_workdir, ToolCommand,
 };
+use crate::engine::runtime::EngineRuntime;
 use crate::error::{Error, Result};
-use crate::template::{Template, TemplateEngine};
+use crate::metrics::profiler::{self, Phase};
+use crate::template::{ExecutionCost, Template, TemplateEngine};
 use crate::types::{Context, Finding, Protocol, Target, TemplateLanguage};
+use crate::workspace::resolve_artifacts;
 use async_trait::async_trait;
 use std::path::Path;
 use std::path::PathBuf;
-use std::process::Stdio;
-use tokio::process::Command;
 
 /// Go template engine - compiles and executes Go templates
 #[derive(Debug)]
 pub struct GoEngine {
     name: String,
-    go_path: String,
+    runtime: EngineRuntime,
     cache_dir: PathBuf,
 }
 
@@ -26,39 +28,48 @@ impl GoEngine {
     pub fn new() -> Self {
         Self {
             name: "go".to_string(),
-            go_path: "go".to_string(),
+            runtime: EngineRuntime::new("go"),
             cache_dir: get_cache_dir("go"),
         }
     }
 
-    /// Compile and execute Go template
-    async fn execute_go_template(
-        &self,
-        template_path: &Path,
-        target: &Target,
-        context: &Context,
-    ) -> Result<Vec<Finding>> {
+    /// Use an `engines.go` configuration entry instead of the defaults
+    pub fn with_runtime_config(mut self, config: EngineRuntimeConfig) -> Self {
+        self.runtime = self.runtime.with_config(config);
+        self
+    }
+
+    /// Compile the template unless an up-to-date cached binary exists
+    async fn ensure_compiled(&self, template_path: &Path) -> Result<PathBuf> {
         // Ensure cache directory exists
         tokio::fs::create_dir_all(&self.cache_dir).await?;
 
         // Generate cache key and binary path
         let cache_key = generate_cache_key(template_path)?;
-        let binary_name = template_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("template");
-        let binary_path = self
-            .cache_dir
-            .join(format!("{}-{}", binary_name, cache_key));
+        let binary_path = cached_binary_path(&self.cache_dir, template_path, &cache_key);
 
         // Check if binary exists and is newer than source
         if !binary_path.exists() || self.is_source_newer(template_path, &binary_path).await? {
             // Compile Go template
+            let _compiling = profiler::enter(Phase::Compile);
             self.compile_template(template_path, &binary_path).await?;
         }
 
+        Ok(binary_path)
+    }
+
+    /// Compile and execute Go template
+    async fn execute_go_template(
+        &self,
+        template_path: &Path,
+        target: &Target,
+        context: &Context,
+    ) -> Result<Vec<Finding>> {
+        let binary_path = self.ensure_compiled(template_path).await?;
+
         // Build environment variables
-        let env_vars = build_env_vars(target, context)?;
+        let mut env_vars = build_env_vars(target, context)?;
+        let workdir = prepare_workdir(&mut env_vars, context, template_path, target)?;
 
         // Execute compiled binary (no arguments, uses environment variables)
         let stdout = execute_command(&binary_path.to_string_lossy(), &[], &env_vars).await?;
@@ -70,22 +81,32 @@ impl GoEngine {
             .unwrap_or("unknown")
             .to_string();
 
-        parse_findings(&stdout, target, &template_id)
+        let mut findings = parse_findings(&stdout, target, &template_id)?;
+        resolve_artifacts(&mut findings, workdir.as_deref());
+        Ok(findings)
     }
 
     /// Compile Go template to binary
     async fn compile_template(&self, source_path: &Path, binary_path: &Path) -> Result<()> {
-        if !check_tool_available("go").await {
-            return Err(Error::Execution("Go compiler not found".to_string()));
-        }
+        let go = self.runtime.program().await?;
 
-        let output = Command::new(&self.go_path)
-            .arg("build")
+        let mut cmd = ToolCommand::new(&go);
+        cmd.arg("build")
+            .args(self.runtime.compile_flags())
             .arg("-o")
-            .arg(binary_path)
-            .arg(source_path)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
+            .arg(binary_path);
+
+        // Build inside the module so go.mod (and its pinned deps) applies
+        match (source_path.parent(), source_path.file_name()) {
+            (Some(dir), Some(file)) if dir.join("go.mod").is_file() => {
+                cmd.current_dir(dir).arg(file);
+            }
+            _ => {
+                cmd.arg(source_path);
+            }
+        }
+
+        let output = cmd
             .output()
             .await
             .map_err(|e| Error::Execution(format!("Failed to compile Go template: {}", e)))?;
@@ -114,7 +135,7 @@ impl Clone for GoEngine {
     fn clone(&self) -> Self {
         Self {
             name: self.name.clone(),
-            go_path: self.go_path.clone(),
+            runtime: self.runtime.clone(),
             cache_dir: self.cache_dir.clone(),
         }
     }
@@ -141,6 +162,10 @@ impl Template for GoTemplate {
             .await
     }
 
+    async fn prepare(&self) -> Result<()> {
+        self.engine.ensure_compiled(&self.path).await.map(|_| ())
+    }
+
     fn validate(&self) -> Result<()> {
         if !self.path.exists() {
             return Err(Error::Io(std::io::Error::new(
@@ -194,6 +219,10 @@ impl TemplateEngine for GoEngine {
         &self.name
     }
 
+    fn cost_hint(&self) -> ExecutionCost {
+        ExecutionCost::Heavy
+    }
+
     fn supports_file(&self, path: &Path) -> bool {
         path.extension()
             .and_then(|s| s.to_str())
@@ -1,24 +1,25 @@
 //! Java template engine implementation
 
+use crate::config::EngineRuntimeConfig;
 use crate::engine::common::{
-    build_env_vars, check_tool_available, create_metadata, execute_command, generate_cache_key,
-    get_cache_dir, parse_findings,
+    build_env_vars, create_metadata, execute_command, generate_cache_key, get_cache_dir,
+    parse_findings, prepare_workdir, ToolCommand,
 };
+use crate::engine::runtime::EngineRuntime;
 use crate::error::{Error, Result};
-use crate::template::{Template, TemplateEngine};
+use crate::metrics::profiler::{self, Phase};
+use crate::template::{ExecutionCost, Template, TemplateEngine};
 use crate::types::{Context, Finding, Protocol, Target, TemplateLanguage};
+use crate::workspace::resolve_artifacts;
 use async_trait::async_trait;
 use std::path::Path;
 use std::path::PathBuf;
-use std::process::Stdio;
-use tokio::process::Command;
 
 /// Java template engine - compiles and executes Java templates
 #[derive(Debug)]
 pub struct JavaEngine {
     name: String,
-    javac_path: String,
-    java_path: String,
+    runtime: EngineRuntime,
     cache_dir: PathBuf,
 }
 
@@ -27,12 +28,41 @@ impl JavaEngine {
     pub fn new() -> Self {
         Self {
             name: "java".to_string(),
-            javac_path: "javac".to_string(),
-            java_path: "java".to_string(),
+            runtime: EngineRuntime::new("java"),
             cache_dir: get_cache_dir("java"),
         }
     }
 
+    /// Use an `engines.java` configuration entry instead of the defaults
+    pub fn with_runtime_config(mut self, config: EngineRuntimeConfig) -> Self {
+        self.runtime = self.runtime.with_config(config);
+        self
+    }
+
+    /// Compile the template unless an up-to-date cached class file exists
+    ///
+    /// Returns the class directory to put on the classpath.
+    async fn ensure_compiled(&self, template_path: &Path) -> Result<PathBuf> {
+        // Each source file gets its own class directory, so templates sharing
+        // a class name and package in different directories never collide
+        let class_dir = self.cache_dir.join(generate_cache_key(template_path)?);
+        tokio::fs::create_dir_all(&class_dir).await?;
+
+        // javac names the class file after the public class (the file stem),
+        // nested under the package directories when a package is declared
+        let class_name = qualified_class_name(template_path);
+        let class_file = class_dir.join(format!("{}.class", class_name.replace('.', "/")));
+
+        // Check if class file exists and is newer than source
+        if !class_file.exists() || self.is_source_newer(template_path, &class_file).await? {
+            // Compile Java template
+            let _compiling = profiler::enter(Phase::Compile);
+            self.compile_template(template_path, &class_dir).await?;
+        }
+
+        Ok(class_dir)
+    }
+
     /// Compile and execute Java template
     async fn execute_java_template(
         &self,
@@ -40,39 +70,22 @@ impl JavaEngine {
         target: &Target,
         context: &Context,
     ) -> Result<Vec<Finding>> {
-        // Ensure cache directory exists
-        tokio::fs::create_dir_all(&self.cache_dir).await?;
-
-        // Generate cache key and class path
-        let cache_key = generate_cache_key(template_path)?;
-        let class_name = template_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("Template");
-        let class_file = self
-            .cache_dir
-            .join(format!("{}-{}.class", class_name, cache_key));
-
-        // Check if class file exists and is newer than source
-        if !class_file.exists() || self.is_source_newer(template_path, &class_file).await? {
-            // Compile Java template
-            self.compile_template(template_path, &class_file).await?;
-        }
+        let class_dir = self.ensure_compiled(template_path).await?;
+        let class_name = qualified_class_name(template_path);
 
         // Build environment variables
-        let env_vars = build_env_vars(target, context)?;
+        let mut env_vars = build_env_vars(target, context)?;
+        let workdir = prepare_workdir(&mut env_vars, context, template_path, target)?;
 
         // Execute Java class (no arguments, uses environment variables)
-        let stdout = execute_command(
-            &self.java_path,
-            &[
-                "-cp".to_string(),
-                self.cache_dir.to_string_lossy().to_string(),
-                class_name.to_string(),
-            ],
-            &env_vars,
-        )
-        .await?;
+        let launcher = java_launcher(&self.runtime.program().await?);
+        let mut args = self.runtime.run_flags().to_vec();
+        args.extend([
+            "-cp".to_string(),
+            class_dir.to_string_lossy().to_string(),
+            class_name,
+        ]);
+        let stdout = execute_command(&launcher, &args, &env_vars).await?;
 
         // Parse findings from JSON output
         let template_id = template_path
@@ -81,23 +94,20 @@ impl JavaEngine {
             .unwrap_or("unknown")
             .to_string();
 
-        parse_findings(&stdout, target, &template_id)
+        let mut findings = parse_findings(&stdout, target, &template_id)?;
+        resolve_artifacts(&mut findings, workdir.as_deref());
+        Ok(findings)
     }
 
-    /// Compile Java template to class file
-    async fn compile_template(&self, source_path: &Path, _class_file: &Path) -> Result<()> {
-        if !check_tool_available("javac").await {
-            return Err(Error::Execution(
-                "Java compiler (javac) not found".to_string(),
-            ));
-        }
+    /// Compile Java template into `class_dir`
+    async fn compile_template(&self, source_path: &Path, class_dir: &Path) -> Result<()> {
+        let javac = self.runtime.program().await?;
 
-        let output = Command::new(&self.javac_path)
+        let output = ToolCommand::new(&javac)
             .arg("-d")
-            .arg(&self.cache_dir)
+            .arg(class_dir)
+            .args(self.runtime.compile_flags())
             .arg(source_path)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
             .output()
             .await
             .map_err(|e| Error::Execution(format!("Failed to compile Java template: {}", e)))?;
@@ -122,12 +132,44 @@ impl JavaEngine {
     }
 }
 
+/// `java` from the same JDK as `javac`, falling back to the one on `PATH`
+fn java_launcher(javac: &str) -> String {
+    Path::new(javac)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| dir.join("java"))
+        .filter(|java| java.is_file())
+        .map(|java| java.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "java".to_string())
+}
+
+/// Fully qualified main class name of a template (`package.Class` or `Class`)
+fn qualified_class_name(template_path: &Path) -> String {
+    let class_name = template_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Template");
+
+    let source = crate::template::read_source(template_path)
+        .map(|source| source.text)
+        .unwrap_or_default();
+    let package = source.lines().map(str::trim).find_map(|line| {
+        line.strip_prefix("package ")
+            .and_then(|rest| rest.strip_suffix(';'))
+            .map(str::trim)
+    });
+
+    match package {
+        Some(package) => format!("{}.{}", package, class_name),
+        None => class_name.to_string(),
+    }
+}
+
 impl Clone for JavaEngine {
     fn clone(&self) -> Self {
         Self {
             name: self.name.clone(),
-            javac_path: self.javac_path.clone(),
-            java_path: self.java_path.clone(),
+            runtime: self.runtime.clone(),
             cache_dir: self.cache_dir.clone(),
         }
     }
@@ -154,6 +196,10 @@ impl Template for JavaTemplate {
             .await
     }
 
+    async fn prepare(&self) -> Result<()> {
+        self.engine.ensure_compiled(&self.path).await.map(|_| ())
+    }
+
     fn validate(&self) -> Result<()> {
         if !self.path.exists() {
             return Err(Error::Io(std::io::Error::new(
@@ -207,6 +253,10 @@ impl TemplateEngine for JavaEngine {
         &self.name
     }
 
+    fn cost_hint(&self) -> ExecutionCost {
+        ExecutionCost::Heavy
+    }
+
     fn supports_file(&self, path: &Path) -> bool {
         path.extension()
             .and_then(|s| s.to_str())
@@ -231,4 +281,23 @@ mod tests {
         let engine = JavaEngine::new();
         assert_eq!(engine.name(), "java");
     }
+
+    #[test]
+    fn test_qualified_class_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let packaged = dir.path().join("MyCheck.java");
+        std::fs::write(
+            &packaged,
+            "package certxgen.templates;\n\npublic class MyCheck {}\n",
+        )
+        .unwrap();
+        let plain = dir.path().join("Plain.java");
+        std::fs::write(&plain, "public class Plain {}\n").unwrap();
+
+        assert_eq!(
+            qualified_class_name(&packaged),
+            "certxgen.templates.MyCheck"
+        );
+        assert_eq!(qualified_class_name(&plain), "Plain");
+    }
 }
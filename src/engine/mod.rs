@@ -1,5 +1,6 @@
 //! Template engine implementations
 
+pub mod browser;
 pub mod c;
 pub mod common;
 pub mod cpp;
@@ -8,13 +9,17 @@ pub mod java;
 pub mod javascript;
 pub mod perl;
 pub mod php;
+pub mod process;
 pub mod python;
+pub mod rhai;
 pub mod ruby;
+pub mod runtime;
 pub mod rust;
 pub mod shell;
 pub mod yaml;
 
 // Re-exports
+pub use browser::{HeadlessBrowserEngine, ScreenshotCapture};
 pub use c::CEngine;
 pub use cpp::CppEngine;
 pub use go::GoEngine;
@@ -23,6 +28,7 @@ pub use javascript::JavaScriptEngine;
 pub use perl::PerlEngine;
 pub use php::PhpEngine;
 pub use python::PythonEngine;
+pub use rhai::RhaiEngine;
 pub use ruby::RubyEngine;
 pub use rust::RustEngine;
 pub use shell::ShellEngine;
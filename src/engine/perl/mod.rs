@@ -1,9 +1,15 @@
 //! Perl template engine implementation
 
-use crate::engine::common::{build_env_vars, create_metadata, execute_command, parse_findings};
+use crate::config::EngineRuntimeConfig;
+use crate::engine::common::{
+    build_env_vars, create_metadata, execute_command, merge_sandbox_env, parse_findings,
+    prepare_workdir,
+};
+use crate::engine::runtime::EngineRuntime;
 use crate::error::{Error, Result};
-use crate::template::{Template, TemplateEngine};
+use crate::template::{ExecutionCost, Template, TemplateEngine};
 use crate::types::{Context, Finding, Protocol, Target, TemplateLanguage};
+use crate::workspace::resolve_artifacts;
 use async_trait::async_trait;
 use std::path::Path;
 use std::path::PathBuf;
@@ -12,7 +18,7 @@ use std::path::PathBuf;
 #[derive(Debug)]
 pub struct PerlEngine {
     name: String,
-    perl_path: String,
+    runtime: EngineRuntime,
 }
 
 impl PerlEngine {
@@ -20,10 +26,16 @@ impl PerlEngine {
     pub fn new() -> Self {
         Self {
             name: "perl".to_string(),
-            perl_path: "perl".to_string(),
+            runtime: EngineRuntime::new("perl"),
         }
     }
 
+    /// Use an `engines.perl` configuration entry instead of the defaults
+    pub fn with_runtime_config(mut self, config: EngineRuntimeConfig) -> Self {
+        self.runtime = self.runtime.with_config(config);
+        self
+    }
+
     /// Execute Perl template
     async fn execute_perl_template(
         &self,
@@ -33,16 +45,16 @@ impl PerlEngine {
     ) -> Result<Vec<Finding>> {
         tracing::debug!("Perl engine executing template: {:?}", template_path);
 
-        // Build environment variables
-        let env_vars = build_env_vars(target, context)?;
+        // Build environment variables, with sandbox-installed libraries
+        let mut env_vars = build_env_vars(target, context)?;
+        let workdir = prepare_workdir(&mut env_vars, context, template_path, target)?;
+        merge_sandbox_env(&mut env_vars, crate::sandbox::active_local_sandbox());
 
         // Execute Perl script
-        let stdout = execute_command(
-            &self.perl_path,
-            &[template_path.to_string_lossy().to_string()],
-            &env_vars,
-        )
-        .await?;
+        let program = self.runtime.program().await?;
+        let mut args = self.runtime.run_flags().to_vec();
+        args.push(template_path.to_string_lossy().to_string());
+        let stdout = execute_command(&program, &args, &env_vars).await?;
 
         // Parse findings from JSON output
         let template_id = template_path
@@ -51,7 +63,9 @@ impl PerlEngine {
             .unwrap_or("unknown")
             .to_string();
 
-        parse_findings(&stdout, target, &template_id)
+        let mut findings = parse_findings(&stdout, target, &template_id)?;
+        resolve_artifacts(&mut findings, workdir.as_deref());
+        Ok(findings)
     }
 }
 
@@ -59,7 +73,7 @@ impl Clone for PerlEngine {
     fn clone(&self) -> Self {
         Self {
             name: self.name.clone(),
-            perl_path: self.perl_path.clone(),
+            runtime: self.runtime.clone(),
         }
     }
 }
@@ -138,6 +152,10 @@ impl TemplateEngine for PerlEngine {
         &self.name
     }
 
+    fn cost_hint(&self) -> ExecutionCost {
+        ExecutionCost::Medium
+    }
+
     fn supports_file(&self, path: &Path) -> bool {
         path.extension()
             .and_then(|s| s.to_str())
@@ -162,4 +180,57 @@ mod tests {
         let engine = PerlEngine::new();
         assert_eq!(engine.name(), "perl");
     }
+
+    /// Imports a module installed into a temp sandbox; skipped without perl
+    #[tokio::test]
+    async fn test_template_imports_sandbox_module() {
+        use crate::sandbox::{Sandbox, SandboxConfig};
+
+        if !crate::engine::common::check_tool_available("perl").await {
+            eprintln!("skipping: perl is not available");
+            return;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = SandboxConfig {
+            root_dir: dir.path().to_path_buf(),
+            enable_python: false,
+            enable_javascript: false,
+            enable_ruby: false,
+            enable_perl: true,
+            enable_php: false,
+            enable_rust: false,
+            enable_go: false,
+            enable_java: false,
+            auto_init: false,
+        };
+        config.save(&dir.path().join("config.yaml")).unwrap();
+        let module_dir = dir.path().join("perl/local/lib/perl5");
+        std::fs::create_dir_all(&module_dir).unwrap();
+        std::fs::write(
+            module_dir.join("CxgTrivial.pm"),
+            "package CxgTrivial;\nsub title { return \"Imported from sandbox\" }\n1;\n",
+        )
+        .unwrap();
+
+        let script = dir.path().join("sandbox_import.pl");
+        std::fs::write(
+            &script,
+            "use CxgTrivial;\n\
+             print '[{\"title\": \"' . CxgTrivial::title() . '\", \"severity\": \"info\"}]';\n",
+        )
+        .unwrap();
+
+        let target = Target::new("example.com", Protocol::Http);
+        let args = vec![script.to_string_lossy().to_string()];
+        let mut env_vars = build_env_vars(&target, &Context::default()).unwrap();
+        assert!(execute_command("perl", &args, &env_vars).await.is_err());
+
+        let sandbox = Sandbox::open(dir.path()).unwrap();
+        merge_sandbox_env(&mut env_vars, Some(&sandbox));
+        let stdout = execute_command("perl", &args, &env_vars).await.unwrap();
+        let findings = parse_findings(&stdout, &target, "sandbox_import").unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].title, "Imported from sandbox");
+    }
 }
@@ -1,9 +1,15 @@
 //! PHP template engine implementation
 
-use crate::engine::common::{build_env_vars, create_metadata, execute_command, parse_findings};
+use crate::config::EngineRuntimeConfig;
+use crate::engine::common::{
+    build_env_vars, create_metadata, execute_command, merge_sandbox_env, parse_findings,
+    prepare_workdir,
+};
+use crate::engine::runtime::EngineRuntime;
 use crate::error::{Error, Result};
-use crate::template::{Template, TemplateEngine};
+use crate::template::{ExecutionCost, Template, TemplateEngine};
 use crate::types::{Context, Finding, Protocol, Target, TemplateLanguage};
+use crate::workspace::resolve_artifacts;
 use async_trait::async_trait;
 use std::path::Path;
 use std::path::PathBuf;
@@ -12,7 +18,7 @@ use std::path::PathBuf;
 #[derive(Debug)]
 pub struct PhpEngine {
     name: String,
-    php_path: String,
+    runtime: EngineRuntime,
 }
 
 impl PhpEngine {
@@ -20,10 +26,16 @@ impl PhpEngine {
     pub fn new() -> Self {
         Self {
             name: "php".to_string(),
-            php_path: "php".to_string(),
+            runtime: EngineRuntime::new("php"),
         }
     }
 
+    /// Use an `engines.php` configuration entry instead of the defaults
+    pub fn with_runtime_config(mut self, config: EngineRuntimeConfig) -> Self {
+        self.runtime = self.runtime.with_config(config);
+        self
+    }
+
     /// Execute PHP template
     async fn execute_php_template(
         &self,
@@ -33,16 +45,16 @@ impl PhpEngine {
     ) -> Result<Vec<Finding>> {
         tracing::debug!("PHP engine executing template: {:?}", template_path);
 
-        // Build environment variables
-        let env_vars = build_env_vars(target, context)?;
+        // Build environment variables, with sandbox-installed libraries
+        let mut env_vars = build_env_vars(target, context)?;
+        let workdir = prepare_workdir(&mut env_vars, context, template_path, target)?;
+        merge_sandbox_env(&mut env_vars, crate::sandbox::active_local_sandbox());
 
         // Execute PHP script
-        let stdout = execute_command(
-            &self.php_path,
-            &[template_path.to_string_lossy().to_string()],
-            &env_vars,
-        )
-        .await?;
+        let program = self.runtime.program().await?;
+        let mut args = self.runtime.run_flags().to_vec();
+        args.push(template_path.to_string_lossy().to_string());
+        let stdout = execute_command(&program, &args, &env_vars).await?;
 
         // Parse findings from JSON output
         let template_id = template_path
@@ -51,7 +63,9 @@ impl PhpEngine {
             .unwrap_or("unknown")
             .to_string();
 
-        parse_findings(&stdout, target, &template_id)
+        let mut findings = parse_findings(&stdout, target, &template_id)?;
+        resolve_artifacts(&mut findings, workdir.as_deref());
+        Ok(findings)
     }
 }
 
@@ -59,7 +73,7 @@ impl Clone for PhpEngine {
     fn clone(&self) -> Self {
         Self {
             name: self.name.clone(),
-            php_path: self.php_path.clone(),
+            runtime: self.runtime.clone(),
         }
     }
 }
@@ -138,6 +152,10 @@ impl TemplateEngine for PhpEngine {
         &self.name
     }
 
+    fn cost_hint(&self) -> ExecutionCost {
+        ExecutionCost::Medium
+    }
+
     fn supports_file(&self, path: &Path) -> bool {
         path.extension()
             .and_then(|s| s.to_str())
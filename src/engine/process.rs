@@ -0,0 +1,111 @@
+//! Process tree handling for template runtimes
+//!
+//! A template that exceeds its timeout is dropped mid-run. Killing only the
+//! interpreter would leave anything the script spawned running, so commands
+//! are started in their own process group (Unix) or console process group
+//! (Windows) and the whole tree is killed when the run is abandoned.
+
+use tokio::process::Command;
+
+/// Start `command` in its own process group so its tree can be killed as a unit
+pub fn isolate(command: &mut Command) {
+    #[cfg(unix)]
+    {
+        command.process_group(0);
+    }
+
+    #[cfg(windows)]
+    {
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    }
+}
+
+/// Kill the process tree rooted at `pid`, started through [`isolate`]
+pub fn kill_tree(pid: u32) {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{killpg, Signal};
+        use nix::unistd::Pid;
+
+        if let Err(e) = killpg(Pid::from_raw(pid as i32), Signal::SIGKILL) {
+            tracing::debug!("Failed to kill process group {}: {}", pid, e);
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        let status = std::process::Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status();
+        if let Err(e) = status {
+            tracing::debug!("Failed to kill process tree {}: {}", pid, e);
+        }
+    }
+}
+
+/// Kills a process tree when dropped, unless the process finished first
+#[derive(Debug)]
+pub struct TreeGuard {
+    pid: Option<u32>,
+}
+
+impl TreeGuard {
+    /// Guard the tree rooted at `pid` (`None` guards nothing)
+    pub fn new(pid: Option<u32>) -> Self {
+        Self { pid }
+    }
+
+    /// The process exited normally; leave its tree alone
+    pub fn disarm(mut self) {
+        self.pid = None;
+    }
+}
+
+impl Drop for TreeGuard {
+    fn drop(&mut self) {
+        if let Some(pid) = self.pid.take() {
+            kill_tree(pid);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_dropped_guard_kills_child_processes() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("marker");
+        let mut command = Command::new("sh");
+        // The grandchild would create the marker if it survived the kill
+        command
+            .arg("-c")
+            .arg(format!("(sleep 1; touch '{}') & wait", marker.display()));
+        isolate(&mut command);
+
+        let mut child = command.spawn().unwrap();
+        drop(TreeGuard::new(child.id()));
+        child.wait().await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!marker.exists());
+    }
+
+    #[tokio::test]
+    async fn test_disarmed_guard_leaves_process_alone() {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg("exit 3");
+        isolate(&mut command);
+
+        let mut child = command.spawn().unwrap();
+        let guard = TreeGuard::new(child.id());
+        let status = child.wait().await.unwrap();
+        guard.disarm();
+        assert_eq!(status.code(), Some(3));
+    }
+}
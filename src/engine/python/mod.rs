@@ -1,9 +1,15 @@
 //! Python template engine implementation
 
-use crate::engine::common::{build_env_vars, create_metadata, execute_command, parse_findings};
+use crate::config::EngineRuntimeConfig;
+use crate::engine::common::{
+    build_env_vars, create_metadata, execute_command, merge_sandbox_env, parse_findings,
+    prepare_workdir,
+};
+use crate::engine::runtime::EngineRuntime;
 use crate::error::{Error, Result};
-use crate::template::{Template, TemplateEngine};
+use crate::template::{ExecutionCost, Template, TemplateEngine};
 use crate::types::{Context, Finding, Protocol, Target, TemplateLanguage};
+use crate::workspace::resolve_artifacts;
 use async_trait::async_trait;
 use std::path::Path;
 use std::path::PathBuf;
@@ -12,7 +18,7 @@ use std::path::PathBuf;
 #[derive(Debug)]
 pub struct PythonEngine {
     name: String,
-    python_path: String,
+    runtime: EngineRuntime,
 }
 
 impl PythonEngine {
@@ -20,10 +26,16 @@ impl PythonEngine {
     pub fn new() -> Self {
         Self {
             name: "python".to_string(),
-            python_path: "python3".to_string(), // Use python3 by default
+            runtime: EngineRuntime::new("python"),
         }
     }
 
+    /// Use an `engines.python` configuration entry instead of the defaults
+    pub fn with_runtime_config(mut self, config: EngineRuntimeConfig) -> Self {
+        self.runtime = self.runtime.with_config(config);
+        self
+    }
+
     /// Execute Python template and parse results
     async fn execute_python_template(
         &self,
@@ -33,16 +45,16 @@ impl PythonEngine {
     ) -> Result<Vec<Finding>> {
         tracing::debug!("Python engine executing template: {:?}", template_path);
 
-        // Build environment variables
-        let env_vars = build_env_vars(target, context)?;
+        // Build environment variables, with sandbox-installed libraries
+        let mut env_vars = build_env_vars(target, context)?;
+        let workdir = prepare_workdir(&mut env_vars, context, template_path, target)?;
+        merge_sandbox_env(&mut env_vars, crate::sandbox::active_local_sandbox());
 
         // Execute Python script
-        let stdout = execute_command(
-            &self.python_path,
-            &[template_path.to_string_lossy().to_string()],
-            &env_vars,
-        )
-        .await?;
+        let program = self.runtime.program().await?;
+        let mut args = self.runtime.run_flags().to_vec();
+        args.push(template_path.to_string_lossy().to_string());
+        let stdout = execute_command(&program, &args, &env_vars).await?;
 
         // Parse findings from JSON output
         let template_id = template_path
@@ -51,7 +63,9 @@ impl PythonEngine {
             .unwrap_or("unknown")
             .to_string();
 
-        parse_findings(&stdout, target, &template_id)
+        let mut findings = parse_findings(&stdout, target, &template_id)?;
+        resolve_artifacts(&mut findings, workdir.as_deref());
+        Ok(findings)
     }
 }
 
@@ -59,7 +73,7 @@ impl Clone for PythonEngine {
     fn clone(&self) -> Self {
         Self {
             name: self.name.clone(),
-            python_path: self.python_path.clone(),
+            runtime: self.runtime.clone(),
         }
     }
 }
@@ -138,6 +152,10 @@ impl TemplateEngine for PythonEngine {
         &self.name
     }
 
+    fn cost_hint(&self) -> ExecutionCost {
+        ExecutionCost::Medium
+    }
+
     fn supports_file(&self, path: &Path) -> bool {
         path.extension()
             .and_then(|s| s.to_str())
@@ -162,4 +180,89 @@ mod tests {
         let engine = PythonEngine::new();
         assert_eq!(engine.name(), "python");
     }
+
+    #[tokio::test]
+    async fn test_load_template_reads_front_matter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("redis_check.py");
+        std::fs::write(
+            &path,
+            "#!/usr/bin/env python3\n\
+             # cert-x-gen:\n\
+             #   id: redis-unauthenticated\n\
+             #   severity: high\n\
+             #   tags: [redis, database]\n\
+             #   description: Detects Redis without authentication\n\
+             # Regular comment, not metadata\n\
+             import os\n",
+        )
+        .unwrap();
+
+        let template = PythonEngine::new().load_template(&path).await.unwrap();
+        let metadata = template.metadata();
+        assert_eq!(metadata.id, "redis-unauthenticated");
+        assert_eq!(metadata.severity, crate::types::Severity::High);
+        assert!(metadata.tags.contains(&"redis".to_string()));
+        assert!(metadata.tags.contains(&"python".to_string()));
+    }
+
+    /// Runs a template inside a throwaway container (pulls `python:3-alpine`)
+    #[tokio::test]
+    #[ignore] // Only run with --ignored when a Docker daemon is available
+    async fn test_execute_in_docker_sandbox() {
+        use crate::engine::common::ToolCommand;
+        use crate::sandbox::docker::ExecRoute;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("sandbox_check.py");
+        std::fs::write(
+            &script,
+            "import json, os\n\
+             print(json.dumps([{\"title\": \"Sandboxed \" + os.environ[\"CERT_X_GEN_TARGET_HOST\"], \
+             \"severity\": \"info\", \"description\": \"ran in container\"}]))\n",
+        )
+        .unwrap();
+
+        let container = format!("cxg-test-{}", uuid::Uuid::new_v4().simple());
+        let mount = format!("{}:/workspace/t", dir.path().display());
+        let started = std::process::Command::new("docker")
+            .args(["run", "-d", "--rm", "--name", &container, "-v", &mount])
+            .args(["python:3-alpine", "sleep", "120"])
+            .output();
+        assert!(
+            started.is_ok_and(|o| o.status.success()),
+            "could not start python:3-alpine container"
+        );
+
+        let route = ExecRoute::with_mounts(
+            &container,
+            [(dir.path().to_path_buf(), PathBuf::from("/workspace/t"))],
+        );
+        let target = Target::new("example.com", Protocol::Http);
+        let mut cmd = ToolCommand::new("python3");
+        cmd.arg(&script);
+        for (key, value) in build_env_vars(&target, &Context::default()).unwrap() {
+            cmd.env(key, value);
+        }
+        let output = cmd.output_via(Some(&route)).await;
+
+        let _ = std::process::Command::new("docker")
+            .args(["rm", "-f", &container])
+            .output();
+
+        let output = output.unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let findings = parse_findings(
+            &String::from_utf8_lossy(&output.stdout),
+            &target,
+            "sandbox_check",
+        )
+        .unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].title, "Sandboxed example.com");
+    }
 }
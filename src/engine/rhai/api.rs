@@ -0,0 +1,356 @@
+//! Functions exposed to `.rhai` templates
+//!
+//! | Function | Returns |
+//! |----------|---------|
+//! | `http_get(url)`, `http_get(url, headers)` | `#{status, headers, body, error}` |
+//! | `http_post(url, body)`, `http_post(url, body, headers)` | `#{status, headers, body, error}` |
+//! | `tcp_send(host, port, data)` | reply text (`""` when unreachable) |
+//! | `regex_match(text, pattern)` | `bool` |
+//! | `regex_extract(text, pattern)` | first capture group (or match) of every match |
+//! | `base64_encode(text)`, `base64_decode(text)` | `String` |
+//! | `emit_finding(#{title, severity, ...})` | records a finding |
+//!
+//! URLs starting with `/` are relative to the target. Failed requests return
+//! `status` 0 with the reason in `error` rather than stopping the script.
+
+use crate::metrics::profiler::{self, ExecutionScope};
+use crate::metrics::requests::{self, BudgetScope};
+use crate::network::{connect_via_proxy, rate_limit, read_response, trace, NetworkClient};
+use crate::types::{Evidence, Finding, Protocol, Severity, Target, TemplateMetadata};
+use base64::Engine as _;
+use parking_lot::Mutex;
+use rhai::{Array, Dynamic, EvalAltResult, Map};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Longest response body handed to a script (bytes)
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Longest `tcp_send` reply (bytes)
+const MAX_TCP_REPLY: usize = 64 * 1024;
+
+/// How long `tcp_send` waits for more data after the first chunk
+const TCP_IDLE_TIMEOUT: Duration = Duration::from_millis(500);
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// State shared between a running script and the host
+#[derive(Debug)]
+pub struct ScriptHost {
+    client: Option<Arc<NetworkClient>>,
+    handle: tokio::runtime::Handle,
+    profile: ExecutionScope,
+    budget: BudgetScope,
+    scan_id: Option<String>,
+    template_id: String,
+    severity: Severity,
+    description: String,
+    confidence: u8,
+    target: Target,
+    io_timeout: Duration,
+    findings: Mutex<Vec<Finding>>,
+}
+
+impl ScriptHost {
+    /// Host for one run of a template against a target
+    pub fn new(
+        client: Option<Arc<NetworkClient>>,
+        handle: tokio::runtime::Handle,
+        metadata: &TemplateMetadata,
+        target: &Target,
+    ) -> Self {
+        let io_timeout = client
+            .as_ref()
+            .map(|c| Duration::from_secs(c.config().network.timeout_secs))
+            .unwrap_or(Duration::from_secs(10));
+        Self {
+            client,
+            handle,
+            profile: profiler::current_scope(),
+            budget: requests::current_budget(),
+            scan_id: trace::current_scan(),
+            template_id: metadata.id.clone(),
+            severity: metadata.severity,
+            description: metadata.description.clone(),
+            confidence: metadata.confidence.unwrap_or(90),
+            target: target.clone(),
+            io_timeout,
+            findings: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Base URL of the target (`http://host:port` for non-HTTP targets)
+    pub fn base_url(&self) -> String {
+        match self.target.protocol {
+            Protocol::Http | Protocol::Https => self.target.url(),
+            _ => match self.target.port {
+                Some(443) => format!("https://{}", self.target.address),
+                Some(port) => format!("http://{}:{}", self.target.address, port),
+                None => format!("http://{}", self.target.address),
+            },
+        }
+    }
+
+    /// Findings emitted so far
+    pub fn take_findings(&self) -> Vec<Finding> {
+        std::mem::take(&mut self.findings.lock())
+    }
+
+    /// Run a future from the script thread, attributing requests to the template and scan
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let future = rate_limit::with_template(&self.template_id, future);
+        let future = self.budget.clone().scope(future);
+        let future = self.profile.clone().scope(future);
+        match &self.scan_id {
+            Some(scan_id) => self.handle.block_on(trace::with_scan(scan_id, future)),
+            None => self.handle.block_on(future),
+        }
+    }
+
+    fn resolve_url(&self, url: &str) -> String {
+        if url.starts_with('/') {
+            if matches!(self.target.protocol, Protocol::Http | Protocol::Https) {
+                self.target.url_with_path(url)
+            } else {
+                format!("{}{}", self.base_url(), url)
+            }
+        } else {
+            url.to_string()
+        }
+    }
+
+    fn http(&self, url: &str, body: Option<String>, headers: Map) -> Map {
+        let Some(client) = &self.client else {
+            return http_error("network access is not available");
+        };
+        let url = self.resolve_url(url);
+        let mut headers: HashMap<String, String> = headers
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        self.target.merge_custom_headers(&mut headers);
+
+        let result = self.block_on(async {
+            let response = match body {
+                Some(body) => client.post_with_headers(&url, body, headers).await?,
+                None => client.get_with_headers(&url, headers).await?,
+            };
+            let response = read_response(response, Duration::ZERO).await?;
+            let mut response_headers = Map::new();
+            for (name, value) in &response.headers {
+                response_headers
+                    .entry(name.as_str().into())
+                    .and_modify(|existing: &mut Dynamic| {
+                        *existing = format!("{}, {}", existing, value).into();
+                    })
+                    .or_insert_with(|| value.clone().into());
+            }
+            Ok::<_, crate::error::Error>((
+                response.status,
+                response_headers,
+                response.body_string(),
+            ))
+        });
+
+        match result {
+            Ok((status, headers, mut body)) => {
+                truncate(&mut body, MAX_BODY_SIZE);
+                let mut map = Map::new();
+                map.insert("status".into(), Dynamic::from_int(i64::from(status)));
+                map.insert("headers".into(), headers.into());
+                map.insert("body".into(), body.into());
+                map.insert("error".into(), "".into());
+                map
+            }
+            Err(e) => {
+                tracing::debug!("{}: request to {} failed: {}", self.template_id, url, e);
+                http_error(&e.to_string())
+            }
+        }
+    }
+
+    fn tcp_send(&self, host: &str, port: i64, data: &str) -> ScriptResult<String> {
+        let port = u16::try_from(port).map_err(|_| format!("invalid port {}", port))?;
+        let reply = self.block_on(async {
+            let mut proxy = None;
+            if let Some(client) = &self.client {
+                client.throttle(host).await.ok()?;
+                proxy = client.config().network.proxy.as_ref().map(|p| p.expose());
+            }
+            let connect = connect_via_proxy(host, port, proxy.map(String::as_str));
+            let mut stream = tokio::time::timeout(self.io_timeout, connect)
+                .await
+                .ok()?
+                .ok()?;
+            stream.write_all(data.as_bytes()).await.ok()?;
+
+            let mut reply = Vec::new();
+            let mut buf = [0u8; 4096];
+            let mut wait = self.io_timeout;
+            while reply.len() < MAX_TCP_REPLY {
+                match tokio::time::timeout(wait, stream.read(&mut buf)).await {
+                    Ok(Ok(n)) if n > 0 => reply.extend_from_slice(&buf[..n]),
+                    _ => break,
+                }
+                wait = TCP_IDLE_TIMEOUT;
+            }
+            reply.truncate(MAX_TCP_REPLY);
+            Some(String::from_utf8_lossy(&reply).into_owned())
+        });
+        if reply.is_none() {
+            tracing::debug!("{}: tcp_send to {}:{} failed", self.template_id, host, port);
+        }
+        Ok(reply.unwrap_or_default())
+    }
+
+    fn emit_finding(&self, map: Map) -> ScriptResult<()> {
+        let spec: ScriptFinding =
+            rhai::serde::from_dynamic(&map.into()).map_err(|e| format!("emit_finding: {}", e))?;
+        let severity = match &spec.severity {
+            Some(s) => parse_severity(s)
+                .ok_or_else(|| format!("emit_finding: unknown severity '{}'", s))?,
+            None => self.severity,
+        };
+
+        let mut evidence = Evidence::new();
+        evidence.request = spec.request;
+        evidence.response = spec.response;
+        evidence.matched_patterns = spec.matched;
+        evidence.data = spec.evidence;
+
+        let mut finding = Finding::new(
+            self.target.address.clone(),
+            self.template_id.clone(),
+            severity,
+            spec.title,
+            spec.description.unwrap_or_else(|| self.description.clone()),
+        )
+        .with_confidence(spec.confidence.unwrap_or(self.confidence))
+        .with_evidence(evidence);
+        finding.cwe_ids = spec.cwe;
+        finding.references = spec.references;
+        finding.remediation = spec.remediation;
+        finding.cvss_score = spec.cvss.map(|score| score as f32);
+
+        self.findings.lock().push(finding);
+        Ok(())
+    }
+}
+
+/// Map passed to `emit_finding`
+#[derive(Debug, Deserialize)]
+struct ScriptFinding {
+    title: String,
+    #[serde(default)]
+    severity: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    confidence: Option<u8>,
+    #[serde(default)]
+    request: Option<String>,
+    #[serde(default)]
+    response: Option<String>,
+    #[serde(default)]
+    matched: Vec<String>,
+    #[serde(default)]
+    evidence: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    cwe: Vec<String>,
+    #[serde(default)]
+    references: Vec<String>,
+    #[serde(default)]
+    remediation: Option<String>,
+    #[serde(default)]
+    cvss: Option<f64>,
+}
+
+/// Register the API functions on an interpreter
+pub fn register(engine: &mut rhai::Engine, host: &Arc<ScriptHost>) {
+    let h = host.clone();
+    engine.register_fn("http_get", move |url: &str| h.http(url, None, Map::new()));
+    let h = host.clone();
+    engine.register_fn("http_get", move |url: &str, headers: Map| {
+        h.http(url, None, headers)
+    });
+    let h = host.clone();
+    engine.register_fn("http_post", move |url: &str, body: &str| {
+        h.http(url, Some(body.to_string()), Map::new())
+    });
+    let h = host.clone();
+    engine.register_fn("http_post", move |url: &str, body: &str, headers: Map| {
+        h.http(url, Some(body.to_string()), headers)
+    });
+    let h = host.clone();
+    engine.register_fn("tcp_send", move |host: &str, port: i64, data: &str| {
+        h.tcp_send(host, port, data)
+    });
+    let h = host.clone();
+    engine.register_fn("emit_finding", move |map: Map| h.emit_finding(map));
+
+    engine.register_fn("regex_match", regex_match);
+    engine.register_fn("regex_extract", regex_extract);
+    engine.register_fn("base64_encode", |text: &str| {
+        base64::engine::general_purpose::STANDARD.encode(text)
+    });
+    engine.register_fn("base64_decode", base64_decode);
+}
+
+fn compile_regex(pattern: &str) -> ScriptResult<regex::Regex> {
+    regex::Regex::new(pattern).map_err(|e| format!("invalid regex '{}': {}", pattern, e).into())
+}
+
+fn regex_match(text: &str, pattern: &str) -> ScriptResult<bool> {
+    Ok(compile_regex(pattern)?.is_match(text))
+}
+
+fn regex_extract(text: &str, pattern: &str) -> ScriptResult<Array> {
+    let re = compile_regex(pattern)?;
+    Ok(re
+        .captures_iter(text)
+        .filter_map(|caps| caps.get(1).or_else(|| caps.get(0)))
+        .map(|m| m.as_str().to_string().into())
+        .collect())
+}
+
+fn base64_decode(text: &str) -> ScriptResult<String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(text.trim())
+        .map_err(|e| format!("base64_decode: {}", e))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn http_error(reason: &str) -> Map {
+    let mut map = Map::new();
+    map.insert("status".into(), Dynamic::from_int(0));
+    map.insert("headers".into(), Map::new().into());
+    map.insert("body".into(), "".into());
+    map.insert("error".into(), reason.into());
+    map
+}
+
+fn parse_severity(severity: &str) -> Option<Severity> {
+    match severity.to_lowercase().as_str() {
+        "critical" => Some(Severity::Critical),
+        "high" => Some(Severity::High),
+        "medium" => Some(Severity::Medium),
+        "low" => Some(Severity::Low),
+        "info" | "informational" => Some(Severity::Info),
+        _ => None,
+    }
+}
+
+/// Cut `text` to at most `max` bytes on a character boundary
+fn truncate(text: &mut String, max: usize) {
+    if text.len() > max {
+        let mut end = max;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+}
@@ -0,0 +1,411 @@
+//! Embedded Rhai template engine
+//!
+//! `.rhai` templates run in-process in a sandboxed interpreter, so they need
+//! no external runtime. Scripts reach the network only through the functions
+//! in [`api`], which go through the shared [`NetworkClient`] (rate limits,
+//! proxy, sessions). Every run is bounded by an operation limit and the
+//! template timeout.
+
+use crate::engine::common::create_metadata;
+use crate::error::{Error, Result};
+use crate::network::NetworkClient;
+use crate::template::{Template, TemplateEngine};
+use crate::types::{Context, Finding, Protocol, Target, TemplateLanguage, TemplateMetadata};
+use async_trait::async_trait;
+use rhai::{Dynamic, EvalAltResult, Map, Scope, AST};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub mod api;
+
+use api::ScriptHost;
+
+/// Default limit on interpreter operations per template run
+pub const DEFAULT_MAX_OPERATIONS: u64 = 5_000_000;
+
+/// Maximum function call depth
+const MAX_CALL_LEVELS: usize = 32;
+
+/// Maximum length of a script string (bytes)
+const MAX_STRING_SIZE: usize = 4 * 1024 * 1024;
+
+/// Maximum number of elements in a script array or map
+const MAX_COLLECTION_SIZE: usize = 100_000;
+
+/// Rhai template engine
+#[derive(Debug, Clone)]
+pub struct RhaiEngine {
+    name: String,
+    network_client: Option<Arc<NetworkClient>>,
+    timeout: Duration,
+    max_operations: u64,
+}
+
+impl RhaiEngine {
+    /// Create a new Rhai engine
+    pub fn new() -> Self {
+        Self {
+            name: "rhai".to_string(),
+            network_client: None,
+            timeout: Duration::from_secs(30),
+            max_operations: DEFAULT_MAX_OPERATIONS,
+        }
+    }
+
+    /// Set the network client used by `http_get`, `http_post` and `tcp_send`
+    pub fn with_network_client(mut self, client: Arc<NetworkClient>) -> Self {
+        self.network_client = Some(client);
+        self
+    }
+
+    /// Stop scripts that run longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Stop scripts after `max_operations` interpreter operations (0 = unlimited)
+    pub fn with_max_operations(mut self, max_operations: u64) -> Self {
+        self.max_operations = max_operations;
+        self
+    }
+
+    /// Run a compiled template against a target on a blocking thread
+    async fn execute_rhai_template(
+        &self,
+        template: &RhaiTemplate,
+        target: &Target,
+        context: &Context,
+    ) -> Result<Vec<Finding>> {
+        let template_id = template.metadata.id.clone();
+        tracing::debug!("Rhai engine executing template: {}", template_id);
+
+        let host = Arc::new(ScriptHost::new(
+            self.network_client.clone(),
+            tokio::runtime::Handle::current(),
+            &template.metadata,
+            target,
+        ));
+        let ast = template.ast.clone();
+        let scope_target = target_map(target, host.base_url());
+        let scope_vars = variables_map(target, context);
+        let max_operations = self.max_operations;
+        let timeout = self.timeout;
+
+        let outcome = tokio::task::spawn_blocking(move || {
+            let engine = sandboxed_engine(max_operations, Instant::now() + timeout, &host);
+            let mut scope = Scope::new();
+            scope.push_constant("target", scope_target);
+            scope.push_constant("vars", scope_vars);
+            engine
+                .run_ast_with_scope(&mut scope, &ast)
+                .map(|()| host.take_findings())
+        })
+        .await
+        .map_err(|e| Error::TemplateExecution(format!("{}: {}", template_id, e)))?;
+
+        outcome.map_err(|e| match *e {
+            EvalAltResult::ErrorTerminated(..) => Error::Timeout {
+                duration: format!("{:?}", timeout),
+            },
+            EvalAltResult::ErrorTooManyOperations(..) => Error::TemplateExecution(format!(
+                "{}: exceeded the limit of {} operations",
+                template_id, max_operations
+            )),
+            e => Error::TemplateExecution(format!("{}: {}", template_id, e)),
+        })
+    }
+}
+
+impl Default for RhaiEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Interpreter with resource limits and the cert-x-gen API registered
+///
+/// Module imports are disabled and `print`/`debug` go to the log instead of
+/// stdout. The script is terminated once `deadline` passes.
+fn sandboxed_engine(
+    max_operations: u64,
+    deadline: Instant,
+    host: &Arc<ScriptHost>,
+) -> rhai::Engine {
+    let mut engine = rhai::Engine::new();
+    engine
+        .set_max_operations(max_operations)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new())
+        .disable_symbol("eval")
+        .on_print(|text| tracing::info!("[rhai] {}", text))
+        .on_debug(|text, _, position| tracing::debug!("[rhai] {} @ {}", text, position))
+        .on_progress(move |_| (Instant::now() >= deadline).then_some(Dynamic::UNIT));
+    api::register(&mut engine, host);
+    engine
+}
+
+/// Compile a script, reporting syntax errors with their line
+pub fn compile(source: &str) -> std::result::Result<AST, rhai::ParseError> {
+    let mut engine = rhai::Engine::new_raw();
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.compile(source)
+}
+
+/// `target` constant visible to scripts
+fn target_map(target: &Target, base_url: String) -> Map {
+    let mut map = Map::new();
+    map.insert("host".into(), target.address.clone().into());
+    map.insert(
+        "port".into(),
+        Dynamic::from_int(target.port.map(i64::from).unwrap_or(80)),
+    );
+    map.insert("protocol".into(), target.protocol.to_string().into());
+    map.insert("url".into(), base_url.into());
+    map
+}
+
+/// `vars` constant visible to scripts: target metadata and context variables
+fn variables_map(target: &Target, context: &Context) -> Map {
+    target
+        .metadata
+        .iter()
+        .chain(&context.variables)
+        .map(|(k, v)| (k.as_str().into(), v.clone().into()))
+        .collect()
+}
+
+/// Rhai template compiled at load time
+struct RhaiTemplate {
+    path: PathBuf,
+    engine: RhaiEngine,
+    metadata: TemplateMetadata,
+    ast: AST,
+}
+
+#[async_trait]
+impl Template for RhaiTemplate {
+    async fn execute(&self, target: &Target, context: &Context) -> Result<Vec<Finding>> {
+        self.engine
+            .execute_rhai_template(self, target, context)
+            .await
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.path.exists() {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Template not found: {:?}", self.path),
+            )));
+        }
+        Ok(())
+    }
+
+    fn metadata(&self) -> &TemplateMetadata {
+        &self.metadata
+    }
+
+    fn supported_protocols(&self) -> Vec<Protocol> {
+        vec![Protocol::Http, Protocol::Https, Protocol::Tcp]
+    }
+}
+
+#[async_trait]
+impl TemplateEngine for RhaiEngine {
+    async fn load_template(&self, path: &Path) -> Result<Box<dyn Template>> {
+        let source = crate::template::read_source(path)?.text;
+        let metadata = create_metadata(path, TemplateLanguage::Rhai);
+        let ast = compile(&source).map_err(|e| Error::TemplateValidation {
+            template: metadata.id.clone(),
+            reason: e.to_string(),
+        })?;
+
+        Ok(Box::new(RhaiTemplate {
+            path: path.to_path_buf(),
+            engine: self.clone(),
+            metadata,
+            ast,
+        }))
+    }
+
+    async fn validate_template(&self, template: &dyn Template) -> Result<()> {
+        template.validate()
+    }
+
+    async fn execute_template(
+        &self,
+        template: &dyn Template,
+        target: &Target,
+        context: &Context,
+    ) -> Result<Vec<Finding>> {
+        template.execute(target, context).await
+    }
+
+    fn supported_protocols(&self) -> Vec<Protocol> {
+        vec![Protocol::Http, Protocol::Https, Protocol::Tcp]
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn supports_file(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|s| s.to_str())
+            .map(|ext| ext == "rhai")
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::types::Severity;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn load(engine: &RhaiEngine, source: &str) -> Box<dyn Template> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("check.rhai");
+        std::fs::write(&path, source).unwrap();
+        engine.load_template(&path).await.unwrap()
+    }
+
+    async fn engine_with_client() -> RhaiEngine {
+        let client = NetworkClient::new(Arc::new(Config::default()))
+            .await
+            .unwrap();
+        RhaiEngine::new().with_network_client(Arc::new(client))
+    }
+
+    #[test]
+    fn test_rhai_engine_supports_file() {
+        let engine = RhaiEngine::new();
+        assert!(engine.supports_file(Path::new("check.rhai")));
+        assert!(!engine.supports_file(Path::new("check.rs")));
+        assert_eq!(engine.name(), "rhai");
+    }
+
+    #[tokio::test]
+    async fn test_http_get_and_emit_finding() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/server-status"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Server", "Apache/2.4.41")
+                    .set_body_string("<h1>Apache Server Status</h1> Server Version: 2.4.41"),
+            )
+            .mount(&server)
+            .await;
+
+        let source = r#"
+// cert-x-gen:
+//   id: apache-status
+//   severity: low
+//   tags: [apache, exposure]
+
+let resp = http_get("/server-status");
+if resp.status == 200 && regex_match(resp.body, "Apache Server Status") {
+    let version = regex_extract(resp.body, "Server Version: ([0-9.]+)");
+    emit_finding(#{
+        title: "Apache server-status exposed",
+        severity: "medium",
+        matched: version,
+        evidence: #{ server: resp.headers["server"] },
+    });
+}
+"#;
+        let engine = engine_with_client().await;
+        let template = load(&engine, source).await;
+        assert_eq!(template.metadata().id, "apache-status");
+        assert!(template.metadata().tags.contains(&"exposure".to_string()));
+
+        let address = server.address();
+        let target = Target::with_port(address.ip().to_string(), address.port(), Protocol::Http);
+        let findings = template
+            .execute(&target, &Context::default())
+            .await
+            .unwrap();
+
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.template_id, "apache-status");
+        assert_eq!(finding.severity, Severity::Medium);
+        assert_eq!(
+            finding.evidence.matched_patterns,
+            vec!["2.4.41".to_string()]
+        );
+        assert_eq!(finding.evidence.data["server"], "Apache/2.4.41");
+    }
+
+    #[tokio::test]
+    async fn test_tcp_send_reads_banner() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 64];
+            let n = socket.read(&mut buf).await.unwrap();
+            if &buf[..n] == b"PING\r\n" {
+                socket.write_all(b"+PONG\r\n").await.unwrap();
+            }
+        });
+
+        let source = r#"
+let reply = tcp_send(target.host, target.port, "PING\r\n");
+if reply.starts_with("+PONG") {
+    emit_finding(#{ title: "Redis without auth", severity: "high", response: reply });
+}
+"#;
+        let engine = engine_with_client().await;
+        let template = load(&engine, source).await;
+        let target = Target::with_port("127.0.0.1", port, Protocol::Tcp);
+        let findings = template
+            .execute(&target, &Context::default())
+            .await
+            .unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::High);
+        assert_eq!(findings[0].evidence.response.as_deref(), Some("+PONG\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_runaway_scripts_are_stopped() {
+        let target = Target::with_port("127.0.0.1", 1, Protocol::Tcp);
+
+        let engine = RhaiEngine::new().with_max_operations(10_000);
+        let template = load(&engine, "let x = 0; loop { x += 1; }").await;
+        let err = template
+            .execute(&target, &Context::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("10000 operations"), "{}", err);
+
+        let engine = RhaiEngine::new()
+            .with_max_operations(0)
+            .with_timeout(Duration::from_millis(100));
+        let template = load(&engine, "loop { }").await;
+        let err = template
+            .execute(&target, &Context::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Timeout { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_syntax_errors_fail_to_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.rhai");
+        std::fs::write(&path, "let x = ;").unwrap();
+        let err = RhaiEngine::new().load_template(&path).await.err().unwrap();
+        assert!(matches!(err, Error::TemplateValidation { .. }));
+    }
+}
@@ -0,0 +1,297 @@
+//! Interpreter and compiler discovery for template engines
+//!
+//! Each engine resolves its runtime once per scan: an explicit
+//! `engines.<language>.path` from the configuration wins, otherwise the
+//! language's fallback list is searched on `PATH` (or probed inside the
+//! active Docker sandbox).
+
+use crate::config::EngineRuntimeConfig;
+use crate::engine::common::ToolCommand;
+use crate::error::{Error, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+/// Languages that accept an `engines.<language>` configuration entry
+pub const ENGINE_LANGUAGES: &[&str] = &[
+    "python",
+    "javascript",
+    "ruby",
+    "perl",
+    "php",
+    "shell",
+    "c",
+    "cpp",
+    "java",
+    "go",
+    "rust",
+];
+
+/// Programs tried, in order, when no explicit path is configured
+pub fn fallback_programs(language: &str) -> &'static [&'static str] {
+    match language {
+        "python" => &[
+            "python3",
+            "python",
+            "python3.13",
+            "python3.12",
+            "python3.11",
+            "python3.10",
+            "python3.9",
+        ],
+        "javascript" => &["node", "nodejs"],
+        "ruby" => &["ruby"],
+        "perl" => &["perl"],
+        "php" => &["php"],
+        "shell" => &["/bin/bash", "bash"],
+        "c" => &["gcc", "clang", "cc"],
+        "cpp" => &["g++", "clang++", "c++"],
+        "java" => &["javac"],
+        "go" => &["go"],
+        "rust" => &["rustc"],
+        _ => &[],
+    }
+}
+
+/// A runtime binary an engine settled on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedRuntime {
+    /// Program to execute (absolute path when found on the host `PATH`)
+    pub program: String,
+    /// First line of the program's version output
+    pub version: Option<String>,
+    /// Whether the program came from `engines.<language>.path`
+    pub configured: bool,
+}
+
+/// Runtime selection for one engine, shared by all clones of that engine
+#[derive(Debug, Clone)]
+pub struct EngineRuntime {
+    language: &'static str,
+    config: EngineRuntimeConfig,
+    resolved: Arc<OnceCell<Option<ResolvedRuntime>>>,
+}
+
+impl EngineRuntime {
+    /// Runtime for `language` using only the fallback list
+    pub fn new(language: &'static str) -> Self {
+        Self {
+            language,
+            config: EngineRuntimeConfig::default(),
+            resolved: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Apply an `engines.<language>` configuration entry
+    pub fn with_config(mut self, config: EngineRuntimeConfig) -> Self {
+        self.config = config;
+        self.resolved = Arc::new(OnceCell::new());
+        self
+    }
+
+    /// Language this runtime serves
+    pub fn language(&self) -> &'static str {
+        self.language
+    }
+
+    /// Extra compiler flags from the configuration
+    pub fn compile_flags(&self) -> &[String] {
+        &self.config.compile_flags
+    }
+
+    /// Extra interpreter/launcher flags from the configuration
+    pub fn run_flags(&self) -> &[String] {
+        &self.config.run_flags
+    }
+
+    /// Resolve the runtime, probing candidates on first call only
+    pub async fn resolve(&self) -> Option<&ResolvedRuntime> {
+        self.resolved
+            .get_or_init(|| async {
+                let resolved = self.discover().await;
+                match &resolved {
+                    Some(runtime) => tracing::info!(
+                        "{} engine using {} ({})",
+                        self.language,
+                        runtime.program,
+                        runtime.version.as_deref().unwrap_or("unknown version")
+                    ),
+                    None => tracing::warn!(
+                        "No {} runtime found (tried {})",
+                        self.language,
+                        self.candidates().join(", ")
+                    ),
+                }
+                resolved
+            })
+            .await
+            .as_ref()
+    }
+
+    /// Program to execute, or an error naming the candidates that were tried
+    pub async fn program(&self) -> Result<String> {
+        match self.resolve().await {
+            Some(runtime) => Ok(runtime.program.clone()),
+            None => Err(Error::Execution(format!(
+                "No {} runtime found (tried {}); set engines.{}.path in the configuration",
+                self.language,
+                self.candidates().join(", "),
+                self.language
+            ))),
+        }
+    }
+
+    fn candidates(&self) -> Vec<String> {
+        match &self.config.path {
+            Some(path) => vec![path.to_string_lossy().into_owned()],
+            None => fallback_programs(self.language)
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        }
+    }
+
+    async fn discover(&self) -> Option<ResolvedRuntime> {
+        let configured = self.config.path.is_some();
+        for candidate in self.candidates() {
+            let Some(program) = locate(&candidate) else {
+                continue;
+            };
+            if let Some(version) = probe(self.language, &program).await {
+                return Some(ResolvedRuntime {
+                    program,
+                    version: Some(version).filter(|v| !v.is_empty()),
+                    configured,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Check every configured `engines.<language>.path` before a scan starts
+pub async fn validate_configured(engines: &BTreeMap<String, EngineRuntimeConfig>) -> Result<()> {
+    for (language, config) in engines {
+        if config.max_concurrency == Some(0) {
+            return Err(Error::config(format!(
+                "engines.{}.max_concurrency must be greater than 0",
+                language
+            )));
+        }
+        // YAML runs in-process: only its concurrency limit is configurable
+        if language == "yaml" {
+            if config.path.is_some()
+                || !config.compile_flags.is_empty()
+                || !config.run_flags.is_empty()
+            {
+                return Err(Error::config("engines.yaml only supports max_concurrency"));
+            }
+            continue;
+        }
+        let Some(known) = ENGINE_LANGUAGES.iter().find(|l| **l == language.as_str()) else {
+            return Err(Error::config(format!(
+                "Unknown engine '{}' in engines section (expected one of: {})",
+                language,
+                ENGINE_LANGUAGES.join(", ")
+            )));
+        };
+        let Some(path) = &config.path else {
+            continue;
+        };
+        let runtime = EngineRuntime::new(known).with_config(config.clone());
+        if runtime.resolve().await.is_none() {
+            return Err(Error::config(format!(
+                "engines.{}.path '{}' does not exist or is not executable",
+                language,
+                path.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Host path for `program`, or the bare name when running inside a sandbox
+fn locate(program: &str) -> Option<String> {
+    if crate::sandbox::active_exec_route().is_some() {
+        return Some(program.to_string());
+    }
+    if program.contains(std::path::MAIN_SEPARATOR) || program.contains('/') {
+        return Path::new(program).is_file().then(|| program.to_string());
+    }
+    find_in_path(program).map(|p| p.to_string_lossy().into_owned())
+}
+
+pub(crate) fn find_in_path(program: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Run the program's version command; `None` if it cannot be executed
+async fn probe(language: &str, program: &str) -> Option<String> {
+    let version_arg = match language {
+        "go" => "version",
+        "java" => "-version",
+        _ => "--version",
+    };
+    let output = ToolCommand::new(program)
+        .arg(version_arg)
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    // Older javac prints its version on stderr
+    let text = if output.stdout.iter().all(u8::is_ascii_whitespace) {
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    } else {
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    Some(
+        text.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_configured_path_must_exist() {
+        let mut engines = BTreeMap::new();
+        engines.insert(
+            "python".to_string(),
+            EngineRuntimeConfig {
+                path: Some(PathBuf::from("/nonexistent/python3.11")),
+                ..Default::default()
+            },
+        );
+        let err = validate_configured(&engines).await.unwrap_err();
+        assert!(err.to_string().contains("engines.python.path"));
+
+        engines.clear();
+        engines.insert("cobol".to_string(), EngineRuntimeConfig::default());
+        assert!(validate_configured(&engines).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fallback_resolution_is_cached() {
+        let runtime = EngineRuntime::new("shell");
+        let first = runtime.resolve().await.cloned();
+        let clone = runtime.clone();
+        assert_eq!(clone.resolve().await.cloned(), first);
+
+        let missing = EngineRuntime::new("shell").with_config(EngineRuntimeConfig {
+            path: Some(PathBuf::from("/nonexistent/bash")),
+            ..Default::default()
+        });
+        let err = missing.program().await.unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/bash"));
+    }
+}
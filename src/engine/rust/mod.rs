@@ -1,23 +1,25 @@
 //! Rust template engine implementation
 
+use crate::config::EngineRuntimeConfig;
 use crate::engine::common::{
-    build_env_vars, create_metadata, execute_command, generate_cache_key, get_cache_dir,
-    parse_findings,
+    build_env_vars, cached_binary_path, create_metadata, execute_command, generate_cache_key,
+    get_cache_dir, parse_findings, prepare_workdir, ToolCommand,
 };
+use crate::engine::runtime::EngineRuntime;
 use crate::error::{Error, Result};
-use crate::template::{Template, TemplateEngine};
+use crate::metrics::profiler::{self, Phase};
+use crate::template::{ExecutionCost, Template, TemplateEngine};
 use crate::types::{Context, Finding, Protocol, Target, TemplateLanguage};
+use crate::workspace::resolve_artifacts;
 use async_trait::async_trait;
 use std::path::Path;
 use std::path::PathBuf;
-use std::process::Stdio;
-use tokio::process::Command;
 
 /// Rust template engine - compiles and executes Rust templates
 #[derive(Debug)]
 pub struct RustEngine {
     name: String,
-    rustc_path: String,
+    runtime: EngineRuntime,
     cache_dir: PathBuf,
 }
 
@@ -26,39 +28,48 @@ impl RustEngine {
     pub fn new() -> Self {
         Self {
             name: "rust".to_string(),
-            rustc_path: "rustc".to_string(),
+            runtime: EngineRuntime::new("rust"),
             cache_dir: get_cache_dir("rust"),
         }
     }
 
-    /// Compile and execute Rust template
-    async fn execute_rust_template(
-        &self,
-        template_path: &Path,
-        target: &Target,
-        context: &Context,
-    ) -> Result<Vec<Finding>> {
+    /// Use an `engines.rust` configuration entry instead of the defaults
+    pub fn with_runtime_config(mut self, config: EngineRuntimeConfig) -> Self {
+        self.runtime = self.runtime.with_config(config);
+        self
+    }
+
+    /// Compile the template unless an up-to-date cached binary exists
+    async fn ensure_compiled(&self, template_path: &Path) -> Result<PathBuf> {
         // Ensure cache directory exists
         tokio::fs::create_dir_all(&self.cache_dir).await?;
 
         // Generate cache key and binary path
         let cache_key = generate_cache_key(template_path)?;
-        let binary_name = template_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("template");
-        let binary_path = self
-            .cache_dir
-            .join(format!("{}-{}", binary_name, cache_key));
+        let binary_path = cached_binary_path(&self.cache_dir, template_path, &cache_key);
 
         // Check if binary exists and is newer than source
         if !binary_path.exists() || self.is_source_newer(template_path, &binary_path).await? {
             // Compile Rust template
+            let _compiling = profiler::enter(Phase::Compile);
             self.compile_template(template_path, &binary_path).await?;
         }
 
+        Ok(binary_path)
+    }
+
+    /// Compile and execute Rust template
+    async fn execute_rust_template(
+        &self,
+        template_path: &Path,
+        target: &Target,
+        context: &Context,
+    ) -> Result<Vec<Finding>> {
+        let binary_path = self.ensure_compiled(template_path).await?;
+
         // Build environment variables
-        let env_vars = build_env_vars(target, context)?;
+        let mut env_vars = build_env_vars(target, context)?;
+        let workdir = prepare_workdir(&mut env_vars, context, template_path, target)?;
 
         // Execute compiled binary (no arguments, uses environment variables)
         let stdout = execute_command(&binary_path.to_string_lossy(), &[], &env_vars).await?;
@@ -70,7 +81,9 @@ impl RustEngine {
             .unwrap_or("unknown")
             .to_string();
 
-        parse_findings(&stdout, target, &template_id)
+        let mut findings = parse_findings(&stdout, target, &template_id)?;
+        resolve_artifacts(&mut findings, workdir.as_deref());
+        Ok(findings)
     }
 
     /// Compile Rust template to binary
@@ -83,13 +96,11 @@ impl RustEngine {
 
         let output = if cargo_toml.exists() {
             // Use cargo build for projects with Cargo.toml
-            let build_output = Command::new("cargo")
+            let build_output = ToolCommand::new("cargo")
                 .arg("build")
                 .arg("--release")
                 .arg("--manifest-path")
                 .arg(&cargo_toml)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
                 .output()
                 .await
                 .map_err(|e| Error::Execution(format!("Failed to run cargo build: {}", e)))?;
@@ -104,7 +115,11 @@ impl RustEngine {
                 .file_stem()
                 .and_then(|s| s.to_str())
                 .unwrap_or("template");
-            let cargo_binary = source_dir.join("target/release").join(binary_name);
+            let cargo_binary = source_dir.join("target").join("release").join(format!(
+                "{}{}",
+                binary_name,
+                std::env::consts::EXE_SUFFIX
+            ));
             tokio::fs::copy(&cargo_binary, binary_path)
                 .await
                 .map_err(|e| Error::Execution(format!("Failed to copy binary: {}", e)))?;
@@ -112,13 +127,12 @@ impl RustEngine {
             return Ok(());
         } else {
             // Use rustc for standalone files
-            Command::new(&self.rustc_path)
+            ToolCommand::new(self.runtime.program().await?)
                 .arg(source_path)
                 .arg("-o")
                 .arg(binary_path)
                 .arg("-O") // Optimize
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
+                .args(self.runtime.compile_flags())
                 .output()
                 .await
                 .map_err(|e| Error::Execution(format!("Failed to compile Rust template: {}", e)))?
@@ -148,7 +162,7 @@ impl Clone for RustEngine {
     fn clone(&self) -> Self {
         Self {
             name: self.name.clone(),
-            rustc_path: self.rustc_path.clone(),
+            runtime: self.runtime.clone(),
             cache_dir: self.cache_dir.clone(),
         }
     }
@@ -175,6 +189,10 @@ impl Template for RustTemplate {
             .await
     }
 
+    async fn prepare(&self) -> Result<()> {
+        self.engine.ensure_compiled(&self.path).await.map(|_| ())
+    }
+
     fn validate(&self) -> Result<()> {
         if !self.path.exists() {
             return Err(Error::Io(std::io::Error::new(
@@ -229,6 +247,10 @@ impl TemplateEngine for RustEngine {
         &self.name
     }
 
+    fn cost_hint(&self) -> ExecutionCost {
+        ExecutionCost::Heavy
+    }
+
     fn supports_file(&self, path: &Path) -> bool {
         path.extension()
             .and_then(|s| s.to_str())
@@ -253,4 +275,23 @@ mod tests {
         let engine = RustEngine::new();
         assert_eq!(engine.name(), "rust");
     }
+
+    #[tokio::test]
+    async fn test_load_template_uses_project_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        crate::template::ProjectScaffold::new(TemplateLanguage::Rust, "my-check", "My Check")
+            .unwrap()
+            .write(dir.path())
+            .unwrap();
+
+        let template = RustEngine::new()
+            .load_template(&dir.path().join("my-check.rs"))
+            .await
+            .unwrap();
+        let metadata = template.metadata();
+        assert_eq!(metadata.id, "my-check");
+        assert_eq!(metadata.severity, crate::types::Severity::Info);
+        assert!(metadata.tags.contains(&"http".to_string()));
+        assert!(metadata.author.name.contains("you@example.com"));
+    }
 }
@@ -1,9 +1,14 @@
 //! Shell template engine implementation
 
-use crate::engine::common::{build_env_vars, create_metadata, execute_command, parse_findings};
+use crate::config::EngineRuntimeConfig;
+use crate::engine::common::{
+    build_env_vars, create_metadata, execute_command, parse_findings, prepare_workdir,
+};
+use crate::engine::runtime::EngineRuntime;
 use crate::error::{Error, Result};
-use crate::template::{Template, TemplateEngine};
+use crate::template::{ExecutionCost, Template, TemplateEngine};
 use crate::types::{Context, Finding, Protocol, Target, TemplateLanguage};
+use crate::workspace::resolve_artifacts;
 use async_trait::async_trait;
 use std::path::Path;
 use std::path::PathBuf;
@@ -12,7 +17,7 @@ use std::path::PathBuf;
 #[derive(Debug)]
 pub struct ShellEngine {
     name: String,
-    shell_path: String,
+    runtime: EngineRuntime,
 }
 
 impl ShellEngine {
@@ -20,10 +25,16 @@ impl ShellEngine {
     pub fn new() -> Self {
         Self {
             name: "shell".to_string(),
-            shell_path: "/bin/bash".to_string(),
+            runtime: EngineRuntime::new("shell"),
         }
     }
 
+    /// Use an `engines.shell` configuration entry instead of the defaults
+    pub fn with_runtime_config(mut self, config: EngineRuntimeConfig) -> Self {
+        self.runtime = self.runtime.with_config(config);
+        self
+    }
+
     /// Execute shell template
     async fn execute_shell_template(
         &self,
@@ -34,18 +45,21 @@ impl ShellEngine {
         tracing::debug!("Shell engine executing template: {:?}", template_path);
 
         // Build environment variables
-        let env_vars = build_env_vars(target, context)?;
+        let mut env_vars = build_env_vars(target, context)?;
+        let workdir = prepare_workdir(&mut env_vars, context, template_path, target)?;
 
         // Execute shell script with arguments
         let port = target.port.unwrap_or(80);
-        let args = vec![
+        let program = self.runtime.program().await?;
+        let mut args = self.runtime.run_flags().to_vec();
+        args.extend([
             template_path.to_string_lossy().to_string(),
             target.address.clone(),
             port.to_string(),
             "--json".to_string(),
-        ];
+        ]);
 
-        let stdout = execute_command(&self.shell_path, &args, &env_vars).await?;
+        let stdout = execute_command(&program, &args, &env_vars).await?;
 
         // Try to extract JSON from output (shell scripts may have mixed output)
         let json_str = if let Some(json_start) = stdout.find("[CERT-X-GEN-JSON]") {
@@ -66,7 +80,9 @@ impl ShellEngine {
             .unwrap_or("unknown")
             .to_string();
 
-        parse_findings(json_str, target, &template_id)
+        let mut findings = parse_findings(json_str, target, &template_id)?;
+        resolve_artifacts(&mut findings, workdir.as_deref());
+        Ok(findings)
     }
 }
 
@@ -74,7 +90,7 @@ impl Clone for ShellEngine {
     fn clone(&self) -> Self {
         Self {
             name: self.name.clone(),
-            shell_path: self.shell_path.clone(),
+            runtime: self.runtime.clone(),
         }
     }
 }
@@ -148,6 +164,10 @@ impl TemplateEngine for ShellEngine {
         &self.name
     }
 
+    fn cost_hint(&self) -> ExecutionCost {
+        ExecutionCost::Medium
+    }
+
     fn supports_file(&self, path: &Path) -> bool {
         path.extension()
             .and_then(|s| s.to_str())
@@ -172,4 +192,63 @@ mod tests {
         let engine = ShellEngine::new();
         assert_eq!(engine.name(), "shell");
     }
+
+    #[tokio::test]
+    async fn test_load_template_reads_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ssh-banner.sh");
+        std::fs::write(
+            &path,
+            "#!/bin/bash\n# cert-x-gen:\n#   severity: low\necho '[]'\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("ssh-banner.meta.yaml"),
+            "severity: medium\ntags: ssh, banner\n",
+        )
+        .unwrap();
+
+        let template = ShellEngine::new().load_template(&path).await.unwrap();
+        let metadata = template.metadata();
+        // The sidecar takes precedence over front-matter
+        assert_eq!(metadata.severity, crate::types::Severity::Medium);
+        assert!(metadata.tags.contains(&"banner".to_string()));
+        assert_eq!(metadata.id, "ssh-banner");
+    }
+
+    #[tokio::test]
+    async fn test_runs_in_scan_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dump.sh");
+        std::fs::write(
+            &path,
+            r#"#!/bin/bash
+pwd -P > where.txt
+echo "[{\"title\": \"Dump\", \"evidence\": {\"data\": {\"artifacts\": \"where.txt\", \"workdir\": \"$CXG_WORKDIR\"}}}]"
+"#,
+        )
+        .unwrap();
+        let base = tempfile::tempdir().unwrap();
+        let workspace = crate::workspace::ScanWorkspace::new(base.path(), uuid::Uuid::new_v4());
+        let context = Context {
+            workspace: Some(std::sync::Arc::new(workspace.clone())),
+            ..Context::default()
+        };
+        let target = Target::with_port("127.0.0.1", 8080, Protocol::Http);
+
+        let template = ShellEngine::new().load_template(&path).await.unwrap();
+        let findings = template.execute(&target, &context).await.unwrap();
+        let workdir = workspace.dir().join("dump").join("127.0.0.1_8080");
+        let data = &findings[0].evidence.data;
+        assert_eq!(data["workdir"], workdir.to_str().unwrap());
+        let artifact = workdir.join("where.txt");
+        assert_eq!(
+            data["artifacts"],
+            serde_json::json!([artifact.to_str().unwrap()])
+        );
+        assert_eq!(
+            std::fs::read_to_string(&artifact).unwrap().trim(),
+            workdir.canonicalize().unwrap().to_str().unwrap()
+        );
+    }
 }
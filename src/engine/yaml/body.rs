@@ -0,0 +1,244 @@
+//! Structured HTTP request bodies for YAML templates
+//!
+//! Besides a raw `body:`, an `http:` request can give its body as data and
+//! let the engine encode it:
+//!
+//! ```yaml
+//! body_form:
+//!   user: admin
+//!   pass: "{{password}}"
+//!
+//! body_multipart:
+//!   - name: file
+//!     filename: shell.php
+//!     content_type: application/x-php
+//!     data: "<?php echo 'cxg'; ?>"
+//!   - name: submit
+//!     data: Upload
+//! ```
+//!
+//! Template variables are substituted in every name and value before
+//! encoding. The matching `Content-Type` (with the generated boundary for
+//! multipart) replaces any `Content-Type` in the request's `headers`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Content type of `body_form:` requests
+pub const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// Content type of multipart file parts that do not set one
+pub const DEFAULT_FILE_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Most bytes of a request body kept in finding evidence
+pub const EVIDENCE_BODY_LIMIT: usize = 4096;
+
+/// One part of a `body_multipart:` request
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MultipartPart {
+    /// Form field name
+    pub name: String,
+
+    /// File name; makes the part a file upload
+    pub filename: Option<String>,
+
+    /// Part `Content-Type` (file parts default to [`DEFAULT_FILE_CONTENT_TYPE`])
+    pub content_type: Option<String>,
+
+    /// Part content
+    #[serde(default)]
+    pub data: String,
+}
+
+/// An encoded request body and the `Content-Type` it is sent with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedBody {
+    /// Body text
+    pub body: String,
+    /// `Content-Type` header value, if the body needs one
+    pub content_type: Option<String>,
+}
+
+/// URL-encode `body_form:` fields in template order
+pub fn form(
+    fields: &serde_yaml::Mapping,
+    variables: &BTreeMap<String, String>,
+) -> Result<EncodedBody, String> {
+    let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+    for (key, value) in fields {
+        let key = scalar(key).ok_or("body_form field names must be strings")?;
+        let value = scalar(value)
+            .ok_or_else(|| format!("body_form field '{}' must be a single value", key))?;
+        serializer.append_pair(
+            &super::substitute(&key, variables),
+            &super::substitute(&value, variables),
+        );
+    }
+    Ok(EncodedBody {
+        body: serializer.finish(),
+        content_type: Some(FORM_CONTENT_TYPE.to_string()),
+    })
+}
+
+/// Build a `multipart/form-data` body with a boundary none of the parts contain
+pub fn multipart(
+    parts: &[MultipartPart],
+    variables: &BTreeMap<String, String>,
+) -> Result<EncodedBody, String> {
+    if let Some(part) = parts.iter().find(|part| part.name.is_empty()) {
+        return Err(format!(
+            "body_multipart part{} needs a name",
+            part.filename
+                .as_deref()
+                .map(|f| format!(" '{}'", f))
+                .unwrap_or_default()
+        ));
+    }
+    let parts: Vec<MultipartPart> = parts
+        .iter()
+        .map(|part| MultipartPart {
+            name: super::substitute(&part.name, variables),
+            filename: part
+                .filename
+                .as_deref()
+                .map(|f| super::substitute(f, variables)),
+            content_type: part
+                .content_type
+                .as_deref()
+                .map(|c| super::substitute(c, variables)),
+            data: super::substitute(&part.data, variables),
+        })
+        .collect();
+
+    let boundary = loop {
+        let boundary = format!("----cxg{}", uuid::Uuid::new_v4().simple());
+        if !parts.iter().any(|part| part.data.contains(&boundary)) {
+            break boundary;
+        }
+    };
+    Ok(EncodedBody {
+        body: encode_parts(&parts, &boundary),
+        content_type: Some(format!("multipart/form-data; boundary={}", boundary)),
+    })
+}
+
+fn encode_parts(parts: &[MultipartPart], boundary: &str) -> String {
+    let mut body = String::new();
+    for part in parts {
+        body.push_str(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+            boundary,
+            quote(&part.name)
+        ));
+        if let Some(filename) = &part.filename {
+            body.push_str(&format!("; filename=\"{}\"", quote(filename)));
+        }
+        body.push_str("\r\n");
+        let content_type = part
+            .content_type
+            .as_deref()
+            .or(part.filename.as_ref().map(|_| DEFAULT_FILE_CONTENT_TYPE));
+        if let Some(content_type) = content_type {
+            body.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        body.push_str("\r\n");
+        body.push_str(&part.data);
+        body.push_str("\r\n");
+    }
+    body.push_str(&format!("--{}--\r\n", boundary));
+    body
+}
+
+/// Escape a `Content-Disposition` parameter the way browsers do
+fn quote(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// A YAML scalar as text
+fn scalar(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        serde_yaml::Value::Null => Some(String::new()),
+        _ => None,
+    }
+}
+
+/// `body` cut to [`EVIDENCE_BODY_LIMIT`] bytes for finding evidence
+pub fn evidence_body(body: &str) -> String {
+    if body.len() <= EVIDENCE_BODY_LIMIT {
+        return body.to_string();
+    }
+    let mut end = EVIDENCE_BODY_LIMIT;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!(
+        "{}\n... [truncated, {} bytes total]",
+        &body[..end],
+        body.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_form_and_multipart_encoding() {
+        let variables: BTreeMap<String, String> =
+            [("password".to_string(), "p@ss word&1".to_string())].into();
+        let fields: serde_yaml::Mapping =
+            serde_yaml::from_str("user: admin\npass: '{{password}}'\npin: 1234").unwrap();
+        let encoded = form(&fields, &variables).unwrap();
+        assert_eq!(encoded.body, "user=admin&pass=p%40ss+word%261&pin=1234");
+        assert_eq!(encoded.content_type.as_deref(), Some(FORM_CONTENT_TYPE));
+
+        let nested: serde_yaml::Mapping = serde_yaml::from_str("user: [a, b]").unwrap();
+        assert!(form(&nested, &variables).unwrap_err().contains("'user'"));
+
+        let parts = vec![
+            MultipartPart {
+                name: "file".to_string(),
+                filename: Some("a\"b.php".to_string()),
+                content_type: None,
+                data: "<?php {{password}} ?>".to_string(),
+            },
+            MultipartPart {
+                name: "submit".to_string(),
+                filename: None,
+                content_type: None,
+                data: "Upload".to_string(),
+            },
+        ];
+        assert_eq!(
+            encode_parts(&parts, "XYZ"),
+            "--XYZ\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a%22b.php\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n<?php {{password}} ?>\r\n\
+             --XYZ\r\nContent-Disposition: form-data; name=\"submit\"\r\n\r\nUpload\r\n\
+             --XYZ--\r\n"
+        );
+
+        let encoded = multipart(&parts, &variables).unwrap();
+        let content_type = encoded.content_type.unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap();
+        assert!(encoded.body.starts_with(&format!("--{}\r\n", boundary)));
+        assert!(encoded.body.ends_with(&format!("--{}--\r\n", boundary)));
+        assert!(encoded.body.contains("<?php p@ss word&1 ?>"));
+    }
+
+    #[test]
+    fn test_evidence_body_is_truncated() {
+        assert_eq!(evidence_body("short"), "short");
+        let long = "é".repeat(EVIDENCE_BODY_LIMIT);
+        let cut = evidence_body(&long);
+        assert!(cut.len() < long.len());
+        assert!(cut.ends_with(&format!("[truncated, {} bytes total]", long.len())));
+    }
+}
@@ -0,0 +1,273 @@
+//! CRLF header injection checks for the YAML `header_injection:` section
+//!
+//! Each configured request header is sent with a value that tries to
+//! terminate the header line and start a new one. If the server splits the
+//! value into its own response header, the injected header shows up in the
+//! response and the target is vulnerable.
+
+use crate::error::Result;
+use crate::network::NetworkClient;
+use reqwest::header::HeaderValue;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Request headers tested when a spec does not list any
+pub const DEFAULT_HEADERS: &[&str] = &["User-Agent", "Referer", "X-Forwarded-For"];
+
+/// Line break sequences tried for each header, raw first
+///
+/// The raw sequence is rejected by the HTTP client for most header values and
+/// is skipped in that case; the encoded forms reach servers that decode them.
+pub const CRLF_VARIANTS: &[&str] = &["\r\n", "%0d%0a", "%0D%0A", "%0a", "%0d"];
+
+/// Header the payload tries to create in the response
+pub const INJECTED_HEADER: &str = "X-CXG-Injected";
+
+/// `header_injection:` entry in a YAML template
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct HeaderInjectionSpec {
+    /// Request paths to test (defaults to `/`)
+    #[serde(default)]
+    pub path: Vec<String>,
+
+    /// Request headers to inject into (defaults to [`DEFAULT_HEADERS`])
+    #[serde(default)]
+    pub headers: Vec<String>,
+
+    /// Line break sequences to try (defaults to [`CRLF_VARIANTS`])
+    #[serde(default)]
+    pub payloads: Vec<String>,
+}
+
+impl HeaderInjectionSpec {
+    /// Paths to request, falling back to the site root
+    pub fn paths(&self) -> Vec<String> {
+        if self.path.is_empty() {
+            vec!["/".to_string()]
+        } else {
+            self.path.clone()
+        }
+    }
+
+    /// Checker configured from this spec
+    pub fn checker(&self) -> HeaderInjectionChecker {
+        let mut checker = HeaderInjectionChecker::new();
+        if !self.headers.is_empty() {
+            checker = checker.with_headers(self.headers.clone());
+        }
+        if !self.payloads.is_empty() {
+            checker = checker.with_variants(self.payloads.clone());
+        }
+        checker
+    }
+}
+
+/// A payload that produced an extra response header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderInjection {
+    /// Request header the payload was sent in
+    pub request_header: String,
+    /// Line break sequence that worked
+    pub variant: String,
+    /// Full request header value
+    pub payload: String,
+    /// Response header created by the payload
+    pub response_header: String,
+    /// Value of that response header
+    pub response_value: String,
+}
+
+/// Sends CRLF payloads in request headers and looks for them split out in the response
+#[derive(Debug, Clone)]
+pub struct HeaderInjectionChecker {
+    headers: Vec<String>,
+    variants: Vec<String>,
+    marker: String,
+}
+
+impl HeaderInjectionChecker {
+    /// Checker for the default headers and line break variants
+    pub fn new() -> Self {
+        Self {
+            headers: DEFAULT_HEADERS.iter().map(|h| h.to_string()).collect(),
+            variants: CRLF_VARIANTS.iter().map(|v| v.to_string()).collect(),
+            marker: format!("cxg{:08x}", fastrand::u32(..)),
+        }
+    }
+
+    /// Test these request headers instead of the defaults
+    pub fn with_headers(mut self, headers: Vec<String>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Try these line break sequences instead of the defaults
+    pub fn with_variants(mut self, variants: Vec<String>) -> Self {
+        self.variants = variants;
+        self
+    }
+
+    /// Requests sent per URL when nothing is found
+    pub fn request_count(&self) -> usize {
+        self.headers.len() * self.variants.len()
+    }
+
+    /// Request header value carrying `variant`
+    pub fn payload(&self, variant: &str) -> String {
+        format!("cxg{}{}: {}", variant, INJECTED_HEADER, self.marker)
+    }
+
+    /// Response header created by one of this checker's payloads, if any
+    pub fn detect(&self, response_headers: &[(String, String)]) -> Option<(String, String)> {
+        response_headers
+            .iter()
+            .find(|(name, value)| {
+                name.eq_ignore_ascii_case(INJECTED_HEADER) && value.contains(&self.marker)
+            })
+            .cloned()
+    }
+
+    /// Test every header/variant pair against `url`
+    ///
+    /// Stops at the first working variant for each header. Payloads the HTTP
+    /// client refuses to send are skipped; request failures are returned.
+    pub async fn check(
+        &self,
+        client: &NetworkClient,
+        url: &str,
+        base_headers: &HashMap<String, String>,
+    ) -> Result<Vec<HeaderInjection>> {
+        let mut injections = Vec::new();
+
+        for header in &self.headers {
+            for variant in &self.variants {
+                let payload = self.payload(variant);
+                if HeaderValue::from_str(&payload).is_err() {
+                    tracing::debug!("Skipping unsendable {} payload {:?}", header, variant);
+                    continue;
+                }
+
+                let mut headers = base_headers.clone();
+                headers.insert(header.clone(), payload.clone());
+                let response = client.get_with_headers(url, headers).await?;
+                let response_headers: Vec<(String, String)> = response
+                    .headers()
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+                    .collect();
+
+                if let Some((response_header, response_value)) = self.detect(&response_headers) {
+                    tracing::debug!("CRLF injection via {} with {:?}", header, variant);
+                    injections.push(HeaderInjection {
+                        request_header: header.clone(),
+                        variant: variant.clone(),
+                        payload,
+                        response_header,
+                        response_value,
+                    });
+                    break;
+                }
+            }
+        }
+
+        Ok(injections)
+    }
+}
+
+impl Default for HeaderInjectionChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::sync::Arc;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+    /// Echoes the User-Agent back in `X-Echo`, optionally decoding line breaks first
+    struct EchoUserAgent {
+        decode: bool,
+    }
+
+    impl Respond for EchoUserAgent {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let user_agent = request
+                .headers
+                .get(&"user-agent".into())
+                .map(|v| v.last().as_str().to_string())
+                .unwrap_or_default();
+            if !self.decode {
+                return ResponseTemplate::new(200).insert_header("X-Echo", user_agent.as_str());
+            }
+
+            let decoded = user_agent
+                .replace("%0d", "\r")
+                .replace("%0D", "\r")
+                .replace("%0a", "\n")
+                .replace("%0A", "\n");
+            let mut lines = decoded.split(['\r', '\n']).filter(|l| !l.is_empty());
+            let mut response =
+                ResponseTemplate::new(200).insert_header("X-Echo", lines.next().unwrap_or(""));
+            for line in lines {
+                if let Some((name, value)) = line.split_once(": ") {
+                    response = response.insert_header(name, value);
+                }
+            }
+            response
+        }
+    }
+
+    async fn check_against(decode: bool) -> (HeaderInjectionChecker, Vec<HeaderInjection>) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(EchoUserAgent { decode })
+            .mount(&server)
+            .await;
+
+        let client = NetworkClient::new(Arc::new(Config::default()))
+            .await
+            .unwrap();
+        let checker = HeaderInjectionChecker::new().with_headers(vec!["User-Agent".to_string()]);
+        let injections = checker
+            .check(&client, &server.uri(), &HashMap::new())
+            .await
+            .unwrap();
+        (checker, injections)
+    }
+
+    #[tokio::test]
+    async fn test_decoding_server_is_flagged() {
+        let (checker, injections) = check_against(true).await;
+
+        assert_eq!(injections.len(), 1);
+        let injection = &injections[0];
+        assert_eq!(injection.request_header, "User-Agent");
+        assert_eq!(injection.variant, "%0d%0a");
+        assert_eq!(injection.payload, checker.payload("%0d%0a"));
+        assert!(injection
+            .response_header
+            .eq_ignore_ascii_case(INJECTED_HEADER));
+        assert_eq!(injection.response_value, checker.marker);
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_reflection_is_not_flagged() {
+        let (_, injections) = check_against(false).await;
+        assert!(injections.is_empty());
+    }
+
+    #[test]
+    fn test_spec_defaults() {
+        let spec: HeaderInjectionSpec = serde_yaml::from_str("headers: [X-Api-Version]").unwrap();
+        assert_eq!(spec.paths(), vec!["/".to_string()]);
+
+        let checker = spec.checker();
+        assert_eq!(checker.headers, vec!["X-Api-Version".to_string()]);
+        assert_eq!(checker.variants.len(), CRLF_VARIANTS.len());
+        assert!(HeaderValue::from_str(&checker.payload("\r\n")).is_err());
+    }
+}
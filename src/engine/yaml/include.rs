@@ -0,0 +1,335 @@
+//! `include:` support for YAML templates
+//!
+//! A template can pull shared matchers, payloads and variables from snippet
+//! files instead of copying them:
+//!
+//! ```yaml
+//! include:
+//!   - snippets/wordpress-version.yaml
+//! ```
+//!
+//! Include paths resolve relative to the including file first, then to the
+//! configured snippets directory. Snippets are merged in order before the
+//! template is parsed, and whatever the template defines itself wins.
+
+use crate::error::{Error, Result};
+use crate::matcher::MatcherType;
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Top-level keys a snippet file may define
+pub const SNIPPET_KEYS: &[&str] = &["include", "matchers", "payloads", "variables"];
+
+/// A YAML document with all of its includes merged in
+#[derive(Debug, Clone)]
+pub struct Composed {
+    /// Merged document
+    pub value: Value,
+    /// Every snippet file pulled in, directly or transitively
+    pub includes: Vec<PathBuf>,
+}
+
+/// Read `path` and merge its includes
+pub fn compose(path: &Path, snippets_dir: Option<&Path>) -> Result<Composed> {
+    let mut composer = Composer {
+        snippets_dir,
+        stack: Vec::new(),
+        includes: Vec::new(),
+    };
+    let value = composer.load(path)?;
+    Ok(Composed {
+        value,
+        includes: composer.includes,
+    })
+}
+
+/// Error for YAML in `path` that does not parse, quoting the offending line
+pub fn yaml_error(path: &Path, content: &str, error: serde_yaml::Error) -> Error {
+    let mut message = format!("Invalid YAML: {}", error);
+    if let Some(location) = error.location() {
+        if let Some(line) = content.lines().nth(location.line().saturating_sub(1)) {
+            let number = location.line().to_string();
+            message.push_str(&format!(
+                "\n  {} | {}\n  {} | {}^",
+                number,
+                line,
+                " ".repeat(number.len()),
+                " ".repeat(location.column().saturating_sub(1))
+            ));
+        }
+    }
+    Error::template(path.display().to_string(), message)
+}
+
+/// Whether a document is a snippet rather than a template
+pub fn is_snippet(value: &Value) -> bool {
+    value.as_mapping().is_some_and(|mapping| {
+        mapping.get("id").is_none()
+            && mapping
+                .keys()
+                .all(|key| key.as_str().is_some_and(|k| SNIPPET_KEYS.contains(&k)))
+    })
+}
+
+/// Check a snippet file on its own, including the snippets it pulls in
+pub fn validate_snippet(path: &Path, snippets_dir: Option<&Path>) -> Result<()> {
+    let composed = compose(path, snippets_dir)?;
+    check_snippet(&composed.value, path)
+}
+
+struct Composer<'a> {
+    snippets_dir: Option<&'a Path>,
+    stack: Vec<PathBuf>,
+    includes: Vec<PathBuf>,
+}
+
+impl Composer<'_> {
+    fn load(&mut self, path: &Path) -> Result<Value> {
+        let content = crate::template::read_source(path)?.text;
+        let canonical = path.canonicalize()?;
+        if let Some(start) = self.stack.iter().position(|p| *p == canonical) {
+            let cycle: Vec<String> = self.stack[start..]
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(Error::template(
+                self.stack[0].display().to_string(),
+                format!("Circular include: {}", cycle.join(" -> ")),
+            ));
+        }
+
+        let mut value: Value =
+            serde_yaml::from_str(&content).map_err(|e| yaml_error(path, &content, e))?;
+        let names = match value.as_mapping_mut().and_then(|m| m.remove("include")) {
+            None => return Ok(value),
+            Some(names) => serde_yaml::from_value::<Vec<String>>(names).map_err(|e| {
+                Error::template(
+                    path.display().to_string(),
+                    format!("'include' must be a list of paths: {}", e),
+                )
+            })?,
+        };
+
+        self.stack.push(canonical.clone());
+        let mut merged = Value::Mapping(Mapping::new());
+        for name in names {
+            let file = self.resolve(&canonical, &name)?;
+            let snippet = self.load(&file)?;
+            check_snippet(&snippet, &file)?;
+            if !self.includes.contains(&file) {
+                self.includes.push(file);
+            }
+            merge(&mut merged, snippet);
+        }
+        self.stack.pop();
+
+        merge(&mut merged, value);
+        Ok(merged)
+    }
+
+    fn resolve(&self, including: &Path, name: &str) -> Result<PathBuf> {
+        let candidates = including
+            .parent()
+            .into_iter()
+            .chain(self.snippets_dir)
+            .map(|dir| dir.join(name));
+        for candidate in candidates {
+            if candidate.is_file() {
+                return Ok(candidate.canonicalize()?);
+            }
+        }
+        Err(Error::template(
+            including.display().to_string(),
+            format!("Included snippet not found: {}", name),
+        ))
+    }
+}
+
+/// Merge `overlay` into `base`; overlay values win
+///
+/// Matchers are appended, replacing base matchers with the same `name`.
+/// `payloads` and `variables` are merged key by key.
+fn merge(base: &mut Value, overlay: Value) {
+    let (Some(base), Value::Mapping(overlay)) = (base.as_mapping_mut(), overlay) else {
+        return;
+    };
+    for (key, value) in overlay {
+        match (key.as_str(), base.get_mut(&key), value) {
+            (Some("matchers"), Some(Value::Sequence(existing)), Value::Sequence(local)) => {
+                let names: Vec<&Value> = local.iter().filter_map(|m| m.get("name")).collect();
+                existing.retain(|m| m.get("name").is_none_or(|n| !names.contains(&n)));
+                existing.extend(local);
+            }
+            (
+                Some("payloads" | "variables"),
+                Some(Value::Mapping(existing)),
+                Value::Mapping(local),
+            ) => existing.extend(local),
+            (_, _, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn check_snippet(value: &Value, path: &Path) -> Result<()> {
+    let invalid = |reason: String| Error::template(path.display().to_string(), reason);
+    let mapping = value
+        .as_mapping()
+        .ok_or_else(|| invalid("Snippet must be a YAML mapping".to_string()))?;
+
+    if let Some(key) = mapping
+        .keys()
+        .find(|key| !key.as_str().is_some_and(|k| SNIPPET_KEYS.contains(&k)))
+    {
+        return Err(invalid(format!(
+            "Snippets may only define {}; found {:?}",
+            SNIPPET_KEYS.join(", "),
+            key
+        )));
+    }
+    if let Some(matchers) = mapping.get("matchers") {
+        serde_yaml::from_value::<Vec<MatcherType>>(matchers.clone())
+            .map_err(|e| invalid(format!("Invalid matchers: {}", e)))?;
+    }
+    if let Some(payloads) = mapping.get("payloads") {
+        super::deserialize_payloads(payloads.clone())
+            .map_err(|e| invalid(format!("Invalid payloads: {}", e)))?;
+    }
+    if let Some(variables) = mapping.get("variables") {
+        serde_yaml::from_value::<BTreeMap<String, String>>(variables.clone())
+            .map_err(|e| invalid(format!("Invalid variables: {}", e)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_local_definitions_override_included_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let shared = tempfile::tempdir().unwrap();
+        write(
+            shared.path(),
+            "wp.yaml",
+            "matchers:\n  - type: word\n    name: version\n    words: [\"ver=\"]\n\
+             \x20 - type: status\n    status: [200]\n\
+             variables:\n  plugin: akismet\n  file: readme.txt\n\
+             payloads:\n  paths: [\"/a\"]\n",
+        );
+        let template = write(
+            dir.path(),
+            "wp-plugin.yaml",
+            "id: wp-plugin\ninclude: [wp.yaml]\nvariables:\n  plugin: jetpack\n\
+             matchers:\n  - type: word\n    name: version\n    words: [\"Stable tag\"]\n",
+        );
+
+        // Not next to the template, so it comes from the snippets directory
+        assert!(compose(&template, None).is_err());
+        let composed = compose(&template, Some(shared.path())).unwrap();
+        assert_eq!(
+            composed.includes,
+            vec![shared.path().join("wp.yaml").canonicalize().unwrap()]
+        );
+
+        let value = composed.value;
+        assert_eq!(value["id"].as_str(), Some("wp-plugin"));
+        assert_eq!(value["variables"]["plugin"].as_str(), Some("jetpack"));
+        assert_eq!(value["variables"]["file"].as_str(), Some("readme.txt"));
+        assert_eq!(value["payloads"]["paths"][0].as_str(), Some("/a"));
+
+        let matchers = value["matchers"].as_sequence().unwrap();
+        assert_eq!(matchers.len(), 2);
+        assert_eq!(matchers[0]["type"].as_str(), Some("status"));
+        assert_eq!(matchers[1]["words"][0].as_str(), Some("Stable tag"));
+        serde_yaml::from_value::<Vec<MatcherType>>(value["matchers"].clone()).unwrap();
+    }
+
+    #[test]
+    fn test_encodings_and_parse_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let utf16: Vec<u8> = "\u{feff}id: wide\ninclude: [shared.yaml]\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let template = dir.path().join("wide.yaml");
+        std::fs::write(&template, utf16).unwrap();
+        std::fs::write(
+            dir.path().join("shared.yaml"),
+            "\u{feff}variables:\n  a: b\n",
+        )
+        .unwrap();
+
+        // The BOM does not end up in the first key of either file
+        let value = compose(&template, None).unwrap().value;
+        assert_eq!(value["id"].as_str(), Some("wide"));
+        assert_eq!(value["variables"]["a"].as_str(), Some("b"));
+
+        let broken = write(
+            dir.path(),
+            "broken.yaml",
+            "id: broken\nhttp:\n  - path: [\"/\"\n    method: GET\n",
+        );
+        let err = compose(&broken, None).unwrap_err().to_string();
+        assert!(err.contains(&broken.display().to_string()), "{}", err);
+        assert!(err.contains("line 4"), "{}", err);
+        assert!(err.contains("4 |     method: GET"), "{}", err);
+    }
+
+    #[test]
+    fn test_circular_include_names_the_cycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = write(dir.path(), "t.yaml", "id: t\ninclude: [a.yaml]\n");
+        write(dir.path(), "a.yaml", "include: [sub/b.yaml]\n");
+        write(dir.path(), "sub/b.yaml", "include: [../a.yaml]\n");
+
+        let err = compose(&template, None).unwrap_err().to_string();
+        let a = dir.path().join("a.yaml").canonicalize().unwrap();
+        let b = dir.path().join("sub/b.yaml").canonicalize().unwrap();
+        let cycle = format!("{} -> {} -> {}", a.display(), b.display(), a.display());
+        assert!(err.contains(&cycle), "{}", err);
+    }
+
+    #[test]
+    fn test_snippets_are_validated_standalone() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = write(
+            dir.path(),
+            "good.yaml",
+            "matchers:\n  - type: status\n    status: [200]\n",
+        );
+        let bad_key = write(dir.path(), "bad-key.yaml", "http:\n  - path: [\"/\"]\n");
+        let bad_matcher = write(
+            dir.path(),
+            "bad-matcher.yaml",
+            "matchers:\n  - type: nope\n",
+        );
+
+        assert!(is_snippet(
+            &serde_yaml::from_str("variables: {a: b}").unwrap()
+        ));
+        assert!(!is_snippet(
+            &serde_yaml::from_str("id: t\nvariables: {a: b}").unwrap()
+        ));
+        validate_snippet(&good, None).unwrap();
+        assert!(validate_snippet(&bad_key, None).is_err());
+        assert!(validate_snippet(&bad_matcher, None).is_err());
+
+        // A template including an invalid snippet fails to compose
+        let template = write(dir.path(), "t.yaml", "id: t\ninclude: [bad-key.yaml]\n");
+        assert!(compose(&template, None).is_err());
+    }
+}
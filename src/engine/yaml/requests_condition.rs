@@ -0,0 +1,305 @@
+//! `requests-condition:` support for YAML templates
+//!
+//! By default every `http:` request reports its own findings. With
+//! `requests-condition`, the requests' results are combined and the template
+//! reports a single finding when the combination holds:
+//!
+//! ```yaml
+//! requests-condition: version && !patched
+//! http:
+//!   - name: version
+//!     path: ["/api/version"]
+//!     matchers: [{ type: word, words: ["\"2.4."] }]
+//!   - name: patched
+//!     path: ["/api/debug"]
+//!     matchers: [{ type: status, status: [404] }]
+//! ```
+//!
+//! `and` and `or` combine every request in order (`and` stops at the first
+//! request that does not match). An expression over request `name`s uses
+//! `&&`, `||`, `!` and parentheses; only the requests it still needs are sent.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// How the results of a template's `http:` requests combine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum RequestsCondition {
+    /// Every request must match
+    And,
+    /// At least one request must match
+    Or,
+    /// Boolean expression over named requests
+    Expression {
+        /// Expression as written
+        text: String,
+        /// Parsed expression
+        expr: RequestExpr,
+    },
+}
+
+impl TryFrom<String> for RequestsCondition {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        match text.trim() {
+            "and" => Ok(RequestsCondition::And),
+            "or" => Ok(RequestsCondition::Or),
+            _ => {
+                let expr = RequestExpr::parse(&text)
+                    .map_err(|e| format!("invalid requests-condition '{}': {}", text, e))?;
+                Ok(RequestsCondition::Expression { text, expr })
+            }
+        }
+    }
+}
+
+impl fmt::Display for RequestsCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestsCondition::And => write!(f, "and"),
+            RequestsCondition::Or => write!(f, "or"),
+            RequestsCondition::Expression { text, .. } => write!(f, "{}", text),
+        }
+    }
+}
+
+impl From<RequestsCondition> for String {
+    fn from(condition: RequestsCondition) -> Self {
+        condition.to_string()
+    }
+}
+
+/// Boolean expression over named request results
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestExpr {
+    /// Whether the named request matched
+    Request(String),
+    /// Negation
+    Not(Box<RequestExpr>),
+    /// Both sides hold
+    And(Box<RequestExpr>, Box<RequestExpr>),
+    /// Either side holds
+    Or(Box<RequestExpr>, Box<RequestExpr>),
+}
+
+impl RequestExpr {
+    /// Parse `a && !(b || c)`; `!` binds tightest, then `&&`, then `||`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected '{}'", token)),
+        }
+    }
+
+    /// Request names used, in order of first use
+    pub fn requests(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect(&mut names);
+        names
+    }
+
+    fn collect<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            RequestExpr::Request(name) => {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+            RequestExpr::Not(inner) => inner.collect(names),
+            RequestExpr::And(a, b) | RequestExpr::Or(a, b) => {
+                a.collect(names);
+                b.collect(names);
+            }
+        }
+    }
+
+    /// Value given the results known so far; `None` while it depends on an unknown request
+    pub fn eval(&self, results: &HashMap<String, bool>) -> Option<bool> {
+        match self {
+            RequestExpr::Request(name) => results.get(name).copied(),
+            RequestExpr::Not(inner) => inner.eval(results).map(|v| !v),
+            RequestExpr::And(a, b) => match (a.eval(results), b.eval(results)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+            RequestExpr::Or(a, b) => match (a.eval(results), b.eval(results)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            },
+        }
+    }
+
+    /// Leftmost unknown request the value still depends on, short-circuiting like `&&` and `||`
+    pub fn next_needed(&self, results: &HashMap<String, bool>) -> Option<&str> {
+        if self.eval(results).is_some() {
+            return None;
+        }
+        match self {
+            RequestExpr::Request(name) => Some(name),
+            RequestExpr::Not(inner) => inner.next_needed(results),
+            RequestExpr::And(a, b) | RequestExpr::Or(a, b) => {
+                a.next_needed(results).or_else(|| b.next_needed(results))
+            }
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '!' | '(' | ')' => {
+                tokens.push(c.to_string());
+                chars.next();
+            }
+            '&' | '|' => {
+                chars.next();
+                if chars.next() != Some(c) {
+                    return Err(format!("expected '{}{}'", c, c));
+                }
+                tokens.push(format!("{}{}", c, c));
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' || c == '-' => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                tokens.push(name);
+            }
+            other => return Err(format!("unexpected character '{}'", other)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, token: &str) -> bool {
+        if self.tokens.get(self.pos).map(String::as_str) == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<RequestExpr, String> {
+        let mut expr = self.and()?;
+        while self.eat("||") {
+            expr = RequestExpr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<RequestExpr, String> {
+        let mut expr = self.unary()?;
+        while self.eat("&&") {
+            expr = RequestExpr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<RequestExpr, String> {
+        if self.eat("!") {
+            return Ok(RequestExpr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let expr = self.or()?;
+            if !self.eat(")") {
+                return Err("missing ')'".to_string());
+            }
+            return Ok(expr);
+        }
+        match self.tokens.get(self.pos) {
+            Some(token) if !matches!(token.as_str(), "&&" | "||" | ")") => {
+                self.pos += 1;
+                Ok(RequestExpr::Request(token.clone()))
+            }
+            Some(token) => Err(format!("expected a request name, found '{}'", token)),
+            None => Err("expected a request name".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(pairs: &[(&str, bool)]) -> HashMap<String, bool> {
+        pairs.iter().map(|(n, v)| (n.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn test_parse_precedence_and_names() {
+        let expr = RequestExpr::parse("a || !b && (c-1 || d_2)").unwrap();
+        let request = |n: &str| Box::new(RequestExpr::Request(n.to_string()));
+        assert_eq!(
+            expr,
+            RequestExpr::Or(
+                request("a"),
+                Box::new(RequestExpr::And(
+                    Box::new(RequestExpr::Not(request("b"))),
+                    Box::new(RequestExpr::Or(request("c-1"), request("d_2"))),
+                )),
+            )
+        );
+        assert_eq!(expr.requests(), ["a", "b", "c-1", "d_2"]);
+
+        for bad in ["", "a &&", "a & b", "(a || b", "a b", "a || )"] {
+            assert!(RequestExpr::parse(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_condition_keywords_and_round_trip() {
+        let parse = |s: &str| RequestsCondition::try_from(s.to_string());
+        assert_eq!(parse("and"), Ok(RequestsCondition::And));
+        assert_eq!(parse("or"), Ok(RequestsCondition::Or));
+        let expr = parse("probe1 && !probe2").unwrap();
+        assert_eq!(expr.to_string(), "probe1 && !probe2");
+        assert!(parse("probe1 &&")
+            .unwrap_err()
+            .contains("requests-condition"));
+    }
+
+    #[test]
+    fn test_short_circuit_order() {
+        let expr = RequestExpr::parse("probe1 && !probe2").unwrap();
+        assert_eq!(expr.next_needed(&results(&[])), Some("probe1"));
+        // A failed left side decides `&&` without the right one
+        let failed = results(&[("probe1", false)]);
+        assert_eq!(expr.eval(&failed), Some(false));
+        assert_eq!(expr.next_needed(&failed), None);
+
+        let matched = results(&[("probe1", true)]);
+        assert_eq!(expr.next_needed(&matched), Some("probe2"));
+        assert_eq!(
+            expr.eval(&results(&[("probe1", true), ("probe2", false)])),
+            Some(true)
+        );
+
+        let either = RequestExpr::parse("a || b").unwrap();
+        assert_eq!(either.eval(&results(&[("a", true)])), Some(true));
+        assert_eq!(either.next_needed(&results(&[("a", false)])), Some("b"));
+    }
+}
@@ -0,0 +1,377 @@
+//! Offline matcher tests embedded in YAML templates
+//!
+//! A template can carry canned responses and the outcome its matchers should
+//! produce for each one:
+//!
+//! ```yaml
+//! tests:
+//!   - name: vulnerable version
+//!     response:
+//!       status: 200
+//!       headers: { Server: Jenkins }
+//!       body: "<title>Dashboard [Jenkins]</title> Jenkins ver. 2.401"
+//!     expect: match
+//!     extracted: ["2.401"]
+//!   - name: unrelated login page
+//!     response:
+//!       fixture: fixtures/login.http   # raw HTTP response, relative to the template
+//!     expect: no-match
+//! ```
+//!
+//! Cases are evaluated with the same matchers and `match_all` logic used
+//! during scans, without sending any requests.
+
+use super::{include, YamlTemplateData};
+use crate::ai::validator::MockResponse;
+use crate::error::{Error, Result};
+use crate::matcher::{self, HttpResponse, MatchCondition, Matcher, MatcherType};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// One `tests:` entry
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TemplateTest {
+    /// Case name shown in reports
+    pub name: String,
+
+    /// Only evaluate this request (index into `http:`, `network:`, `grpc:`, `websocket:`, `ssh:`, then `snmp:`)
+    #[serde(default)]
+    pub request: Option<usize>,
+
+    /// Response the matchers are run against
+    pub response: CannedResponse,
+
+    /// Whether the template should fire
+    #[serde(default)]
+    pub expect: Expectation,
+
+    /// Values regex matchers must capture
+    #[serde(default)]
+    pub extracted: Vec<String>,
+}
+
+/// Response given inline or loaded from a fixture file
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CannedResponse {
+    /// Status code (defaults to 200)
+    #[serde(default = "default_status")]
+    pub status: u16,
+
+    /// Response headers
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Response body
+    #[serde(default)]
+    pub body: String,
+
+    /// Raw HTTP response file, relative to the template; replaces the fields above
+    pub fixture: Option<PathBuf>,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+impl CannedResponse {
+    /// Build the response, reading the fixture relative to `template_dir`
+    pub fn load(&self, template_dir: &Path) -> Result<HttpResponse> {
+        let Some(fixture) = &self.fixture else {
+            return Ok(HttpResponse {
+                status: self.status,
+                headers: self.headers.clone().into_iter().collect(),
+                body: self.body.clone().into_bytes(),
+                response_time: Default::default(),
+                segments: Vec::new(),
+                url: None,
+            });
+        };
+
+        let path = template_dir.join(fixture);
+        let raw = std::fs::read_to_string(&path).map_err(|e| {
+            Error::config(format!("Failed to read fixture {}: {}", path.display(), e))
+        })?;
+        let parsed = MockResponse::parse(&raw)
+            .map_err(|e| Error::Parse(format!("Invalid fixture {}: {}", path.display(), e)))?;
+        Ok(HttpResponse {
+            status: parsed.status,
+            headers: parsed.headers,
+            body: parsed.body.into_bytes(),
+            response_time: Default::default(),
+            segments: Vec::new(),
+            url: None,
+        })
+    }
+}
+
+/// Expected outcome of a test case
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Expectation {
+    /// The template fires
+    #[default]
+    Match,
+    /// The template does not fire
+    NoMatch,
+}
+
+/// Outcome of one test case
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseResult {
+    /// Case name
+    pub name: String,
+    /// Whether the case behaved as expected
+    pub passed: bool,
+    /// Whether the template fired
+    pub matched: bool,
+    /// Values captured by regex matchers that fired
+    pub extracted: Vec<String>,
+    /// Why the case failed
+    pub reason: Option<String>,
+}
+
+/// Self-test results for one template
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    /// Template file
+    pub template: PathBuf,
+    /// Result of every case, in template order
+    pub cases: Vec<CaseResult>,
+}
+
+impl SelfTestReport {
+    /// Whether every case passed; in strict mode a template also needs at least one case
+    pub fn passed(&self, strict: bool) -> bool {
+        !(strict && self.cases.is_empty()) && self.cases.iter().all(|c| c.passed)
+    }
+}
+
+/// Matchers evaluated for one request, as during a scan
+struct MatcherSet {
+    matchers: Vec<Matcher>,
+    condition: MatchCondition,
+}
+
+/// Run the `tests:` cases of the template at `path`
+pub fn run(path: &Path, snippets_dir: Option<&Path>) -> Result<SelfTestReport> {
+    let composed = include::compose(path, snippets_dir)?;
+    let data: YamlTemplateData = serde_yaml::from_value(composed.value)?;
+    let template_dir = path.parent().unwrap_or(Path::new("."));
+    let sets = matcher_sets(&data);
+
+    let cases = data
+        .tests
+        .iter()
+        .map(|case| run_case(case, &sets, template_dir))
+        .collect();
+    Ok(SelfTestReport {
+        template: path.to_path_buf(),
+        cases,
+    })
+}
+
+/// Matchers for each `http:`, `network:`, `grpc:`, `websocket:`, `ssh:`, `snmp:` and `browser:` request, falling back to the top level
+fn matcher_sets(data: &YamlTemplateData) -> Vec<Option<MatcherSet>> {
+    let set = |matchers: Option<&Vec<MatcherType>>, condition: Option<MatchCondition>| {
+        matchers.or(data.matchers.as_ref()).map(|m| MatcherSet {
+            matchers: m.iter().cloned().map(Matcher::new).collect(),
+            condition: condition
+                .or(data.matchers_condition)
+                .unwrap_or(MatchCondition::Or),
+        })
+    };
+
+    let mut sets: Vec<Option<MatcherSet>> = Vec::new();
+    for spec in data.http.iter().flatten() {
+        sets.push(set(spec.matchers.as_ref(), spec.matchers_condition));
+    }
+    for spec in data.network.iter().flatten() {
+        sets.push(set(spec.matchers.as_ref(), spec.matchers_condition));
+    }
+    for spec in data.grpc.iter().flatten() {
+        sets.push(set(spec.matchers.as_ref(), spec.matchers_condition));
+    }
+    for spec in data.websocket.iter().flatten() {
+        sets.push(set(spec.matchers.as_ref(), spec.matchers_condition));
+    }
+    for spec in data.ssh.iter().flatten() {
+        sets.push(set(spec.matchers.as_ref(), spec.matchers_condition));
+    }
+    for spec in data.snmp.iter().flatten() {
+        sets.push(set(spec.matchers.as_ref(), spec.matchers_condition));
+    }
+    for spec in data.browser.iter().flatten() {
+        sets.push(set(spec.matchers.as_ref(), spec.matchers_condition));
+    }
+    if sets.is_empty() {
+        sets.push(set(None, None));
+    }
+    sets
+}
+
+fn run_case(case: &TemplateTest, sets: &[Option<MatcherSet>], template_dir: &Path) -> CaseResult {
+    let mut result = CaseResult {
+        name: case.name.clone(),
+        passed: false,
+        matched: false,
+        extracted: Vec::new(),
+        reason: None,
+    };
+    if let Err(reason) = evaluate(case, sets, template_dir, &mut result) {
+        result.reason = Some(reason);
+    } else {
+        result.passed = true;
+    }
+    result
+}
+
+fn evaluate(
+    case: &TemplateTest,
+    sets: &[Option<MatcherSet>],
+    template_dir: &Path,
+    result: &mut CaseResult,
+) -> std::result::Result<(), String> {
+    let response = case
+        .response
+        .load(template_dir)
+        .map_err(|e| e.to_string())?;
+    let selected: Vec<&MatcherSet> = match case.request {
+        Some(index) => vec![sets
+            .get(index)
+            .ok_or_else(|| format!("Template has no request #{}", index))?
+            .as_ref()
+            .ok_or_else(|| format!("Request #{} has no matchers", index))?],
+        None => sets.iter().flatten().collect(),
+    };
+    if selected.is_empty() {
+        return Err("Template has no matchers".to_string());
+    }
+
+    for set in selected {
+        if matcher::match_all(&set.matchers, &response, set.condition).map_err(|e| e.to_string())? {
+            result.matched = true;
+            for m in &set.matchers {
+                result
+                    .extracted
+                    .extend(m.extract(&response).map_err(|e| e.to_string())?);
+            }
+        }
+    }
+
+    match (case.expect, result.matched) {
+        (Expectation::Match, false) => return Err("Expected a match, template did not fire".into()),
+        (Expectation::NoMatch, true) => return Err("Expected no match, template fired".into()),
+        _ => {}
+    }
+    if let Some(missing) = case
+        .extracted
+        .iter()
+        .find(|value| !result.extracted.contains(value))
+    {
+        return Err(format!(
+            "Expected extracted value {:?}, got {:?}",
+            missing, result.extracted
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = r#"
+id: jenkins-version
+name: Jenkins version
+author:
+  name: t
+severity: info
+description: Jenkins version disclosure
+language: yaml
+http:
+  - path: ["/"]
+    matchers-condition: and
+    matchers:
+      - type: status
+        status: [200]
+      - type: regex
+        regex: ['Jenkins ver\. ([0-9.]+)']
+        group: 1
+"#;
+
+    fn write_template(dir: &Path, tests: &str) -> PathBuf {
+        let path = dir.join("jenkins-version.yaml");
+        std::fs::write(&path, format!("{}tests:\n{}", TEMPLATE, tests)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_cases_pass_and_extract_values() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("login.http"),
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n<form>Sign in</form>",
+        )
+        .unwrap();
+        let path = write_template(
+            dir.path(),
+            r#"  - name: vulnerable
+    response:
+      body: "footer: Jenkins ver. 2.401"
+    extracted: ["2.401"]
+  - name: login page
+    response:
+      fixture: login.http
+    expect: no-match
+"#,
+        );
+
+        let report = run(&path, None).unwrap();
+        assert!(report.passed(true), "{:?}", report);
+        assert_eq!(report.cases[0].extracted, vec!["2.401".to_string()]);
+        assert!(!report.cases[1].matched);
+    }
+
+    #[test]
+    fn test_regressions_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_template(
+            dir.path(),
+            r#"  - name: wrong status
+    response:
+      status: 404
+      body: "Jenkins ver. 2.401"
+  - name: wrong version
+    response:
+      body: "Jenkins ver. 2.300"
+    extracted: ["2.401"]
+  - name: missing fixture
+    response:
+      fixture: nope.http
+"#,
+        );
+
+        let report = run(&path, None).unwrap();
+        assert!(!report.passed(false));
+        let reasons: Vec<&str> = report
+            .cases
+            .iter()
+            .map(|c| c.reason.as_deref().unwrap())
+            .collect();
+        assert_eq!(reasons[0], "Expected a match, template did not fire");
+        assert!(reasons[1].starts_with("Expected extracted value \"2.401\""));
+        assert!(reasons[2].contains("nope.http"));
+    }
+
+    #[test]
+    fn test_strict_mode_requires_cases() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("untested.yaml");
+        std::fs::write(&path, TEMPLATE).unwrap();
+
+        let report = run(&path, None).unwrap();
+        assert!(report.passed(false));
+        assert!(!report.passed(true));
+    }
+}
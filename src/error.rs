@@ -1,7 +1,27 @@
 //! Error types for CERT-X-GEN
 //!
 //! Comprehensive error handling system with context-rich error messages.
+//!
+//! Every variant has a stable code (`E0001`), shown as a prefix of its
+//! message, and a one-line remediation hint. Codes are grouped by area:
+//!
+//! | Range | Area |
+//! |-------|------|
+//! | `E00xx` | Configuration and input validation |
+//! | `E01xx` | Templates and matchers |
+//! | `E02xx` | Network, targets and protocols |
+//! | `E03xx` | Parsing, serialization and files |
+//! | `E04xx` | Plugins |
+//! | `E05xx` | Scheduling, execution and sandboxing |
+//! | `E06xx` | Authentication and authorization |
+//! | `E07xx` | Storage and metrics |
+//! | `E08xx` | Distributed scanning |
+//! | `E09xx` | AI providers |
+//! | `E99xx` | Internal errors |
+//!
+//! An error with context keeps the code and hint of the error it wraps.
 
+use serde::Serialize;
 use std::io;
 use std::path::PathBuf;
 use thiserror::Error;
@@ -13,11 +33,11 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[derive(Error, Debug)]
 pub enum Error {
     /// Configuration errors
-    #[error("Configuration error: {0}")]
+    #[error("[E0001] Configuration error: {0}")]
     Config(String),
 
     /// Template errors
-    #[error("Template error in {template}: {message}")]
+    #[error("[E0100] Template error in {template}: {message}")]
     Template {
         /// Template ID or path
         template: String,
@@ -26,11 +46,11 @@ pub enum Error {
     },
 
     /// Template not found
-    #[error("Template not found: {0}")]
+    #[error("[E0101] Template not found: {0}")]
     TemplateNotFound(String),
 
     /// Template validation failed
-    #[error("Template validation failed for {template}: {reason}")]
+    #[error("[E0102] Template validation failed for {template}: {reason}")]
     TemplateValidation {
         /// Template ID
         template: String,
@@ -39,11 +59,11 @@ pub enum Error {
     },
 
     /// Template execution error
-    #[error("Template execution failed: {0}")]
+    #[error("[E0103] Template execution failed: {0}")]
     TemplateExecution(String),
 
     /// Template compilation error
-    #[error("Template compilation failed for {template}: {error}")]
+    #[error("[E0104] Template compilation failed for {template}: {error}")]
     TemplateCompilation {
         /// Template path
         template: PathBuf,
@@ -51,16 +71,25 @@ pub enum Error {
         error: String,
     },
 
+    /// Output template rendering error
+    #[error("[E0105] Output template {template} failed to render: {reason}")]
+    TemplateRender {
+        /// Output template path
+        template: PathBuf,
+        /// Rendering failure reason
+        reason: String,
+    },
+
     /// Network errors
-    #[error("Network error: {0}")]
+    #[error("[E0200] Network error: {0}")]
     Network(String),
 
     /// HTTP request error
-    #[error("HTTP request failed: {0}")]
+    #[error("[E0201] HTTP request failed: {0}")]
     HttpRequest(#[from] reqwest::Error),
 
     /// Target errors
-    #[error("Invalid target: {target} - {reason}")]
+    #[error("[E0202] Invalid target: {target} - {reason}")]
     InvalidTarget {
         /// Target specification
         target: String,
@@ -69,31 +98,31 @@ pub enum Error {
     },
 
     /// Target unreachable
-    #[error("Target unreachable: {0}")]
+    #[error("[E0203] Target unreachable: {0}")]
     TargetUnreachable(String),
 
     /// Parsing errors
-    #[error("Parse error: {0}")]
+    #[error("[E0300] Parse error: {0}")]
     Parse(String),
 
     /// YAML parsing error
-    #[error("YAML parse error: {0}")]
+    #[error("[E0301] YAML parse error: {0}")]
     YamlParse(#[from] serde_yaml::Error),
 
     /// JSON parsing error
-    #[error("JSON parse error: {0}")]
+    #[error("[E0302] JSON parse error: {0}")]
     JsonParse(#[from] serde_json::Error),
 
     /// IO errors
-    #[error("IO error: {0}")]
+    #[error("[E0310] IO error: {0}")]
     Io(#[from] io::Error),
 
     /// File not found
-    #[error("File not found: {0}")]
+    #[error("[E0311] File not found: {0}")]
     FileNotFound(PathBuf),
 
     /// Plugin errors
-    #[error("Plugin error in {plugin}: {message}")]
+    #[error("[E0400] Plugin error in {plugin}: {message}")]
     Plugin {
         /// Plugin name
         plugin: String,
@@ -102,15 +131,15 @@ pub enum Error {
     },
 
     /// Plugin not found
-    #[error("Plugin not found: {0}")]
+    #[error("[E0401] Plugin not found: {0}")]
     PluginNotFound(String),
 
     /// Scheduler errors
-    #[error("Scheduler error: {0}")]
+    #[error("[E0500] Scheduler error: {0}")]
     Scheduler(String),
 
     /// Resource limit exceeded
-    #[error("Resource limit exceeded: {resource} (limit: {limit}, current: {current})")]
+    #[error("[E0501] Resource limit exceeded: {resource} (limit: {limit}, current: {current})")]
     ResourceLimitExceeded {
         /// Resource type
         resource: String,
@@ -121,46 +150,55 @@ pub enum Error {
     },
 
     /// Timeout error
-    #[error("Operation timed out after {duration}")]
+    #[error("[E0209] Operation timed out after {duration}")]
     Timeout {
         /// Timeout duration
         duration: String,
     },
 
+    /// Request budget spent (`max-requests`, `--max-requests-per-host`, `--max-requests-total`)
+    #[error("[E0210] Request budget exceeded: {0}")]
+    RequestBudgetExceeded(String),
+
     /// Execution errors
-    #[error("Execution error: {0}")]
+    #[error("[E0502] Execution error: {0}")]
     Execution(String),
 
     /// Command execution error
-    #[error("Command execution error: {0}")]
+    #[error("[E0503] Command execution error: {0}")]
     Command(String),
 
-    /// Sandbox violation
-    #[error("Sandbox violation: {0}")]
-    SandboxViolation(String),
+    /// Template made a syscall the sandbox forbids
+    #[error("[E0504] Sandbox violation: template {template_id} made forbidden syscall {syscall}")]
+    SandboxViolation {
+        /// Template ID
+        template_id: String,
+        /// Name of the syscall
+        syscall: String,
+    },
 
     /// Rate limit exceeded
-    #[error("Rate limit exceeded: {0}")]
+    #[error("[E0208] Rate limit exceeded: {0}")]
     RateLimitExceeded(String),
 
     /// Authentication error
-    #[error("Authentication failed: {0}")]
+    #[error("[E0600] Authentication failed: {0}")]
     Authentication(String),
 
     /// Authorization error
-    #[error("Authorization failed: {0}")]
+    #[error("[E0601] Authorization failed: {0}")]
     Authorization(String),
 
     /// Validation errors
-    #[error("Validation error: {0}")]
+    #[error("[E0002] Validation error: {0}")]
     Validation(String),
 
     /// Serialization error
-    #[error("Serialization error: {0}")]
+    #[error("[E0303] Serialization error: {0}")]
     Serialization(String),
 
     /// Output format error
-    #[error("Output format error: {format} - {message}")]
+    #[error("[E0320] Output format error: {format} - {message}")]
     OutputFormat {
         /// Output format
         format: String,
@@ -168,8 +206,22 @@ pub enum Error {
         message: String,
     },
 
+    /// Some of several output formats could not be written
+    #[error(
+        "[E0321] Failed to write {} output format(s): {}; written: {}",
+        .failed.len(),
+        .failed.join("; "),
+        list_or_none(.written)
+    )]
+    OutputWrite {
+        /// Formats written, with their files
+        written: Vec<String>,
+        /// Formats that failed, with the reason
+        failed: Vec<String>,
+    },
+
     /// Protocol error
-    #[error("Protocol error for {protocol}: {message}")]
+    #[error("[E0204] Protocol error for {protocol}: {message}")]
     Protocol {
         /// Protocol name
         protocol: String,
@@ -178,7 +230,7 @@ pub enum Error {
     },
 
     /// DNS resolution error
-    #[error("DNS resolution failed for {hostname}: {error}")]
+    #[error("[E0205] DNS resolution failed for {hostname}: {error}")]
     DnsResolution {
         /// Hostname
         hostname: String,
@@ -187,35 +239,35 @@ pub enum Error {
     },
 
     /// TLS error
-    #[error("TLS error: {0}")]
+    #[error("[E0206] TLS error: {0}")]
     Tls(String),
 
     /// Certificate error
-    #[error("Certificate error: {0}")]
+    #[error("[E0207] Certificate error: {0}")]
     Certificate(String),
 
     /// Matcher error
-    #[error("Matcher error: {0}")]
+    #[error("[E0106] Matcher error: {0}")]
     Matcher(String),
 
     /// Database error
-    #[error("Database error: {0}")]
+    #[error("[E0700] Database error: {0}")]
     Database(String),
 
     /// Cache error
-    #[error("Cache error: {0}")]
+    #[error("[E0701] Cache error: {0}")]
     Cache(String),
 
     /// Metrics error
-    #[error("Metrics error: {0}")]
+    #[error("[E0702] Metrics error: {0}")]
     Metrics(String),
 
     /// Distributed system error
-    #[error("Distributed system error: {0}")]
+    #[error("[E0800] Distributed system error: {0}")]
     Distributed(String),
 
     /// Worker error
-    #[error("Worker {worker_id} error: {message}")]
+    #[error("[E0801] Worker {worker_id} error: {message}")]
     Worker {
         /// Worker ID
         worker_id: String,
@@ -224,23 +276,23 @@ pub enum Error {
     },
 
     /// Coordinator error
-    #[error("Coordinator error: {0}")]
+    #[error("[E0802] Coordinator error: {0}")]
     Coordinator(String),
 
     /// AI/LLM errors
-    #[error("AI error: {0}")]
+    #[error("[E0900] AI error: {0}")]
     Ai(String),
 
     /// Internal error
-    #[error("Internal error: {0}")]
+    #[error("[E9900] Internal error: {0}")]
     Internal(String),
 
     /// Not implemented
-    #[error("Not implemented: {0}")]
+    #[error("[E9901] Not implemented: {0}")]
     NotImplemented(String),
 
     /// Generic error with context
-    #[error("{context}: {source}")]
+    #[error("[{}] {context}: {}", .source.code(), .source.message())]
     WithContext {
         /// Error context
         context: String,
@@ -249,10 +301,19 @@ pub enum Error {
     },
 
     /// Multiple errors
-    #[error("Multiple errors occurred: {}", .0.len())]
+    #[error("[E9902] Multiple errors occurred: {}", .0.len())]
     Multiple(Vec<Error>),
 }
 
+/// `items` joined with commas, or `none`
+fn list_or_none(items: &[String]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+
 // Implement From for prometheus::Error
 impl From<prometheus::Error> for Error {
     fn from(err: prometheus::Error) -> Self {
@@ -309,12 +370,138 @@ impl Error {
         Error::Command(message.into())
     }
 
+    /// Stable machine-readable code, such as `E0001`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Config(_) => "E0001",
+            Error::Validation(_) => "E0002",
+            Error::Template { .. } => "E0100",
+            Error::TemplateNotFound(_) => "E0101",
+            Error::TemplateValidation { .. } => "E0102",
+            Error::TemplateExecution(_) => "E0103",
+            Error::TemplateCompilation { .. } => "E0104",
+            Error::TemplateRender { .. } => "E0105",
+            Error::Matcher(_) => "E0106",
+            Error::Network(_) => "E0200",
+            Error::HttpRequest(_) => "E0201",
+            Error::InvalidTarget { .. } => "E0202",
+            Error::TargetUnreachable(_) => "E0203",
+            Error::Protocol { .. } => "E0204",
+            Error::DnsResolution { .. } => "E0205",
+            Error::Tls(_) => "E0206",
+            Error::Certificate(_) => "E0207",
+            Error::RateLimitExceeded(_) => "E0208",
+            Error::Timeout { .. } => "E0209",
+            Error::RequestBudgetExceeded(_) => "E0210",
+            Error::Parse(_) => "E0300",
+            Error::YamlParse(_) => "E0301",
+            Error::JsonParse(_) => "E0302",
+            Error::Serialization(_) => "E0303",
+            Error::Io(_) => "E0310",
+            Error::FileNotFound(_) => "E0311",
+            Error::OutputFormat { .. } => "E0320",
+            Error::OutputWrite { .. } => "E0321",
+            Error::Plugin { .. } => "E0400",
+            Error::PluginNotFound(_) => "E0401",
+            Error::Scheduler(_) => "E0500",
+            Error::ResourceLimitExceeded { .. } => "E0501",
+            Error::Execution(_) => "E0502",
+            Error::Command(_) => "E0503",
+            Error::SandboxViolation { .. } => "E0504",
+            Error::Authentication(_) => "E0600",
+            Error::Authorization(_) => "E0601",
+            Error::Database(_) => "E0700",
+            Error::Cache(_) => "E0701",
+            Error::Metrics(_) => "E0702",
+            Error::Distributed(_) => "E0800",
+            Error::Worker { .. } => "E0801",
+            Error::Coordinator(_) => "E0802",
+            Error::Ai(_) => "E0900",
+            Error::Internal(_) => "E9900",
+            Error::NotImplemented(_) => "E9901",
+            Error::Multiple(_) => "E9902",
+            Error::WithContext { source, .. } => source.code(),
+        }
+    }
+
+    /// One-line suggestion for fixing the error
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Error::Config(_) => "Check the configuration file and profile, or regenerate one with 'cxg config generate'",
+            Error::Validation(_) => "Check the value against the option's documented format",
+            Error::Template { .. } => "Fix the template named in the message and reload it",
+            Error::TemplateNotFound(_) => "Run 'cxg template list' or 'cxg search' to find the template ID, or 'cxg template update'",
+            Error::TemplateValidation { .. } => "Run 'cxg template validate' on the template for details",
+            Error::TemplateExecution(_) => "Re-run with -vvv to see the template's output",
+            Error::TemplateCompilation { .. } => "Run 'cxg doctor' to check the compiler, then fix the reported source error",
+            Error::TemplateRender { .. } => "Check the output template's syntax and the fields it references",
+            Error::Matcher(_) => "Check the matcher's type, part and patterns (regexes must compile)",
+            Error::Network(_) => "Check connectivity and proxy settings, then retry",
+            Error::HttpRequest(_) => "Check the URL, TLS settings and proxy, then retry",
+            Error::InvalidTarget { .. } => "Use an IP, hostname, CIDR range or URL, optionally with :port",
+            Error::TargetUnreachable(_) => "Check the target is up and reachable from this host",
+            Error::Protocol { .. } => "Check the service on that port speaks the expected protocol",
+            Error::DnsResolution { .. } => "Check the hostname spelling and the configured resolvers",
+            Error::Tls(_) => "Check the target's TLS configuration, or try plain HTTP",
+            Error::Certificate(_) => "Check the certificate chain, or disable verification for testing",
+            Error::RateLimitExceeded(_) => "Lower --rate-limit or --parallel-targets and retry later",
+            Error::Timeout { .. } => "Raise --timeout or lower --parallel-targets",
+            Error::RequestBudgetExceeded(_) => "Raise the request budget if the rules of engagement allow, or run fewer templates",
+            Error::Parse(_) => "Check the input's syntax at the reported location",
+            Error::YamlParse(_) => "Fix the YAML syntax at the reported line and column",
+            Error::JsonParse(_) => "Fix the JSON syntax at the reported line and column",
+            Error::Serialization(_) => "Report this with the data that failed to serialize",
+            Error::Io(_) => "Check the path exists and has the required permissions",
+            Error::FileNotFound(_) => "Check the path, or create the file first",
+            Error::OutputFormat { .. } => "Use one of the formats listed by 'cxg scan --help'",
+            Error::OutputWrite { .. } => "The written formats are complete; fix the failed ones and re-run",
+            Error::Plugin { .. } => "Check the plugin's configuration, or disable it",
+            Error::PluginNotFound(_) => "Check the plugin name and that it is installed",
+            Error::Scheduler(_) => "Lower --parallel-targets, or re-run with -vvv for details",
+            Error::ResourceLimitExceeded { .. } => "Raise the limit in the configuration, or scan fewer targets at once",
+            Error::Execution(_) => "Re-run with -vvv for details",
+            Error::Command(_) => "Check the command is installed and on PATH ('cxg doctor')",
+            Error::SandboxViolation { .. } => "Review the template; it tried an operation the sandbox forbids",
+            Error::Authentication(_) => "Check the credentials or API key",
+            Error::Authorization(_) => "Check the account has permission for this operation",
+            Error::Database(_) => "Check the database is reachable and its schema is current",
+            Error::Cache(_) => "Clear the cache directory and retry",
+            Error::Metrics(_) => "Check the metrics configuration, or disable metrics",
+            Error::Distributed(_) => "Check the coordinator and workers can reach each other",
+            Error::Worker { .. } => "Check the worker's logs, then restart it",
+            Error::Coordinator(_) => "Check the coordinator's logs, then restart it",
+            Error::Ai(_) => "Check the AI provider settings and API key",
+            Error::Internal(_) => "This is a bug; please report it with the -vvv output",
+            Error::NotImplemented(_) => "This feature is not available yet",
+            Error::Multiple(_) => "Fix each of the listed errors",
+            Error::WithContext { source, .. } => source.hint(),
+        }
+    }
+
+    /// The message without its `[code]` prefix
+    pub fn message(&self) -> String {
+        let text = self.to_string();
+        match text.strip_prefix(&format!("[{}] ", self.code())) {
+            Some(message) => message.to_string(),
+            None => text,
+        }
+    }
+
+    /// Code, message and hint, for machine-readable output
+    pub fn report(&self) -> ErrorReport {
+        ErrorReport {
+            code: self.code(),
+            message: self.message(),
+            hint: self.hint(),
+        }
+    }
+
     /// Check if error is fatal (should stop execution)
     pub fn is_fatal(&self) -> bool {
         matches!(
             self,
             Error::Internal(_)
-                | Error::SandboxViolation(_)
+                | Error::SandboxViolation { .. }
                 | Error::ResourceLimitExceeded { .. }
                 | Error::Coordinator(_)
         )
@@ -333,6 +520,17 @@ impl Error {
     }
 }
 
+/// Serializable form of an [`Error`] (`--output-errors json`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorReport {
+    /// Error code, such as `E0001`
+    pub code: &'static str,
+    /// Message without the code prefix
+    pub message: String,
+    /// Remediation hint
+    pub hint: &'static str,
+}
+
 /// Trait for adding context to results
 pub trait ResultExt<T> {
     /// Add context to the error
@@ -376,6 +574,170 @@ mod tests {
         assert!(!Error::Internal("panic".to_string()).is_retryable());
     }
 
+    /// One error of every variant with its expected code
+    fn every_variant() -> Vec<(Error, &'static str)> {
+        let s = || "x".to_string();
+        let http = reqwest::Client::new().get("not a url").build().unwrap_err();
+        let yaml = serde_yaml::from_str::<u8>("[").unwrap_err();
+        let json = serde_json::from_str::<u8>("[").unwrap_err();
+        vec![
+            (Error::Config(s()), "E0001"),
+            (Error::Validation(s()), "E0002"),
+            (Error::template("t", "m"), "E0100"),
+            (Error::TemplateNotFound(s()), "E0101"),
+            (
+                Error::TemplateValidation {
+                    template: s(),
+                    reason: s(),
+                },
+                "E0102",
+            ),
+            (Error::TemplateExecution(s()), "E0103"),
+            (
+                Error::TemplateCompilation {
+                    template: PathBuf::from("t.rs"),
+                    error: s(),
+                },
+                "E0104",
+            ),
+            (
+                Error::TemplateRender {
+                    template: PathBuf::from("t.hbs"),
+                    reason: s(),
+                },
+                "E0105",
+            ),
+            (Error::Matcher(s()), "E0106"),
+            (Error::Network(s()), "E0200"),
+            (Error::HttpRequest(http), "E0201"),
+            (Error::invalid_target("t", "r"), "E0202"),
+            (Error::TargetUnreachable(s()), "E0203"),
+            (
+                Error::Protocol {
+                    protocol: s(),
+                    message: s(),
+                },
+                "E0204",
+            ),
+            (
+                Error::DnsResolution {
+                    hostname: s(),
+                    error: s(),
+                },
+                "E0205",
+            ),
+            (Error::Tls(s()), "E0206"),
+            (Error::Certificate(s()), "E0207"),
+            (Error::RateLimitExceeded(s()), "E0208"),
+            (Error::Timeout { duration: s() }, "E0209"),
+            (Error::RequestBudgetExceeded(s()), "E0210"),
+            (Error::Parse(s()), "E0300"),
+            (Error::YamlParse(yaml), "E0301"),
+            (Error::JsonParse(json), "E0302"),
+            (Error::Serialization(s()), "E0303"),
+            (Error::Io(io::Error::other("x")), "E0310"),
+            (Error::FileNotFound(PathBuf::from("f")), "E0311"),
+            (
+                Error::OutputFormat {
+                    format: s(),
+                    message: s(),
+                },
+                "E0320",
+            ),
+            (
+                Error::OutputWrite {
+                    written: vec![s()],
+                    failed: vec![s()],
+                },
+                "E0321",
+            ),
+            (
+                Error::Plugin {
+                    plugin: s(),
+                    message: s(),
+                },
+                "E0400",
+            ),
+            (Error::PluginNotFound(s()), "E0401"),
+            (Error::Scheduler(s()), "E0500"),
+            (Error::resource_limit("memory", "1", "2"), "E0501"),
+            (Error::Execution(s()), "E0502"),
+            (Error::command("x"), "E0503"),
+            (
+                Error::SandboxViolation {
+                    template_id: s(),
+                    syscall: s(),
+                },
+                "E0504",
+            ),
+            (Error::Authentication(s()), "E0600"),
+            (Error::Authorization(s()), "E0601"),
+            (Error::Database(s()), "E0700"),
+            (Error::Cache(s()), "E0701"),
+            (Error::Metrics(s()), "E0702"),
+            (Error::Distributed(s()), "E0800"),
+            (
+                Error::Worker {
+                    worker_id: s(),
+                    message: s(),
+                },
+                "E0801",
+            ),
+            (Error::Coordinator(s()), "E0802"),
+            (Error::Ai(s()), "E0900"),
+            (Error::Internal(s()), "E9900"),
+            (Error::NotImplemented(s()), "E9901"),
+            (Error::Multiple(vec![Error::Cache(s())]), "E9902"),
+        ]
+    }
+
+    #[test]
+    fn test_every_variant_has_code_and_hint() {
+        let variants = every_variant();
+        let mut seen = std::collections::HashSet::new();
+        for (error, code) in &variants {
+            assert_eq!(error.code(), *code, "{:?}", error);
+            assert!(seen.insert(*code), "duplicate code {}", code);
+            assert!(!error.hint().is_empty(), "{} has no hint", code);
+            assert!(!error.hint().contains('\n'), "{} hint spans lines", code);
+            assert!(
+                error.to_string().starts_with(&format!("[{}] ", code)),
+                "{}",
+                error
+            );
+        }
+        // Every variant but WithContext, which takes its source's code
+        assert_eq!(variants.len(), 47);
+    }
+
+    #[test]
+    fn test_context_keeps_source_code_and_hint() {
+        let source = Error::TemplateNotFound("cve-2024-1234".to_string());
+        let hint = source.hint();
+        let err = source.context("Failed to read template");
+
+        assert_eq!(err.code(), "E0101");
+        assert_eq!(err.hint(), hint);
+        assert_eq!(
+            err.to_string(),
+            "[E0101] Failed to read template: Template not found: cve-2024-1234"
+        );
+    }
+
+    #[test]
+    fn test_report_serializes_code_message_and_hint() {
+        let err = Error::config("missing api key");
+        let json = serde_json::to_value(err.report()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "code": "E0001",
+                "message": "Configuration error: missing api key",
+                "hint": err.hint(),
+            })
+        );
+    }
+
     #[test]
     fn test_error_is_fatal() {
         assert!(Error::Internal("critical failure".to_string()).is_fatal());
@@ -1,16 +1,180 @@
 //! Execution orchestrator for running templates against targets
 
-use crate::config::Config;
+use crate::config::{Config, EngineRuntimeConfig, SchedulingStrategy};
 use crate::core::ScanJob;
 use crate::error::{Error, Result};
 use crate::flows::FlowExecutor;
-use crate::network::NetworkClient;
+use crate::metrics::{profiler, requests};
+use crate::network::{rate_limit, trace, NetworkClient};
 use crate::progress::get_progress;
 use crate::session::SessionManager;
-use crate::types::Finding;
-use futures::stream::{self, StreamExt};
+use crate::template::{missing_dependencies, DependencyPlan, ExecutionCost, TemplateLoader};
+use crate::types::{ConcurrencyChange, Finding};
+use futures::future::{BoxFuture, FutureExt};
+use futures::stream::{self, FuturesUnordered, StreamExt};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::Semaphore;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Concurrency limit and cost hint of one template engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EngineLimit {
+    /// Most templates of this engine running at once (`None` = unlimited)
+    pub max_concurrency: Option<usize>,
+    /// Relative cost of one execution
+    pub cost: ExecutionCost,
+}
+
+/// Per-engine limits keyed by engine name, which matches the template language
+#[derive(Debug, Clone, Default)]
+pub struct EngineLimits {
+    limits: BTreeMap<String, EngineLimit>,
+}
+
+impl EngineLimits {
+    /// Engine defaults, with `engines.<name>.max_concurrency` taking precedence
+    pub fn from_loader(
+        loader: &TemplateLoader,
+        overrides: &BTreeMap<String, EngineRuntimeConfig>,
+    ) -> Self {
+        let limits = loader
+            .engines()
+            .iter()
+            .map(|engine| {
+                let name = engine.name().to_string();
+                let max_concurrency = overrides
+                    .get(&name)
+                    .and_then(|c| c.max_concurrency)
+                    .or_else(|| engine.max_concurrency());
+                let limit = EngineLimit {
+                    max_concurrency,
+                    cost: engine.cost_hint(),
+                };
+                (name, limit)
+            })
+            .collect();
+        Self { limits }
+    }
+
+    /// Limit for one engine
+    pub fn get(&self, engine: &str) -> Option<&EngineLimit> {
+        self.limits.get(engine)
+    }
+
+    /// All engines and their limits, by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &EngineLimit)> {
+        self.limits
+            .iter()
+            .map(|(name, limit)| (name.as_str(), limit))
+    }
+}
+
+/// Per-scan engine semaphores, tracking how many executions overlapped
+struct EngineGate {
+    slots: HashMap<String, EngineSlot>,
+}
+
+struct EngineSlot {
+    semaphore: Option<Semaphore>,
+    active: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+/// Holds an engine slot until the template finishes
+struct EngineGuard<'a> {
+    slot: &'a EngineSlot,
+    _permit: Option<SemaphorePermit<'a>>,
+}
+
+impl Drop for EngineGuard<'_> {
+    fn drop(&mut self) {
+        self.slot.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl EngineGate {
+    fn new(limits: &EngineLimits) -> Self {
+        let slots = limits
+            .iter()
+            .map(|(name, limit)| {
+                let slot = EngineSlot {
+                    semaphore: limit.max_concurrency.map(|n| Semaphore::new(n.max(1))),
+                    active: AtomicUsize::new(0),
+                    peak: AtomicUsize::new(0),
+                };
+                (name.to_string(), slot)
+            })
+            .collect();
+        Self { slots }
+    }
+
+    /// Wait for a slot; engines without a registered limit are not gated
+    async fn enter(&self, engine: &str) -> Result<Option<EngineGuard<'_>>> {
+        let Some(slot) = self.slots.get(engine) else {
+            return Ok(None);
+        };
+        let permit = match &slot.semaphore {
+            Some(semaphore) => Some(
+                semaphore
+                    .acquire()
+                    .await
+                    .map_err(|e| Error::Scheduler(e.to_string()))?,
+            ),
+            None => None,
+        };
+        let active = slot.active.fetch_add(1, Ordering::SeqCst) + 1;
+        slot.peak.fetch_max(active, Ordering::SeqCst);
+        Ok(Some(EngineGuard {
+            slot,
+            _permit: permit,
+        }))
+    }
+
+    /// Peak concurrency of every engine that ran at least one template
+    fn peaks(&self) -> BTreeMap<String, usize> {
+        self.slots
+            .iter()
+            .map(|(name, slot)| (name.clone(), slot.peak.load(Ordering::SeqCst)))
+            .filter(|(_, peak)| *peak > 0)
+            .collect()
+    }
+}
+
+/// State of one running scan job
+struct JobRun {
+    gate: EngineGate,
+    /// Work units not started because a scan request budget was spent
+    skipped: AtomicUsize,
+}
+
+/// Record a template skipped on `target` because a dependency reported nothing there
+fn skip_unmet_dependencies(
+    template: &dyn crate::template::Template,
+    target: &crate::types::Target,
+) {
+    tracing::debug!(
+        "Skipping {} on {}: depends-on templates {:?} reported nothing",
+        template.id(),
+        target.address,
+        template.metadata().depends_on
+    );
+    if let Some(progress) = get_progress() {
+        progress.template_done(&target.address, template.id(), 0);
+    }
+}
+
+/// What running a scan job used besides its findings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionUsage {
+    /// Peak number of templates running at once, per engine
+    pub engine_peaks: BTreeMap<String, usize>,
+    /// Template/target pairs skipped once `--max-requests-per-host` or
+    /// `--max-requests-total` was reached
+    pub skipped: usize,
+    /// Limits set by adaptive concurrency, starting with the initial one
+    pub concurrency_timeline: Vec<ConcurrencyChange>,
+}
 
 /// Executor for running scan jobs
 #[derive(Debug)]
@@ -20,6 +184,7 @@ pub struct Executor {
     session_manager: Arc<SessionManager>,
     flow_executor: Arc<FlowExecutor>,
     semaphore: Arc<Semaphore>,
+    engine_limits: EngineLimits,
 }
 
 impl Executor {
@@ -37,9 +202,20 @@ impl Executor {
             session_manager,
             flow_executor,
             semaphore,
+            engine_limits: EngineLimits::default(),
         })
     }
 
+    /// Enforce per-engine limits beneath the global parallelism settings
+    pub fn set_engine_limits(&mut self, limits: EngineLimits) {
+        self.engine_limits = limits;
+    }
+
+    /// Per-engine limits in effect
+    pub fn engine_limits(&self) -> &EngineLimits {
+        &self.engine_limits
+    }
+
     /// Get session manager
     pub fn session_manager(&self) -> &Arc<SessionManager> {
         &self.session_manager
@@ -52,19 +228,146 @@ impl Executor {
 
     /// Execute a scan job
     pub async fn execute(&self, job: &ScanJob) -> Result<Vec<Finding>> {
+        self.execute_with_usage(job)
+            .await
+            .map(|(findings, _)| findings)
+    }
+
+    /// Execute a scan job, also returning what it used
+    pub async fn execute_with_usage(
+        &self,
+        job: &ScanJob,
+    ) -> Result<(Vec<Finding>, ExecutionUsage)> {
         tracing::info!(
             "Executing scan job {} with {} targets and {} templates",
             job.id,
             job.targets.len(),
             job.templates.len()
         );
+        for (template, dependency) in missing_dependencies(&job.templates) {
+            tracing::info!(
+                "Template {} depends on {}, which is not in this scan; running it unconditionally",
+                template,
+                dependency
+            );
+        }
+
+        for template in &job.templates {
+            if let Some(rate) = template.metadata().rate_limit {
+                self.network_client
+                    .rate_limiter()
+                    .set_template_limit(template.id(), rate);
+            }
+        }
+
+        if let Some(concurrency) = self.network_client.concurrency() {
+            let bounds = concurrency.bounds();
+            tracing::info!(
+                "Adaptive concurrency: starting at {}, between {} and {}",
+                concurrency.limit(),
+                bounds.min,
+                bounds.max
+            );
+            if let Some(progress) = get_progress() {
+                progress.set_concurrency(concurrency.limit());
+            }
+        }
 
-        let findings = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+        let run = JobRun {
+            gate: EngineGate::new(&self.engine_limits),
+            skipped: AtomicUsize::new(0),
+        };
+        // Traced requests are filed under the job's id
+        let execution = async {
+            match self.config.execution.scheduling {
+                SchedulingStrategy::Interleaved => self.execute_interleaved(job, &run).await,
+                SchedulingStrategy::PerTarget => self.execute_per_target(job, &run).await,
+            }
+        };
+        let findings = trace::with_scan(&job.id.to_string(), execution).await?;
+
+        let peaks = run.gate.peaks();
+        for (engine, peak) in &peaks {
+            if let Some(limit) = self.engine_limits.get(engine) {
+                tracing::debug!(
+                    "Engine {} ({}) peak concurrency {} (limit {})",
+                    engine,
+                    limit.cost,
+                    peak,
+                    limit
+                        .max_concurrency
+                        .map_or("none".to_string(), |n| n.to_string())
+                );
+            }
+        }
+        let skipped = run.skipped.into_inner();
+        if skipped > 0 {
+            tracing::warn!(
+                "Request budget reached: skipped {} template/target pair(s)",
+                skipped
+            );
+        }
+        Ok((
+            findings,
+            ExecutionUsage {
+                engine_peaks: peaks,
+                skipped,
+                concurrency_timeline: self
+                    .network_client
+                    .concurrency()
+                    .map(|c| c.timeline())
+                    .unwrap_or_default(),
+            },
+        ))
+    }
+
+    /// Run the targets side by side, each through its own template queue
+    ///
+    /// At most `parallel_targets` targets are in flight: each is admitted in
+    /// turn and keeps its slot until its queue drains. Admitted targets start
+    /// their first templates at once, so every one of them progresses early,
+    /// and at most `parallel_templates` units run against any single target.
+    async fn execute_interleaved(&self, job: &ScanJob, run: &JobRun) -> Result<Vec<Finding>> {
+        let admission = Semaphore::new(self.config.execution.parallel_targets.max(1));
+        let findings = std::sync::Mutex::new(Vec::new());
+        // Boxed up front so the scan future is provably `Send` for the job queue
+        let targets: Vec<BoxFuture<'_, ()>> = (0..job.targets.len())
+            .map(|target_idx| {
+                let admission = &admission;
+                let findings = &findings;
+                async move {
+                    let result = match admission.acquire().await {
+                        Ok(_admitted) => {
+                            self.execute_templates_for_target(target_idx, job, run, findings)
+                                .await
+                        }
+                        Err(e) => Err(Error::Scheduler(e.to_string())),
+                    };
+                    if let Err(e) = result {
+                        let target = &job.targets[target_idx];
+                        tracing::error!("Error processing target {}: {}", target.address, e);
+                    }
+                }
+                .boxed()
+            })
+            .collect();
+        // The semaphore admits targets in order; polling them all keeps every slot busy
+        futures::future::join_all(targets).await;
+
+        Ok(findings.into_inner().unwrap())
+    }
+
+    /// Execute all templates for one target before moving to the next
+    async fn execute_per_target(&self, job: &ScanJob, run: &JobRun) -> Result<Vec<Finding>> {
+        let findings = std::sync::Mutex::new(Vec::new());
 
         // Process targets in parallel with semaphore control
-        stream::iter(&job.targets)
-            .map(|target| {
-                let findings = Arc::clone(&findings);
+        let units: Vec<BoxFuture<'_, ()>> = job
+            .targets
+            .iter()
+            .enumerate()
+            .map(|(target_idx, target)| {
+                let findings = &findings;
                 let executor = self;
 
                 async move {
@@ -79,15 +382,17 @@ impl Executor {
                     tracing::debug!("Processing target: {}", target.address);
 
                     // Execute all templates for this target
-                    match executor.execute_templates_for_target(target, job).await {
-                        Ok(target_findings) => {
-                            if !target_findings.is_empty() {
+                    match executor
+                        .execute_templates_for_target(target_idx, job, run, findings)
+                        .await
+                    {
+                        Ok(found) => {
+                            if found > 0 {
                                 tracing::info!(
                                     "Found {} findings for target {}",
-                                    target_findings.len(),
+                                    found,
                                     target.address
                                 );
-                                findings.lock().await.extend(target_findings);
                             }
                         }
                         Err(e) => {
@@ -95,85 +400,178 @@ impl Executor {
                         }
                     }
                 }
+                .boxed()
             })
+            .collect();
+        stream::iter(units)
             .buffer_unordered(self.config.execution.parallel_targets)
             .collect::<Vec<_>>()
             .await;
 
-        let findings = match Arc::try_unwrap(findings) {
-            Ok(mutex) => mutex.into_inner(),
-            Err(arc) => arc.blocking_lock().clone(),
-        };
-
-        Ok(findings)
+        Ok(findings.into_inner().unwrap())
     }
 
     /// Execute all templates for a single target
+    ///
+    /// A template starts as soon as its dependencies have finished on the
+    /// target, and the ready ones start in priority order, dependencies taking
+    /// the place of their most urgent dependent. Findings are added to
+    /// `findings` as each template finishes; returns how many there were.
     async fn execute_templates_for_target(
         &self,
+        target_idx: usize,
+        job: &ScanJob,
+        run: &JobRun,
+        findings: &std::sync::Mutex<Vec<Finding>>,
+    ) -> Result<usize> {
+        let target = &job.targets[target_idx];
+        let plan = DependencyPlan::new(&job.templates);
+        let mut pending: Vec<usize> = plan
+            .prioritize(&job.template_order(target_idx))
+            .into_iter()
+            .filter(|&idx| job.runs(job.templates[idx].as_ref(), target))
+            .collect();
+        // Templates not run on this target count as finished without findings
+        let mut finished = vec![true; job.templates.len()];
+        for &template_idx in &pending {
+            finished[template_idx] = false;
+        }
+        // Templates with findings on this target, which dependent templates wait for
+        let mut matched: HashSet<usize> = HashSet::new();
+        let mut running = FuturesUnordered::new();
+        let limit = self.config.execution.parallel_templates.max(1);
+        let mut found = 0;
+
+        loop {
+            while running.len() < limit {
+                let Some(position) = pending
+                    .iter()
+                    .position(|&idx| plan.requires(idx).iter().all(|&dep| finished[dep]))
+                else {
+                    break;
+                };
+                let template_idx = pending.remove(position);
+                let template = &job.templates[template_idx];
+                if !plan
+                    .requires(template_idx)
+                    .iter()
+                    .all(|dep| matched.contains(dep))
+                {
+                    skip_unmet_dependencies(template.as_ref(), target);
+                    finished[template_idx] = true;
+                    continue;
+                }
+                // Boxed so the scan future is provably `Send` for the job queue
+                let unit: BoxFuture<'_, (usize, Result<Vec<Finding>>)> = self
+                    .execute_work_unit(template.as_ref(), target, job, run)
+                    .map(move |result| (template_idx, result))
+                    .boxed();
+                running.push(unit);
+            }
+
+            // Collect successful results as they come in
+            let Some((template_idx, result)) = running.next().await else {
+                break;
+            };
+            finished[template_idx] = true;
+            if let Ok(template_findings) = result {
+                if !template_findings.is_empty() {
+                    matched.insert(template_idx);
+                }
+                found += template_findings.len();
+                findings.lock().unwrap().extend(template_findings);
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Execute one template against one target, reporting progress
+    async fn execute_work_unit(
+        &self,
+        template: &dyn crate::template::Template,
         target: &crate::types::Target,
         job: &ScanJob,
+        run: &JobRun,
     ) -> Result<Vec<Finding>> {
-        let mut findings = Vec::new();
+        // Templates start only while the job is neither paused nor cancelled
+        if !job.control.checkpoint().await {
+            return Ok(Vec::new());
+        }
 
-        // Execute templates in parallel with limited concurrency
-        let template_findings: Vec<Result<Vec<Finding>>> = stream::iter(&job.templates)
-            .map(|template| async {
-                // Update progress with current template
-                if let Some(progress) = get_progress() {
-                    progress.set_template(template.id(), &target.address);
-                }
+        // Once a scan budget is spent, remaining work for the host is skipped
+        let host = crate::utils::extract_domain(&target.address);
+        if self.network_client.requests().exhausted(&host) {
+            run.skipped.fetch_add(1, Ordering::SeqCst);
+            if let Some(progress) = get_progress() {
+                progress.template_done(&target.address, template.id(), 0);
+            }
+            return Ok(Vec::new());
+        }
 
-                match self
-                    .execute_single_template(template.as_ref(), target, &job.context)
-                    .await
-                {
-                    Ok(template_findings) => {
-                        let findings_count = template_findings.len();
+        // Held back while adaptive concurrency has the host or the scan at its limit
+        let _concurrency_slot = match self.network_client.concurrency() {
+            Some(concurrency) => Some(concurrency.enter(&host).await),
+            None => None,
+        };
 
-                        // Update progress
-                        if let Some(progress) = get_progress() {
-                            progress.template_done(&target.address, template.id(), findings_count);
-                        }
+        let engine = template.metadata().language.to_string();
+        let _engine_slot = run.gate.enter(&engine).await?;
 
-                        if !template_findings.is_empty() {
-                            tracing::info!(
-                                "Template {} found {} findings for {}",
-                                template.id(),
-                                template_findings.len(),
-                                target.address
-                            );
-                        }
-                        Ok(template_findings)
-                    }
-                    Err(e) => {
-                        // Update progress even on failure
-                        if let Some(progress) = get_progress() {
-                            progress.template_done(&target.address, template.id(), 0);
-                        }
+        // Update progress with current template
+        if let Some(progress) = get_progress() {
+            progress.set_template(template.id(), &target.address);
+        }
 
-                        tracing::warn!(
-                            "Template {} failed for target {}: {}",
-                            template.id(),
-                            target.address,
-                            e
-                        );
-                        Err(e)
-                    }
+        match self
+            .execute_single_template(template, target, &job.context)
+            .await
+        {
+            Ok(template_findings) => {
+                let findings_count = template_findings.len();
+
+                // Update progress
+                if let Some(on_finding) = &job.on_finding {
+                    template_findings.iter().for_each(|f| on_finding(f));
+                }
+                if let Some(progress) = get_progress() {
+                    progress.add_severities(&template_findings);
+                    progress.template_done(&target.address, template.id(), findings_count);
                 }
-            })
-            .buffer_unordered(self.config.execution.parallel_templates)
-            .collect()
-            .await;
 
-        // Collect successful results
-        for result in template_findings {
-            if let Ok(mut template_findings) = result {
-                findings.append(&mut template_findings);
+                if !template_findings.is_empty() {
+                    tracing::info!(
+                        "Template {} found {} findings for {}",
+                        template.id(),
+                        template_findings.len(),
+                        target.address
+                    );
+                }
+                Ok(template_findings)
             }
-        }
+            Err(e) => {
+                // Update progress even on failure
+                if let Some(progress) = get_progress() {
+                    progress.template_done(&target.address, template.id(), 0);
+                }
 
-        Ok(findings)
+                // Cut off by a scan budget mid-run: count it with the unstarted work
+                if matches!(e, Error::RequestBudgetExceeded(_))
+                    && self.network_client.requests().exhausted(&host)
+                {
+                    run.skipped.fetch_add(1, Ordering::SeqCst);
+                    return Ok(Vec::new());
+                }
+
+                tracing::warn!(
+                    "Template {} failed for target {}: {}",
+                    template.id(),
+                    target.address,
+                    e
+                );
+                Err(e)
+            }
+        }
     }
 
     /// Execute a single template against a target
@@ -192,8 +590,36 @@ impl Executor {
         // Set timeout for template execution
         let timeout = std::time::Duration::from_secs(self.config.templates.timeout_secs);
 
-        match tokio::time::timeout(timeout, template.execute(target, context)).await {
-            Ok(Ok(findings)) => Ok(findings),
+        // Target metadata (e.g. the OpenAPI operation) is visible as context variables
+        let target_context;
+        let context = if target.metadata.is_empty() {
+            context
+        } else {
+            let mut scoped = context.clone();
+            scoped.variables.extend(target.metadata.clone());
+            target_context = scoped;
+            &target_context
+        };
+
+        // Attribute requests to the template so its own rate limit and request budget apply
+        let execution = rate_limit::with_template(template.id(), template.execute(target, context));
+        let execution = requests::with_template_budget(template.metadata().max_requests, execution);
+        let execution = tokio::time::timeout(timeout, execution);
+        let outcome = match profiler::get_profiler() {
+            Some(profiler) => profiler.execute(template.id(), execution).await,
+            None => execution.await,
+        };
+        match outcome {
+            Ok(Ok(mut findings)) => {
+                let references = &template.metadata().references;
+                for finding in &mut findings {
+                    finding.merge_references(references);
+                    finding
+                        .target_labels
+                        .extend(target.labels.iter().map(|(k, v)| (k.clone(), v.clone())));
+                }
+                Ok(findings)
+            }
             Ok(Err(e)) => Err(e),
             Err(_) => Err(Error::Timeout {
                 duration: format!("{}s", self.config.templates.timeout_secs),
@@ -210,6 +636,126 @@ impl Executor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::template::Template;
+    use crate::types::{
+        AuthorInfo, Context, Protocol, Severity, Target, TemplateLanguage, TemplateMetadata,
+    };
+    use async_trait::async_trait;
+
+    struct SlowTemplate {
+        metadata: TemplateMetadata,
+    }
+
+    #[async_trait]
+    impl Template for SlowTemplate {
+        fn metadata(&self) -> &TemplateMetadata {
+            &self.metadata
+        }
+
+        async fn execute(&self, target: &Target, _context: &Context) -> Result<Vec<Finding>> {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            Ok(vec![Finding::new(
+                target.address.as_str(),
+                self.metadata.id.as_str(),
+                Severity::Info,
+                "slow",
+                "slow template",
+            )])
+        }
+    }
+
+    /// Reports a finding only on `host`
+    struct HostTemplate {
+        metadata: TemplateMetadata,
+        host: &'static str,
+    }
+
+    #[async_trait]
+    impl Template for HostTemplate {
+        fn metadata(&self) -> &TemplateMetadata {
+            &self.metadata
+        }
+
+        async fn execute(&self, target: &Target, _context: &Context) -> Result<Vec<Finding>> {
+            if target.address != self.host {
+                return Ok(Vec::new());
+            }
+            Ok(vec![Finding::new(
+                target.address.as_str(),
+                self.metadata.id.as_str(),
+                Severity::Info,
+                "host",
+                "host template",
+            )])
+        }
+    }
+
+    /// Records how many distinct hosts it runs against at once
+    struct HostCountingTemplate {
+        metadata: TemplateMetadata,
+        in_flight: Arc<std::sync::Mutex<HashMap<String, usize>>>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Template for HostCountingTemplate {
+        fn metadata(&self) -> &TemplateMetadata {
+            &self.metadata
+        }
+
+        async fn execute(&self, target: &Target, _context: &Context) -> Result<Vec<Finding>> {
+            {
+                let mut in_flight = self.in_flight.lock().unwrap();
+                *in_flight.entry(target.address.clone()).or_default() += 1;
+                self.peak.fetch_max(in_flight.len(), Ordering::SeqCst);
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            let mut in_flight = self.in_flight.lock().unwrap();
+            let count = in_flight.get_mut(&target.address).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(&target.address);
+            }
+            Ok(Vec::new())
+        }
+    }
+
+    fn slow_template(id: &str) -> Box<dyn Template> {
+        Box::new(SlowTemplate {
+            metadata: metadata(id),
+        })
+    }
+
+    fn metadata(id: &str) -> TemplateMetadata {
+        TemplateMetadata {
+            id: id.to_string(),
+            name: id.to_string(),
+            author: AuthorInfo {
+                name: "Test".to_string(),
+                email: None,
+                github: None,
+            },
+            severity: Severity::Info,
+            description: String::new(),
+            cve_ids: Vec::new(),
+            cwe_ids: Vec::new(),
+            references: Vec::new(),
+            cvss_score: None,
+            tags: Vec::new(),
+            language: TemplateLanguage::Yaml,
+            file_path: std::path::PathBuf::from("test.yaml"),
+            created: chrono::Utc::now(),
+            updated: chrono::Utc::now(),
+            version: "1.0".to_string(),
+            confidence: None,
+            rate_limit: None,
+            max_requests: None,
+            deprecated: false,
+            superseded_by: None,
+            created_date: None,
+            depends_on: Vec::new(),
+        }
+    }
 
     #[tokio::test]
     async fn test_executor_creation() {
@@ -217,4 +763,312 @@ mod tests {
         let executor = Executor::new(config).await;
         assert!(executor.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_interleaved_scheduling_is_fair() {
+        let mut config = Config::default();
+        config.execution.parallel_targets = 3;
+        config.execution.parallel_templates = 1;
+        let config = Arc::new(config);
+
+        let targets = ["a.test", "b.test", "c.test"]
+            .iter()
+            .map(|host| Target::new(*host, Protocol::Https))
+            .collect();
+        let templates = (0..4).map(|i| slow_template(&format!("t{}", i))).collect();
+        let job = ScanJob::new(targets, templates, config.clone());
+
+        let executor = Executor::new(config).await.unwrap();
+        let findings = executor.execute(&job).await.unwrap();
+        assert_eq!(findings.len(), 12);
+
+        let first = |host: &&str| findings.iter().position(|f| f.target == *host).unwrap();
+        let last = |host: &&str| findings.iter().rposition(|f| f.target == *host).unwrap();
+
+        let hosts = ["a.test", "b.test", "c.test"];
+        let latest_first = hosts.iter().map(first).max().unwrap();
+        let earliest_completion = hosts.iter().map(last).min().unwrap();
+        assert!(latest_first < earliest_completion);
+    }
+
+    #[tokio::test]
+    async fn test_interleaved_scheduling_caps_targets_in_flight() {
+        for parallel_targets in [1, 2] {
+            let mut config = Config::default();
+            config.execution.parallel_targets = parallel_targets;
+            config.execution.parallel_templates = 3;
+            let config = Arc::new(config);
+
+            let targets = ["a.test", "b.test", "c.test"]
+                .iter()
+                .map(|host| Target::new(*host, Protocol::Https))
+                .collect();
+            let in_flight = Arc::new(std::sync::Mutex::new(HashMap::new()));
+            let peak = Arc::new(AtomicUsize::new(0));
+            let templates = (0..3)
+                .map(|i| -> Box<dyn Template> {
+                    Box::new(HostCountingTemplate {
+                        metadata: metadata(&format!("t{}", i)),
+                        in_flight: in_flight.clone(),
+                        peak: peak.clone(),
+                    })
+                })
+                .collect();
+            let job = ScanJob::new(targets, templates, config.clone());
+
+            let executor = Executor::new(config).await.unwrap();
+            executor.execute(&job).await.unwrap();
+            assert_eq!(peak.load(Ordering::SeqCst), parallel_targets);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_priority_orders_each_target() {
+        for (priority, expected) in [
+            ("severity", ["rce", "exposure", "fingerprint"]),
+            ("tags:tech", ["fingerprint", "rce", "exposure"]),
+            ("none", ["fingerprint", "exposure", "rce"]),
+        ] {
+            let mut config = Config::default();
+            config.execution.parallel_targets = 2;
+            config.execution.parallel_templates = 1;
+            config.execution.priority = priority.parse().unwrap();
+            let config = Arc::new(config);
+
+            let targets = ["a.test", "b.test"]
+                .iter()
+                .map(|host| Target::new(*host, Protocol::Https))
+                .collect();
+            let template = |id: &str, severity, tags: &[&str]| -> Box<dyn Template> {
+                let mut metadata = metadata(id);
+                metadata.severity = severity;
+                metadata.tags = tags.iter().map(|t| t.to_string()).collect();
+                Box::new(SlowTemplate { metadata })
+            };
+            let templates = vec![
+                template("fingerprint", Severity::Info, &["tech"]),
+                template("exposure", Severity::Medium, &[]),
+                template("rce", Severity::Critical, &[]),
+            ];
+            let job = ScanJob::new(targets, templates, config.clone());
+
+            let executor = Executor::new(config).await.unwrap();
+            let findings = executor.execute(&job).await.unwrap();
+            for host in ["a.test", "b.test"] {
+                let order: Vec<&str> = findings
+                    .iter()
+                    .filter(|f| f.target == host)
+                    .map(|f| f.template_id.as_str())
+                    .collect();
+                assert_eq!(order, expected, "{} with --priority {}", host, priority);
+            }
+            // Both targets get their top template before either gets a second
+            let hosts: Vec<&str> = findings.iter().take(2).map(|f| f.target.as_str()).collect();
+            assert!(hosts.contains(&"a.test") && hosts.contains(&"b.test"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dependents_wait_for_matches() {
+        for scheduling in [
+            SchedulingStrategy::Interleaved,
+            SchedulingStrategy::PerTarget,
+        ] {
+            let mut config = Config::default();
+            config.execution.scheduling = scheduling;
+            let config = Arc::new(config);
+
+            let targets = ["a.test", "b.test"]
+                .iter()
+                .map(|host| Target::new(*host, Protocol::Https))
+                .collect();
+            let mut dependent = metadata("dependent");
+            dependent.depends_on = vec!["detect".to_string()];
+            let mut orphan = metadata("orphan");
+            orphan.depends_on = vec!["not-selected".to_string()];
+            let templates: Vec<Box<dyn Template>> = vec![
+                Box::new(SlowTemplate {
+                    metadata: dependent,
+                }),
+                Box::new(HostTemplate {
+                    metadata: metadata("detect"),
+                    host: "a.test",
+                }),
+                Box::new(SlowTemplate { metadata: orphan }),
+            ];
+            let job = ScanJob::new(targets, templates, config.clone());
+
+            let executor = Executor::new(config).await.unwrap();
+            let findings = executor.execute(&job).await.unwrap();
+            let mut found: Vec<(&str, &str)> = findings
+                .iter()
+                .map(|f| (f.target.as_str(), f.template_id.as_str()))
+                .collect();
+            found.sort();
+            assert_eq!(
+                found,
+                [
+                    ("a.test", "dependent"),
+                    ("a.test", "detect"),
+                    ("a.test", "orphan"),
+                    ("b.test", "orphan"),
+                ]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_critical_dependents_run_before_unrelated_templates() {
+        for scheduling in [
+            SchedulingStrategy::Interleaved,
+            SchedulingStrategy::PerTarget,
+        ] {
+            let mut config = Config::default();
+            config.execution.scheduling = scheduling;
+            config.execution.parallel_templates = 1;
+            let config = Arc::new(config);
+
+            let template = |id: &str, severity, depends_on: &[&str]| -> Box<dyn Template> {
+                let mut metadata = metadata(id);
+                metadata.severity = severity;
+                metadata.depends_on = depends_on.iter().map(|d| d.to_string()).collect();
+                Box::new(SlowTemplate { metadata })
+            };
+            let templates = vec![
+                template("fingerprint", Severity::Info, &[]),
+                template("banner", Severity::Info, &[]),
+                template("detect", Severity::Info, &[]),
+                template("heapdump", Severity::Critical, &["detect"]),
+            ];
+            let targets = vec![Target::new("a.test", Protocol::Https)];
+            let job = ScanJob::new(targets, templates, config.clone());
+
+            let executor = Executor::new(config).await.unwrap();
+            let findings = executor.execute(&job).await.unwrap();
+            let order: Vec<&str> = findings.iter().map(|f| f.template_id.as_str()).collect();
+            assert_eq!(order, ["detect", "heapdump", "fingerprint", "banner"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_engine_limit_caps_concurrency() {
+        let mut config = Config::default();
+        config.execution.parallel_targets = 4;
+        config.execution.parallel_templates = 4;
+        let config = Arc::new(config);
+
+        let targets = ["a.test", "b.test"]
+            .iter()
+            .map(|host| Target::new(*host, Protocol::Https))
+            .collect();
+        let templates = (0..4).map(|i| slow_template(&format!("t{}", i))).collect();
+        let job = ScanJob::new(targets, templates, config.clone());
+
+        let mut executor = Executor::new(config).await.unwrap();
+        let mut limits = EngineLimits::default();
+        limits.limits.insert(
+            "yaml".to_string(),
+            EngineLimit {
+                max_concurrency: Some(2),
+                cost: ExecutionCost::Light,
+            },
+        );
+        executor.set_engine_limits(limits);
+
+        let (findings, usage) = executor.execute_with_usage(&job).await.unwrap();
+        assert_eq!(findings.len(), 8);
+        assert_eq!(usage.engine_peaks.get("yaml"), Some(&2));
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_concurrency_caps_running_templates() {
+        let mut config = Config::default();
+        config.execution.parallel_targets = 4;
+        config.execution.parallel_templates = 4;
+        config.execution.adaptive_concurrency = true;
+        config.execution.max_concurrency = Some(3);
+        let config = Arc::new(config);
+
+        let targets = ["a.test", "b.test"]
+            .iter()
+            .map(|host| Target::new(*host, Protocol::Https))
+            .collect();
+        let templates = (0..4).map(|i| slow_template(&format!("t{}", i))).collect();
+        let job = ScanJob::new(targets, templates, config.clone());
+
+        // An unlimited engine entry only records the peak
+        let mut executor = Executor::new(config).await.unwrap();
+        let mut limits = EngineLimits::default();
+        limits.limits.insert(
+            "yaml".to_string(),
+            EngineLimit {
+                max_concurrency: None,
+                cost: ExecutionCost::Light,
+            },
+        );
+        executor.set_engine_limits(limits);
+
+        let (findings, usage) = executor.execute_with_usage(&job).await.unwrap();
+        assert_eq!(findings.len(), 8);
+        assert_eq!(usage.engine_peaks.get("yaml"), Some(&3));
+        let limits: Vec<usize> = usage
+            .concurrency_timeline
+            .iter()
+            .map(|c| c.concurrency)
+            .collect();
+        assert_eq!(limits, vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_spent_request_budget_skips_remaining_work() {
+        let mut config = Config::default();
+        config.network.max_requests_total = Some(0);
+        let config = Arc::new(config);
+
+        let targets = ["a.test", "b.test"]
+            .iter()
+            .map(|host| Target::new(*host, Protocol::Https))
+            .collect();
+        let templates = (0..3).map(|i| slow_template(&format!("t{}", i))).collect();
+        let job = ScanJob::new(targets, templates, config.clone());
+
+        let executor = Executor::new(config).await.unwrap();
+        let (findings, usage) = executor.execute_with_usage(&job).await.unwrap();
+        assert!(findings.is_empty());
+        assert_eq!(usage.skipped, 6);
+    }
+
+    #[test]
+    fn test_engine_limits_defaults_and_overrides() {
+        let mut loader = TemplateLoader::new();
+        loader.register_engine(Box::new(crate::engine::YamlTemplateEngine::new()));
+        loader.register_engine(Box::new(crate::engine::PythonEngine::new()));
+        loader.register_engine(Box::new(crate::engine::JavaEngine::new()));
+
+        let mut overrides = BTreeMap::new();
+        overrides.insert(
+            "python".to_string(),
+            EngineRuntimeConfig {
+                max_concurrency: Some(3),
+                ..Default::default()
+            },
+        );
+        let limits = EngineLimits::from_loader(&loader, &overrides);
+
+        let yaml = limits.get("yaml").unwrap();
+        assert_eq!(
+            (yaml.max_concurrency, yaml.cost),
+            (None, ExecutionCost::Light)
+        );
+        let python = limits.get("python").unwrap();
+        assert_eq!(
+            (python.max_concurrency, python.cost),
+            (Some(3), ExecutionCost::Medium)
+        );
+        let java = limits.get("java").unwrap();
+        assert_eq!(
+            (java.max_concurrency, java.cost),
+            (Some(num_cpus::get()), ExecutionCost::Heavy)
+        );
+    }
 }
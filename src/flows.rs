@@ -3,7 +3,8 @@
 //! Supports multi-step workflows with dependencies and conditional execution.
 
 use crate::error::{Error, Result};
-use crate::session::SessionManager;
+use crate::network::NetworkClient;
+use crate::session::{SessionManager, SessionState};
 use crate::types::{Context, Finding, Target};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -18,6 +19,8 @@ pub struct FlowContext {
     pub session: Arc<SessionManager>,
     /// Flow variables
     pub variables: HashMap<String, String>,
+    /// Named user sessions steps can run as, from the execution context
+    pub sessions: HashMap<String, SessionState>,
     /// Execution context
     pub context: Context,
 }
@@ -25,10 +28,16 @@ pub struct FlowContext {
 impl FlowContext {
     /// Create a new flow context
     pub fn new(target: Target, session: Arc<SessionManager>, context: Context) -> Self {
+        let sessions = context
+            .sessions
+            .iter()
+            .map(|state| (state.name.clone(), state.clone()))
+            .collect();
         Self {
             target,
             session,
             variables: HashMap::new(),
+            sessions,
             context,
         }
     }
@@ -102,6 +111,9 @@ pub enum FlowStep {
         body: Option<String>,
         /// Store response in variable
         store: Option<String>,
+        /// Named session to send the request as
+        #[serde(default)]
+        session: Option<String>,
     },
     /// Set variable
     SetVariable {
@@ -137,13 +149,37 @@ pub enum FlowStep {
 #[derive(Debug)]
 pub struct FlowExecutor {
     /// Network client
-    network_client: Arc<crate::network::NetworkClient>,
+    network_client: Arc<NetworkClient>,
+    /// Clients of named sessions, each with its own cookie jar
+    session_clients: tokio::sync::Mutex<HashMap<SessionState, Arc<NetworkClient>>>,
 }
 
 impl FlowExecutor {
     /// Create a new flow executor
-    pub fn new(network_client: Arc<crate::network::NetworkClient>) -> Self {
-        Self { network_client }
+    pub fn new(network_client: Arc<NetworkClient>) -> Self {
+        Self {
+            network_client,
+            session_clients: tokio::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Client for requests made as `session`
+    ///
+    /// Created on first use and kept, so cookies the session picks up carry
+    /// over to its later requests without reaching the other sessions.
+    pub async fn session_client(&self, session: &SessionState) -> Arc<NetworkClient> {
+        self.session_clients
+            .lock()
+            .await
+            .entry(session.clone())
+            .or_insert_with(|| {
+                tracing::debug!("Starting cookie jar for session {}", session.name);
+                Arc::new(
+                    self.network_client
+                        .with_session_manager(Arc::new(SessionManager::new())),
+                )
+            })
+            .clone()
     }
 
     /// Execute a flow
@@ -201,7 +237,21 @@ impl FlowExecutor {
                 headers,
                 body,
                 store,
+                session,
             } => {
+                // Steps run as a named session use its client, jar and credentials
+                let (network_client, jar, credentials) = match session {
+                    Some(name) => {
+                        let state = context.sessions.get(name).cloned().ok_or_else(|| {
+                            Error::config(format!("Flow step uses undefined session '{}'", name))
+                        })?;
+                        let client = self.session_client(&state).await;
+                        let jar = client.session_manager().clone();
+                        (client, jar, Some(state))
+                    }
+                    None => (self.network_client.clone(), context.session.clone(), None),
+                };
+
                 let url = format!(
                     "{}{}",
                     context.target.url(),
@@ -210,25 +260,39 @@ impl FlowExecutor {
                 tracing::debug!("HTTP {} {}", method, url);
 
                 // Build request
-                let mut request = self
-                    .network_client
+                let mut request = network_client
                     .client()
                     .request(method.parse().unwrap_or(reqwest::Method::GET), &url);
 
-                // Add headers
+                // Add headers, then the target's own for names the step leaves unset
+                let mut headers: HashMap<String, String> = headers
+                    .iter()
+                    .map(|(key, value)| (key.clone(), context.replace_variables(value)))
+                    .collect();
+                if let Some(state) = &credentials {
+                    for (key, value) in &state.headers {
+                        headers.entry(key.clone()).or_insert_with(|| value.clone());
+                    }
+                }
+                context.target.merge_custom_headers(&mut headers);
                 for (key, value) in headers {
-                    let value = context.replace_variables(value);
                     request = request.header(key, value);
                 }
 
                 // Add cookies if available
                 let domain = &context.target.address;
-                if let Some(cookie_header) = context.session.get_cookie_header(domain).await {
-                    request = request.header("Cookie", cookie_header);
+                let mut cookies: Vec<String> = credentials
+                    .iter()
+                    .flat_map(|state| &state.cookies)
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect();
+                cookies.extend(jar.get_cookie_header(domain).await);
+                if !cookies.is_empty() {
+                    request = request.header("Cookie", cookies.join("; "));
                 }
 
                 // Add JWT if available
-                if let Some(jwt_header) = context.session.get_jwt_header("default").await {
+                if let Some(jwt_header) = jar.get_jwt_header("default").await {
                     request = request.header("Authorization", jwt_header);
                 }
 
@@ -238,25 +302,22 @@ impl FlowExecutor {
                     request = request.body(body_content);
                 }
 
-                // Send request
-                let response = request
-                    .send()
-                    .await
-                    .map_err(|e| Error::Network(format!("HTTP request failed: {}", e)))?;
+                // Send through the client so the exchange is traced
+                let response = network_client.request(request).await?;
 
                 // Process Set-Cookie headers
                 for cookie in response.headers().get_all("set-cookie") {
                     if let Ok(cookie_str) = cookie.to_str() {
-                        let _ = context.session.parse_set_cookie(domain, cookie_str).await;
+                        let _ = jar.parse_set_cookie(domain, cookie_str).await;
                     }
                 }
 
                 // Store response if requested
                 if let Some(var_name) = store {
-                    let response_text = response
-                        .text()
-                        .await
-                        .map_err(|e| Error::Network(format!("Failed to read response: {}", e)))?;
+                    let response_text =
+                        crate::network::read_response(response, std::time::Duration::ZERO)
+                            .await?
+                            .body_string();
                     context.set_variable(var_name.clone(), response_text);
                 }
 
@@ -407,6 +468,69 @@ mod tests {
         assert_eq!(result, "https://example.com/api");
     }
 
+    #[tokio::test]
+    async fn test_sessions_keep_separate_cookie_jars() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/login"))
+            .respond_with(ResponseTemplate::new(200).insert_header("set-cookie", "jar=a; Path=/"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/me"))
+            .and(header("cookie", "sid=alice; jar=a"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("alice with jar"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/me"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("no jar"))
+            .mount(&server)
+            .await;
+
+        let flow: Flow = serde_yaml::from_str(
+            "name: two-users\nsteps:\n\
+             \x20 - {action: http_request, method: GET, path: /login, session: user_a}\n\
+             \x20 - {action: http_request, method: GET, path: /me, session: user_b, store: b}\n\
+             \x20 - {action: http_request, method: GET, path: /me, session: user_a, store: a}\n",
+        )
+        .unwrap();
+        let mut user_a = SessionState::new("user_a");
+        user_a.cookies = vec![("sid".to_string(), "alice".to_string())];
+        let mut user_b = SessionState::new("user_b");
+        user_b.cookies = vec![("sid".to_string(), "bob".to_string())];
+        let context = Context {
+            sessions: vec![user_a, user_b],
+            ..Context::default()
+        };
+
+        let client = NetworkClient::new(Arc::new(crate::config::Config::default()))
+            .await
+            .unwrap();
+        let executor = FlowExecutor::new(Arc::new(client));
+        let target = Target::with_port("127.0.0.1", server.address().port(), Protocol::Http);
+        let mut flow_context = FlowContext::new(target, Arc::new(SessionManager::new()), context);
+        executor
+            .execute_flow(&flow, &mut flow_context)
+            .await
+            .unwrap();
+        assert_eq!(flow_context.get_variable("a").unwrap(), "alice with jar");
+        assert_eq!(flow_context.get_variable("b").unwrap(), "no jar");
+
+        // Undefined sessions fail the step
+        let flow: Flow = serde_yaml::from_str(
+            "name: unknown\nsteps:\n  - {action: http_request, method: GET, path: /me, session: user_c}\n",
+        )
+        .unwrap();
+        assert!(executor
+            .execute_flow(&flow, &mut flow_context)
+            .await
+            .is_err());
+    }
+
     #[test]
     fn test_flow_step_serialization() {
         let step = FlowStep::HttpRequest {
@@ -415,6 +539,7 @@ mod tests {
             headers: HashMap::new(),
             body: None,
             store: Some("response".to_string()),
+            session: None,
         };
 
         let json = serde_json::to_string(&step).unwrap();
@@ -0,0 +1,289 @@
+//! Multi-job scans (`cxg scan --input jobs.yaml`)
+//!
+//! A jobs file is a YAML list of scans that share one engine: templates are
+//! loaded and compiled once, then every entry runs its own scope, template
+//! filters and mode flags and writes its own outputs. Jobs run one after the
+//! other (or `--jobs-parallel N` at a time); a failed job does not stop the
+//! others unless `--fail-fast` is set.
+//!
+//! ```yaml
+//! - name: external-web
+//!   scope: [example.com, "@web-hosts.txt"]
+//!   tags: [web, cve]
+//!   severity: [critical, high]
+//!   safe: true
+//!   output: results/external-web
+//!   output_format: json,html
+//! - name: internal-infra
+//!   scope: [10.0.0.0/24]
+//!   templates: [redis-unauth, mongodb-unauth]
+//!   ports: ["6379", "27017"]
+//! ```
+
+use crate::error::{Error, Result};
+use crate::types::ScanResults;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
+use std::path::Path;
+
+/// One entry of a jobs file; unset fields fall back to the command line
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobSpec {
+    /// Job name, used in the summary and default output path (`job-<n>` when unset)
+    pub name: Option<String>,
+    /// Scope entries, as accepted by `--scope`
+    pub scope: Vec<String>,
+    /// Template IDs or patterns to run, as accepted by `--templates`
+    pub templates: Vec<String>,
+    /// Only templates with one of these tags
+    pub tags: Vec<String>,
+    /// Only templates with one of these severities
+    pub severity: Vec<String>,
+    /// Only templates in one of these languages
+    pub languages: Vec<String>,
+    /// Template ID patterns to skip
+    pub exclude: Vec<String>,
+    /// Ports added to template defaults, as accepted by `--ports`
+    pub ports: Vec<String>,
+    /// Also scan the N most common ports
+    pub top_ports: Option<u16>,
+    /// Drop dangerous templates
+    pub safe: bool,
+    /// Only passive templates
+    pub passive: bool,
+    /// Aggressive mode
+    pub aggressive: bool,
+    /// Stealth mode
+    pub stealth: bool,
+    /// Output basename (`<--output>-<name>` when unset)
+    pub output: Option<String>,
+    /// Output formats (`--output-format` when unset)
+    pub output_format: Option<String>,
+}
+
+impl JobSpec {
+    /// Parse a jobs file and check that every job has a scope and a unique name
+    pub fn load_file(path: &Path) -> Result<Vec<Self>> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            Error::config(format!(
+                "Failed to read jobs file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let mut jobs: Vec<Self> = serde_yaml::from_str(&content)
+            .map_err(|e| Error::config(format!("Invalid jobs file {}: {}", path.display(), e)))?;
+        if jobs.is_empty() {
+            return Err(Error::config(format!(
+                "Jobs file {} has no jobs",
+                path.display()
+            )));
+        }
+
+        let mut names = HashSet::new();
+        for (index, job) in jobs.iter_mut().enumerate() {
+            let name = job
+                .name
+                .get_or_insert_with(|| format!("job-{}", index + 1))
+                .clone();
+            if job.scope.is_empty() {
+                return Err(Error::config(format!("Job '{}' has no scope", name)));
+            }
+            if !names.insert(name.clone()) {
+                return Err(Error::config(format!("Duplicate job name '{}'", name)));
+            }
+        }
+        Ok(jobs)
+    }
+
+    /// Job name (set for every job returned by [`JobSpec::load_file`])
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("job")
+    }
+}
+
+/// How one job of a multi-job scan ended
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobOutcome {
+    /// Job name
+    pub name: String,
+    /// Output basename the job wrote to
+    pub output: String,
+    /// Targets scanned
+    pub targets: usize,
+    /// Templates run
+    pub templates: usize,
+    /// Findings reported, by severity
+    pub findings: BTreeMap<String, usize>,
+    /// Wall-clock duration in seconds
+    pub duration_secs: f64,
+    /// Why the job failed; `None` when it completed
+    pub error: Option<String>,
+}
+
+impl JobOutcome {
+    /// Outcome of a completed job
+    pub fn completed(name: &str, output: &str, results: &ScanResults, duration_secs: f64) -> Self {
+        let mut findings = BTreeMap::new();
+        for finding in &results.findings {
+            *findings.entry(finding.severity.to_string()).or_default() += 1;
+        }
+        Self {
+            name: name.to_string(),
+            output: output.to_string(),
+            targets: results.statistics.targets_scanned,
+            templates: results.statistics.templates_executed,
+            findings,
+            duration_secs,
+            error: None,
+        }
+    }
+
+    /// Outcome of a job that failed before or while scanning
+    pub fn failed(name: &str, output: &str, error: &Error, duration_secs: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            output: output.to_string(),
+            targets: 0,
+            templates: 0,
+            findings: BTreeMap::new(),
+            duration_secs,
+            error: Some(error.to_string()),
+        }
+    }
+
+    /// Total findings across severities
+    pub fn total_findings(&self) -> usize {
+        self.findings.values().sum()
+    }
+}
+
+/// Combined summary of a multi-job scan, written to `<--output>.jobs.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobsSummary {
+    /// Outcome of each job that ran, in jobs-file order
+    pub jobs: Vec<JobOutcome>,
+    /// Jobs not started because an earlier one failed with `--fail-fast`
+    pub skipped: Vec<String>,
+}
+
+impl JobsSummary {
+    /// Jobs that failed
+    pub fn failed(&self) -> usize {
+        self.jobs.iter().filter(|j| j.error.is_some()).count()
+    }
+
+    /// Findings across all jobs
+    pub fn total_findings(&self) -> usize {
+        self.jobs.iter().map(JobOutcome::total_findings).sum()
+    }
+}
+
+/// Run `count` jobs, `parallel` at a time, and return their results in job
+/// order; with `fail_fast`, no job starts after one has failed (`None`)
+pub async fn run_jobs<T, E, F, Fut>(
+    count: usize,
+    parallel: usize,
+    fail_fast: bool,
+    run: F,
+) -> Vec<Option<std::result::Result<T, E>>>
+where
+    F: Fn(usize) -> Fut,
+    Fut: Future<Output = std::result::Result<T, E>>,
+{
+    let mut results: Vec<Option<std::result::Result<T, E>>> = (0..count).map(|_| None).collect();
+    let mut running = stream::iter(0..count)
+        .map(|index| {
+            let job = run(index);
+            async move { (index, job.await) }
+        })
+        .buffer_unordered(parallel.max(1));
+    while let Some((index, result)) = running.next().await {
+        let failed = result.is_err();
+        results[index] = Some(result);
+        if failed && fail_fast {
+            // Dropping the stream cancels jobs still running
+            break;
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn write_jobs(content: &str) -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.yaml");
+        std::fs::write(&path, content).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn test_load_jobs_file() {
+        let (_dir, path) = write_jobs(
+            "- name: external-web\n  scope: [example.com]\n  tags: [web]\n  safe: true\n\
+             - scope: [10.0.0.0/30]\n  templates: [redis-unauth]\n  ports: [\"6379\"]\n",
+        );
+        let jobs = JobSpec::load_file(&path).unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].name(), "external-web");
+        assert!(jobs[0].safe);
+        assert_eq!(jobs[1].name(), "job-2");
+        assert_eq!(jobs[1].templates, vec!["redis-unauth"]);
+
+        for (content, reason) in [
+            ("[]", "has no jobs"),
+            ("- name: a\n  tags: [web]\n", "has no scope"),
+            (
+                "- name: a\n  scope: [x]\n- name: a\n  scope: [y]\n",
+                "Duplicate",
+            ),
+            ("- scope: [x]\n  severities: [high]\n", "unknown field"),
+        ] {
+            let (_dir, path) = write_jobs(content);
+            let err = JobSpec::load_file(&path).unwrap_err().to_string();
+            assert!(err.contains(reason), "{}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_job_does_not_stop_the_rest() {
+        let results = run_jobs(3, 1, false, |index| async move {
+            if index == 0 {
+                Err("boom")
+            } else {
+                Ok(index)
+            }
+        })
+        .await;
+        assert!(matches!(results[0], Some(Err(_))));
+        assert!(matches!(results[1], Some(Ok(1))));
+        assert!(matches!(results[2], Some(Ok(2))));
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast_skips_remaining_jobs() {
+        let started = AtomicUsize::new(0);
+        let results = run_jobs(3, 1, true, |index| {
+            started.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if index == 1 {
+                    Err("boom")
+                } else {
+                    Ok(index)
+                }
+            }
+        })
+        .await;
+        assert!(matches!(results[0], Some(Ok(0))));
+        assert!(matches!(results[1], Some(Err(_))));
+        assert!(results[2].is_none());
+        assert_eq!(started.load(Ordering::SeqCst), 2);
+    }
+}
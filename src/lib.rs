@@ -5,6 +5,8 @@
 //!
 //! A next-generation security scanning engine supporting multiple programming languages
 //! for template creation with unprecedented flexibility and power in security testing.
+//!
+//! To run scans from another tool, start with [`ScanBuilder`].
 
 #![warn(
     missing_docs,
@@ -12,9 +14,12 @@
     unused_qualifications,
     missing_debug_implementations
 )]
-#![forbid(unsafe_code)]
+// Not `forbid`: the seccomp listener in `sandbox::syscall` needs raw
+// syscalls and is the only module that opts back in
+#![deny(unsafe_code)]
 
 // Core modules
+pub mod abtest;
 pub mod ai;
 pub mod banner;
 pub mod config;
@@ -24,6 +29,7 @@ pub mod engine;
 pub mod error;
 pub mod executor;
 pub mod flows;
+pub mod jobs;
 pub mod matcher;
 pub mod metrics;
 pub mod network;
@@ -32,11 +38,15 @@ pub mod plugin;
 pub mod progress;
 pub mod sandbox;
 pub mod scheduler;
+pub mod scope;
 pub mod search;
+pub mod secrets;
 pub mod session;
 pub mod template;
 pub mod types;
 pub mod utils;
+pub mod wordlist;
+pub mod workspace;
 
 // Re-exports for convenience
 pub use crate::ai::{
@@ -44,7 +54,7 @@ pub use crate::ai::{
     TemplateValidator,
 };
 pub use crate::config::Config;
-pub use crate::core::{CertXGen, ScanJob};
+pub use crate::core::{CertXGen, PerTargetOverrides, PreparedScan, ScanBuilder, ScanJob};
 pub use crate::error::{Error, Result};
 pub use crate::template::{Template, TemplateEngine};
 pub use crate::types::{Finding, Severity, Target, TemplateMetadata};
@@ -55,7 +65,7 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// Prelude module for common imports
 pub mod prelude {
     pub use crate::config::Config;
-    pub use crate::core::{CertXGen, ScanJob};
+    pub use crate::core::{CertXGen, ScanBuilder, ScanJob};
     pub use crate::error::{Error, Result};
     pub use crate::matcher::{Matcher, MatcherType};
     pub use crate::template::{Template, TemplateEngine};
@@ -4,33 +4,38 @@
 use cert_x_gen::{
     ai::{AIManager, TemplateValidator},
     config::Config,
-    core::CertXGen,
+    core::{CertXGen, PerTargetOverrides, PreparedScan, ScanBuilder, ScanJob, ScanPlan},
     error::{Error, Result},
-    output::OutputManager,
-    plugin::{LoggingPlugin, PluginManager},
+    metrics::profiler,
+    output::{self, ClusterSummary, Clusterer, OutputManager, TemplateRenderer},
+    plugin::{JiraPlugin, LoggingPlugin, NotificationPlugin, Plugin, PluginManager},
     progress::{get_progress, init_progress},
-    template::{Template, TemplateFilter},
-    types::{Protocol, Target, TemplateLanguage},
+    scope::{self, openapi::ApiSpec, parse_port_entries, parse_ports},
+    session::SessionState,
+    template::{parse_template_entries, Template, TemplateFilter},
+    types::TemplateLanguage,
     utils,
+    workspace::{self, ScanWorkspace},
 };
 use clap::Parser;
 use std::sync::Arc;
 use std::{
-    collections::HashSet,
     fs,
     path::{Path, PathBuf},
 };
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 mod cli;
-use cli::{Cli, Commands};
+use cli::{Cli, Commands, ErrorFormat};
 
 #[tokio::main]
 async fn main() {
     // Display banner first (before parsing CLI)
-    // Check if --quiet flag is present in args
+    // Check if --quiet flag is present in args (JSON plans must stay parseable)
     let args: Vec<String> = std::env::args().collect();
-    let is_quiet = args.iter().any(|arg| arg == "--quiet" || arg == "-q");
+    let is_quiet = args
+        .iter()
+        .any(|arg| arg == "--quiet" || arg == "-q" || arg == "--dry-run-json");
 
     if !is_quiet {
         cert_x_gen::banner::display_banner();
@@ -46,20 +51,35 @@ async fn main() {
     }
 
     // Check if we should auto-enter a Docker sandbox
+    let error_format = cli.output_errors;
     if let Err(e) = check_and_enter_sandbox(&cli).await {
         tracing::error!("Sandbox error: {}", e);
-        eprintln!("Sandbox error: {}", e);
+        report_error(&e.context("Sandbox error"), error_format);
         std::process::exit(1);
     }
 
     // Run the command
     if let Err(e) = run(cli).await {
         tracing::error!("Error: {}", e);
-        eprintln!("Error: {}", e);
+        report_error(&e, error_format);
         std::process::exit(1);
     }
 }
 
+/// Print a command's error on stderr in the `--output-errors` format
+fn report_error(error: &Error, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Text => {
+            eprintln!("Error: {}", error);
+            eprintln!("Hint: {}", error.hint());
+        }
+        ErrorFormat::Json => match serde_json::to_string(&error.report()) {
+            Ok(json) => eprintln!("{}", json),
+            Err(_) => eprintln!("Error: {}", error),
+        },
+    }
+}
+
 /// Initialize logging system based on verbosity level
 /// - 0: No logging (progress bar mode) - only errors logged internally
 /// - 1 (-v): INFO + WARN logs
@@ -135,26 +155,38 @@ async fn run(cli: Cli) -> Result<()> {
 
     match command {
         Commands::Scan(args) => {
-            run_scan(args, cli.config).await?;
+            run_scan(args, cli.config, cli.profile).await?;
         }
         Commands::Template(cmd) => {
-            run_template_command(cmd).await?;
+            let config = load_config(cli.config.as_deref(), cli.profile.as_deref())?;
+            run_template_command(cmd, config).await?;
         }
         Commands::Ai(cmd) => {
-            run_ai_command(cmd).await?;
+            run_ai_command(cmd, cli.config.as_deref(), cli.profile.as_deref()).await?;
         }
         Commands::Search(args) => {
-            run_search_command(args).await?;
+            let config = load_config(cli.config.as_deref(), cli.profile.as_deref())?;
+            run_search_command(args, config).await?;
         }
         Commands::Server(args) => {
+            reject_config_flags("server", cli.config.as_deref(), cli.profile.as_deref())?;
             run_server(args).await?;
         }
         Commands::Config(cmd) => {
-            run_config_command(cmd)?;
+            run_config_command(cmd, cli.config, cli.profile)?;
         }
         Commands::Sandbox(cmd) => {
+            reject_config_flags("sandbox", cli.config.as_deref(), cli.profile.as_deref())?;
             run_sandbox_command(cmd).await?;
         }
+        Commands::Wordlist(cmd) => {
+            reject_config_flags("wordlist", cli.config.as_deref(), cli.profile.as_deref())?;
+            run_wordlist_command(cmd)?;
+        }
+        Commands::Doctor => {
+            let config = load_config(cli.config.as_deref(), cli.profile.as_deref())?;
+            run_doctor(&config).await?;
+        }
         Commands::Version => {
             print_version();
         }
@@ -163,6 +195,62 @@ async fn run(cli: Cli) -> Result<()> {
     Ok(())
 }
 
+/// Print the runtime each template engine resolves to
+async fn run_doctor(config: &Config) -> Result<()> {
+    use cert_x_gen::engine::runtime::{fallback_programs, EngineRuntime, ENGINE_LANGUAGES};
+    use console::{style, Term};
+
+    let term = Term::stdout();
+    term.write_line(&format!("{}", style("Template engine runtimes").bold()))?;
+
+    let mut missing = 0;
+    for language in ENGINE_LANGUAGES {
+        let engine_config = config.engines.get(*language).cloned().unwrap_or_default();
+        let configured = engine_config.path.clone();
+        let runtime = EngineRuntime::new(language).with_config(engine_config);
+        match runtime.resolve().await {
+            Some(resolved) => term.write_line(&format!(
+                "  {} {:<11} {} {}",
+                style("✓").green(),
+                language,
+                resolved.program,
+                style(format!(
+                    "({}{})",
+                    resolved.version.as_deref().unwrap_or("unknown version"),
+                    if resolved.configured {
+                        ", from config"
+                    } else {
+                        ""
+                    }
+                ))
+                .dim()
+            ))?,
+            None => {
+                missing += 1;
+                let tried = match &configured {
+                    Some(path) => format!("configured path {} not found", path.display()),
+                    None => format!("tried {}", fallback_programs(language).join(", ")),
+                };
+                term.write_line(&format!(
+                    "  {} {:<11} {}",
+                    style("✗").red(),
+                    language,
+                    style(tried).dim()
+                ))?;
+            }
+        }
+    }
+
+    if missing > 0 {
+        term.write_line("")?;
+        term.write_line(&format!(
+            "{} Set engines.<language>.path in the configuration to point at a runtime outside PATH",
+            style("ℹ").blue()
+        ))?;
+    }
+    Ok(())
+}
+
 /// Handle auto-update logic based on CLI flags
 async fn handle_auto_update(cli: &Cli) -> Result<()> {
     use cert_x_gen::template::AutoUpdater;
@@ -205,382 +293,868 @@ async fn handle_auto_update(cli: &Cli) -> Result<()> {
     Ok(())
 }
 
+/// Compile all compiled-language templates in the job, dropping failures
+async fn precompile_templates(job: &mut cert_x_gen::core::ScanJob) {
+    use console::style;
+
+    let total = job
+        .templates
+        .iter()
+        .filter(|t| t.metadata().language.is_compiled())
+        .count();
+    if total == 0 {
+        return;
+    }
+
+    tracing::info!("Pre-compiling {} templates", total);
+    let bar = get_progress().and_then(|p| p.compile_bar(total));
+
+    let failures = job
+        .precompile_templates(|template_id, _ok| {
+            if let Some(bar) = &bar {
+                bar.inc(1);
+                bar.set_message(template_id.to_string());
+            }
+        })
+        .await;
+
+    if let Some(bar) = bar {
+        bar.finish_and_clear();
+    }
+
+    for (template_id, error) in &failures {
+        tracing::warn!("Template {} failed to compile: {}", template_id, error);
+        eprintln!(
+            "{} Skipping template {}: {}",
+            style("⚠").yellow(),
+            style(template_id).bold(),
+            error.to_string().lines().next().unwrap_or_default()
+        );
+    }
+}
+
+/// Load configuration from file (with optional profile), `~/.cert-x-gen/config.yaml`, or defaults
+fn load_config(config_path: Option<&Path>, profile: Option<&str>) -> Result<Config> {
+    match config_file(config_path, profile)? {
+        Some(path) => Config::from_file_with_profile(path, profile),
+        None => Ok(Config::default()),
+    }
+}
+
+/// Configuration file to read: `--config`, else the default file if it exists
+fn config_file(config_path: Option<&Path>, profile: Option<&str>) -> Result<Option<PathBuf>> {
+    let default_path = Config::default_path();
+    match config_path {
+        Some(path) => Ok(Some(path.to_path_buf())),
+        None if default_path.is_file() => Ok(Some(default_path)),
+        None if profile.is_some() => Err(Error::config(
+            "--profile requires a configuration file (use --config <FILE>)",
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Fail if `--config`/`--profile` are given to a subcommand that never reads them
+fn reject_config_flags(
+    command: &str,
+    config_path: Option<&Path>,
+    profile: Option<&str>,
+) -> Result<()> {
+    if config_path.is_some() || profile.is_some() {
+        return Err(Error::config(format!(
+            "'cxg {}' does not read the scan configuration; remove --config/--profile",
+            command
+        )));
+    }
+    Ok(())
+}
+
 /// Run a security scan
-async fn run_scan(args: cli::ScanArgs, config_path: Option<PathBuf>) -> Result<()> {
+async fn run_scan(
+    mut args: cli::ScanArgs,
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+) -> Result<()> {
     // Load configuration
-    let mut config = if let Some(path) = config_path {
-        Config::from_file(path)?
-    } else {
-        Config::default()
-    };
+    let mut config = load_config(config_path.as_deref(), profile.as_deref())?;
 
     // Override config with CLI arguments
     apply_scan_args_to_config(&mut config, &args);
+    if let Some(priority) = &args.priority {
+        config.execution.priority = priority.parse()?;
+    }
 
     tracing::info!("Starting CERT-X-GEN v{}", env!("CARGO_PKG_VERSION"));
     tracing::info!("Configuration loaded and validated");
 
+    // Every file of this run (and of each --input job) shares one timestamp
+    if args.output_timestamp {
+        args.output = output::timestamped_path(Path::new(&args.output), chrono::Utc::now())
+            .to_string_lossy()
+            .into_owned();
+    }
+
+    // A missing or misnamed output template should fail before scanning, not after
+    if let Some(template) = &args.output_template {
+        if !TemplateRenderer::is_template(&template.to_string_lossy()) {
+            return Err(Error::config(format!(
+                "Output template {} must have a .j2 extension",
+                template.display()
+            )));
+        }
+        TemplateRenderer::from_file(template)?;
+    }
+
+    // Template loads are timed too, so profiling starts before anything is loaded
+    if args.profile_templates {
+        profiler::init_profiler();
+    }
+
+    prune_workspaces(&config);
+
     // Create CERT-X-GEN engine (template engines are auto-registered)
     let engine = CertXGen::new(config.clone()).await?;
 
-    // Check for direct template file paths in --templates argument
-    let (direct_template_paths, filter_ids) = if !args.templates.is_empty() {
-        tracing::debug!("Processing --templates argument: {:?}", args.templates);
-        separate_template_entries(&args.templates, &config.templates.directories)?
-    } else {
-        (Vec::new(), Vec::new())
-    };
+    if let Some(pair) = &args.ab_test.ab_test {
+        return run_ab_test_scan(&engine, &args, &pair[0], &pair[1]).await;
+    }
 
-    // Determine loading strategy
-    let has_direct_paths = !direct_template_paths.is_empty();
-    let has_filter_ids = !filter_ids.is_empty();
+    if let Some(input) = &args.input {
+        return run_scan_jobs(&engine, &args, &config, input).await;
+    }
 
-    // Load templates based on what user specified
-    tracing::info!("Loading templates...");
+    let targeted = !args.templates.is_empty() || !args.test_creds.is_empty();
+    let mut builder = scan_builder(&args, &config, targeted)
+        .await?
+        .test_credentials(&args.test_creds);
+    if !args.templates.is_empty() {
+        builder = builder.templates(&args.templates);
+    }
+    let prepared = builder.prepare(&engine).await?;
+    if args.watch && prepared.template_paths.is_empty() {
+        return Err(Error::config(
+            "--watch requires template file or directory paths via --templates",
+        ));
+    }
 
-    let templates: Vec<Box<dyn Template>> = if has_direct_paths {
-        // CASE 1: User specified direct file paths - load ONLY those (most efficient)
-        tracing::info!(
-            "Loading {} template(s) from direct paths",
-            direct_template_paths.len()
-        );
-        let mut direct_templates: Vec<Box<dyn Template>> = Vec::new();
-
-        for path in &direct_template_paths {
-            tracing::debug!("Loading template from: {}", path.display());
-            match engine.template_loader().load_template(path).await {
-                Ok(template) => {
-                    tracing::info!(
-                        "Loaded template: {} ({}) from {}",
-                        template.id(),
-                        template.metadata().language,
-                        path.display()
-                    );
-                    direct_templates.push(template);
-                }
-                Err(e) => {
-                    tracing::error!("Failed to load template from {}: {}", path.display(), e);
-                    return Err(Error::config(format!(
-                        "Failed to load template '{}': {}",
-                        path.display(),
-                        e
-                    )));
-                }
-            }
+    if args.dry_run || args.dry_run_json {
+        let plan = prepared.plan();
+        if args.dry_run_json {
+            println!("{}", serde_json::to_string_pretty(&plan)?);
+        } else {
+            print_scan_plan(&plan);
         }
+        return Ok(());
+    }
 
-        if direct_templates.is_empty() {
-            return Err(Error::config(
-                "No templates could be loaded from the specified paths.",
-            ));
+    // Refuse before scanning rather than after, when the results can't be written
+    if !args.output_overwrite {
+        let existing =
+            OutputManager::new().existing_files(Path::new(&args.output), &output_formats(&args));
+        if let Some(path) = existing.first() {
+            return Err(output::already_exists(path));
         }
+    }
 
-        direct_templates
-    } else if has_filter_ids {
-        // CASE 2: User specified template IDs - search and load only matching templates
-        tracing::info!(
-            "Searching for {} specified template ID(s): {:?}",
-            filter_ids.len(),
-            filter_ids
+    let PreparedScan {
+        mut job,
+        template_paths,
+        ..
+    } = prepared;
+
+    // Compile templates up front so workers don't stall on builds mid-scan
+    if !args.no_precompile {
+        precompile_templates(&mut job).await;
+    }
+
+    tracing::info!(
+        "Scan job created: {} targets × {} templates = {} total checks",
+        job.targets.len(),
+        job.templates.len(),
+        job.total_work_units()
+    );
+
+    let plugins = ScanPlugins::new(&config, &args)?;
+
+    // Keep targets, overrides and context around for re-runs in watch mode
+    let watch_state = args.watch.then(|| {
+        (
+            job.targets.clone(),
+            job.per_target_overrides.clone(),
+            job.context.clone(),
+        )
+    });
+
+    execute_scan_job(&engine, job, &args, &config, &plugins, true).await?;
+    print_template_profile();
+
+    if let Some((targets, overrides, context)) = watch_state {
+        watch_templates(&engine, &template_paths, targets, overrides, context).await?;
+    }
+
+    Ok(())
+}
+
+/// Remove scan workspaces older than `execution.workspace_retention_days`
+fn prune_workspaces(config: &Config) {
+    let days = config.execution.workspace_retention_days;
+    if days == 0 {
+        return;
+    }
+    let base = ScanWorkspace::default_base();
+    match workspace::prune(&base, std::time::Duration::from_secs(days * 24 * 60 * 60)) {
+        Ok(0) => {}
+        Ok(removed) => tracing::info!(
+            "Removed {} scan workspace(s) older than {} days from {}",
+            removed,
+            days,
+            base.display()
+        ),
+        Err(e) => tracing::warn!(
+            "Failed to prune scan workspaces in {}: {}",
+            base.display(),
+            e
+        ),
+    }
+}
+
+/// Print the slowest templates of a `--profile-templates` scan
+fn print_template_profile() {
+    if let Some(profiler) = profiler::get_profiler() {
+        println!(
+            "\n{}",
+            console::style("Slowest templates (by total time):").bold()
         );
-        let mut matched_templates: Vec<Box<dyn Template>> = Vec::new();
-        let mut found_ids: HashSet<String> = HashSet::new();
+        print!("{}", profiler.render_table(10));
+    }
+}
 
-        // Search in template directories for matching templates
-        for dir in engine.template_manager().get_template_dirs() {
-            if !dir.exists() {
-                continue;
-            }
+/// Plugins notified during a scan; shared by every job of `--input`
+struct ScanPlugins {
+    manager: PluginManager,
+    notifier: Option<Arc<NotificationPlugin>>,
+    jira: Option<Arc<JiraPlugin>>,
+}
 
-            match engine.template_loader().load_templates_from_dir(&dir).await {
-                Ok(templates) => {
-                    for template in templates {
-                        let template_id = template.id().to_string();
-                        let template_name = template.name().to_string();
-                        let file_path = template.metadata().file_path.to_string_lossy().to_string();
-
-                        // Check if this template matches any of the filter IDs
-                        let matches = filter_ids.iter().any(|filter_id| {
-                            template_id.eq_ignore_ascii_case(filter_id)
-                                || template_name.eq_ignore_ascii_case(filter_id)
-                                || file_path.contains(filter_id)
-                                || template
-                                    .metadata()
-                                    .file_path
-                                    .file_stem()
-                                    .and_then(|s| s.to_str())
-                                    .map(|s| s.eq_ignore_ascii_case(filter_id))
-                                    .unwrap_or(false)
-                        });
-
-                        if matches && !found_ids.contains(&template_id) {
-                            tracing::info!(
-                                "Found matching template: {} ({}) in {}",
-                                template_id,
-                                template.metadata().language,
-                                dir.display()
-                            );
-                            found_ids.insert(template_id);
-                            matched_templates.push(template);
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to search templates in {}: {}", dir.display(), e);
-                }
+impl ScanPlugins {
+    fn new(config: &Config, args: &cli::ScanArgs) -> Result<Self> {
+        let mut manager = PluginManager::new();
+        manager.register(Arc::new(LoggingPlugin::new()));
+        let notifier = if config.notifications.is_enabled() {
+            let mut notifier = NotificationPlugin::new();
+            let mut plugin_config = config.clone();
+            if plugin_config.notifications.report_url.is_none() {
+                plugin_config.notifications.report_url = report_file_url(args);
             }
-        }
-
-        // Report any filter IDs that weren't found
-        for filter_id in &filter_ids {
-            if !found_ids
-                .iter()
-                .any(|id| id.eq_ignore_ascii_case(filter_id))
-            {
-                tracing::warn!("Template not found: {}", filter_id);
+            notifier.initialize(&plugin_config)?;
+            let notifier = Arc::new(notifier);
+            manager.register(notifier.clone());
+            Some(notifier)
+        } else {
+            None
+        };
+        let jira = match &config.plugins.jira {
+            Some(_) => {
+                let mut jira = JiraPlugin::new();
+                jira.initialize(config)?;
+                let jira = Arc::new(jira);
+                manager.register(jira.clone());
+                Some(jira)
             }
-        }
+            None => None,
+        };
+        Ok(Self {
+            manager,
+            notifier,
+            jira,
+        })
+    }
+}
 
-        if matched_templates.is_empty() {
-            return Err(Error::config(format!(
-                "No templates found matching: {:?}. Use 'cxg template list' to see available templates.",
-                filter_ids
-            )));
-        }
+/// Run a planned job, then write its outputs and notify plugins
+///
+/// `show_progress` is off for concurrent `--input` jobs, which would fight over one progress bar.
+async fn execute_scan_job(
+    engine: &CertXGen,
+    job: ScanJob,
+    args: &cli::ScanArgs,
+    config: &Config,
+    plugins: &ScanPlugins,
+    show_progress: bool,
+) -> Result<cert_x_gen::types::ScanResults> {
+    plugins.manager.notify_scan_start(job.id);
 
-        matched_templates
-    } else {
-        // CASE 3: No specific templates - load all templates from directories
-        tracing::debug!("No specific templates specified, loading all from directories");
-        let loaded = engine.load_templates().await?;
-        tracing::info!(
-            "Loaded {} templates from template directories",
-            loaded.len()
-        );
-        loaded
-    };
+    // Initialize progress bar
+    if let Some(progress) = get_progress().filter(|_| show_progress) {
+        progress.init(job.targets.len(), job.templates.len());
+    }
 
-    tracing::info!("Total templates to use: {}", templates.len());
+    // Execute scan
+    tracing::info!("Starting scan execution...");
+    let start = std::time::Instant::now();
+    let usage = config.templates.usage_tracking.then(|| {
+        let ids: Vec<String> = job.templates.iter().map(|t| t.id().to_string()).collect();
+        (ids, job.targets.len())
+    });
+    let mut results = engine.execute_scan(job).await?;
+    let duration = start.elapsed();
 
-    // Debug: Print all loaded template IDs
-    for template in &templates {
-        let metadata = template.metadata();
-        tracing::debug!(
-            "Available template: {} ({})",
-            metadata.id,
-            metadata.language
-        );
+    if let Some((template_ids, targets)) = usage {
+        record_template_usage(&template_ids, targets, &results);
     }
 
-    if templates.is_empty() {
-        return Err(Error::config(
-            "No templates loaded. Please add templates to the templates directory.",
-        ));
+    // Severity threshold only trims what gets reported
+    if let Some(min) = args.report_min_severity {
+        results.retain_min_severity(min.into());
+    }
+    if let Some(min) = args.min_confidence {
+        results.retain_min_confidence(min);
     }
 
-    // Parse targets
-    let mut targets = parse_targets(&args)?;
-    if targets.is_empty() {
-        return Err(Error::config(
-            "No scope provided. Use --scope (aliases: --target, --targets, --target-file, --domain, --cidr, etc.).",
-        ));
+    if args.collapse_clusters {
+        Clusterer::new().collapse(&mut results);
+    } else if args.cluster {
+        Clusterer::new().assign(&mut results);
     }
-    tracing::info!("Parsed {} targets", targets.len());
 
-    // Expand targets for additional ports
-    let mut additional_ports = Vec::new();
-    if !args.ports.is_empty() {
-        additional_ports = parse_port_entries(&args.ports)?;
-        if !additional_ports.is_empty() {
-            targets = expand_targets_for_ports(targets, &additional_ports);
-            tracing::info!(
-                "Expanded to {} targets with additional ports: {:?}",
-                targets.len(),
-                additional_ports
-            );
+    // Finish progress bar
+    if let Some(progress) = get_progress().filter(|_| show_progress) {
+        if args.report_min_severity.is_some()
+            || args.min_confidence.is_some()
+            || args.collapse_clusters
+        {
+            progress.finish_with_reported(results.statistics.reported_findings);
+        } else {
+            progress.finish();
         }
     }
 
-    let mut top_ports = Vec::new();
-    if let Some(count) = args.top_ports {
-        top_ports = select_top_ports(count);
-        if !top_ports.is_empty() {
-            targets = expand_targets_for_ports(targets, &top_ports);
-            tracing::info!(
-                "Expanded to {} targets with top ports: {:?}",
-                targets.len(),
-                top_ports
-            );
+    tracing::info!("Scan completed in {:.2}s", duration.as_secs_f64());
+    tracing::info!(
+        "Found {} total findings ({} reported)",
+        results.statistics.total_findings,
+        results.statistics.reported_findings
+    );
+
+    // Notify plugins
+    plugins.manager.notify_scan_complete(&results);
+
+    // Kept artifacts are copied first so the reports point at the copies
+    if config.execution.keep_artifacts {
+        let workspace = ScanWorkspace::new(ScanWorkspace::default_base(), results.scan_id);
+        let copied = workspace.collect_artifacts(&mut results, Path::new(&args.output))?;
+        if copied > 0 {
+            tracing::info!("Copied {} artifact(s) next to the results", copied);
         }
     }
 
-    // Create template filter
-    // When we've already done targeted loading (direct paths or filter_ids), skip ID filtering
-    let skip_id_filter = has_direct_paths || has_filter_ids;
-    let filter = create_template_filter(&args, skip_id_filter)?;
+    // Output results
+    let output_manager = OutputManager::new().with_overwrite(args.output_overwrite);
+    let formats = output_formats(args);
+    output_manager.write_results(&results, Path::new(&args.output), &formats)?;
 
-    // Debug: Print filter details
-    if !filter.ids.is_empty() {
-        tracing::info!("Filtering templates by IDs: {:?}", filter.ids);
-    }
-    if !filter.tags.is_empty() {
-        tracing::info!("Filtering templates by tags: {:?}", filter.tags);
+    if formats.iter().any(|f| f == "elasticsearch") || config.output.elasticsearch_url.is_some() {
+        write_elasticsearch_output(&config.output, &results).await?;
     }
-    if !filter.severities.is_empty() {
-        tracing::info!("Filtering templates by severities: {:?}", filter.severities);
+
+    // Findings are dispatched once the report they link to has been written
+    for finding in &results.findings {
+        plugins.manager.notify_finding(finding);
     }
-    if !filter.languages.is_empty() {
-        tracing::info!("Filtering templates by languages: {:?}", filter.languages);
+    if let Some(notifier) = &plugins.notifier {
+        notifier.flush().await;
     }
-    if !filter.exclude_ids.is_empty() {
-        tracing::info!("Excluding templates: {:?}", filter.exclude_ids);
+    if let Some(jira) = &plugins.jira {
+        jira.flush().await;
     }
 
-    // Create scan job
-    let mut job = engine.create_scan_job(targets, templates);
-    let templates_before = job.templates.len();
-    job.filter_templates(&filter);
+    // Print summary
+    print_scan_summary(&results, args.summary_by.as_deref());
 
-    // Apply mode-based template filtering
-    if args.safe {
-        // Safe mode: Exclude dangerous templates
-        let dangerous_tags = vec![
-            "dos",
-            "resource-exhaustion",
-            "intrusive",
-            "destructive",
-            "brute-force",
-            "exploit",
-        ];
-        let before_safe = job.templates.len();
-        job.templates.retain(|template| {
-            let metadata = template.metadata();
-            !dangerous_tags
-                .iter()
-                .any(|tag| metadata.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
-        });
-        let after_safe = job.templates.len();
-        if before_safe != after_safe {
-            tracing::info!(
-                "Safe mode: Excluded {} dangerous templates (DoS, resource-exhaustion, etc.)",
-                before_safe - after_safe
-            );
+    Ok(results)
+}
+
+/// Run every job of an `--input` jobs file with one engine and one template load
+async fn run_scan_jobs(
+    engine: &CertXGen,
+    args: &cli::ScanArgs,
+    config: &Config,
+    input: &Path,
+) -> Result<()> {
+    use cert_x_gen::jobs::{run_jobs, JobOutcome, JobSpec, JobsSummary};
+
+    if args.watch {
+        return Err(Error::config("--watch cannot be combined with --input"));
+    }
+    let specs = JobSpec::load_file(input)?;
+    // Resolve every job's options first so a typo in the last job fails before any scanning
+    let job_args = specs
+        .iter()
+        .map(|spec| job_scan_args(args, spec))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Templates are loaded and compiled once for all jobs
+    let loaded_targeted = !args.templates.is_empty() || !args.test_creds.is_empty();
+    let mut selection = ScanBuilder::new(config.clone()).test_credentials(&args.test_creds);
+    if !args.templates.is_empty() {
+        selection = selection.templates(&args.templates);
+    }
+    let dry_run = args.dry_run || args.dry_run_json;
+    let mut pool =
+        engine.create_shared_scan_job(Vec::new(), selection.load_templates(engine).await?);
+    if !args.no_precompile && !dry_run {
+        precompile_templates(&mut pool).await;
+    }
+    let templates = pool.templates;
+    // Jobs without their own `templates:` keep the command line's selection
+    let targeted = |index: usize| loaded_targeted && specs[index].templates.is_empty();
+    tracing::info!(
+        "Running {} job(s) from {} with {} templates",
+        specs.len(),
+        input.display(),
+        templates.len()
+    );
+
+    if dry_run {
+        let mut plans = Vec::new();
+        for (index, spec) in specs.iter().enumerate() {
+            let plan =
+                prepare_input_job(engine, &job_args[index], templates.clone(), targeted(index))
+                    .await?
+                    .plan();
+            if args.dry_run_json {
+                plans.push(serde_json::json!({ "name": spec.name(), "plan": plan }));
+            } else {
+                println!("\n{} {}", console::style("Job").bold(), spec.name());
+                print_scan_plan(&plan);
+            }
+        }
+        if args.dry_run_json {
+            println!("{}", serde_json::to_string_pretty(&plans)?);
         }
+        return Ok(());
     }
 
-    if args.passive {
-        // Passive mode: Only include passive templates or exclude active ones
-        let active_tags = vec!["active", "probe", "intrusive", "exploit"];
-        let before_passive = job.templates.len();
-        job.templates.retain(|template| {
-            let metadata = template.metadata();
-            // Include if tagged as passive, exclude if tagged as active
-            let has_passive_tag = metadata
-                .tags
-                .iter()
-                .any(|t| t.eq_ignore_ascii_case("passive"));
-            let has_active_tag = active_tags
-                .iter()
-                .any(|tag| metadata.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
-            has_passive_tag || !has_active_tag
-        });
-        let after_passive = job.templates.len();
-        if before_passive != after_passive {
-            tracing::info!(
-                "Passive mode: Excluded {} active probe templates",
-                before_passive - after_passive
-            );
+    let plugins = ScanPlugins::new(config, args)?;
+    let show_progress = args.jobs_parallel <= 1;
+    let outcomes = run_jobs(specs.len(), args.jobs_parallel, args.fail_fast, |index| {
+        let name = specs[index].name();
+        let job_args = &job_args[index];
+        let templates = templates.clone();
+        let plugins = &plugins;
+        async move {
+            let start = std::time::Instant::now();
+            let result = async {
+                let job = prepare_input_job(engine, job_args, templates, targeted(index))
+                    .await?
+                    .job;
+                tracing::info!(
+                    "Job {}: {} targets × {} templates",
+                    name,
+                    job.targets.len(),
+                    job.templates.len()
+                );
+                execute_scan_job(engine, job, job_args, config, plugins, show_progress).await
+            }
+            .await;
+            let secs = start.elapsed().as_secs_f64();
+            match result {
+                Ok(results) => Ok(JobOutcome::completed(
+                    name,
+                    &job_args.output,
+                    &results,
+                    secs,
+                )),
+                Err(e) => {
+                    tracing::error!("Job {} failed: {}", name, e);
+                    Err(JobOutcome::failed(name, &job_args.output, &e, secs))
+                }
+            }
+        }
+    })
+    .await;
+
+    let mut summary = JobsSummary::default();
+    for (spec, outcome) in specs.iter().zip(outcomes) {
+        match outcome {
+            Some(Ok(outcome) | Err(outcome)) => summary.jobs.push(outcome),
+            None => summary.skipped.push(spec.name().to_string()),
         }
     }
+    print_jobs_summary(&summary);
+    print_template_profile();
+
+    let summary_path = PathBuf::from(format!("{}.jobs.json", args.output));
+    fs::write(&summary_path, serde_json::to_string_pretty(&summary)?)?;
+    println!("Jobs summary written to {}", summary_path.display());
+
+    if summary.failed() > 0 {
+        return Err(Error::Execution(format!(
+            "{} of {} jobs failed",
+            summary.failed(),
+            specs.len()
+        )));
+    }
+    Ok(())
+}
 
-    let templates_after = job.templates.len();
+/// Build one `--input` job over the shared template pool
+///
+/// `targeted` means the pool was picked by path or ID and the job keeps that selection, so
+/// neither ID filtering nor deprecated exclusion applies.
+async fn prepare_input_job(
+    engine: &CertXGen,
+    job_args: &cli::ScanArgs,
+    templates: Vec<Arc<dyn Template>>,
+    targeted: bool,
+) -> Result<PreparedScan> {
+    scan_builder(job_args, engine.config(), targeted)
+        .await?
+        .with_templates(templates)
+        .include_deprecated(job_args.include_deprecated || targeted)
+        .prepare(engine)
+        .await
+}
 
-    tracing::info!(
-        "Templates selected: {} (total available: {})",
-        templates_after,
-        templates_before
-    );
+/// Scan options for one `--input` job: its fields over the command line's
+fn job_scan_args(args: &cli::ScanArgs, spec: &cert_x_gen::jobs::JobSpec) -> Result<cli::ScanArgs> {
+    use clap::ValueEnum;
+
+    let invalid = |field: &str, value: &str| {
+        Error::config(format!(
+            "Job '{}': invalid {} '{}'",
+            spec.name(),
+            field,
+            value
+        ))
+    };
 
-    // List selected templates in verbose mode
-    if templates_after > 0 && templates_after <= 10 {
-        for template in &job.templates {
-            tracing::info!(
-                "  - {} ({})",
-                template.metadata().id,
-                template.metadata().language
-            );
-        }
+    let mut job_args = args.clone();
+    // The job's scope replaces every command-line scope source
+    job_args.scope = spec.scope.clone();
+    job_args.scope_from.clear();
+    job_args.openapi = None;
+    if !spec.templates.is_empty() {
+        job_args.templates = spec.templates.clone();
+    }
+    if !spec.tags.is_empty() {
+        job_args.tags = Some(spec.tags.join(","));
+    }
+    if !spec.severity.is_empty() {
+        let severities = spec
+            .severity
+            .iter()
+            .map(|s| cli::SeverityArg::from_str(s, true).map_err(|_| invalid("severity", s)))
+            .collect::<Result<_>>()?;
+        job_args.severity = Some(severities);
     }
+    if !spec.languages.is_empty() {
+        let languages = spec
+            .languages
+            .iter()
+            .map(|l| cli::LanguageArg::from_str(l, true).map_err(|_| invalid("language", l)))
+            .collect::<Result<_>>()?;
+        job_args.template_language = Some(languages);
+    }
+    if !spec.exclude.is_empty() {
+        job_args.exclude_templates = Some(spec.exclude.join(","));
+    }
+    if !spec.ports.is_empty() {
+        job_args.ports = spec.ports.clone();
+    }
+    if spec.top_ports.is_some() {
+        job_args.top_ports = spec.top_ports;
+    }
+    job_args.safe |= spec.safe;
+    job_args.passive |= spec.passive;
+    job_args.aggressive |= spec.aggressive;
+    job_args.stealth |= spec.stealth;
+    job_args.output = spec
+        .output
+        .clone()
+        .unwrap_or_else(|| format!("{}-{}", args.output, spec.name()));
+    if let Some(formats) = &spec.output_format {
+        job_args.output_format = formats.clone();
+    }
+    Ok(job_args)
+}
 
-    if !additional_ports.is_empty() || !top_ports.is_empty() {
-        let mut combined = additional_ports.clone();
-        combined.extend(top_ports.iter().copied());
-        combined.sort_unstable();
-        combined.dedup();
-        job.context.additional_ports = combined;
-        tracing::info!(
-            "Adding {} additional ports to scan (custom + top): {:?}",
-            job.context.additional_ports.len(),
-            job.context.additional_ports
+/// Combined table of `--input` job results
+fn print_jobs_summary(summary: &cert_x_gen::jobs::JobsSummary) {
+    use console::style;
+
+    println!("\n{}", style("Jobs summary").bold());
+    println!(
+        "  {:<24} {:>8} {:>10} {:>9} {:>9}  STATUS",
+        "JOB", "TARGETS", "TEMPLATES", "FINDINGS", "TIME"
+    );
+    for job in &summary.jobs {
+        let status = match &job.error {
+            Some(error) => style(format!("failed: {}", error)).red(),
+            None => style(format!("ok -> {}", job.output)).green(),
+        };
+        println!(
+            "  {:<24} {:>8} {:>10} {:>9} {:>8.1}s  {}",
+            job.name,
+            job.targets,
+            job.templates,
+            job.total_findings(),
+            job.duration_secs,
+            status
         );
     }
+    for name in &summary.skipped {
+        println!("  {:<24} {}", name, style("skipped (--fail-fast)").yellow());
+    }
+    println!(
+        "\n{} job(s), {} failed, {} skipped, {} finding(s) in total",
+        summary.jobs.len() + summary.skipped.len(),
+        summary.failed(),
+        summary.skipped.len(),
+        summary.total_findings()
+    );
+}
 
-    if let Some(ref override_ports) = args.override_ports {
-        let override_ports = parse_ports(override_ports)?;
-        job.context.override_ports = Some(override_ports.clone());
-        tracing::info!(
-            "Overriding template default ports with: {:?}",
-            override_ports
+/// Human-readable `--dry-run` output
+fn print_scan_plan(plan: &ScanPlan) {
+    use console::style;
+
+    println!(
+        "\n{} Scan plan (dry run, no requests sent)",
+        style("📋").cyan()
+    );
+    println!(
+        "\n{} ({}):",
+        style("Templates").bold(),
+        plan.templates.len()
+    );
+    for template in &plan.templates {
+        println!(
+            "  {:<40} {:<8} {:<9} ~{} request(s)",
+            template.id, template.language, template.severity, template.estimated_requests
         );
     }
+    println!("\n{} ({}):", style("Targets").bold(), plan.targets.len());
+    for target in &plan.targets {
+        println!("  {}", target);
+    }
+
+    let languages: Vec<String> = plan
+        .languages
+        .iter()
+        .map(|(language, count)| format!("{} ({})", language, count))
+        .collect();
+    println!("\n{}", style("Summary").bold());
+    println!("  Engines:              {}", languages.join(", "));
+    println!("  Work units:           {}", plan.work_units);
+    println!("  Requests per target:  ~{}", plan.requests_per_target);
+    println!("  Total requests:       ~{}", plan.estimated_requests);
+    if !plan.additional_ports.is_empty() {
+        println!("  Additional ports:     {:?}", plan.additional_ports);
+    }
+    if let Some(ports) = &plan.override_ports {
+        println!("  Override ports:       {:?}", ports);
+    }
+    for (stage, count) in plan.excluded.iter().filter(|(_, count)| **count > 0) {
+        println!("  Excluded by {}: {}", stage, count);
+    }
+    for expansion in &plan.unexpanded_scope {
+        println!("  Not expanded:         {}", expansion);
+    }
+}
+
+/// Output formats of a scan: `--output-format`, then `--output-template`
+fn output_formats(args: &cli::ScanArgs) -> Vec<String> {
+    let mut formats: Vec<String> = args
+        .output_format
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .collect();
+    if let Some(template) = &args.output_template {
+        formats.push(template.to_string_lossy().into_owned());
+    }
+    formats
+}
 
+/// `file://` URL of the first report file written by a scan
+fn report_file_url(args: &cli::ScanArgs) -> Option<String> {
+    let format = args
+        .output_format
+        .split(',')
+        .map(str::trim)
+        .find(|f| *f != "elasticsearch")?;
+    let path = PathBuf::from(&args.output).with_extension(format);
+    let path = std::env::current_dir().ok()?.join(path);
+    Some(format!("file://{}", path.display()))
+}
+
+/// Bulk-index scan findings into the configured Elasticsearch cluster
+async fn write_elasticsearch_output(
+    output: &cert_x_gen::config::OutputConfig,
+    results: &cert_x_gen::types::ScanResults,
+) -> Result<()> {
+    use cert_x_gen::output::ElasticsearchOutput;
+
+    let url = output.elasticsearch_url.as_deref().ok_or_else(|| {
+        Error::config(
+            "--output-format elasticsearch requires output.elasticsearch_url in the config",
+        )
+    })?;
+
+    let elastic = ElasticsearchOutput::new(url, output.elasticsearch.clone())?;
+    let summary = elastic.write_results(results).await?;
     tracing::info!(
-        "Scan job created: {} targets × {} templates = {} total checks",
-        job.targets.len(),
-        job.templates.len(),
-        job.total_work_units()
+        "Indexed {} findings into Elasticsearch index '{}' ({} rejected)",
+        summary.indexed,
+        output.elasticsearch.index,
+        summary.failed
     );
+    Ok(())
+}
 
-    // Initialize plugin system
-    let mut plugin_manager = PluginManager::new();
-    plugin_manager.register(Arc::new(LoggingPlugin::new()));
-    plugin_manager.notify_scan_start(job.id);
+/// Re-run changed templates against the scan targets until Ctrl-C
+async fn watch_templates(
+    engine: &CertXGen,
+    paths: &[PathBuf],
+    targets: Vec<cert_x_gen::types::Target>,
+    overrides: PerTargetOverrides,
+    context: cert_x_gen::types::Context,
+) -> Result<()> {
+    use cert_x_gen::template::TemplateWatcher;
+    use console::style;
 
-    // Initialize progress bar
-    if let Some(progress) = get_progress() {
-        progress.init(job.targets.len(), job.templates.len());
-    }
+    let mut watcher = TemplateWatcher::new(paths, std::time::Duration::from_millis(300))?;
+    println!(
+        "\n{} Watching {} path(s) for changes (Ctrl-C to exit)",
+        style("👀").cyan(),
+        paths.len()
+    );
 
-    // Execute scan
-    tracing::info!("Starting scan execution...");
-    let start = std::time::Instant::now();
-    let results = engine.execute_scan(job).await?;
-    let duration = start.elapsed();
+    loop {
+        let changed = tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            changed = watcher.next_changes() => match changed {
+                Some(changed) => changed,
+                None => break,
+            },
+        };
 
-    // Finish progress bar
-    if let Some(progress) = get_progress() {
-        progress.finish();
+        for path in changed {
+            println!("\n{} {}", style("↻").yellow(), path.display());
+
+            let template = match engine.template_loader().load_template(&path).await {
+                Ok(template) => template,
+                Err(e) => {
+                    println!("  {} {}", style("✗ load failed:").red(), e);
+                    continue;
+                }
+            };
+            if let Err(e) = template.validate() {
+                println!("  {} {}", style("✗ invalid:").red(), e);
+                continue;
+            }
+            if let Err(e) = template.prepare().await {
+                println!("  {} {}", style("✗ compile failed:").red(), e);
+                continue;
+            }
+
+            let mut job = engine.create_scan_job(targets.clone(), vec![template]);
+            job.per_target_overrides = overrides.clone();
+            job.context = context.clone();
+            match engine.execute_scan(job).await {
+                Ok(results) if results.findings.is_empty() => {
+                    println!("  {} no findings", style("✓").green());
+                }
+                Ok(results) => {
+                    for finding in &results.findings {
+                        println!(
+                            "  [{}] {} - {}",
+                            style(finding.severity.to_string()).bold(),
+                            finding.target,
+                            finding.title
+                        );
+                    }
+                }
+                Err(e) => println!("  {} {}", style("✗ execution failed:").red(), e),
+            }
+        }
     }
 
-    tracing::info!("Scan completed in {:.2}s", duration.as_secs_f64());
-    tracing::info!("Found {} total findings", results.findings.len());
+    println!("\nStopped watching templates");
+    Ok(())
+}
 
-    // Notify plugins
-    plugin_manager.notify_scan_complete(&results);
+/// Run two template versions side by side and report where they differ
+async fn run_ab_test_scan(
+    engine: &CertXGen,
+    args: &cli::ScanArgs,
+    path_a: &Path,
+    path_b: &Path,
+) -> Result<()> {
+    use cert_x_gen::abtest::{run_ab_test, AbOutcome};
+    use console::style;
 
-    // Output results
-    let output_manager = OutputManager::new();
-    let formats: Vec<String> = args
-        .output_format
-        .split(',')
-        .map(|s| s.trim().to_string())
-        .collect();
+    let mut loaded = Vec::with_capacity(2);
+    for path in [path_a, path_b] {
+        let template = engine.template_loader().load_template(path).await?;
+        template.validate()?;
+        template.prepare().await?;
+        loaded.push(template);
+    }
 
-    let output_path = PathBuf::from(&args.output);
-    output_manager.write_results(&results, &output_path, &formats)?;
+    let job = scan_builder(args, engine.config(), false)
+        .await?
+        .with_templates(Vec::new())
+        .prepare(engine)
+        .await?
+        .job;
+
+    let result = run_ab_test(
+        loaded[0].as_ref(),
+        loaded[1].as_ref(),
+        &job.targets,
+        &job.context,
+    )
+    .await;
 
-    // Print summary
-    print_scan_summary(&results);
+    println!(
+        "\n{} A: {}  B: {}",
+        style("A/B test").bold(),
+        result.template_a,
+        result.template_b
+    );
+    for target in &result.targets {
+        let label = match target.outcome {
+            AbOutcome::Agreement => style("agree").green(),
+            AbOutcome::Disagreement => style("only A").yellow(),
+            AbOutcome::Regression => style("only B").red(),
+        };
+        println!(
+            "  [{}] {} (A: {}, B: {})",
+            label,
+            target.target,
+            target.findings_a.len(),
+            target.findings_b.len()
+        );
+        for (name, error) in [("A", &target.error_a), ("B", &target.error_b)] {
+            if let Some(error) = error {
+                println!("      {} {}: {}", style("✗").red(), name, error);
+            }
+        }
+        for line in &target.evidence_diff {
+            println!("      {}", line);
+        }
+    }
+    println!(
+        "\n{} agreements, {} disagreements, {} regressions",
+        result.agreements(),
+        result.disagreements(),
+        result.regressions()
+    );
+
+    let report_path = PathBuf::from(format!("{}.ab-test.json", args.output));
+    std::fs::write(&report_path, serde_json::to_string_pretty(&result)?)?;
+    println!("Report written to {}", report_path.display());
 
     Ok(())
 }
@@ -595,6 +1169,13 @@ fn apply_scan_args_to_config(config: &mut Config, args: &cli::ScanArgs) {
     config.execution.stealth_mode = args.stealth;
     config.execution.passive_mode = args.passive;
     config.execution.safe_mode = args.safe;
+    config.execution.adaptive_concurrency |= args.adaptive_concurrency;
+    if let Some(min) = args.min_concurrency {
+        config.execution.min_concurrency = min;
+    }
+    if let Some(max) = args.max_concurrency {
+        config.execution.max_concurrency = Some(max);
+    }
 
     // Apply mode-specific optimizations
     if args.aggressive {
@@ -663,7 +1244,7 @@ fn apply_scan_args_to_config(config: &mut Config, args: &cli::ScanArgs) {
     }
 
     if let Some(proxy) = &args.proxy {
-        config.network.proxy = Some(proxy.clone());
+        config.network.proxy = Some(proxy.clone().into());
     }
 
     if let Some(user_agent) = &args.user_agent {
@@ -676,8 +1257,54 @@ fn apply_scan_args_to_config(config: &mut Config, args: &cli::ScanArgs) {
     if let Some(rate_limit) = args.rate_limit {
         config.network.rate_limit = Some(rate_limit);
     }
+    if let Some(domain_rate_limit) = args.domain_rate_limit {
+        config.network.per_domain_rate_limit = Some(domain_rate_limit);
+    }
+    if let Some(jitter_factor) = args.jitter_factor {
+        config.network.jitter_factor = jitter_factor;
+    }
+    if let Some(max) = args.max_requests_per_host {
+        config.network.max_requests_per_host = Some(max);
+    }
+    if let Some(max) = args.max_requests_total {
+        config.network.max_requests_total = Some(max);
+    }
+    if let Some(dir) = &args.trace_requests {
+        config.network.trace_requests = Some(dir.clone());
+        config.network.trace_redact = !args.trace_no_redact;
+    }
+    config.network.probe_summary |= args.probe_summary;
+    if args.no_detect_scheme || (args.passive && !args.detect_scheme) {
+        config.network.detect_scheme = false;
+    } else if args.detect_scheme {
+        config.network.detect_scheme = true;
+    }
 
     config.output.stream = args.stream;
+    config.output.screenshots |= args.screenshot;
+    config.execution.keep_artifacts |= args.keep_artifacts;
+    if let Some(days) = args.workspace_retention_days {
+        config.execution.workspace_retention_days = days;
+    }
+    if args.no_usage_tracking {
+        config.templates.usage_tracking = false;
+    }
+
+    if let Some(path) = &args.browser.browser_path {
+        config.browser.executable = Some(path.clone());
+    }
+    if let Some(tabs) = args.browser.browser_tabs {
+        config.browser.max_tabs = tabs;
+    }
+    if let Some(timeout) = args.browser.browser_timeout {
+        config.browser.page_timeout_secs = timeout;
+    }
+    if args.browser.browser_headful {
+        config.browser.headless = false;
+    }
+    if args.browser.browser_no_sandbox {
+        config.browser.sandbox = false;
+    }
 
     // Apply template directory if specified
     if let Some(template_dir) = &args.template_dir {
@@ -708,182 +1335,117 @@ fn apply_scan_args_to_config(config: &mut Config, args: &cli::ScanArgs) {
         }
         if !config.network.cookies.is_empty() {
             tracing::info!(
-                "Using {} cookie(s) for authenticated scanning",
-                config.network.cookies.len()
-            );
-        }
-    }
-}
-
-/// Expand targets to create one target per port
-/// This enables testing multiple ports on the same host
-fn expand_targets_for_ports(targets: Vec<Target>, ports: &[u16]) -> Vec<Target> {
-    if ports.is_empty() {
-        return targets;
-    }
-
-    let mut expanded = Vec::new();
-
-    for target in targets {
-        // If target already has a port, keep it and add additional ports
-        if target.port.is_some() {
-            expanded.push(target.clone());
-        }
-
-        // Create a target for each additional port
-        for &port in ports {
-            // Skip if this port is already the target's port
-            if target.port == Some(port) {
-                continue;
-            }
-
-            let mut new_target = target.clone();
-            new_target.port = Some(port);
-            expanded.push(new_target);
-        }
-    }
-
-    expanded
-}
-
-/// Parse a single target string (supports host:port format)
-fn parse_target_string(target_str: &str) -> Target {
-    if let Ok(url) = url::Url::parse(target_str) {
-        if let Some(host) = url.host_str() {
-            let protocol = match url.scheme().to_lowercase().as_str() {
-                "http" => Protocol::Http,
-                "https" => Protocol::Https,
-                "tcp" => Protocol::Tcp,
-                "udp" => Protocol::Udp,
-                other => Protocol::Custom(other.to_string()),
-            };
-
-            let mut target = Target::new(host, protocol);
-            if let Some(port) = url.port() {
-                target.port = Some(port);
-            }
-            return target;
-        }
-    }
-
-    match utils::parse_target(target_str) {
-        Ok((host, port)) => {
-            if let Some(port) = port {
-                let protocol = match port {
-                    80 | 8000 | 8080 => Protocol::Http,
-                    443 | 8443 => Protocol::Https,
-                    _ => Protocol::Https,
-                };
-                Target::with_port(host, port, protocol)
-            } else {
-                Target::new(host, Protocol::Https)
-            }
-        }
-        Err(_) => Target::new(target_str, Protocol::Https),
-    }
-}
-
-/// Parse targets from CLI scope arguments
-fn parse_targets(args: &cli::ScanArgs) -> Result<Vec<Target>> {
-    let mut expanded_entries = Vec::new();
-    let mut in_progress_files = HashSet::new();
-
-    for entry in &args.scope {
-        expand_scope_entry(entry, &mut expanded_entries, &mut in_progress_files)?;
-    }
-
-    let mut seen = HashSet::new();
-    let mut targets = Vec::new();
-
-    for raw in expanded_entries {
-        let trimmed = raw.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-
-        if !seen.insert(trimmed.to_string()) {
-            continue;
-        }
-
-        targets.push(parse_target_string(trimmed));
-    }
-
-    Ok(targets)
-}
-
-fn expand_scope_entry(
-    entry: &str,
-    acc: &mut Vec<String>,
-    file_stack: &mut HashSet<PathBuf>,
-) -> Result<()> {
-    let trimmed = entry.trim();
-    if trimmed.is_empty() {
-        return Ok(());
-    }
-
-    // Allow comma-separated lists inside scope values (e.g., from files)
-    if trimmed.contains(',') {
-        for part in trimmed.split(',') {
-            expand_scope_entry(part, acc, file_stack)?;
-        }
-        return Ok(());
-    }
-
-    let (candidate, forced_file) = if let Some(rest) = trimmed.strip_prefix('@') {
-        (rest, true)
-    } else if let Some(rest) = trimmed.strip_prefix("file://") {
-        (rest, true)
-    } else if let Some(rest) = trimmed.strip_prefix("file:") {
-        (rest, true)
-    } else {
-        (trimmed, false)
-    };
-
-    let path = Path::new(candidate);
-    if forced_file || path.exists() {
-        if !path.exists() {
-            return Err(Error::config(format!(
-                "Scope file not found: {}",
-                candidate
-            )));
-        }
-
-        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
-
-        if !file_stack.insert(canonical.clone()) {
-            return Err(Error::config(format!(
-                "Recursive scope file reference detected: {}",
-                canonical.display()
-            )));
-        }
-
-        let content = fs::read_to_string(path).map_err(|e| {
-            Error::config(format!("Failed to read scope file '{}': {}", candidate, e))
-        })?;
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-            expand_scope_entry(line, acc, file_stack)?;
+                "Using {} cookie(s) for authenticated scanning",
+                config.network.cookies.len()
+            );
         }
+    }
 
-        file_stack.remove(&canonical);
-        return Ok(());
+    // Named sessions for multi-account checks
+    let session_entries = args
+        .session_cookie
+        .iter()
+        .map(|entry| (entry, '='))
+        .chain(args.session_header.iter().map(|entry| (entry, ':')));
+    for (entry, separator) in session_entries {
+        let parsed = entry.split_once(':').and_then(|(name, credential)| {
+            let (key, value) = credential.split_once(separator)?;
+            Some((name.trim(), key.trim(), value.trim()))
+        });
+        let Some((name, key, value)) =
+            parsed.filter(|(name, key, _)| !name.is_empty() && !key.is_empty())
+        else {
+            tracing::warn!("Ignoring malformed session credential: {}", entry);
+            continue;
+        };
+        let session = SessionState::named(&mut config.network.sessions, name);
+        let credentials = if separator == '=' {
+            &mut session.cookies
+        } else {
+            &mut session.headers
+        };
+        credentials.push((key.to_string(), value.to_string()));
+    }
+    if !config.network.sessions.is_empty() {
+        tracing::info!(
+            "Using {} named session(s): {}",
+            config.network.sessions.len(),
+            config
+                .network
+                .sessions
+                .iter()
+                .map(|session| session.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
     }
+}
 
-    if trimmed.contains('/') && !trimmed.contains("://") {
-        if let Ok(addresses) = utils::parse_cidr(trimmed) {
-            for ip in addresses {
-                acc.push(ip.to_string());
-            }
-            return Ok(());
-        }
+/// Add a finished scan to the local usage counters; failures only warn
+fn record_template_usage(
+    template_ids: &[String],
+    targets: usize,
+    results: &cert_x_gen::types::ScanResults,
+) {
+    use cert_x_gen::template::UsageStore;
+
+    let recorded = UsageStore::load_default().and_then(|mut store| {
+        store.record_scan(template_ids.iter().map(String::as_str), targets, results);
+        store.save()
+    });
+    if let Err(e) = recorded {
+        tracing::warn!("Failed to record template usage: {}", e);
     }
+}
 
-    acc.push(trimmed.to_string());
-    Ok(())
+/// A scan builder with the scope, ports, filter and modes of `args`
+///
+/// `targeted` means the templates were picked by path or ID, so ID filtering is already done.
+async fn scan_builder(
+    args: &cli::ScanArgs,
+    config: &Config,
+    targeted: bool,
+) -> Result<ScanBuilder> {
+    let mut builder = ScanBuilder::new(config.clone())
+        .targets(&args.scope)
+        .offline(args.offline)
+        .dry_run(args.dry_run || args.dry_run_json)
+        .normalize(!args.no_normalize)
+        .ports(parse_port_entries(&args.ports)?)
+        .max_endpoints(args.max_endpoints)
+        .filter(create_template_filter(args, targeted)?)
+        .include_deprecated(args.include_deprecated)
+        .follow_deprecations(args.follow_deprecations)
+        .safe_mode(args.safe)
+        .passive_mode(args.passive)
+        .aggressive_mode(args.aggressive)
+        .stealth_mode(args.stealth)
+        .randomize_targets(args.randomize_targets)
+        .randomize_templates(args.randomize_templates);
+
+    for spec in &args.scope_from {
+        builder = builder.scope_from(scope::ScopeSource::parse(spec)?);
+    }
+    for domain in &args.zone_transfer {
+        builder = builder.zone_transfer(domain);
+    }
+    for label in &args.labels {
+        let (key, value) = scope::parse_label(label)?;
+        builder = builder.label(key, value);
+    }
+    if let Some(source) = &args.openapi {
+        builder = builder.openapi(ApiSpec::load(source, args.offline).await?);
+    }
+    if let Some(count) = args.top_ports {
+        builder = builder.top_ports(count);
+    }
+    if let Some(override_ports) = &args.override_ports {
+        builder = builder.override_ports(parse_ports(override_ports)?);
+    }
+    if let Some(seed) = args.seed {
+        builder = builder.seed(seed);
+    }
+    Ok(builder)
 }
 
 /// Create template filter from CLI arguments
@@ -922,412 +1484,28 @@ fn create_template_filter(args: &cli::ScanArgs, skip_id_filter: bool) -> Result<
     Ok(filter)
 }
 
-/// Parse port specification string (supports individual ports and ranges)
-/// Examples: "80,443,8000-9000" -> [80, 443, 8000, 8001, ..., 9000]
-fn parse_ports(port_spec: &str) -> Result<Vec<u16>> {
-    let mut ports = Vec::new();
-
-    for part in port_spec.split(',') {
-        let part = part.trim();
-
-        if part.contains('-') {
-            // Handle range (e.g., "8000-9000")
-            let range_parts: Vec<&str> = part.split('-').collect();
-            if range_parts.len() != 2 {
-                return Err(Error::config(format!("Invalid port range: {}", part)));
-            }
-
-            let start: u16 = range_parts[0]
-                .trim()
-                .parse()
-                .map_err(|_| Error::config(format!("Invalid port number: {}", range_parts[0])))?;
-            let end: u16 = range_parts[1]
-                .trim()
-                .parse()
-                .map_err(|_| Error::config(format!("Invalid port number: {}", range_parts[1])))?;
-
-            if start > end {
-                return Err(Error::config(format!(
-                    "Invalid port range: {} > {}",
-                    start, end
-                )));
-            }
-
-            for port in start..=end {
-                ports.push(port);
-            }
-        } else {
-            // Handle single port
-            let port: u16 = part
-                .parse()
-                .map_err(|_| Error::config(format!("Invalid port number: {}", part)))?;
-            ports.push(port);
-        }
-    }
-
-    // Remove duplicates and sort
-    ports.sort_unstable();
-    ports.dedup();
-
-    Ok(ports)
-}
-
-fn parse_port_entries(entries: &[String]) -> Result<Vec<u16>> {
-    let mut collected = Vec::new();
-    let mut file_stack = HashSet::new();
-
-    for entry in entries {
-        expand_port_entry(entry, &mut collected, &mut file_stack)?;
-    }
-
-    collected.sort_unstable();
-    collected.dedup();
-    Ok(collected)
-}
-
-fn expand_port_entry(
-    entry: &str,
-    acc: &mut Vec<u16>,
-    file_stack: &mut HashSet<PathBuf>,
-) -> Result<()> {
-    let trimmed = entry.trim();
-    if trimmed.is_empty() {
-        return Ok(());
-    }
-
-    if trimmed.contains(',') {
-        for part in trimmed.split(',') {
-            expand_port_entry(part, acc, file_stack)?;
-        }
-        return Ok(());
-    }
-
-    let (candidate, forced_file) = if let Some(rest) = trimmed.strip_prefix('@') {
-        (rest, true)
-    } else if let Some(rest) = trimmed.strip_prefix("file://") {
-        (rest, true)
-    } else if let Some(rest) = trimmed.strip_prefix("file:") {
-        (rest, true)
-    } else {
-        (trimmed, false)
-    };
-
-    let path = Path::new(candidate);
-    if forced_file || path.exists() {
-        if !path.exists() {
-            return Err(Error::config(format!("Port file not found: {}", candidate)));
-        }
-
-        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
-
-        if !file_stack.insert(canonical.clone()) {
-            return Err(Error::config(format!(
-                "Recursive port file reference detected: {}",
-                canonical.display()
-            )));
-        }
-
-        let content = fs::read_to_string(path).map_err(|e| {
-            Error::config(format!("Failed to read port file '{}': {}", candidate, e))
-        })?;
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-            expand_port_entry(line, acc, file_stack)?;
-        }
-
-        file_stack.remove(&canonical);
-        return Ok(());
-    }
-
-    acc.extend(parse_ports(trimmed)?);
-    Ok(())
-}
-
-fn select_top_ports(count: u16) -> Vec<u16> {
-    utils::top_ports(count)
-}
-
-/// Check if a path is a template source file (actual template, not a list file)
-fn is_template_source_file(path: &Path) -> bool {
-    match path.extension().and_then(|ext| ext.to_str()) {
-        Some(ext) => matches!(
-            ext.to_ascii_lowercase().as_str(),
-            "yaml" | "yml" |  // YAML templates
-            "py" |            // Python
-            "js" |            // JavaScript
-            "rs" |            // Rust
-            "c" |             // C
-            "cpp" | "cc" | "cxx" | // C++
-            "java" |          // Java
-            "go" |            // Go
-            "rb" |            // Ruby
-            "pl" |            // Perl
-            "php" |           // PHP
-            "sh" | "bash" // Shell
-        ),
-        None => false,
-    }
-}
-
-/// Separate template entries into direct file paths and filter IDs
-/// Returns (direct_template_paths, filter_ids)
-///
-/// When a relative path is provided, it will be resolved against the template directories.
-fn separate_template_entries(
-    entries: &[String],
-    template_dirs: &[PathBuf],
-) -> Result<(Vec<PathBuf>, Vec<String>)> {
-    let mut direct_paths = Vec::new();
-    let mut filter_ids = Vec::new();
-
-    tracing::debug!(
-        "Separating {} template entries (template_dirs: {:?})",
-        entries.len(),
-        template_dirs
-    );
-
-    for entry in entries {
-        let trimmed = entry.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-
-        // Handle comma-separated entries
-        if trimmed.contains(',') {
-            for part in trimmed.split(',') {
-                let (mut paths, mut ids) =
-                    separate_template_entries(&[part.to_string()], template_dirs)?;
-                direct_paths.append(&mut paths);
-                filter_ids.append(&mut ids);
-            }
-            continue;
-        }
-
-        let path = Path::new(trimmed);
-        let exists = path.exists();
-        let is_file = path.is_file();
-        let is_template = is_template_source_file(path);
-        let is_abs = path.is_absolute();
-
-        tracing::debug!(
-            "Entry '{}': exists={}, is_file={}, is_template={}, is_absolute={}",
-            trimmed,
-            exists,
-            is_file,
-            is_template,
-            is_abs
-        );
-
-        // Check if it's a direct path to a template file (absolute or relative that exists)
-        if exists && is_file && is_template {
-            tracing::info!("Detected direct template path: {}", trimmed);
-            direct_paths.push(path.to_path_buf());
-        } else if is_abs && is_template && !exists {
-            // Absolute path that looks like a template but doesn't exist - report error
-            return Err(Error::config(format!(
-                "Template file not found: {}",
-                trimmed
-            )));
-        } else if !is_abs && (trimmed.contains('/') || trimmed.contains('\\')) && is_template {
-            // Relative path that looks like a template - try resolving against template directories
-            let mut found = false;
-
-            for template_dir in template_dirs {
-                let resolved_path = template_dir.join(trimmed);
-                tracing::debug!(
-                    "Trying to resolve relative path: {} -> {}",
-                    trimmed,
-                    resolved_path.display()
-                );
-
-                if resolved_path.exists() && resolved_path.is_file() {
-                    tracing::info!(
-                        "Resolved relative template path: {} -> {}",
-                        trimmed,
-                        resolved_path.display()
-                    );
-                    direct_paths.push(resolved_path);
-                    found = true;
-                    break;
-                }
-            }
-
-            if !found {
-                // Couldn't find the template in any directory
-                let searched_dirs: Vec<String> = template_dirs
-                    .iter()
-                    .map(|d| d.display().to_string())
-                    .collect();
-                return Err(Error::config(format!(
-                    "Template file not found: {} (searched in: {})",
-                    trimmed,
-                    if searched_dirs.is_empty() {
-                        "current directory".to_string()
-                    } else {
-                        searched_dirs.join(", ")
-                    }
-                )));
-            }
-        } else {
-            // Treat as template ID for filtering
-            tracing::debug!("Treating as filter ID: {}", trimmed);
-            filter_ids.push(trimmed.to_string());
-        }
-    }
-
-    tracing::info!(
-        "Separated template entries: {} direct paths, {} filter IDs",
-        direct_paths.len(),
-        filter_ids.len()
-    );
-
-    Ok((direct_paths, filter_ids))
-}
-
-fn parse_template_entries(entries: &[String]) -> Result<Vec<String>> {
-    let mut collected = Vec::new();
-    let mut file_stack = HashSet::new();
-
-    for entry in entries {
-        expand_template_entry(entry, &mut collected, &mut file_stack)?;
-    }
-
-    let mut seen = HashSet::new();
-    let mut unique = Vec::new();
-
-    for entry in collected {
-        if seen.insert(entry.clone()) {
-            unique.push(entry);
-        }
-    }
-
-    Ok(unique)
-}
-
-fn expand_template_entry(
-    entry: &str,
-    acc: &mut Vec<String>,
-    file_stack: &mut HashSet<PathBuf>,
-) -> Result<()> {
-    let trimmed = entry.trim();
-    if trimmed.is_empty() {
-        return Ok(());
-    }
-
-    if trimmed.contains(',') {
-        for part in trimmed.split(',') {
-            expand_template_entry(part, acc, file_stack)?;
-        }
-        return Ok(());
-    }
-
-    let (candidate, forced_file) = if let Some(rest) = trimmed.strip_prefix('@') {
-        (rest, true)
-    } else if let Some(rest) = trimmed.strip_prefix("file://") {
-        (rest, true)
-    } else if let Some(rest) = trimmed.strip_prefix("file:") {
-        (rest, true)
-    } else {
-        (trimmed, false)
-    };
-
-    let path = Path::new(candidate);
-
-    if forced_file || (path.exists() && path.is_file() && is_template_list_file(path)) {
-        if !path.exists() {
-            return Err(Error::config(format!(
-                "Template list file not found: {}",
-                candidate
-            )));
-        }
-
-        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
-
-        if !file_stack.insert(canonical.clone()) {
-            return Err(Error::config(format!(
-                "Recursive template file reference detected: {}",
-                canonical.display()
-            )));
-        }
-
-        let content = fs::read_to_string(path).map_err(|e| {
-            Error::config(format!(
-                "Failed to read template list '{}': {}",
-                candidate, e
-            ))
-        })?;
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-            expand_template_entry(line, acc, file_stack)?;
-        }
-
-        file_stack.remove(&canonical);
-        return Ok(());
-    }
-
-    acc.push(trimmed.to_string());
-    Ok(())
-}
-
-fn is_template_list_file(path: &Path) -> bool {
-    match path.extension().and_then(|ext| ext.to_str()) {
-        Some(ext) => matches!(
-            ext.to_ascii_lowercase().as_str(),
-            "txt" | "list" | "lst" | "cfg"
-        ),
-        None => false,
-    }
-}
-
 /// Run template validation command
-async fn run_validate_command(
-    path: PathBuf,
-    recursive: bool,
-    _show_score: bool,
-    strict: bool,
-    format: String,
-    _summary: bool,
-    _language: Option<cli::LanguageArg>,
-    _min_score: u8,
-) -> Result<()> {
-    use cert_x_gen::ai::validator::{DiagnosticSeverity, TemplateDiagnostic};
-    use console::style;
-    use std::fs;
-
-    println!("{}", style("═".repeat(80)).dim());
-    println!("{}", style("CERT-X-GEN Template Validator").bold().cyan());
-    println!("{}", style("═".repeat(80)).dim());
-    println!();
+/// Collect template files under a path, optionally recursing into subdirectories
+fn collect_template_files(path: &Path, recursive: bool) -> Vec<PathBuf> {
+    use cert_x_gen::engine::common::is_metadata_sidecar;
 
-    // Create validator
-    let validator = TemplateValidator::new();
-
-    // Collect template files
     let mut template_files = Vec::new();
 
     if path.is_file() {
-        template_files.push(path.clone());
+        template_files.push(path.to_path_buf());
     } else if path.is_dir() {
         if recursive {
-            for entry in walkdir::WalkDir::new(&path)
+            for entry in walkdir::WalkDir::new(path)
                 .into_iter()
                 .filter_map(|e| e.ok())
             {
                 let entry_path = entry.path();
-                if entry_path.is_file() {
+                if entry_path.is_file() && !is_metadata_sidecar(entry_path) {
                     if let Some(ext) = entry_path.extension() {
                         let ext_str = ext.to_string_lossy();
                         if [
-                            "py", "js", "sh", "rb", "pl", "php", "rs", "c", "cpp", "go", "java",
-                            "yaml", "yml",
+                            "py", "js", "sh", "rb", "pl", "php", "rhai", "rs", "c", "cpp", "go",
+                            "java", "yaml", "yml",
                         ]
                         .contains(&ext_str.as_ref())
                         {
@@ -1337,15 +1515,15 @@ async fn run_validate_command(
                 }
             }
         } else {
-            if let Ok(entries) = fs::read_dir(&path) {
+            if let Ok(entries) = fs::read_dir(path) {
                 for entry in entries.filter_map(|e| e.ok()) {
                     let entry_path = entry.path();
-                    if entry_path.is_file() {
+                    if entry_path.is_file() && !is_metadata_sidecar(&entry_path) {
                         if let Some(ext) = entry_path.extension() {
                             let ext_str = ext.to_string_lossy();
                             if [
-                                "py", "js", "sh", "rb", "pl", "php", "rs", "c", "cpp", "go",
-                                "java", "yaml", "yml",
+                                "py", "js", "sh", "rb", "pl", "php", "rhai", "rs", "c", "cpp",
+                                "go", "java", "yaml", "yml",
                             ]
                             .contains(&ext_str.as_ref())
                             {
@@ -1358,6 +1536,56 @@ async fn run_validate_command(
         }
     }
 
+    template_files
+}
+
+/// Options of `template validate`, also used by `ai explain`
+#[derive(Debug, Default)]
+struct ValidateOptions<'a> {
+    /// Validate templates in subdirectories too
+    recursive: bool,
+    /// Fail when any template fails validation
+    strict: bool,
+    /// Treat lint warnings as errors
+    strict_lint: bool,
+    /// `text` or `json`
+    format: String,
+    /// Where `include:` snippets are looked up
+    snippets_dir: Option<&'a Path>,
+    /// Run the `tests:` cases of YAML templates
+    with_tests: bool,
+    /// Ask the AI provider to explain each diagnostic
+    explain: bool,
+}
+
+async fn run_validate_command(path: PathBuf, options: ValidateOptions<'_>) -> Result<()> {
+    use cert_x_gen::ai::validator::{
+        DiagnosticSeverity, LintConfig, TemplateDiagnostic, TemplateLinter,
+    };
+    use console::style;
+    use std::fs;
+
+    let ValidateOptions {
+        recursive,
+        strict,
+        strict_lint,
+        format,
+        snippets_dir,
+        with_tests,
+        explain,
+    } = options;
+
+    println!("{}", style("═".repeat(80)).dim());
+    println!("{}", style("CERT-X-GEN Template Validator").bold().cyan());
+    println!("{}", style("═".repeat(80)).dim());
+    println!();
+
+    // Create validator
+    let validator = TemplateValidator::new();
+
+    // Collect template files
+    let template_files = collect_template_files(&path, recursive);
+
     if template_files.is_empty() {
         println!("{}", style("No template files found!").red().bold());
         return Ok(());
@@ -1377,16 +1605,22 @@ async fn run_validate_command(
         passed: bool,
         diagnostics: Vec<TemplateDiagnostic>,
         error: Option<String>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        explanations: Vec<cert_x_gen::ai::DiagnosticExplanation>,
     }
 
     // Validate each template
     let mut results = Vec::new();
     let mut passed_count = 0;
     let mut failed_count = 0;
+    // (result index, template ID, superseded-by) for checking replacements across the set
+    let mut declared: Vec<(usize, String, Option<String>)> = Vec::new();
+    // (result index, validated source) of the templates to explain with --explain
+    let mut explain_sources: Vec<(usize, String)> = Vec::new();
 
     for template_path in &template_files {
         // Read template content
-        let content = match fs::read_to_string(template_path) {
+        let content = match fs::read(template_path) {
             Ok(c) => c,
             Err(e) => {
                 let msg = format!("Failed to read file: {}", e);
@@ -1399,6 +1633,7 @@ async fn run_validate_command(
                 };
                 let result = ValidationResult {
                     template_path: template_path.display().to_string(),
+                    explanations: Vec::new(),
                     language: None,
                     passed: false,
                     diagnostics: vec![diag],
@@ -1410,6 +1645,23 @@ async fn run_validate_command(
             }
         };
 
+        // UTF-16 and BOM-prefixed files are transcoded with a warning
+        let (content, encoding_warning) = match TemplateValidator::decode_template(&content) {
+            Ok(decoded) => decoded,
+            Err(diag) => {
+                results.push(ValidationResult {
+                    template_path: template_path.display().to_string(),
+                    explanations: Vec::new(),
+                    language: None,
+                    passed: false,
+                    error: Some(diag.message.clone()),
+                    diagnostics: vec![diag],
+                });
+                failed_count += 1;
+                continue;
+            }
+        };
+
         // Determine language from extension
         let language = match template_path.extension().and_then(|e| e.to_str()) {
             Some("py") => TemplateLanguage::Python,
@@ -1418,6 +1670,7 @@ async fn run_validate_command(
             Some("rb") => TemplateLanguage::Ruby,
             Some("pl") => TemplateLanguage::Perl,
             Some("php") => TemplateLanguage::Php,
+            Some("rhai") => TemplateLanguage::Rhai,
             Some("rs") => TemplateLanguage::Rust,
             Some("c") => TemplateLanguage::C,
             Some("cpp") | Some("cc") | Some("cxx") => TemplateLanguage::Cpp,
@@ -1435,6 +1688,7 @@ async fn run_validate_command(
                 };
                 let result = ValidationResult {
                     template_path: template_path.display().to_string(),
+                    explanations: Vec::new(),
                     language: None,
                     passed: false,
                     diagnostics: vec![diag],
@@ -1446,6 +1700,56 @@ async fn run_validate_command(
             }
         };
 
+        // Snippets are checked on their own; templates with includes in composed form
+        let mut content = content;
+        if let TemplateLanguage::Yaml = language {
+            use cert_x_gen::engine::yaml::include;
+
+            let yaml_value = serde_yaml::from_str::<serde_yaml::Value>(&content).ok();
+            let composed = if yaml_value.as_ref().is_some_and(include::is_snippet) {
+                Err(include::validate_snippet(template_path, snippets_dir).err())
+            } else if yaml_value
+                .as_ref()
+                .is_some_and(|v| v.get("include").is_some())
+            {
+                include::compose(template_path, snippets_dir)
+                    .map_err(Some)
+                    .and_then(|c| serde_yaml::to_string(&c.value).map_err(|e| Some(e.into())))
+            } else {
+                Ok(content.clone())
+            };
+            match composed {
+                Ok(composed) => content = composed,
+                Err(error) => {
+                    let diagnostics: Vec<TemplateDiagnostic> = error
+                        .iter()
+                        .map(|e| TemplateDiagnostic {
+                            code: "yaml.include_error".to_string(),
+                            message: e.to_string(),
+                            severity: DiagnosticSeverity::Error,
+                            line: None,
+                            column: None,
+                        })
+                        .collect();
+                    let passed = diagnostics.is_empty();
+                    if passed {
+                        passed_count += 1;
+                    } else {
+                        failed_count += 1;
+                    }
+                    results.push(ValidationResult {
+                        template_path: template_path.display().to_string(),
+                        explanations: Vec::new(),
+                        language: Some(language),
+                        passed,
+                        error: error.map(|e| e.to_string()),
+                        diagnostics,
+                    });
+                    continue;
+                }
+            }
+        }
+
         // Validate template with structured diagnostics
         let mut diagnostics =
             match validator.validate_with_diagnostics(&content, language, Some(&template_path)) {
@@ -1460,6 +1764,7 @@ async fn run_validate_command(
                     }]
                 }
             };
+        diagnostics.extend(encoding_warning);
 
         // YAML-specific best-practice: id should match filename stem
         if let TemplateLanguage::Yaml = language {
@@ -1489,6 +1794,42 @@ async fn run_validate_command(
             }
         }
 
+        // With --strict-lint, style warnings fail validation
+        if strict_lint {
+            let lint_config = LintConfig::for_path(template_path)
+                .map_err(|e| Error::config(format!("Failed to load lint config: {}", e)))?;
+            for mut diag in TemplateLinter::with_config(lint_config).lint(&content, language) {
+                if diag.severity == DiagnosticSeverity::Warning {
+                    diag.severity = DiagnosticSeverity::Error;
+                }
+                diagnostics.push(diag);
+            }
+        }
+
+        // With --with-tests, failing `tests:` cases are errors
+        if with_tests && matches!(language, TemplateLanguage::Yaml) {
+            use cert_x_gen::engine::yaml::self_test;
+
+            let failures = match self_test::run(template_path, snippets_dir) {
+                Ok(report) => report
+                    .cases
+                    .into_iter()
+                    .filter_map(|case| {
+                        case.reason
+                            .map(|reason| format!("test '{}': {}", case.name, reason))
+                    })
+                    .collect(),
+                Err(e) => vec![e.to_string()],
+            };
+            diagnostics.extend(failures.into_iter().map(|message| TemplateDiagnostic {
+                code: "yaml.self_test_failed".to_string(),
+                message,
+                severity: DiagnosticSeverity::Error,
+                line: None,
+                column: None,
+            }));
+        }
+
         let has_error = diagnostics
             .iter()
             .any(|d| matches!(d.severity, DiagnosticSeverity::Error));
@@ -1508,8 +1849,38 @@ async fn run_validate_command(
             None
         };
 
+        let (id, superseded_by) = if let TemplateLanguage::Yaml = language {
+            let yaml_value = serde_yaml::from_str::<serde_yaml::Value>(&content).ok();
+            let field = |key: &str| {
+                yaml_value
+                    .as_ref()
+                    .and_then(|v| v.get(key))
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            };
+            (
+                field("id"),
+                field("superseded-by").or_else(|| field("superseded_by")),
+            )
+        } else {
+            let parsed = cert_x_gen::engine::common::parse_template_metadata(
+                &content,
+                language,
+                Some(template_path),
+            );
+            (parsed.id, parsed.superseded_by)
+        };
+        if let Some(id) = id.or_else(|| {
+            template_path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+        }) {
+            declared.push((results.len(), id, superseded_by));
+        }
+
         let result = ValidationResult {
             template_path: template_path.display().to_string(),
+            explanations: Vec::new(),
             language: Some(language),
             passed: !has_error,
             diagnostics,
@@ -1521,13 +1892,88 @@ async fn run_validate_command(
         } else {
             failed_count += 1;
         }
-
-        results.push(result);
+
+        if explain {
+            explain_sources.push((results.len(), content.clone()));
+        }
+        results.push(result);
+    }
+
+    // `superseded-by` must name a template in the validated set
+    let replacements: Vec<(String, Option<String>)> = declared
+        .iter()
+        .map(|(_, id, superseded_by)| (id.clone(), superseded_by.clone()))
+        .collect();
+    for (id, replacement) in cert_x_gen::template::missing_replacements(&replacements) {
+        let Some((index, _, _)) = declared
+            .iter()
+            .find(|(_, declared_id, _)| declared_id == id)
+        else {
+            continue;
+        };
+        let result = &mut results[*index];
+        let message = format!(
+            "superseded-by '{}' does not match any validated template id",
+            replacement
+        );
+        result.diagnostics.push(TemplateDiagnostic {
+            code: "template.unknown_replacement".to_string(),
+            message: message.clone(),
+            severity: DiagnosticSeverity::Error,
+            line: None,
+            column: None,
+        });
+        result.error = Some(match result.error.take() {
+            Some(error) => format!("{} | template.unknown_replacement: {}", error, message),
+            None => format!("template.unknown_replacement: {}", message),
+        });
+        if result.passed {
+            result.passed = false;
+            passed_count -= 1;
+            failed_count += 1;
+        }
+    }
+
+    // With --explain, the AI provider explains each template's diagnostics;
+    // without a healthy provider the plain diagnostics are shown
+    let mut explain_unavailable = None;
+    if explain {
+        match cert_x_gen::ai::AIManager::new() {
+            Ok(manager) => {
+                for (index, source) in explain_sources {
+                    let result = &mut results[index];
+                    let Some(language) = result.language else {
+                        continue;
+                    };
+                    if result.diagnostics.is_empty() {
+                        continue;
+                    }
+                    let file_name = PathBuf::from(&result.template_path)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    match manager
+                        .explain_diagnostics(&source, language, &result.diagnostics, &file_name)
+                        .await
+                    {
+                        Ok(explanations) => result.explanations = explanations,
+                        Err(e) => {
+                            explain_unavailable = Some(e.to_string());
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => explain_unavailable = Some(e.to_string()),
+        }
     }
 
     // Output results based on format
     match format.as_str() {
         "json" => {
+            if let Some(reason) = &explain_unavailable {
+                eprintln!("AI explanations unavailable: {}", reason);
+            }
             println!("{}", serde_json::to_string_pretty(&results)?);
         }
         "table" | "text" => {
@@ -1563,11 +2009,45 @@ async fn run_validate_command(
                     );
                 }
 
+                for explanation in &result.explanations {
+                    println!(
+                        "    {} {}: {}",
+                        style("explain").cyan().bold(),
+                        explanation.code,
+                        explanation.explanation
+                    );
+                    if let Some(patch) = &explanation.patch {
+                        println!("      {}", style("suggested patch (not applied):").dim());
+                        for line in patch.lines() {
+                            let line = if line.starts_with("+++") || line.starts_with("---") {
+                                style(line).bold()
+                            } else if line.starts_with('+') {
+                                style(line).green()
+                            } else if line.starts_with('-') {
+                                style(line).red()
+                            } else if line.starts_with("@@") {
+                                style(line).cyan()
+                            } else {
+                                style(line)
+                            };
+                            println!("      {}", line);
+                        }
+                    }
+                }
+
                 if !result.diagnostics.is_empty() {
                     println!();
                 }
             }
 
+            if let Some(reason) = &explain_unavailable {
+                println!(
+                    "{} AI explanations unavailable ({}); showing diagnostics only",
+                    style("!").yellow().bold(),
+                    reason
+                );
+            }
+
             println!();
 
             // Print summary
@@ -1611,8 +2091,92 @@ async fn run_validate_command(
     Ok(())
 }
 
+/// Run style lint rules over template files
+fn run_lint_command(path: &Path, recursive: bool, json: bool) -> Result<()> {
+    use cert_x_gen::ai::validator::{
+        detect_language_from_filename, DiagnosticSeverity, LintConfig, TemplateDiagnostic,
+        TemplateLinter,
+    };
+    use console::style;
+
+    #[derive(serde::Serialize)]
+    struct LintResult {
+        template_path: String,
+        diagnostics: Vec<TemplateDiagnostic>,
+    }
+
+    let template_files = collect_template_files(path, recursive);
+    if template_files.is_empty() {
+        println!("{}", style("No template files found!").red().bold());
+        return Ok(());
+    }
+
+    let mut results = Vec::new();
+    for template_path in &template_files {
+        let Some(language) = detect_language_from_filename(template_path) else {
+            continue;
+        };
+        let content = fs::read_to_string(template_path)?;
+        let lint_config = LintConfig::for_path(template_path)
+            .map_err(|e| Error::config(format!("Failed to load lint config: {}", e)))?;
+        results.push(LintResult {
+            template_path: template_path.display().to_string(),
+            diagnostics: TemplateLinter::with_config(lint_config).lint(&content, language),
+        });
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+
+    let mut warning_count = 0;
+    for result in &results {
+        if result.diagnostics.is_empty() {
+            continue;
+        }
+        println!("{}", style(&result.template_path).bold());
+        for diag in &result.diagnostics {
+            let sev_label = match diag.severity {
+                DiagnosticSeverity::Warning => {
+                    warning_count += 1;
+                    style("warning").yellow().bold()
+                }
+                DiagnosticSeverity::Error => style("error").red().bold(),
+                DiagnosticSeverity::Info => style("info").blue().bold(),
+            };
+            println!("    [{}] {}: {}", sev_label, diag.code, diag.message);
+        }
+        println!();
+    }
+
+    let clean = results.iter().filter(|r| r.diagnostics.is_empty()).count();
+    println!(
+        "Linted {} template(s): {} clean, {} warning(s)",
+        style(results.len()).bold(),
+        style(clean).green(),
+        style(warning_count).yellow()
+    );
+
+    Ok(())
+}
+
+/// Convert a kebab-case template ID to a Title Case name
+fn title_from_id(id: &str) -> String {
+    id.split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
 /// Run template management commands
-async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
+async fn run_template_command(cmd: cli::TemplateCommand, config: Config) -> Result<()> {
     use cli::TemplateAction;
 
     match cmd.action {
@@ -1620,10 +2184,8 @@ async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
             language,
             severity,
             tags,
+            usage,
         } => {
-            // Load configuration
-            let config = Config::default();
-
             // Create CERT-X-GEN engine
             let engine = CertXGen::new(config).await?;
 
@@ -1657,6 +2219,12 @@ async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
                 });
             }
 
+            let usage_store = if usage {
+                Some(cert_x_gen::template::UsageStore::load_default()?)
+            } else {
+                None
+            };
+
             // Display templates
             println!("Found {} templates:", filtered_templates.len());
             println!();
@@ -1671,6 +2239,18 @@ async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
                 println!("Description: {}", metadata.description);
                 println!("Tags: {}", metadata.tags.join(", "));
                 println!("File: {}", metadata.file_path.display());
+                if metadata.deprecated {
+                    match &metadata.superseded_by {
+                        Some(replacement) => {
+                            println!("Deprecated: yes (superseded by {})", replacement)
+                        }
+                        None => println!("Deprecated: yes"),
+                    }
+                }
+                if let Some(store) = &usage_store {
+                    let stats = store.get(&metadata.id).cloned().unwrap_or_default();
+                    println!("Runs / Hits: {} / {}", stats.executions, stats.findings);
+                }
                 println!("---");
             }
 
@@ -1680,6 +2260,9 @@ async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
             path,
             recursive,
             json,
+            strict_lint,
+            with_tests,
+            explain,
         } => {
             // Call the validation function with default parameters
             let format = if json {
@@ -1688,16 +2271,36 @@ async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
                 "text".to_string()
             };
             run_validate_command(
-                path, recursive, false,  // show_score
-                false,  // strict
-                format, // format
-                false,  // summary
-                None,   // language
-                0,      // min_score
+                path,
+                ValidateOptions {
+                    recursive,
+                    strict_lint,
+                    format,
+                    snippets_dir: config.templates.snippets_dir.as_deref(),
+                    with_tests,
+                    explain,
+                    ..Default::default()
+                },
             )
             .await?;
             Ok(())
         }
+        TemplateAction::Lint {
+            path,
+            recursive,
+            json,
+        } => run_lint_command(&path, recursive, json),
+        TemplateAction::SelfTest { path, strict, json } => run_self_test_command(
+            &path,
+            strict,
+            json,
+            config.templates.snippets_dir.as_deref(),
+        ),
+        TemplateAction::MutateTest {
+            template,
+            mock_pass_response,
+            json,
+        } => run_mutate_test_command(config, &template, &mock_pass_response, json).await,
         TemplateAction::Update { force: _ } => {
             use cert_x_gen::template::AutoUpdater;
 
@@ -1725,7 +2328,6 @@ async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
         }
         TemplateAction::Info { template_id } => {
             // Use the same template loading as search command
-            let config = Config::default();
             let engine = CertXGen::new(config).await?;
             let templates = engine.load_templates().await?;
 
@@ -1773,6 +2375,15 @@ async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
                 println!("  Tags:        {}", meta.tags.join(", "));
             }
 
+            if meta.deprecated {
+                match &meta.superseded_by {
+                    Some(replacement) => {
+                        println!("  Deprecated:  yes (superseded by {})", replacement)
+                    }
+                    None => println!("  Deprecated:  yes"),
+                }
+            }
+
             if !meta.file_path.as_os_str().is_empty() {
                 println!("  File:        {}", meta.file_path.display());
 
@@ -1787,9 +2398,41 @@ async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
                 }
             }
 
+            let usage = cert_x_gen::template::UsageStore::load_default()?;
+            match usage.get(template.id()) {
+                Some(stats) => {
+                    if let Some(last_used) = stats.last_used {
+                        println!(
+                            "  Last used:   {}",
+                            last_used.format("%Y-%m-%d %H:%M:%S UTC")
+                        );
+                    }
+                    println!(
+                        "  Hit rate:    {:.1}% ({} findings in {} runs)",
+                        stats.hit_rate() * 100.0,
+                        stats.findings,
+                        stats.executions
+                    );
+                }
+                None => println!("  Last used:   never"),
+            }
+
             println!();
             Ok(())
         }
+        TemplateAction::Stats {
+            top,
+            format,
+            json,
+            usage,
+        } => {
+            let format = if json { cli::StatsFormat::Json } else { format };
+            if usage {
+                run_template_stats_command(top, format == cli::StatsFormat::Json)
+            } else {
+                run_collection_stats_command(config, top, format).await
+            }
+        }
         TemplateAction::Create {
             id,
             language,
@@ -1810,6 +2453,7 @@ async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
                 LanguageArg::Ruby => ("rb", "ruby-template-skeleton.rb"),
                 LanguageArg::Perl => ("pl", "perl-template-skeleton.pl"),
                 LanguageArg::Php => ("php", "php-template-skeleton.php"),
+                LanguageArg::Rhai => ("rhai", "rhai-template-skeleton.rhai"),
                 LanguageArg::Shell => ("sh", "shell-template-skeleton.sh"),
                 LanguageArg::Yaml => ("yaml", "yaml-template-skeleton.yaml"),
             };
@@ -1837,17 +2481,7 @@ async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
 
             // Replace placeholders
             let template_name = if name.is_empty() {
-                // Convert kebab-case to Title Case
-                id.split('-')
-                    .map(|word| {
-                        let mut chars = word.chars();
-                        match chars.next() {
-                            Some(c) => c.to_uppercase().chain(chars).collect(),
-                            None => String::new(),
-                        }
-                    })
-                    .collect::<Vec<_>>()
-                    .join(" ")
+                title_from_id(&id)
             } else {
                 name.clone()
             };
@@ -1879,6 +2513,40 @@ async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
 
             Ok(())
         }
+        TemplateAction::InitProject {
+            id,
+            language,
+            name,
+            output,
+        } => {
+            use cert_x_gen::template::ProjectScaffold;
+
+            let name = name.unwrap_or_else(|| title_from_id(&id));
+            let scaffold = ProjectScaffold::new(language.into(), &id, &name)?;
+            let project_dir = output.unwrap_or_else(|| PathBuf::from(&id));
+            let written = scaffold.write(&project_dir)?;
+
+            println!("✅ Created template project: {}", project_dir.display());
+            for path in &written {
+                println!("   {}", path.display());
+            }
+            let entry = project_dir.join(scaffold.entry());
+            println!("\nNext steps:");
+            println!("   1. Edit {} to add your detection logic", entry.display());
+            println!(
+                "   2. Update {} (severity, tags, description)",
+                project_dir
+                    .join(cert_x_gen::engine::common::PROJECT_METADATA_FILE)
+                    .display()
+            );
+            println!("   3. Validate: cxg template validate {}", entry.display());
+            println!(
+                "   4. Test: cxg scan --scope <target> --templates {}",
+                entry.display()
+            );
+
+            Ok(())
+        }
         TemplateAction::Test {
             template,
             target,
@@ -1888,22 +2556,443 @@ async fn run_template_command(cmd: cli::TemplateCommand) -> Result<()> {
             // TODO: Implement template testing
             Ok(())
         }
+        TemplateAction::Pack { action } => run_pack_command(action).await,
+        TemplateAction::Deduplicate(cmd) => run_deduplicate_command(cmd, config).await,
+    }
+}
+
+/// Print the most and least productive templates from local usage counters
+/// `template stats`: statistics over every loaded template
+async fn run_collection_stats_command(
+    config: Config,
+    top: usize,
+    format: cli::StatsFormat,
+) -> Result<()> {
+    use cert_x_gen::search::TemplateSearchEngine;
+
+    let engine = CertXGen::new(config).await?;
+    let templates = engine.load_templates().await?;
+    let report = TemplateSearchEngine::new(templates).stats_report();
+
+    match format {
+        cli::StatsFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        cli::StatsFormat::Table => print!("{}", report.render_table(top)),
+        cli::StatsFormat::Chart => print!("{}", report.render_chart(top)),
+    }
+    Ok(())
+}
+
+/// `template stats --usage`: most and least productive templates
+fn run_template_stats_command(top: usize, json: bool) -> Result<()> {
+    use cert_x_gen::template::{TemplateUsage, UsageStore};
+    use console::style;
+
+    let store = UsageStore::load_default()?;
+    let most: Vec<(&str, &TemplateUsage)> = store.ranked().into_iter().take(top).collect();
+    // Least productive: fewest findings, then the most wasted runs
+    let mut least = store.ranked();
+    least.sort_by(|(_, a), (_, b)| {
+        a.findings
+            .cmp(&b.findings)
+            .then(b.executions.cmp(&a.executions))
+    });
+    least.truncate(top);
+
+    if json {
+        let entries = |list: &[(&str, &TemplateUsage)]| -> Vec<serde_json::Value> {
+            list.iter()
+                .map(|(id, usage)| {
+                    serde_json::json!({
+                        "id": id,
+                        "executions": usage.executions,
+                        "findings": usage.findings,
+                        "hit_rate": usage.hit_rate(),
+                        "last_used": usage.last_used,
+                    })
+                })
+                .collect()
+        };
+        let report = serde_json::json!({
+            "templates_tracked": store.templates.len(),
+            "most_productive": entries(&most),
+            "least_productive": entries(&least),
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if store.templates.is_empty() {
+        println!("No template usage recorded yet. Run a scan first.");
+        return Ok(());
+    }
+
+    let print_list = |title: &str, list: &[(&str, &TemplateUsage)]| {
+        println!("{}", style(title).bold());
+        println!(
+            "  {:<40} {:>8} {:>8} {:>9}  LAST USED",
+            "TEMPLATE", "RUNS", "HITS", "HIT RATE"
+        );
+        for (id, usage) in list {
+            println!(
+                "  {:<40} {:>8} {:>8} {:>8.1}%  {}",
+                id,
+                usage.executions,
+                usage.findings,
+                usage.hit_rate() * 100.0,
+                usage
+                    .last_used
+                    .map(|t| t.format("%Y-%m-%d").to_string())
+                    .unwrap_or_else(|| "-".to_string())
+            );
+        }
+        println!();
+    };
+    print_list("Most productive templates", &most);
+    print_list("Least productive templates", &least);
+    println!(
+        "{} templates tracked in {}",
+        store.templates.len(),
+        UsageStore::default_path().display()
+    );
+    Ok(())
+}
+
+/// Report pairs of templates that are likely duplicates
+async fn run_deduplicate_command(cmd: cli::DeduplicateCommand, config: Config) -> Result<()> {
+    use cert_x_gen::ai::embedder::TemplateDocument;
+    use cert_x_gen::ai::{AIManager, TemplateEmbedder};
+    use console::style;
+
+    if !(0.0..=1.0).contains(&cmd.threshold) {
+        return Err(Error::config("--threshold must be between 0.0 and 1.0"));
+    }
+
+    let engine = CertXGen::new(config).await?;
+    let mut documents = Vec::new();
+    for template in engine.load_templates().await? {
+        let metadata = template.metadata();
+        if let Some(dir) = &cmd.path {
+            if !metadata.file_path.starts_with(dir) {
+                continue;
+            }
+        }
+        let code = fs::read_to_string(&metadata.file_path).unwrap_or_default();
+        documents.push(TemplateDocument::new(
+            metadata.id.clone(),
+            metadata.file_path.clone(),
+            &metadata.description,
+            &code,
+        ));
+    }
+
+    let embedder = if cmd.offline {
+        TemplateEmbedder::offline()
+    } else {
+        let manager = AIManager::new()
+            .map_err(|e| Error::Ai(format!("Failed to initialize AI manager: {}", e)))?;
+        TemplateEmbedder::with_manager(manager)
+    };
+    let pairs = embedder
+        .find_duplicates(&documents, cmd.threshold)
+        .await
+        .map_err(|e| Error::Ai(format!("Failed to compare templates: {}", e)))?;
+
+    if cmd.json {
+        println!("{}", serde_json::to_string_pretty(&pairs)?);
+        return Ok(());
+    }
+
+    for pair in &pairs {
+        println!(
+            "{} {:.2}  {} <-> {}",
+            style("≈").yellow().bold(),
+            pair.similarity,
+            style(&pair.first).bold(),
+            style(&pair.second).bold()
+        );
+        println!("    {}", pair.first_path.display());
+        println!("    {}", pair.second_path.display());
+        println!("    recommendation: {}", pair.recommendation());
+    }
+    println!(
+        "Compared {} templates using {}: {} potential duplicate pair(s) at threshold {:.2}",
+        style(documents.len()).bold(),
+        embedder.backend(),
+        style(pairs.len()).yellow(),
+        cmd.threshold
+    );
+
+    Ok(())
+}
+
+/// Create, install and list template packs
+async fn run_pack_command(action: cli::PackAction) -> Result<()> {
+    use cert_x_gen::template::{PackInstaller, PackManifest, TemplateManager, PACK_EXTENSION};
+    use cli::PackAction;
+    use console::style;
+
+    match action {
+        PackAction::Create {
+            name,
+            templates,
+            version,
+            author,
+            description,
+            output,
+        } => {
+            let manifest =
+                PackManifest::from_dir(&name, &version, &author, &description, &templates)?;
+            let output =
+                output.unwrap_or_else(|| PathBuf::from(format!("{}.{}", name, PACK_EXTENSION)));
+            manifest.write_archive(&templates, &output)?;
+
+            println!(
+                "{} Created {} {} ({} templates, {} other files): {}",
+                style("✓").green().bold(),
+                style(&manifest.name).bold(),
+                manifest.version,
+                manifest.templates.len(),
+                manifest.assets.len(),
+                output.display()
+            );
+            Ok(())
+        }
+        PackAction::Install { source } => {
+            let installed = PackInstaller::for_user().install(&source).await?;
+
+            // Re-discover so the new templates are attributed to the pack
+            let manager = TemplateManager::new();
+            manager.initialize().await?;
+            let discovered = manager.templates_in_pack(&installed.manifest.name).await;
+
+            println!(
+                "{} Installed {} {} ({} templates, checksums verified) into {}",
+                style("✓").green().bold(),
+                style(&installed.manifest.name).bold(),
+                installed.manifest.version,
+                installed.template_count(),
+                installed.path.display()
+            );
+            let shadowed = installed.template_count().saturating_sub(discovered.len());
+            if shadowed > 0 {
+                println!(
+                    "  {} {} template(s) are overridden by local templates with the same ID",
+                    style("!").yellow().bold(),
+                    shadowed
+                );
+            }
+            Ok(())
+        }
+        PackAction::List { json } => {
+            let registry = PackInstaller::for_user().registry()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&registry)?);
+                return Ok(());
+            }
+
+            if registry.packs.is_empty() {
+                println!("No template packs installed");
+                println!("\nInstall one with: cxg template pack install <path-or-url>");
+                return Ok(());
+            }
+            println!(
+                "{:<30} {:<12} {:>9}  {}",
+                style("PACK").bold(),
+                style("VERSION").bold(),
+                style("TEMPLATES").bold(),
+                style("AUTHOR").bold()
+            );
+            for pack in registry.list() {
+                println!(
+                    "{:<30} {:<12} {:>9}  {}",
+                    pack.manifest.name,
+                    pack.manifest.version,
+                    pack.template_count(),
+                    pack.manifest.author
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Run a template against mutations of a passing response to find dead matchers
+async fn run_mutate_test_command(
+    config: Config,
+    template_path: &Path,
+    pass_response_path: &Path,
+    json: bool,
+) -> Result<()> {
+    use cert_x_gen::ai::validator::{load_pass_response, TemplateMutator};
+    use console::style;
+
+    let pass = load_pass_response(pass_response_path).map_err(|e| Error::config(e.to_string()))?;
+
+    let is_yaml = matches!(
+        template_path.extension().and_then(|e| e.to_str()),
+        Some("yaml" | "yml")
+    );
+    let mutator = if is_yaml {
+        TemplateMutator::from_yaml(&fs::read_to_string(template_path)?)
+            .map_err(|e| Error::config(e.to_string()))?
+    } else {
+        TemplateMutator::default()
+    };
+
+    let engine = CertXGen::new(config).await?;
+    let template = engine
+        .template_loader()
+        .load_template(template_path)
+        .await?;
+    template.validate()?;
+    template.prepare().await?;
+
+    let report = mutator
+        .run(template.as_ref(), &pass)
+        .await
+        .map_err(|e| Error::Execution(e.to_string()))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if !report.baseline_fired {
+        println!(
+            "{} Template did not fire on the passing response; nothing to mutate",
+            style("✗").red()
+        );
+    } else {
+        for outcome in &report.outcomes {
+            let label = if outcome.fired {
+                style("survived").red().bold()
+            } else {
+                style("killed").green()
+            };
+            let matcher = outcome
+                .mutation
+                .matcher
+                .map(|i| format!("matcher #{}", i + 1))
+                .unwrap_or_else(|| "generic".to_string());
+            println!(
+                "  [{}] {}: {}",
+                label, matcher, outcome.mutation.description
+            );
+        }
+
+        let dead = report.dead_matchers();
+        println!();
+        if !dead.is_empty() {
+            let names: Vec<String> = dead.iter().map(|i| format!("#{}", i + 1)).collect();
+            println!(
+                "{} Dead matcher(s): {}",
+                style("⚠").yellow(),
+                names.join(", ")
+            );
+        }
+        println!(
+            "{} of {} mutation(s) survived",
+            report.survivors().count(),
+            report.outcomes.len()
+        );
+    }
+
+    if report.passed() {
+        Ok(())
+    } else {
+        Err(Error::Validation(
+            "Mutation test failed: template does not react to every mutation".to_string(),
+        ))
+    }
+}
+
+/// Run the `tests:` cases of every YAML template under `path`
+fn run_self_test_command(
+    path: &Path,
+    strict: bool,
+    json: bool,
+    snippets_dir: Option<&Path>,
+) -> Result<()> {
+    use cert_x_gen::engine::yaml::{include, self_test};
+    use console::style;
+
+    let templates: Vec<PathBuf> = collect_template_files(path, true)
+        .into_iter()
+        .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("yaml" | "yml")))
+        .filter(|p| {
+            // Snippets have no matchers of their own to test
+            fs::read_to_string(p)
+                .ok()
+                .and_then(|c| serde_yaml::from_str::<serde_yaml::Value>(&c).ok())
+                .is_none_or(|v| !include::is_snippet(&v))
+        })
+        .collect();
+
+    let mut reports = Vec::new();
+    let mut failed = 0;
+    for template in &templates {
+        match self_test::run(template, snippets_dir) {
+            Ok(report) => {
+                if !report.passed(strict) {
+                    failed += 1;
+                }
+                reports.push(report);
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("{} {}: {}", style("✗").red(), template.display(), e);
+            }
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        for report in &reports {
+            let mark = if report.passed(strict) {
+                style("✓").green().bold()
+            } else {
+                style("✗").red().bold()
+            };
+            println!("{} {}", mark, report.template.display());
+            if report.cases.is_empty() {
+                println!("    no test cases");
+            }
+            for case in &report.cases {
+                match &case.reason {
+                    None => println!("    {} {}", style("pass").green(), case.name),
+                    Some(reason) => {
+                        println!(
+                            "    {} {}: {}",
+                            style("FAIL").red().bold(),
+                            case.name,
+                            reason
+                        )
+                    }
+                }
+            }
+        }
+        println!();
+        println!("{} template(s) tested, {} failed", templates.len(), failed);
+    }
+
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(Error::Validation(format!(
+            "{} template(s) failed self-test",
+            failed
+        )))
     }
 }
 
 /// Run search command
-async fn run_search_command(args: cli::SearchArgs) -> Result<()> {
+async fn run_search_command(args: cli::SearchArgs, config: Config) -> Result<()> {
     use cert_x_gen::search::{
-        SearchArgs as LibSearchArgs, SearchFormat, SearchResultFormatter, SearchSort,
+        SearchArgs as LibSearchArgs, SearchFormat, SearchIndex, SearchResultFormatter, SearchSort,
         TemplateSearchEngine,
     };
     use std::fs;
 
     tracing::info!("Starting template search...");
 
-    // Load configuration
-    let config = Config::default();
-
     // Create CERT-X-GEN engine
     let engine = CertXGen::new(config).await?;
 
@@ -1919,9 +3008,11 @@ async fn run_search_command(args: cli::SearchArgs) -> Result<()> {
         tags: args.tags,
         author: args.author,
         cwe: args.cwe,
+        has_references: args.has_references,
         content: args.content,
         case_sensitive: args.case_sensitive,
         regex: args.regex,
+        semantic: args.semantic,
         limit: args.limit,
         format: match args.format {
             cli::SearchFormat::Table => SearchFormat::Table,
@@ -1946,8 +3037,40 @@ async fn run_search_command(args: cli::SearchArgs) -> Result<()> {
         stats: args.stats,
     };
 
-    // Create search engine
-    let search_engine = TemplateSearchEngine::new(templates);
+    // Create search engine, ranking popularity by local usage
+    let usage = cert_x_gen::template::UsageStore::load_default().unwrap_or_else(|e| {
+        tracing::warn!("Ignoring unreadable template usage stats: {}", e);
+        Default::default()
+    });
+    let mut search_engine = TemplateSearchEngine::new(templates).with_usage(usage);
+
+    // Attach the full-text index, rebuilding it when templates changed
+    match SearchIndex::open_default() {
+        Ok(index) => {
+            search_engine = search_engine.with_index(index);
+            if args.rebuild_index || search_engine.index_is_stale() {
+                let pb = indicatif::ProgressBar::new_spinner();
+                pb.set_style(
+                    indicatif::ProgressStyle::default_spinner()
+                        .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏")
+                        .template("{spinner:.cyan} {msg}")
+                        .unwrap(),
+                );
+                pb.enable_steady_tick(std::time::Duration::from_millis(80));
+                pb.set_message("Rebuilding search index...");
+                let rebuilt = search_engine.rebuild_index();
+                pb.finish_and_clear();
+                if let Err(e) = rebuilt {
+                    tracing::warn!("Searching without the index: {}", e);
+                }
+            }
+        }
+        Err(e) => tracing::warn!("Searching without the index: {}", e),
+    }
+
+    if args.semantic {
+        search_engine = search_engine.with_embedder(cert_x_gen::search::default_embedder()?);
+    }
 
     // Perform search
     let (results, stats) = search_engine.search(&search_args);
@@ -1997,36 +3120,217 @@ async fn run_search_command(args: cli::SearchArgs) -> Result<()> {
 /// Run API server
 async fn run_server(args: cli::ServerArgs) -> Result<()> {
     tracing::info!("Starting API server on {}:{}", args.bind, args.port);
-    // TODO: Implement API server
     Err(Error::NotImplemented(
         "API server not yet implemented".to_string(),
     ))
 }
 
+/// Run wordlist commands
+fn run_wordlist_command(cmd: cli::WordlistCommand) -> Result<()> {
+    use cert_x_gen::wordlist::{self, WordlistStore};
+    use cli::WordlistAction;
+
+    let store = WordlistStore::new();
+    match cmd.action {
+        WordlistAction::List { json } => {
+            let wordlists = store.list()?;
+            if json {
+                let rows: Vec<_> = wordlists
+                    .iter()
+                    .map(|w| {
+                        serde_json::json!({
+                            "name": w.name,
+                            "version": w.version,
+                            "description": w.description,
+                            "source": w.source,
+                            "entries": w.entries().len(),
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&rows)?);
+                return Ok(());
+            }
+            println!(
+                "{:<24} {:>7} {:>8}  {:<10} DESCRIPTION",
+                "NAME", "VERSION", "ENTRIES", "SOURCE"
+            );
+            for w in &wordlists {
+                let version = w.version.map_or_else(|| "-".to_string(), |v| v.to_string());
+                println!(
+                    "{:<24} {:>7} {:>8}  {:<10} {}",
+                    w.name,
+                    version,
+                    w.entries().len(),
+                    w.source.to_string(),
+                    w.description.as_deref().unwrap_or("")
+                );
+            }
+            println!(
+                "\nUser wordlists: {}",
+                WordlistStore::default_user_dir().display()
+            );
+        }
+        WordlistAction::Show { name, json } => {
+            let entries = store.load(&name)?.entries();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                for entry in entries {
+                    println!("{}", entry);
+                }
+            }
+        }
+        WordlistAction::Export { name, output } => {
+            let list = store.load(&name)?;
+            match output {
+                Some(path) => {
+                    wordlist::export(&list, &path)?;
+                    eprintln!("Exported wordlist '{}' to {}", name, path.display());
+                }
+                None => print!("{}", list.contents),
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Run configuration commands
-fn run_config_command(cmd: cli::ConfigCommand) -> Result<()> {
+fn run_config_command(
+    cmd: cli::ConfigCommand,
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+) -> Result<()> {
     use cli::ConfigAction;
 
     match cmd.action {
-        ConfigAction::Generate { output, format: _ } => {
-            let config = Config::default();
-            config.save(&output)?;
+        ConfigAction::Generate {
+            output,
+            format: _,
+            interactive,
+            no_interactive: _,
+            template_dir,
+            timeout,
+            rate_limit,
+            ai_provider,
+            proxy,
+            output_format,
+        } => {
+            use cert_x_gen::config::ConfigWizard;
+
+            let mut wizard = ConfigWizard::new();
+            let answers = wizard.answers_mut();
+            if template_dir.is_some() {
+                answers.template_dir = template_dir;
+            }
+            if let Some(timeout) = timeout {
+                answers.timeout_secs = timeout;
+            }
+            if let Some(rate_limit) = rate_limit {
+                answers.rate_limit = (rate_limit > 0).then_some(rate_limit);
+            }
+            if let Some(provider) = &ai_provider {
+                answers.ai_provider = provider.to_lowercase();
+            }
+            if proxy.is_some() {
+                answers.proxy = proxy;
+            }
+            if let Some(format) = output_format {
+                answers.output_format = format.to_lowercase();
+            }
+
+            if interactive {
+                if !wizard.detected_providers().is_empty() {
+                    println!(
+                        "Detected AI providers: {}",
+                        wizard.detected_providers().join(", ")
+                    );
+                }
+                wizard.prompt()?;
+            }
+            wizard.write(&output)?;
             println!("Configuration generated: {}", output.display());
+
+            // The AI provider lives in the AI config read by `cxg ai` commands
+            if interactive || ai_provider.is_some() {
+                use cert_x_gen::ai::AIConfig;
+
+                let provider = &wizard.answers().ai_provider;
+                let ai_path = AIConfig::config_path().map_err(|e| Error::config(e.to_string()))?;
+                // Read the file as-is so saving does not write out expanded API keys
+                let mut ai_config: AIConfig = if ai_path.exists() {
+                    serde_yaml::from_str(&std::fs::read_to_string(&ai_path)?)
+                        .map_err(|e| Error::config(format!("Invalid AI config: {}", e)))?
+                } else {
+                    AIConfig::default()
+                };
+                ai_config
+                    .enable_provider(provider)
+                    .and_then(|_| ai_config.set_default_provider(provider))
+                    .and_then(|_| ai_config.save())
+                    .map_err(|e| Error::config(e.to_string()))?;
+                println!(
+                    "Default AI provider set to {} in {}",
+                    provider,
+                    ai_path.display()
+                );
+            }
             Ok(())
         }
         ConfigAction::Validate { config } => {
-            let cfg = Config::from_file(&config)?;
+            let cfg = Config::from_file_with_profile(&config, profile.as_deref())?;
             cfg.validate()?;
             println!("Configuration is valid");
             Ok(())
         }
         ConfigAction::Show => {
-            let config = Config::default();
+            // Never decrypt for display; encrypted secrets stay ciphertext
+            let mut config = match config_file(config_path.as_deref(), profile.as_deref())? {
+                Some(path) => Config::from_file_raw_with_profile(path, profile.as_deref())?,
+                None => Config::default(),
+            };
+            config.redact_secrets();
             let yaml =
                 serde_yaml::to_string(&config).map_err(|e| Error::Serialization(e.to_string()))?;
             println!("{}", yaml);
             Ok(())
         }
+        ConfigAction::EncryptSecrets { config, ai } => {
+            use cert_x_gen::secrets::SecretKey;
+
+            let key_path = SecretKey::default_path();
+            let key = SecretKey::load_or_generate(&key_path)?;
+
+            if let Some(path) = config {
+                let mut cfg = Config::from_file_raw(&path)?;
+                let count = cfg.encrypt_secrets(&key)?;
+                if count > 0 {
+                    cfg.save(&path)?;
+                }
+                println!("Encrypted {} secret(s) in {}", count, path.display());
+            }
+
+            if ai {
+                use cert_x_gen::ai::AIConfig;
+
+                let ai_path = AIConfig::config_path().map_err(|e| Error::config(e.to_string()))?;
+                let content = std::fs::read_to_string(&ai_path)?;
+                let mut ai_config: AIConfig = serde_yaml::from_str(&content)
+                    .map_err(|e| Error::config(format!("Invalid AI config: {}", e)))?;
+                let count = ai_config
+                    .encrypt_secrets(&key)
+                    .map_err(|e| Error::config(e.to_string()))?;
+                if count > 0 {
+                    ai_config.save().map_err(|e| Error::config(e.to_string()))?;
+                }
+                println!("Encrypted {} API key(s) in {}", count, ai_path.display());
+            }
+
+            println!(
+                "Secrets are decrypted with the key at {}",
+                key_path.display()
+            );
+            Ok(())
+        }
     }
 }
 
@@ -2240,54 +3544,50 @@ async fn run_sandbox_command(cmd: cli::SandboxCommand) -> Result<()> {
                 "{} Initializing sandbox environment...",
                 style("→").cyan()
             ))?;
-            sandbox.init().await?;
+            let report = sandbox.init().await?;
 
             // Show summary of what was initialized
-            let status = sandbox.status();
-            let mut initialized_langs = Vec::new();
-            if status.python_ready {
-                initialized_langs.push("Python");
-            }
-            if status.javascript_ready {
-                initialized_langs.push("JavaScript");
-            }
-            if status.ruby_ready {
-                initialized_langs.push("Ruby");
-            }
-            if status.perl_ready {
-                initialized_langs.push("Perl");
-            }
-            if status.php_ready {
-                initialized_langs.push("PHP");
-            }
-            if status.rust_ready {
-                initialized_langs.push("Rust");
-            }
-            if status.go_ready {
-                initialized_langs.push("Go");
-            }
-            if status.java_ready {
-                initialized_langs.push("Java");
-            }
+            let succeeded = report.succeeded();
+            let skipped = report.skipped();
+            let failed = report.failed();
 
-            if !initialized_langs.is_empty() {
+            if failed.is_empty() && skipped.is_empty() && !succeeded.is_empty() {
                 term.write_line(&format!(
                     "{} Sandbox initialized successfully!",
                     style("✓").green()
                 ))?;
-                term.write_line(&format!(
-                    "  Initialized languages: {}",
-                    style(initialized_langs.join(", ")).cyan()
-                ))?;
             } else {
                 term.write_line(&format!(
                     "{} Sandbox initialization completed with warnings",
                     style("⚠").yellow()
                 ))?;
+            }
+            term.write_line(&format!(
+                "  Succeeded: {}",
+                if succeeded.is_empty() {
+                    style("none".to_string()).dim()
+                } else {
+                    style(succeeded.join(", ")).cyan()
+                }
+            ))?;
+            for (language, reason) in &skipped {
+                term.write_line(&format!(
+                    "  {} {}: {}",
+                    style("Skipped").yellow(),
+                    language,
+                    reason
+                ))?;
+            }
+            for (language, error) in &failed {
                 term.write_line(&format!(
-                    "  No language environments were successfully initialized"
+                    "  {} {}: {}",
+                    style("Failed").red(),
+                    language,
+                    error
                 ))?;
-                term.write_line(&format!("  Check logs above for details on what failed"))?;
+            }
+            if succeeded.is_empty() {
+                term.write_line("  Check logs above for details on what failed")?;
             }
 
             term.write_line("")?;
@@ -2487,8 +3787,7 @@ async fn run_sandbox_command(cmd: cli::SandboxCommand) -> Result<()> {
             term.write_line(&format!("Location: {}", sandbox.root_dir().display()))?;
             term.write_line("Type 'exit' to return.\n")?;
 
-            let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
-            std::process::Command::new(shell)
+            std::process::Command::new(cert_x_gen::sandbox::interactive_shell())
                 .current_dir(sandbox.root_dir())
                 .envs(sandbox.get_env_vars())
                 .status()
@@ -2503,11 +3802,53 @@ async fn run_sandbox_command(cmd: cli::SandboxCommand) -> Result<()> {
             Ok(())
         }
 
-        SandboxAction::Update { language: _ } => {
-            term.write_line(&format!("{} Updating packages...", style("→").cyan()))?;
+        SandboxAction::Update { language, dry_run } => {
+            use cert_x_gen::sandbox::update::{self, PackageUpdater};
+
+            let sandbox = Sandbox::new();
+            let route = cert_x_gen::sandbox::active_exec_route();
+
+            // The package sandbox lives inside the container for Docker sandboxes
+            if route.is_none() && !sandbox.root_dir().exists() {
+                return Err(Error::config(
+                    "Sandbox not initialized. Run 'cxg sandbox init' first.",
+                ));
+            }
+
+            let languages = match &language {
+                Some(language) => vec![update::normalize_language(language).ok_or_else(|| {
+                    Error::config(format!(
+                        "Unsupported language: {} (supported: {})",
+                        language,
+                        update::UPDATABLE_LANGUAGES.join(", ")
+                    ))
+                })?],
+                None => {
+                    let config_path = sandbox.root_dir().join("config.yaml");
+                    let config = SandboxConfig::load(&config_path)
+                        .unwrap_or_else(|_| sandbox.config().clone());
+                    update::enabled_languages(&config)
+                }
+            };
+
+            let updater = match route {
+                Some(route) => PackageUpdater::in_container(&sandbox, route.container()),
+                None => PackageUpdater::new(&sandbox),
+            }
+            .dry_run(dry_run);
+
+            term.write_line(&format!(
+                "{} {} packages for {}{}...",
+                style("→").cyan(),
+                if dry_run { "Checking" } else { "Updating" },
+                languages.join(", "),
+                route
+                    .map(|r| format!(" in sandbox '{}'", r.container()))
+                    .unwrap_or_default()
+            ))?;
 
-            // Implement update logic here
-            term.write_line(&format!("{} Update complete!", style("✓").green()))?;
+            let updates = updater.update_all(&languages).await;
+            print_package_updates(&term, &updates, dry_run)?;
 
             Ok(())
         }
@@ -2709,6 +4050,7 @@ async fn run_sandbox_command(cmd: cli::SandboxCommand) -> Result<()> {
             languages,
             persist,
             auto_start,
+            quiet,
         } => {
             use cert_x_gen::sandbox::config::SandboxConfigFile;
             use cert_x_gen::sandbox::docker::{DockerConfig, DockerSandbox, ResourceLimits};
@@ -2773,7 +4115,7 @@ async fn run_sandbox_command(cmd: cli::SandboxCommand) -> Result<()> {
                 );
             }
 
-            let config = DockerConfig {
+            let mut config = DockerConfig {
                 name: name.clone(),
                 image: format!("cert-x-gen/sandbox:{}", name),
                 languages: selected_languages.clone(),
@@ -2786,19 +4128,37 @@ async fn run_sandbox_command(cmd: cli::SandboxCommand) -> Result<()> {
                 volumes,
                 environment: std::collections::HashMap::new(),
                 network_mode: "bridge".to_string(), // Allow network access
+                ..Default::default()
             };
 
+            // Installed templates and the compilation cache are mounted so scans
+            // routed through the sandbox can reach them
+            let user_templates = cert_x_gen::template::PathResolver::user_template_dir();
+            if user_templates.exists() {
+                config.volumes.insert(
+                    user_templates.to_string_lossy().to_string(),
+                    "/workspace/user-templates".to_string(),
+                );
+            }
+            std::fs::create_dir_all(config.host_cache_dir())?;
+            config.mount_cache();
+
             let mut sandbox = DockerSandbox::new(config.clone());
 
             term.write_line(&format!(
                 "  Building Docker image with languages: {}",
                 selected_languages.join(", ")
             ))?;
-            sandbox.build_image(None).await?;
+            sandbox
+                .build_image_with_progress(None, |line| print_build_line(&term, quiet, line))
+                .await?;
 
             term.write_line(&format!("  Creating container..."))?;
             sandbox.create().await?;
 
+            term.write_line("  Checking sandbox health...")?;
+            print_sandbox_health(&term, &sandbox.health_check().await)?;
+
             // Save to config file
             let mut cfg = SandboxConfigFile::load()?;
             cfg.set_sandbox(name.clone(), config);
@@ -2983,9 +4343,10 @@ async fn run_sandbox_command(cmd: cli::SandboxCommand) -> Result<()> {
                     term.write_line(&format!("    Auto-start: {}", config.auto_start))?;
 
                     // Check if container running
-                    if let Ok(sandbox) = DockerSandbox::load(name) {
+                    if let Ok(sandbox) = DockerSandbox::load_with_config(config.clone()) {
                         if sandbox.is_running() {
                             term.write_line(&format!("    Status: {}", style("Running").green()))?;
+                            print_sandbox_health(&term, &sandbox.health_check().await)?;
                         } else {
                             term.write_line(&format!("    Status: {}", style("Stopped").dim()))?;
                         }
@@ -3002,7 +4363,7 @@ async fn run_sandbox_command(cmd: cli::SandboxCommand) -> Result<()> {
             Ok(())
         }
 
-        SandboxAction::Build { dockerfile } => {
+        SandboxAction::Build { dockerfile, quiet } => {
             use cert_x_gen::sandbox::config::SandboxConfigFile;
             use cert_x_gen::sandbox::docker::DockerSandbox;
 
@@ -3015,7 +4376,11 @@ async fn run_sandbox_command(cmd: cli::SandboxCommand) -> Result<()> {
 
             let sandbox = DockerSandbox::new(config.clone());
 
-            sandbox.build_image(dockerfile.as_deref()).await?;
+            sandbox
+                .build_image_with_progress(dockerfile.as_deref(), |line| {
+                    print_build_line(&term, quiet, line)
+                })
+                .await?;
 
             term.write_line(&format!("{} Image built successfully!", style("✓").green()))?;
 
@@ -3024,6 +4389,107 @@ async fn run_sandbox_command(cmd: cli::SandboxCommand) -> Result<()> {
     }
 }
 
+/// Echo a docker build output line unless running quietly
+fn print_build_line(term: &console::Term, quiet: bool, line: &str) {
+    if !quiet {
+        let _ = term.write_line(&format!("    {}", console::style(line).dim()));
+    }
+}
+
+/// Print the result of a sandbox health probe
+fn print_sandbox_health(
+    term: &console::Term,
+    health: &cert_x_gen::sandbox::docker::SandboxHealth,
+) -> Result<()> {
+    use console::style;
+
+    match &health.responsive {
+        Ok(()) => term.write_line(&format!("    Health: {}", style("Responsive").green()))?,
+        Err(e) => {
+            term.write_line(&format!("    Health: {}", style("Not responding").red()))?;
+            for line in e.lines() {
+                term.write_line(&format!("      {}", style(line).dim()))?;
+            }
+        }
+    }
+    for runtime in &health.runtimes {
+        match &runtime.version {
+            Ok(version) => term.write_line(&format!(
+                "      {} {}: {}",
+                style("✓").green(),
+                runtime.language,
+                version
+            ))?,
+            Err(e) => term.write_line(&format!(
+                "      {} {}: {}",
+                style("✗").red(),
+                runtime.language,
+                e
+            ))?,
+        }
+    }
+    Ok(())
+}
+
+/// Print per-language package changes from `cxg sandbox update`
+fn print_package_updates(
+    term: &console::Term,
+    updates: &[cert_x_gen::sandbox::update::LanguageUpdate],
+    dry_run: bool,
+) -> Result<()> {
+    use console::style;
+
+    let mut changed = 0;
+    let mut failed = 0;
+    for update in updates {
+        term.write_line(&format!("\n{}", style(&update.language).bold()))?;
+        let changes = match &update.result {
+            Ok(changes) => changes,
+            Err(e) => {
+                failed += 1;
+                term.write_line(&format!("  {} {}", style("✗").red(), e))?;
+                continue;
+            }
+        };
+        if changes.is_empty() {
+            term.write_line(&format!("  {} up to date", style("✓").green()))?;
+            continue;
+        }
+
+        changed += changes.len();
+        let width = changes.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        for change in changes {
+            term.write_line(&format!(
+                "  {:<width$}  {:>12} → {}",
+                change.name,
+                change.before.as_deref().unwrap_or("-"),
+                style(change.after.as_deref().unwrap_or("?")).green(),
+                width = width
+            ))?;
+        }
+    }
+
+    term.write_line("")?;
+    let verb = if dry_run { "available" } else { "updated" };
+    if failed > 0 {
+        term.write_line(&format!(
+            "{} {} package(s) {}, {} language(s) failed",
+            style("⚠").yellow(),
+            changed,
+            verb,
+            failed
+        ))?;
+    } else {
+        term.write_line(&format!(
+            "{} {} package(s) {}",
+            style("✓").green(),
+            changed,
+            verb
+        ))?;
+    }
+    Ok(())
+}
+
 /// Check if we should auto-enter a Docker sandbox
 async fn check_and_enter_sandbox(cli: &Cli) -> Result<()> {
     use cert_x_gen::sandbox::config::SandboxConfigFile;
@@ -3070,7 +4536,7 @@ async fn check_and_enter_sandbox(cli: &Cli) -> Result<()> {
             tracing::info!("Auto-entering sandbox: {}", name);
 
             // Load sandbox
-            let mut sandbox = match DockerSandbox::load(name) {
+            let mut sandbox = match DockerSandbox::load_with_config(config.clone()) {
                 Ok(s) => s,
                 Err(e) => {
                     tracing::warn!("Failed to load default sandbox '{}': {}", name, e);
@@ -3082,7 +4548,7 @@ async fn check_and_enter_sandbox(cli: &Cli) -> Result<()> {
             if !sandbox.is_running() {
                 tracing::info!("Starting sandbox container...");
                 if let Err(e) = sandbox.start().await {
-                    tracing::warn!("Failed to start sandbox: {}", e);
+                    tracing::warn!("Failed to start sandbox '{}': {}", name, e);
                     return Ok(()); // Don't fail, just skip
                 }
             }
@@ -3115,8 +4581,8 @@ fn print_version() {
     println!("  OS: {}", std::env::consts::OS);
 }
 
-/// Print scan summary
-fn print_scan_summary(results: &cert_x_gen::types::ScanResults) {
+/// Print scan summary, with finding counts per value of the `summary_by` label
+fn print_scan_summary(results: &cert_x_gen::types::ScanResults, summary_by: Option<&str>) {
     use console::{style, Term};
 
     let _term = Term::stdout();
@@ -3137,8 +4603,44 @@ fn print_scan_summary(results: &cert_x_gen::types::ScanResults) {
         "  Templates Executed: {}",
         results.statistics.templates_executed
     );
+    if !results.statistics.engine_peak_concurrency.is_empty() {
+        let peaks = results
+            .statistics
+            .engine_peak_concurrency
+            .iter()
+            .map(|(engine, peak)| format!("{} {}", engine, peak))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("  Peak Engine Concurrency: {}", peaks);
+    }
+    if let Some(last) = results.statistics.concurrency_timeline.last() {
+        let timeline = &results.statistics.concurrency_timeline;
+        let lowest = timeline.iter().map(|c| c.concurrency).min().unwrap_or(0);
+        let highest = timeline.iter().map(|c| c.concurrency).max().unwrap_or(0);
+        println!(
+            "  Adaptive Concurrency: {} at the end, {}-{} over {} change(s)",
+            last.concurrency,
+            lowest,
+            highest,
+            timeline.len() - 1
+        );
+    }
+    println!("  Requests Issued: {}", results.statistics.network_requests);
+    for (host, count) in &results.statistics.requests_per_host {
+        println!("    {:<40} {}", host, count);
+    }
+    if results.statistics.skipped_work_units > 0 {
+        println!(
+            "  Skipped (request budget reached): {}",
+            style(results.statistics.skipped_work_units).yellow()
+        );
+    }
     println!();
 
+    if !results.targets.is_empty() {
+        print_probe_summary(&results.targets);
+    }
+
     println!("{}", style("Findings by Severity:").bold());
 
     let critical = results
@@ -3185,44 +4687,159 @@ fn print_scan_summary(results: &cert_x_gen::types::ScanResults) {
     println!(
         "  {} {}",
         style("TOTAL:").bold(),
-        style(results.findings.len()).bold()
+        style(results.statistics.total_findings).bold()
     );
+    if results.statistics.reported_findings != results.statistics.total_findings {
+        println!(
+            "  {} {}",
+            style("REPORTED:").bold(),
+            style(results.statistics.reported_findings).bold()
+        );
+    }
     println!();
+
+    let clusters = ClusterSummary::from_results(results);
+    if !clusters.is_empty() {
+        println!("{}", style("Response clusters:").bold());
+        let mut repeated = clusters.repeated().peekable();
+        if repeated.peek().is_none() {
+            println!("  {}", style("(every finding is distinct)").dim());
+        }
+        for (template, counts) in repeated {
+            println!(
+                "  {}: {} findings in {} cluster{}",
+                template,
+                counts.findings,
+                counts.clusters,
+                if counts.clusters == 1 { "" } else { "s" }
+            );
+        }
+        println!();
+    }
+
+    if let Some(key) = summary_by {
+        println!("{}", style(format!("Findings by {}:", key)).bold());
+        let counts = results.findings_by_label(key);
+        if counts.is_empty() {
+            println!("  {}", style("(no findings)").dim());
+        }
+        // Labelled values first, findings without the label last
+        for (value, count) in counts.iter().filter(|(v, _)| v.is_some()) {
+            println!("  {}: {}", value.as_deref().unwrap_or_default(), count);
+        }
+        if let Some(count) = counts.get(&None) {
+            println!("  {} {}", style(format!("(no {}):", key)).dim(), count);
+        }
+        println!();
+    }
     println!("{}", style("═".repeat(80)).dim());
 }
 
+/// Per-target liveness table (`--probe-summary`)
+fn print_probe_summary(targets: &[cert_x_gen::types::TargetProbe]) {
+    use console::style;
+
+    fn clip(value: Option<&str>, width: usize) -> String {
+        let value = value.unwrap_or("-");
+        if value.chars().count() > width {
+            let mut clipped: String = value.chars().take(width - 1).collect();
+            clipped.push('…');
+            clipped
+        } else {
+            value.to_string()
+        }
+    }
+
+    println!("{}", style("Targets:").bold());
+    println!(
+        "  {:<24} {:<4} {:>6}  {:<16} {:<20} TLS CN",
+        "TARGET", "UP", "STATUS", "SERVER", "TITLE"
+    );
+    for probe in targets {
+        if !probe.reachable {
+            println!(
+                "  {:<24} {:<4} {}",
+                clip(Some(&probe.target), 24),
+                style("no").red(),
+                style(probe.error.as_deref().unwrap_or("not contacted")).dim()
+            );
+            continue;
+        }
+        let status = probe
+            .lowest_status
+            .map_or_else(|| "-".to_string(), |status| status.to_string());
+        println!(
+            "  {:<24} {:<4} {:>6}  {:<16} {:<20} {}",
+            clip(Some(&probe.target), 24),
+            style("yes").green(),
+            status,
+            clip(probe.server.as_deref(), 16),
+            clip(probe.title.as_deref(), 20),
+            probe.tls_cn.as_deref().unwrap_or("-")
+        );
+    }
+    println!();
+}
+
 /// Run AI command
-async fn run_ai_command(cmd: cli::AiCommand) -> Result<()> {
+async fn run_ai_command(
+    cmd: cli::AiCommand,
+    config_path: Option<&Path>,
+    profile: Option<&str>,
+) -> Result<()> {
     use cli::AiAction;
 
     match cmd.action {
         AiAction::Generate {
             prompt,
+            from_cve,
+            poc_file,
             language,
             provider,
             model,
+            complexity,
             output,
             test,
             test_target,
             force,
             estimate_cost,
+            max_cost,
+            no_interactive,
         } => {
             handle_ai_generate(
                 prompt,
+                from_cve,
+                poc_file,
                 language,
                 provider,
                 model,
+                complexity,
                 output,
                 test,
                 test_target,
                 force,
                 estimate_cost,
+                max_cost,
+                no_interactive,
             )
             .await?;
         }
         AiAction::Providers { action } => {
             handle_providers_command(action).await?;
         }
+        AiAction::Explain { template, json } => {
+            let config = load_config(config_path, profile)?;
+            run_validate_command(
+                template,
+                ValidateOptions {
+                    format: if json { "json" } else { "text" }.to_string(),
+                    snippets_dir: config.templates.snippets_dir.as_deref(),
+                    explain: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
     }
 
     Ok(())
@@ -3230,16 +4847,22 @@ async fn run_ai_command(cmd: cli::AiCommand) -> Result<()> {
 
 /// Handle AI template generation
 async fn handle_ai_generate(
-    prompt: String,
+    prompt: Option<String>,
+    from_cve: Option<String>,
+    poc_file: Option<PathBuf>,
     language: cli::LanguageArg,
     provider: Option<String>,
     model: Option<String>,
+    complexity: Option<cli::ComplexityArg>,
     output: Option<PathBuf>,
     test: bool,
     test_target: Option<String>,
     force: bool,
     estimate_cost: bool,
+    max_cost: Option<f64>,
+    no_interactive: bool,
 ) -> Result<()> {
+    use cert_x_gen::ai::{CostDecision, CostGuard, GenerationTask};
     use console::{style, Term};
     use std::fs;
 
@@ -3248,11 +4871,28 @@ async fn handle_ai_generate(
     // Convert language argument to TemplateLanguage
     let template_lang: TemplateLanguage = language.into();
 
+    let poc = poc_file
+        .as_ref()
+        .map(|path| {
+            fs::read_to_string(path).map_err(|e| {
+                Error::config(format!("Failed to read PoC file {}: {}", path.display(), e))
+            })
+        })
+        .transpose()?;
+
     println!();
     println!("{}", style("🤖 AI Template Generation").bold().cyan());
     println!("{}", style("═".repeat(60)).dim());
     println!();
-    println!("  {}  {}", style("Prompt:").bold(), prompt);
+    if let Some(ref prompt) = prompt {
+        println!("  {}  {}", style("Prompt:").bold(), prompt);
+    }
+    if let Some(ref cve_id) = from_cve {
+        println!("  {}  {}", style("CVE:").bold(), cve_id);
+    }
+    if let Some(ref path) = poc_file {
+        println!("  {}  {}", style("PoC:").bold(), path.display());
+    }
     println!("  {}  {:?}", style("Language:").bold(), template_lang);
     if let Some(ref p) = provider {
         println!("  {}  {}", style("Provider:").bold(), p);
@@ -3270,13 +4910,79 @@ async fn handle_ai_generate(
     let manager = AIManager::new()
         .map_err(|e| Error::Ai(format!("Failed to initialize AI manager: {}", e)))?;
 
-    // Show cost estimate if requested
-    if estimate_cost {
+    // With --from-cve the prompt is built from the NVD record (and PoC)
+    let prompt = match &from_cve {
+        Some(cve_id) => {
+            term.write_line(&format!("  Fetching {} from the NVD...", cve_id))?;
+            manager
+                .cve_prompt(cve_id, poc.as_deref())
+                .await
+                .map_err(|e| Error::Ai(format!("CVE lookup failed: {}", e)))?
+        }
+        None => prompt.unwrap_or_default(),
+    };
+
+    // --model wins; otherwise pick one for the task's complexity
+    let model = match model {
+        Some(model) => model,
+        None => {
+            let mut task =
+                GenerationTask::new(prompt.as_str(), template_lang).with_provider(provider.clone());
+            if let Some(complexity) = complexity {
+                task = task.with_complexity(complexity.into());
+            }
+            let selected = manager
+                .select_model(&task)
+                .await
+                .map_err(|e| Error::Ai(format!("Model selection failed: {}", e)))?;
+            println!(
+                "  {} Model: {} (auto-selected for {} complexity)",
+                style("ℹ").blue(),
+                selected,
+                task.complexity
+            );
+            selected
+        }
+    };
+
+    // Show the cost estimate if requested, and hold generation to --max-cost
+    if estimate_cost || max_cost.is_some() {
         term.write_line(&format!("{} Estimating cost...", style("[2/5]").dim()))?;
-        println!(
-            "  {} Cost estimation not yet implemented",
-            style("ℹ").blue()
-        );
+        let estimated = manager
+            .estimate_generation_cost(&prompt, template_lang, provider.as_deref(), Some(&model))
+            .map_err(|e| Error::Ai(format!("Cost estimation failed: {}", e)))?;
+        match estimated {
+            Some(cost) => println!("  {} Estimated cost: ${:.4}", style("ℹ").blue(), cost),
+            None if estimate_cost => println!(
+                "  {} No per-token cost for this provider",
+                style("ℹ").blue()
+            ),
+            None => {}
+        }
+
+        match CostGuard::new(!no_interactive).check(estimated, max_cost) {
+            CostDecision::Unchecked | CostDecision::WithinLimit { .. } => {}
+            CostDecision::Confirm { estimated, limit } => {
+                let proceed = dialoguer::Confirm::new()
+                    .with_prompt(format!(
+                        "  Estimated cost ${:.4} exceeds --max-cost ${:.4}. Generate anyway?",
+                        estimated, limit
+                    ))
+                    .default(false)
+                    .interact()
+                    .map_err(|e| Error::Ai(format!("Confirmation prompt failed: {}", e)))?;
+                if !proceed {
+                    println!("  {} Generation cancelled", style("✗").red());
+                    return Ok(());
+                }
+            }
+            CostDecision::Abort { estimated, limit } => {
+                return Err(Error::Ai(format!(
+                    "Estimated cost ${:.4} exceeds --max-cost ${:.4}",
+                    estimated, limit
+                )));
+            }
+        }
         println!();
     }
 
@@ -3284,10 +4990,30 @@ async fn handle_ai_generate(
     term.write_line(&format!("{} Generating template...", style("[2/5]").dim()))?;
     term.write_line("  This may take 10-30 seconds depending on the model...")?;
 
-    let template_code = manager
-        .generate_template(&prompt, template_lang, provider.as_deref())
-        .await
-        .map_err(|e| Error::Ai(format!("Template generation failed: {}", e)))?;
+    let template_code = match &from_cve {
+        Some(cve_id) => {
+            manager
+                .generate_from_cve(
+                    cve_id,
+                    poc.as_deref(),
+                    template_lang,
+                    provider.as_deref(),
+                    Some(&model),
+                )
+                .await
+        }
+        None => {
+            manager
+                .generate_template_with_model(
+                    &prompt,
+                    template_lang,
+                    provider.as_deref(),
+                    Some(&model),
+                )
+                .await
+        }
+    }
+    .map_err(|e| Error::Ai(format!("Template generation failed: {}", e)))?;
 
     term.write_line(&format!(
         "  {} Template generated successfully!",
@@ -3310,7 +5036,8 @@ async fn handle_ai_generate(
         path
     } else {
         // Auto-generate filename
-        let filename = manager.generate_filename(&prompt, template_lang);
+        let filename =
+            manager.generate_filename(from_cve.as_deref().unwrap_or(&prompt), template_lang);
         let ai_templates_dir = dirs::home_dir()
             .ok_or_else(|| Error::Internal("Could not determine home directory".to_string()))?
             .join(".cert-x-gen")
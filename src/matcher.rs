@@ -3,10 +3,22 @@
 //! Provides various matcher types for identifying security issues.
 
 use crate::error::{Error, Result};
+use crate::metrics::profiler::{self, Phase};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+pub mod confidence;
+pub mod diff;
+pub mod latency;
+pub mod regex_cache;
+pub mod structured;
+
+pub use confidence::{ConfidenceAggregator, EvidenceKind};
+pub use diff::{line_diff, DiffRequest, LineDiff};
+pub use latency::LatencyBaselines;
+pub use structured::PathCheck;
+
 /// Matcher types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
@@ -35,6 +47,22 @@ pub enum MatcherType {
         #[serde(default)]
         group: Option<usize>,
     },
+    /// JSON body matcher (see [`structured`])
+    Json {
+        /// Paths and the conditions on what they select
+        json: Vec<PathCheck>,
+        /// Match condition (and/or)
+        #[serde(default = "default_condition")]
+        condition: MatchCondition,
+    },
+    /// XML/HTML body matcher (see [`structured`])
+    Xpath {
+        /// Paths and the conditions on what they select
+        xpath: Vec<PathCheck>,
+        /// Match condition (and/or)
+        #[serde(default = "default_condition")]
+        condition: MatchCondition,
+    },
     /// Binary matcher
     Binary {
         /// Binary patterns (hex encoded)
@@ -79,13 +107,37 @@ pub enum MatcherType {
         /// Specific value to match
         value: Option<String>,
     },
-    /// Diff matcher (compare with baseline)
+    /// Diff matcher (compare a neutral request with a detection request)
+    ///
+    /// Sends its own two requests, so it is evaluated by the template engine.
     Diff {
-        /// Baseline response to compare against
-        baseline: String,
-        /// Similarity threshold (0-100)
-        threshold: u8,
+        /// Request with a neutral payload
+        baseline_request: DiffRequest,
+        /// Request with the detection payload
+        detect_request: DiffRequest,
+        /// Fire when at least this fraction of lines changed (0.0-1.0)
+        min_diff_ratio: f32,
+    },
+    /// Time-based blind matcher: the response is at least `payload_delay`
+    /// seconds (less `tolerance`) slower than the target's latency baseline
+    ///
+    /// Slow responses are re-sent `confirmations` times and only match when
+    /// every attempt is slow, so it is evaluated by the template engine.
+    Latency {
+        /// Delay the payload induces, in seconds
+        payload_delay: f64,
+        /// Slack for network jitter, in seconds
+        #[serde(default = "default_latency_tolerance")]
+        tolerance: f64,
+        /// Extra attempts that must also be slow
+        #[serde(default = "default_latency_confirmations")]
+        confirmations: u32,
     },
+    /// HTML body served under a type browsers sniff, without `nosniff`
+    ///
+    /// See [`ContentSniffingDetector`](crate::network::content_sniff::ContentSniffingDetector).
+    #[serde(rename = "content_sniff")]
+    ContentSniff,
     /// Custom matcher (code-based)
     Custom {
         /// Language for custom code
@@ -103,6 +155,14 @@ fn default_part() -> ResponsePart {
     ResponsePart::Body
 }
 
+fn default_latency_tolerance() -> f64 {
+    latency::DEFAULT_TOLERANCE_SECS
+}
+
+fn default_latency_confirmations() -> u32 {
+    latency::DEFAULT_CONFIRMATIONS
+}
+
 /// Match condition
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -115,7 +175,7 @@ pub enum MatchCondition {
 
 /// Response part to match against
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(try_from = "String", into = "String")]
 pub enum ResponsePart {
     /// Match against response body
     Body,
@@ -125,6 +185,50 @@ pub enum ResponsePart {
     All,
     /// Match against raw data (for network protocols)
     Data,
+    /// Match against the Nth (1-based) response read on a raw `connection:`, written `response_N`
+    Segment(usize),
+}
+
+impl std::fmt::Display for ResponsePart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResponsePart::Body => write!(f, "body"),
+            ResponsePart::Header => write!(f, "header"),
+            ResponsePart::All => write!(f, "all"),
+            ResponsePart::Data => write!(f, "data"),
+            ResponsePart::Segment(n) => write!(f, "response_{}", n),
+        }
+    }
+}
+
+impl TryFrom<String> for ResponsePart {
+    type Error = String;
+
+    fn try_from(part: String) -> std::result::Result<Self, Self::Error> {
+        match part.as_str() {
+            "body" => Ok(ResponsePart::Body),
+            "header" => Ok(ResponsePart::Header),
+            "all" => Ok(ResponsePart::All),
+            "data" => Ok(ResponsePart::Data),
+            _ => part
+                .strip_prefix("response_")
+                .and_then(|n| n.parse::<usize>().ok())
+                .filter(|n| *n > 0)
+                .map(ResponsePart::Segment)
+                .ok_or_else(|| {
+                    format!(
+                        "unknown part '{}' (expected body, header, all, data or response_N)",
+                        part
+                    )
+                }),
+        }
+    }
+}
+
+impl From<ResponsePart> for String {
+    fn from(part: ResponsePart) -> Self {
+        part.to_string()
+    }
 }
 
 /// Time-based condition
@@ -172,16 +276,28 @@ pub struct HttpResponse {
     pub status: u16,
     /// Response headers
     pub headers: Vec<(String, String)>,
-    /// Response body
+    /// Response body, after undoing any `Content-Encoding`
     pub body: Vec<u8>,
     /// Response time
     pub response_time: Duration,
+    /// Each response read on a raw `connection:`, in order; empty for single requests
+    pub segments: Vec<String>,
+    /// URL the response was served from, after redirects; `None` when not fetched over HTTP
+    pub url: Option<String>,
 }
 
 impl HttpResponse {
-    /// Get response body as string
+    /// Get response body as string, transcoded from its charset
     pub fn body_string(&self) -> String {
-        String::from_utf8_lossy(&self.body).to_string()
+        crate::network::decode::decode_text(&self.body, self.header("content-type"))
+    }
+
+    /// First value of a header (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
     }
 
     /// Get headers as string
@@ -197,18 +313,94 @@ impl HttpResponse {
     pub fn all_string(&self) -> String {
         format!("{}\n\n{}", self.headers_string(), self.body_string())
     }
+
+    /// The `n`th (1-based) raw connection segment
+    pub fn segment(&self, n: usize) -> Option<&str> {
+        n.checked_sub(1)
+            .and_then(|i| self.segments.get(i))
+            .map(String::as_str)
+    }
+}
+
+impl MatcherType {
+    /// Compile this matcher's regex patterns into the shared cache and check
+    /// its JSON and XPath paths
+    ///
+    /// Templates call this when they load, so a broken pattern fails
+    /// validation instead of every match.
+    pub fn precompile(&self) -> Result<()> {
+        if let Some((syntax, checks, _)) = self.structured() {
+            structured::validate(syntax, checks)?;
+        }
+        compile_patterns(self).map(|_| ())
+    }
+
+    /// Path syntax, checks and condition of a `json` or `xpath` matcher
+    fn structured(&self) -> Option<(structured::Syntax, &[PathCheck], MatchCondition)> {
+        match self {
+            MatcherType::Json { json, condition } => {
+                Some((structured::Syntax::Json, json, *condition))
+            }
+            MatcherType::Xpath { xpath, condition } => {
+                Some((structured::Syntax::Xpath, xpath, *condition))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Compiled `regex` patterns of a regex matcher; other types have none
+fn compile_patterns(matcher_type: &MatcherType) -> Result<Vec<Regex>> {
+    let MatcherType::Regex { regex, .. } = matcher_type else {
+        return Ok(Vec::new());
+    };
+    regex
+        .iter()
+        .map(|pattern| {
+            regex_cache::compile(pattern)
+                .map_err(|e| Error::Matcher(format!("Invalid regex '{}': {}", pattern, e)))
+        })
+        .collect()
 }
 
 /// Matcher for vulnerability detection
 #[derive(Debug)]
 pub struct Matcher {
     matcher_type: MatcherType,
+    /// Compiled `regex` patterns, or why they failed to compile
+    regexes: std::result::Result<Vec<Regex>, String>,
 }
 
 impl Matcher {
     /// Create a new matcher
+    ///
+    /// An invalid regex pattern is reported by [`matches`](Self::matches);
+    /// use [`compile`](Self::compile) to fail early.
     pub fn new(matcher_type: MatcherType) -> Self {
-        Self { matcher_type }
+        let regexes = compile_patterns(&matcher_type).map_err(|e| match e {
+            Error::Matcher(message) => message,
+            other => other.to_string(),
+        });
+        Self {
+            matcher_type,
+            regexes,
+        }
+    }
+
+    /// Create a matcher, failing on an invalid regex pattern
+    pub fn compile(matcher_type: MatcherType) -> Result<Self> {
+        let regexes = compile_patterns(&matcher_type)?;
+        Ok(Self {
+            matcher_type,
+            regexes: Ok(regexes),
+        })
+    }
+
+    /// Compiled regex patterns
+    fn regexes(&self) -> Result<&[Regex]> {
+        self.regexes
+            .as_deref()
+            .map_err(|message| Error::Matcher(message.clone()))
     }
 
     /// Match against an HTTP response
@@ -226,6 +418,9 @@ impl Matcher {
                     ResponsePart::Header => response.headers_string(),
                     ResponsePart::All => response.all_string(),
                     ResponsePart::Data => response.body_string(), // For network protocols, data = body
+                    ResponsePart::Segment(n) => {
+                        response.segment(*n).unwrap_or_default().to_string()
+                    }
                 };
 
                 let matches: Vec<bool> = words.iter().map(|word| content.contains(word)).collect();
@@ -236,12 +431,9 @@ impl Matcher {
                 })
             }
 
-            MatcherType::Regex { regex, group } => {
+            MatcherType::Regex { group, .. } => {
                 let content = response.body_string();
-                for pattern in regex {
-                    let re = Regex::new(pattern)
-                        .map_err(|e| Error::Matcher(format!("Invalid regex: {}", e)))?;
-
+                for re in self.regexes()? {
                     if let Some(captures) = re.captures(&content) {
                         if let Some(g) = group {
                             if captures.get(*g).is_some() {
@@ -255,6 +447,13 @@ impl Matcher {
                 Ok(false)
             }
 
+            MatcherType::Json { .. } | MatcherType::Xpath { .. } => {
+                let Some((syntax, checks, condition)) = self.matcher_type.structured() else {
+                    return Ok(false);
+                };
+                structured::matches(syntax, checks, condition, &response.body_string())
+            }
+
             MatcherType::Binary { binary } => {
                 for pattern in binary {
                     let bytes = hex::decode(pattern.trim_start_matches("0x"))
@@ -366,7 +565,7 @@ impl Matcher {
 
                 // Check pattern if provided
                 if let Some(p) = pattern {
-                    if let Ok(re) = Regex::new(p) {
+                    if let Ok(re) = regex_cache::compile(p) {
                         matched = matched && re.is_match(&body_str);
                     }
                 }
@@ -381,18 +580,19 @@ impl Matcher {
                 Ok(matched)
             }
 
-            MatcherType::Diff {
-                baseline,
-                threshold,
-            } => {
-                // Calculate difference between current response and baseline
-                let current = response.body_string();
-                let similarity = calculate_similarity(&current, baseline);
+            MatcherType::Diff { .. } => Err(Error::NotImplemented(
+                "Diff matchers send their own requests and are evaluated by the template engine"
+                    .to_string(),
+            )),
 
-                // If similarity is below threshold, responses are different
-                let difference_percentage = 100 - similarity;
-                Ok(difference_percentage >= *threshold as usize)
-            }
+            MatcherType::Latency { .. } => Err(Error::NotImplemented(
+                "Latency matchers need a target baseline and are evaluated by the template engine"
+                    .to_string(),
+            )),
+
+            MatcherType::ContentSniff => Ok(crate::network::ContentSniffingDetector::new()
+                .analyze(response)
+                .is_some()),
 
             MatcherType::Custom { .. } => {
                 // Custom matchers would be evaluated by the template engine
@@ -407,6 +607,30 @@ impl Matcher {
     pub fn matcher_type(&self) -> &MatcherType {
         &self.matcher_type
     }
+
+    /// Values captured by a regex matcher (its `group`, or the whole match),
+    /// or selected by the passing checks of a `json` or `xpath` matcher
+    ///
+    /// Other matcher types capture nothing.
+    pub fn extract(&self, response: &HttpResponse) -> Result<Vec<String>> {
+        if let Some((syntax, checks, _)) = self.matcher_type.structured() {
+            return structured::extract(syntax, checks, &response.body_string());
+        }
+        let MatcherType::Regex { group, .. } = &self.matcher_type else {
+            return Ok(Vec::new());
+        };
+
+        let content = response.body_string();
+        let mut values = Vec::new();
+        for re in self.regexes()? {
+            for captures in re.captures_iter(&content) {
+                if let Some(value) = captures.get(group.unwrap_or(0)) {
+                    values.push(value.as_str().to_string());
+                }
+            }
+        }
+        Ok(values)
+    }
 }
 
 /// Match multiple matchers against a response
@@ -419,6 +643,7 @@ pub fn match_all(
         return Ok(false);
     }
 
+    let _matching = profiler::enter(Phase::Matching);
     let results: Result<Vec<bool>> = matchers.iter().map(|m| m.matches(response)).collect();
     let results = results?;
 
@@ -428,34 +653,6 @@ pub fn match_all(
     })
 }
 
-/// Calculate similarity between two strings (0-100)
-/// Uses a simple character-based comparison
-fn calculate_similarity(s1: &str, s2: &str) -> usize {
-    if s1.is_empty() && s2.is_empty() {
-        return 100;
-    }
-
-    if s1.is_empty() || s2.is_empty() {
-        return 0;
-    }
-
-    // Simple character-based similarity
-    let chars1: Vec<char> = s1.chars().collect();
-    let chars2: Vec<char> = s2.chars().collect();
-
-    let max_len = chars1.len().max(chars2.len());
-    let min_len = chars1.len().min(chars2.len());
-
-    let mut matches = 0;
-    for i in 0..min_len {
-        if chars1.get(i) == chars2.get(i) {
-            matches += 1;
-        }
-    }
-
-    ((matches as f64 / max_len as f64) * 100.0) as usize
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -466,6 +663,8 @@ mod tests {
             headers: vec![("Content-Type".to_string(), "text/html".to_string())],
             body: body.as_bytes().to_vec(),
             response_time: Duration::from_millis(100),
+            segments: Vec::new(),
+            url: None,
         }
     }
 
@@ -496,6 +695,30 @@ mod tests {
         assert!(!matcher.matches(&response).unwrap());
     }
 
+    #[test]
+    fn test_word_matcher_on_connection_segment() {
+        let matcher_type: MatcherType =
+            serde_yaml::from_str("type: word\nwords: [\"GPOST\"]\npart: response_2\n").unwrap();
+        let matcher = Matcher::new(matcher_type);
+
+        let mut response = create_test_response(200, "HTTP/1.1 200 OK");
+        assert!(!matcher.matches(&response).unwrap());
+        response.segments = vec![
+            "HTTP/1.1 200 OK\r\n\r\n".to_string(),
+            "HTTP/1.1 405 Not Allowed\r\n\r\nUnrecognized method GPOST".to_string(),
+        ];
+        assert!(matcher.matches(&response).unwrap());
+
+        assert_eq!(
+            serde_yaml::to_string(&ResponsePart::Segment(2))
+                .unwrap()
+                .trim(),
+            "response_2"
+        );
+        assert!(serde_yaml::from_str::<ResponsePart>("response_0").is_err());
+        assert!(serde_yaml::from_str::<ResponsePart>("trailer").is_err());
+    }
+
     #[test]
     fn test_size_matcher() {
         let matcher = Matcher::new(MatcherType::Size {
@@ -520,4 +743,44 @@ mod tests {
         let response = create_test_response(200, "Server version: 2.5.30");
         assert!(matcher.matches(&response).unwrap());
     }
+
+    #[test]
+    fn test_invalid_regex_fails_compile() {
+        let broken = MatcherType::Regex {
+            regex: vec![r"version:\s*(\d+".to_string()],
+            group: None,
+        };
+        assert!(broken.precompile().is_err());
+        let err = Matcher::compile(broken.clone()).unwrap_err().to_string();
+        assert!(err.contains("Invalid regex"), "{}", err);
+
+        // Built without checking, the error surfaces when matching
+        let matcher = Matcher::new(broken);
+        assert!(matcher
+            .matches(&create_test_response(200, "version: 1"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_rebuilt_matchers_reuse_cached_regex() {
+        let pattern = r"(?i)(?:mysql|postgres|oracle).*?(?:error|exception|warning):\s*([^<\n]+)";
+        let matcher_type = MatcherType::Regex {
+            regex: vec![pattern.to_string()],
+            group: Some(1),
+        };
+        let response = create_test_response(200, "<p>MySQL server error: syntax near '1'</p>");
+        let rounds = 50;
+
+        matcher_type.precompile().unwrap();
+        assert!(regex_cache::global().contains(pattern));
+
+        // Templates rebuild matchers per request; each match must be a cache hit.
+        // Other tests share the global cache, so only a lower bound holds.
+        let hits = regex_cache::global().hits();
+        for _ in 0..rounds {
+            let matcher = Matcher::new(matcher_type.clone());
+            assert!(matcher.matches(&response).unwrap());
+        }
+        assert!(regex_cache::global().hits() - hits >= rounds);
+    }
 }
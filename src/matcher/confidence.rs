@@ -0,0 +1,228 @@
+//! Finding confidence from the evidence behind a match
+//!
+//! The template's `confidence` (90 when unset) is the starting point and is
+//! adjusted by what fired:
+//!
+//! - each matcher beyond the first that fired: +[`MATCHER_BONUS`]
+//! - two or more kinds of evidence (status, word, regex, ...) fired and no
+//!   matcher missed: +[`AGREEMENT_BONUS`]
+//! - only weak evidence (a status code or body size) fired: -[`WEAK_PENALTY`]
+//! - every re-sent request behaved like the first: +[`CONSISTENCY_BONUS`];
+//!   otherwise up to -[`INCONSISTENCY_PENALTY`], in proportion to the
+//!   attempts that did not
+//!
+//! The result is clamped to 0-100.
+
+use super::MatcherType;
+
+/// Added for each matcher beyond the first that fired
+pub const MATCHER_BONUS: i32 = 5;
+
+/// Added when different kinds of evidence agree
+pub const AGREEMENT_BONUS: i32 = 5;
+
+/// Taken off when only weak evidence fired
+pub const WEAK_PENALTY: i32 = 20;
+
+/// Added when every retry agreed with the first response
+pub const CONSISTENCY_BONUS: i32 = 5;
+
+/// Taken off when no retry agreed with the first response
+pub const INCONSISTENCY_PENALTY: i32 = 30;
+
+/// What a matcher looked at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EvidenceKind {
+    /// Status code
+    Status,
+    /// Words in the response
+    Word,
+    /// Regular expressions
+    Regex,
+    /// JSON or XPath selections
+    Structured,
+    /// Byte patterns
+    Binary,
+    /// Response time
+    Timing,
+    /// Body size
+    Size,
+    /// Body hash
+    Hash,
+    /// Difference between a neutral and a detection request
+    Diff,
+    /// Any other check on the response
+    Other,
+}
+
+impl EvidenceKind {
+    /// Kind of evidence `matcher` produces
+    pub fn of(matcher: &MatcherType) -> Self {
+        match matcher {
+            MatcherType::Status { .. } => EvidenceKind::Status,
+            MatcherType::Word { .. } => EvidenceKind::Word,
+            MatcherType::Regex { .. } => EvidenceKind::Regex,
+            MatcherType::Json { .. } | MatcherType::Xpath { .. } => EvidenceKind::Structured,
+            MatcherType::Binary { .. } => EvidenceKind::Binary,
+            MatcherType::Time { .. } | MatcherType::Latency { .. } => EvidenceKind::Timing,
+            MatcherType::Size { .. } => EvidenceKind::Size,
+            MatcherType::Hash { .. } => EvidenceKind::Hash,
+            MatcherType::Diff { .. } => EvidenceKind::Diff,
+            _ => EvidenceKind::Other,
+        }
+    }
+
+    /// Evidence that ordinary pages often show too
+    pub fn is_weak(self) -> bool {
+        matches!(self, EvidenceKind::Status | EvidenceKind::Size)
+    }
+}
+
+/// Collects what fired for one match and turns it into a confidence
+#[derive(Debug, Clone)]
+pub struct ConfidenceAggregator {
+    base: u8,
+    fired: Vec<EvidenceKind>,
+    missed: usize,
+    retries: u32,
+    consistent_retries: u32,
+}
+
+impl ConfidenceAggregator {
+    /// Start from the template's confidence
+    pub fn new(base: u8) -> Self {
+        Self {
+            base: base.min(100),
+            fired: Vec::new(),
+            missed: 0,
+            retries: 0,
+            consistent_retries: 0,
+        }
+    }
+
+    /// Record a matcher that fired
+    pub fn fired(&mut self, kind: EvidenceKind) {
+        self.fired.push(kind);
+    }
+
+    /// Record a matcher that did not fire (possible under `matchers-condition: or`)
+    pub fn missed(&mut self) {
+        self.missed += 1;
+    }
+
+    /// Record `total` re-sent requests, `consistent` of which matched again
+    pub fn retries(&mut self, consistent: u32, total: u32) {
+        self.retries += total;
+        self.consistent_retries += consistent.min(total);
+    }
+
+    /// Confidence (0-100) for everything recorded
+    pub fn confidence(&self) -> u8 {
+        let mut confidence = i32::from(self.base);
+
+        confidence += MATCHER_BONUS * self.fired.len().saturating_sub(1) as i32;
+
+        let mut kinds = self.fired.clone();
+        kinds.sort_unstable();
+        kinds.dedup();
+        if kinds.len() > 1 && self.missed == 0 {
+            confidence += AGREEMENT_BONUS;
+        }
+        if !kinds.is_empty() && kinds.iter().all(|k| k.is_weak()) {
+            confidence -= WEAK_PENALTY;
+        }
+
+        if self.retries > 0 {
+            if self.consistent_retries == self.retries {
+                confidence += CONSISTENCY_BONUS;
+            } else {
+                let inconsistent = (self.retries - self.consistent_retries) as i32;
+                confidence -= INCONSISTENCY_PENALTY * inconsistent / self.retries as i32;
+            }
+        }
+
+        confidence.clamp(0, 100) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_matcher_keeps_the_base() {
+        let mut aggregator = ConfidenceAggregator::new(80);
+        aggregator.fired(EvidenceKind::Regex);
+        assert_eq!(aggregator.confidence(), 80);
+        assert_eq!(ConfidenceAggregator::new(80).confidence(), 80);
+    }
+
+    #[test]
+    fn test_independent_evidence_adds_up() {
+        let mut aggregator = ConfidenceAggregator::new(70);
+        aggregator.fired(EvidenceKind::Word);
+        aggregator.fired(EvidenceKind::Regex);
+        aggregator.fired(EvidenceKind::Status);
+        // Two extra matchers plus agreement across kinds
+        assert_eq!(aggregator.confidence(), 85);
+
+        // A matcher that missed cancels the agreement bonus only
+        aggregator.missed();
+        assert_eq!(aggregator.confidence(), 80);
+
+        // Two matchers of one kind are not independent
+        let mut same_kind = ConfidenceAggregator::new(70);
+        same_kind.fired(EvidenceKind::Word);
+        same_kind.fired(EvidenceKind::Word);
+        assert_eq!(same_kind.confidence(), 75);
+    }
+
+    #[test]
+    fn test_weak_evidence_alone_is_penalized() {
+        let mut status_only = ConfidenceAggregator::new(90);
+        status_only.fired(EvidenceKind::Status);
+        assert_eq!(status_only.confidence(), 70);
+
+        let mut corroborated = ConfidenceAggregator::new(90);
+        corroborated.fired(EvidenceKind::Status);
+        corroborated.fired(EvidenceKind::Word);
+        assert_eq!(corroborated.confidence(), 100);
+        assert!(corroborated.confidence() > status_only.confidence());
+    }
+
+    #[test]
+    fn test_retry_consistency() {
+        let mut consistent = ConfidenceAggregator::new(60);
+        consistent.fired(EvidenceKind::Timing);
+        consistent.retries(2, 2);
+        assert_eq!(consistent.confidence(), 65);
+
+        let mut flaky = ConfidenceAggregator::new(60);
+        flaky.fired(EvidenceKind::Timing);
+        flaky.retries(1, 3);
+        assert_eq!(flaky.confidence(), 40);
+        flaky.retries(0, 1);
+        assert_eq!(flaky.confidence(), 38);
+    }
+
+    #[test]
+    fn test_confidence_is_clamped() {
+        let mut ceiling = ConfidenceAggregator::new(95);
+        for kind in [
+            EvidenceKind::Word,
+            EvidenceKind::Regex,
+            EvidenceKind::Structured,
+            EvidenceKind::Hash,
+        ] {
+            ceiling.fired(kind);
+        }
+        ceiling.retries(3, 3);
+        assert_eq!(ceiling.confidence(), 100);
+        assert_eq!(ConfidenceAggregator::new(250).confidence(), 100);
+
+        let mut floor = ConfidenceAggregator::new(10);
+        floor.fired(EvidenceKind::Status);
+        floor.retries(0, 4);
+        assert_eq!(floor.confidence(), 0);
+    }
+}
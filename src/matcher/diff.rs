@@ -0,0 +1,117 @@
+//! Differential response comparison for the `diff` matcher
+//!
+//! Blind injections often only show up as a difference between a neutral
+//! request and a detection request (`id=1 AND 1=1` vs `id=1 AND 1=2`). The
+//! `diff` matcher sends both and fires when enough lines changed, which does
+//! not depend on response timing.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Lines compared from each response; longer bodies are truncated
+pub const MAX_DIFF_LINES: usize = 2000;
+
+/// One of the two requests sent by a `diff` matcher
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffRequest {
+    /// HTTP method
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// Request path, appended to the target URL
+    pub path: String,
+    /// Extra request headers
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Request body
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+/// Result of comparing two bodies line by line
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineDiff {
+    /// Changed lines over total lines (0.0 identical, 1.0 nothing in common)
+    pub ratio: f32,
+    /// Removed (`- `) and added (`+ `) lines in order
+    pub diff: String,
+}
+
+/// Compare `baseline` and `detect` using the longest common subsequence of lines
+pub fn line_diff(baseline: &str, detect: &str) -> LineDiff {
+    let a: Vec<&str> = baseline.lines().take(MAX_DIFF_LINES).collect();
+    let b: Vec<&str> = detect.lines().take(MAX_DIFF_LINES).collect();
+    if a.is_empty() && b.is_empty() {
+        return LineDiff {
+            ratio: 0.0,
+            diff: String::new(),
+        };
+    }
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let width = b.len() + 1;
+    let mut lcs = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = if a[i] == b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if j == b.len()
+            || (i < a.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+        {
+            diff.push_str(&format!("- {}\n", a[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+ {}\n", b[j]));
+            j += 1;
+        }
+    }
+
+    let common = lcs[0] as usize;
+    let changed = (a.len() - common) + (b.len() - common);
+    LineDiff {
+        ratio: changed as f32 / (a.len() + b.len()) as f32,
+        diff,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff_ratio_and_output() {
+        let same = line_diff("a\nb\nc", "a\nb\nc");
+        assert_eq!(same.ratio, 0.0);
+        assert!(same.diff.is_empty());
+
+        let changed = line_diff("a\nb\nc\nd", "a\nx\nc\nd");
+        assert_eq!(changed.ratio, 0.25);
+        assert_eq!(changed.diff, "- b\n+ x\n");
+
+        assert_eq!(line_diff("", "only new").ratio, 1.0);
+        assert_eq!(line_diff("", "").ratio, 0.0);
+    }
+
+    #[test]
+    fn test_diff_request_defaults() {
+        let request: DiffRequest = serde_yaml::from_str("path: /item?id=1").unwrap();
+        assert_eq!(request.method, "GET");
+        assert!(request.headers.is_empty());
+        assert_eq!(request.body, None);
+    }
+}
@@ -0,0 +1,137 @@
+//! Latency baselines for time-based blind detection
+//!
+//! A `latency` matcher fires when a response is slower than the target's
+//! normal latency plus the delay the payload asks for (`SLEEP(5)`,
+//! `; sleep 5`). The baseline is the median time of a few plain requests,
+//! measured once per target and shared through the [`Context`].
+//!
+//! [`Context`]: crate::types::Context
+
+use dashmap::DashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+/// Plain requests whose median latency makes a target's baseline
+pub const DEFAULT_BASELINE_SAMPLES: usize = 5;
+
+/// Default slack subtracted from a `latency` matcher's `payload_delay`, in seconds
+pub const DEFAULT_TOLERANCE_SECS: f64 = 1.0;
+
+/// Default number of times a slow response is re-sent before it counts
+pub const DEFAULT_CONFIRMATIONS: u32 = 1;
+
+/// Median latency of each target, measured on first use
+#[derive(Debug)]
+pub struct LatencyBaselines {
+    samples: usize,
+    baselines: DashMap<String, Arc<OnceCell<Option<Duration>>>>,
+}
+
+impl LatencyBaselines {
+    /// Baselines taken from `samples` requests per target
+    pub fn new(samples: usize) -> Self {
+        Self {
+            samples: samples.max(1),
+            baselines: DashMap::new(),
+        }
+    }
+
+    /// Requests sent to measure a baseline
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Baseline for `target`, computed by `measure` the first time only
+    ///
+    /// `measure` returns the timings it managed to collect; `None` when there
+    /// are none.
+    pub async fn get_or_measure<F, Fut>(&self, target: &str, measure: F) -> Option<Duration>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Vec<Duration>>,
+    {
+        let cell = self
+            .baselines
+            .entry(target.to_string())
+            .or_default()
+            .clone();
+        *cell
+            .get_or_init(|| async {
+                let baseline = median(measure().await);
+                tracing::debug!("Latency baseline for {}: {:?}", target, baseline);
+                baseline
+            })
+            .await
+    }
+}
+
+impl Default for LatencyBaselines {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASELINE_SAMPLES)
+    }
+}
+
+/// Median of `samples` (mean of the middle two for an even count)
+pub fn median(mut samples: Vec<Duration>) -> Option<Duration> {
+    if samples.is_empty() {
+        return None;
+    }
+    samples.sort();
+    let mid = samples.len() / 2;
+    Some(if samples.len().is_multiple_of(2) {
+        (samples[mid - 1] + samples[mid]) / 2
+    } else {
+        samples[mid]
+    })
+}
+
+/// Slowest a response can be without counting as delayed:
+/// `baseline + payload_delay - tolerance` (never below the baseline)
+pub fn latency_threshold(baseline: Duration, payload_delay: f64, tolerance: f64) -> Duration {
+    let extra = Duration::try_from_secs_f64(payload_delay - tolerance).unwrap_or(Duration::ZERO);
+    baseline + extra
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_median_and_threshold() {
+        let ms = Duration::from_millis;
+        assert_eq!(median(vec![]), None);
+        assert_eq!(median(vec![ms(900), ms(100), ms(120)]), Some(ms(120)));
+        assert_eq!(
+            median(vec![ms(100), ms(140), ms(120), ms(5000)]),
+            Some(ms(130))
+        );
+
+        assert_eq!(latency_threshold(ms(200), 5.0, 1.0), ms(4200));
+        // A tolerance larger than the delay never drops below the baseline
+        assert_eq!(latency_threshold(ms(200), 1.0, 2.0), ms(200));
+    }
+
+    #[tokio::test]
+    async fn test_baseline_is_measured_once_per_target() {
+        let baselines = LatencyBaselines::default();
+        let measured = AtomicUsize::new(0);
+        let measure = || async {
+            measured.fetch_add(1, Ordering::SeqCst);
+            vec![Duration::from_millis(80); 3]
+        };
+
+        for _ in 0..3 {
+            let baseline = baselines.get_or_measure("http://a.example", measure).await;
+            assert_eq!(baseline, Some(Duration::from_millis(80)));
+        }
+        assert_eq!(measured.load(Ordering::SeqCst), 1);
+
+        let unreachable = baselines
+            .get_or_measure("http://b.example", || async { Vec::new() })
+            .await;
+        assert_eq!(unreachable, None);
+    }
+}
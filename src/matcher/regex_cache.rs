@@ -0,0 +1,136 @@
+//! Compiled regex cache
+//!
+//! Templates build their [`Matcher`](super::Matcher)s again for every request
+//! they send, so patterns are compiled through a process-wide LRU keyed by
+//! the pattern text instead of once per match.
+
+use parking_lot::Mutex;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Patterns kept by the global cache
+pub const DEFAULT_CAPACITY: usize = 4096;
+
+static GLOBAL: OnceLock<RegexCache> = OnceLock::new();
+
+/// The process-wide cache used by matchers
+pub fn global() -> &'static RegexCache {
+    GLOBAL.get_or_init(|| RegexCache::new(DEFAULT_CAPACITY))
+}
+
+/// Compile `pattern` through the global cache
+pub fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    global().get(pattern)
+}
+
+/// Least-recently-used cache of compiled regexes
+#[derive(Debug)]
+pub struct RegexCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// Compiled pattern and the tick it was last used at
+    compiled: HashMap<String, (Regex, u64)>,
+    tick: u64,
+    /// Lookups served without compiling
+    hits: u64,
+}
+
+impl RegexCache {
+    /// Cache holding at most `capacity` patterns (at least one)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Compiled `pattern`, compiling and caching it on first use
+    ///
+    /// Invalid patterns are not cached.
+    pub fn get(&self, pattern: &str) -> Result<Regex, regex::Error> {
+        {
+            let mut entries = self.entries.lock();
+            entries.tick += 1;
+            let tick = entries.tick;
+            if let Some((regex, used)) = entries.compiled.get_mut(pattern) {
+                *used = tick;
+                let regex = regex.clone();
+                entries.hits += 1;
+                return Ok(regex);
+            }
+        }
+
+        // Compile outside the lock; a racing thread compiling the same pattern is harmless
+        let regex = Regex::new(pattern)?;
+        let mut entries = self.entries.lock();
+        if entries.compiled.len() >= self.capacity && !entries.compiled.contains_key(pattern) {
+            if let Some(oldest) = entries
+                .compiled
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(pattern, _)| pattern.clone())
+            {
+                entries.compiled.remove(&oldest);
+            }
+        }
+        entries.tick += 1;
+        let tick = entries.tick;
+        entries
+            .compiled
+            .insert(pattern.to_string(), (regex.clone(), tick));
+        Ok(regex)
+    }
+
+    /// Number of cached patterns
+    pub fn len(&self) -> usize {
+        self.entries.lock().compiled.len()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of lookups answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.entries.lock().hits
+    }
+
+    /// Whether `pattern` is cached
+    pub fn contains(&self, pattern: &str) -> bool {
+        self.entries.lock().compiled.contains_key(pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_pattern_is_evicted() {
+        let cache = RegexCache::new(2);
+        assert!(cache.get(r"a+").unwrap().is_match("aaa"));
+        cache.get(r"b+").unwrap();
+        // Touch `a+` so `b+` is the oldest
+        cache.get(r"a+").unwrap();
+        cache.get(r"c+").unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains("a+"));
+        assert!(!cache.contains("b+"));
+        assert!(cache.contains("c+"));
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_not_cached() {
+        let cache = RegexCache::new(8);
+        assert!(cache.get("(unclosed").is_err());
+        assert!(cache.is_empty());
+    }
+}
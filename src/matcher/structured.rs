@@ -0,0 +1,891 @@
+//! Structured matchers for API responses
+//!
+//! `type: json` selects values from a JSON body with a JSONPath subset and
+//! `type: xpath` selects nodes from an XML or HTML body with an XPath subset.
+//! Each check then tests what its path selected:
+//!
+//! ```yaml
+//! matchers:
+//!   - type: json
+//!     condition: and
+//!     json:
+//!       - path: $.status
+//!         equals: ok
+//!       - path: $.users[*].roles[0]
+//!         equals: admin
+//!       - path: $..debug
+//!         exists: true
+//!   - type: xpath
+//!     xpath:
+//!       - path: //meta[@name='generator']/@content
+//!         contains: WordPress
+//! ```
+//!
+//! A check passes when at least one selected value meets all of its
+//! conditions (`equals`, `contains`, `gt`, `lt`); with none it passes when
+//! the path selects anything, and with `exists: false` when it selects
+//! nothing. A body that does not parse does not match.
+//!
+//! JSON paths support `.key`, `['key']`, `[n]` (negative from the end),
+//! `[*]` / `.*` and recursive `..key`. XPath supports `/` and `//` steps over
+//! element names or `*`, the predicates `[n]`, `[@attr]`, `[@attr='v']` and
+//! `[contains(@attr, 'v')]`, and a final `@attr` or `text()`. Element names
+//! compare case-insensitively, so HTML void elements and unclosed tags are
+//! tolerated.
+
+use super::MatchCondition;
+use crate::error::{Error, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// One path of a `json` or `xpath` matcher and the conditions on what it selects
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PathCheck {
+    /// JSONPath or XPath expression
+    pub path: String,
+    /// Whether the path must select something (`false`: must select nothing)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+    /// Value a selected value must equal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<Value>,
+    /// Text a selected value must contain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contains: Option<String>,
+    /// Number a selected value must exceed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gt: Option<f64>,
+    /// Number a selected value must stay below
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lt: Option<f64>,
+}
+
+/// Language of a structured matcher's paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// JSONPath over a JSON body
+    Json,
+    /// XPath over an XML or HTML body
+    Xpath,
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckResult {
+    /// Whether the check passed
+    pub passed: bool,
+    /// Selected values meeting the check's conditions
+    pub values: Vec<String>,
+}
+
+/// Fail on the first path that does not parse
+pub fn validate(syntax: Syntax, checks: &[PathCheck]) -> Result<()> {
+    for check in checks {
+        let parsed = match syntax {
+            Syntax::Json => JsonPath::parse(&check.path).map(|_| ()),
+            Syntax::Xpath => XPath::parse(&check.path).map(|_| ()),
+        };
+        parsed.map_err(|e| invalid(check, e))?;
+    }
+    Ok(())
+}
+
+fn invalid(check: &PathCheck, reason: String) -> Error {
+    Error::Matcher(format!("Invalid path '{}': {}", check.path, reason))
+}
+
+/// Run every check against `body`; `None` when the body does not parse
+pub fn evaluate(
+    syntax: Syntax,
+    checks: &[PathCheck],
+    body: &str,
+) -> Result<Option<Vec<CheckResult>>> {
+    let selections: Vec<Vec<Value>> = match syntax {
+        Syntax::Json => {
+            let Ok(root) = serde_json::from_str::<Value>(body) else {
+                return Ok(None);
+            };
+            checks
+                .iter()
+                .map(|check| {
+                    let path = JsonPath::parse(&check.path).map_err(|e| invalid(check, e))?;
+                    Ok(path.select(&root).into_iter().cloned().collect())
+                })
+                .collect::<Result<_>>()?
+        }
+        Syntax::Xpath => {
+            let Some(root) = parse_markup(body) else {
+                return Ok(None);
+            };
+            checks
+                .iter()
+                .map(|check| {
+                    let path = XPath::parse(&check.path).map_err(|e| invalid(check, e))?;
+                    Ok(path.select(&root).into_iter().map(Value::String).collect())
+                })
+                .collect::<Result<_>>()?
+        }
+    };
+
+    Ok(Some(
+        checks
+            .iter()
+            .zip(selections)
+            .map(|(check, selected)| check.apply(&selected))
+            .collect(),
+    ))
+}
+
+/// Whether the checks hold for `body` under `condition`
+pub fn matches(
+    syntax: Syntax,
+    checks: &[PathCheck],
+    condition: MatchCondition,
+    body: &str,
+) -> Result<bool> {
+    let Some(results) = evaluate(syntax, checks, body)? else {
+        return Ok(false);
+    };
+    Ok(match condition {
+        MatchCondition::And => !results.is_empty() && results.iter().all(|r| r.passed),
+        MatchCondition::Or => results.iter().any(|r| r.passed),
+    })
+}
+
+/// Values selected by the checks that passed
+pub fn extract(syntax: Syntax, checks: &[PathCheck], body: &str) -> Result<Vec<String>> {
+    Ok(evaluate(syntax, checks, body)?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|r| r.passed)
+        .flat_map(|r| r.values)
+        .collect())
+}
+
+impl PathCheck {
+    fn apply(&self, selected: &[Value]) -> CheckResult {
+        if self.exists == Some(false) {
+            return CheckResult {
+                passed: selected.is_empty(),
+                values: Vec::new(),
+            };
+        }
+        let values: Vec<String> = selected
+            .iter()
+            .filter(|value| self.accepts(value))
+            .map(text)
+            .collect();
+        CheckResult {
+            passed: !values.is_empty(),
+            values,
+        }
+    }
+
+    fn accepts(&self, value: &Value) -> bool {
+        let number = || match value {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        };
+        self.equals
+            .as_ref()
+            .is_none_or(|expected| value == expected || text(value) == text(expected))
+            && self
+                .contains
+                .as_ref()
+                .is_none_or(|needle| text(value).contains(needle.as_str()))
+            && self.gt.is_none_or(|gt| number().is_some_and(|n| n > gt))
+            && self.lt.is_none_or(|lt| number().is_some_and(|n| n < lt))
+    }
+}
+
+/// Strings as they are, anything else as JSON
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Key(String),
+    Index(i64),
+    All,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct JsonStep {
+    /// `..`: apply to the node and all of its descendants
+    recursive: bool,
+    selector: Selector,
+}
+
+/// Parsed JSONPath subset
+#[derive(Debug, Clone, PartialEq)]
+struct JsonPath(Vec<JsonStep>);
+
+impl JsonPath {
+    fn parse(path: &str) -> std::result::Result<Self, String> {
+        let path = path.trim();
+        let (mut rest, rooted) = match path.strip_prefix('$') {
+            Some(rest) => (rest, true),
+            None => (path, false),
+        };
+        let mut steps = Vec::new();
+        // A leading bare key reads as `$.key`
+        if !rooted && !rest.is_empty() && !rest.starts_with(['.', '[']) {
+            let (key, tail) = split_key(rest);
+            steps.push(JsonStep {
+                recursive: false,
+                selector: Selector::Key(key.to_string()),
+            });
+            rest = tail;
+        }
+
+        while !rest.is_empty() {
+            if let Some(tail) = rest.strip_prefix("..") {
+                let (selector, tail) = dotted(tail)?;
+                steps.push(JsonStep {
+                    recursive: true,
+                    selector,
+                });
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix('.') {
+                let (selector, tail) = dotted(tail)?;
+                steps.push(JsonStep {
+                    recursive: false,
+                    selector,
+                });
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix('[') {
+                let end = tail.find(']').ok_or("missing ']'")?;
+                steps.push(JsonStep {
+                    recursive: false,
+                    selector: bracketed(tail[..end].trim())?,
+                });
+                rest = &tail[end + 1..];
+            } else {
+                return Err(format!("unexpected '{}'", rest));
+            }
+        }
+        Ok(Self(steps))
+    }
+
+    fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![root];
+        for step in &self.0 {
+            let context: Vec<&Value> = if step.recursive {
+                current.into_iter().flat_map(descendants).collect()
+            } else {
+                current
+            };
+            current = context
+                .into_iter()
+                .flat_map(|value| children(value, &step.selector))
+                .collect();
+        }
+        current
+    }
+}
+
+/// Key after `.` or `..`: a name or `*`
+fn dotted(rest: &str) -> std::result::Result<(Selector, &str), String> {
+    let (key, tail) = split_key(rest);
+    match key {
+        "" => Err("expected a key after '.'".to_string()),
+        "*" => Ok((Selector::All, tail)),
+        key => Ok((Selector::Key(key.to_string()), tail)),
+    }
+}
+
+fn split_key(rest: &str) -> (&str, &str) {
+    let end = rest.find(['.', '[']).unwrap_or(rest.len());
+    rest.split_at(end)
+}
+
+fn bracketed(inner: &str) -> std::result::Result<Selector, String> {
+    if inner == "*" {
+        return Ok(Selector::All);
+    }
+    if let Some(key) = unquote(inner) {
+        return Ok(Selector::Key(key.to_string()));
+    }
+    inner
+        .parse()
+        .map(Selector::Index)
+        .map_err(|_| format!("expected an index, '*' or a quoted key in '[{}]'", inner))
+}
+
+fn children<'a>(value: &'a Value, selector: &Selector) -> Vec<&'a Value> {
+    match (selector, value) {
+        (Selector::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+        (Selector::Index(index), Value::Array(items)) => {
+            let index = if *index < 0 {
+                items.len() as i64 + index
+            } else {
+                *index
+            };
+            usize::try_from(index)
+                .ok()
+                .and_then(|i| items.get(i))
+                .into_iter()
+                .collect()
+        }
+        (Selector::All, Value::Array(items)) => items.iter().collect(),
+        (Selector::All, Value::Object(map)) => map.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// `value` and everything nested in it, in document order
+fn descendants(value: &Value) -> Vec<&Value> {
+    let mut all = vec![value];
+    let nested: Vec<&Value> = match value {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(map) => map.values().collect(),
+        _ => Vec::new(),
+    };
+    for child in nested {
+        all.extend(descendants(child));
+    }
+    all
+}
+
+fn unquote(text: &str) -> Option<&str> {
+    let text = text.trim();
+    ['\'', '"'].into_iter().find_map(|quote| {
+        text.strip_prefix(quote)
+            .and_then(|inner| inner.strip_suffix(quote))
+    })
+}
+
+/// Element of a parsed XML or HTML document
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+/// HTML elements that never have an end tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// HTML elements closed by the start of a sibling of the same name
+const IMPLIED_END_ELEMENTS: &[&str] = &["dd", "dt", "li", "option", "p", "td", "th", "tr"];
+
+/// Document node holding the top-level elements; `None` without any element
+///
+/// Markup that stops parsing partway keeps the elements read until then.
+fn parse_markup(body: &str) -> Option<Element> {
+    let mut reader = Reader::from_str(body);
+    reader.check_end_names(false);
+
+    // Open elements, the document node first
+    let mut stack = vec![Element::default()];
+    while let Ok(event) = reader.read_event() {
+        match event {
+            Event::Start(start) => {
+                let element = element(&start);
+                let implied_end = stack.len() > 1
+                    && IMPLIED_END_ELEMENTS.contains(&element.name.to_ascii_lowercase().as_str())
+                    && stack[stack.len() - 1]
+                        .name
+                        .eq_ignore_ascii_case(&element.name);
+                if implied_end {
+                    close(&mut stack);
+                }
+                if VOID_ELEMENTS.contains(&element.name.to_ascii_lowercase().as_str()) {
+                    push(&mut stack, Node::Element(element));
+                } else {
+                    stack.push(element);
+                }
+            }
+            Event::Empty(start) => push(&mut stack, Node::Element(element(&start))),
+            Event::End(end) => {
+                let name = String::from_utf8_lossy(end.name().as_ref()).to_string();
+                // Close up to the matching open element; a stray end tag is ignored
+                if let Some(open) = stack[1..]
+                    .iter()
+                    .rposition(|e| e.name.eq_ignore_ascii_case(&name))
+                {
+                    while stack.len() > open + 1 {
+                        close(&mut stack);
+                    }
+                }
+            }
+            Event::Text(content) => {
+                let content = content
+                    .unescape()
+                    .map(|text| text.into_owned())
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&content).to_string());
+                push(&mut stack, Node::Text(content));
+            }
+            Event::CData(content) => {
+                push(
+                    &mut stack,
+                    Node::Text(String::from_utf8_lossy(&content).to_string()),
+                );
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    while stack.len() > 1 {
+        close(&mut stack);
+    }
+
+    let document = stack.pop()?;
+    document
+        .children
+        .iter()
+        .any(|node| matches!(node, Node::Element(_)))
+        .then_some(document)
+}
+
+fn element(start: &BytesStart<'_>) -> Element {
+    Element {
+        name: String::from_utf8_lossy(start.name().as_ref()).to_string(),
+        attributes: start
+            .html_attributes()
+            .with_checks(false)
+            .flatten()
+            .map(|attr| {
+                let value = attr
+                    .unescape_value()
+                    .map(|value| value.into_owned())
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).to_string());
+                (
+                    String::from_utf8_lossy(attr.key.as_ref()).to_string(),
+                    value,
+                )
+            })
+            .collect(),
+        children: Vec::new(),
+    }
+}
+
+fn push(stack: &mut [Element], node: Node) {
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(node);
+    }
+}
+
+fn close(stack: &mut Vec<Element>) {
+    if let Some(element) = stack.pop() {
+        push(stack, Node::Element(element));
+    }
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// This element and every element inside it, in document order
+    fn descendants_or_self(&self) -> Vec<&Element> {
+        let mut all = vec![self];
+        for child in self.elements() {
+            all.extend(child.descendants_or_self());
+        }
+        all
+    }
+
+    /// Text directly inside this element
+    fn own_text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|node| match node {
+                Node::Text(text) => Some(text.as_str()),
+                Node::Element(_) => None,
+            })
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+
+    /// All text inside this element
+    fn text_content(&self) -> String {
+        fn collect(element: &Element, text: &mut String) {
+            for node in &element.children {
+                match node {
+                    Node::Text(t) => text.push_str(t),
+                    Node::Element(child) => collect(child, text),
+                }
+            }
+        }
+        let mut text = String::new();
+        collect(self, &mut text);
+        text.trim().to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Predicate {
+    Position(usize),
+    HasAttribute(String),
+    AttributeEquals(String, String),
+    AttributeContains(String, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct XStep {
+    /// Reached through `//` rather than `/`
+    descendant: bool,
+    /// Element name, or `*`
+    name: String,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Output {
+    /// Text content of the selected elements
+    Element,
+    /// Text directly inside them (`text()`)
+    Text,
+    /// One attribute of them (`@name`)
+    Attribute(String),
+}
+
+/// Parsed XPath subset
+#[derive(Debug, Clone, PartialEq)]
+struct XPath {
+    steps: Vec<XStep>,
+    output: Output,
+    /// The output step was reached through `//`
+    output_descendant: bool,
+}
+
+impl XPath {
+    fn parse(path: &str) -> std::result::Result<Self, String> {
+        let mut rest = path.trim();
+        if !rest.starts_with('/') {
+            return Err("expected the path to start with '/' or '//'".to_string());
+        }
+
+        let mut steps = Vec::new();
+        while !rest.is_empty() {
+            let tail = rest
+                .strip_prefix('/')
+                .ok_or_else(|| format!("unexpected '{}'", rest))?;
+            let (descendant, tail) = match tail.strip_prefix('/') {
+                Some(tail) => (true, tail),
+                None => (false, tail),
+            };
+
+            if let Some(attribute) = tail.strip_prefix('@') {
+                return Self::finish(steps, Output::Attribute(attribute.to_string()), descendant);
+            }
+            if tail == "text()" {
+                return Self::finish(steps, Output::Text, descendant);
+            }
+
+            let end = tail.find(['/', '[']).unwrap_or(tail.len());
+            let name = &tail[..end];
+            if name.is_empty() {
+                return Err("expected an element name".to_string());
+            }
+            rest = &tail[end..];
+
+            let mut predicates = Vec::new();
+            while let Some(inner) = rest.strip_prefix('[') {
+                let close = closing_bracket(inner).ok_or("missing ']'")?;
+                predicates.push(predicate(inner[..close].trim())?);
+                rest = &inner[close + 1..];
+            }
+            steps.push(XStep {
+                descendant,
+                name: name.to_string(),
+                predicates,
+            });
+        }
+        Self::finish(steps, Output::Element, false)
+    }
+
+    fn finish(
+        steps: Vec<XStep>,
+        output: Output,
+        output_descendant: bool,
+    ) -> std::result::Result<Self, String> {
+        if let Output::Attribute(name) = &output {
+            if name.is_empty() || name.contains(['/', '[', ']']) {
+                return Err(format!("invalid attribute '@{}'", name));
+            }
+        }
+        if steps.is_empty() && output == Output::Element {
+            return Err("expected an element name".to_string());
+        }
+        Ok(Self {
+            steps,
+            output,
+            output_descendant,
+        })
+    }
+
+    fn select(&self, document: &Element) -> Vec<String> {
+        let mut current = vec![document];
+        for step in &self.steps {
+            let parents: Vec<&Element> = if step.descendant {
+                current
+                    .into_iter()
+                    .flat_map(Element::descendants_or_self)
+                    .collect()
+            } else {
+                current
+            };
+            let mut selected: Vec<&Element> = Vec::new();
+            for parent in parents {
+                let matching: Vec<&Element> = parent
+                    .elements()
+                    .filter(|e| step.name == "*" || e.name.eq_ignore_ascii_case(&step.name))
+                    .collect();
+                for element in step.filter(matching) {
+                    // Nested matches of `//` can reach an element twice
+                    if !selected.iter().any(|seen| std::ptr::eq(*seen, element)) {
+                        selected.push(element);
+                    }
+                }
+            }
+            current = selected;
+        }
+
+        if self.output_descendant {
+            current = current
+                .into_iter()
+                .flat_map(Element::descendants_or_self)
+                .collect();
+        }
+        current
+            .into_iter()
+            .filter_map(|element| match &self.output {
+                Output::Element => Some(element.text_content()),
+                Output::Text => Some(element.own_text()),
+                Output::Attribute(name) => element.attribute(name).map(str::to_string),
+            })
+            .collect()
+    }
+}
+
+impl XStep {
+    fn filter<'a>(&self, mut elements: Vec<&'a Element>) -> Vec<&'a Element> {
+        for predicate in &self.predicates {
+            elements = match predicate {
+                Predicate::Position(n) => elements.get(n - 1).copied().into_iter().collect(),
+                Predicate::HasAttribute(name) => elements
+                    .into_iter()
+                    .filter(|e| e.attribute(name).is_some())
+                    .collect(),
+                Predicate::AttributeEquals(name, value) => elements
+                    .into_iter()
+                    .filter(|e| e.attribute(name) == Some(value.as_str()))
+                    .collect(),
+                Predicate::AttributeContains(name, value) => elements
+                    .into_iter()
+                    .filter(|e| {
+                        e.attribute(name)
+                            .is_some_and(|v| v.contains(value.as_str()))
+                    })
+                    .collect(),
+            };
+        }
+        elements
+    }
+}
+
+/// Position of the `]` closing a predicate, skipping quoted text
+fn closing_bracket(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ']') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn predicate(text: &str) -> std::result::Result<Predicate, String> {
+    if let Ok(n) = text.parse::<usize>() {
+        return match n {
+            0 => Err("positions start at 1".to_string()),
+            n => Ok(Predicate::Position(n)),
+        };
+    }
+    if let Some(args) = text
+        .strip_prefix("contains(")
+        .and_then(|t| t.strip_suffix(')'))
+    {
+        let (attribute, value) = args
+            .split_once(',')
+            .ok_or("expected contains(@attr, 'value')")?;
+        let attribute = attribute
+            .trim()
+            .strip_prefix('@')
+            .ok_or("expected contains(@attr, 'value')")?;
+        let value = unquote(value).ok_or("expected a quoted value")?;
+        return Ok(Predicate::AttributeContains(
+            attribute.to_string(),
+            value.to_string(),
+        ));
+    }
+    if let Some(attribute) = text.strip_prefix('@') {
+        return match attribute.split_once('=') {
+            Some((name, value)) => Ok(Predicate::AttributeEquals(
+                name.trim().to_string(),
+                unquote(value).ok_or("expected a quoted value")?.to_string(),
+            )),
+            None => Ok(Predicate::HasAttribute(attribute.trim().to_string())),
+        };
+    }
+    Err(format!("unsupported predicate '[{}]'", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(path: &str) -> PathCheck {
+        PathCheck {
+            path: path.to_string(),
+            exists: None,
+            equals: None,
+            contains: None,
+            gt: None,
+            lt: None,
+        }
+    }
+
+    #[test]
+    fn test_json_paths() {
+        let body: Value = serde_json::from_str(
+            r#"{"data": {"users": [{"name": "ann", "roles": ["admin"]},
+                                    {"name": "bob", "roles": ["user", "ops"]}]},
+                "meta": {"total": 2, "debug": {"enabled": true}}}"#,
+        )
+        .unwrap();
+        let select = |path: &str| -> Vec<String> {
+            JsonPath::parse(path)
+                .unwrap()
+                .select(&body)
+                .into_iter()
+                .map(text)
+                .collect()
+        };
+
+        assert_eq!(select("$.data.users[*].name"), ["ann", "bob"]);
+        assert_eq!(select("$.data.users[-1].roles[1]"), ["ops"]);
+        assert_eq!(select("$['meta'][\"total\"]"), ["2"]);
+        assert_eq!(select("meta.debug.enabled"), ["true"]);
+        assert_eq!(select("$..name"), ["ann", "bob"]);
+        assert_eq!(select("$..enabled"), ["true"]);
+        assert!(select("$.data.users[5]").is_empty());
+        assert!(select("$.meta.total.deeper").is_empty());
+
+        for bad in ["$.", "$[1", "$[x]", "$.a..", "$ .a", "$a"] {
+            assert!(JsonPath::parse(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_xpath_on_loose_html() {
+        let html = r#"<!DOCTYPE html>
+            <html><head>
+              <meta name="generator" content="WordPress 6.4.2">
+              <link rel="stylesheet" href="/style.css">
+            </head><body>
+              <ul id="nav"><li><a href="/">Home</a><li><a href="/admin" class="x admin">Admin</a></ul>
+              <p>Version <b>2.1</b> &amp; up<br>
+            </body></html>"#;
+        let root = parse_markup(html).unwrap();
+        let select = |path: &str| XPath::parse(path).unwrap().select(&root);
+
+        assert_eq!(
+            select("//meta[@name='generator']/@content"),
+            ["WordPress 6.4.2"]
+        );
+        assert_eq!(select("//ul[@id=\"nav\"]/li/a/@href"), ["/", "/admin"]);
+        assert_eq!(select("//li[2]/a"), ["Admin"]);
+        assert_eq!(select("//a[contains(@class, 'admin')]/@href"), ["/admin"]);
+        assert_eq!(select("/html/body/p"), ["Version 2.1 & up"]);
+        assert_eq!(select("//p/text()"), ["Version  & up"]);
+        assert_eq!(select("//*[@href]/@href").len(), 3);
+        assert_eq!(select("//@rel"), ["stylesheet"]);
+
+        for bad in ["meta", "//", "//a[", "//a[0]", "//a[@x=y]", "//a/@"] {
+            assert!(XPath::parse(bad).is_err(), "{:?}", bad);
+        }
+        assert!(parse_markup("plain text, no markup").is_none());
+    }
+
+    #[test]
+    fn test_checks_and_conditions() {
+        let body = r#"{"status": "ok", "count": 12, "items": [{"id": "7"}, {"id": 40}]}"#;
+        let status = PathCheck {
+            equals: Some(Value::from("ok")),
+            ..check("$.status")
+        };
+        let many = PathCheck {
+            gt: Some(10.0),
+            lt: Some(100.0),
+            ..check("$.count")
+        };
+        let big_ids = PathCheck {
+            gt: Some(5.0),
+            ..check("$.items[*].id")
+        };
+        let no_error = PathCheck {
+            exists: Some(false),
+            ..check("$.error")
+        };
+
+        let results = evaluate(
+            Syntax::Json,
+            &[status.clone(), many, big_ids.clone(), no_error],
+            body,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(results.iter().all(|r| r.passed));
+        // Numeric conditions read numbers out of strings too
+        assert_eq!(results[2].values, ["7", "40"]);
+
+        let wrong = PathCheck {
+            equals: Some(Value::from("error")),
+            ..check("$.status")
+        };
+        let checks = [status, wrong];
+        assert!(!matches(Syntax::Json, &checks, MatchCondition::And, body).unwrap());
+        assert!(matches(Syntax::Json, &checks, MatchCondition::Or, body).unwrap());
+        assert_eq!(extract(Syntax::Json, &checks, body).unwrap(), ["ok"]);
+
+        // Bodies that do not parse do not match; bad paths are errors
+        assert!(!matches(Syntax::Json, &checks, MatchCondition::Or, "<html>").unwrap());
+        assert!(!matches(Syntax::Xpath, &[check("//a")], MatchCondition::Or, "{}").unwrap());
+        assert!(validate(Syntax::Json, &[check("$[")]).is_err());
+        assert!(matches(Syntax::Xpath, &[check("a")], MatchCondition::Or, "<a/>").is_err());
+    }
+}
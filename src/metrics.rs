@@ -1,5 +1,11 @@
 //! Metrics collection and instrumentation
 
+pub mod profiler;
+pub mod requests;
+
+pub use profiler::{Phase, TemplateProfiler};
+pub use requests::{RequestCounter, RequestLimits};
+
 use crate::error::Result;
 use prometheus::{Counter, CounterVec, Gauge, Histogram, HistogramVec, Opts, Registry};
 use std::sync::Arc;
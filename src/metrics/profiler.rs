@@ -0,0 +1,437 @@
+//! Per-template execution profiling (`cxg scan --profile-templates`)
+//!
+//! Each execution runs in a task-local scope that engines, the network
+//! client and the matchers mark as they go: compilation and matcher
+//! evaluation are timed with [`enter`] guards, and [`mark_request_sent`] /
+//! [`mark_response_received`] bound the network window. Outside a scope,
+//! and whenever profiling is off, the marks are a task-local lookup and
+//! nothing else.
+//!
+//! Only [`Instant`] is used, so profiling adds no syscalls. Network time is
+//! only seen for requests made through the shared network client; templates
+//! running as child processes report it as `other`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Global profiler, set when `--profile-templates` is given
+static PROFILER: OnceLock<TemplateProfiler> = OnceLock::new();
+
+tokio::task_local! {
+    static CURRENT: Arc<Recorder>;
+}
+
+/// Turn profiling on for this process
+pub fn init_profiler() -> &'static TemplateProfiler {
+    PROFILER.get_or_init(TemplateProfiler::new)
+}
+
+/// The profiler, if profiling is on
+pub fn get_profiler() -> Option<&'static TemplateProfiler> {
+    PROFILER.get()
+}
+
+/// Where an execution's time went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Parsing the template file
+    Load,
+    /// Compiling the template (compiled engines)
+    Compile,
+    /// From the first request sent to the last response received
+    Network,
+    /// Evaluating matchers
+    Matching,
+    /// Everything else (process start-up, script execution, waiting)
+    Other,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Phase::Load => write!(f, "load"),
+            Phase::Compile => write!(f, "compile"),
+            Phase::Network => write!(f, "network"),
+            Phase::Matching => write!(f, "matching"),
+            Phase::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// Times one `Compile` or `Matching` interval of the current execution until dropped
+#[derive(Debug)]
+#[must_use = "the phase ends when the guard is dropped"]
+pub struct PhaseGuard {
+    phase: Phase,
+    started: Option<Instant>,
+}
+
+impl Drop for PhaseGuard {
+    fn drop(&mut self) {
+        let Some(started) = self.started else {
+            return;
+        };
+        let ended = Instant::now();
+        let _ = CURRENT.try_with(|recorder| {
+            let mut marks = recorder.marks.lock().unwrap();
+            match self.phase {
+                Phase::Compile => marks.compile += ended - started,
+                Phase::Matching => marks.matching.push((started, ended)),
+                _ => {}
+            }
+        });
+    }
+}
+
+/// Start timing `phase` for the execution running on this task
+pub fn enter(phase: Phase) -> PhaseGuard {
+    let started = CURRENT.try_with(|_| Instant::now()).ok();
+    PhaseGuard { phase, started }
+}
+
+/// A request is about to go out
+pub fn mark_request_sent() {
+    let _ = CURRENT.try_with(|recorder| {
+        let now = Instant::now();
+        let mut marks = recorder.marks.lock().unwrap();
+        marks.first_request.get_or_insert(now);
+        marks.network_end = Some(now);
+    });
+}
+
+/// A response has arrived
+pub fn mark_response_received() {
+    let _ = CURRENT.try_with(|recorder| {
+        recorder.marks.lock().unwrap().network_end = Some(Instant::now());
+    });
+}
+
+/// The execution profiled on this task, for carrying onto threads it hands work to
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionScope(Option<Arc<Recorder>>);
+
+impl ExecutionScope {
+    /// Run `future` with its marks attributed to this execution
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        match self.0 {
+            Some(recorder) => CURRENT.scope(recorder, future).await,
+            None => future.await,
+        }
+    }
+}
+
+/// Scope of the execution running on this task (empty when none is profiled)
+pub fn current_scope() -> ExecutionScope {
+    ExecutionScope(CURRENT.try_with(Arc::clone).ok())
+}
+
+/// Marks of one execution
+#[derive(Debug)]
+struct Recorder {
+    started: Instant,
+    marks: Mutex<Marks>,
+}
+
+#[derive(Debug, Default)]
+struct Marks {
+    compile: Duration,
+    matching: Vec<(Instant, Instant)>,
+    first_request: Option<Instant>,
+    network_end: Option<Instant>,
+}
+
+impl Recorder {
+    /// Exclusive phase times of an execution that ended at `finished`
+    ///
+    /// Matcher time inside the network window is taken out of `network`, and
+    /// each phase is capped by what the earlier ones left of the total, so
+    /// the phases never add up to more than the execution took.
+    fn breakdown(&self, finished: Instant) -> PhaseTimes {
+        let marks = self.marks.lock().unwrap();
+        let total = finished.saturating_duration_since(self.started);
+
+        let matching: Duration = marks.matching.iter().map(|(a, b)| *b - *a).sum();
+        let network = match (marks.first_request, marks.network_end) {
+            (Some(first), Some(last)) => {
+                let overlap: Duration = marks
+                    .matching
+                    .iter()
+                    .map(|(a, b)| (*b).min(last).saturating_duration_since((*a).max(first)))
+                    .sum();
+                last.saturating_duration_since(first)
+                    .saturating_sub(overlap)
+            }
+            _ => Duration::ZERO,
+        };
+
+        let compile = marks.compile.min(total);
+        let matching = matching.min(total - compile);
+        let network = network.min(total - compile - matching);
+        PhaseTimes {
+            load: Duration::ZERO,
+            compile,
+            network,
+            matching,
+            other: total - compile - matching - network,
+        }
+    }
+}
+
+/// Time spent in each phase
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimes {
+    /// Template load
+    pub load: Duration,
+    /// Compilation
+    pub compile: Duration,
+    /// Network window, less matcher time inside it
+    pub network: Duration,
+    /// Matcher evaluation
+    pub matching: Duration,
+    /// Remainder of the execution
+    pub other: Duration,
+}
+
+impl PhaseTimes {
+    /// Time of one phase
+    pub fn get(&self, phase: Phase) -> Duration {
+        match phase {
+            Phase::Load => self.load,
+            Phase::Compile => self.compile,
+            Phase::Network => self.network,
+            Phase::Matching => self.matching,
+            Phase::Other => self.other,
+        }
+    }
+
+    /// Sum of every phase
+    pub fn total(&self) -> Duration {
+        self.load + self.compile + self.network + self.matching + self.other
+    }
+
+    fn add(&mut self, other: &PhaseTimes) {
+        self.load += other.load;
+        self.compile += other.compile;
+        self.network += other.network;
+        self.matching += other.matching;
+        self.other += other.other;
+    }
+}
+
+/// Profile of one template over the scan
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateProfile {
+    /// Template ID
+    pub template_id: String,
+    /// Executions profiled (one per target)
+    pub executions: usize,
+    /// Wall time of those executions, plus load and up-front compilation
+    pub total: Duration,
+    /// Breakdown of `total`
+    pub phases: PhaseTimes,
+}
+
+/// Collects per-template phase times during a scan
+#[derive(Debug, Default)]
+pub struct TemplateProfiler {
+    templates: Mutex<HashMap<String, TemplateProfile>>,
+}
+
+impl TemplateProfiler {
+    /// Empty profiler
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record how long loading a template took
+    pub fn record_load(&self, template_id: &str, elapsed: Duration) {
+        self.update(template_id, |profile| {
+            profile.phases.load += elapsed;
+            profile.total += elapsed;
+        });
+    }
+
+    /// Run `future`, which compiles the template ahead of the scan, timing it as compilation
+    pub async fn prepare<F: Future>(&self, template_id: &str, future: F) -> F::Output {
+        let started = Instant::now();
+        let output = future.await;
+        let elapsed = started.elapsed();
+        self.update(template_id, |profile| {
+            profile.phases.compile += elapsed;
+            profile.total += elapsed;
+        });
+        output
+    }
+
+    /// Run one execution of a template, attributing the marks made during it
+    ///
+    /// The execution ends, and its findings count as emitted, when `future` completes.
+    pub async fn execute<F: Future>(&self, template_id: &str, future: F) -> F::Output {
+        let recorder = Arc::new(Recorder {
+            started: Instant::now(),
+            marks: Mutex::new(Marks::default()),
+        });
+        let output = CURRENT.scope(recorder.clone(), future).await;
+        let finished = Instant::now();
+
+        let phases = recorder.breakdown(finished);
+        let total = finished.saturating_duration_since(recorder.started);
+        self.update(template_id, |profile| {
+            profile.executions += 1;
+            profile.total += total;
+            profile.phases.add(&phases);
+        });
+        output
+    }
+
+    /// The `n` templates that took longest overall
+    pub fn slowest(&self, n: usize) -> Vec<TemplateProfile> {
+        let mut profiles: Vec<TemplateProfile> =
+            self.templates.lock().unwrap().values().cloned().collect();
+        profiles.sort_by(|a, b| {
+            b.total
+                .cmp(&a.total)
+                .then_with(|| a.template_id.cmp(&b.template_id))
+        });
+        profiles.truncate(n);
+        profiles
+    }
+
+    /// Table of the `n` slowest templates with their phase breakdown
+    pub fn render_table(&self, n: usize) -> String {
+        let profiles = self.slowest(n);
+        if profiles.is_empty() {
+            return "No template executions were profiled\n".to_string();
+        }
+
+        let phases = [
+            Phase::Load,
+            Phase::Compile,
+            Phase::Network,
+            Phase::Matching,
+            Phase::Other,
+        ];
+        let width = profiles
+            .iter()
+            .map(|p| p.template_id.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("Template".len());
+
+        let mut output = format!("{:<width$} {:>5} {:>9}", "Template", "Runs", "Total");
+        for phase in phases {
+            output.push_str(&format!(" {:>9}", phase.to_string()));
+        }
+        output.push('\n');
+        for profile in &profiles {
+            output.push_str(&format!(
+                "{:<width$} {:>5} {:>9}",
+                profile.template_id,
+                profile.executions,
+                format_duration(profile.total)
+            ));
+            for phase in phases {
+                output.push_str(&format!(
+                    " {:>9}",
+                    format_duration(profile.phases.get(phase))
+                ));
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    fn update(&self, template_id: &str, f: impl FnOnce(&mut TemplateProfile)) {
+        let mut templates = self.templates.lock().unwrap();
+        let profile = templates
+            .entry(template_id.to_string())
+            .or_insert_with(|| TemplateProfile {
+                template_id: template_id.to_string(),
+                ..Default::default()
+            });
+        f(profile);
+    }
+}
+
+/// `1.234s` or `56.7ms`
+fn format_duration(duration: Duration) -> String {
+    if duration >= Duration::from_secs(1) {
+        format!("{:.3}s", duration.as_secs_f64())
+    } else {
+        format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn simulated_execution() {
+        {
+            let _compiling = enter(Phase::Compile);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        mark_request_sent();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        mark_response_received();
+        {
+            let _matching = enter(Phase::Matching);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        // A second request whose window includes the matcher run above
+        mark_request_sent();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        mark_response_received();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    #[tokio::test]
+    async fn test_phase_durations_do_not_exceed_total() {
+        let profiler = TemplateProfiler::new();
+        profiler.record_load("slow", Duration::from_millis(3));
+        profiler.execute("slow", simulated_execution()).await;
+        profiler.execute("slow", simulated_execution()).await;
+        profiler.execute("fast", async {}).await;
+
+        let slowest = profiler.slowest(10);
+        assert_eq!(slowest.len(), 2);
+        let slow = &slowest[0];
+        assert_eq!(slow.template_id, "slow");
+        assert_eq!(slow.executions, 2);
+        assert!(slow.phases.total() <= slow.total);
+        assert!(slow.phases.compile >= Duration::from_millis(40));
+        assert!(slow.phases.network >= Duration::from_millis(60));
+        assert!(slow.phases.matching >= Duration::from_millis(10));
+        assert_eq!(slow.phases.load, Duration::from_millis(3));
+        assert!(slowest[1].phases.total() <= slowest[1].total);
+
+        let table = profiler.render_table(1);
+        assert!(table.starts_with("Template"));
+        assert!(table.contains("\nslow "));
+        assert!(!table.contains("fast"));
+    }
+
+    #[tokio::test]
+    async fn test_marks_outside_an_execution_are_ignored() {
+        let guard = enter(Phase::Matching);
+        assert!(guard.started.is_none());
+        drop(guard);
+        mark_request_sent();
+        mark_response_received();
+
+        let profiler = TemplateProfiler::new();
+        let value = profiler
+            .prepare("compiled", async {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                42
+            })
+            .await;
+        assert_eq!(value, 42);
+        let profile = &profiler.slowest(1)[0];
+        assert_eq!(profile.executions, 0);
+        assert_eq!(profile.phases.compile, profile.total);
+    }
+}
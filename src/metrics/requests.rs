@@ -0,0 +1,256 @@
+//! Request accounting and budgets for rules of engagement
+//!
+//! Every request the [`NetworkClient`] sends is charged to its host and to the
+//! scan total before it goes out. `--max-requests-per-host` and
+//! `--max-requests-total` cap those counts; a template's `max-requests`
+//! caps what one execution against one target may send. A request over any
+//! cap is refused with [`Error::RequestBudgetExceeded`] and not counted.
+//!
+//! The counters are atomics, so charging a request is a few atomic operations
+//! and a sharded map lookup.
+//!
+//! [`NetworkClient`]: crate::network::NetworkClient
+
+use crate::error::{Error, Result};
+use dashmap::DashMap;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+tokio::task_local! {
+    static CURRENT_BUDGET: Arc<TemplateBudget>;
+}
+
+/// Scan-wide request caps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestLimits {
+    /// Most requests to any one host
+    pub per_host: Option<u64>,
+    /// Most requests in total
+    pub total: Option<u64>,
+}
+
+/// Requests issued per host and in total
+#[derive(Debug, Default)]
+pub struct RequestCounter {
+    limits: RequestLimits,
+    total: AtomicU64,
+    hosts: DashMap<String, AtomicU64>,
+}
+
+impl RequestCounter {
+    /// Counter refusing requests beyond `limits`
+    pub fn new(limits: RequestLimits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    /// Caps in effect
+    pub fn limits(&self) -> RequestLimits {
+        self.limits
+    }
+
+    /// Count one request to `host`, or refuse it if a budget is spent
+    ///
+    /// The scan caps are checked first, then the budget of the template
+    /// execution running on this task (see [`with_template_budget`]).
+    pub fn charge(&self, host: &str) -> Result<()> {
+        if !reserve(&self.total, self.limits.total) {
+            return Err(Error::RequestBudgetExceeded(format!(
+                "{} requests sent in total (--max-requests-total)",
+                self.total.load(Ordering::Relaxed)
+            )));
+        }
+
+        // The read guard must be gone before `entry` takes the shard's write lock
+        let existing = self
+            .hosts
+            .get(host)
+            .map(|count| reserve(&count, self.limits.per_host));
+        let reserved = match existing {
+            Some(reserved) => reserved,
+            None => reserve(
+                &self.hosts.entry(host.to_string()).or_default(),
+                self.limits.per_host,
+            ),
+        };
+        if !reserved {
+            self.total.fetch_sub(1, Ordering::Relaxed);
+            return Err(Error::RequestBudgetExceeded(format!(
+                "{} requests sent to {} (--max-requests-per-host)",
+                self.issued_to(host),
+                host
+            )));
+        }
+
+        if let Err(e) = charge_template() {
+            self.total.fetch_sub(1, Ordering::Relaxed);
+            if let Some(count) = self.hosts.get(host) {
+                count.fetch_sub(1, Ordering::Relaxed);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Whether a scan cap leaves no requests for `host`
+    pub fn exhausted(&self, host: &str) -> bool {
+        let spent = |count: u64, limit: Option<u64>| limit.is_some_and(|limit| count >= limit);
+        spent(self.total(), self.limits.total) || spent(self.issued_to(host), self.limits.per_host)
+    }
+
+    /// Requests issued in total
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// Requests issued to `host`
+    pub fn issued_to(&self, host: &str) -> u64 {
+        self.hosts
+            .get(host)
+            .map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+
+    /// Requests issued to each host that was sent any
+    pub fn per_host(&self) -> BTreeMap<String, u64> {
+        self.hosts
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().load(Ordering::Relaxed)))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+}
+
+/// Take one request from `count` unless it has reached `limit`
+fn reserve(count: &AtomicU64, limit: Option<u64>) -> bool {
+    let previous = count.fetch_add(1, Ordering::Relaxed);
+    match limit {
+        Some(limit) if previous >= limit => {
+            count.fetch_sub(1, Ordering::Relaxed);
+            false
+        }
+        _ => true,
+    }
+}
+
+/// `max-requests` of one template execution
+#[derive(Debug)]
+pub struct TemplateBudget {
+    max: u64,
+    used: AtomicU64,
+}
+
+impl TemplateBudget {
+    /// Requests sent so far
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+}
+
+/// Run `future` with at most `max` requests (unlimited when `None`)
+pub async fn with_template_budget<F: Future>(max: Option<u64>, future: F) -> F::Output {
+    match max {
+        Some(max) => {
+            let budget = Arc::new(TemplateBudget {
+                max,
+                used: AtomicU64::new(0),
+            });
+            CURRENT_BUDGET.scope(budget, future).await
+        }
+        None => future.await,
+    }
+}
+
+/// Budget of the execution on this task, for carrying onto threads it hands work to
+#[derive(Debug, Clone, Default)]
+pub struct BudgetScope(Option<Arc<TemplateBudget>>);
+
+impl BudgetScope {
+    /// Run `future` charging its requests to this budget
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        match self.0 {
+            Some(budget) => CURRENT_BUDGET.scope(budget, future).await,
+            None => future.await,
+        }
+    }
+}
+
+/// Budget scope of the execution running on this task (empty when unlimited)
+pub fn current_budget() -> BudgetScope {
+    BudgetScope(CURRENT_BUDGET.try_with(Arc::clone).ok())
+}
+
+fn charge_template() -> Result<()> {
+    CURRENT_BUDGET
+        .try_with(|budget| {
+            if reserve(&budget.used, Some(budget.max)) {
+                Ok(())
+            } else {
+                Err(Error::RequestBudgetExceeded(format!(
+                    "template sent its max-requests of {}",
+                    budget.max
+                )))
+            }
+        })
+        .unwrap_or(Ok(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_caps_refuse_without_counting() {
+        let counter = RequestCounter::new(RequestLimits {
+            per_host: Some(2),
+            total: Some(3),
+        });
+        counter.charge("a.test").unwrap();
+        counter.charge("a.test").unwrap();
+        assert!(counter.exhausted("a.test"));
+        assert!(matches!(
+            counter.charge("a.test"),
+            Err(Error::RequestBudgetExceeded(_))
+        ));
+
+        counter.charge("b.test").unwrap();
+        assert!(counter.exhausted("c.test"));
+        assert!(counter.charge("c.test").is_err());
+
+        assert_eq!(counter.total(), 3);
+        let expected: BTreeMap<String, u64> =
+            [("a.test".to_string(), 2), ("b.test".to_string(), 1)].into();
+        assert_eq!(counter.per_host(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_template_budget_is_per_execution() {
+        let counter = RequestCounter::default();
+        let run = || {
+            with_template_budget(Some(2), async {
+                (0..3)
+                    .map(|_| counter.charge("a.test").is_ok())
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(run().await, [true, true, false]);
+        assert_eq!(run().await, [true, true, false]);
+        // Refused requests are not counted
+        assert_eq!(counter.issued_to("a.test"), 4);
+
+        // Budgets follow work handed to other tasks
+        let carried = with_template_budget(Some(1), async {
+            counter.charge("b.test").unwrap();
+            current_budget()
+        })
+        .await;
+        assert!(carried
+            .scope(async { counter.charge("b.test") })
+            .await
+            .is_err());
+        assert!(counter.charge("b.test").is_ok());
+    }
+}
@@ -2,29 +2,67 @@
 
 use crate::config::Config;
 use crate::error::{Error, Result};
+use crate::metrics::{profiler, RequestCounter, RequestLimits};
 use crate::session::SessionManager;
-use governor::{
-    clock::DefaultClock,
-    state::{InMemoryState, NotKeyed},
-    Quota, RateLimiter,
-};
 use reqwest::{Client, ClientBuilder, Response};
 use std::collections::HashMap;
-use std::num::NonZeroU32;
 use std::sync::Arc;
 use std::time::Duration;
 
-/// Type alias for the rate limiter used in NetworkClient
-type ClientRateLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock>;
+pub mod azure_blob;
+pub mod baseline;
+pub mod bucket;
+pub mod concurrency;
+pub mod content_sniff;
+pub mod decode;
+pub mod default_creds;
+pub mod gcs;
+pub mod grpc;
+pub mod host_header;
+pub mod path_traversal;
+pub mod probe_summary;
+pub mod proxy;
+pub mod rate_limit;
+pub mod raw_http;
+pub mod scheme_probe;
+pub mod snmp;
+pub mod ssh;
+pub mod trace;
+pub mod websocket;
+
+pub use azure_blob::AzureBlobChecker;
+pub use baseline::BaselineChecker;
+pub use bucket::{BucketExposure, StorageExposure};
+pub use concurrency::AdaptiveConcurrency;
+pub use content_sniff::ContentSniffingDetector;
+pub use decode::read_response;
+pub use default_creds::{Credential, CredentialDatabase, DefaultCredentialChecker, Service};
+pub use gcs::GcsBucketChecker;
+pub use grpc::GrpcReflectionProbe;
+pub use host_header::HostHeaderAttackDetector;
+pub use path_traversal::PathTraversalDetector;
+pub use probe_summary::ProbeRecorder;
+pub use proxy::connect_via_proxy;
+pub use rate_limit::RateLimiter;
+pub use raw_http::{RawConnection, RawPayload, ReadUntil, ResponseSegment};
+pub use scheme_probe::{settle_schemes, SchemeDetector, SettledSchemes};
+pub use snmp::SnmpProbe;
+pub use ssh::SshProbe;
+pub use trace::TraceWriter;
+pub use websocket::WebSocketClient;
 
 /// Network client for making HTTP/HTTPS requests
 #[derive(Debug)]
 pub struct NetworkClient {
     client: Client,
-    #[allow(dead_code)]
     config: Arc<Config>,
     session_manager: Arc<SessionManager>,
-    rate_limiter: Option<Arc<ClientRateLimiter>>,
+    rate_limiter: Arc<RateLimiter>,
+    baseline_checker: Option<Arc<BaselineChecker>>,
+    trace: Option<Arc<TraceWriter>>,
+    probes: Option<Arc<ProbeRecorder>>,
+    concurrency: Option<Arc<AdaptiveConcurrency>>,
+    requests: Arc<RequestCounter>,
 }
 
 impl NetworkClient {
@@ -42,7 +80,16 @@ impl NetworkClient {
             .timeout(Duration::from_secs(config.network.timeout_secs))
             .user_agent(&config.network.user_agent)
             .pool_max_idle_per_host(config.network.connection_pool_size)
-            .pool_idle_timeout(Duration::from_secs(30));
+            .pool_idle_timeout(Duration::from_secs(30))
+            // Bodies are decoded by `decode::read_response`, which keeps the
+            // original Content-Encoding header that reqwest would strip
+            .no_gzip()
+            .no_brotli()
+            .no_deflate()
+            .default_headers(reqwest::header::HeaderMap::from_iter([(
+                reqwest::header::ACCEPT_ENCODING,
+                reqwest::header::HeaderValue::from_static(decode::ACCEPT_ENCODING),
+            )]));
 
         // NOTE: We intentionally do NOT use .http2_prior_knowledge() here.
         // That setting forces HTTP/2 without negotiation, which breaks compatibility
@@ -60,9 +107,14 @@ impl NetworkClient {
             builder = builder.redirect(reqwest::redirect::Policy::none());
         }
 
+        // The TLS certificate is kept on responses for the probe summary
+        if config.network.probe_summary {
+            builder = builder.tls_info(true);
+        }
+
         // Configure proxy if specified
         if let Some(ref proxy_url) = config.network.proxy {
-            let proxy = reqwest::Proxy::all(proxy_url)
+            let proxy = reqwest::Proxy::all(proxy_url.expose())
                 .map_err(|e| Error::config(format!("Invalid proxy URL: {}", e)))?;
             builder = builder.proxy(proxy);
         }
@@ -71,34 +123,132 @@ impl NetworkClient {
             .build()
             .map_err(|e| Error::Network(format!("Failed to create HTTP client: {}", e)))?;
 
-        // Initialize rate limiter if configured
-        let rate_limiter = if let Some(rate_limit) = config.network.rate_limit {
-            if rate_limit > 0 {
-                // Create a quota that allows `rate_limit` requests per second
-                let quota = Quota::per_second(
-                    NonZeroU32::new(rate_limit).unwrap_or(NonZeroU32::new(1).unwrap()),
-                );
-                Some(Arc::new(RateLimiter::direct(quota)))
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        let rate_limiter = Arc::new(
+            RateLimiter::new(
+                config.network.rate_limit,
+                config.network.per_domain_rate_limit,
+            )
+            .with_jitter(config.network.jitter_factor),
+        );
+
+        let baseline_checker = config
+            .network
+            .baseline_threshold
+            .map(|threshold| Arc::new(BaselineChecker::new(threshold)));
+
+        let trace =
+            config.network.trace_requests.as_ref().map(|dir| {
+                Arc::new(TraceWriter::new(dir).with_redaction(config.network.trace_redact))
+            });
+
+        let probes = config
+            .network
+            .probe_summary
+            .then(|| Arc::new(ProbeRecorder::new()));
+
+        let concurrency = config
+            .execution
+            .adaptive_concurrency
+            .then(|| Arc::new(AdaptiveConcurrency::from_config(&config.execution)));
+
+        let requests = Arc::new(RequestCounter::new(RequestLimits {
+            per_host: config.network.max_requests_per_host,
+            total: config.network.max_requests_total,
+        }));
 
         Ok(Self {
             client,
             config,
             session_manager,
             rate_limiter,
+            baseline_checker,
+            trace,
+            probes,
+            concurrency,
+            requests,
         })
     }
 
+    /// A client sharing this one's connections, limits and recorders but
+    /// keeping cookies in `session_manager`, for requests made as another user
+    pub fn with_session_manager(&self, session_manager: Arc<SessionManager>) -> Self {
+        Self {
+            client: self.client.clone(),
+            config: self.config.clone(),
+            session_manager,
+            rate_limiter: self.rate_limiter.clone(),
+            baseline_checker: self.baseline_checker.clone(),
+            trace: self.trace.clone(),
+            probes: self.probes.clone(),
+            concurrency: self.concurrency.clone(),
+            requests: self.requests.clone(),
+        }
+    }
+
+    /// Write every request and response to `writer`
+    pub fn with_trace(mut self, writer: Arc<TraceWriter>) -> Self {
+        self.trace = Some(writer);
+        self
+    }
+
+    /// Trace export for requests, if enabled
+    pub fn trace_writer(&self) -> Option<&Arc<TraceWriter>> {
+        self.trace.as_ref()
+    }
+
+    /// Per-target liveness recorder, if the probe summary is enabled
+    pub fn probe_recorder(&self) -> Option<&Arc<ProbeRecorder>> {
+        self.probes.as_ref()
+    }
+
+    /// Limits tuned from request outcomes, if adaptive concurrency is enabled
+    pub fn concurrency(&self) -> Option<&Arc<AdaptiveConcurrency>> {
+        self.concurrency.as_ref()
+    }
+
+    /// Configuration the client was built from
+    pub fn config(&self) -> &Arc<Config> {
+        &self.config
+    }
+
     /// Get session manager
     pub fn session_manager(&self) -> &Arc<SessionManager> {
         &self.session_manager
     }
 
+    /// Rate limiter applied to every request
+    pub fn rate_limiter(&self) -> &Arc<RateLimiter> {
+        &self.rate_limiter
+    }
+
+    /// Baseline comparison for matches, unless disabled in the config
+    pub fn baseline_checker(&self) -> Option<&Arc<BaselineChecker>> {
+        self.baseline_checker.as_ref()
+    }
+
+    /// Requests issued so far and the budgets they count against
+    pub fn requests(&self) -> &Arc<RequestCounter> {
+        &self.requests
+    }
+
+    /// Charge a request to `domain` against the request budgets, then wait
+    /// for the rate limits that apply to it
+    pub(crate) async fn throttle(&self, domain: &str) -> Result<()> {
+        self.requests.charge(domain)?;
+        self.wait_for_rate_limit(domain).await;
+        profiler::mark_request_sent();
+        Ok(())
+    }
+
+    /// Wait for the rate limits that apply to `domain`, for requests sent
+    /// with [`NetworkClient::request`], which charges the budgets itself
+    pub(crate) async fn wait_for_rate_limit(&self, domain: &str) {
+        match rate_limit::current_template() {
+            Some(template_id) => self.rate_limiter.acquire_for(domain, &template_id).await,
+            None => self.rate_limiter.acquire(domain).await,
+        }
+    }
+
     /// Make a GET request
     pub async fn get(&self, url: &str) -> Result<Response> {
         self.get_with_headers(url, HashMap::new()).await
@@ -129,10 +279,8 @@ impl NetworkClient {
                 tokio::time::sleep(delay).await;
             }
 
-            // Apply rate limiting if configured
-            if let Some(ref limiter) = self.rate_limiter {
-                limiter.until_ready().await;
-            }
+            // Apply request budgets and global, per-domain and per-template rate limits
+            self.throttle(&domain).await?;
 
             let mut request = self.client.get(url);
 
@@ -151,7 +299,9 @@ impl NetworkClient {
                 request = request.header("Authorization", jwt_header);
             }
 
-            match request.send().await {
+            let sent = self.send(request).await;
+            profiler::mark_response_received();
+            match sent {
                 Ok(response) => {
                     // Check if response is retryable (5xx status codes)
                     let status = response.status();
@@ -235,10 +385,8 @@ impl NetworkClient {
                 tokio::time::sleep(delay).await;
             }
 
-            // Apply rate limiting if configured
-            if let Some(ref limiter) = self.rate_limiter {
-                limiter.until_ready().await;
-            }
+            // Apply request budgets and global, per-domain and per-template rate limits
+            self.throttle(&domain).await?;
 
             let mut request = self.client.post(url).body(body.clone());
 
@@ -257,7 +405,9 @@ impl NetworkClient {
                 request = request.header("Authorization", jwt_header);
             }
 
-            match request.send().await {
+            let sent = self.send(request).await;
+            profiler::mark_response_received();
+            match sent {
                 Ok(response) => {
                     // Check if response is retryable (5xx status codes)
                     let status = response.status();
@@ -327,10 +477,133 @@ impl NetworkClient {
 
     /// Make a custom request
     pub async fn request(&self, builder: reqwest::RequestBuilder) -> Result<Response> {
-        builder
-            .send()
-            .await
-            .map_err(|e| Error::Network(format!("Request failed: {}", e)))
+        let request = builder
+            .build()
+            .map_err(|e| Error::Network(format!("Request failed: {}", e)))?;
+        self.requests
+            .charge(&crate::utils::extract_domain(request.url().as_str()))?;
+        profiler::mark_request_sent();
+        let response = self.execute(request).await;
+        profiler::mark_response_received();
+        response.map_err(|e| Error::Network(format!("Request failed: {}", e)))
+    }
+
+    /// Build and send a request
+    async fn send(&self, builder: reqwest::RequestBuilder) -> reqwest::Result<Response> {
+        self.execute(builder.build()?).await
+    }
+
+    /// Send a request, counting how it ended for adaptive concurrency
+    async fn execute(&self, request: reqwest::Request) -> reqwest::Result<Response> {
+        let Some(controller) = &self.concurrency else {
+            return self.execute_observed(request).await;
+        };
+        let host = crate::utils::extract_domain(request.url().as_str());
+        let response = self.execute_observed(request).await;
+        let outcome = match &response {
+            Ok(response) => concurrency::RequestOutcome::of_status(response.status().as_u16()),
+            Err(_) => concurrency::RequestOutcome::Failed,
+        };
+        controller.record(&host, outcome);
+        response
+    }
+
+    /// Send a request, writing the exchange to the trace directory when tracing
+    /// and noting what it reveals about the target for the probe summary
+    async fn execute_observed(&self, request: reqwest::Request) -> reqwest::Result<Response> {
+        if self.trace.is_none() && self.probes.is_none() {
+            return self.client.execute(request).await;
+        }
+
+        let url = request.url().clone();
+        let entry = self.trace.as_ref().and_then(|trace| {
+            match trace.record_request(&self.trace_request(&request)) {
+                Ok(entry) => Some((trace, entry)),
+                Err(e) => {
+                    tracing::warn!("Failed to trace request to {}: {}", request.url(), e);
+                    None
+                }
+            }
+        });
+        let response = match self.client.execute(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                if let Some(probes) = &self.probes {
+                    probes.record_error(&url, &e);
+                }
+                if let Some((trace, entry)) = &entry {
+                    if let Err(write_error) = trace.record_error(entry, &e.to_string()) {
+                        tracing::warn!("Failed to trace response: {}", write_error);
+                    }
+                }
+                return Err(e);
+            }
+        };
+
+        let wants_title = match &self.probes {
+            Some(probes) => {
+                probes.record_response(&url, &response);
+                probes.wants_title(&url, response.headers())
+            }
+            None => false,
+        };
+        if entry.is_none() && !wants_title {
+            return Ok(response);
+        }
+
+        // The body is read here, then handed back in a rebuilt response
+        let status = response.status();
+        let version = response.version();
+        let final_url = response.url().clone();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        if wants_title {
+            if let Some(probes) = &self.probes {
+                probes.record_body(&url, &headers, &body);
+            }
+        }
+        if let Some((trace, entry)) = &entry {
+            let traced = trace::TraceResponse {
+                version: format!("{:?}", version),
+                status: status.as_u16(),
+                headers: header_pairs(&headers),
+                body: body.to_vec(),
+            };
+            if let Err(e) = trace.record_response(entry, &traced) {
+                tracing::warn!("Failed to trace response: {}", e);
+            }
+        }
+
+        let mut rebuilt = http::Response::new(body);
+        *rebuilt.status_mut() = status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = headers;
+        rebuilt.extensions_mut().insert(decode::FinalUrl(final_url));
+        Ok(Response::from(rebuilt))
+    }
+
+    /// Request as it goes on the wire, including the client's default headers
+    fn trace_request(&self, request: &reqwest::Request) -> trace::TraceRequest {
+        let mut headers = header_pairs(request.headers());
+        let defaults = [
+            ("user-agent", self.config.network.user_agent.as_str()),
+            ("accept-encoding", decode::ACCEPT_ENCODING),
+        ];
+        for (name, value) in defaults {
+            if !request.headers().contains_key(name) {
+                headers.push((name.to_string(), value.to_string()));
+            }
+        }
+        trace::TraceRequest {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers,
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(<[u8]>::to_vec)
+                .unwrap_or_default(),
+        }
     }
 
     /// Get the underlying client
@@ -339,6 +612,18 @@ impl NetworkClient {
     }
 }
 
+fn header_pairs(headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect()
+}
+
 /// Protocol handler trait
 #[async_trait::async_trait]
 pub trait ProtocolHandler: Send + Sync {
@@ -413,11 +698,7 @@ impl ProtocolHandler for HttpHandler {
 
         match self.client.get(&url).await {
             Ok(response) => {
-                let data = response
-                    .bytes()
-                    .await
-                    .map_err(|e| Error::Network(format!("Failed to read response: {}", e)))?
-                    .to_vec();
+                let data = read_response(response, start.elapsed()).await?.body;
 
                 Ok(ProtocolResponse {
                     data,
@@ -477,6 +758,175 @@ mod tests {
         assert!(client.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_requests_beyond_host_budget_are_not_sent() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let mut config = Config::default();
+        config.network.max_requests_per_host = Some(2);
+        let client = NetworkClient::new(Arc::new(config)).await.unwrap();
+        let url = format!("{}/", server.uri());
+
+        client.get(&url).await.unwrap();
+        client.request(client.client().get(&url)).await.unwrap();
+        assert!(matches!(
+            client.get(&url).await,
+            Err(Error::RequestBudgetExceeded(_))
+        ));
+        assert_eq!(client.requests().issued_to("127.0.0.1"), 2);
+        assert_eq!(client.requests().total(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_traced_exchange_keeps_response_readable() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Set-Cookie", "session=s3cr3t")
+                    .set_body_string("welcome"),
+            )
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.network.trace_requests = Some(dir.path().to_path_buf());
+        let client = NetworkClient::new(Arc::new(config)).await.unwrap();
+        let url = format!("{}/login", server.uri());
+        let headers = HashMap::from([("Authorization".to_string(), "Basic YWRtaW4=".to_string())]);
+
+        let response = trace::with_scan(
+            "scan-1",
+            rate_limit::with_template("login", async {
+                client
+                    .post_with_headers(&url, "user=admin".to_string(), headers)
+                    .await
+                    .unwrap()
+            }),
+        )
+        .await;
+        let response = read_response(response, Duration::ZERO).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body_string(), "welcome");
+        assert_eq!(response.url.as_deref(), Some(url.as_str()));
+
+        let port = reqwest::Url::parse(&server.uri()).unwrap().port().unwrap();
+        let traced = dir
+            .path()
+            .join("scan-1")
+            .join(format!("127.0.0.1_{}", port))
+            .join("login");
+        let request = std::fs::read_to_string(traced.join("0001.req.txt")).unwrap();
+        assert!(request.starts_with(&format!("POST {}\n", url)));
+        assert!(request.contains("authorization: [REDACTED]\n"));
+        assert!(request.contains("user-agent: cert-x-gen/"));
+        assert!(request.ends_with("\n\nuser=admin"));
+
+        let traced_response = std::fs::read_to_string(traced.join("0001.res.txt")).unwrap();
+        assert!(traced_response.starts_with("HTTP/1.1 200 OK\n"));
+        assert!(traced_response.contains("set-cookie: [REDACTED]\n"));
+        assert!(traced_response.ends_with("\n\nwelcome"));
+        assert!(!traced_response.contains("s3cr3t"));
+    }
+
+    #[tokio::test]
+    async fn test_throttled_responses_lower_adaptive_concurrency() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429))
+            .mount(&server)
+            .await;
+
+        let mut config = Config::default();
+        config.execution.adaptive_concurrency = true;
+        config.execution.parallel_targets = 4;
+        config.execution.parallel_templates = 4;
+        let client = NetworkClient::new(Arc::new(config)).await.unwrap();
+        let concurrency = client.concurrency().unwrap();
+        assert_eq!(concurrency.limit(), 16);
+
+        for _ in 0..concurrency::WINDOW {
+            client.get(&server.uri()).await.unwrap();
+        }
+        assert_eq!(concurrency.limit(), 8);
+        assert_eq!(concurrency.host_limit("127.0.0.1"), Some(2));
+        let timeline = concurrency.timeline();
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(timeline[1].error_rate, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_probe_summary_is_recorded_from_scan_requests() {
+        use crate::types::{Protocol, Target};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Server", "Apache/2.4.57")
+                    .set_body_raw("<html><title>Router Login</title></html>", "text/html"),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let closed_port = closed.local_addr().unwrap().port();
+        drop(closed);
+
+        let mut config = Config::default();
+        config.network.probe_summary = true;
+        let client = NetworkClient::new(Arc::new(config)).await.unwrap();
+        let response = client.get(&format!("{}/", server.uri())).await.unwrap();
+        let response = read_response(response, Duration::ZERO).await.unwrap();
+        assert!(response.body_string().contains("Router Login"));
+        client
+            .get(&format!("{}/missing", server.uri()))
+            .await
+            .unwrap();
+        assert!(client
+            .get(&format!("http://127.0.0.1:{}/", closed_port))
+            .await
+            .is_err());
+
+        let port = server.address().port();
+        let probes = client.probe_recorder().unwrap();
+        let live = probes.summarize(&Target::with_port("127.0.0.1", port, Protocol::Http));
+        assert!(live.reachable);
+        assert_eq!(live.lowest_status, Some(200));
+        assert_eq!(live.server.as_deref(), Some("Apache/2.4.57"));
+        assert_eq!(live.title.as_deref(), Some("Router Login"));
+        assert_eq!(live.tls_cn, None);
+        assert_eq!(live.error, None);
+
+        let down = probes.summarize(&Target::with_port("127.0.0.1", closed_port, Protocol::Http));
+        assert!(!down.reachable);
+        assert_eq!(down.lowest_status, None);
+        assert!(down.error.is_some());
+    }
+
     #[tokio::test]
     async fn test_dns_resolver() {
         let resolver = DnsResolver::new().await;
@@ -0,0 +1,326 @@
+//! Azure Blob Storage public container checks
+//!
+//! Probes a container of a storage account without credentials (see
+//! [`super::bucket`] for the levels):
+//!
+//! - read: `GET /<container>?restype=container&comp=list` returns
+//!   `EnumerationResults` (container-level public access)
+//! - write: `PUT /<container>/<probe object>` creates a block blob
+//! - full control: `GET /<container>?restype=container&comp=acl` returns
+//!   the container's `SignedIdentifiers`
+//!
+//! Accounts are reached at `https://<account>.blob.core.windows.net`. An
+//! account that does not resolve ends the check after one request.
+
+use super::bucket::{
+    probe_object_name, send_probe, xml_values, BucketExposure, StorageExposure, PROBE_OBJECT_BODY,
+    SAMPLE_OBJECTS,
+};
+use super::NetworkClient;
+use crate::error::Result;
+
+/// Default account endpoint; `{account}` is replaced by the account name
+pub const AZURE_BLOB_ENDPOINT: &str = "https://{account}.blob.core.windows.net";
+
+/// Storage service version sent with every request
+const API_VERSION: &str = "2021-08-06";
+
+/// Checks Azure Blob Storage containers for anonymous access
+#[derive(Debug, Clone)]
+pub struct AzureBlobChecker {
+    endpoint: String,
+    allow_write: bool,
+}
+
+impl Default for AzureBlobChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AzureBlobChecker {
+    /// Checker against `<account>.blob.core.windows.net`
+    pub fn new() -> Self {
+        Self {
+            endpoint: AZURE_BLOB_ENDPOINT.to_string(),
+            allow_write: true,
+        }
+    }
+
+    /// Use another account endpoint, with `{account}` standing for the
+    /// account name (e.g. Azurite's `http://127.0.0.1:10000/{account}`)
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Whether the write probe may upload a blob (default `true`)
+    pub fn with_write_probe(mut self, allow: bool) -> Self {
+        self.allow_write = allow;
+        self
+    }
+
+    /// Whether `name` is a valid storage account name
+    pub fn is_valid_account(name: &str) -> bool {
+        (3..=24).contains(&name.len())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    }
+
+    /// Whether `name` is a valid container name (`$root` and `$web` included)
+    pub fn is_valid_container(name: &str) -> bool {
+        if name == "$root" || name == "$web" {
+            return true;
+        }
+        (3..=63).contains(&name.len())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && name.ends_with(|c: char| c.is_ascii_alphanumeric())
+            && !name.contains("--")
+    }
+
+    /// URL of `container` in `account`
+    pub fn container_url(&self, account: &str, container: &str) -> String {
+        format!(
+            "{}/{}",
+            self.endpoint.replace("{account}", account),
+            container
+        )
+    }
+
+    /// The levels in `levels` that anonymous users have on `container`
+    pub async fn check(
+        &self,
+        client: &NetworkClient,
+        account: &str,
+        container: &str,
+        levels: &[StorageExposure],
+    ) -> Result<Vec<BucketExposure>> {
+        let resource = self.container_url(account, container);
+        let http = client.client();
+        let get = |url: &str| http.get(url).header("x-ms-version", API_VERSION);
+        let mut exposures = Vec::new();
+
+        // Sent even when read is not checked, to find out whether the account exists
+        let url = format!("{}?restype=container&comp=list", resource);
+        let Some((status, body)) = send_probe(client, get(&url), &url).await? else {
+            return Ok(exposures);
+        };
+        if body.contains("<Code>ContainerNotFound</Code>") {
+            tracing::debug!("Azure container {}/{} does not exist", account, container);
+            return Ok(exposures);
+        }
+        if levels.contains(&StorageExposure::PublicRead)
+            && status == 200
+            && body.contains("<EnumerationResults")
+        {
+            let mut exposure = BucketExposure::new(
+                StorageExposure::PublicRead,
+                &resource,
+                format!("GET {}", url),
+                status,
+                &body,
+            );
+            exposure.objects = xml_values(&body, "Name", SAMPLE_OBJECTS);
+            exposures.push(exposure);
+        }
+
+        if levels.contains(&StorageExposure::PublicWrite) {
+            if self.allow_write {
+                let url = format!("{}/{}", resource, probe_object_name());
+                let upload = http
+                    .put(&url)
+                    .header("x-ms-version", API_VERSION)
+                    .header("x-ms-blob-type", "BlockBlob")
+                    .header("Content-Type", "text/plain")
+                    .body(PROBE_OBJECT_BODY);
+                if let Some((status, body)) = send_probe(client, upload, &url).await? {
+                    if status == 201 {
+                        let mut exposure = BucketExposure::new(
+                            StorageExposure::PublicWrite,
+                            &resource,
+                            format!("PUT {}", url),
+                            status,
+                            &body,
+                        );
+                        let delete = http.delete(&url).header("x-ms-version", API_VERSION);
+                        let deleted = send_probe(client, delete, &url).await?;
+                        exposure.cleaned_up =
+                            Some(deleted.is_some_and(|(status, _)| status == 202));
+                        exposures.push(exposure);
+                    }
+                }
+            } else {
+                tracing::debug!(
+                    "Skipping write probe of Azure container {}/{}",
+                    account,
+                    container
+                );
+            }
+        }
+
+        if levels.contains(&StorageExposure::FullControl) {
+            let url = format!("{}?restype=container&comp=acl", resource);
+            if let Some((status, body)) = send_probe(client, get(&url), &url).await? {
+                if status == 200 && body.contains("<SignedIdentifiers") {
+                    exposures.push(BucketExposure::new(
+                        StorageExposure::FullControl,
+                        &resource,
+                        format!("GET {}", url),
+                        status,
+                        &body,
+                    ));
+                }
+            }
+        }
+
+        Ok(exposures)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use wiremock::matchers::{header, method, path, path_regex, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    pub(crate) const LISTING: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<EnumerationResults ServiceEndpoint="https://acme.blob.core.windows.net/" ContainerName="backups"><Blobs><Blob><Name>sql/prod-2024-03-01.bacpac</Name><Properties><Content-Length>52428800</Content-Length></Properties></Blob><Blob><Name>web.config</Name><Properties><Content-Length>2048</Content-Length></Properties></Blob></Blobs><NextMarker /></EnumerationResults>"#;
+
+    pub(crate) const ACL: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<SignedIdentifiers><SignedIdentifier><Id>full-access</Id><AccessPolicy><Permission>racwdl</Permission></AccessPolicy></SignedIdentifier></SignedIdentifiers>"#;
+
+    const RESOURCE_NOT_FOUND: &str = r#"<?xml version="1.0" encoding="utf-8"?><Error><Code>ResourceNotFound</Code><Message>The specified resource does not exist.</Message></Error>"#;
+
+    const CONTAINER_NOT_FOUND: &str = r#"<?xml version="1.0" encoding="utf-8"?><Error><Code>ContainerNotFound</Code><Message>The specified container does not exist.</Message></Error>"#;
+
+    const NO_AUTHENTICATION: &str = r#"<?xml version="1.0" encoding="utf-8"?><Error><Code>NoAuthenticationInformation</Code><Message>Server failed to authenticate the request.</Message></Error>"#;
+
+    /// Mount Azure answers (path-style, `/<account>/<container>`) granting
+    /// anonymous users `levels` on `container`
+    pub(crate) async fn mount_container(
+        server: &MockServer,
+        account: &str,
+        container: &str,
+        levels: &[StorageExposure],
+    ) {
+        let granted = |level| levels.contains(&level);
+        let resource = format!("/{}/{}", account, container);
+        let listing = match granted(StorageExposure::PublicRead) {
+            true => ResponseTemplate::new(200).set_body_string(LISTING),
+            // Private containers look missing to anonymous callers
+            false => ResponseTemplate::new(404).set_body_string(RESOURCE_NOT_FOUND),
+        };
+        Mock::given(method("GET"))
+            .and(path(resource.as_str()))
+            .and(query_param("comp", "list"))
+            .and(header("x-ms-version", API_VERSION))
+            .respond_with(listing)
+            .mount(server)
+            .await;
+        let upload = match granted(StorageExposure::PublicWrite) {
+            true => ResponseTemplate::new(201),
+            false => ResponseTemplate::new(401).set_body_string(NO_AUTHENTICATION),
+        };
+        let blob = format!("^{}/cxg-write-probe-[0-9a-f-]+\\.txt$", resource);
+        Mock::given(method("PUT"))
+            .and(path_regex(blob.as_str()))
+            .and(header("x-ms-blob-type", "BlockBlob"))
+            .respond_with(upload)
+            .mount(server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path_regex(blob.as_str()))
+            .respond_with(ResponseTemplate::new(202))
+            .mount(server)
+            .await;
+        let acl = match granted(StorageExposure::FullControl) {
+            true => ResponseTemplate::new(200).set_body_string(ACL),
+            false => ResponseTemplate::new(404).set_body_string(RESOURCE_NOT_FOUND),
+        };
+        Mock::given(method("GET"))
+            .and(path(resource.as_str()))
+            .and(query_param("comp", "acl"))
+            .respond_with(acl)
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404).set_body_string(CONTAINER_NOT_FOUND))
+            .with_priority(10)
+            .mount(server)
+            .await;
+    }
+
+    async fn check(server: &MockServer, account: &str, container: &str) -> Vec<BucketExposure> {
+        let client = NetworkClient::new(Arc::new(crate::config::Config::default()))
+            .await
+            .unwrap();
+        AzureBlobChecker::new()
+            .with_endpoint(format!("{}/{{account}}", server.uri()))
+            .check(&client, account, container, &StorageExposure::ALL)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_exposure_levels() {
+        let cases: [&[StorageExposure]; 4] = [
+            &[StorageExposure::PublicRead],
+            &[StorageExposure::PublicWrite],
+            &[StorageExposure::FullControl],
+            &StorageExposure::ALL,
+        ];
+        for levels in cases {
+            let server = MockServer::start().await;
+            mount_container(&server, "acme", "backups", levels).await;
+            let exposures = check(&server, "acme", "backups").await;
+            let found: Vec<StorageExposure> = exposures.iter().map(|e| e.level).collect();
+            assert_eq!(found, levels);
+            for exposure in &exposures {
+                assert_eq!(exposure.resource, format!("{}/acme/backups", server.uri()));
+                match exposure.level {
+                    StorageExposure::PublicRead => assert_eq!(
+                        exposure.objects,
+                        ["sql/prod-2024-03-01.bacpac", "web.config"]
+                    ),
+                    StorageExposure::PublicWrite => assert_eq!(exposure.cleaned_up, Some(true)),
+                    StorageExposure::FullControl => {
+                        assert!(exposure.response.contains("racwdl"))
+                    }
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_private_and_missing_containers() {
+        let server = MockServer::start().await;
+        mount_container(&server, "acme", "internal", &[]).await;
+        assert!(check(&server, "acme", "internal").await.is_empty());
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+
+        let server = MockServer::start().await;
+        mount_container(&server, "acme", "internal", &[]).await;
+        assert!(check(&server, "acme", "gone").await.is_empty());
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_names() {
+        assert!(AzureBlobChecker::is_valid_account("acmeprod01"));
+        assert!(!AzureBlobChecker::is_valid_account("acme-prod"));
+        assert!(!AzureBlobChecker::is_valid_account("ab"));
+        assert!(AzureBlobChecker::is_valid_container("backups"));
+        assert!(AzureBlobChecker::is_valid_container("$web"));
+        assert!(!AzureBlobChecker::is_valid_container("a--b"));
+        assert!(!AzureBlobChecker::is_valid_container("Backups"));
+        assert_eq!(
+            AzureBlobChecker::new().container_url("acme", "backups"),
+            "https://acme.blob.core.windows.net/backups"
+        );
+    }
+}
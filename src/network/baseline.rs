@@ -0,0 +1,236 @@
+//! False positive reduction by comparing matches against a baseline response
+//!
+//! Some targets answer every path with the same page (a catch-all 200, a
+//! login wall, a parked domain). A matcher that fires on such a page usually
+//! fires everywhere, so the matched response is compared with the response
+//! for a path that cannot exist. If the two are nearly identical the finding
+//! is kept, but downgraded and tagged for review.
+
+use super::NetworkClient;
+use crate::matcher::HttpResponse;
+use dashmap::DashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+/// Similarity above which a match is considered indistinguishable from the baseline
+pub const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.9;
+
+/// Tag added to findings that look like the baseline response
+pub const FALSE_POSITIVE_TAG: &str = "possible-false-positive";
+
+/// Confidence assigned to findings that look like the baseline response
+pub const LOW_CONFIDENCE: u8 = 30;
+
+/// Response to a random non-existent path on a target
+#[derive(Debug, Clone)]
+pub struct Baseline {
+    /// Status code of the baseline response
+    pub status: u16,
+    tokens: HashSet<String>,
+}
+
+impl Baseline {
+    /// Baseline from an already fetched response
+    pub fn from_response(response: &HttpResponse) -> Self {
+        Self {
+            status: response.status,
+            tokens: tokenize(&response.body_string()),
+        }
+    }
+
+    /// Jaccard similarity between this baseline and `response` (0.0 if the status differs)
+    pub fn similarity(&self, response: &HttpResponse) -> f64 {
+        if response.status != self.status {
+            return 0.0;
+        }
+        jaccard(&self.tokens, &tokenize(&response.body_string()))
+    }
+}
+
+/// Fetches and caches one baseline per target base URL
+#[derive(Debug)]
+pub struct BaselineChecker {
+    threshold: f64,
+    baselines: DashMap<String, Arc<OnceCell<Option<Arc<Baseline>>>>>,
+}
+
+impl BaselineChecker {
+    /// Checker flagging matches more similar than `threshold` (0.0-1.0)
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            baselines: DashMap::new(),
+        }
+    }
+
+    /// Similarity threshold in use
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// Path that should not exist on any target
+    pub fn random_path() -> String {
+        format!("/cxg-{:08x}{:08x}", fastrand::u32(..), fastrand::u32(..))
+    }
+
+    /// Baseline for `base_url`, requested once and then reused
+    ///
+    /// Returns `None` when the baseline request fails.
+    pub async fn baseline(
+        &self,
+        client: &NetworkClient,
+        base_url: &str,
+        headers: &HashMap<String, String>,
+    ) -> Option<Arc<Baseline>> {
+        let cell = self
+            .baselines
+            .entry(base_url.to_string())
+            .or_default()
+            .clone();
+        cell.get_or_init(|| async {
+            let url = format!("{}{}", base_url, Self::random_path());
+            match fetch(client, &url, headers).await {
+                Ok(response) => Some(Arc::new(Baseline::from_response(&response))),
+                Err(e) => {
+                    tracing::debug!("Baseline request to {} failed: {}", url, e);
+                    None
+                }
+            }
+        })
+        .await
+        .clone()
+    }
+
+    /// Similarity of `response` to the baseline, if it exceeds the threshold
+    pub async fn check(
+        &self,
+        client: &NetworkClient,
+        base_url: &str,
+        headers: &HashMap<String, String>,
+        response: &HttpResponse,
+    ) -> Option<f64> {
+        let baseline = self.baseline(client, base_url, headers).await?;
+        let similarity = baseline.similarity(response);
+        (similarity > self.threshold).then_some(similarity)
+    }
+}
+
+impl Default for BaselineChecker {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIMILARITY_THRESHOLD)
+    }
+}
+
+/// Lowercased alphanumeric words in `body`
+pub fn tokenize(body: &str) -> HashSet<String> {
+    body.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Size of the intersection over size of the union (1.0 for two empty sets)
+pub fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+async fn fetch(
+    client: &NetworkClient,
+    url: &str,
+    headers: &HashMap<String, String>,
+) -> crate::error::Result<HttpResponse> {
+    let start = std::time::Instant::now();
+    let response = client.get_with_headers(url, headers.clone()).await?;
+    super::read_response(response, start.elapsed()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const CATCH_ALL: &str = "<html><title>Welcome</title><body>Admin panel: sign in</body></html>";
+
+    fn response(status: u16, body: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+            response_time: Default::default(),
+            segments: Vec::new(),
+            url: None,
+        }
+    }
+
+    async fn client() -> NetworkClient {
+        NetworkClient::new(Arc::new(Config::default()))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_catch_all_page_is_flagged() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(CATCH_ALL))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let checker = BaselineChecker::default();
+        let client = client().await;
+        let matched = response(200, CATCH_ALL);
+        for _ in 0..2 {
+            let similarity = checker
+                .check(&client, &server.uri(), &HashMap::new(), &matched)
+                .await;
+            assert_eq!(similarity, Some(1.0));
+        }
+        // Dropping the server verifies the baseline was only requested once
+    }
+
+    #[tokio::test]
+    async fn test_distinct_page_is_not_flagged() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/admin"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(CATCH_ALL))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("Not Found"))
+            .mount(&server)
+            .await;
+
+        let checker = BaselineChecker::default();
+        let similarity = checker
+            .check(
+                &client().await,
+                &server.uri(),
+                &HashMap::new(),
+                &response(200, CATCH_ALL),
+            )
+            .await;
+        assert_eq!(similarity, None);
+    }
+
+    #[test]
+    fn test_jaccard_on_tokens() {
+        let a = tokenize("Hello, World! hello");
+        let b = tokenize("hello there world");
+        assert_eq!(a.len(), 2);
+        assert!((jaccard(&a, &b) - 2.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(jaccard(&HashSet::new(), &HashSet::new()), 1.0);
+
+        let baseline = Baseline::from_response(&response(200, "same page"));
+        assert_eq!(baseline.similarity(&response(200, "same page")), 1.0);
+        assert_eq!(baseline.similarity(&response(302, "same page")), 0.0);
+    }
+}
@@ -0,0 +1,262 @@
+//! Shared model for public cloud storage checks
+//!
+//! [`GcsBucketChecker`] and [`AzureBlobChecker`] probe a bucket or container
+//! anonymously at three levels, each reported as its own
+//! [`BucketExposure`]:
+//!
+//! | Level | Probe | Severity |
+//! |-------|-------|----------|
+//! | `read` | list the objects | High |
+//! | `write` | upload a probe object, then delete it | Critical |
+//! | `full-control` | read the access control list | Critical |
+//!
+//! The write probe uploads a small `cxg-write-probe-<uuid>.txt` object and
+//! deletes it straight away; whether the delete worked is recorded in the
+//! exposure so a leftover object can be removed by hand.
+//!
+//! [`GcsBucketChecker`]: super::gcs::GcsBucketChecker
+//! [`AzureBlobChecker`]: super::azure_blob::AzureBlobChecker
+
+use super::NetworkClient;
+use crate::error::{Error, Result};
+use crate::types::Severity;
+use std::fmt;
+use std::str::FromStr;
+
+/// Object names sampled from a public listing as evidence
+pub(crate) const SAMPLE_OBJECTS: usize = 5;
+
+/// Response body bytes kept as evidence
+const EVIDENCE_BODY_LIMIT: usize = 2048;
+
+/// What anonymous users can do with a bucket or container
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StorageExposure {
+    /// Objects can be listed
+    PublicRead,
+    /// Objects can be created
+    PublicWrite,
+    /// The access control list can be read, so permissions can be managed
+    FullControl,
+}
+
+impl StorageExposure {
+    /// Every level, in probing order
+    pub const ALL: [StorageExposure; 3] = [
+        StorageExposure::PublicRead,
+        StorageExposure::PublicWrite,
+        StorageExposure::FullControl,
+    ];
+
+    /// Name used in templates (`read`, `write`, `full-control`)
+    pub fn name(self) -> &'static str {
+        match self {
+            StorageExposure::PublicRead => "read",
+            StorageExposure::PublicWrite => "write",
+            StorageExposure::FullControl => "full-control",
+        }
+    }
+
+    /// Human-readable name
+    pub fn title(self) -> &'static str {
+        match self {
+            StorageExposure::PublicRead => "Public Read",
+            StorageExposure::PublicWrite => "Public Write",
+            StorageExposure::FullControl => "Full Control",
+        }
+    }
+
+    /// Severity of a finding at this level
+    pub fn severity(self) -> Severity {
+        match self {
+            StorageExposure::PublicRead => Severity::High,
+            StorageExposure::PublicWrite | StorageExposure::FullControl => Severity::Critical,
+        }
+    }
+
+    /// Requests the probe sends (the write probe also deletes its object)
+    pub fn request_count(self) -> usize {
+        match self {
+            StorageExposure::PublicWrite => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for StorageExposure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for StorageExposure {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "read" | "list" => Ok(StorageExposure::PublicRead),
+            "write" => Ok(StorageExposure::PublicWrite),
+            "full-control" | "full_control" | "acl" => Ok(StorageExposure::FullControl),
+            _ => Err(Error::Validation(format!(
+                "Unknown storage exposure check '{}' (supported: read, write, full-control)",
+                s
+            ))),
+        }
+    }
+}
+
+/// One exposure level confirmed on a bucket or container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BucketExposure {
+    /// Level confirmed
+    pub level: StorageExposure,
+    /// URL of the bucket or container
+    pub resource: String,
+    /// Request that confirmed it, e.g. `GET https://.../bucket/`
+    pub request: String,
+    /// HTTP status of that request
+    pub status: u16,
+    /// Start of the response body
+    pub response: String,
+    /// Object names from a public listing (at most [`SAMPLE_OBJECTS`])
+    pub objects: Vec<String>,
+    /// Whether the write probe's object was deleted again
+    pub cleaned_up: Option<bool>,
+}
+
+impl BucketExposure {
+    pub(crate) fn new(
+        level: StorageExposure,
+        resource: &str,
+        request: String,
+        status: u16,
+        body: &str,
+    ) -> Self {
+        let mut end = body.len().min(EVIDENCE_BODY_LIMIT);
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        Self {
+            level,
+            resource: resource.to_string(),
+            request,
+            status,
+            response: body[..end].to_string(),
+            objects: Vec::new(),
+            cleaned_up: None,
+        }
+    }
+}
+
+/// Send a probe; `None` when the request failed in transit
+///
+/// Connection errors are expected for guessed names, so they are logged at
+/// debug level. Running out of request budget stops the check.
+pub(crate) async fn send_probe(
+    client: &NetworkClient,
+    builder: reqwest::RequestBuilder,
+    url: &str,
+) -> Result<Option<(u16, String)>> {
+    client
+        .wait_for_rate_limit(&crate::utils::extract_domain(url))
+        .await;
+    match client.request(builder).await {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            Ok(Some((status, response.text().await.unwrap_or_default())))
+        }
+        Err(e @ Error::RequestBudgetExceeded(_)) => Err(e),
+        Err(e) => {
+            tracing::debug!("Storage probe {} failed: {}", url, e);
+            Ok(None)
+        }
+    }
+}
+
+/// Text of every `<tag>` element in `xml`, in order
+pub(crate) fn xml_values(xml: &str, tag: &str, limit: usize) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while values.len() < limit {
+        let Some(start) = rest.find(&open) else {
+            break;
+        };
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        values.push(rest[..end].to_string());
+        rest = &rest[end + close.len()..];
+    }
+    values
+}
+
+/// Name of a write probe object
+pub(crate) fn probe_object_name() -> String {
+    format!("cxg-write-probe-{}.txt", uuid::Uuid::new_v4())
+}
+
+/// Body of a write probe object
+pub(crate) const PROBE_OBJECT_BODY: &str =
+    "Written by a cert-x-gen public write check. Safe to delete.\n";
+
+/// Organisation name guessed from a host, for bucket name candidates
+///
+/// The label before the public suffix: `www.example.com` and
+/// `shop.example.co.uk` give `example`. `None` for IP addresses.
+pub fn org_name(host: &str) -> Option<String> {
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        return None;
+    }
+    let mut labels: Vec<&str> = host
+        .trim_end_matches('.')
+        .split('.')
+        .filter(|l| !l.is_empty())
+        .collect();
+    if labels.len() > 1 {
+        labels.pop();
+    }
+    // Second-level registries such as co.uk and com.au
+    const SECOND_LEVEL: &[&str] = &["co", "com", "net", "org", "gov", "edu", "ac"];
+    if labels.len() > 1 && SECOND_LEVEL.contains(labels.last()?) {
+        labels.pop();
+    }
+    labels.last().map(|label| label.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exposure_levels() {
+        let parsed: Vec<StorageExposure> = ["read", "write", "full-control"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert_eq!(parsed, StorageExposure::ALL);
+        assert_eq!(StorageExposure::PublicRead.severity(), Severity::High);
+        assert_eq!(StorageExposure::PublicWrite.severity(), Severity::Critical);
+        assert_eq!(StorageExposure::FullControl.severity(), Severity::Critical);
+        assert!("delete".parse::<StorageExposure>().is_err());
+    }
+
+    #[test]
+    fn test_org_name() {
+        assert_eq!(org_name("www.example.com").as_deref(), Some("example"));
+        assert_eq!(org_name("shop.example.co.uk").as_deref(), Some("example"));
+        assert_eq!(org_name("Example.IO").as_deref(), Some("example"));
+        assert_eq!(org_name("localhost").as_deref(), Some("localhost"));
+        assert_eq!(org_name("10.0.0.1"), None);
+    }
+
+    #[test]
+    fn test_xml_values() {
+        let xml = "<R><Contents><Key>a.txt</Key></Contents><Contents><Key>b/c</Key></Contents></R>";
+        assert_eq!(xml_values(xml, "Key", 5), ["a.txt", "b/c"]);
+        assert_eq!(xml_values(xml, "Key", 1), ["a.txt"]);
+        assert!(xml_values(xml, "Name", 5).is_empty());
+    }
+}
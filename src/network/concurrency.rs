@@ -0,0 +1,431 @@
+//! Adaptive concurrency (`cxg scan --adaptive-concurrency`)
+//!
+//! Instead of running a fixed number of templates at once, the executor asks
+//! for a slot before each template/target pair and the limit follows how the
+//! targets cope. Every HTTP request sent through [`NetworkClient`] is counted
+//! for its host and for the whole scan; after each window of [`WINDOW`]
+//! requests the limit is adjusted AIMD-style:
+//!
+//! - more than [`BACKOFF_ERROR_RATE`] of the window failed (connection error,
+//!   timeout, 429 or 503): the limit is halved
+//! - nothing failed: the limit grows by one
+//!
+//! The scan-wide limit starts at its maximum and stays within
+//! [`ConcurrencyBounds`]; a host's limit stays between 1 and
+//! `parallel_templates`. Scan-wide changes are logged and kept as the
+//! scan's concurrency timeline.
+//!
+//! [`NetworkClient`]: super::NetworkClient
+
+use crate::config::ExecutionConfig;
+use crate::progress::get_progress;
+use crate::types::ConcurrencyChange;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::Instant;
+use tokio::sync::Notify;
+
+/// Requests per host or scan-wide before the limit is reconsidered
+pub const WINDOW: u32 = 20;
+
+/// Share of failed requests in a window above which the limit is halved
+pub const BACKOFF_ERROR_RATE: f64 = 0.1;
+
+/// Highest scan-wide limit in stealth mode
+pub const STEALTH_MAX_CONCURRENCY: usize = 2;
+
+/// Highest scan-wide limit in safe mode
+pub const SAFE_MAX_CONCURRENCY: usize = 5;
+
+/// How a request ended, as far as target load is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestOutcome {
+    /// A response that does not ask to slow down
+    Success,
+    /// No response: connection error or timeout
+    Failed,
+    /// 429 Too Many Requests or 503 Service Unavailable
+    Throttled,
+}
+
+impl RequestOutcome {
+    /// Outcome of a request answered with `status`
+    pub fn of_status(status: u16) -> Self {
+        match status {
+            429 | 503 => RequestOutcome::Throttled,
+            _ => RequestOutcome::Success,
+        }
+    }
+}
+
+/// Range the scan-wide limit moves in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConcurrencyBounds {
+    /// Lowest limit backed off to
+    pub min: usize,
+    /// Highest limit, and the starting one
+    pub max: usize,
+}
+
+impl ConcurrencyBounds {
+    /// `min_concurrency`/`max_concurrency`, the maximum defaulting to
+    /// `parallel_targets * parallel_templates` and capped in stealth and safe mode
+    pub fn from_config(execution: &ExecutionConfig) -> Self {
+        let mut max = execution
+            .max_concurrency
+            .unwrap_or(execution.parallel_targets.max(1) * execution.parallel_templates.max(1));
+        if execution.stealth_mode {
+            max = max.min(STEALTH_MAX_CONCURRENCY);
+        } else if execution.safe_mode {
+            max = max.min(SAFE_MAX_CONCURRENCY);
+        }
+        let max = max.max(1);
+        Self {
+            min: execution.min_concurrency.clamp(1, max),
+            max,
+        }
+    }
+}
+
+/// One limit with the templates running under it and the current window
+#[derive(Debug)]
+struct Aimd {
+    limit: usize,
+    min: usize,
+    max: usize,
+    active: usize,
+    requests: u32,
+    failed: u32,
+}
+
+impl Aimd {
+    fn new(min: usize, max: usize) -> Self {
+        Self {
+            limit: max,
+            min,
+            max,
+            active: 0,
+            requests: 0,
+            failed: 0,
+        }
+    }
+
+    /// Count a request; when its window closes with a new limit, the
+    /// window's error rate
+    fn record(&mut self, failed: bool) -> Option<f64> {
+        self.requests += 1;
+        if failed {
+            self.failed += 1;
+        }
+        if self.requests < WINDOW {
+            return None;
+        }
+        let error_rate = f64::from(self.failed) / f64::from(self.requests);
+        self.requests = 0;
+        self.failed = 0;
+
+        let limit = if error_rate > BACKOFF_ERROR_RATE {
+            (self.limit / 2).max(self.min)
+        } else if error_rate == 0.0 {
+            (self.limit + 1).min(self.max)
+        } else {
+            self.limit
+        };
+        if limit == self.limit {
+            return None;
+        }
+        self.limit = limit;
+        Some(error_rate)
+    }
+
+    fn has_room(&self) -> bool {
+        self.active < self.limit
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    global: Aimd,
+    hosts: HashMap<String, Aimd>,
+    timeline: Vec<ConcurrencyChange>,
+}
+
+/// Scan-wide and per-host limits tuned from request outcomes
+#[derive(Debug)]
+pub struct AdaptiveConcurrency {
+    bounds: ConcurrencyBounds,
+    per_host_max: usize,
+    started: Instant,
+    state: Mutex<State>,
+    /// Woken when a slot is released or a limit grows
+    released: Notify,
+}
+
+/// A running template's share of the limits, given back on drop
+#[derive(Debug)]
+pub struct ConcurrencySlot<'a> {
+    controller: &'a AdaptiveConcurrency,
+    host: String,
+}
+
+impl Drop for ConcurrencySlot<'_> {
+    fn drop(&mut self) {
+        {
+            let mut state = self.controller.state.lock();
+            state.global.active -= 1;
+            if let Some(host) = state.hosts.get_mut(&self.host) {
+                host.active -= 1;
+            }
+        }
+        self.controller.released.notify_waiters();
+    }
+}
+
+impl AdaptiveConcurrency {
+    /// Controller starting at `bounds.max`, with at most `per_host_max`
+    /// templates on one host
+    pub fn new(bounds: ConcurrencyBounds, per_host_max: usize) -> Self {
+        let global = Aimd::new(bounds.min, bounds.max);
+        let timeline = vec![ConcurrencyChange {
+            elapsed_ms: 0,
+            concurrency: global.limit,
+            error_rate: 0.0,
+        }];
+        Self {
+            bounds,
+            per_host_max: per_host_max.max(1),
+            started: Instant::now(),
+            state: Mutex::new(State {
+                global,
+                hosts: HashMap::new(),
+                timeline,
+            }),
+            released: Notify::new(),
+        }
+    }
+
+    /// Controller for the execution settings
+    pub fn from_config(execution: &ExecutionConfig) -> Self {
+        Self::new(
+            ConcurrencyBounds::from_config(execution),
+            execution.parallel_templates,
+        )
+    }
+
+    /// Range of the scan-wide limit
+    pub fn bounds(&self) -> ConcurrencyBounds {
+        self.bounds
+    }
+
+    /// Templates allowed to run at once right now
+    pub fn limit(&self) -> usize {
+        self.state.lock().global.limit
+    }
+
+    /// Templates allowed to run at once on `host`; `None` before anything reached it
+    pub fn host_limit(&self, host: &str) -> Option<usize> {
+        self.state.lock().hosts.get(host).map(|h| h.limit)
+    }
+
+    /// Scan-wide limit changes so far, starting with the initial limit
+    pub fn timeline(&self) -> Vec<ConcurrencyChange> {
+        self.state.lock().timeline.clone()
+    }
+
+    /// Wait until a template may run against `host`
+    pub async fn enter(&self, host: &str) -> ConcurrencySlot<'_> {
+        loop {
+            // Registered before checking, so a release in between is not missed
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            {
+                let mut state = self.state.lock();
+                let state = &mut *state;
+                let host_limit = state
+                    .hosts
+                    .entry(host.to_string())
+                    .or_insert_with(|| Aimd::new(1, self.per_host_max));
+                if state.global.has_room() && host_limit.has_room() {
+                    state.global.active += 1;
+                    host_limit.active += 1;
+                    return ConcurrencySlot {
+                        controller: self,
+                        host: host.to_string(),
+                    };
+                }
+            }
+            released.await;
+        }
+    }
+
+    /// Count a request to `host`, adjusting the limits when a window closes
+    pub fn record(&self, host: &str, outcome: RequestOutcome) {
+        let failed = outcome != RequestOutcome::Success;
+        let mut raised = false;
+        {
+            let mut state = self.state.lock();
+            let state = &mut *state;
+
+            let host_limit = state
+                .hosts
+                .entry(host.to_string())
+                .or_insert_with(|| Aimd::new(1, self.per_host_max));
+            let before = host_limit.limit;
+            if let Some(error_rate) = host_limit.record(failed) {
+                tracing::debug!(
+                    "Adaptive concurrency for {}: {} -> {} ({:.0}% of the last {} requests failed)",
+                    host,
+                    before,
+                    host_limit.limit,
+                    error_rate * 100.0,
+                    WINDOW
+                );
+                raised |= host_limit.limit > before;
+            }
+
+            let before = state.global.limit;
+            if let Some(error_rate) = state.global.record(failed) {
+                let limit = state.global.limit;
+                tracing::info!(
+                    "Adaptive concurrency: {} -> {} ({:.0}% of the last {} requests failed)",
+                    before,
+                    limit,
+                    error_rate * 100.0,
+                    WINDOW
+                );
+                state.timeline.push(ConcurrencyChange {
+                    elapsed_ms: self.started.elapsed().as_millis() as u64,
+                    concurrency: limit,
+                    error_rate,
+                });
+                if let Some(progress) = get_progress() {
+                    progress.set_concurrency(limit);
+                }
+                raised |= limit > before;
+            }
+        }
+        if raised {
+            self.released.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn record_window(controller: &AdaptiveConcurrency, host: &str, failures: u32) {
+        for i in 0..WINDOW {
+            let outcome = if i < failures {
+                RequestOutcome::Throttled
+            } else {
+                RequestOutcome::Success
+            };
+            controller.record(host, outcome);
+        }
+    }
+
+    #[test]
+    fn test_backs_off_multiplicatively_and_grows_additively() {
+        let controller = AdaptiveConcurrency::new(ConcurrencyBounds { min: 2, max: 16 }, 4);
+        assert_eq!(controller.limit(), 16);
+
+        record_window(&controller, "a.example", 10);
+        assert_eq!(controller.limit(), 8);
+        record_window(&controller, "a.example", WINDOW);
+        assert_eq!(controller.limit(), 4);
+        record_window(&controller, "a.example", WINDOW);
+        record_window(&controller, "a.example", WINDOW);
+        assert_eq!(controller.limit(), 2);
+
+        // A few errors below the threshold hold the limit
+        record_window(&controller, "a.example", 1);
+        assert_eq!(controller.limit(), 2);
+        record_window(&controller, "a.example", 0);
+        record_window(&controller, "a.example", 0);
+        assert_eq!(controller.limit(), 4);
+
+        let limits: Vec<usize> = controller
+            .timeline()
+            .iter()
+            .map(|c| c.concurrency)
+            .collect();
+        assert_eq!(limits, vec![16, 8, 4, 2, 3, 4]);
+        assert_eq!(controller.timeline()[1].error_rate, 0.5);
+    }
+
+    #[test]
+    fn test_hosts_back_off_independently() {
+        let controller = AdaptiveConcurrency::new(ConcurrencyBounds { min: 1, max: 40 }, 4);
+        record_window(&controller, "slow.example", WINDOW);
+        record_window(&controller, "fast.example", 0);
+        assert_eq!(controller.host_limit("slow.example"), Some(2));
+        assert_eq!(controller.host_limit("fast.example"), Some(4));
+        assert_eq!(controller.host_limit("unseen.example"), None);
+        // Scan-wide, the failing window halved the limit and the clean one added one
+        assert_eq!(controller.limit(), 21);
+    }
+
+    #[tokio::test]
+    async fn test_enter_waits_for_a_free_slot() {
+        let controller = AdaptiveConcurrency::new(ConcurrencyBounds { min: 1, max: 2 }, 1);
+        let first = controller.enter("a.example").await;
+        // The host allows one template, the scan two
+        let second = controller.enter("b.example").await;
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), controller.enter("a.example"))
+                .await
+                .is_err()
+        );
+
+        drop(second);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), controller.enter("a.example"))
+                .await
+                .is_err()
+        );
+        drop(first);
+        let _third = tokio::time::timeout(Duration::from_secs(1), controller.enter("a.example"))
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_bounds_from_config() {
+        let mut execution = ExecutionConfig {
+            parallel_targets: 10,
+            parallel_templates: 4,
+            ..ExecutionConfig::default()
+        };
+        assert_eq!(
+            ConcurrencyBounds::from_config(&execution),
+            ConcurrencyBounds { min: 1, max: 40 }
+        );
+
+        execution.min_concurrency = 5;
+        execution.max_concurrency = Some(12);
+        assert_eq!(
+            ConcurrencyBounds::from_config(&execution),
+            ConcurrencyBounds { min: 5, max: 12 }
+        );
+
+        execution.safe_mode = true;
+        assert_eq!(
+            ConcurrencyBounds::from_config(&execution),
+            ConcurrencyBounds {
+                min: 5,
+                max: SAFE_MAX_CONCURRENCY
+            }
+        );
+        execution.stealth_mode = true;
+        assert_eq!(
+            ConcurrencyBounds::from_config(&execution),
+            ConcurrencyBounds {
+                min: STEALTH_MAX_CONCURRENCY,
+                max: STEALTH_MAX_CONCURRENCY
+            }
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! Content sniffing detection
+//!
+//! A response served as `text/plain`, `application/octet-stream` (or with no
+//! `Content-Type` at all) whose body starts with markup can be rendered as
+//! HTML by a browser that sniffs content, turning reflected or uploaded text
+//! into XSS. `X-Content-Type-Options: nosniff` turns sniffing off. YAML
+//! templates use this through the `content_sniff` matcher.
+
+use crate::matcher::HttpResponse;
+
+/// Declared types that browsers may override after sniffing the body
+const SNIFFABLE_TYPES: &[&str] = &[
+    "text/plain",
+    "application/octet-stream",
+    "application/unknown",
+    "unknown/unknown",
+    "*/*",
+];
+
+/// Leading tags that make the MIME sniffing algorithm settle on HTML
+const HTML_TAGS: &[&str] = &[
+    "<!doctype html",
+    "<html",
+    "<head",
+    "<script",
+    "<iframe",
+    "<h1",
+    "<div",
+    "<font",
+    "<table",
+    "<a",
+    "<style",
+    "<title",
+    "<b",
+    "<body",
+    "<br",
+    "<p",
+    "<!--",
+];
+
+/// Bytes of the body looked at, as browsers only sniff the start
+const SNIFF_WINDOW: usize = 512;
+
+/// A response a sniffing browser would render as HTML
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentSniffing {
+    /// Declared `Content-Type`, if any
+    pub content_type: Option<String>,
+    /// Leading tag that reads as HTML
+    pub tag: String,
+}
+
+impl std::fmt::Display for ContentSniffing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.content_type {
+            Some(content_type) => write!(
+                f,
+                "{} body starts with {} and nosniff is not set",
+                content_type, self.tag
+            ),
+            None => write!(
+                f,
+                "body without Content-Type starts with {} and nosniff is not set",
+                self.tag
+            ),
+        }
+    }
+}
+
+/// Flags responses whose declared type a browser would sniff past into HTML
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContentSniffingDetector;
+
+impl ContentSniffingDetector {
+    /// Create a detector
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// `Some` when `response` lacks `nosniff`, has a sniffable type and an
+    /// HTML-looking body
+    pub fn analyze(&self, response: &HttpResponse) -> Option<ContentSniffing> {
+        let nosniff = response
+            .header("x-content-type-options")
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("nosniff"));
+        if nosniff {
+            return None;
+        }
+
+        let content_type = response.header("content-type").map(str::to_string);
+        if let Some(declared) = &content_type {
+            let essence = declared.split(';').next().unwrap_or_default().trim();
+            if !SNIFFABLE_TYPES
+                .iter()
+                .any(|t| essence.eq_ignore_ascii_case(t))
+            {
+                return None;
+            }
+        }
+
+        let start = &response.body[..response.body.len().min(SNIFF_WINDOW)];
+        let text = String::from_utf8_lossy(start).to_ascii_lowercase();
+        let text = text.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '\u{feff}');
+        let tag = HTML_TAGS.iter().find(|tag| {
+            text.strip_prefix(*tag).is_some_and(|rest| {
+                // The tag has to end there: `<a ` or `<a>`, not `<abbr`
+                **tag == "<!--" || rest.starts_with([' ', '>', '\t', '\n', '\r', '\x0c'])
+            })
+        })?;
+
+        Some(ContentSniffing {
+            content_type,
+            tag: tag.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn response(headers: &[(&str, &str)], body: &str) -> HttpResponse {
+        HttpResponse {
+            status: 200,
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            body: body.as_bytes().to_vec(),
+            response_time: Duration::ZERO,
+            segments: Vec::new(),
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_html_served_as_text_is_flagged() {
+        let detector = ContentSniffingDetector::new();
+        let sniffed = detector
+            .analyze(&response(
+                &[("Content-Type", "text/plain; charset=utf-8")],
+                "\n  <script>alert(document.domain)</script>",
+            ))
+            .unwrap();
+        assert_eq!(sniffed.tag, "<script");
+        assert_eq!(
+            sniffed.content_type.as_deref(),
+            Some("text/plain; charset=utf-8")
+        );
+
+        let octet = response(
+            &[("Content-Type", "application/octet-stream")],
+            "<HTML><body>upload</body></HTML>",
+        );
+        assert_eq!(detector.analyze(&octet).unwrap().tag, "<html");
+
+        // No declared type at all is sniffed too
+        assert!(detector.analyze(&response(&[], "<p>hi</p>")).is_some());
+    }
+
+    #[test]
+    fn test_nosniff_prevents_finding() {
+        let nosniff = response(
+            &[
+                ("Content-Type", "text/plain"),
+                ("X-Content-Type-Options", "nosniff"),
+            ],
+            "<script>alert(1)</script>",
+        );
+        assert_eq!(ContentSniffingDetector::new().analyze(&nosniff), None);
+    }
+
+    #[test]
+    fn test_correct_or_non_html_content_is_not_flagged() {
+        let detector = ContentSniffingDetector::new();
+        let html = response(
+            &[("Content-Type", "text/html; charset=utf-8")],
+            "<html><script>app()</script></html>",
+        );
+        assert_eq!(detector.analyze(&html), None);
+
+        let text = response(&[("Content-Type", "text/plain")], "a < b <script later");
+        assert_eq!(detector.analyze(&text), None);
+
+        let abbr = response(
+            &[("Content-Type", "text/plain")],
+            "<abbr>not a tag we sniff",
+        );
+        assert_eq!(detector.analyze(&abbr), None);
+    }
+}
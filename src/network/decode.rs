@@ -0,0 +1,304 @@
+//! Response body decoding before matching
+//!
+//! The scanning client asks for `gzip, deflate, br` and decodes bodies itself
+//! instead of letting reqwest do it, so the original `Content-Encoding` header
+//! survives for evidence. Raw TCP replies carry no headers to go by and are
+//! decompressed when they start with gzip, zlib or zstd magic bytes (or are an
+//! HTTP response with a `Content-Encoding` header). Decompressed bytes are kept
+//! for binary and hash matchers; [`decode_text`] transcodes them to UTF-8 for
+//! the string-based matchers.
+
+use crate::error::{Error, Result};
+use crate::matcher::HttpResponse;
+use encoding_rs::Encoding;
+use std::io::Read;
+use std::time::Duration;
+
+/// `Accept-Encoding` sent with every scan request
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br";
+
+/// Largest decompressed body kept; guards against decompression bombs
+const MAX_DECODED_SIZE: u64 = 32 * 1024 * 1024;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// URL a response was served from, kept on responses rebuilt after their
+/// body was read (reqwest does not carry the URL over)
+#[derive(Debug, Clone)]
+pub(crate) struct FinalUrl(pub(crate) reqwest::Url);
+
+/// Read a response into an [`HttpResponse`], decompressing its body
+pub async fn read_response(
+    response: reqwest::Response,
+    response_time: Duration,
+) -> Result<HttpResponse> {
+    let status = response.status().as_u16();
+    let url = match response.extensions().get::<FinalUrl>() {
+        Some(FinalUrl(url)) => url.to_string(),
+        None => response.url().to_string(),
+    };
+    let headers: Vec<(String, String)> = response
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect();
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| Error::Network(format!("Failed to read response: {}", e)))?
+        .to_vec();
+
+    let content_encoding = header(&headers, "content-encoding");
+    let body = match content_encoding {
+        Some(encoding) => decompress(body, encoding),
+        None => body,
+    };
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+        response_time,
+        segments: Vec::new(),
+        url: Some(url),
+    })
+}
+
+/// Undo a `Content-Encoding` (`gzip`, `deflate`, `br`, `zstd`, or a list of
+/// them); the body is returned unchanged when it cannot be decoded
+pub fn decompress(body: Vec<u8>, content_encoding: &str) -> Vec<u8> {
+    let mut decoded = body.clone();
+    // Encodings are listed in the order they were applied
+    for encoding in content_encoding.rsplit(',').map(str::trim) {
+        let result = match encoding.to_ascii_lowercase().as_str() {
+            "" | "identity" => continue,
+            "gzip" | "x-gzip" => read_all(flate2::read::MultiGzDecoder::new(&decoded[..])),
+            // Servers disagree on whether deflate means zlib-wrapped or raw
+            "deflate" => read_all(flate2::read::ZlibDecoder::new(&decoded[..]))
+                .or_else(|_| read_all(flate2::read::DeflateDecoder::new(&decoded[..]))),
+            "br" => read_all(brotli::Decompressor::new(&decoded[..], 4096)),
+            "zstd" => zstd::stream::read::Decoder::new(&decoded[..]).and_then(read_all),
+            other => {
+                tracing::debug!("Leaving body with unknown content encoding {:?}", other);
+                return body;
+            }
+        };
+        match result {
+            Ok(bytes) => decoded = bytes,
+            Err(e) => {
+                tracing::debug!("Failed to decode {} body: {}", encoding, e);
+                return body;
+            }
+        }
+    }
+    decoded
+}
+
+/// Decompress a raw TCP reply, going by an HTTP `Content-Encoding` header
+/// when the reply is an HTTP response and by magic bytes otherwise
+pub fn decompress_raw(data: Vec<u8>) -> Vec<u8> {
+    if data.starts_with(b"HTTP/") {
+        if let Some(end) = find(&data, b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&data[..end]).into_owned();
+            let encoding = head.lines().skip(1).find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.trim()
+                    .eq_ignore_ascii_case("content-encoding")
+                    .then(|| value.trim().to_string())
+            });
+            if let Some(encoding) = encoding {
+                let mut decoded = data[..end + 4].to_vec();
+                decoded.extend(decompress(data[end + 4..].to_vec(), &encoding));
+                return decoded;
+            }
+        }
+        return data;
+    }
+
+    let encoding = if data.starts_with(GZIP_MAGIC) {
+        "gzip"
+    } else if data.starts_with(ZSTD_MAGIC) {
+        "zstd"
+    } else if is_zlib_header(&data) {
+        "deflate"
+    } else {
+        return data;
+    };
+    decompress(data, encoding)
+}
+
+/// Body as UTF-8, honouring a byte order mark or the `Content-Type` charset
+pub fn decode_text(body: &[u8], content_type: Option<&str>) -> String {
+    let encoding = content_type
+        .and_then(charset)
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    // decode() prefers a byte order mark over the declared charset
+    let (text, _, _) = encoding.decode(body);
+    text.into_owned()
+}
+
+/// `charset` parameter of a `Content-Type` value
+fn charset(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+pub(crate) fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+fn read_all(reader: impl Read) -> std::io::Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    reader.take(MAX_DECODED_SIZE).read_to_end(&mut decoded)?;
+    Ok(decoded)
+}
+
+fn is_zlib_header(data: &[u8]) -> bool {
+    matches!(data, [0x78, flags, ..]
+        if matches!(flags, 0x01 | 0x5e | 0x9c | 0xda)
+            && (u16::from(data[0]) << 8 | u16::from(*flags)) % 31 == 0)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const BODY: &str = "<title>Apache Tomcat/9.0.1 - Error report</title>";
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn raw_deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn brotli(data: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        {
+            let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22);
+            writer.write_all(data).unwrap();
+        }
+        compressed
+    }
+
+    #[test]
+    fn test_each_content_encoding() {
+        let body = BODY.as_bytes();
+        let fixtures = [
+            ("gzip", gzip(body)),
+            ("deflate", zlib(body)),
+            ("deflate", raw_deflate(body)),
+            ("br", brotli(body)),
+            ("zstd", zstd::encode_all(body, 3).unwrap()),
+            ("identity", body.to_vec()),
+            ("br, gzip", gzip(&brotli(body))),
+        ];
+        for (encoding, compressed) in fixtures {
+            assert_eq!(decompress(compressed, encoding), body, "{}", encoding);
+        }
+
+        // Mislabelled or unknown bodies are left alone
+        assert_eq!(decompress(body.to_vec(), "gzip"), body);
+        assert_eq!(decompress(body.to_vec(), "compress"), body);
+    }
+
+    #[test]
+    fn test_raw_replies_are_sniffed() {
+        let body = BODY.as_bytes();
+        assert_eq!(decompress_raw(gzip(body)), body);
+        assert_eq!(decompress_raw(zlib(body)), body);
+        assert_eq!(decompress_raw(zstd::encode_all(body, 3).unwrap()), body);
+        assert_eq!(decompress_raw(b"+PONG\r\n".to_vec()), b"+PONG\r\n");
+
+        let mut http = b"HTTP/1.1 200 OK\r\nContent-Encoding: br\r\n\r\n".to_vec();
+        http.extend(brotli(body));
+        let decoded = String::from_utf8(decompress_raw(http)).unwrap();
+        assert!(decoded.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(decoded.ends_with(BODY));
+    }
+
+    #[test]
+    fn test_charsets_are_transcoded() {
+        // "Café" in ISO-8859-1
+        let latin1 = b"Caf\xe9";
+        assert_eq!(
+            decode_text(latin1, Some("text/html; charset=ISO-8859-1")),
+            "Café"
+        );
+        assert_eq!(decode_text(latin1, Some("text/html")), "Caf\u{fffd}");
+
+        let mut utf16le = vec![0xff, 0xfe];
+        utf16le.extend("Café".encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(decode_text(&utf16le, None), "Café");
+
+        let mut utf16be = vec![0xfe, 0xff];
+        utf16be.extend("Café".encode_utf16().flat_map(u16::to_be_bytes));
+        // A byte order mark wins over a wrong declared charset
+        assert_eq!(
+            decode_text(&utf16be, Some("text/plain; charset=\"utf-8\"")),
+            "Café"
+        );
+
+        assert_eq!(decode_text(b"\xef\xbb\xbfCaf\xc3\xa9", None), "Café");
+    }
+
+    #[tokio::test]
+    async fn test_client_decodes_and_keeps_content_encoding() {
+        use wiremock::matchers::{headers, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let mut latin1 = b"<p>Bienvenue \xe0 ".to_vec();
+        latin1.extend_from_slice(BODY.as_bytes());
+        Mock::given(method("GET"))
+            .and(headers("accept-encoding", vec!["gzip", "deflate", "br"]))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-encoding", "gzip")
+                    .insert_header("content-type", "text/html; charset=iso-8859-1")
+                    .set_body_bytes(gzip(&latin1)),
+            )
+            .mount(&server)
+            .await;
+
+        let client = crate::network::NetworkClient::new(std::sync::Arc::new(
+            crate::config::Config::default(),
+        ))
+        .await
+        .unwrap();
+        let response = client.get(&server.uri()).await.unwrap();
+        let response = read_response(response, Duration::ZERO).await.unwrap();
+
+        assert_eq!(response.body, latin1);
+        assert_eq!(response.header("Content-Encoding"), Some("gzip"));
+        assert!(response.body_string().starts_with("<p>Bienvenue à <title>"));
+    }
+}
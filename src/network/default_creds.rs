@@ -0,0 +1,1266 @@
+//! Default credential checks
+//!
+//! Tries username/password pairs from a per-service database against the
+//! service's own authentication API: HTTP Basic or form login for web
+//! consoles, `AUTH` for Redis and SCRAM-SHA-256 for MongoDB. Checking stops
+//! at the first pair that logs in, since trying more only risks locking
+//! accounts.
+//!
+//! The database is bundled from `src/data/default_creds.yaml`; a file with
+//! the same layout at `~/.cert-x-gen/default_creds.yaml` replaces the lists
+//! of the services it names, so it can be updated without a new release.
+//!
+//! Passwords never reach the logs: [`Credential`]'s `Debug` and
+//! [`Credential::redacted`] mask them. Connection failures are expected
+//! outcomes of a scan, so they are logged at debug level rather than
+//! surfaced as errors.
+
+use super::NetworkClient;
+use crate::error::{Error, Result};
+use crate::template::PathResolver;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Bundled credential database
+const BUNDLED_DATABASE: &str = include_str!("../data/default_creds.yaml");
+
+/// A service with a known login API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Service {
+    /// Jenkins (`GET /whoAmI/api/json` with Basic auth)
+    Jenkins,
+    /// Grafana (`POST /login`)
+    Grafana,
+    /// Elasticsearch or OpenSearch (`GET /_security/_authenticate`)
+    Elasticsearch,
+    /// Tomcat Manager (`GET /manager/text/serverinfo`)
+    Tomcat,
+    /// RabbitMQ management API (`GET /api/whoami`)
+    RabbitMq,
+    /// Redis (`AUTH`)
+    Redis,
+    /// MongoDB (SCRAM-SHA-256 against the `admin` database)
+    Mongo,
+}
+
+impl Service {
+    /// Every supported service
+    pub const ALL: [Service; 7] = [
+        Service::Jenkins,
+        Service::Grafana,
+        Service::Elasticsearch,
+        Service::Tomcat,
+        Service::RabbitMq,
+        Service::Redis,
+        Service::Mongo,
+    ];
+
+    /// Name used in templates, the database and `--test-creds`
+    pub fn name(self) -> &'static str {
+        match self {
+            Service::Jenkins => "jenkins",
+            Service::Grafana => "grafana",
+            Service::Elasticsearch => "elasticsearch",
+            Service::Tomcat => "tomcat",
+            Service::RabbitMq => "rabbitmq",
+            Service::Redis => "redis",
+            Service::Mongo => "mongo",
+        }
+    }
+
+    /// Display name for findings
+    pub fn title(self) -> &'static str {
+        match self {
+            Service::Jenkins => "Jenkins",
+            Service::Grafana => "Grafana",
+            Service::Elasticsearch => "Elasticsearch",
+            Service::Tomcat => "Tomcat Manager",
+            Service::RabbitMq => "RabbitMQ",
+            Service::Redis => "Redis",
+            Service::Mongo => "MongoDB",
+        }
+    }
+
+    /// Port the service listens on by default
+    pub fn default_port(self) -> u16 {
+        match self {
+            Service::Jenkins | Service::Tomcat => 8080,
+            Service::Grafana => 3000,
+            Service::Elasticsearch => 9200,
+            Service::RabbitMq => 15672,
+            Service::Redis => 6379,
+            Service::Mongo => 27017,
+        }
+    }
+
+    /// Whether the login API is HTTP
+    pub fn is_http(self) -> bool {
+        !matches!(self, Service::Redis | Service::Mongo)
+    }
+
+    /// The login request, for evidence
+    pub fn login_request(self) -> &'static str {
+        match self {
+            Service::Jenkins => "GET /whoAmI/api/json (Basic auth)",
+            Service::Grafana => "POST /login {\"user\": ..., \"password\": ...}",
+            Service::Elasticsearch => "GET /_security/_authenticate (Basic auth)",
+            Service::Tomcat => "GET /manager/text/serverinfo (Basic auth)",
+            Service::RabbitMq => "GET /api/whoami (Basic auth)",
+            Service::Redis => "AUTH <user> <password>",
+            Service::Mongo => "saslStart/saslContinue SCRAM-SHA-256 on admin",
+        }
+    }
+
+    /// Path of the HTTP login endpoint
+    fn login_path(self) -> &'static str {
+        match self {
+            Service::Jenkins => "/whoAmI/api/json",
+            Service::Grafana => "/login",
+            Service::Elasticsearch => "/_security/_authenticate",
+            Service::Tomcat => "/manager/text/serverinfo",
+            Service::RabbitMq => "/api/whoami",
+            Service::Redis | Service::Mongo => "",
+        }
+    }
+
+    /// Whether a 200 response body shows `credential` logged in
+    ///
+    /// The body must name the user (or carry the service's success marker)
+    /// so that endpoints open to anonymous users do not count as logins.
+    fn accepts(self, body: &str, credential: &Credential) -> bool {
+        let json: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+        let user = Some(credential.username.as_str());
+        match self {
+            Service::Jenkins => {
+                json["name"].as_str() == user && json["anonymous"].as_bool() != Some(true)
+            }
+            Service::Grafana => json["message"].as_str() == Some("Logged in"),
+            Service::Elasticsearch => json["username"].as_str() == user,
+            Service::Tomcat => body.starts_with("OK -"),
+            Service::RabbitMq => json["name"].as_str() == user,
+            Service::Redis | Service::Mongo => false,
+        }
+    }
+}
+
+impl fmt::Display for Service {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Service {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "jenkins" => Ok(Service::Jenkins),
+            "grafana" => Ok(Service::Grafana),
+            "elasticsearch" | "elastic" | "opensearch" => Ok(Service::Elasticsearch),
+            "tomcat" => Ok(Service::Tomcat),
+            "rabbitmq" => Ok(Service::RabbitMq),
+            "redis" => Ok(Service::Redis),
+            "mongo" | "mongodb" => Ok(Service::Mongo),
+            other => Err(Error::Validation(format!(
+                "Unknown default-credential service '{}' (supported: {})",
+                other,
+                Service::ALL.map(Service::name).join(", ")
+            ))),
+        }
+    }
+}
+
+/// A username/password pair
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credential {
+    /// User name
+    pub username: String,
+    /// Password, possibly empty
+    pub password: String,
+}
+
+impl Credential {
+    /// Pair from its parts
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// `user:p***` form for logs
+    pub fn redacted(&self) -> String {
+        match self.password.chars().next() {
+            Some(first) => format!("{}:{}***", self.username, first),
+            None => format!("{}:<empty>", self.username),
+        }
+    }
+}
+
+impl fmt::Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Credential({})", self.redacted())
+    }
+}
+
+impl FromStr for Credential {
+    type Err = Error;
+
+    /// `user:password`, split at the first colon
+    fn from_str(s: &str) -> Result<Self> {
+        let (username, password) = s.split_once(':').ok_or_else(|| {
+            Error::Validation(format!(
+                "Credential '{}' must be 'user:password'",
+                s.split(':').next().unwrap_or_default()
+            ))
+        })?;
+        Ok(Self::new(username.trim(), password))
+    }
+}
+
+/// Credential pairs per service
+#[derive(Debug, Clone, Default)]
+pub struct CredentialDatabase {
+    version: u32,
+    services: BTreeMap<Service, Vec<Credential>>,
+}
+
+/// On-disk layout of a credential database
+#[derive(Debug, Deserialize)]
+struct DatabaseFile {
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    services: BTreeMap<String, Vec<String>>,
+}
+
+impl CredentialDatabase {
+    /// The database compiled into the binary
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_DATABASE).expect("bundled default_creds.yaml is valid")
+    }
+
+    /// Default location of the user's database
+    pub fn user_path() -> PathBuf {
+        PathResolver::user_config_dir().join("default_creds.yaml")
+    }
+
+    /// The bundled database, with lists from the user's database taking precedence
+    pub fn load_default() -> Result<Self> {
+        let mut database = Self::bundled();
+        let user_path = Self::user_path();
+        if user_path.is_file() {
+            database.merge(Self::load(&user_path)?);
+        }
+        Ok(database)
+    }
+
+    /// Read a database file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            Error::config(format!(
+                "Failed to read credential database {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::parse(&text).map_err(|e| {
+            Error::config(format!(
+                "Invalid credential database {}: {}",
+                path.display(),
+                e.message()
+            ))
+        })
+    }
+
+    /// Parse a database from YAML
+    pub fn parse(text: &str) -> Result<Self> {
+        let file: DatabaseFile = serde_yaml::from_str(text)?;
+        let mut services = BTreeMap::new();
+        for (name, pairs) in file.services {
+            let service: Service = name.parse()?;
+            let credentials = pairs
+                .iter()
+                .map(|pair| pair.parse())
+                .collect::<Result<Vec<Credential>>>()?;
+            services.insert(service, credentials);
+        }
+        Ok(Self {
+            version: file.version,
+            services,
+        })
+    }
+
+    /// Replace this database's lists with those `other` has
+    pub fn merge(&mut self, other: CredentialDatabase) {
+        self.version = self.version.max(other.version);
+        self.services.extend(other.services);
+    }
+
+    /// Database version
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Pairs to try for `service`, likeliest first
+    pub fn credentials(&self, service: Service) -> &[Credential] {
+        self.services
+            .get(&service)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+}
+
+/// Tries credentials against one service
+#[derive(Debug, Clone)]
+pub struct DefaultCredentialChecker {
+    service: Service,
+    timeout: Duration,
+}
+
+impl DefaultCredentialChecker {
+    /// Checker for `service`
+    pub fn new(service: Service) -> Self {
+        Self {
+            service,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Timeout for each Redis or MongoDB exchange (HTTP uses the client's)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Service being checked
+    pub fn service(&self) -> Service {
+        self.service
+    }
+
+    /// The first pair in `credentials` that logs in to `address:port`
+    ///
+    /// `tls` selects HTTPS for the HTTP services; Redis and MongoDB are
+    /// spoken in plain text.
+    pub async fn check(
+        &self,
+        client: &NetworkClient,
+        address: &str,
+        port: u16,
+        tls: bool,
+        credentials: &[Credential],
+    ) -> Result<Option<Credential>> {
+        let hit = if self.service.is_http() {
+            let scheme = if tls { "https" } else { "http" };
+            let base = format!("{}://{}:{}", scheme, address, port);
+            self.check_http(client, &base, credentials).await?
+        } else {
+            let addr = format!("{}:{}", address, port);
+            let mut stream =
+                match tokio::time::timeout(self.timeout, TcpStream::connect(&addr)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => {
+                        tracing::debug!("{} login check of {} failed: {}", self.service, addr, e);
+                        return Ok(None);
+                    }
+                    Err(_) => {
+                        tracing::debug!("{} login check of {} timed out", self.service, addr);
+                        return Ok(None);
+                    }
+                };
+            self.check_stream(client, address, &mut stream, credentials)
+                .await?
+        };
+
+        if let Some(credential) = &hit {
+            tracing::debug!(
+                "{} at {}:{} accepts default credential {}",
+                self.service.title(),
+                address,
+                port,
+                credential.redacted()
+            );
+        }
+        Ok(hit)
+    }
+
+    /// Try each pair against an HTTP login endpoint under `base`
+    async fn check_http(
+        &self,
+        client: &NetworkClient,
+        base: &str,
+        credentials: &[Credential],
+    ) -> Result<Option<Credential>> {
+        let url = format!("{}{}", base, self.service.login_path());
+        let domain = crate::utils::extract_domain(&url);
+        for credential in credentials {
+            let http = client.client();
+            let request = match self.service {
+                Service::Grafana => http.post(&url).json(&serde_json::json!({
+                    "user": credential.username,
+                    "password": credential.password,
+                })),
+                _ => http
+                    .get(&url)
+                    .basic_auth(&credential.username, Some(&credential.password)),
+            };
+
+            client.wait_for_rate_limit(&domain).await;
+            let response = match client.request(request).await {
+                Ok(response) => response,
+                Err(e @ Error::RequestBudgetExceeded(_)) => return Err(e),
+                Err(e) => {
+                    tracing::debug!("{} login check of {} failed: {}", self.service, url, e);
+                    return Ok(None);
+                }
+            };
+            let status = response.status().as_u16();
+            if status == 404 {
+                tracing::debug!("No {} login endpoint at {}", self.service, url);
+                return Ok(None);
+            }
+            let body = response.text().await.unwrap_or_default();
+            tracing::debug!(
+                "{} login as {} at {}: {}",
+                self.service,
+                credential.redacted(),
+                url,
+                status
+            );
+            if status == 200 && self.service.accepts(&body, credential) {
+                return Ok(Some(credential.clone()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Try each pair over one Redis or MongoDB connection
+    async fn check_stream(
+        &self,
+        client: &NetworkClient,
+        address: &str,
+        stream: &mut TcpStream,
+        credentials: &[Credential],
+    ) -> Result<Option<Credential>> {
+        for credential in credentials {
+            client.throttle(address).await?;
+            let attempt = async {
+                match self.service {
+                    Service::Redis => redis_auth(stream, credential).await,
+                    _ => mongo_auth(stream, credential).await,
+                }
+            };
+            match tokio::time::timeout(self.timeout, attempt).await {
+                Ok(Ok(true)) => return Ok(Some(credential.clone())),
+                Ok(Ok(false)) => tracing::debug!(
+                    "{} at {} rejected {}",
+                    self.service,
+                    address,
+                    credential.redacted()
+                ),
+                Ok(Err(e)) => {
+                    tracing::debug!("{} login check of {} failed: {}", self.service, address, e);
+                    return Ok(None);
+                }
+                Err(_) => {
+                    tracing::debug!("{} login check of {} timed out", self.service, address);
+                    return Ok(None);
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Send `AUTH` and read the status line
+///
+/// The user `default` sends the pre-6.0 single-argument form, which
+/// authenticates the default user on every version.
+async fn redis_auth(stream: &mut TcpStream, credential: &Credential) -> std::io::Result<bool> {
+    let mut args = vec!["AUTH"];
+    if credential.username != "default" {
+        args.push(&credential.username);
+    }
+    args.push(&credential.password);
+
+    let mut command = format!("*{}\r\n", args.len());
+    for arg in args {
+        command.push_str(&format!("${}\r\n{}\r\n", arg.len(), arg));
+    }
+    stream.write_all(command.as_bytes()).await?;
+
+    let mut line = String::new();
+    BufReader::new(&mut *stream)
+        .take(1024)
+        .read_line(&mut line)
+        .await?;
+    if line.is_empty() {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(line.trim_end() == "+OK")
+}
+
+/// Run a SCRAM-SHA-256 conversation for `credential` on the `admin` database
+async fn mongo_auth(stream: &mut TcpStream, credential: &Credential) -> std::io::Result<bool> {
+    let nonce = BASE64.encode(
+        std::iter::repeat_with(|| fastrand::u8(..))
+            .take(24)
+            .collect::<Vec<u8>>(),
+    );
+    let client_first_bare = format!("n={},r={}", scram_escape(&credential.username), nonce);
+
+    let start = bson::document(&[
+        ("saslStart", bson::Value::Int32(1)),
+        ("mechanism", bson::Value::String("SCRAM-SHA-256".into())),
+        (
+            "payload",
+            bson::Value::Binary(format!("n,,{}", client_first_bare).into_bytes()),
+        ),
+        ("autoAuthorize", bson::Value::Int32(1)),
+        ("$db", bson::Value::String("admin".into())),
+    ]);
+    let reply = op_msg_roundtrip(stream, 1, &start).await?;
+    if !bson::is_ok(&reply) {
+        return Ok(false);
+    }
+    let (Some(conversation_id), Some(server_first)) =
+        (bson::get(&reply, "conversationId"), bson::payload(&reply))
+    else {
+        return Ok(false);
+    };
+
+    let Some(exchange) = ScramExchange::new(&client_first_bare, &server_first, &nonce) else {
+        return Ok(false);
+    };
+    let keys = ScramKeys::derive(&credential.password, &exchange.salt, exchange.iterations)?;
+    let auth_message = exchange.auth_message();
+    let proof: Vec<u8> = keys
+        .client_key
+        .iter()
+        .zip(hmac_sha256(&keys.stored_key, auth_message.as_bytes())?)
+        .map(|(key, signature)| key ^ signature)
+        .collect();
+    let client_final = format!(
+        "{},p={}",
+        exchange.client_final_without_proof(),
+        BASE64.encode(proof)
+    );
+
+    let next = bson::document(&[
+        ("saslContinue", bson::Value::Int32(1)),
+        ("conversationId", conversation_id.clone()),
+        ("payload", bson::Value::Binary(client_final.into_bytes())),
+        ("$db", bson::Value::String("admin".into())),
+    ]);
+    let reply = op_msg_roundtrip(stream, 2, &next).await?;
+    if !bson::is_ok(&reply) {
+        return Ok(false);
+    }
+
+    // A server that really holds the password proves it back
+    let server_signature = hmac_sha256(&keys.server_key, auth_message.as_bytes())?;
+    Ok(bson::payload(&reply).as_deref()
+        == Some(format!("v={}", BASE64.encode(server_signature)).as_str()))
+}
+
+/// Escape a SCRAM user name (RFC 5802 section 5.1)
+fn scram_escape(name: &str) -> String {
+    name.replace('=', "=3D").replace(',', "=2C")
+}
+
+/// Values carried through a SCRAM conversation
+struct ScramExchange<'a> {
+    client_first_bare: &'a str,
+    server_first: &'a str,
+    nonce: &'a str,
+    salt: Vec<u8>,
+    iterations: u32,
+}
+
+impl<'a> ScramExchange<'a> {
+    /// Parse the server-first message, which must extend the client nonce
+    fn new(client_first_bare: &'a str, server_first: &'a str, client_nonce: &str) -> Option<Self> {
+        let attr = |key: &str| {
+            server_first
+                .split(',')
+                .find_map(|part| part.strip_prefix(key)?.strip_prefix('='))
+        };
+        let nonce = attr("r").filter(|r| r.starts_with(client_nonce))?;
+        Some(Self {
+            client_first_bare,
+            server_first,
+            nonce,
+            salt: BASE64.decode(attr("s")?).ok()?,
+            iterations: attr("i")?.parse().ok().filter(|&i| i > 0)?,
+        })
+    }
+
+    fn client_final_without_proof(&self) -> String {
+        // "biws" is base64 of the "n,," GS2 header
+        format!("c=biws,r={}", self.nonce)
+    }
+
+    fn auth_message(&self) -> String {
+        format!(
+            "{},{},{}",
+            self.client_first_bare,
+            self.server_first,
+            self.client_final_without_proof()
+        )
+    }
+}
+
+/// Keys derived from a password (RFC 5802 section 3)
+struct ScramKeys {
+    client_key: Vec<u8>,
+    stored_key: Vec<u8>,
+    server_key: Vec<u8>,
+}
+
+impl ScramKeys {
+    fn derive(password: &str, salt: &[u8], iterations: u32) -> std::io::Result<Self> {
+        use sha2::Digest;
+
+        let mut salted = [0u8; 32];
+        openssl::pkcs5::pbkdf2_hmac(
+            password.as_bytes(),
+            salt,
+            iterations as usize,
+            openssl::hash::MessageDigest::sha256(),
+            &mut salted,
+        )
+        .map_err(std::io::Error::other)?;
+        let client_key = hmac_sha256(&salted, b"Client Key")?;
+        Ok(Self {
+            stored_key: sha2::Sha256::digest(&client_key).to_vec(),
+            server_key: hmac_sha256(&salted, b"Server Key")?,
+            client_key,
+        })
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let key = openssl::pkey::PKey::hmac(key).map_err(std::io::Error::other)?;
+    let mut signer = openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), &key)
+        .map_err(std::io::Error::other)?;
+    signer.update(data).map_err(std::io::Error::other)?;
+    signer.sign_to_vec().map_err(std::io::Error::other)
+}
+
+/// `OP_MSG` opcode
+const OP_MSG: i32 = 2013;
+
+/// Largest reply accepted from a server
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// Send `body` as an `OP_MSG` and return the document of the reply
+async fn op_msg_roundtrip(
+    stream: &mut TcpStream,
+    request_id: i32,
+    body: &[u8],
+) -> std::io::Result<bson::Document> {
+    stream.write_all(&op_msg(request_id, 0, body)).await?;
+    read_op_msg(stream).await
+}
+
+/// Frame `body` as a single-section `OP_MSG`
+fn op_msg(request_id: i32, response_to: i32, body: &[u8]) -> Vec<u8> {
+    let length = 16 + 4 + 1 + body.len();
+    let mut message = Vec::with_capacity(length);
+    for field in [length as i32, request_id, response_to, OP_MSG] {
+        message.extend_from_slice(&field.to_le_bytes());
+    }
+    message.extend_from_slice(&0u32.to_le_bytes());
+    message.push(0);
+    message.extend_from_slice(body);
+    message
+}
+
+/// Read one `OP_MSG` and decode its body section
+async fn read_op_msg<R: tokio::io::AsyncRead + Unpin>(
+    stream: &mut R,
+) -> std::io::Result<bson::Document> {
+    let invalid =
+        |what: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, what.to_string());
+
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length).await?;
+    let length = i32::from_le_bytes(length) as usize;
+    if !(21..=MAX_MESSAGE_LEN).contains(&length) {
+        return Err(invalid("bad OP_MSG length"));
+    }
+    let mut rest = vec![0u8; length - 4];
+    stream.read_exact(&mut rest).await?;
+    if i32::from_le_bytes([rest[8], rest[9], rest[10], rest[11]]) != OP_MSG || rest[16] != 0 {
+        return Err(invalid("not an OP_MSG body section"));
+    }
+    bson::decode(&rest[17..]).ok_or_else(|| invalid("malformed BSON"))
+}
+
+/// The small subset of BSON the MongoDB handshake needs
+mod bson {
+    /// A decoded document, in field order
+    pub type Document = Vec<(String, Value)>;
+
+    /// A BSON value
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Value {
+        Double(f64),
+        String(String),
+        Document(Document),
+        Binary(Vec<u8>),
+        Bool(bool),
+        Int32(i32),
+        Int64(i64),
+        /// A value of a type the handshake never reads
+        Other,
+    }
+
+    /// Encode a document
+    pub fn document(fields: &[(&str, Value)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (key, value) in fields {
+            let tag: u8 = match value {
+                Value::Double(_) => 0x01,
+                Value::String(_) => 0x02,
+                Value::Document(_) => 0x03,
+                Value::Binary(_) => 0x05,
+                Value::Bool(_) => 0x08,
+                Value::Int32(_) => 0x10,
+                Value::Int64(_) => 0x12,
+                Value::Other => 0x0a,
+            };
+            body.push(tag);
+            body.extend_from_slice(key.as_bytes());
+            body.push(0);
+            match value {
+                Value::Double(v) => body.extend_from_slice(&v.to_le_bytes()),
+                Value::String(s) => {
+                    body.extend_from_slice(&(s.len() as i32 + 1).to_le_bytes());
+                    body.extend_from_slice(s.as_bytes());
+                    body.push(0);
+                }
+                Value::Document(fields) => {
+                    let fields: Vec<(&str, Value)> = fields
+                        .iter()
+                        .map(|(k, v)| (k.as_str(), v.clone()))
+                        .collect();
+                    body.extend_from_slice(&document(&fields));
+                }
+                Value::Binary(bytes) => {
+                    body.extend_from_slice(&(bytes.len() as i32).to_le_bytes());
+                    body.push(0);
+                    body.extend_from_slice(bytes);
+                }
+                Value::Bool(b) => body.push(u8::from(*b)),
+                Value::Int32(v) => body.extend_from_slice(&v.to_le_bytes()),
+                Value::Int64(v) => body.extend_from_slice(&v.to_le_bytes()),
+                Value::Other => {}
+            }
+        }
+        let mut encoded = ((body.len() + 5) as i32).to_le_bytes().to_vec();
+        encoded.extend(body);
+        encoded.push(0);
+        encoded
+    }
+
+    /// Decode a document, or `None` if it is malformed
+    pub fn decode(bytes: &[u8]) -> Option<Document> {
+        let length = i32::from_le_bytes(bytes.get(..4)?.try_into().ok()?) as usize;
+        let body = bytes.get(4..length.checked_sub(1)?)?;
+        let mut fields = Vec::new();
+        let mut pos = 0;
+        while pos < body.len() {
+            let tag = body[pos];
+            let key_end = pos + 1 + body[pos + 1..].iter().position(|&b| b == 0)?;
+            let key = String::from_utf8_lossy(&body[pos + 1..key_end]).into_owned();
+            pos = key_end + 1;
+            let int32 = |at: usize| -> Option<i32> {
+                Some(i32::from_le_bytes(body.get(at..at + 4)?.try_into().ok()?))
+            };
+            let (value, size) = match tag {
+                0x01 => (
+                    Value::Double(f64::from_le_bytes(body.get(pos..pos + 8)?.try_into().ok()?)),
+                    8,
+                ),
+                0x02 => {
+                    let len = usize::try_from(int32(pos)?).ok()?;
+                    let text = body.get(pos + 4..(pos + 4 + len).checked_sub(1)?)?;
+                    (
+                        Value::String(String::from_utf8_lossy(text).into_owned()),
+                        4 + len,
+                    )
+                }
+                0x03 | 0x04 => {
+                    let len = usize::try_from(int32(pos)?).ok()?;
+                    (Value::Document(decode(body.get(pos..pos + len)?)?), len)
+                }
+                0x05 => {
+                    let len = usize::try_from(int32(pos)?).ok()?;
+                    (
+                        Value::Binary(body.get(pos + 5..pos + 5 + len)?.to_vec()),
+                        5 + len,
+                    )
+                }
+                0x07 => (Value::Other, 12),
+                0x08 => (Value::Bool(*body.get(pos)? != 0), 1),
+                0x09 | 0x11 => (Value::Other, 8),
+                0x0a => (Value::Other, 0),
+                0x10 => (Value::Int32(int32(pos)?), 4),
+                0x12 => (
+                    Value::Int64(i64::from_le_bytes(body.get(pos..pos + 8)?.try_into().ok()?)),
+                    8,
+                ),
+                _ => return None,
+            };
+            fields.push((key, value));
+            pos += size;
+        }
+        Some(fields)
+    }
+
+    /// Field `key` of `document`
+    pub fn get<'a>(document: &'a Document, key: &str) -> Option<&'a Value> {
+        document.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Whether the reply's `ok` field is 1
+    pub fn is_ok(document: &Document) -> bool {
+        match get(document, "ok") {
+            Some(Value::Double(v)) => *v == 1.0,
+            Some(Value::Int32(v)) => *v == 1,
+            Some(Value::Int64(v)) => *v == 1,
+            _ => false,
+        }
+    }
+
+    /// SASL `payload` of a reply, as text
+    pub fn payload(document: &Document) -> Option<String> {
+        match get(document, "payload")? {
+            Value::Binary(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    async fn client() -> NetworkClient {
+        NetworkClient::new(Arc::new(crate::config::Config::default()))
+            .await
+            .unwrap()
+    }
+
+    fn pairs(list: &[&str]) -> Vec<Credential> {
+        list.iter().map(|pair| pair.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_bundled_database_covers_every_service() {
+        let database = CredentialDatabase::bundled();
+        assert!(database.version() >= 1);
+        for service in Service::ALL {
+            assert!(!database.credentials(service).is_empty(), "{}", service);
+            assert_eq!(service.name().parse::<Service>().unwrap(), service);
+        }
+        assert!(database
+            .credentials(Service::RabbitMq)
+            .contains(&Credential::new("guest", "guest")));
+        assert!("couchdb".parse::<Service>().is_err());
+    }
+
+    #[test]
+    fn test_user_database_replaces_named_services() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("default_creds.yaml");
+        std::fs::write(
+            &path,
+            "version: 7\nservices:\n  grafana: [\"ops:hunter2:x\", \"ops:\"]\n",
+        )
+        .unwrap();
+
+        let mut database = CredentialDatabase::bundled();
+        database.merge(CredentialDatabase::load(&path).unwrap());
+        assert_eq!(database.version(), 7);
+        assert_eq!(
+            database.credentials(Service::Grafana),
+            pairs(&["ops:hunter2:x", "ops:"])
+        );
+        assert_eq!(
+            database.credentials(Service::Jenkins),
+            CredentialDatabase::bundled().credentials(Service::Jenkins)
+        );
+
+        std::fs::write(&path, "services:\n  grafana: [nocolon]\n").unwrap();
+        assert!(CredentialDatabase::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_credentials_are_redacted() {
+        let credential = Credential::new("admin", "s3cret");
+        assert_eq!(credential.redacted(), "admin:s***");
+        assert!(!format!("{:?}", credential).contains("s3cret"));
+        assert_eq!(Credential::new("tomcat", "").redacted(), "tomcat:<empty>");
+    }
+
+    /// Mount a Basic-auth login endpoint accepting `user:password` on `server`
+    async fn mount_basic_login(
+        server: &wiremock::MockServer,
+        path: &str,
+        user: &str,
+        password: &str,
+        body: serde_json::Value,
+    ) {
+        let token = BASE64.encode(format!("{}:{}", user, password));
+        wiremock::Mock::given(wiremock::matchers::path(path))
+            .and(wiremock::matchers::header(
+                "authorization",
+                format!("Basic {}", token).as_str(),
+            ))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(body))
+            .with_priority(1)
+            .mount(server)
+            .await;
+        wiremock::Mock::given(wiremock::matchers::path(path))
+            .respond_with(wiremock::ResponseTemplate::new(401))
+            .with_priority(2)
+            .mount(server)
+            .await;
+    }
+
+    async fn check_http(
+        service: Service,
+        server: &wiremock::MockServer,
+        credentials: &[Credential],
+    ) -> Option<Credential> {
+        let port = url::Url::parse(&server.uri()).unwrap().port().unwrap();
+        DefaultCredentialChecker::new(service)
+            .check(&client().await, "127.0.0.1", port, false, credentials)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_http_services_accept_matching_credentials() {
+        let cases = [
+            (
+                Service::Jenkins,
+                "/whoAmI/api/json",
+                "admin",
+                "password",
+                serde_json::json!({"name": "admin", "anonymous": false, "authenticated": true}),
+            ),
+            (
+                Service::Elasticsearch,
+                "/_security/_authenticate",
+                "elastic",
+                "changeme",
+                serde_json::json!({"username": "elastic", "roles": ["superuser"]}),
+            ),
+            (
+                Service::RabbitMq,
+                "/api/whoami",
+                "guest",
+                "guest",
+                serde_json::json!({"name": "guest", "tags": ["administrator"]}),
+            ),
+        ];
+        for (service, path, user, password, body) in cases {
+            let server = wiremock::MockServer::start().await;
+            mount_basic_login(&server, path, user, password, body).await;
+
+            let database = CredentialDatabase::bundled();
+            let hit = check_http(service, &server, database.credentials(service)).await;
+            assert_eq!(hit, Some(Credential::new(user, password)), "{}", service);
+            assert_eq!(check_http(service, &server, &pairs(&["x:y"])).await, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tomcat_manager_login() {
+        let server = wiremock::MockServer::start().await;
+        let token = BASE64.encode("tomcat:s3cret");
+        wiremock::Mock::given(wiremock::matchers::path("/manager/text/serverinfo"))
+            .and(wiremock::matchers::header(
+                "authorization",
+                format!("Basic {}", token).as_str(),
+            ))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200)
+                    .set_body_string("OK - Server info\nTomcat Version: [Apache Tomcat/9.0.80]\n"),
+            )
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        wiremock::Mock::given(wiremock::matchers::any())
+            .respond_with(wiremock::ResponseTemplate::new(401))
+            .with_priority(2)
+            .mount(&server)
+            .await;
+
+        let hit = check_http(
+            Service::Tomcat,
+            &server,
+            CredentialDatabase::bundled().credentials(Service::Tomcat),
+        )
+        .await;
+        assert_eq!(hit, Some(Credential::new("tomcat", "s3cret")));
+    }
+
+    #[tokio::test]
+    async fn test_grafana_form_login_and_anonymous_endpoints() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .and(wiremock::matchers::path("/login"))
+            .and(wiremock::matchers::body_json(
+                serde_json::json!({"user": "admin", "password": "prom-operator"}),
+            ))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"message": "Logged in", "redirectUrl": "/"})),
+            )
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        wiremock::Mock::given(wiremock::matchers::path("/login"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(401)
+                    .set_body_json(serde_json::json!({"message": "Invalid username or password"})),
+            )
+            .with_priority(2)
+            .mount(&server)
+            .await;
+
+        let hit = check_http(
+            Service::Grafana,
+            &server,
+            CredentialDatabase::bundled().credentials(Service::Grafana),
+        )
+        .await;
+        assert_eq!(hit, Some(Credential::new("admin", "prom-operator")));
+
+        // Jenkins lets anonymous users read whoAmI; that is not a login
+        let open = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/whoAmI/api/json"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({"name": "anonymous", "anonymous": true, "authenticated": true}),
+            ))
+            .mount(&open)
+            .await;
+        assert_eq!(
+            check_http(Service::Jenkins, &open, &pairs(&["admin:admin"])).await,
+            None
+        );
+
+        // Nothing listening at the login path stops after one request
+        let empty = wiremock::MockServer::start().await;
+        assert_eq!(
+            check_http(
+                Service::Elasticsearch,
+                &empty,
+                &pairs(&["elastic:a", "elastic:b"])
+            )
+            .await,
+            None
+        );
+        assert_eq!(empty.received_requests().await.unwrap().len(), 1);
+    }
+
+    /// Redis accepting `password` for the default user
+    pub(crate) async fn spawn_redis(password: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut reader = BufReader::new(stream);
+                    loop {
+                        let mut header = String::new();
+                        if reader.read_line(&mut header).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        let count: usize = header.trim()[1..].parse().unwrap();
+                        let mut args = Vec::new();
+                        for _ in 0..count {
+                            let mut len = String::new();
+                            let mut arg = String::new();
+                            reader.read_line(&mut len).await.unwrap();
+                            reader.read_line(&mut arg).await.unwrap();
+                            args.push(arg.trim_end().to_string());
+                        }
+                        let reply = match args.as_slice() {
+                            [_, pass] | [_, _, pass] if pass == password => "+OK\r\n",
+                            _ => "-WRONGPASS invalid username-password pair\r\n",
+                        };
+                        reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_redis_auth() {
+        let port = spawn_redis("foobared").await;
+        let checker = DefaultCredentialChecker::new(Service::Redis);
+        let client = client().await;
+
+        let hit = checker
+            .check(
+                &client,
+                "127.0.0.1",
+                port,
+                false,
+                &pairs(&["default:redis", "default:foobared"]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(hit, Some(Credential::new("default", "foobared")));
+        let miss = checker
+            .check(&client, "127.0.0.1", port, false, &pairs(&["default:x"]))
+            .await
+            .unwrap();
+        assert_eq!(miss, None);
+    }
+
+    /// mongod that knows one SCRAM-SHA-256 user on `admin`
+    pub(crate) async fn spawn_mongod(user: &'static str, password: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let salt = b"cert-x-gen-salt!".to_vec();
+        let keys = Arc::new(ScramKeys::derive(password, &salt, 4096).unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let keys = keys.clone();
+                let salt = salt.clone();
+                tokio::spawn(async move {
+                    let failed = || {
+                        bson::document(&[
+                            ("ok", bson::Value::Double(0.0)),
+                            (
+                                "errmsg",
+                                bson::Value::String("Authentication failed.".into()),
+                            ),
+                            ("code", bson::Value::Int32(18)),
+                        ])
+                    };
+                    let mut conversation: Option<(String, String)> = None;
+                    while let Ok(request) = read_op_msg(&mut stream).await {
+                        let payload = bson::payload(&request).unwrap_or_default();
+                        let reply = if bson::get(&request, "saslStart").is_some() {
+                            let bare = payload.trim_start_matches("n,,").to_string();
+                            let nonce = bare.split_once(",r=").map(|(_, r)| r.to_string());
+                            match nonce {
+                                Some(nonce) if bare.starts_with(&format!("n={},", user)) => {
+                                    let server_first =
+                                        format!("r={}srv,s={},i=4096", nonce, BASE64.encode(&salt));
+                                    conversation = Some((bare, server_first.clone()));
+                                    bson::document(&[
+                                        ("conversationId", bson::Value::Int32(1)),
+                                        ("done", bson::Value::Bool(false)),
+                                        ("payload", bson::Value::Binary(server_first.into_bytes())),
+                                        ("ok", bson::Value::Double(1.0)),
+                                    ])
+                                }
+                                _ => failed(),
+                            }
+                        } else {
+                            let (bare, server_first) = conversation.take().unwrap();
+                            let (without_proof, proof) = payload.rsplit_once(",p=").unwrap();
+                            let auth_message =
+                                format!("{},{},{}", bare, server_first, without_proof);
+                            let signature =
+                                hmac_sha256(&keys.stored_key, auth_message.as_bytes()).unwrap();
+                            let client_key: Vec<u8> = BASE64
+                                .decode(proof)
+                                .unwrap()
+                                .iter()
+                                .zip(signature)
+                                .map(|(p, s)| p ^ s)
+                                .collect();
+                            if client_key == keys.client_key {
+                                let verifier = BASE64.encode(
+                                    hmac_sha256(&keys.server_key, auth_message.as_bytes()).unwrap(),
+                                );
+                                bson::document(&[
+                                    ("conversationId", bson::Value::Int32(1)),
+                                    ("done", bson::Value::Bool(true)),
+                                    (
+                                        "payload",
+                                        bson::Value::Binary(format!("v={}", verifier).into_bytes()),
+                                    ),
+                                    ("ok", bson::Value::Double(1.0)),
+                                ])
+                            } else {
+                                failed()
+                            }
+                        };
+                        if stream.write_all(&op_msg(0, 1, &reply)).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_mongo_scram_login() {
+        let port = spawn_mongod("root", "example").await;
+        let checker = DefaultCredentialChecker::new(Service::Mongo);
+        let client = client().await;
+
+        let hit = checker
+            .check(
+                &client,
+                "127.0.0.1",
+                port,
+                false,
+                &pairs(&["admin:admin", "root:root", "root:example"]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(hit, Some(Credential::new("root", "example")));
+
+        let miss = checker
+            .check(&client, "127.0.0.1", port, false, &pairs(&["root:nope"]))
+            .await
+            .unwrap();
+        assert_eq!(miss, None);
+    }
+
+    #[test]
+    fn test_bson_round_trip() {
+        let encoded = bson::document(&[
+            ("ok", bson::Value::Double(1.0)),
+            ("payload", bson::Value::Binary(b"r=abc".to_vec())),
+            (
+                "nested",
+                bson::Value::Document(vec![("n".to_string(), bson::Value::Int64(5))]),
+            ),
+            ("name", bson::Value::String("x".into())),
+        ]);
+        let decoded = bson::decode(&encoded).unwrap();
+        assert!(bson::is_ok(&decoded));
+        assert_eq!(bson::payload(&decoded).as_deref(), Some("r=abc"));
+        assert_eq!(
+            bson::get(&decoded, "name"),
+            Some(&bson::Value::String("x".into()))
+        );
+        assert!(bson::decode(&encoded[..encoded.len() - 3]).is_none());
+    }
+}
@@ -0,0 +1,302 @@
+//! Google Cloud Storage public bucket checks
+//!
+//! Probes a bucket through the XML API at `storage.googleapis.com` without
+//! credentials (see [`super::bucket`] for the levels):
+//!
+//! - read: `GET /<bucket>/` returns a `ListBucketResult`
+//! - write: `PUT /<bucket>/<probe object>` is accepted
+//! - full control: `GET /<bucket>?acl` returns the `AccessControlList`
+//!
+//! A `NoSuchBucket` answer to the listing ends the check, so guessed names
+//! that do not exist cost one request.
+
+use super::bucket::{
+    probe_object_name, send_probe, xml_values, BucketExposure, StorageExposure, PROBE_OBJECT_BODY,
+    SAMPLE_OBJECTS,
+};
+use super::NetworkClient;
+use crate::error::Result;
+
+/// Default XML API endpoint
+pub const GCS_ENDPOINT: &str = "https://storage.googleapis.com";
+
+/// Checks GCS buckets for anonymous access
+#[derive(Debug, Clone)]
+pub struct GcsBucketChecker {
+    endpoint: String,
+    allow_write: bool,
+}
+
+impl Default for GcsBucketChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GcsBucketChecker {
+    /// Checker against `storage.googleapis.com`
+    pub fn new() -> Self {
+        Self {
+            endpoint: GCS_ENDPOINT.to_string(),
+            allow_write: true,
+        }
+    }
+
+    /// Use another XML API endpoint (an emulator or a private gateway)
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Whether the write probe may upload an object (default `true`)
+    pub fn with_write_probe(mut self, allow: bool) -> Self {
+        self.allow_write = allow;
+        self
+    }
+
+    /// Whether `name` is a valid bucket name, so guesses that cannot exist
+    /// are not sent
+    pub fn is_valid_name(name: &str) -> bool {
+        (3..=222).contains(&name.len())
+            && name.split('.').all(|part| (1..=63).contains(&part.len()))
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-_.".contains(c))
+            && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && name.ends_with(|c: char| c.is_ascii_alphanumeric())
+            && !name.starts_with("goog")
+    }
+
+    /// URL of `bucket`
+    pub fn bucket_url(&self, bucket: &str) -> String {
+        format!("{}/{}", self.endpoint, bucket)
+    }
+
+    /// The levels in `levels` that anonymous users have on `bucket`
+    pub async fn check(
+        &self,
+        client: &NetworkClient,
+        bucket: &str,
+        levels: &[StorageExposure],
+    ) -> Result<Vec<BucketExposure>> {
+        let resource = self.bucket_url(bucket);
+        let http = client.client();
+        let mut exposures = Vec::new();
+
+        // The listing also tells whether the bucket exists
+        let url = format!("{}/", resource);
+        let Some((status, body)) = send_probe(client, http.get(&url), &url).await? else {
+            return Ok(exposures);
+        };
+        if status == 404 || body.contains("<Code>NoSuchBucket</Code>") {
+            tracing::debug!("GCS bucket {} does not exist", bucket);
+            return Ok(exposures);
+        }
+        if levels.contains(&StorageExposure::PublicRead)
+            && status == 200
+            && body.contains("<ListBucketResult")
+        {
+            let mut exposure = BucketExposure::new(
+                StorageExposure::PublicRead,
+                &resource,
+                format!("GET {}", url),
+                status,
+                &body,
+            );
+            exposure.objects = xml_values(&body, "Key", SAMPLE_OBJECTS);
+            exposures.push(exposure);
+        }
+
+        if levels.contains(&StorageExposure::PublicWrite) {
+            if self.allow_write {
+                let url = format!("{}/{}", resource, probe_object_name());
+                let upload = http
+                    .put(&url)
+                    .header("Content-Type", "text/plain")
+                    .body(PROBE_OBJECT_BODY);
+                if let Some((status, body)) = send_probe(client, upload, &url).await? {
+                    if status == 200 || status == 201 {
+                        let mut exposure = BucketExposure::new(
+                            StorageExposure::PublicWrite,
+                            &resource,
+                            format!("PUT {}", url),
+                            status,
+                            &body,
+                        );
+                        let deleted = send_probe(client, http.delete(&url), &url).await?;
+                        exposure.cleaned_up =
+                            Some(deleted.is_some_and(|(status, _)| (200..300).contains(&status)));
+                        exposures.push(exposure);
+                    }
+                }
+            } else {
+                tracing::debug!("Skipping write probe of GCS bucket {}", bucket);
+            }
+        }
+
+        if levels.contains(&StorageExposure::FullControl) {
+            let url = format!("{}?acl", resource);
+            if let Some((status, body)) = send_probe(client, http.get(&url), &url).await? {
+                if status == 200 && body.contains("<AccessControlList") {
+                    exposures.push(BucketExposure::new(
+                        StorageExposure::FullControl,
+                        &resource,
+                        format!("GET {}", url),
+                        status,
+                        &body,
+                    ));
+                }
+            }
+        }
+
+        Ok(exposures)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use wiremock::matchers::{method, path, path_regex, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    pub(crate) const LISTING: &str = r#"<?xml version='1.0' encoding='UTF-8'?>
+<ListBucketResult xmlns="http://doc.s3.amazonaws.com/2006-03-01"><Name>acme-backup</Name><Prefix></Prefix><Marker></Marker><IsTruncated>false</IsTruncated><Contents><Key>db/2024-03-01.sql.gz</Key><Size>7340032</Size></Contents><Contents><Key>id_rsa</Key><Size>1679</Size></Contents></ListBucketResult>"#;
+
+    pub(crate) const ACL: &str = r#"<?xml version='1.0' encoding='UTF-8'?>
+<AccessControlList><Entries><Entry><Scope type="AllUsers"/><Permission>FULL_CONTROL</Permission></Entry></Entries></AccessControlList>"#;
+
+    const NO_SUCH_BUCKET: &str = "<?xml version='1.0' encoding='UTF-8'?><Error><Code>NoSuchBucket</Code><Message>The specified bucket does not exist.</Message></Error>";
+
+    const ACCESS_DENIED: &str = "<?xml version='1.0' encoding='UTF-8'?><Error><Code>AccessDenied</Code><Message>Anonymous caller does not have storage.objects.list access to the Google Cloud Storage bucket.</Message></Error>";
+
+    /// Mount GCS answers for `bucket` granting anonymous users `levels`
+    pub(crate) async fn mount_bucket(
+        server: &MockServer,
+        bucket: &str,
+        levels: &[StorageExposure],
+    ) {
+        let granted = |level| levels.contains(&level);
+        let listing = match granted(StorageExposure::PublicRead) {
+            true => ResponseTemplate::new(200).set_body_string(LISTING),
+            false => ResponseTemplate::new(403).set_body_string(ACCESS_DENIED),
+        };
+        Mock::given(method("GET"))
+            .and(path(format!("/{}/", bucket)))
+            .respond_with(listing)
+            .mount(server)
+            .await;
+        let upload = match granted(StorageExposure::PublicWrite) {
+            true => ResponseTemplate::new(200),
+            false => ResponseTemplate::new(403).set_body_string(ACCESS_DENIED),
+        };
+        let object = format!("^/{}/cxg-write-probe-[0-9a-f-]+\\.txt$", bucket);
+        Mock::given(method("PUT"))
+            .and(path_regex(object.as_str()))
+            .respond_with(upload)
+            .mount(server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path_regex(object.as_str()))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(server)
+            .await;
+        let acl = match granted(StorageExposure::FullControl) {
+            true => ResponseTemplate::new(200).set_body_string(ACL),
+            false => ResponseTemplate::new(403).set_body_string(ACCESS_DENIED),
+        };
+        Mock::given(method("GET"))
+            .and(path(format!("/{}", bucket)))
+            .and(query_param("acl", ""))
+            .respond_with(acl)
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404).set_body_string(NO_SUCH_BUCKET))
+            .with_priority(10)
+            .mount(server)
+            .await;
+    }
+
+    async fn check(server: &MockServer, bucket: &str) -> Vec<BucketExposure> {
+        let client = NetworkClient::new(Arc::new(crate::config::Config::default()))
+            .await
+            .unwrap();
+        GcsBucketChecker::new()
+            .with_endpoint(server.uri())
+            .check(&client, bucket, &StorageExposure::ALL)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_exposure_levels() {
+        let cases: [&[StorageExposure]; 4] = [
+            &[StorageExposure::PublicRead],
+            &[StorageExposure::PublicWrite],
+            &[StorageExposure::FullControl],
+            &StorageExposure::ALL,
+        ];
+        for levels in cases {
+            let server = MockServer::start().await;
+            mount_bucket(&server, "acme-backup", levels).await;
+            let exposures = check(&server, "acme-backup").await;
+            let found: Vec<StorageExposure> = exposures.iter().map(|e| e.level).collect();
+            assert_eq!(found, levels);
+            for exposure in &exposures {
+                assert_eq!(exposure.resource, format!("{}/acme-backup", server.uri()));
+                match exposure.level {
+                    StorageExposure::PublicRead => {
+                        assert_eq!(exposure.objects, ["db/2024-03-01.sql.gz", "id_rsa"])
+                    }
+                    StorageExposure::PublicWrite => assert_eq!(exposure.cleaned_up, Some(true)),
+                    StorageExposure::FullControl => {
+                        assert!(exposure.response.contains("FULL_CONTROL"))
+                    }
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_and_private_buckets() {
+        let server = MockServer::start().await;
+        mount_bucket(&server, "acme-private", &[]).await;
+        assert!(check(&server, "acme-private").await.is_empty());
+        // Listing, write probe and ACL read
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+
+        let server = MockServer::start().await;
+        mount_bucket(&server, "acme-private", &[]).await;
+        assert!(check(&server, "acme-gone").await.is_empty());
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_write_probe_can_be_disabled() {
+        let server = MockServer::start().await;
+        mount_bucket(&server, "acme-drop", &[StorageExposure::PublicWrite]).await;
+        let client = NetworkClient::new(Arc::new(crate::config::Config::default()))
+            .await
+            .unwrap();
+        let exposures = GcsBucketChecker::new()
+            .with_endpoint(server.uri())
+            .with_write_probe(false)
+            .check(&client, "acme-drop", &StorageExposure::ALL)
+            .await
+            .unwrap();
+        assert!(exposures.is_empty());
+        let requests = server.received_requests().await.unwrap();
+        assert!(requests.iter().all(|r| r.method.to_string() == "GET"));
+    }
+
+    #[test]
+    fn test_bucket_names() {
+        assert!(GcsBucketChecker::is_valid_name("acme-backup"));
+        assert!(GcsBucketChecker::is_valid_name("assets.acme.com"));
+        assert!(!GcsBucketChecker::is_valid_name("Acme"));
+        assert!(!GcsBucketChecker::is_valid_name("ab"));
+        assert!(!GcsBucketChecker::is_valid_name("-acme"));
+        assert!(!GcsBucketChecker::is_valid_name("google-things"));
+    }
+}
@@ -0,0 +1,595 @@
+//! gRPC server reflection probe
+//!
+//! gRPC services rarely answer anything a plain HTTP request can match on,
+//! but many of them leave the server reflection service enabled. The probe
+//! speaks HTTP/2 with prior knowledge (or over TLS with ALPN `h2`), asks the
+//! reflection service to list its services, then requests the descriptor of
+//! each service to recover its method names.
+//!
+//! Connection failures, servers that are not gRPC and servers without
+//! reflection are expected outcomes of a scan, so they are logged at debug
+//! level rather than surfaced as errors.
+
+use crate::error::{Error, Result};
+use h2::client::SendRequest;
+use h2::RecvStream;
+use hyper::body::Bytes;
+use hyper::{Method, Request};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::WebPkiSupportedAlgorithms;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+
+/// Reflection RPC paths, newest API first
+pub const REFLECTION_PATHS: [&str; 2] = [
+    "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+    "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+];
+
+/// Services whose descriptors are requested for method names
+const MAX_DESCRIBED_SERVICES: usize = 32;
+
+/// Largest reflection message accepted from a server
+const MAX_MESSAGE_LEN: usize = 4 * 1024 * 1024;
+
+/// What a gRPC server revealed to the probe
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GrpcReflection {
+    /// Whether the connection used TLS
+    pub tls: bool,
+    /// Reflection RPC that answered, `None` if the server has no reflection
+    pub api: Option<String>,
+    /// Fully qualified service names
+    pub services: Vec<String>,
+    /// Methods as `service/Method`
+    pub methods: Vec<String>,
+}
+
+impl GrpcReflection {
+    /// Matchable body: one service per line, then one method per line
+    pub fn body(&self) -> String {
+        let mut lines: Vec<&str> = self.services.iter().map(String::as_str).collect();
+        lines.extend(self.methods.iter().map(String::as_str));
+        lines.join("\n")
+    }
+}
+
+/// Result of asking one reflection RPC to list services
+enum Listing {
+    Listed(Vec<String>, Vec<String>),
+    Unsupported,
+    NotGrpc,
+}
+
+/// Lists services exposed through gRPC server reflection
+#[derive(Debug, Clone)]
+pub struct GrpcReflectionProbe {
+    timeout: Duration,
+}
+
+impl GrpcReflectionProbe {
+    /// Probe giving up on a target after `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+
+    /// Probe `address:port`, returning `None` unless a gRPC server answered
+    pub async fn probe(&self, address: &str, port: u16, tls: bool) -> Option<GrpcReflection> {
+        let addr = format!("{}:{}", address, port);
+        match tokio::time::timeout(self.timeout, self.probe_inner(address, port, tls)).await {
+            Ok(Ok(reflection)) => reflection,
+            Ok(Err(e)) => {
+                tracing::debug!("gRPC probe of {} (tls: {}) failed: {}", addr, tls, e);
+                None
+            }
+            Err(_) => {
+                tracing::debug!("gRPC probe of {} (tls: {}) timed out", addr, tls);
+                None
+            }
+        }
+    }
+
+    async fn probe_inner(
+        &self,
+        address: &str,
+        port: u16,
+        tls: bool,
+    ) -> Result<Option<GrpcReflection>> {
+        let authority = format!("{}:{}", address, port);
+        let stream = TcpStream::connect(&authority)
+            .await
+            .map_err(|e| Error::Network(format!("connect failed: {}", e)))?;
+
+        if !tls {
+            return reflect(stream, "http", &authority, false).await;
+        }
+
+        let server_name = ServerName::try_from(address.to_string())
+            .map_err(|e| Error::Network(format!("invalid server name: {}", e)))?;
+        let stream = tls_connector(&[b"h2"])
+            .connect(server_name, stream)
+            .await
+            .map_err(|e| Error::Network(format!("TLS handshake failed: {}", e)))?;
+        if stream.get_ref().1.alpn_protocol() != Some(b"h2") {
+            return Ok(None);
+        }
+        reflect(stream, "https", &authority, true).await
+    }
+}
+
+/// Run the reflection exchange over an established connection
+async fn reflect<T>(
+    io: T,
+    scheme: &str,
+    authority: &str,
+    tls: bool,
+) -> Result<Option<GrpcReflection>>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (client, connection) = h2::client::handshake(io)
+        .await
+        .map_err(|e| Error::Network(format!("HTTP/2 handshake failed: {}", e)))?;
+    let connection = tokio::spawn(async move {
+        let _ = connection.await;
+    });
+
+    let mut reflection = GrpcReflection {
+        tls,
+        ..Default::default()
+    };
+    let mut outcome = Ok(Some(()));
+    for path in REFLECTION_PATHS {
+        let uri = format!("{}://{}{}", scheme, authority, path);
+        match list_services(client.clone(), &uri).await {
+            Ok(Listing::Listed(services, methods)) => {
+                reflection.api = Some(path.to_string());
+                reflection.services = services;
+                reflection.methods = methods;
+                break;
+            }
+            Ok(Listing::Unsupported) => continue,
+            Ok(Listing::NotGrpc) => {
+                outcome = Ok(None);
+                break;
+            }
+            Err(e) => {
+                outcome = Err(e);
+                break;
+            }
+        }
+    }
+    connection.abort();
+
+    Ok(outcome?.map(|_| reflection))
+}
+
+/// Ask one reflection RPC for the service list and each service's methods
+async fn list_services(client: SendRequest<Bytes>, uri: &str) -> Result<Listing> {
+    let h2_error = |e: h2::Error| Error::Network(format!("HTTP/2 error: {}", e));
+
+    let mut client = client.ready().await.map_err(h2_error)?;
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        .body(())
+        .map_err(|e| Error::Network(format!("invalid gRPC request: {}", e)))?;
+    let (response, mut send) = client.send_request(request, false).map_err(h2_error)?;
+    send.send_data(frame(&proto::list_services_request()), false)
+        .map_err(h2_error)?;
+
+    let response = response.await.map_err(h2_error)?;
+    let headers = response.headers();
+    let is_grpc = headers
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/grpc"));
+    if !is_grpc {
+        return Ok(Listing::NotGrpc);
+    }
+    // Trailers-only responses carry a non-OK status (usually UNIMPLEMENTED)
+    if headers
+        .get("grpc-status")
+        .is_some_and(|status| status.as_bytes() != b"0")
+    {
+        return Ok(Listing::Unsupported);
+    }
+
+    let mut messages = MessageReader::new(response.into_body());
+    let services = match messages.next().await {
+        Some(message) => match proto::listed_services(&message) {
+            Some(services) => services,
+            None => return Ok(Listing::Unsupported),
+        },
+        None => return Ok(Listing::Unsupported),
+    };
+
+    let mut methods = Vec::new();
+    for service in services.iter().take(MAX_DESCRIBED_SERVICES) {
+        if send
+            .send_data(
+                frame(&proto::file_containing_symbol_request(service)),
+                false,
+            )
+            .is_err()
+        {
+            break;
+        }
+        let Some(message) = messages.next().await else {
+            break;
+        };
+        for method in proto::described_methods(&message, &services) {
+            if !methods.contains(&method) {
+                methods.push(method);
+            }
+        }
+    }
+    let _ = send.send_data(Bytes::new(), true);
+
+    Ok(Listing::Listed(services, methods))
+}
+
+/// Wrap a protobuf message in the gRPC length-prefixed framing
+fn frame(message: &[u8]) -> Bytes {
+    let mut framed = Vec::with_capacity(message.len() + 5);
+    framed.push(0);
+    framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    framed.extend_from_slice(message);
+    Bytes::from(framed)
+}
+
+/// Splits a gRPC response body into messages
+struct MessageReader {
+    body: RecvStream,
+    buffer: Vec<u8>,
+}
+
+impl MessageReader {
+    fn new(body: RecvStream) -> Self {
+        Self {
+            body,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Next uncompressed message, `None` once the stream ends or misbehaves
+    async fn next(&mut self) -> Option<Vec<u8>> {
+        loop {
+            if self.buffer.len() >= 5 {
+                let len = u32::from_be_bytes(self.buffer[1..5].try_into().ok()?) as usize;
+                if self.buffer[0] != 0 || len > MAX_MESSAGE_LEN {
+                    return None;
+                }
+                if self.buffer.len() >= 5 + len {
+                    let message = self.buffer[5..5 + len].to_vec();
+                    self.buffer.drain(..5 + len);
+                    return Some(message);
+                }
+            }
+
+            let chunk = self.body.data().await?.ok()?;
+            let _ = self.body.flow_control().release_capacity(chunk.len());
+            self.buffer.extend_from_slice(&chunk);
+        }
+    }
+}
+
+/// TLS connector offering the `alpn` protocols that accepts any server certificate
+pub(crate) fn tls_connector(alpn: &[&[u8]]) -> TlsConnector {
+    let algorithms = rustls::crypto::ring::default_provider().signature_verification_algorithms;
+    let mut config = rustls::ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AnyServerCertificate(algorithms)))
+        .with_no_client_auth();
+    config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+    TlsConnector::from(Arc::new(config))
+}
+
+/// Accepts any certificate; scanned services commonly use self-signed ones
+#[derive(Debug)]
+struct AnyServerCertificate(WebPkiSupportedAlgorithms);
+
+impl ServerCertVerifier for AnyServerCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.0)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.0)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_schemes()
+    }
+}
+
+/// Just enough protobuf for the reflection messages
+mod proto {
+    /// `ServerReflectionRequest.file_containing_symbol`
+    const REQUEST_FILE_CONTAINING_SYMBOL: u32 = 4;
+    /// `ServerReflectionRequest.list_services`
+    const REQUEST_LIST_SERVICES: u32 = 7;
+    /// `ServerReflectionResponse.file_descriptor_response`
+    const RESPONSE_FILE_DESCRIPTOR: u32 = 4;
+    /// `ServerReflectionResponse.list_services_response`
+    const RESPONSE_LIST_SERVICES: u32 = 6;
+
+    const WIRE_VARINT: u8 = 0;
+    const WIRE_FIXED64: u8 = 1;
+    const WIRE_LEN: u8 = 2;
+    const WIRE_FIXED32: u8 = 5;
+
+    pub(super) fn list_services_request() -> Vec<u8> {
+        encode_len(REQUEST_LIST_SERVICES, b"*")
+    }
+
+    pub(super) fn file_containing_symbol_request(symbol: &str) -> Vec<u8> {
+        encode_len(REQUEST_FILE_CONTAINING_SYMBOL, symbol.as_bytes())
+    }
+
+    /// Service names from a `ServerReflectionResponse`, `None` if it holds no listing
+    pub(super) fn listed_services(response: &[u8]) -> Option<Vec<String>> {
+        let listing = len_fields(response, RESPONSE_LIST_SERVICES)?
+            .into_iter()
+            .next()?;
+        let services = len_fields(listing, 1)?
+            .into_iter()
+            .filter_map(|service| len_fields(service, 1)?.into_iter().next())
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .collect();
+        Some(services)
+    }
+
+    /// `service/Method` names for `services` found in a file descriptor response
+    pub(super) fn described_methods(response: &[u8], services: &[String]) -> Vec<String> {
+        let mut methods = Vec::new();
+        let files = len_fields(response, RESPONSE_FILE_DESCRIPTOR)
+            .and_then(|r| r.into_iter().next())
+            .and_then(|r| len_fields(r, 1))
+            .unwrap_or_default();
+        for file in files {
+            let package = string_field(file, 2).unwrap_or_default();
+            for service in len_fields(file, 6).unwrap_or_default() {
+                let Some(name) = string_field(service, 1) else {
+                    continue;
+                };
+                let full_name = if package.is_empty() {
+                    name
+                } else {
+                    format!("{}.{}", package, name)
+                };
+                if !services.contains(&full_name) {
+                    continue;
+                }
+                for method in len_fields(service, 2).unwrap_or_default() {
+                    if let Some(method) = string_field(method, 1) {
+                        methods.push(format!("{}/{}", full_name, method));
+                    }
+                }
+            }
+        }
+        methods
+    }
+
+    fn string_field(message: &[u8], field: u32) -> Option<String> {
+        len_fields(message, field)?
+            .into_iter()
+            .next()
+            .map(|s| String::from_utf8_lossy(s).into_owned())
+    }
+
+    pub(super) fn encode_len(field: u32, value: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(value.len() + 6);
+        encode_varint(&mut out, u64::from((field << 3) | WIRE_LEN as u32));
+        encode_varint(&mut out, value.len() as u64);
+        out.extend_from_slice(value);
+        out
+    }
+
+    fn encode_varint(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn decode_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *buf.get(*pos)?;
+            *pos += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    /// All length-delimited values of `field`, `None` if `message` is malformed
+    fn len_fields(message: &[u8], field: u32) -> Option<Vec<&[u8]>> {
+        let mut values = Vec::new();
+        let mut pos = 0;
+        while pos < message.len() {
+            let key = decode_varint(message, &mut pos)?;
+            let skip = match (key & 0x7) as u8 {
+                WIRE_VARINT => {
+                    decode_varint(message, &mut pos)?;
+                    0
+                }
+                WIRE_FIXED64 => 8,
+                WIRE_FIXED32 => 4,
+                WIRE_LEN => {
+                    let len = decode_varint(message, &mut pos)? as usize;
+                    let value = message.get(pos..pos.checked_add(len)?)?;
+                    if key >> 3 == u64::from(field) {
+                        values.push(value);
+                    }
+                    len
+                }
+                _ => return None,
+            };
+            pos = pos.checked_add(skip)?;
+        }
+        (pos == message.len()).then_some(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::proto::encode_len;
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    fn list_response(services: &[&str]) -> Vec<u8> {
+        let listing: Vec<u8> = services
+            .iter()
+            .flat_map(|s| encode_len(1, &encode_len(1, s.as_bytes())))
+            .collect();
+        encode_len(6, &listing)
+    }
+
+    fn descriptor_response(package: &str, service: &str, methods: &[&str]) -> Vec<u8> {
+        let mut service_proto = encode_len(1, service.as_bytes());
+        for method in methods {
+            service_proto.extend(encode_len(2, &encode_len(1, method.as_bytes())));
+        }
+        let mut file = encode_len(1, b"service.proto");
+        file.extend(encode_len(2, package.as_bytes()));
+        file.extend(encode_len(6, &service_proto));
+        encode_len(4, &encode_len(1, &file))
+    }
+
+    /// HTTP/2 server answering the reflection RPC on `path` only
+    async fn reflection_server(path: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut connection = h2::server::handshake(socket).await.unwrap();
+            while let Some(Ok((request, mut respond))) = connection.accept().await {
+                tokio::spawn(async move {
+                    let builder =
+                        hyper::Response::builder().header("content-type", "application/grpc");
+                    if request.uri().path() != path {
+                        let response = builder.header("grpc-status", "12").body(()).unwrap();
+                        let _ = respond.send_response(response, true);
+                        return;
+                    }
+                    let mut send = respond
+                        .send_response(builder.body(()).unwrap(), false)
+                        .unwrap();
+                    let mut messages = MessageReader::new(request.into_body());
+                    while let Some(message) = messages.next().await {
+                        let reply = if message == proto::list_services_request() {
+                            list_response(&["grpc.health.v1.Health", "admin.Debug"])
+                        } else if message.ends_with(b"grpc.health.v1.Health") {
+                            descriptor_response("grpc.health.v1", "Health", &["Check", "Watch"])
+                        } else {
+                            descriptor_response("admin", "Debug", &["Dump"])
+                        };
+                        send.send_data(frame(&reply), false).unwrap();
+                    }
+                    let mut trailers = hyper::HeaderMap::new();
+                    trailers.insert("grpc-status", "0".parse().unwrap());
+                    let _ = send.send_trailers(trailers);
+                });
+            }
+        });
+        port
+    }
+
+    fn probe() -> GrpcReflectionProbe {
+        GrpcReflectionProbe::new(Duration::from_secs(5))
+    }
+
+    #[tokio::test]
+    async fn test_lists_services_and_methods() {
+        let port = reflection_server(REFLECTION_PATHS[0]).await;
+        let reflection = probe().probe("127.0.0.1", port, false).await.unwrap();
+
+        assert_eq!(reflection.api.as_deref(), Some(REFLECTION_PATHS[0]));
+        assert_eq!(
+            reflection.services,
+            vec!["grpc.health.v1.Health", "admin.Debug"]
+        );
+        assert_eq!(
+            reflection.methods,
+            vec![
+                "grpc.health.v1.Health/Check",
+                "grpc.health.v1.Health/Watch",
+                "admin.Debug/Dump"
+            ]
+        );
+        assert!(reflection.body().contains("admin.Debug/Dump"));
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_v1alpha() {
+        let port = reflection_server(REFLECTION_PATHS[1]).await;
+        let reflection = probe().probe("127.0.0.1", port, false).await.unwrap();
+        assert_eq!(reflection.api.as_deref(), Some(REFLECTION_PATHS[1]));
+        assert_eq!(reflection.services.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_non_grpc_servers_are_quiet() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let _ = socket
+                .write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")
+                .await;
+        });
+
+        assert!(probe().probe("127.0.0.1", port, false).await.is_none());
+
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed_port = closed.local_addr().unwrap().port();
+        drop(closed);
+        assert!(probe()
+            .probe("127.0.0.1", closed_port, false)
+            .await
+            .is_none());
+    }
+
+    #[test]
+    fn test_malformed_listing_is_rejected() {
+        assert_eq!(
+            proto::listed_services(&list_response(&["a.B"])),
+            Some(vec!["a.B".to_string()])
+        );
+        assert_eq!(proto::listed_services(&[0x32, 0x05, 0x0a]), None);
+    }
+}
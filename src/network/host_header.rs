@@ -0,0 +1,340 @@
+//! Host header injection detection
+//!
+//! Applications that build absolute URLs from the `Host` (or
+//! `X-Forwarded-Host`) header can be tricked into pointing password reset
+//! links, redirects and cached pages at an attacker-controlled domain.
+
+use super::NetworkClient;
+use crate::error::{Error, Result};
+use crate::matcher::HttpResponse;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Template variable that expands to each attacker-controlled Host payload
+pub const HOST_INJECTION_PAYLOAD: &str = "{{host_injection_payload}}";
+
+/// Headers sent for one Host header manipulation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostHeaderPayload {
+    /// Short description used in evidence
+    pub name: String,
+    /// Headers to add or replace on the request
+    pub headers: Vec<(String, String)>,
+}
+
+/// What a manipulated Host header changed in the response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostHeaderImpact {
+    /// An absolute URL in the body points at the injected host
+    AbsoluteUrl(String),
+    /// A password reset link points at the injected host
+    PasswordResetLink(String),
+    /// The `Location` header redirects to the injected host
+    Redirect(String),
+    /// A local Host value unlocked content the real host refuses
+    InternalExposure {
+        /// Status for the unmodified request
+        baseline_status: u16,
+        /// Status with the local Host value
+        status: u16,
+    },
+}
+
+impl std::fmt::Display for HostHeaderImpact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AbsoluteUrl(url) => write!(f, "absolute URL uses injected host: {}", url),
+            Self::PasswordResetLink(url) => {
+                write!(f, "password reset link uses injected host: {}", url)
+            }
+            Self::Redirect(url) => write!(f, "redirect to injected host: {}", url),
+            Self::InternalExposure {
+                baseline_status,
+                status,
+            } => write!(
+                f,
+                "local Host value returned {} (normally {})",
+                status, baseline_status
+            ),
+        }
+    }
+}
+
+/// A payload together with the impacts it caused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostHeaderFinding {
+    /// Payload that was sent
+    pub payload: HostHeaderPayload,
+    /// Impacts observed in the response
+    pub impacts: Vec<HostHeaderImpact>,
+}
+
+/// Sends requests with manipulated Host headers and inspects the responses
+#[derive(Debug, Clone)]
+pub struct HostHeaderAttackDetector {
+    attacker_host: String,
+}
+
+impl HostHeaderAttackDetector {
+    /// Detector using a unique, non-resolvable attacker host
+    pub fn new() -> Self {
+        Self {
+            attacker_host: format!("cxg{:08x}.invalid", fastrand::u32(..)),
+        }
+    }
+
+    /// Use a specific attacker host (e.g. an out-of-band interaction domain)
+    pub fn with_attacker_host(mut self, host: impl Into<String>) -> Self {
+        self.attacker_host = host.into();
+        self
+    }
+
+    /// Host injected by the payloads
+    pub fn attacker_host(&self) -> &str {
+        &self.attacker_host
+    }
+
+    /// Payloads that try to make the application use the attacker host
+    pub fn payloads(&self, target_host: &str) -> Vec<HostHeaderPayload> {
+        let attacker = &self.attacker_host;
+        vec![
+            HostHeaderPayload {
+                name: format!("Host: {}", attacker),
+                headers: vec![("Host".to_string(), attacker.clone())],
+            },
+            HostHeaderPayload {
+                name: format!("Host: {}:80@{}", target_host, attacker),
+                headers: vec![(
+                    "Host".to_string(),
+                    format!("{}:80@{}", target_host, attacker),
+                )],
+            },
+            HostHeaderPayload {
+                name: format!("X-Forwarded-Host: {}", attacker),
+                headers: vec![("X-Forwarded-Host".to_string(), attacker.clone())],
+            },
+        ]
+    }
+
+    /// Impacts of an attacker payload visible in `response`
+    pub fn analyze(&self, response: &HttpResponse) -> Vec<HostHeaderImpact> {
+        static ABSOLUTE_URL: OnceLock<Regex> = OnceLock::new();
+        let absolute_url = ABSOLUTE_URL
+            .get_or_init(|| Regex::new(r#"(?i)\bhttps?://[^\s"'<>]+"#).expect("valid regex"));
+
+        let mut impacts = Vec::new();
+        let body = response.body_string();
+        for found in absolute_url.find_iter(&body) {
+            let url = found.as_str();
+            if !self.points_at_attacker(url) {
+                continue;
+            }
+            let lower = url.to_lowercase();
+            let impact = if ["reset", "password", "recover", "token"]
+                .iter()
+                .any(|word| lower.contains(word))
+            {
+                HostHeaderImpact::PasswordResetLink(url.to_string())
+            } else {
+                HostHeaderImpact::AbsoluteUrl(url.to_string())
+            };
+            if !impacts.contains(&impact) {
+                impacts.push(impact);
+            }
+        }
+
+        if let Some((_, location)) = response
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("location"))
+        {
+            if self.points_at_attacker(location) {
+                impacts.push(HostHeaderImpact::Redirect(location.clone()));
+            }
+        }
+        impacts
+    }
+
+    /// Send every payload to `url` and report the ones that had an effect
+    ///
+    /// Also checks whether `Host: localhost` unlocks a page the real host
+    /// answers with 401/403/404.
+    pub async fn detect(
+        &self,
+        client: &NetworkClient,
+        url: &str,
+        base_headers: &HashMap<String, String>,
+    ) -> Result<Vec<HostHeaderFinding>> {
+        let target_host = url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .ok_or_else(|| {
+                Error::Network(format!("Invalid URL for Host header checks: {}", url))
+            })?;
+
+        let mut findings = Vec::new();
+        for payload in self.payloads(&target_host) {
+            let response = send(client, url, base_headers, &payload).await?;
+            let impacts = self.analyze(&response);
+            if !impacts.is_empty() {
+                findings.push(HostHeaderFinding { payload, impacts });
+            }
+        }
+
+        let baseline = client.get_with_headers(url, base_headers.clone()).await?;
+        let baseline_status = baseline.status().as_u16();
+        if matches!(baseline_status, 401 | 403 | 404) {
+            let payload = HostHeaderPayload {
+                name: "Host: localhost".to_string(),
+                headers: vec![("Host".to_string(), "localhost".to_string())],
+            };
+            let response = send(client, url, base_headers, &payload).await?;
+            if (200..300).contains(&response.status) {
+                findings.push(HostHeaderFinding {
+                    payload,
+                    impacts: vec![HostHeaderImpact::InternalExposure {
+                        baseline_status,
+                        status: response.status,
+                    }],
+                });
+            }
+        }
+
+        Ok(findings)
+    }
+
+    fn points_at_attacker(&self, url: &str) -> bool {
+        url::Url::parse(url)
+            .ok()
+            .and_then(|u| {
+                u.host_str()
+                    .map(|h| h.eq_ignore_ascii_case(&self.attacker_host))
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl Default for HostHeaderAttackDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn send(
+    client: &NetworkClient,
+    url: &str,
+    base_headers: &HashMap<String, String>,
+    payload: &HostHeaderPayload,
+) -> Result<HttpResponse> {
+    let mut headers = base_headers.clone();
+    headers.extend(payload.headers.iter().cloned());
+
+    let start = std::time::Instant::now();
+    let response = client.get_with_headers(url, headers).await?;
+    super::read_response(response, start.elapsed()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::sync::Arc;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+    /// Renders a password reset link built from the request's Host header
+    struct ResetPage {
+        trust_host: bool,
+    }
+
+    impl Respond for ResetPage {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let host = if self.trust_host {
+                request
+                    .headers
+                    .get(&"x-forwarded-host".into())
+                    .or_else(|| request.headers.get(&"host".into()))
+                    .map(|v| v.last().as_str().to_string())
+                    .unwrap_or_default()
+            } else {
+                "shop.example.com".to_string()
+            };
+            ResponseTemplate::new(200).set_body_string(format!(
+                r#"<html><a href="http://{}/account/reset?token=abc">Reset password</a>
+                <a href="/about">About</a></html>"#,
+                host
+            ))
+        }
+    }
+
+    async fn detect_against(
+        trust_host: bool,
+    ) -> (HostHeaderAttackDetector, Vec<HostHeaderFinding>) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResetPage { trust_host })
+            .mount(&server)
+            .await;
+
+        let client = NetworkClient::new(Arc::new(Config::default()))
+            .await
+            .unwrap();
+        let detector = HostHeaderAttackDetector::new().with_attacker_host("attacker.com");
+        let findings = detector
+            .detect(&client, &server.uri(), &HashMap::new())
+            .await
+            .unwrap();
+        (detector, findings)
+    }
+
+    #[tokio::test]
+    async fn test_reset_link_poisoning_detected() {
+        let (detector, findings) = detect_against(true).await;
+
+        let names: Vec<_> = findings.iter().map(|f| f.payload.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Host: attacker.com",
+                "Host: 127.0.0.1:80@attacker.com",
+                "X-Forwarded-Host: attacker.com"
+            ]
+        );
+        assert_eq!(
+            findings[0].impacts,
+            vec![HostHeaderImpact::PasswordResetLink(format!(
+                "http://{}/account/reset?token=abc",
+                detector.attacker_host()
+            ))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fixed_host_is_not_flagged() {
+        let (_, findings) = detect_against(false).await;
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_redirect_and_foreign_hosts() {
+        let detector = HostHeaderAttackDetector::new().with_attacker_host("evil.test");
+        let response = HttpResponse {
+            status: 302,
+            headers: vec![(
+                "Location".to_string(),
+                "https://evil.test/login".to_string(),
+            )],
+            body: br#"<a href="https://evil.test.example.com/">x</a>"#.to_vec(),
+            response_time: Default::default(),
+            segments: Vec::new(),
+            url: None,
+        };
+        assert_eq!(
+            detector.analyze(&response),
+            vec![HostHeaderImpact::Redirect(
+                "https://evil.test/login".to_string()
+            )]
+        );
+    }
+}
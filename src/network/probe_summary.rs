@@ -0,0 +1,267 @@
+//! Per-target liveness summary (`cxg scan --probe-summary`)
+//!
+//! Nothing is sent for the summary: while it is enabled, [`NetworkClient`]
+//! notes what each request the scan already makes reveals about its
+//! endpoint (`host:port`):
+//!
+//! - whether a connection succeeded, or the last error if none did
+//! - the lowest HTTP status code received
+//! - the first `Server` header
+//! - the `<title>` of the first HTML page
+//! - the common name of the TLS certificate
+//!
+//! A target without a port is summarized over every port it was reached on.
+//!
+//! [`NetworkClient`]: super::NetworkClient
+
+use super::decode;
+use crate::types::{Target, TargetProbe};
+use openssl::nid::Nid;
+use openssl::x509::X509;
+use parking_lot::Mutex;
+use reqwest::header::{HeaderMap, CONTENT_ENCODING, CONTENT_TYPE, SERVER};
+use reqwest::Url;
+use std::collections::BTreeMap;
+
+/// Longest title kept, in characters
+const MAX_TITLE_CHARS: usize = 120;
+
+/// What the scan has seen of one `host:port`
+#[derive(Debug, Clone, Default)]
+struct Endpoint {
+    reachable: bool,
+    lowest_status: Option<u16>,
+    server: Option<String>,
+    title: Option<String>,
+    tls_cn: Option<String>,
+    error: Option<String>,
+}
+
+/// Collects liveness data from responses passing through the client
+#[derive(Debug, Default)]
+pub struct ProbeRecorder {
+    endpoints: Mutex<BTreeMap<(String, u16), Endpoint>>,
+}
+
+impl ProbeRecorder {
+    /// Empty recorder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Note the status, `Server` header and TLS certificate of a response to `url`
+    pub fn record_response(&self, url: &Url, response: &reqwest::Response) {
+        let Some(key) = endpoint_key(url) else {
+            return;
+        };
+        // After a redirect to another endpoint the certificate is not this one's
+        let same_endpoint = endpoint_key(response.url()).as_ref() == Some(&key);
+        let mut endpoints = self.endpoints.lock();
+        let endpoint = endpoints.entry(key).or_default();
+        endpoint.reachable = true;
+        endpoint.error = None;
+
+        let status = response.status().as_u16();
+        endpoint.lowest_status = Some(endpoint.lowest_status.map_or(status, |s| s.min(status)));
+        if endpoint.server.is_none() {
+            endpoint.server = response
+                .headers()
+                .get(SERVER)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty());
+        }
+        if same_endpoint && endpoint.tls_cn.is_none() {
+            endpoint.tls_cn = response
+                .extensions()
+                .get::<reqwest::tls::TlsInfo>()
+                .and_then(|info| info.peer_certificate())
+                .and_then(certificate_cn);
+        }
+    }
+
+    /// Whether the body of this response should be read for a page title
+    pub fn wants_title(&self, url: &Url, headers: &HeaderMap) -> bool {
+        let is_html = headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.to_ascii_lowercase().contains("text/html"));
+        is_html
+            && endpoint_key(url).is_some_and(|key| {
+                self.endpoints
+                    .lock()
+                    .get(&key)
+                    .is_none_or(|endpoint| endpoint.title.is_none())
+            })
+    }
+
+    /// Note the `<title>` of an HTML body received from `url`
+    pub fn record_body(&self, url: &Url, headers: &HeaderMap, body: &[u8]) {
+        let Some(key) = endpoint_key(url) else {
+            return;
+        };
+        let body = match headers.get(CONTENT_ENCODING).and_then(|v| v.to_str().ok()) {
+            Some(encoding) => decode::decompress(body.to_vec(), encoding),
+            None => body.to_vec(),
+        };
+        let content_type = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok());
+        let Some(title) = html_title(&decode::decode_text(&body, content_type)) else {
+            return;
+        };
+        let mut endpoints = self.endpoints.lock();
+        let endpoint = endpoints.entry(key).or_default();
+        if endpoint.title.is_none() {
+            endpoint.title = Some(title);
+        }
+    }
+
+    /// Note a request to `url` that got no response
+    pub fn record_error(&self, url: &Url, error: &reqwest::Error) {
+        let Some(key) = endpoint_key(url) else {
+            return;
+        };
+        let mut endpoints = self.endpoints.lock();
+        let endpoint = endpoints.entry(key).or_default();
+        if !endpoint.reachable {
+            endpoint.error = Some(error_message(error));
+        }
+    }
+
+    /// Summary for `target`; a target the scan never contacted is unreachable
+    /// without an error
+    pub fn summarize(&self, target: &Target) -> TargetProbe {
+        let host = target
+            .address
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase();
+        let mut probe = TargetProbe {
+            target: match target.port {
+                Some(port) => format!("{}:{}", target.address, port),
+                None => target.address.clone(),
+            },
+            ..TargetProbe::default()
+        };
+        let endpoints = self.endpoints.lock();
+        let seen = endpoints
+            .iter()
+            .filter(|((h, port), _)| *h == host && target.port.is_none_or(|p| p == *port))
+            .map(|(_, endpoint)| endpoint);
+        for endpoint in seen {
+            probe.reachable |= endpoint.reachable;
+            probe.lowest_status = match (probe.lowest_status, endpoint.lowest_status) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            probe.server = probe.server.or_else(|| endpoint.server.clone());
+            probe.title = probe.title.or_else(|| endpoint.title.clone());
+            probe.tls_cn = probe.tls_cn.or_else(|| endpoint.tls_cn.clone());
+            probe.error = probe.error.or_else(|| endpoint.error.clone());
+        }
+        if probe.reachable {
+            probe.error = None;
+        }
+        probe
+    }
+}
+
+/// `(host, port)` a request to `url` connects to
+fn endpoint_key(url: &Url) -> Option<(String, u16)> {
+    let host = url
+        .host_str()?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_ascii_lowercase();
+    Some((host, url.port_or_known_default()?))
+}
+
+/// Innermost cause of a request error, e.g. "Connection refused (os error 111)"
+fn error_message(error: &reqwest::Error) -> String {
+    let mut source: &dyn std::error::Error = error;
+    while let Some(inner) = source.source() {
+        source = inner;
+    }
+    source.to_string()
+}
+
+/// Common name from a DER-encoded certificate
+fn certificate_cn(der: &[u8]) -> Option<String> {
+    let cert = X509::from_der(der).ok()?;
+    let entry = cert.subject_name().entries_by_nid(Nid::COMMONNAME).next()?;
+    entry.data().as_utf8().ok().map(|cn| cn.to_string())
+}
+
+/// Text of the first `<title>` element, with whitespace collapsed
+fn html_title(html: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets valid in the original
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = html[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if title.is_empty() {
+        return None;
+    }
+    Some(title.chars().take(MAX_TITLE_CHARS).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Protocol;
+
+    #[test]
+    fn test_html_title() {
+        assert_eq!(
+            html_title("<html><HEAD><Title lang=\"en\">\n  Admin\n  Console </title>").as_deref(),
+            Some("Admin Console")
+        );
+        assert_eq!(html_title("<title></title>"), None);
+        assert_eq!(html_title("<h1>no title</h1>"), None);
+    }
+
+    #[test]
+    fn test_portless_target_is_summarized_over_every_port() {
+        let recorder = ProbeRecorder::new();
+        recorder.endpoints.lock().extend([
+            (
+                ("example.com".to_string(), 80),
+                Endpoint {
+                    reachable: true,
+                    lowest_status: Some(301),
+                    server: Some("nginx".to_string()),
+                    ..Endpoint::default()
+                },
+            ),
+            (
+                ("example.com".to_string(), 443),
+                Endpoint {
+                    reachable: true,
+                    lowest_status: Some(200),
+                    title: Some("Welcome".to_string()),
+                    tls_cn: Some("example.com".to_string()),
+                    ..Endpoint::default()
+                },
+            ),
+        ]);
+
+        let probe = recorder.summarize(&Target::new("example.com", Protocol::Http));
+        assert!(probe.reachable);
+        assert_eq!(probe.lowest_status, Some(200));
+        assert_eq!(probe.server.as_deref(), Some("nginx"));
+        assert_eq!(probe.title.as_deref(), Some("Welcome"));
+        assert_eq!(probe.tls_cn.as_deref(), Some("example.com"));
+
+        let http_only = recorder.summarize(&Target::with_port("example.com", 80, Protocol::Http));
+        assert_eq!(http_only.target, "example.com:80");
+        assert_eq!(http_only.lowest_status, Some(301));
+        assert_eq!(http_only.tls_cn, None);
+
+        let unseen = recorder.summarize(&Target::new("other.com", Protocol::Http));
+        assert_eq!(unseen.target, "other.com");
+        assert!(!unseen.reachable);
+    }
+}
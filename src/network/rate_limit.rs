@@ -0,0 +1,197 @@
+//! Request rate limiting across the whole scan, per domain and per template
+//!
+//! Every request waits on each limit that applies to it, so the effective
+//! rate is the minimum of the global, per-domain and per-template limits.
+//! Template limits apply to requests issued through [`NetworkClient`]
+//! while the template is running (see [`with_template`]).
+//!
+//! [`NetworkClient`]: super::NetworkClient
+
+use dashmap::DashMap;
+use governor::{
+    clock::DefaultClock,
+    state::{InMemoryState, NotKeyed},
+    Quota,
+};
+use std::future::Future;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::time::Duration;
+
+type DirectLimiter = governor::RateLimiter<NotKeyed, InMemoryState, DefaultClock>;
+
+/// Reference interval for jitter when no rate limit applies to a request
+const UNLIMITED_JITTER_BASE: Duration = Duration::from_millis(100);
+
+tokio::task_local! {
+    static CURRENT_TEMPLATE: String;
+}
+
+/// Run `future` with its requests attributed to `template_id`
+pub async fn with_template<F: Future>(template_id: &str, future: F) -> F::Output {
+    CURRENT_TEMPLATE
+        .scope(template_id.to_string(), future)
+        .await
+}
+
+/// Template whose requests are being issued on this task, if any
+pub fn current_template() -> Option<String> {
+    CURRENT_TEMPLATE.try_with(|id| id.clone()).ok()
+}
+
+struct Limit {
+    rate: u32,
+    limiter: DirectLimiter,
+}
+
+impl Limit {
+    fn new(rate: u32) -> Arc<Self> {
+        let rate = rate.max(1);
+        let quota = Quota::per_second(NonZeroU32::new(rate).expect("rate is at least 1"));
+        Arc::new(Self {
+            rate,
+            limiter: DirectLimiter::direct(quota),
+        })
+    }
+}
+
+/// Global, per-domain and per-template request limits (requests/second)
+pub struct RateLimiter {
+    global: Option<Arc<Limit>>,
+    per_domain: Option<u32>,
+    domains: DashMap<String, Arc<Limit>>,
+    templates: DashMap<String, Arc<Limit>>,
+    jitter_factor: f64,
+}
+
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("global", &self.global.as_ref().map(|l| l.rate))
+            .field("per_domain", &self.per_domain)
+            .field("templates", &self.templates.len())
+            .field("jitter_factor", &self.jitter_factor)
+            .finish()
+    }
+}
+
+impl RateLimiter {
+    /// Limiter with optional global and per-domain rates; `0` means unlimited
+    pub fn new(global: Option<u32>, per_domain: Option<u32>) -> Self {
+        Self {
+            global: global.filter(|r| *r > 0).map(Limit::new),
+            per_domain: per_domain.filter(|r| *r > 0),
+            domains: DashMap::new(),
+            templates: DashMap::new(),
+            jitter_factor: 0.0,
+        }
+    }
+
+    /// Add a random delay of up to `factor` times the request interval
+    pub fn with_jitter(mut self, factor: f64) -> Self {
+        self.jitter_factor = factor.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Limit requests made while `template_id` runs
+    pub fn set_template_limit(&self, template_id: &str, rate: u32) {
+        if rate == 0 {
+            self.templates.remove(template_id);
+        } else {
+            self.templates
+                .insert(template_id.to_string(), Limit::new(rate));
+        }
+    }
+
+    /// Wait until a request to `host` is allowed under the global and domain limits
+    pub async fn acquire(&self, host: &str) {
+        self.wait(host, None).await
+    }
+
+    /// Wait until a request to `host` by `template_id` is allowed
+    ///
+    /// Uses the minimum of the global, per-domain and per-template limits,
+    /// then applies jitter.
+    pub async fn acquire_for(&self, host: &str, template_id: &str) {
+        self.wait(host, Some(template_id)).await
+    }
+
+    async fn wait(&self, host: &str, template_id: Option<&str>) {
+        let template = template_id.and_then(|id| self.templates.get(id).map(|l| l.clone()));
+        let domain = self.per_domain.map(|rate| {
+            self.domains
+                .entry(host.to_string())
+                .or_insert_with(|| Limit::new(rate))
+                .clone()
+        });
+
+        // Most specific first, so a slow template does not hold global capacity
+        let limits: Vec<Arc<Limit>> = [template, domain, self.global.clone()]
+            .into_iter()
+            .flatten()
+            .collect();
+        for limit in &limits {
+            limit.limiter.until_ready().await;
+        }
+
+        let slowest = limits.iter().map(|l| l.rate).min();
+        let delay = self.jitter(slowest);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Random delay proportional to the interval of the slowest applicable limit
+    fn jitter(&self, rate: Option<u32>) -> Duration {
+        if self.jitter_factor <= 0.0 {
+            return Duration::ZERO;
+        }
+        let interval = rate
+            .map(|r| Duration::from_secs(1) / r)
+            .unwrap_or(UNLIMITED_JITTER_BASE);
+        interval.mul_f64(self.jitter_factor * fastrand::f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_template_limit_of_one_per_second() {
+        let limiter = RateLimiter::new(Some(100), Some(50));
+        limiter.set_template_limit("slow-template", 1);
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire_for("example.com", "slow-template").await;
+        }
+        // First request is immediate, then one per second
+        assert!(start.elapsed() >= Duration::from_millis(1900));
+
+        // Other templates only see the global and domain limits
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire_for("example.com", "fast-template").await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_attributed_to_running_template() {
+        assert_eq!(current_template(), None);
+        let id = with_template("tmpl", async { current_template() }).await;
+        assert_eq!(id.as_deref(), Some("tmpl"));
+    }
+
+    #[test]
+    fn test_jitter_is_bounded_by_interval() {
+        let limiter = RateLimiter::new(None, None).with_jitter(0.5);
+        for _ in 0..100 {
+            assert!(limiter.jitter(Some(10)) <= Duration::from_millis(50));
+            assert!(limiter.jitter(None) <= UNLIMITED_JITTER_BASE / 2);
+        }
+        assert_eq!(RateLimiter::new(None, None).jitter(Some(1)), Duration::ZERO);
+    }
+}
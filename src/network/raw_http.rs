@@ -0,0 +1,530 @@
+//! Raw HTTP exchanges on one persistent connection
+//!
+//! Request smuggling (CL.TE, TE.CL) only shows when several requests share
+//! a connection: front-end and back-end disagree on where the first request
+//! ends, and its leftover bytes prefix the next one. The HTTP client always
+//! frames requests itself and may pool or retry connections, so these
+//! exchanges write template bytes verbatim, in order, on a single connection
+//! and read each response segment with its own [`ReadUntil`] strategy,
+//! timing every segment.
+//!
+//! Bytes read past the end of a framed response (a pipelined or smuggled
+//! second response) are kept for the next segment rather than dropped.
+//!
+//! Connection failures are expected outcomes of a scan, so they are logged
+//! at debug level rather than surfaced as errors.
+
+use super::connect_via_proxy;
+use rustls::pki_types::ServerName;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest segment kept; reading stops once it is reached
+const MAX_SEGMENT_LEN: usize = 1024 * 1024;
+
+/// When to stop reading the response to a payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ReadUntil {
+    /// The header block plus the body its `Content-Length` or chunked encoding frames
+    #[default]
+    Headers,
+    /// The server closes the connection
+    Close,
+    /// Whatever arrives within this long
+    Timeout(Duration),
+}
+
+impl FromStr for ReadUntil {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "headers" => Ok(ReadUntil::Headers),
+            "close" => Ok(ReadUntil::Close),
+            _ => s
+                .strip_prefix("timeout:")
+                .and_then(|ms| ms.parse::<u64>().ok())
+                .map(|ms| ReadUntil::Timeout(Duration::from_millis(ms)))
+                .ok_or_else(|| {
+                    format!(
+                        "invalid read-until '{}' (expected headers, close or timeout:<ms>)",
+                        s
+                    )
+                }),
+        }
+    }
+}
+
+impl fmt::Display for ReadUntil {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadUntil::Headers => write!(f, "headers"),
+            ReadUntil::Close => write!(f, "close"),
+            ReadUntil::Timeout(d) => write!(f, "timeout:{}", d.as_millis()),
+        }
+    }
+}
+
+impl TryFrom<String> for ReadUntil {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ReadUntil> for String {
+    fn from(read_until: ReadUntil) -> Self {
+        read_until.to_string()
+    }
+}
+
+/// Bytes written verbatim, and how to read what comes back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawPayload {
+    /// Request bytes, sent as-is
+    pub data: Vec<u8>,
+    /// Read strategy for its response
+    pub read_until: ReadUntil,
+}
+
+impl RawPayload {
+    /// Payload read with `read_until`
+    pub fn new(data: impl Into<Vec<u8>>, read_until: ReadUntil) -> Self {
+        Self {
+            data: data.into(),
+            read_until,
+        }
+    }
+}
+
+/// What was read after one payload
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseSegment {
+    /// Bytes read for this payload
+    pub data: Vec<u8>,
+    /// Time from the end of the write to the first byte, if any arrived
+    pub first_byte: Option<Duration>,
+    /// Time from the end of the write until reading stopped
+    pub elapsed: Duration,
+    /// The server closed the connection during the read
+    pub closed: bool,
+    /// Reading stopped at the deadline rather than on its strategy
+    pub timed_out: bool,
+}
+
+impl ResponseSegment {
+    /// The segment as text (lossy for binary data)
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.data).into_owned()
+    }
+
+    /// Status code of the status line the segment starts with
+    pub fn status(&self) -> Option<u16> {
+        let line = self.data.split(|&b| b == b'\n').next()?;
+        let line = std::str::from_utf8(line).ok()?;
+        line.strip_prefix("HTTP/")?
+            .split_whitespace()
+            .nth(1)?
+            .parse()
+            .ok()
+    }
+}
+
+/// Stream a raw exchange runs over, plain TCP or TLS
+pub trait RawStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> RawStream for T {}
+
+/// Sends raw payloads on one connection and reads a segment for each
+#[derive(Debug, Clone)]
+pub struct RawConnection {
+    timeout: Duration,
+    half_close: bool,
+}
+
+impl RawConnection {
+    /// Exchange where connecting, and each `headers` or `close` read, gives up after `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            half_close: false,
+        }
+    }
+
+    /// Shut down the write side after the last payload is sent
+    pub fn with_half_close(mut self, half_close: bool) -> Self {
+        self.half_close = half_close;
+        self
+    }
+
+    /// Connect to `host:port` (through `proxy` when set), over TLS when `tls` is set
+    pub async fn connect(
+        &self,
+        host: &str,
+        port: u16,
+        tls: bool,
+        proxy: Option<&str>,
+    ) -> Option<Box<dyn RawStream>> {
+        let tcp =
+            match tokio::time::timeout(self.timeout, connect_via_proxy(host, port, proxy)).await {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => {
+                    tracing::debug!("Raw HTTP connect to {}:{} failed: {}", host, port, e);
+                    return None;
+                }
+                Err(_) => {
+                    tracing::debug!("Raw HTTP connect to {}:{} timed out", host, port);
+                    return None;
+                }
+            };
+        if !tls {
+            return Some(Box::new(tcp));
+        }
+
+        let server_name = ServerName::try_from(host.to_string()).ok()?;
+        let connector = super::grpc::tls_connector(&[b"http/1.1"]);
+        match tokio::time::timeout(self.timeout, connector.connect(server_name, tcp)).await {
+            Ok(Ok(stream)) => Some(Box::new(stream)),
+            Ok(Err(e)) => {
+                tracing::debug!("TLS handshake with {}:{} failed: {}", host, port, e);
+                None
+            }
+            Err(_) => {
+                tracing::debug!("TLS handshake with {}:{} timed out", host, port);
+                None
+            }
+        }
+    }
+
+    /// Write each payload in turn and read its segment
+    ///
+    /// Stops at the first failed write or once the server has closed the
+    /// connection, so later payloads get no segment.
+    pub async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        stream: S,
+        payloads: &[RawPayload],
+    ) -> Vec<ResponseSegment> {
+        let (mut reader, mut writer) = tokio::io::split(stream);
+        let mut segments = Vec::new();
+        let mut pending = Vec::new();
+
+        for (i, payload) in payloads.iter().enumerate() {
+            if let Err(e) = writer.write_all(&payload.data).await {
+                tracing::debug!("Raw HTTP write {} failed: {}", i + 1, e);
+                break;
+            }
+            let _ = writer.flush().await;
+            if self.half_close && i + 1 == payloads.len() {
+                let _ = writer.shutdown().await;
+            }
+
+            let segment = self
+                .read_segment(&mut reader, &mut pending, payload.read_until)
+                .await;
+            let closed = segment.closed;
+            segments.push(segment);
+            if closed {
+                break;
+            }
+        }
+        segments
+    }
+
+    /// Read one segment, starting with bytes left over from the previous one
+    async fn read_segment<R: AsyncRead + Unpin>(
+        &self,
+        reader: &mut R,
+        pending: &mut Vec<u8>,
+        read_until: ReadUntil,
+    ) -> ResponseSegment {
+        let started = Instant::now();
+        let deadline = started
+            + match read_until {
+                ReadUntil::Timeout(limit) => limit,
+                _ => self.timeout,
+            };
+        let mut segment = ResponseSegment {
+            data: std::mem::take(pending),
+            ..Default::default()
+        };
+        if !segment.data.is_empty() {
+            segment.first_byte = Some(Duration::ZERO);
+        }
+
+        let mut buffer = vec![0u8; 8192];
+        loop {
+            if read_until == ReadUntil::Headers {
+                if let Some(end) = response_end(&segment.data) {
+                    *pending = segment.data.split_off(end);
+                    break;
+                }
+            }
+            if segment.data.len() >= MAX_SEGMENT_LEN {
+                break;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match tokio::time::timeout(remaining, reader.read(&mut buffer)).await {
+                Ok(Ok(0)) => {
+                    segment.closed = true;
+                    break;
+                }
+                Ok(Ok(n)) => {
+                    segment.first_byte.get_or_insert_with(|| started.elapsed());
+                    segment.data.extend_from_slice(&buffer[..n]);
+                }
+                Ok(Err(e)) => {
+                    tracing::debug!("Raw HTTP read failed: {}", e);
+                    segment.closed = true;
+                    break;
+                }
+                Err(_) => {
+                    segment.timed_out = true;
+                    break;
+                }
+            }
+        }
+        segment.elapsed = started.elapsed();
+        segment
+    }
+}
+
+/// Length of the complete HTTP response at the start of `data`, if it has fully arrived
+///
+/// Interim `1xx` responses are included with the response that follows them.
+/// Responses framed by neither `Content-Length` nor chunked encoding end when
+/// the connection does, so they never complete here.
+pub fn response_end(data: &[u8]) -> Option<usize> {
+    let mut start = 0;
+    loop {
+        let rest = &data[start..];
+        let header_len = find(rest, b"\r\n\r\n")? + 4;
+        let head = String::from_utf8_lossy(&rest[..header_len]).to_ascii_lowercase();
+        let status = ResponseSegment {
+            data: rest[..header_len].to_vec(),
+            ..Default::default()
+        }
+        .status()?;
+
+        if (100..200).contains(&status) && status != 101 {
+            start += header_len;
+            continue;
+        }
+        if status == 204 || status == 304 || status == 101 {
+            return Some(start + header_len);
+        }
+
+        let header = |name: &str| {
+            head.lines()
+                .skip(1)
+                .filter_map(|line| line.split_once(':'))
+                .find(|(key, _)| key.trim() == name)
+                .map(|(_, value)| value.trim().to_string())
+        };
+        if header("transfer-encoding").is_some_and(|te| te.contains("chunked")) {
+            return chunked_end(&rest[header_len..]).map(|len| start + header_len + len);
+        }
+        let length: usize = header("content-length")?.parse().ok()?;
+        return (rest.len() >= header_len + length).then_some(start + header_len + length);
+    }
+}
+
+/// Length of a complete chunked body at the start of `body`
+fn chunked_end(body: &[u8]) -> Option<usize> {
+    let mut offset = 0;
+    loop {
+        let line_len = find(&body[offset..], b"\r\n")?;
+        let size_line = std::str::from_utf8(&body[offset..offset + line_len]).ok()?;
+        let size = usize::from_str_radix(size_line.split(';').next()?.trim(), 16).ok()?;
+        offset += line_len + 2;
+        if size == 0 {
+            // Trailer fields, then the empty line
+            loop {
+                let line_len = find(&body[offset..], b"\r\n")?;
+                offset += line_len + 2;
+                if line_len == 0 {
+                    return Some(offset);
+                }
+            }
+        }
+        offset += size + 2;
+        if offset > body.len() {
+            return None;
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    /// Back-end that honours `Transfer-Encoding` over `Content-Length`, one
+    /// connection at a time; bytes past a chunked body prefix the next request
+    pub(crate) async fn spawn_desync_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buffer = Vec::new();
+                let mut chunk = [0u8; 4096];
+                loop {
+                    while let Some((method, consumed)) = parse_request(&buffer) {
+                        buffer.drain(..consumed);
+                        let response = match method.as_str() {
+                            "GET" | "POST" => {
+                                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".to_string()
+                            }
+                            other => {
+                                let body = format!("Unrecognized method {}", other);
+                                format!(
+                                    "HTTP/1.1 405 Method Not Allowed\r\nContent-Length: {}\r\n\r\n{}",
+                                    body.len(),
+                                    body
+                                )
+                            }
+                        };
+                        if socket.write_all(response.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                    match socket.read(&mut chunk).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+                    }
+                }
+            }
+        });
+        port
+    }
+
+    /// Method and length of the complete request at the start of `buffer`
+    fn parse_request(buffer: &[u8]) -> Option<(String, usize)> {
+        let header_len = find(buffer, b"\r\n\r\n")? + 4;
+        let head = String::from_utf8_lossy(&buffer[..header_len]).to_ascii_lowercase();
+        let method = String::from_utf8_lossy(buffer)
+            .split_whitespace()
+            .next()?
+            .to_string();
+        if head.contains("transfer-encoding: chunked") {
+            return chunked_end(&buffer[header_len..]).map(|len| (method, header_len + len));
+        }
+        let length = head
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .map_or(0, |value| value.trim().parse().unwrap_or(0));
+        (buffer.len() >= header_len + length).then_some((method, header_len + length))
+    }
+
+    #[test]
+    fn test_read_until_parsing() {
+        assert_eq!("headers".parse(), Ok(ReadUntil::Headers));
+        assert_eq!("close".parse(), Ok(ReadUntil::Close));
+        assert_eq!(
+            "timeout:250".parse(),
+            Ok(ReadUntil::Timeout(Duration::from_millis(250)))
+        );
+        assert!("timeout:soon".parse::<ReadUntil>().is_err());
+        assert_eq!(
+            ReadUntil::Timeout(Duration::from_secs(2)).to_string(),
+            "timeout:2000"
+        );
+    }
+
+    #[test]
+    fn test_response_end_framing() {
+        let fixed = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nokHTTP/1.1";
+        assert_eq!(response_end(fixed), Some(fixed.len() - 8));
+        assert_eq!(
+            response_end(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nok"),
+            None
+        );
+
+        let chunked = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nok\r\n0\r\n\r\n";
+        assert_eq!(response_end(chunked), Some(chunked.len()));
+        assert_eq!(response_end(&chunked[..chunked.len() - 2]), None);
+
+        let interim = b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 204 No Content\r\n\r\n";
+        assert_eq!(response_end(interim), Some(interim.len()));
+        assert_eq!(response_end(b"HTTP/1.0 200 OK\r\n\r\nuntil close"), None);
+    }
+
+    #[tokio::test]
+    async fn test_cl_te_desync_poisons_second_request() {
+        let port = spawn_desync_server().await;
+        let connection = RawConnection::new(Duration::from_secs(5));
+        let stream = connection
+            .connect("127.0.0.1", port, false, None)
+            .await
+            .unwrap();
+
+        let payloads = [
+            RawPayload::new(
+                "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 6\r\n\
+                 Transfer-Encoding: chunked\r\n\r\n0\r\n\r\nG",
+                ReadUntil::Headers,
+            ),
+            RawPayload::new(
+                "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\n\r\n",
+                ReadUntil::Headers,
+            ),
+        ];
+        let segments = connection.exchange(stream, &payloads).await;
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].status(), Some(200));
+        assert_eq!(segments[1].status(), Some(405));
+        assert!(segments[1].text().ends_with("Unrecognized method GPOST"));
+        assert!(segments
+            .iter()
+            .all(|s| s.first_byte.is_some() && !s.timed_out));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_half_close_strategies() {
+        // Answers only once the client has finished sending
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            socket.read_to_end(&mut request).await.unwrap();
+            socket
+                .write_all(format!("got {} bytes", request.len()).as_bytes())
+                .await
+                .unwrap();
+        });
+
+        let connection = RawConnection::new(Duration::from_secs(5)).with_half_close(true);
+        let stream = connection
+            .connect("127.0.0.1", port, false, None)
+            .await
+            .unwrap();
+        let payloads = [
+            RawPayload::new(
+                "GET / HTTP/1.1\r\n",
+                ReadUntil::Timeout(Duration::from_millis(100)),
+            ),
+            RawPayload::new("\r\n", ReadUntil::Close),
+        ];
+        let segments = connection.exchange(stream, &payloads).await;
+
+        assert_eq!(segments.len(), 2);
+        assert!(segments[0].timed_out && segments[0].data.is_empty());
+        assert!(segments[0].elapsed >= Duration::from_millis(100));
+        assert_eq!(segments[1].text(), "got 18 bytes");
+        assert!(segments[1].closed);
+    }
+}
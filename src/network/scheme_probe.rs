@@ -0,0 +1,324 @@
+//! Scheme detection for `host:port` targets (`cxg scan --detect-scheme`)
+//!
+//! A port number says little about whether it speaks TLS: admin UIs serve
+//! HTTPS on 8080 and plain HTTP turns up on 8443. Before the scan, each such
+//! target gets a TLS handshake and, when that fails, a plaintext `HEAD`
+//! request, both bounded by [`PROBE_TIMEOUT`]. Whichever succeeds settles the
+//! target's scheme, so HTTP templates do not fall back to the other one.
+//! When neither does, the configurable port table ([`SchemePorts`]) decides.
+//!
+//! [`SchemePorts`]: crate::types::SchemePorts
+
+use super::grpc::tls_connector;
+use super::proxy::connect_via_proxy;
+use crate::config::NetworkConfig;
+use crate::types::{Protocol, SchemeSource, Target};
+use futures::stream::{self, StreamExt};
+use rustls::pki_types::ServerName;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Longest wait for each probe; shorter request timeouts apply instead
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Ports probed at once
+const PROBE_CONCURRENCY: usize = 32;
+
+/// How many targets [`settle_schemes`] settled, by source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SettledSchemes {
+    /// Settled by a probe
+    pub probed: usize,
+    /// Settled by the port table
+    pub port_table: usize,
+}
+
+/// Settle the scheme of each HTTP target that has a port but no scheme yet
+///
+/// Each distinct `host:port` is probed once when `network.detect_scheme` is
+/// set. Targets the probe learns nothing about, or all of them with detection
+/// off, take the scheme `network.scheme_ports` lists their port under; the
+/// rest keep their guess and the usual fallback to the other scheme.
+pub async fn settle_schemes(targets: &mut [Target], network: &NetworkConfig) -> SettledSchemes {
+    let unsettled = |target: &Target| {
+        target.scheme_source.is_none()
+            && target.port.is_some()
+            && matches!(target.protocol, Protocol::Http | Protocol::Https)
+    };
+
+    let mut detected: HashMap<(String, u16), Option<Protocol>> = HashMap::new();
+    if network.detect_scheme {
+        let mut ports: Vec<(String, u16)> = targets
+            .iter()
+            .filter(|target| unsettled(target))
+            .filter_map(|target| Some((target.address.clone(), target.port?)))
+            .collect();
+        ports.sort();
+        ports.dedup();
+        let detector = SchemeDetector::new(network);
+        detected = stream::iter(ports)
+            .map(|(host, port)| {
+                let detector = &detector;
+                async move {
+                    let scheme = detector.detect(&host, port).await;
+                    ((host, port), scheme)
+                }
+            })
+            .buffer_unordered(PROBE_CONCURRENCY)
+            .collect()
+            .await;
+    }
+
+    let mut settled = SettledSchemes::default();
+    for target in targets.iter_mut().filter(|target| unsettled(target)) {
+        let Some(port) = target.port else { continue };
+        if let Some(Some(scheme)) = detected.get(&(target.address.clone(), port)) {
+            target.protocol = scheme.clone();
+            target.scheme_source = Some(SchemeSource::Probe);
+            settled.probed += 1;
+        } else if let Some(scheme) = network.scheme_ports.scheme_for(port) {
+            target.protocol = scheme;
+            target.scheme_source = Some(SchemeSource::PortTable);
+            settled.port_table += 1;
+        }
+    }
+    settled
+}
+
+/// Tells TLS ports from plaintext HTTP ports
+#[derive(Debug, Clone)]
+pub struct SchemeDetector {
+    timeout: Duration,
+    proxy: Option<String>,
+}
+
+impl SchemeDetector {
+    /// Detector connecting through the configured proxy
+    pub fn new(network: &NetworkConfig) -> Self {
+        Self {
+            timeout: PROBE_TIMEOUT.min(Duration::from_secs(network.timeout_secs.max(1))),
+            proxy: network.proxy.as_ref().map(|p| p.expose().clone()),
+        }
+    }
+
+    /// Wait at most `timeout` for each probe
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// `Https` if `host:port` completes a TLS handshake, `Http` if it answers
+    /// a plaintext request, `None` if neither
+    pub async fn detect(&self, host: &str, port: u16) -> Option<Protocol> {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if self.completes_tls_handshake(host, port).await {
+            return Some(Protocol::Https);
+        }
+        if self.answers_plain_http(host, port).await {
+            return Some(Protocol::Http);
+        }
+        None
+    }
+
+    async fn completes_tls_handshake(&self, host: &str, port: u16) -> bool {
+        let handshake = async {
+            let tcp = connect_via_proxy(host, port, self.proxy.as_deref()).await?;
+            let server_name = ServerName::try_from(host.to_string())
+                .map_err(|e| crate::error::Error::Network(e.to_string()))?;
+            tls_connector(&[]).connect(server_name, tcp).await?;
+            crate::error::Result::Ok(())
+        };
+        match tokio::time::timeout(self.timeout, handshake).await {
+            Ok(Ok(())) => true,
+            Ok(Err(e)) => {
+                tracing::debug!("No TLS on {}:{}: {}", host, port, e);
+                false
+            }
+            Err(_) => {
+                tracing::debug!("TLS handshake with {}:{} timed out", host, port);
+                false
+            }
+        }
+    }
+
+    async fn answers_plain_http(&self, host: &str, port: u16) -> bool {
+        let exchange = async {
+            let mut tcp = connect_via_proxy(host, port, self.proxy.as_deref()).await?;
+            let request = format!(
+                "HEAD / HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+                authority(host, port)
+            );
+            tcp.write_all(request.as_bytes()).await?;
+            let mut status_line = [0u8; 5];
+            tcp.read_exact(&mut status_line).await?;
+            crate::error::Result::Ok(&status_line == b"HTTP/")
+        };
+        match tokio::time::timeout(self.timeout, exchange).await {
+            Ok(Ok(is_http)) => is_http,
+            Ok(Err(e)) => {
+                tracing::debug!("No plaintext HTTP on {}:{}: {}", host, port, e);
+                false
+            }
+            Err(_) => {
+                tracing::debug!("Plaintext HTTP request to {}:{} timed out", host, port);
+                false
+            }
+        }
+    }
+}
+
+/// `Host` header value for `host:port`
+fn authority(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    fn detector() -> SchemeDetector {
+        SchemeDetector::new(&NetworkConfig::default()).with_timeout(Duration::from_secs(2))
+    }
+
+    /// TLS server with a throwaway self-signed certificate
+    async fn tls_server() -> u16 {
+        use openssl::asn1::Asn1Time;
+        use openssl::hash::MessageDigest;
+        use openssl::pkey::PKey;
+        use openssl::rsa::Rsa;
+        use openssl::x509::{X509NameBuilder, X509};
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "localhost").unwrap();
+        let name = name.build();
+        let mut cert = X509::builder().unwrap();
+        cert.set_version(2).unwrap();
+        cert.set_subject_name(&name).unwrap();
+        cert.set_issuer_name(&name).unwrap();
+        cert.set_pubkey(&key).unwrap();
+        cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        cert.set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        cert.sign(&key, MessageDigest::sha256()).unwrap();
+
+        let config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![CertificateDer::from(cert.build().to_der().unwrap())],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+                    key.private_key_to_pkcs8().unwrap(),
+                )),
+            )
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(std::sync::Arc::new(config));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let _ = acceptor.accept(socket).await;
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_tls_port() {
+        let port = tls_server().await;
+        assert_eq!(
+            detector().detect("127.0.0.1", port).await,
+            Some(Protocol::Https)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plain_http_port() {
+        let server = wiremock::MockServer::start().await;
+        let port = server.address().port();
+        assert_eq!(
+            detector().detect("127.0.0.1", port).await,
+            Some(Protocol::Http)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_port_speaking_neither() {
+        // Accepts connections, then closes them without a word
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket.shutdown().await;
+            }
+        });
+        assert_eq!(detector().detect("127.0.0.1", port).await, None);
+
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let closed_port = closed.local_addr().unwrap().port();
+        drop(closed);
+        assert_eq!(detector().detect("127.0.0.1", closed_port).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_settle_schemes() {
+        let server = wiremock::MockServer::start().await;
+        let http_port = server.address().port();
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let closed_port = closed.local_addr().unwrap().port();
+        drop(closed);
+
+        let mut network = NetworkConfig::default();
+        network.scheme_ports.http.push(closed_port);
+        let explicit = Target {
+            scheme_source: Some(SchemeSource::Explicit),
+            ..Target::with_port("127.0.0.1", http_port, Protocol::Https)
+        };
+        let mut targets = vec![
+            Target::with_port("127.0.0.1", http_port, Protocol::Https),
+            Target::with_port("127.0.0.1", closed_port, Protocol::Https),
+            explicit.clone(),
+            Target::new("127.0.0.1", Protocol::Https),
+        ];
+        let settled = settle_schemes(&mut targets, &network).await;
+        assert_eq!(
+            settled,
+            SettledSchemes {
+                probed: 1,
+                port_table: 1
+            }
+        );
+        assert_eq!(targets[0].protocol, Protocol::Http);
+        assert_eq!(targets[0].scheme_source, Some(SchemeSource::Probe));
+        assert_eq!(targets[1].protocol, Protocol::Http);
+        assert_eq!(targets[1].scheme_source, Some(SchemeSource::PortTable));
+        assert_eq!(targets[2].protocol, explicit.protocol);
+        assert_eq!(targets[3].scheme_source, None);
+
+        // With detection off only the port table applies
+        network.detect_scheme = false;
+        let mut targets = vec![Target::with_port("127.0.0.1", http_port, Protocol::Https)];
+        assert_eq!(
+            settle_schemes(&mut targets, &network).await,
+            SettledSchemes::default()
+        );
+        assert_eq!(targets[0].protocol, Protocol::Https);
+    }
+
+    #[test]
+    fn test_authority() {
+        assert_eq!(authority("example.com", 8443), "example.com:8443");
+        assert_eq!(authority("::1", 8080), "[::1]:8080");
+    }
+}
@@ -0,0 +1,527 @@
+//! SNMP v1/v2c community string probe
+//!
+//! Sends a BER-encoded GetRequest for a single OID over UDP once per
+//! community string and decodes the agent's GetResponse. Agents silently
+//! drop requests with an unknown community, so an answer means the
+//! community was accepted.
+//!
+//! Timeouts and ICMP port-unreachable (surfaced as a refused connection on
+//! a connected UDP socket) are expected outcomes of a scan, so they are
+//! logged at debug level rather than surfaced as errors.
+
+use std::io::ErrorKind;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+/// sysDescr.0
+pub const SYS_DESCR_OID: &str = "1.3.6.1.2.1.1.1.0";
+
+/// Largest datagram read from an agent
+const MAX_DATAGRAM_LEN: usize = 65_507;
+
+/// BER tags used by SNMP
+mod tag {
+    pub const INTEGER: u8 = 0x02;
+    pub const OCTET_STRING: u8 = 0x04;
+    pub const NULL: u8 = 0x05;
+    pub const OID: u8 = 0x06;
+    pub const SEQUENCE: u8 = 0x30;
+    pub const IP_ADDRESS: u8 = 0x40;
+    pub const COUNTER32: u8 = 0x41;
+    pub const GAUGE32: u8 = 0x42;
+    pub const TIMETICKS: u8 = 0x43;
+    pub const COUNTER64: u8 = 0x46;
+    pub const NO_SUCH_OBJECT: u8 = 0x80;
+    pub const NO_SUCH_INSTANCE: u8 = 0x81;
+    pub const END_OF_MIB_VIEW: u8 = 0x82;
+    pub const GET_REQUEST: u8 = 0xa0;
+    pub const GET_RESPONSE: u8 = 0xa2;
+}
+
+/// SNMP protocol version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnmpVersion {
+    /// SNMPv1
+    V1,
+    /// SNMPv2c (community-based v2)
+    V2c,
+}
+
+impl SnmpVersion {
+    /// Value of the message's version field
+    fn wire_value(self) -> i64 {
+        match self {
+            SnmpVersion::V1 => 0,
+            SnmpVersion::V2c => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for SnmpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnmpVersion::V1 => write!(f, "v1"),
+            SnmpVersion::V2c => write!(f, "v2c"),
+        }
+    }
+}
+
+/// An agent's answer to a GetRequest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnmpResponse {
+    /// Community string the agent accepted
+    pub community: String,
+    /// OID of the returned variable binding
+    pub oid: String,
+    /// Value rendered as text
+    pub value: String,
+    /// Error status from the PDU (0 = noError)
+    pub error_status: i64,
+}
+
+/// Queries one OID with a series of community strings
+#[derive(Debug, Clone)]
+pub struct SnmpProbe {
+    version: SnmpVersion,
+    timeout: Duration,
+}
+
+impl SnmpProbe {
+    /// Probe waiting up to `timeout` for each answer
+    pub fn new(version: SnmpVersion, timeout: Duration) -> Self {
+        Self { version, timeout }
+    }
+
+    /// Every community in `communities` that `address:port` answers for `oid`
+    ///
+    /// Stops early if the port is unreachable.
+    pub async fn probe(
+        &self,
+        address: &str,
+        port: u16,
+        communities: &[String],
+        oid: &str,
+    ) -> Vec<SnmpResponse> {
+        let addr = format!("{}:{}", address, port);
+        let Some(oid_parts) = parse_oid(oid) else {
+            tracing::debug!("Invalid SNMP OID {:?}", oid);
+            return Vec::new();
+        };
+
+        let socket = match Self::connect(&addr).await {
+            Ok(socket) => socket,
+            Err(e) => {
+                tracing::debug!("SNMP probe of {} failed: {}", addr, e);
+                return Vec::new();
+            }
+        };
+
+        let mut responses = Vec::new();
+        for (i, community) in communities.iter().enumerate() {
+            let request_id = 0x4358_0000 + i as i64;
+            let request = encode_get_request(self.version, community, request_id, &oid_parts);
+            if let Err(e) = socket.send(&request).await {
+                tracing::debug!("SNMP send to {} failed: {}", addr, e);
+                break;
+            }
+
+            match self.receive(&socket, request_id).await {
+                Ok(Some((oid, value, error_status))) => responses.push(SnmpResponse {
+                    community: community.clone(),
+                    oid,
+                    value,
+                    error_status,
+                }),
+                Ok(None) => {
+                    tracing::debug!("No SNMP answer from {} for community {:?}", addr, community)
+                }
+                Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+                    tracing::debug!("SNMP port {} unreachable", addr);
+                    break;
+                }
+                Err(e) => {
+                    tracing::debug!("SNMP receive from {} failed: {}", addr, e);
+                    break;
+                }
+            }
+        }
+        responses
+    }
+
+    async fn connect(addr: &str) -> std::io::Result<UdpSocket> {
+        let remote = tokio::net::lookup_host(addr)
+            .await?
+            .next()
+            .ok_or_else(|| std::io::Error::new(ErrorKind::NotFound, "no address"))?;
+        let local = if remote.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(remote).await?;
+        Ok(socket)
+    }
+
+    /// Wait for the response to `request_id`, ignoring stray datagrams
+    async fn receive(
+        &self,
+        socket: &UdpSocket,
+        request_id: i64,
+    ) -> std::io::Result<Option<(String, String, i64)>> {
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        let mut buf = vec![0u8; MAX_DATAGRAM_LEN];
+        loop {
+            let n = match tokio::time::timeout_at(deadline, socket.recv(&mut buf)).await {
+                Ok(result) => result?,
+                Err(_) => return Ok(None),
+            };
+            if let Some((id, oid, value, error_status)) = decode_get_response(&buf[..n]) {
+                if id == request_id {
+                    return Ok(Some((oid, value, error_status)));
+                }
+            }
+        }
+    }
+}
+
+/// Parse a dotted OID such as `1.3.6.1.2.1.1.1.0`
+fn parse_oid(oid: &str) -> Option<Vec<u64>> {
+    let parts: Vec<u64> = oid
+        .trim_start_matches('.')
+        .split('.')
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    (parts.len() >= 2 && parts[0] <= 2).then_some(parts)
+}
+
+fn encode_length(len: usize, out: &mut Vec<u8>) {
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|&b| b == 0)
+            .collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend(bytes);
+    }
+}
+
+fn encode_tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    encode_length(value.len(), &mut out);
+    out.extend_from_slice(value);
+    out
+}
+
+fn encode_integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    // Drop redundant leading bytes while keeping the sign bit
+    let mut start = 0;
+    while start < 7
+        && ((bytes[start] == 0 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    encode_tlv(tag::INTEGER, &bytes[start..])
+}
+
+fn encode_oid(parts: &[u64]) -> Vec<u8> {
+    let mut value = Vec::new();
+    let mut encode_arc = |mut arc: u64| {
+        let mut chunk = vec![(arc & 0x7f) as u8];
+        arc >>= 7;
+        while arc > 0 {
+            chunk.push(0x80 | (arc & 0x7f) as u8);
+            arc >>= 7;
+        }
+        value.extend(chunk.into_iter().rev());
+    };
+    encode_arc(parts[0] * 40 + parts[1]);
+    for &arc in &parts[2..] {
+        encode_arc(arc);
+    }
+    encode_tlv(tag::OID, &value)
+}
+
+/// Encode a GetRequest message for a single OID
+fn encode_get_request(
+    version: SnmpVersion,
+    community: &str,
+    request_id: i64,
+    oid: &[u64],
+) -> Vec<u8> {
+    let varbind = encode_tlv(
+        tag::SEQUENCE,
+        &[encode_oid(oid), encode_tlv(tag::NULL, &[])].concat(),
+    );
+    let pdu = encode_tlv(
+        tag::GET_REQUEST,
+        &[
+            encode_integer(request_id),
+            encode_integer(0),
+            encode_integer(0),
+            encode_tlv(tag::SEQUENCE, &varbind),
+        ]
+        .concat(),
+    );
+    encode_tlv(
+        tag::SEQUENCE,
+        &[
+            encode_integer(version.wire_value()),
+            encode_tlv(tag::OCTET_STRING, community.as_bytes()),
+            pdu,
+        ]
+        .concat(),
+    )
+}
+
+/// Read one TLV, returning its tag, value and the remaining input
+fn read_tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first & 0x80 == 0 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let len = rest[..count]
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        (len, &rest[count..])
+    };
+    if rest.len() < len {
+        return None;
+    }
+    Some((tag, &rest[..len], &rest[len..]))
+}
+
+fn decode_integer(value: &[u8]) -> Option<i64> {
+    if value.is_empty() || value.len() > 8 {
+        return None;
+    }
+    let sign = if value[0] & 0x80 != 0 { -1i64 } else { 0 };
+    Some(value.iter().fold(sign, |acc, &b| (acc << 8) | b as i64))
+}
+
+fn decode_unsigned(value: &[u8]) -> Option<u64> {
+    if value.len() > 9 {
+        return None;
+    }
+    Some(value.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64))
+}
+
+fn decode_oid(value: &[u8]) -> Option<String> {
+    let mut arcs = Vec::new();
+    let mut arc = 0u64;
+    for &b in value {
+        arc = (arc << 7) | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            arcs.push(arc);
+            arc = 0;
+        }
+    }
+    let first = *arcs.first()?;
+    let (a, b) = if first < 80 {
+        (first / 40, first % 40)
+    } else {
+        (2, first - 80)
+    };
+    let mut parts = vec![a.to_string(), b.to_string()];
+    parts.extend(arcs[1..].iter().map(u64::to_string));
+    Some(parts.join("."))
+}
+
+/// Render a varbind value as text
+fn render_value(kind: u8, value: &[u8]) -> String {
+    match kind {
+        tag::OCTET_STRING => String::from_utf8_lossy(value).to_string(),
+        tag::INTEGER => decode_integer(value).map_or_else(String::new, |v| v.to_string()),
+        tag::COUNTER32 | tag::GAUGE32 | tag::TIMETICKS | tag::COUNTER64 => {
+            decode_unsigned(value).map_or_else(String::new, |v| v.to_string())
+        }
+        tag::OID => decode_oid(value).unwrap_or_default(),
+        tag::IP_ADDRESS if value.len() == 4 => {
+            format!("{}.{}.{}.{}", value[0], value[1], value[2], value[3])
+        }
+        tag::NULL => String::new(),
+        tag::NO_SUCH_OBJECT => "noSuchObject".to_string(),
+        tag::NO_SUCH_INSTANCE => "noSuchInstance".to_string(),
+        tag::END_OF_MIB_VIEW => "endOfMibView".to_string(),
+        _ => value.iter().map(|b| format!("{:02x}", b)).collect(),
+    }
+}
+
+/// Decode a GetResponse into (request ID, OID, value, error status)
+fn decode_get_response(datagram: &[u8]) -> Option<(i64, String, String, i64)> {
+    let (tag::SEQUENCE, message, _) = read_tlv(datagram)? else {
+        return None;
+    };
+    let (tag::INTEGER, _, rest) = read_tlv(message)? else {
+        return None;
+    };
+    let (tag::OCTET_STRING, _, rest) = read_tlv(rest)? else {
+        return None;
+    };
+    let (tag::GET_RESPONSE, pdu, _) = read_tlv(rest)? else {
+        return None;
+    };
+
+    let (tag::INTEGER, request_id, rest) = read_tlv(pdu)? else {
+        return None;
+    };
+    let (tag::INTEGER, error_status, rest) = read_tlv(rest)? else {
+        return None;
+    };
+    let (tag::INTEGER, _, rest) = read_tlv(rest)? else {
+        return None;
+    };
+    let (tag::SEQUENCE, varbinds, _) = read_tlv(rest)? else {
+        return None;
+    };
+    let (tag::SEQUENCE, varbind, _) = read_tlv(varbinds)? else {
+        return None;
+    };
+    let (tag::OID, oid, rest) = read_tlv(varbind)? else {
+        return None;
+    };
+    let (value_tag, value, _) = read_tlv(rest)?;
+
+    Some((
+        decode_integer(request_id)?,
+        decode_oid(oid)?,
+        render_value(value_tag, value),
+        decode_integer(error_status)?,
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Answer `GetRequest`s carrying `community` with `sys_descr`
+    pub(crate) async fn spawn_snmp_agent(community: &'static str, sys_descr: &'static str) -> u16 {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 1500];
+            while let Ok((n, peer)) = socket.recv_from(&mut buf).await {
+                let Some((tag::SEQUENCE, message, _)) = read_tlv(&buf[..n]) else {
+                    continue;
+                };
+                let (_, version, rest) = read_tlv(message).unwrap();
+                let (_, received_community, rest) = read_tlv(rest).unwrap();
+                if received_community != community.as_bytes() {
+                    continue;
+                }
+                let (_, pdu, _) = read_tlv(rest).unwrap();
+                let (_, request_id, rest) = read_tlv(pdu).unwrap();
+                let (_, _, rest) = read_tlv(rest).unwrap();
+                let (_, _, rest) = read_tlv(rest).unwrap();
+                let (_, varbinds, _) = read_tlv(rest).unwrap();
+                let (_, varbind, _) = read_tlv(varbinds).unwrap();
+                let (_, oid, _) = read_tlv(varbind).unwrap();
+
+                let varbind = encode_tlv(
+                    tag::SEQUENCE,
+                    &[
+                        encode_tlv(tag::OID, oid),
+                        encode_tlv(tag::OCTET_STRING, sys_descr.as_bytes()),
+                    ]
+                    .concat(),
+                );
+                let pdu = encode_tlv(
+                    tag::GET_RESPONSE,
+                    &[
+                        encode_tlv(tag::INTEGER, request_id),
+                        encode_integer(0),
+                        encode_integer(0),
+                        encode_tlv(tag::SEQUENCE, &varbind),
+                    ]
+                    .concat(),
+                );
+                let response = encode_tlv(
+                    tag::SEQUENCE,
+                    &[
+                        encode_tlv(tag::INTEGER, version),
+                        encode_tlv(tag::OCTET_STRING, received_community),
+                        pdu,
+                    ]
+                    .concat(),
+                );
+                let _ = socket.send_to(&response, peer).await;
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn test_encode_get_request() {
+        let request = encode_get_request(
+            SnmpVersion::V2c,
+            "public",
+            1,
+            &parse_oid(SYS_DESCR_OID).unwrap(),
+        );
+        let expected: &[u8] = &[
+            0x30, 0x26, 0x02, 0x01, 0x01, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xa0,
+            0x19, 0x02, 0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x0e, 0x30, 0x0c,
+            0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00, 0x05, 0x00,
+        ];
+        assert_eq!(request, expected);
+    }
+
+    #[test]
+    fn test_integer_and_oid_round_trip() {
+        for value in [0, 127, 128, 255, 256, -1, -129, 0x4358_0001, i64::MAX] {
+            let encoded = encode_integer(value);
+            let (_, bytes, _) = read_tlv(&encoded).unwrap();
+            assert_eq!(decode_integer(bytes), Some(value));
+        }
+        let oid = "1.3.6.1.4.1.311.21.20.1";
+        let encoded = encode_oid(&parse_oid(oid).unwrap());
+        let (_, bytes, _) = read_tlv(&encoded).unwrap();
+        assert_eq!(decode_oid(bytes).as_deref(), Some(oid));
+        assert!(parse_oid("1.3.x").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_probe_reports_accepted_communities() {
+        let port = spawn_snmp_agent("public", "Linux router 5.10").await;
+        let communities = vec!["private".to_string(), "public".to_string()];
+        let responses = SnmpProbe::new(SnmpVersion::V2c, Duration::from_millis(300))
+            .probe("127.0.0.1", port, &communities, SYS_DESCR_OID)
+            .await;
+
+        assert_eq!(
+            responses,
+            vec![SnmpResponse {
+                community: "public".to_string(),
+                oid: SYS_DESCR_OID.to_string(),
+                value: "Linux router 5.10".to_string(),
+                error_status: 0,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_closed_port_is_quiet() {
+        // Bind then drop to find a port nobody listens on
+        let port = UdpSocket::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let communities = vec!["public".to_string(), "private".to_string()];
+        let responses = SnmpProbe::new(SnmpVersion::V1, Duration::from_millis(300))
+            .probe("127.0.0.1", port, &communities, SYS_DESCR_OID)
+            .await;
+        assert!(responses.is_empty());
+    }
+}
@@ -0,0 +1,417 @@
+//! SSH transport probe
+//!
+//! Performs the identification exchange and reads the server's KEXINIT
+//! packet (RFC 4253 sections 4.2 and 7.1). That is enough to learn the
+//! server software and every algorithm it offers without authenticating or
+//! even completing key exchange.
+//!
+//! Listing authentication methods would require finishing key exchange and
+//! encrypting the transport, so it is deliberately not attempted.
+//!
+//! Connection failures and servers that do not speak SSH are expected
+//! outcomes of a scan, so they are logged at debug level rather than
+//! surfaced as errors.
+
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Identification string sent to the server
+pub const CLIENT_IDENTIFICATION: &str = "SSH-2.0-cert-x-gen";
+
+/// Algorithms considered weak by current hardening guides
+pub const WEAK_ALGORITHMS: &[&str] = &[
+    "diffie-hellman-group1-sha1",
+    "diffie-hellman-group14-sha1",
+    "diffie-hellman-group-exchange-sha1",
+    "gss-gex-sha1-toWM5Slw5Ew8Mqkay+al2g==",
+    "ssh-dss",
+    "ssh-rsa",
+    "3des-cbc",
+    "aes128-cbc",
+    "aes192-cbc",
+    "aes256-cbc",
+    "blowfish-cbc",
+    "cast128-cbc",
+    "arcfour",
+    "arcfour128",
+    "arcfour256",
+    "rijndael-cbc@lysator.liu.se",
+    "hmac-md5",
+    "hmac-md5-96",
+    "hmac-md5-etm@openssh.com",
+    "hmac-sha1-96",
+    "hmac-ripemd160",
+    "umac-64@openssh.com",
+    "none",
+];
+
+/// SSH_MSG_KEXINIT
+const MSG_KEXINIT: u8 = 20;
+
+/// Messages a server may send before KEXINIT (IGNORE, DEBUG)
+const SKIPPABLE_MESSAGES: [u8; 2] = [2, 4];
+
+/// Lines a server may send before its identification string
+const MAX_PREAMBLE_LINES: usize = 16;
+
+/// Longest identification line accepted (RFC 4253 allows 255)
+const MAX_LINE_LEN: usize = 1024;
+
+/// Largest packet accepted before KEXINIT is parsed
+const MAX_PACKET_LEN: usize = 64 * 1024;
+
+/// What an SSH server revealed before key exchange
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SshServerInfo {
+    /// Full identification line, e.g. `SSH-2.0-OpenSSH_8.9p1 Ubuntu-3`
+    pub banner: String,
+    /// Protocol version, e.g. `2.0`
+    pub protocol_version: String,
+    /// Software version, e.g. `OpenSSH_8.9p1`
+    pub software: String,
+    /// Comments after the software version
+    pub comments: Option<String>,
+    /// Algorithm lists from the server's KEXINIT
+    pub algorithms: SshAlgorithms,
+}
+
+/// Name-lists offered in a KEXINIT packet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SshAlgorithms {
+    /// Key exchange methods
+    pub kex: Vec<String>,
+    /// Host key types
+    pub host_key: Vec<String>,
+    /// Ciphers, client to server
+    pub encryption_client_to_server: Vec<String>,
+    /// Ciphers, server to client
+    pub encryption_server_to_client: Vec<String>,
+    /// MACs, client to server
+    pub mac_client_to_server: Vec<String>,
+    /// MACs, server to client
+    pub mac_server_to_client: Vec<String>,
+    /// Compression, client to server
+    pub compression_client_to_server: Vec<String>,
+    /// Compression, server to client
+    pub compression_server_to_client: Vec<String>,
+}
+
+impl SshAlgorithms {
+    /// Lists labelled as they appear in the matchable body
+    pub fn labelled(&self) -> [(&'static str, &[String]); 8] {
+        [
+            ("kex", &self.kex),
+            ("host_key", &self.host_key),
+            (
+                "encryption_client_to_server",
+                &self.encryption_client_to_server,
+            ),
+            (
+                "encryption_server_to_client",
+                &self.encryption_server_to_client,
+            ),
+            ("mac_client_to_server", &self.mac_client_to_server),
+            ("mac_server_to_client", &self.mac_server_to_client),
+            (
+                "compression_client_to_server",
+                &self.compression_client_to_server,
+            ),
+            (
+                "compression_server_to_client",
+                &self.compression_server_to_client,
+            ),
+        ]
+    }
+
+    /// Offered algorithms that appear in [`WEAK_ALGORITHMS`], without repeats
+    pub fn weak(&self) -> Vec<String> {
+        let mut weak: Vec<String> = Vec::new();
+        for (_, list) in self.labelled() {
+            for name in list {
+                if WEAK_ALGORITHMS.contains(&name.as_str()) && !weak.contains(name) {
+                    weak.push(name.clone());
+                }
+            }
+        }
+        weak
+    }
+}
+
+impl SshServerInfo {
+    /// Matchable body: one `label: a,b,c` line per field
+    pub fn body(&self) -> String {
+        let mut lines = vec![
+            format!("banner: {}", self.banner),
+            format!("software: {}", self.software),
+        ];
+        for (label, list) in self.algorithms.labelled() {
+            lines.push(format!("{}: {}", label, list.join(",")));
+        }
+        lines.push(format!("weak: {}", self.algorithms.weak().join(",")));
+        lines.join("\n")
+    }
+}
+
+/// Collects an SSH server's banner and KEXINIT algorithm lists
+#[derive(Debug, Clone)]
+pub struct SshProbe {
+    timeout: Duration,
+}
+
+impl SshProbe {
+    /// Probe giving up on a target after `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+
+    /// Probe `address:port`, returning `None` if it does not speak SSH-2
+    pub async fn probe(&self, address: &str, port: u16) -> Option<SshServerInfo> {
+        let addr = format!("{}:{}", address, port);
+        match tokio::time::timeout(self.timeout, Self::exchange(&addr)).await {
+            Ok(Ok(info)) => Some(info),
+            Ok(Err(reason)) => {
+                tracing::debug!("SSH probe of {} failed: {}", addr, reason);
+                None
+            }
+            Err(_) => {
+                tracing::debug!("SSH probe of {} timed out", addr);
+                None
+            }
+        }
+    }
+
+    async fn exchange(addr: &str) -> Result<SshServerInfo, String> {
+        let mut stream = TcpStream::connect(addr)
+            .await
+            .map_err(|e| format!("connect: {}", e))?;
+        stream
+            .write_all(format!("{}\r\n", CLIENT_IDENTIFICATION).as_bytes())
+            .await
+            .map_err(|e| format!("write: {}", e))?;
+
+        let mut reader = BufReader::new(stream);
+        let banner = read_identification(&mut reader).await?;
+        let (protocol_version, software, comments) = parse_identification(&banner)?;
+
+        loop {
+            let payload = read_packet(&mut reader).await?;
+            match payload.first() {
+                Some(&MSG_KEXINIT) => {
+                    return Ok(SshServerInfo {
+                        banner,
+                        protocol_version,
+                        software,
+                        comments,
+                        algorithms: parse_kexinit(&payload)?,
+                    });
+                }
+                Some(code) if SKIPPABLE_MESSAGES.contains(code) => continue,
+                other => return Err(format!("expected KEXINIT, got message {:?}", other)),
+            }
+        }
+    }
+}
+
+/// Read lines until the `SSH-` identification line
+async fn read_identification<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<String, String> {
+    for _ in 0..MAX_PREAMBLE_LINES {
+        let mut line = Vec::new();
+        let n = (&mut *reader)
+            .take(MAX_LINE_LEN as u64)
+            .read_until(b'\n', &mut line)
+            .await
+            .map_err(|e| format!("read: {}", e))?;
+        if n == 0 {
+            return Err("connection closed before identification".to_string());
+        }
+        let line = String::from_utf8_lossy(&line)
+            .trim_end_matches(['\r', '\n'])
+            .to_string();
+        if line.starts_with("SSH-") {
+            return Ok(line);
+        }
+    }
+    Err("no SSH identification line".to_string())
+}
+
+/// Split `SSH-protoversion-softwareversion SP comments`
+fn parse_identification(line: &str) -> Result<(String, String, Option<String>), String> {
+    let rest = line.strip_prefix("SSH-").unwrap_or(line);
+    let (version, rest) = rest
+        .split_once('-')
+        .ok_or_else(|| format!("malformed identification: {}", line))?;
+    if version != "2.0" && version != "1.99" {
+        return Err(format!("unsupported protocol version {}", version));
+    }
+    let (software, comments) = match rest.split_once(' ') {
+        Some((software, comments)) => (software, Some(comments.to_string())),
+        None => (rest, None),
+    };
+    Ok((version.to_string(), software.to_string(), comments))
+}
+
+/// Read one unencrypted binary packet and return its payload
+async fn read_packet<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Vec<u8>, String> {
+    let packet_len = reader
+        .read_u32()
+        .await
+        .map_err(|e| format!("read: {}", e))? as usize;
+    if !(1..=MAX_PACKET_LEN).contains(&packet_len) {
+        return Err(format!("implausible packet length {}", packet_len));
+    }
+    let mut packet = vec![0u8; packet_len];
+    reader
+        .read_exact(&mut packet)
+        .await
+        .map_err(|e| format!("read: {}", e))?;
+    let padding = packet[0] as usize;
+    if padding + 1 > packet_len {
+        return Err(format!("padding {} exceeds packet", padding));
+    }
+    Ok(packet[1..packet_len - padding].to_vec())
+}
+
+/// Parse the name-lists of a KEXINIT payload
+fn parse_kexinit(payload: &[u8]) -> Result<SshAlgorithms, String> {
+    // Message code and 16-byte cookie
+    let mut rest = payload
+        .get(17..)
+        .ok_or_else(|| "KEXINIT too short".to_string())?;
+    let mut next = || -> Result<Vec<String>, String> {
+        let len = rest
+            .get(..4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or_else(|| "truncated KEXINIT".to_string())?;
+        let list = rest
+            .get(4..4 + len)
+            .ok_or_else(|| "truncated KEXINIT".to_string())?;
+        rest = &rest[4 + len..];
+        Ok(String::from_utf8_lossy(list)
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect())
+    };
+
+    Ok(SshAlgorithms {
+        kex: next()?,
+        host_key: next()?,
+        encryption_client_to_server: next()?,
+        encryption_server_to_client: next()?,
+        mac_client_to_server: next()?,
+        mac_server_to_client: next()?,
+        compression_client_to_server: next()?,
+        compression_server_to_client: next()?,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Build a KEXINIT packet offering `lists` (kex, host key, ...)
+    fn kexinit_packet(lists: [&str; 10]) -> Vec<u8> {
+        let mut payload = vec![MSG_KEXINIT];
+        payload.extend_from_slice(&[7u8; 16]);
+        for list in lists {
+            payload.extend_from_slice(&(list.len() as u32).to_be_bytes());
+            payload.extend_from_slice(list.as_bytes());
+        }
+        payload.push(0);
+        payload.extend_from_slice(&0u32.to_be_bytes());
+
+        let padding = 8 - (payload.len() + 5) % 8 + 8;
+        let mut packet = ((payload.len() + padding + 1) as u32)
+            .to_be_bytes()
+            .to_vec();
+        packet.push(padding as u8);
+        packet.extend_from_slice(&payload);
+        packet.resize(packet.len() + padding, 0);
+        packet
+    }
+
+    /// Serve a legacy-looking SSH server on a local port
+    pub(crate) async fn spawn_ssh_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut wire = b"Welcome\r\nSSH-2.0-OpenSSH_7.4 Debian-10\r\n".to_vec();
+                // An IGNORE message before KEXINIT must be skipped
+                wire.extend_from_slice(&[0, 0, 0, 12, 10, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+                wire.extend(kexinit_packet([
+                    "curve25519-sha256,diffie-hellman-group1-sha1",
+                    "rsa-sha2-512,ssh-rsa",
+                    "aes128-ctr,aes128-cbc",
+                    "aes128-ctr,aes128-cbc",
+                    "hmac-sha2-256,hmac-md5",
+                    "hmac-sha2-256,hmac-md5",
+                    "none,zlib@openssh.com",
+                    "none,zlib@openssh.com",
+                    "",
+                    "",
+                ]));
+                let _ = stream.write_all(&wire).await;
+                let mut sink = [0u8; 64];
+                let _ = stream.read(&mut sink).await;
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_probe_reads_banner_and_kexinit() {
+        let port = spawn_ssh_server().await;
+        let info = SshProbe::new(Duration::from_secs(5))
+            .probe("127.0.0.1", port)
+            .await
+            .unwrap();
+
+        assert_eq!(info.banner, "SSH-2.0-OpenSSH_7.4 Debian-10");
+        assert_eq!(info.protocol_version, "2.0");
+        assert_eq!(info.software, "OpenSSH_7.4");
+        assert_eq!(info.comments.as_deref(), Some("Debian-10"));
+        assert_eq!(
+            info.algorithms.kex,
+            vec!["curve25519-sha256", "diffie-hellman-group1-sha1"]
+        );
+        assert_eq!(
+            info.algorithms.weak(),
+            vec![
+                "diffie-hellman-group1-sha1",
+                "ssh-rsa",
+                "aes128-cbc",
+                "hmac-md5",
+                "none"
+            ]
+        );
+        assert!(info.body().contains("host_key: rsa-sha2-512,ssh-rsa\n"));
+    }
+
+    #[tokio::test]
+    async fn test_non_ssh_server_and_closed_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let _ = stream.write_all(b"220 smtp.example ESMTP\r\n").await;
+            }
+        });
+        let probe = SshProbe::new(Duration::from_secs(2));
+        assert!(probe.probe("127.0.0.1", port).await.is_none());
+
+        assert!(probe.probe("127.0.0.1", 1).await.is_none());
+    }
+
+    #[test]
+    fn test_parse_identification() {
+        assert_eq!(
+            parse_identification("SSH-1.99-Cisco-1.25").unwrap(),
+            ("1.99".to_string(), "Cisco-1.25".to_string(), None)
+        );
+        assert!(parse_identification("SSH-1.5-Legacy").is_err());
+        assert!(parse_identification("SSH-garbage").is_err());
+    }
+}
@@ -0,0 +1,341 @@
+//! HTTP request/response trace export (`cxg scan --trace-requests <dir>`)
+//!
+//! Every request sent through [`NetworkClient`] while tracing is enabled is
+//! written as a numbered pair of files:
+//!
+//! ```text
+//! <dir>/<scan_id>/<target>/<template_id>/0001.req.txt
+//! <dir>/<scan_id>/<target>/<template_id>/0001.res.txt
+//! ```
+//!
+//! Sequence numbers count per directory in the order requests are sent, so
+//! retries and multi-step flows read back in order. Response bodies are
+//! written after undoing any `Content-Encoding`. Credentials in
+//! `Authorization`, `Proxy-Authorization`, `Cookie` and `Set-Cookie` are
+//! redacted unless redaction is turned off (`--trace-no-redact`).
+//!
+//! [`NetworkClient`]: super::NetworkClient
+
+use super::{decode, rate_limit};
+use crate::error::Result;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+/// Headers whose values are replaced by [`REDACTED`]
+pub const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// Stand-in for a redacted header value
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Directory used for requests made outside a scan or template
+const UNSCOPED: &str = "unscoped";
+
+tokio::task_local! {
+    static CURRENT_SCAN: String;
+}
+
+/// Run `future` with its traced requests filed under `scan_id`
+pub async fn with_scan<F: Future>(scan_id: &str, future: F) -> F::Output {
+    CURRENT_SCAN.scope(scan_id.to_string(), future).await
+}
+
+/// Scan whose requests are being issued on this task, if any
+pub fn current_scan() -> Option<String> {
+    CURRENT_SCAN.try_with(|id| id.clone()).ok()
+}
+
+/// A request as sent
+#[derive(Debug, Clone)]
+pub struct TraceRequest {
+    /// HTTP method
+    pub method: String,
+    /// Full URL
+    pub url: String,
+    /// Request headers, in order
+    pub headers: Vec<(String, String)>,
+    /// Request body
+    pub body: Vec<u8>,
+}
+
+/// A response as received
+#[derive(Debug, Clone)]
+pub struct TraceResponse {
+    /// HTTP version, e.g. `HTTP/1.1`
+    pub version: String,
+    /// Status code
+    pub status: u16,
+    /// Response headers, in order
+    pub headers: Vec<(String, String)>,
+    /// Response body as received (possibly compressed)
+    pub body: Vec<u8>,
+}
+
+/// Files for one traced exchange
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// `<dir>/<scan_id>/<target>/<template_id>`
+    pub dir: PathBuf,
+    /// Position of the request in `dir`, from 1
+    pub seq: u64,
+}
+
+impl TraceEntry {
+    /// `<seq>.req.txt`
+    pub fn request_path(&self) -> PathBuf {
+        self.dir.join(format!("{:04}.req.txt", self.seq))
+    }
+
+    /// `<seq>.res.txt`
+    pub fn response_path(&self) -> PathBuf {
+        self.dir.join(format!("{:04}.res.txt", self.seq))
+    }
+}
+
+/// Writes traced exchanges under a root directory
+#[derive(Debug)]
+pub struct TraceWriter {
+    root: PathBuf,
+    redact: bool,
+    sequences: Mutex<HashMap<PathBuf, u64>>,
+}
+
+impl TraceWriter {
+    /// Trace into `root`, redacting sensitive headers
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            redact: true,
+            sequences: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether sensitive header values are redacted (default `true`)
+    pub fn with_redaction(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self
+    }
+
+    /// Root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Write `<seq>.req.txt` for a request about to be sent
+    ///
+    /// The scan and template come from the current task (see [`with_scan`]
+    /// and [`rate_limit::with_template`]).
+    pub fn record_request(&self, request: &TraceRequest) -> Result<TraceEntry> {
+        let scan = current_scan().unwrap_or_else(|| UNSCOPED.to_string());
+        let template = rate_limit::current_template().unwrap_or_else(|| UNSCOPED.to_string());
+        let dir = self
+            .root
+            .join(path_component(&scan))
+            .join(path_component(&target_name(&request.url)))
+            .join(path_component(&template));
+
+        std::fs::create_dir_all(&dir)?;
+        let seq = {
+            let mut sequences = self.sequences.lock();
+            let seq = sequences.entry(dir.clone()).or_insert(0);
+            *seq += 1;
+            *seq
+        };
+        let entry = TraceEntry { dir, seq };
+
+        let mut text = format!("{} {}\n", request.method, request.url);
+        self.write_headers(&mut text, &request.headers);
+        text.push('\n');
+        text.push_str(&String::from_utf8_lossy(&request.body));
+        std::fs::write(entry.request_path(), text)?;
+        Ok(entry)
+    }
+
+    /// Write `<seq>.res.txt` for the response to `entry`
+    pub fn record_response(&self, entry: &TraceEntry, response: &TraceResponse) -> Result<()> {
+        let reason = reqwest::StatusCode::from_u16(response.status)
+            .ok()
+            .and_then(|s| s.canonical_reason())
+            .unwrap_or("");
+        let mut text = format!("{} {} {}\n", response.version, response.status, reason);
+        self.write_headers(&mut text, &response.headers);
+        text.push('\n');
+
+        let body = match decode::header(&response.headers, "content-encoding") {
+            Some(encoding) => decode::decompress(response.body.clone(), encoding),
+            None => response.body.clone(),
+        };
+        text.push_str(&String::from_utf8_lossy(&body));
+        std::fs::write(entry.response_path(), text)?;
+        Ok(())
+    }
+
+    /// Write `<seq>.res.txt` for a request that got no response
+    pub fn record_error(&self, entry: &TraceEntry, error: &str) -> Result<()> {
+        std::fs::write(entry.response_path(), format!("# no response: {}\n", error))?;
+        Ok(())
+    }
+
+    fn write_headers(&self, text: &mut String, headers: &[(String, String)]) {
+        for (name, value) in headers {
+            let value = if self.redact && is_sensitive(name) {
+                REDACTED
+            } else {
+                value.as_str()
+            };
+            let _ = writeln!(text, "{}: {}", name, value);
+        }
+    }
+}
+
+fn is_sensitive(header: &str) -> bool {
+    SENSITIVE_HEADERS
+        .iter()
+        .any(|sensitive| header.eq_ignore_ascii_case(sensitive))
+}
+
+/// `host` or `host_port` of a URL
+fn target_name(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(url) => match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}_{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => UNSCOPED.to_string(),
+        },
+        Err(_) => UNSCOPED.to_string(),
+    }
+}
+
+/// `name` made safe to use as a single path component
+pub(crate) fn path_component(name: &str) -> String {
+    let safe: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match safe.trim_matches('.') {
+        "" => "_".to_string(),
+        _ => safe,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(headers: &[(&str, &str)]) -> TraceRequest {
+        TraceRequest {
+            method: "POST".to_string(),
+            url: "http://127.0.0.1:8080/login?next=/".to_string(),
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            body: b"user=admin".to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exchange_files_are_numbered_per_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let writer = TraceWriter::new(dir.path());
+
+        let entries = with_scan(
+            "scan-1",
+            rate_limit::with_template("login-flow", async {
+                let first = writer.record_request(&request(&[])).unwrap();
+                let second = writer.record_request(&request(&[])).unwrap();
+                (first, second)
+            }),
+        )
+        .await;
+
+        let expected = dir
+            .path()
+            .join("scan-1")
+            .join("127.0.0.1_8080")
+            .join("login-flow");
+        assert_eq!(entries.0.dir, expected);
+        assert_eq!((entries.0.seq, entries.1.seq), (1, 2));
+        assert!(expected.join("0001.req.txt").is_file());
+        assert!(expected.join("0002.req.txt").is_file());
+
+        writer
+            .record_response(
+                &entries.0,
+                &TraceResponse {
+                    version: "HTTP/1.1".to_string(),
+                    status: 302,
+                    headers: vec![("Location".to_string(), "/home".to_string())],
+                    body: b"moved".to_vec(),
+                },
+            )
+            .unwrap();
+        let response = std::fs::read_to_string(expected.join("0001.res.txt")).unwrap();
+        assert_eq!(response, "HTTP/1.1 302 Found\nLocation: /home\n\nmoved");
+
+        writer.record_error(&entries.1, "timed out").unwrap();
+        let error = std::fs::read_to_string(expected.join("0002.res.txt")).unwrap();
+        assert!(error.contains("timed out"));
+
+        // Outside a scan and template
+        let entry = writer.record_request(&request(&[])).unwrap();
+        assert_eq!(
+            entry.dir,
+            dir.path()
+                .join(UNSCOPED)
+                .join("127.0.0.1_8080")
+                .join(UNSCOPED)
+        );
+    }
+
+    #[test]
+    fn test_sensitive_headers_are_redacted() {
+        let headers = [
+            ("Authorization", "Bearer secret-token"),
+            ("cookie", "session=abc123"),
+            ("X-Trace", "visible"),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+
+        let redacted = TraceWriter::new(dir.path().join("redacted"));
+        let entry = redacted.record_request(&request(&headers)).unwrap();
+        let text = std::fs::read_to_string(entry.request_path()).unwrap();
+        assert!(text.starts_with("POST http://127.0.0.1:8080/login?next=/\n"));
+        assert!(text.contains("Authorization: [REDACTED]\n"));
+        assert!(text.contains("cookie: [REDACTED]\n"));
+        assert!(text.contains("X-Trace: visible\n"));
+        assert!(text.ends_with("\n\nuser=admin"));
+        assert!(!text.contains("secret-token") && !text.contains("abc123"));
+
+        let raw = TraceWriter::new(dir.path().join("raw")).with_redaction(false);
+        let entry = raw.record_request(&request(&headers)).unwrap();
+        let text = std::fs::read_to_string(entry.request_path()).unwrap();
+        assert!(text.contains("Authorization: Bearer secret-token\n"));
+        assert!(text.contains("cookie: session=abc123\n"));
+    }
+
+    #[test]
+    fn test_path_components_are_sanitized() {
+        assert_eq!(path_component("../etc"), ".._etc");
+        assert_eq!(path_component(".."), "_");
+        assert_eq!(path_component("cve/2021:44228"), "cve_2021_44228");
+        assert_eq!(
+            path_component(&target_name("https://[::1]:8443/")),
+            "___1__8443"
+        );
+        assert_eq!(target_name("not a url"), UNSCOPED);
+    }
+}
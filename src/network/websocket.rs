@@ -0,0 +1,285 @@
+//! WebSocket probes: an upgrade handshake followed by a short frame exchange
+//!
+//! The connection honours the scan's proxy (HTTP `CONNECT` or SOCKS5) and user
+//! agent, and `wss://` uses the same rustls/webpki-roots verification as the HTTP
+//! client. Frames received during the read window are collected so template
+//! matchers can run over them.
+
+use super::proxy::connect_via_proxy;
+use crate::config::NetworkConfig;
+use crate::error::{Error, Result};
+use futures::{SinkExt, StreamExt};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+
+/// One WebSocket exchange to perform
+#[derive(Debug, Clone)]
+pub struct WebSocketRequest {
+    /// `ws://` or `wss://` URL
+    pub url: String,
+    /// Subprotocol offered in `Sec-WebSocket-Protocol`
+    pub subprotocol: Option<String>,
+    /// Extra handshake headers
+    pub headers: Vec<(String, String)>,
+    /// Text frames sent after the upgrade, in order
+    pub frames: Vec<String>,
+    /// How long to collect frames after the last one is sent
+    pub read_window: Duration,
+}
+
+/// What happened during a WebSocket exchange
+#[derive(Debug, Clone, Default)]
+pub struct WebSocketExchange {
+    /// Status of the handshake response (101 when upgraded)
+    pub status: u16,
+    /// Handshake response headers
+    pub headers: Vec<(String, String)>,
+    /// Whether the connection was upgraded
+    pub upgraded: bool,
+    /// Frames sent
+    pub sent: Vec<String>,
+    /// Text (or lossily decoded binary) frames received
+    pub received: Vec<String>,
+}
+
+impl WebSocketExchange {
+    /// Received frames concatenated, one per line, for matchers
+    pub fn body(&self) -> String {
+        self.received.join("\n")
+    }
+
+    /// Handshake status line and headers
+    pub fn upgrade_response(&self) -> String {
+        let mut lines = vec![format!("HTTP/1.1 {}", self.status)];
+        lines.extend(self.headers.iter().map(|(k, v)| format!("{}: {}", k, v)));
+        lines.join("\n")
+    }
+
+    /// Frame exchange with `>` for sent and `<` for received frames
+    pub fn transcript(&self) -> String {
+        let sent = self.sent.iter().map(|f| format!("> {}", f));
+        let received = self.received.iter().map(|f| format!("< {}", f));
+        sent.chain(received).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// Performs WebSocket exchanges with the scan's network settings
+#[derive(Debug, Clone)]
+pub struct WebSocketClient {
+    timeout: Duration,
+    user_agent: String,
+    proxy: Option<String>,
+}
+
+impl WebSocketClient {
+    /// Client using the timeout, user agent and proxy from `config`
+    pub fn new(config: &NetworkConfig) -> Self {
+        Self {
+            timeout: Duration::from_secs(config.timeout_secs),
+            user_agent: config.user_agent.clone(),
+            proxy: config.proxy.as_ref().map(|p| p.expose().to_string()),
+        }
+    }
+
+    /// Upgrade, send the request's frames and collect replies
+    ///
+    /// Returns `Ok(None)` when the target cannot be reached, and an exchange
+    /// with `upgraded: false` when the server refuses the upgrade.
+    pub async fn exchange(&self, request: &WebSocketRequest) -> Result<Option<WebSocketExchange>> {
+        let mut handshake =
+            request.url.as_str().into_client_request().map_err(|e| {
+                Error::Network(format!("Invalid WebSocket URL {}: {}", request.url, e))
+            })?;
+        let header_value = |value: &str| {
+            HeaderValue::from_str(value)
+                .map_err(|e| Error::Network(format!("Invalid WebSocket header value: {}", e)))
+        };
+        let headers = handshake.headers_mut();
+        headers.insert("User-Agent", header_value(&self.user_agent)?);
+        if let Some(subprotocol) = &request.subprotocol {
+            headers.insert("Sec-WebSocket-Protocol", header_value(subprotocol)?);
+        }
+        for (name, value) in &request.headers {
+            let name: tokio_tungstenite::tungstenite::http::HeaderName = name
+                .parse()
+                .map_err(|e| Error::Network(format!("Invalid WebSocket header name: {}", e)))?;
+            headers.insert(name, header_value(value)?);
+        }
+
+        let uri = handshake.uri();
+        let host = uri
+            .host()
+            .ok_or_else(|| Error::Network(format!("WebSocket URL has no host: {}", request.url)))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = uri
+            .port_u16()
+            .unwrap_or(if uri.scheme_str() == Some("wss") {
+                443
+            } else {
+                80
+            });
+
+        let stream = match tokio::time::timeout(
+            self.timeout,
+            connect_via_proxy(&host, port, self.proxy.as_deref()),
+        )
+        .await
+        {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) => {
+                tracing::debug!("WebSocket connect to {} failed: {}", request.url, e);
+                return Ok(None);
+            }
+            Err(_) => {
+                tracing::debug!("WebSocket connect to {} timed out", request.url);
+                return Ok(None);
+            }
+        };
+
+        let upgrade =
+            tokio_tungstenite::client_async_tls_with_config(handshake, stream, None, None);
+        let (mut socket, response) = match tokio::time::timeout(self.timeout, upgrade).await {
+            Ok(Ok(upgraded)) => upgraded,
+            Ok(Err(WsError::Http(response))) => {
+                return Ok(Some(WebSocketExchange {
+                    status: response.status().as_u16(),
+                    headers: header_pairs(response.headers()),
+                    ..Default::default()
+                }));
+            }
+            Ok(Err(e)) => {
+                tracing::debug!("WebSocket handshake with {} failed: {}", request.url, e);
+                return Ok(None);
+            }
+            Err(_) => {
+                tracing::debug!("WebSocket handshake with {} timed out", request.url);
+                return Ok(None);
+            }
+        };
+
+        let mut exchange = WebSocketExchange {
+            status: response.status().as_u16(),
+            headers: header_pairs(response.headers()),
+            upgraded: true,
+            ..Default::default()
+        };
+        for frame in &request.frames {
+            if let Err(e) = socket.send(Message::Text(frame.clone())).await {
+                tracing::debug!("WebSocket send to {} failed: {}", request.url, e);
+                break;
+            }
+            exchange.sent.push(frame.clone());
+        }
+
+        let deadline = tokio::time::Instant::now() + request.read_window;
+        while let Ok(Some(message)) = tokio::time::timeout_at(deadline, socket.next()).await {
+            match message {
+                Ok(Message::Text(text)) => exchange.received.push(text),
+                Ok(Message::Binary(data)) => {
+                    exchange
+                        .received
+                        .push(String::from_utf8_lossy(&data).into_owned());
+                }
+                Ok(Message::Close(_)) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+        let _ = socket.close(None).await;
+
+        Ok(Some(exchange))
+    }
+}
+
+fn header_pairs(
+    headers: &tokio_tungstenite::tungstenite::http::HeaderMap,
+) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use tokio::net::TcpListener;
+
+    /// Server answering `{"type":"ping"}` with `{"type":"pong"}`
+    async fn pong_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let Ok(mut socket) = tokio_tungstenite::accept_async(stream).await else {
+                        return;
+                    };
+                    socket
+                        .send(Message::Text(r#"{"type":"hello"}"#.into()))
+                        .await
+                        .unwrap();
+                    while let Some(Ok(Message::Text(text))) = socket.next().await {
+                        if text == r#"{"type":"ping"}"# {
+                            let _ = socket
+                                .send(Message::Text(r#"{"type":"pong"}"#.into()))
+                                .await;
+                        }
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    fn request(url: String) -> WebSocketRequest {
+        WebSocketRequest {
+            url,
+            subprotocol: None,
+            headers: Vec::new(),
+            frames: vec![r#"{"type":"ping"}"#.to_string()],
+            read_window: Duration::from_millis(300),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exchange_collects_replies() {
+        let port = pong_server().await;
+        let client = WebSocketClient::new(&Config::default().network);
+        let exchange = client
+            .exchange(&request(format!("ws://127.0.0.1:{}/ws", port)))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(exchange.upgraded);
+        assert_eq!(exchange.status, 101);
+        assert_eq!(exchange.body(), "{\"type\":\"hello\"}\n{\"type\":\"pong\"}");
+        assert!(exchange.transcript().starts_with("> {\"type\":\"ping\"}"));
+    }
+
+    #[tokio::test]
+    async fn test_refused_upgrade_and_closed_port() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::any())
+            .respond_with(wiremock::ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        let client = WebSocketClient::new(&Config::default().network);
+        let url = server.uri().replacen("http", "ws", 1) + "/ws";
+        let exchange = client.exchange(&request(url)).await.unwrap().unwrap();
+        assert!(!exchange.upgraded);
+        assert_eq!(exchange.status, 401);
+        assert!(exchange.received.is_empty());
+
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = closed.local_addr().unwrap().port();
+        drop(closed);
+        let url = format!("ws://127.0.0.1:{}/ws", port);
+        assert!(client.exchange(&request(url)).await.unwrap().is_none());
+    }
+}
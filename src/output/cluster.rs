@@ -0,0 +1,407 @@
+//! Response-similarity clustering (`--cluster`, `--collapse-clusters`)
+//!
+//! One template matching a CDN-fronted estate reports the same page from
+//! hundreds of hosts. Findings of the same template whose response bodies
+//! have close simhashes (over normalized word shingles) and similar lengths
+//! share a cluster id; collapsing keeps the first finding of each cluster and
+//! records how many it stands for.
+//!
+//! Findings without response evidence cannot be compared, so each one is a
+//! cluster of its own.
+
+use crate::types::{Finding, ScanResults};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Default number of simhash bits two responses may differ in
+pub const DEFAULT_MAX_DISTANCE: u32 = 3;
+
+/// Words per shingle
+const SHINGLE_WORDS: usize = 3;
+
+/// Largest relative body length difference two clustered responses may have
+const MAX_LENGTH_DIFF: f64 = 0.1;
+
+/// Simhash and length of a response body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseSignature {
+    simhash: u64,
+    length: usize,
+}
+
+impl ResponseSignature {
+    /// Signature of a response, ignoring a leading HTTP header block
+    ///
+    /// Returns `None` for empty bodies.
+    pub fn from_response(response: &str) -> Option<Self> {
+        let body = strip_headers(response).trim();
+        if body.is_empty() {
+            return None;
+        }
+        Some(Self {
+            simhash: simhash(&tokens(body)),
+            length: body.len(),
+        })
+    }
+
+    /// Signature of the finding's `evidence.response`, if it has one
+    pub fn from_finding(finding: &Finding) -> Option<Self> {
+        finding
+            .evidence
+            .response
+            .as_deref()
+            .and_then(Self::from_response)
+    }
+
+    /// Simhash bits that differ from `other`
+    pub fn distance(&self, other: &Self) -> u32 {
+        (self.simhash ^ other.simhash).count_ones()
+    }
+
+    /// Whether both responses are the same page up to `max_distance` bits
+    pub fn is_similar(&self, other: &Self, max_distance: u32) -> bool {
+        let longest = self.length.max(other.length) as f64;
+        let length_diff = self.length.abs_diff(other.length) as f64;
+        self.distance(other) <= max_distance && length_diff <= longest * MAX_LENGTH_DIFF
+    }
+}
+
+/// Assigns cluster ids and collapses clusters
+#[derive(Debug, Clone)]
+pub struct Clusterer {
+    max_distance: u32,
+}
+
+impl Default for Clusterer {
+    fn default() -> Self {
+        Self {
+            max_distance: DEFAULT_MAX_DISTANCE,
+        }
+    }
+}
+
+impl Clusterer {
+    /// Clusterer with [`DEFAULT_MAX_DISTANCE`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Let clustered responses differ in up to `bits` simhash bits
+    pub fn with_max_distance(mut self, bits: u32) -> Self {
+        self.max_distance = bits;
+        self
+    }
+
+    /// Set `cluster_id` on every finding, numbering clusters per template in order
+    pub fn assign(&self, results: &mut ScanResults) {
+        // Per template: the signature of each cluster's first finding, and the cluster count
+        let mut templates: HashMap<String, (Vec<(ResponseSignature, usize)>, usize)> =
+            HashMap::new();
+        for finding in &mut results.findings {
+            let (representatives, count) =
+                templates.entry(finding.template_id.clone()).or_default();
+            let number = match ResponseSignature::from_finding(finding) {
+                Some(signature) => match representatives
+                    .iter()
+                    .find(|(rep, _)| rep.is_similar(&signature, self.max_distance))
+                {
+                    Some((_, number)) => *number,
+                    None => {
+                        *count += 1;
+                        representatives.push((signature, *count));
+                        *count
+                    }
+                },
+                None => {
+                    *count += 1;
+                    *count
+                }
+            };
+            finding.cluster_id = Some(format!("{}#{}", finding.template_id, number));
+        }
+    }
+
+    /// Keep one finding per cluster, carrying the cluster's size and targets
+    ///
+    /// Assigns clusters first when any finding has none. The scan totals in
+    /// the statistics are kept; `reported_findings` becomes the cluster count.
+    pub fn collapse(&self, results: &mut ScanResults) {
+        if results.findings.iter().any(|f| f.cluster_id.is_none()) {
+            self.assign(results);
+        }
+
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut collapsed: Vec<Finding> = Vec::new();
+        for finding in std::mem::take(&mut results.findings) {
+            let cluster = finding.cluster_id.clone().unwrap_or_default();
+            match index.get(&cluster) {
+                Some(&i) => {
+                    let representative = &mut collapsed[i];
+                    representative.duplicates = representative.duplicates.map(|n| n + 1);
+                    if !representative.affected_targets.contains(&finding.target) {
+                        representative.affected_targets.push(finding.target);
+                    }
+                }
+                None => {
+                    let mut representative = finding;
+                    representative.duplicates = Some(0);
+                    representative.affected_targets = vec![representative.target.clone()];
+                    index.insert(cluster, collapsed.len());
+                    collapsed.push(representative);
+                }
+            }
+        }
+        results.findings = collapsed;
+        results.statistics.reported_findings = results.findings.len();
+    }
+}
+
+/// Findings and clusters of one template
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TemplateClusters {
+    /// Findings, counting those folded into collapsed representatives
+    pub findings: usize,
+    /// Distinct clusters
+    pub clusters: usize,
+}
+
+/// Cluster counts per template, for the scan summary
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClusterSummary {
+    /// Counts by template id
+    pub templates: BTreeMap<String, TemplateClusters>,
+}
+
+impl ClusterSummary {
+    /// Count the clustered findings of `results`, collapsed or not
+    pub fn from_results(results: &ScanResults) -> Self {
+        let mut clusters: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        let mut templates: BTreeMap<String, TemplateClusters> = BTreeMap::new();
+        for finding in &results.findings {
+            let Some(cluster) = finding.cluster_id.as_deref() else {
+                continue;
+            };
+            clusters
+                .entry(finding.template_id.as_str())
+                .or_default()
+                .insert(cluster);
+            templates
+                .entry(finding.template_id.clone())
+                .or_default()
+                .findings += 1 + finding.duplicates.unwrap_or(0);
+        }
+        for (template, ids) in clusters {
+            if let Some(counts) = templates.get_mut(template) {
+                counts.clusters = ids.len();
+            }
+        }
+        Self { templates }
+    }
+
+    /// Whether no finding carried a cluster id
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    /// Templates where several findings share a cluster
+    pub fn repeated(&self) -> impl Iterator<Item = (&String, &TemplateClusters)> {
+        self.templates
+            .iter()
+            .filter(|(_, counts)| counts.findings > counts.clusters)
+    }
+}
+
+/// Body of a raw HTTP response, or the whole text when it has no header block
+fn strip_headers(response: &str) -> &str {
+    if !response.starts_with("HTTP/") {
+        return response;
+    }
+    response
+        .split_once("\r\n\r\n")
+        .or_else(|| response.split_once("\n\n"))
+        .map_or("", |(_, body)| body)
+}
+
+/// Lowercase words, with any word containing a digit (dates, ids, nonces) replaced by `0`
+fn tokens(body: &str) -> Vec<String> {
+    body.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            if word.chars().any(|c| c.is_ascii_digit()) {
+                "0".to_string()
+            } else {
+                word.to_lowercase()
+            }
+        })
+        .collect()
+}
+
+/// 64-bit simhash over word shingles
+fn simhash(tokens: &[String]) -> u64 {
+    let mut weights = [0i64; 64];
+    let shingles: Vec<&[String]> = if tokens.len() < SHINGLE_WORDS {
+        vec![tokens]
+    } else {
+        tokens.windows(SHINGLE_WORDS).collect()
+    };
+    for shingle in shingles {
+        let hash = fnv1a(&shingle.join(" "));
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << bit) != 0 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |hash, (bit, _)| hash | (1 << bit))
+}
+
+/// FNV-1a, stable across runs unlike the std hasher
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Severity;
+    use uuid::Uuid;
+
+    const PAGE: &str = "<html><head><title>Index of /.git</title></head><body>\
+        <h1>Index of /.git</h1><ul><li>HEAD</li><li>config</li><li>objects</li>\
+        <li>refs</li><li>hooks</li><li>description</li></ul>\
+        <p>Generated at 2024-05-01 12:00:00 by nginx request id abc123</p></body></html>";
+
+    fn finding(target: &str, template: &str, response: Option<&str>) -> Finding {
+        let mut finding = Finding::new(target, template, Severity::High, "Git", "Exposed .git");
+        finding.evidence.response = response.map(str::to_string);
+        finding
+    }
+
+    fn results(findings: Vec<Finding>) -> ScanResults {
+        let mut results = ScanResults::new(Uuid::new_v4());
+        for finding in findings {
+            results.add_finding(finding);
+        }
+        results
+    }
+
+    #[test]
+    fn test_signature_ignores_headers_and_numbers() {
+        let a = ResponseSignature::from_response(&format!(
+            "HTTP/1.1 200 OK\r\nDate: Mon, 01 Jan 2024\r\n\r\n{}",
+            PAGE
+        ))
+        .unwrap();
+        let b = ResponseSignature::from_response(
+            &PAGE.replace("2024-05-01 12:00:00", "2025-11-30 23:59:59"),
+        )
+        .unwrap();
+        assert_eq!(a.distance(&b), 0);
+        assert!(a.is_similar(&b, 0));
+
+        let other = ResponseSignature::from_response(
+            "<html><body><h1>Welcome to nginx!</h1><p>If you see this page, the web server \
+             is successfully installed and working.</p></body></html>",
+        )
+        .unwrap();
+        assert!(!a.is_similar(&other, DEFAULT_MAX_DISTANCE));
+
+        assert!(ResponseSignature::from_response("HTTP/1.1 204 No Content\r\n\r\n").is_none());
+    }
+
+    #[test]
+    fn test_length_gate() {
+        let short = ResponseSignature::from_response("forbidden").unwrap();
+        let long = ResponseSignature::from_response(&"forbidden ".repeat(20)).unwrap();
+        assert!(!short.is_similar(&long, 64));
+    }
+
+    #[test]
+    fn test_assign_per_template() {
+        let mut results = results(vec![
+            finding("a.example.com", "git-exposed", Some(PAGE)),
+            finding(
+                "b.example.com",
+                "git-exposed",
+                Some(&PAGE.replace("abc123", "def456")),
+            ),
+            finding(
+                "c.example.com",
+                "git-exposed",
+                Some("<html>Not found</html>"),
+            ),
+            finding("d.example.com", "git-exposed", None),
+            finding("a.example.com", "svn-exposed", Some(PAGE)),
+        ]);
+        Clusterer::new().assign(&mut results);
+
+        let ids: Vec<&str> = results
+            .findings
+            .iter()
+            .map(|f| f.cluster_id.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                "git-exposed#1",
+                "git-exposed#1",
+                "git-exposed#2",
+                "git-exposed#3",
+                "svn-exposed#1"
+            ]
+        );
+
+        let summary = ClusterSummary::from_results(&results);
+        assert_eq!(
+            summary.templates["git-exposed"],
+            TemplateClusters {
+                findings: 4,
+                clusters: 3
+            }
+        );
+        assert_eq!(summary.repeated().count(), 1);
+    }
+
+    #[test]
+    fn test_collapse() {
+        let mut results = results(vec![
+            finding("a.example.com", "git-exposed", Some(PAGE)),
+            finding("b.example.com", "git-exposed", Some(PAGE)),
+            finding("d.example.com", "git-exposed", None),
+            finding("c.example.com", "git-exposed", Some(PAGE)),
+            finding("c.example.com", "git-exposed", Some(PAGE)),
+        ]);
+        Clusterer::new().collapse(&mut results);
+
+        assert_eq!(results.findings.len(), 2);
+        assert_eq!(results.statistics.total_findings, 5);
+        assert_eq!(results.statistics.reported_findings, 2);
+
+        let first = &results.findings[0];
+        assert_eq!(first.target, "a.example.com");
+        assert_eq!(first.duplicates, Some(3));
+        assert_eq!(
+            first.affected_targets,
+            ["a.example.com", "b.example.com", "c.example.com"]
+        );
+        assert_eq!(results.findings[1].duplicates, Some(0));
+
+        // Counts survive collapsing
+        let summary = ClusterSummary::from_results(&results);
+        assert_eq!(summary.templates["git-exposed"].findings, 5);
+        assert_eq!(summary.templates["git-exposed"].clusters, 2);
+    }
+
+    #[test]
+    fn test_unclustered_summary_is_empty() {
+        let results = results(vec![finding("a.example.com", "git-exposed", Some(PAGE))]);
+        assert!(ClusterSummary::from_results(&results).is_empty());
+    }
+}
@@ -0,0 +1,385 @@
+//! Elasticsearch output
+//!
+//! Bulk-indexes findings into an Elasticsearch index. Documents use
+//! [`Finding::fingerprint`] as their `_id`, so re-running a scan updates the
+//! existing documents instead of duplicating them.
+
+use crate::config::ElasticConfig;
+use crate::error::{Error, Result};
+use crate::types::{Finding, ScanResults};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Delay before the first retry of a throttled batch; doubles on each retry
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Outcome of indexing a set of findings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexSummary {
+    /// Documents accepted by Elasticsearch
+    pub indexed: usize,
+    /// Documents rejected or never sent
+    pub failed: usize,
+}
+
+/// Writes findings to Elasticsearch through the `_bulk` API
+#[derive(Debug)]
+pub struct ElasticsearchOutput {
+    client: Client,
+    base_url: String,
+    config: ElasticConfig,
+    retry_delay: Duration,
+}
+
+impl ElasticsearchOutput {
+    /// Create an output for the cluster at `base_url`
+    pub fn new(base_url: &str, config: ElasticConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            config,
+            retry_delay: DEFAULT_RETRY_DELAY,
+        })
+    }
+
+    /// Override the initial delay between retries of a throttled batch
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Index mapping for finding documents
+    pub fn index_mapping() -> Value {
+        json!({
+            "mappings": {
+                "properties": {
+                    "finding_id": { "type": "keyword" },
+                    "scan_id": { "type": "keyword" },
+                    "target": { "type": "keyword" },
+                    "template_id": { "type": "keyword" },
+                    "severity": { "type": "keyword" },
+                    "confidence": { "type": "integer" },
+                    "title": { "type": "text", "fields": { "raw": { "type": "keyword" } } },
+                    "description": { "type": "text" },
+                    "matched_patterns": { "type": "keyword" },
+                    "cve_ids": { "type": "keyword" },
+                    "cwe_ids": { "type": "keyword" },
+                    "cvss": { "type": "float" },
+                    "tags": { "type": "keyword" },
+                    "references": { "type": "keyword" },
+                    "remediation": { "type": "text" },
+                    "request": { "type": "text", "index": false },
+                    "response": { "type": "text", "index": false },
+                    "timestamp": { "type": "date" }
+                }
+            }
+        })
+    }
+
+    /// Flatten a finding into an index document
+    pub fn document(finding: &Finding, scan_id: Option<&str>) -> Value {
+        json!({
+            "finding_id": finding.id.to_string(),
+            "scan_id": scan_id,
+            "target": finding.target,
+            "template_id": finding.template_id,
+            "severity": finding.severity.to_string(),
+            "confidence": finding.confidence,
+            "title": finding.title,
+            "description": finding.description,
+            "matched_patterns": finding.evidence.matched_patterns,
+            "cve_ids": finding.cve_ids,
+            "cwe_ids": finding.cwe_ids,
+            "cvss": finding.cvss_score,
+            "tags": finding.tags,
+            "references": finding.references,
+            "remediation": finding.remediation,
+            "request": finding.evidence.request,
+            "response": finding.evidence.response,
+            "timestamp": finding.timestamp.to_rfc3339(),
+        })
+    }
+
+    /// Build the NDJSON body of a `_bulk` request
+    pub fn bulk_body(&self, findings: &[&Finding], scan_id: Option<&str>) -> String {
+        let mut body = String::new();
+        for finding in findings {
+            let action = json!({
+                "index": { "_index": self.config.index, "_id": finding.fingerprint() }
+            });
+            body.push_str(&action.to_string());
+            body.push('\n');
+            body.push_str(&Self::document(finding, scan_id).to_string());
+            body.push('\n');
+        }
+        body
+    }
+
+    /// Create the index with the finding mapping unless it already exists
+    pub async fn ensure_index(&self) -> Result<()> {
+        let url = format!("{}/{}", self.base_url, self.config.index);
+        let response = self
+            .authorize(self.client.put(&url))
+            .json(&Self::index_mapping())
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = response.text().await.unwrap_or_default();
+        if body.contains("resource_already_exists_exception") {
+            return Ok(());
+        }
+        Err(Error::Network(format!(
+            "Failed to create Elasticsearch index '{}': {} {}",
+            self.config.index, status, body
+        )))
+    }
+
+    /// Create the index if needed and bulk-index all findings of a scan
+    pub async fn write_results(&self, results: &ScanResults) -> Result<IndexSummary> {
+        self.ensure_index().await?;
+        let findings: Vec<&Finding> = results.findings.iter().collect();
+        self.index_findings(&findings, Some(&results.scan_id.to_string()))
+            .await
+    }
+
+    /// Bulk-index findings in batches
+    ///
+    /// Batches throttled with 429 are retried with exponential backoff. After
+    /// `failure_threshold` consecutive failed batches the remaining batches
+    /// are skipped and an error is returned.
+    pub async fn index_findings(
+        &self,
+        findings: &[&Finding],
+        scan_id: Option<&str>,
+    ) -> Result<IndexSummary> {
+        let mut summary = IndexSummary::default();
+        let mut consecutive_failures = 0;
+        let mut last_error = None;
+
+        for batch in findings.chunks(self.config.batch_size.max(1)) {
+            match self.send_batch(batch, scan_id).await {
+                Ok(failed) => {
+                    consecutive_failures = 0;
+                    summary.indexed += batch.len() - failed;
+                    summary.failed += failed;
+                }
+                Err(e) => {
+                    tracing::warn!("Elasticsearch bulk request failed: {}", e);
+                    consecutive_failures += 1;
+                    last_error = Some(e);
+                }
+            }
+
+            if consecutive_failures >= self.config.failure_threshold.max(1) {
+                return Err(Error::Network(format!(
+                    "Elasticsearch output disabled after {} consecutive failures ({} of {} findings indexed): {}",
+                    consecutive_failures,
+                    summary.indexed,
+                    findings.len(),
+                    last_error.map(|e| e.to_string()).unwrap_or_default()
+                )));
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Send one batch, retrying throttled documents; returns the number rejected
+    async fn send_batch(&self, batch: &[&Finding], scan_id: Option<&str>) -> Result<usize> {
+        let url = format!("{}/_bulk", self.base_url);
+        let mut pending: Vec<&Finding> = batch.to_vec();
+        let mut delay = self.retry_delay;
+        let mut rejected = 0;
+
+        for attempt in 0..=self.config.max_retries {
+            if attempt > 0 {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+
+            let response = self
+                .authorize(self.client.post(&url))
+                .header("Content-Type", "application/x-ndjson")
+                .body(self.bulk_body(&pending, scan_id))
+                .send()
+                .await?;
+
+            let status = response.status();
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                continue;
+            }
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                return Err(Error::Network(format!(
+                    "Elasticsearch _bulk returned {}: {}",
+                    status, body
+                )));
+            }
+
+            let body: Value = response.json().await?;
+            let statuses = item_statuses(&body);
+            let mut throttled = Vec::new();
+            for (finding, status) in pending.iter().zip(&statuses) {
+                match *status {
+                    429 => throttled.push(*finding),
+                    s if s >= 300 => rejected += 1,
+                    _ => {}
+                }
+            }
+            if throttled.is_empty() {
+                return Ok(rejected);
+            }
+            pending = throttled;
+        }
+
+        Err(Error::RateLimitExceeded(format!(
+            "Elasticsearch kept throttling {} documents after {} retries",
+            pending.len(),
+            self.config.max_retries
+        )))
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        if let Some(api_key) = &self.config.api_key {
+            request.header("Authorization", format!("ApiKey {}", api_key.expose()))
+        } else if let Some(username) = &self.config.username {
+            request.basic_auth(username, self.config.password.as_ref().map(|p| p.expose()))
+        } else {
+            request
+        }
+    }
+}
+
+/// Per-document HTTP statuses from a `_bulk` response, in request order
+fn item_statuses(body: &Value) -> Vec<u64> {
+    body.get("items")
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| {
+                    item.as_object()
+                        .and_then(|o| o.values().next())
+                        .and_then(|action| action.get("status"))
+                        .and_then(Value::as_u64)
+                        .unwrap_or(500)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Severity;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    fn finding(target: &str) -> Finding {
+        let mut finding = Finding::new(
+            target,
+            "redis-unauth",
+            Severity::High,
+            "Redis without auth",
+            "",
+        )
+        .with_cvss_score(7.5);
+        finding.evidence.matched_patterns = vec!["redis_version".to_string()];
+        finding
+    }
+
+    fn bulk_ok(count: usize) -> ResponseTemplate {
+        let items: Vec<Value> = (0..count)
+            .map(|_| json!({ "index": { "status": 201 } }))
+            .collect();
+        ResponseTemplate::new(200).set_body_json(json!({ "errors": false, "items": items }))
+    }
+
+    #[tokio::test]
+    async fn test_bulk_request_format() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/_bulk"))
+            .respond_with(bulk_ok(2))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let output = ElasticsearchOutput::new(&server.uri(), ElasticConfig::default()).unwrap();
+        let findings = [finding("10.0.0.1:6379"), finding("10.0.0.2:6379")];
+        let refs: Vec<&Finding> = findings.iter().collect();
+        let summary = output.index_findings(&refs, Some("scan-1")).await.unwrap();
+        assert_eq!(summary.indexed, 2);
+
+        let requests: Vec<Request> = server.received_requests().await.unwrap();
+        let body = String::from_utf8(requests[0].body.clone()).unwrap();
+        assert!(body.ends_with('\n'));
+        let lines: Vec<Value> = body
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["index"]["_index"], "cert-x-gen-findings");
+        assert_eq!(lines[0]["index"]["_id"], findings[0].fingerprint());
+        assert_eq!(lines[1]["severity"], "high");
+        assert_eq!(lines[1]["matched_patterns"], json!(["redis_version"]));
+        assert_eq!(lines[1]["cvss"].as_f64(), Some(7.5));
+        assert_eq!(lines[1]["scan_id"], "scan-1");
+        assert_ne!(lines[0]["index"]["_id"], lines[2]["index"]["_id"]);
+    }
+
+    #[tokio::test]
+    async fn test_retries_throttled_batch() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/_bulk"))
+            .respond_with(ResponseTemplate::new(429))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/_bulk"))
+            .respond_with(bulk_ok(1))
+            .mount(&server)
+            .await;
+
+        let output = ElasticsearchOutput::new(&server.uri(), ElasticConfig::default())
+            .unwrap()
+            .with_retry_delay(Duration::from_millis(1));
+        let finding = finding("10.0.0.1:6379");
+        let summary = output.index_findings(&[&finding], None).await.unwrap();
+        assert_eq!(summary.indexed, 1);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaks_on_persistent_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/_bulk"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let config = ElasticConfig {
+            batch_size: 1,
+            failure_threshold: 2,
+            ..ElasticConfig::default()
+        };
+        let output = ElasticsearchOutput::new(&server.uri(), config).unwrap();
+        let findings: Vec<Finding> = (1..=5).map(|i| finding(&format!("10.0.0.{}", i))).collect();
+        let refs: Vec<&Finding> = findings.iter().collect();
+
+        assert!(output.index_findings(&refs, None).await.is_err());
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,272 @@
+//! Technology inventory (`--output-format inventory`)
+//!
+//! Collects the [`Technology`] of every finding and groups it by target:
+//! each product and version is listed once with the templates that evidenced
+//! it and a CPE 2.3 name where one is known. Written as
+//! `<basename>.inventory.json` and a plain-text table in
+//! `<basename>.inventory.txt`.
+
+use super::{write_atomic, OutputFormatter};
+use crate::error::{Error, Result};
+use crate::types::{ScanResults, Technology};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// One product seen on a target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InventoryEntry {
+    /// Product name
+    pub product: String,
+    /// Vendor, when known
+    pub vendor: Option<String>,
+    /// Detected version
+    pub version: Option<String>,
+    /// CPE 2.3 name, when a version is known or a template gave one
+    pub cpe: Option<String>,
+    /// Templates whose findings identified it
+    pub templates: BTreeSet<String>,
+}
+
+/// Technologies detected per target
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Inventory {
+    /// Entries by target, sorted by product and version
+    pub targets: BTreeMap<String, Vec<InventoryEntry>>,
+}
+
+impl Inventory {
+    /// Aggregate the technologies of all findings
+    pub fn from_results(results: &ScanResults) -> Self {
+        let mut inventory = Self::default();
+        for finding in &results.findings {
+            if let Some(technology) = &finding.technology {
+                inventory.add(&finding.target, &finding.template_id, technology);
+            }
+        }
+        for entries in inventory.targets.values_mut() {
+            entries.sort_by(|a, b| {
+                (&a.product, &a.version, &a.vendor).cmp(&(&b.product, &b.version, &b.vendor))
+            });
+        }
+        inventory
+    }
+
+    /// Record that `template_id` identified `technology` on `target`
+    pub fn add(&mut self, target: &str, template_id: &str, technology: &Technology) {
+        let entries = self.targets.entry(target.to_string()).or_default();
+        let same = |e: &&mut InventoryEntry| {
+            e.product.eq_ignore_ascii_case(&technology.product)
+                && e.version == technology.version
+                && (e.vendor.is_none()
+                    || technology.vendor.is_none()
+                    || e.vendor == technology.vendor)
+        };
+        match entries.iter_mut().find(same) {
+            Some(entry) => {
+                entry.templates.insert(template_id.to_string());
+                // A vendor learned from a later finding makes for a better CPE
+                if entry.vendor.is_none() && technology.vendor.is_some() {
+                    entry.vendor = technology.vendor.clone();
+                    entry.cpe = technology.cpe().or(entry.cpe.take());
+                } else if entry.cpe.is_none() {
+                    entry.cpe = technology.cpe();
+                }
+            }
+            None => entries.push(InventoryEntry {
+                product: technology.product.clone(),
+                vendor: technology.vendor.clone(),
+                version: technology.version.clone(),
+                cpe: technology.cpe(),
+                templates: BTreeSet::from([template_id.to_string()]),
+            }),
+        }
+    }
+
+    /// Whether no technology was detected
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Plain-text table, one row per target and product
+    pub fn to_table(&self) -> String {
+        let headers = ["TARGET", "PRODUCT", "VERSION", "CPE", "TEMPLATES"];
+        let rows: Vec<[String; 5]> = self
+            .targets
+            .iter()
+            .flat_map(|(target, entries)| {
+                entries.iter().map(move |entry| {
+                    let product = match &entry.vendor {
+                        Some(vendor) => format!("{} ({})", entry.product, vendor),
+                        None => entry.product.clone(),
+                    };
+                    [
+                        target.clone(),
+                        product,
+                        entry.version.clone().unwrap_or_else(|| "-".to_string()),
+                        entry.cpe.clone().unwrap_or_else(|| "-".to_string()),
+                        entry
+                            .templates
+                            .iter()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ]
+                })
+            })
+            .collect();
+        if rows.is_empty() {
+            return "No technologies detected\n".to_string();
+        }
+
+        let mut widths = headers.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let line = |cells: &[&str]| {
+            let padded: Vec<String> = cells
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            format!("{}\n", padded.join("  ").trim_end())
+        };
+
+        let mut table = line(&headers);
+        for row in &rows {
+            table.push_str(&line(&row.each_ref().map(String::as_str)));
+        }
+        table
+    }
+}
+
+/// Technology inventory formatter
+#[derive(Debug, Default)]
+pub struct InventoryFormatter;
+
+impl InventoryFormatter {
+    /// Create a new inventory formatter
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl OutputFormatter for InventoryFormatter {
+    fn name(&self) -> &str {
+        "inventory"
+    }
+
+    fn format(&self, results: &ScanResults) -> Result<String> {
+        serde_json::to_string_pretty(&Inventory::from_results(results))
+            .map_err(|e| Error::Serialization(e.to_string()))
+    }
+
+    fn output_paths(&self, path: &Path) -> Vec<PathBuf> {
+        vec![
+            path.with_extension("inventory.json"),
+            path.with_extension("inventory.txt"),
+        ]
+    }
+
+    /// Writes the JSON and the table side by side (`<basename>.inventory.json`
+    /// and `<basename>.inventory.txt`)
+    fn write_to_file(&self, results: &ScanResults, path: &Path) -> Result<()> {
+        let inventory = Inventory::from_results(results);
+        let json = serde_json::to_string_pretty(&inventory)
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        write_atomic(&path.with_extension("inventory.json"), json)?;
+        write_atomic(&path.with_extension("inventory.txt"), inventory.to_table())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Finding, Severity};
+    use uuid::Uuid;
+
+    fn finding(target: &str, template_id: &str, technology: Technology) -> Finding {
+        Finding::new(target, template_id, Severity::Info, "Tech", "detected")
+            .with_technology(technology)
+    }
+
+    #[test]
+    fn test_inventory_groups_by_target_and_version() {
+        let nginx = |version: &str| Technology {
+            version: Some(version.to_string()),
+            ..Technology::new("nginx")
+        };
+        let mut results = ScanResults::new(Uuid::new_v4());
+        results.findings = vec![
+            finding("https://a.example", "nginx-version-detect", nginx("1.18.0")),
+            finding(
+                "https://a.example",
+                "tech-detect",
+                Technology {
+                    vendor: Some("f5".to_string()),
+                    ..nginx("1.18.0")
+                },
+            ),
+            finding("https://a.example", "php-detect", Technology::new("PHP")),
+            finding("https://b.example", "nginx-version-detect", nginx("1.25.3")),
+            Finding::new(
+                "https://b.example",
+                "xss",
+                Severity::High,
+                "XSS",
+                "reflected",
+            ),
+        ];
+
+        let inventory = Inventory::from_results(&results);
+        let a = &inventory.targets["https://a.example"];
+        assert_eq!(a.len(), 2);
+        assert_eq!(a[0].product, "PHP");
+        assert_eq!(a[0].cpe, None);
+        assert_eq!(a[1].vendor.as_deref(), Some("f5"));
+        assert_eq!(
+            a[1].templates,
+            BTreeSet::from([
+                "nginx-version-detect".to_string(),
+                "tech-detect".to_string()
+            ])
+        );
+        assert_eq!(
+            a[1].cpe.as_deref(),
+            Some("cpe:2.3:a:f5:nginx:1.18.0:*:*:*:*:*:*:*")
+        );
+        assert_eq!(inventory.targets["https://b.example"].len(), 1);
+
+        let table = inventory.to_table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("TARGET"));
+        assert!(lines[2].contains("nginx (f5)") && lines[2].contains("1.18.0"));
+        assert!(lines[3].starts_with("https://b.example"));
+    }
+
+    #[test]
+    fn test_inventory_formatter_writes_json_and_table() {
+        let mut results = ScanResults::new(Uuid::new_v4());
+        results.findings.push(finding(
+            "https://a.example",
+            "nginx-version-detect",
+            Technology::new("nginx"),
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        InventoryFormatter::new()
+            .write_to_file(&results, &dir.path().join("scan.inventory"))
+            .unwrap();
+        let json: Inventory = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join("scan.inventory.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json.targets["https://a.example"][0].product, "nginx");
+        let table = std::fs::read_to_string(dir.path().join("scan.inventory.txt")).unwrap();
+        assert!(table.contains("nginx-version-detect"));
+    }
+}
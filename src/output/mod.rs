@@ -1,11 +1,72 @@
 //! Output formatting and reporting for scan results
 
 use crate::error::{Error, Result};
-use crate::types::{ScanResults, Severity};
+use crate::types::{Finding, ScanResults, Severity, SCREENSHOT_DIR};
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
 use serde_json;
-use std::fs::File;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+mod cluster;
+mod elasticsearch;
+mod inventory;
+mod renderer;
+
+pub use cluster::{
+    ClusterSummary, Clusterer, ResponseSignature, TemplateClusters, DEFAULT_MAX_DISTANCE,
+};
+pub use elasticsearch::{ElasticsearchOutput, IndexSummary};
+pub use inventory::{Inventory, InventoryEntry, InventoryFormatter};
+pub use renderer::TemplateRenderer;
+
+/// Write `contents` to `path` atomically, creating its parent directories
+///
+/// The data goes to a temporary file in the same directory that is then
+/// renamed over `path`, so a crash mid-write never leaves a truncated report.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let dir = match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            dir
+        }
+        None => Path::new("."),
+    };
+    let mut file = tempfile::Builder::new()
+        .prefix(".cxg-output-")
+        .tempfile_in(dir)?;
+    file.write_all(contents.as_ref())?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|e| Error::Io(e.error))?;
+    Ok(())
+}
+
+/// `base` with the scan's start time added to the file name, so each scan
+/// writes new files: `results` becomes `results-20240301-142501`
+pub fn timestamped_path(base: &Path, at: DateTime<Utc>) -> PathBuf {
+    let stamp = at.format("%Y%m%d-%H%M%S");
+    let stem = base
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match base.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, stamp, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, stamp),
+    };
+    base.with_file_name(name)
+}
+
+/// Write each finding's screenshot to [`Finding::screenshot_path`] beside `report`
+fn write_screenshots(results: &ScanResults, report: &Path) -> Result<()> {
+    let dir = report.parent().unwrap_or_else(|| Path::new(""));
+    for finding in &results.findings {
+        if let Some(png) = &finding.screenshot {
+            std::fs::create_dir_all(dir.join(SCREENSHOT_DIR))?;
+            std::fs::write(dir.join(finding.screenshot_path()), png)?;
+        }
+    }
+    Ok(())
+}
 
 /// Output formatter trait
 pub trait OutputFormatter: Send + Sync {
@@ -15,13 +76,14 @@ pub trait OutputFormatter: Send + Sync {
     /// Format scan results
     fn format(&self, results: &ScanResults) -> Result<String>;
 
+    /// Files [`write_to_file`](Self::write_to_file) creates for `path`
+    fn output_paths(&self, path: &Path) -> Vec<PathBuf> {
+        vec![path.to_path_buf()]
+    }
+
     /// Write formatted results to file
     fn write_to_file(&self, results: &ScanResults, path: &Path) -> Result<()> {
-        let output = self.format(results)?;
-        let mut file = File::create(path).map_err(|e| Error::Io(e))?;
-        file.write_all(output.as_bytes())
-            .map_err(|e| Error::Io(e))?;
-        Ok(())
+        write_atomic(path, self.format(results)?)
     }
 }
 
@@ -50,6 +112,13 @@ impl OutputFormatter for JsonFormatter {
             serde_json::to_string(results).map_err(|e| Error::Serialization(e.to_string()))
         }
     }
+
+    /// Screenshots are written to `screenshots/<finding id>.png` beside the
+    /// report, the path the `screenshot` evidence data refers to
+    fn write_to_file(&self, results: &ScanResults, path: &Path) -> Result<()> {
+        write_atomic(path, self.format(results)?)?;
+        write_screenshots(results, path)
+    }
 }
 
 /// CSV output formatter
@@ -78,13 +147,13 @@ impl OutputFormatter for CsvFormatter {
         let mut output = String::new();
 
         // Header
-        output.push_str("Finding ID,Target,Template ID,Severity,Confidence,Title,Description,CVE IDs,Timestamp\n");
+        output.push_str("Finding ID,Target,Template ID,Severity,Confidence,Title,Description,CVE IDs,References,Timestamp,Labels\n");
 
         // Findings
         for finding in &results.findings {
             let cve_ids = finding.cve_ids.join(";");
             let line = format!(
-                "{},{},{},{},{},{},{},{},{}\n",
+                "{},{},{},{},{},{},{},{},{},{},{}\n",
                 finding.id,
                 finding.target,
                 finding.template_id,
@@ -93,7 +162,9 @@ impl OutputFormatter for CsvFormatter {
                 Self::escape_csv(&finding.title),
                 Self::escape_csv(&finding.description),
                 cve_ids,
-                finding.timestamp
+                Self::escape_csv(&finding.references.join(";")),
+                finding.timestamp,
+                Self::escape_csv(&finding.labels_display(";"))
             );
             output.push_str(&line);
         }
@@ -193,18 +264,57 @@ impl OutputFormatter for MarkdownFormatter {
                     output.push_str(&format!("- **CVE IDs**: {}\n", finding.cve_ids.join(", ")));
                 }
 
+                if !finding.target_labels.is_empty() {
+                    output.push_str(&format!("- **Labels**: {}\n", finding.labels_display(", ")));
+                }
+
+                if let Some(ref cluster) = finding.cluster_id {
+                    output.push_str(&format!("- **Cluster**: {}\n", cluster));
+                }
+
+                if finding.affected_targets.len() > 1 {
+                    output.push_str(&format!(
+                        "- **Affected Targets** ({}): {}\n",
+                        finding.affected_targets.len(),
+                        finding.affected_targets.join(", ")
+                    ));
+                }
+
                 output.push_str(&format!("\n**Description**: {}\n\n", finding.description));
 
                 if let Some(ref remediation) = finding.remediation {
                     output.push_str(&format!("**Remediation**: {}\n\n", remediation));
                 }
 
+                if !finding.references.is_empty() {
+                    output.push_str("**References**:\n\n");
+                    for reference in &finding.references {
+                        output.push_str(&format!("- <{}>\n", reference));
+                    }
+                    output.push('\n');
+                }
+
+                if finding.screenshot.is_some() {
+                    output.push_str(&format!(
+                        "**Screenshot**:\n\n![Screenshot of {}]({})\n\n",
+                        finding.target,
+                        finding.screenshot_path()
+                    ));
+                }
+
                 output.push_str("---\n\n");
             }
         }
 
         Ok(output)
     }
+
+    /// Screenshots are written to `screenshots/<finding id>.png` beside the
+    /// report, where its image links point
+    fn write_to_file(&self, results: &ScanResults, path: &Path) -> Result<()> {
+        write_atomic(path, self.format(results)?)?;
+        write_screenshots(results, path)
+    }
 }
 
 /// SARIF output formatter (for CI/CD integration)
@@ -245,11 +355,13 @@ impl OutputFormatter for SarifFormatter {
             }]
         });
 
+        // Screenshots are embedded as run artifacts that results attach by index
+        let mut artifacts = Vec::new();
         let sarif_results = results
             .findings
             .iter()
             .map(|finding| {
-                serde_json::json!({
+                let mut result = serde_json::json!({
                     "ruleId": finding.template_id,
                     "level": Self::severity_to_sarif_level(&finding.severity),
                     "message": {
@@ -268,11 +380,60 @@ impl OutputFormatter for SarifFormatter {
                         "cveIds": finding.cve_ids,
                         "cweIds": finding.cwe_ids
                     }
-                })
+                });
+                if !finding.target_labels.is_empty() {
+                    result["properties"]["labels"] = serde_json::json!(finding.target_labels);
+                }
+                if let Some(ref cluster) = finding.cluster_id {
+                    result["properties"]["clusterId"] = serde_json::json!(cluster);
+                }
+                if let Some(duplicates) = finding.duplicates {
+                    result["properties"]["duplicates"] = serde_json::json!(duplicates);
+                    result["properties"]["affectedTargets"] =
+                        serde_json::json!(finding.affected_targets);
+                }
+                if finding.screenshot.is_some() {
+                    result["attachments"] = serde_json::json!([{
+                        "description": { "text": "Screenshot" },
+                        "artifactLocation": {
+                            "uri": finding.screenshot_path(),
+                            "index": artifacts.len()
+                        }
+                    }]);
+                }
+                if let Some(png) = &finding.screenshot {
+                    artifacts.push(serde_json::json!({
+                        "location": { "uri": finding.screenshot_path() },
+                        "mimeType": "image/png",
+                        "contents": {
+                            "binary": base64::engine::general_purpose::STANDARD.encode(png)
+                        }
+                    }));
+                }
+                if !finding.references.is_empty() {
+                    result["relatedLocations"] = finding
+                        .references
+                        .iter()
+                        .enumerate()
+                        .map(|(id, reference)| {
+                            serde_json::json!({
+                                "id": id,
+                                "physicalLocation": {
+                                    "artifactLocation": { "uri": reference }
+                                },
+                                "message": { "text": "Reference" }
+                            })
+                        })
+                        .collect();
+                }
+                result
             })
             .collect::<Vec<_>>();
 
         sarif["runs"][0]["results"] = serde_json::json!(sarif_results);
+        if !artifacts.is_empty() {
+            sarif["runs"][0]["artifacts"] = serde_json::json!(artifacts);
+        }
 
         serde_json::to_string_pretty(&sarif).map_err(|e| Error::Serialization(e.to_string()))
     }
@@ -629,6 +790,18 @@ a:hover { text-decoration: underline; }
   color: var(--fg); 
   font-size: 13px; 
 }
+.confidence-bar {
+  margin-top: 6px;
+  height: 4px;
+  background: var(--border);
+  border-radius: 2px;
+  overflow: hidden;
+}
+.confidence-bar span {
+  display: block;
+  height: 100%;
+  background: var(--teal);
+}
 
 .desc {
   background: var(--bg-panel-2);
@@ -678,6 +851,11 @@ a:hover { text-decoration: underline; }
   max-height: 300px;
 }
 
+.evidence img.screenshot {
+  display: block;
+  max-width: 100%;
+}
+
 /* Tags */
 .tags {
   display: flex;
@@ -941,12 +1119,36 @@ a:hover { text-decoration: underline; }
                     String::new()
                 };
 
-                // Build tags section
-                let tags_html = if !finding.tags.is_empty() {
+                // Build screenshot section
+                let screenshot_html = if let Some(ref png) = finding.screenshot {
+                    format!(
+                        r#"
+                    <div class="evidence">
+                        <div class="evidence-head">
+                            <span>Screenshot</span>
+                        </div>
+                        <img class="screenshot" alt="Screenshot of {}" src="data:image/png;base64,{}">
+                    </div>"#,
+                        Self::escape_html(&finding.target),
+                        base64::engine::general_purpose::STANDARD.encode(png)
+                    )
+                } else {
+                    String::new()
+                };
+
+                // Build tags section, with target labels rendered as key=value tags
+                let tags_html = if !finding.tags.is_empty() || !finding.target_labels.is_empty() {
                     let tags: String = finding
                         .tags
                         .iter()
-                        .map(|t| format!(r#"<span class="tag">{}</span>"#, Self::escape_html(t)))
+                        .cloned()
+                        .chain(
+                            finding
+                                .target_labels
+                                .iter()
+                                .map(|(key, value)| format!("{}={}", key, value)),
+                        )
+                        .map(|t| format!(r#"<span class="tag">{}</span>"#, Self::escape_html(&t)))
                         .collect::<Vec<_>>()
                         .join("");
                     format!(r#"<div class="tags">{}</div>"#, tags)
@@ -1021,6 +1223,7 @@ a:hover { text-decoration: underline; }
                         <div class="kv">
                             <div class="k">Confidence</div>
                             <div class="v">{}%</div>
+                            <div class="confidence-bar"><span style="width: {}%"></span></div>
                         </div>
                         <div class="kv">
                             <div class="k">Timestamp</div>
@@ -1034,6 +1237,7 @@ a:hover { text-decoration: underline; }
                     {}
                     {}
                     {}
+                    {}
                 </div>
             </article>
 "#,
@@ -1043,11 +1247,13 @@ a:hover { text-decoration: underline; }
                     Self::escape_html(&finding.target),
                     Self::escape_html(&finding.template_id),
                     finding.confidence,
+                    finding.confidence,
                     finding.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
                     vuln_html,
                     Self::escape_html(&finding.description),
                     tags_html,
                     evidence_html,
+                    screenshot_html,
                     refs_html
                 ));
             }
@@ -1087,10 +1293,22 @@ impl HtmlFormatter {
     }
 }
 
+/// Error for a results file that is kept because overwriting is off
+pub fn already_exists(path: &Path) -> Error {
+    Error::Io(std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        format!(
+            "{} already exists (use --output-overwrite or --output-timestamp)",
+            path.display()
+        ),
+    ))
+}
+
 /// Output manager for handling multiple output formats
 #[allow(missing_debug_implementations)]
 pub struct OutputManager {
     formatters: Vec<Box<dyn OutputFormatter>>,
+    overwrite: bool,
 }
 
 impl OutputManager {
@@ -1103,10 +1321,18 @@ impl OutputManager {
                 Box::new(MarkdownFormatter::new()),
                 Box::new(SarifFormatter::new()),
                 Box::new(HtmlFormatter::new()),
+                Box::new(InventoryFormatter::new()),
             ],
+            overwrite: false,
         }
     }
 
+    /// Replace existing results files instead of refusing to write them
+    pub fn with_overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
     /// Get formatter by name
     pub fn get_formatter(&self, name: &str) -> Option<&dyn OutputFormatter> {
         self.formatters
@@ -1116,26 +1342,99 @@ impl OutputManager {
     }
 
     /// Write results in multiple formats
+    ///
+    /// A format ending in `.j2` is a path to an output template; its result is
+    /// written with the extension before `.j2` (see [`TemplateRenderer`]).
+    ///
+    /// Files are written atomically and parent directories are created.
+    /// Existing files are left alone unless overwriting is enabled. When one
+    /// format fails the others are still written; with several formats the
+    /// error is an [`Error::OutputWrite`] listing what was and was not written.
     pub fn write_results(
         &self,
         results: &ScanResults,
         base_path: &Path,
         formats: &[String],
     ) -> Result<()> {
+        let mut written = Vec::new();
+        let mut failed = Vec::new();
         for format in formats {
-            if let Some(formatter) = self.get_formatter(format) {
-                let file_path = base_path.with_extension(format);
-                tracing::info!("Writing {} output to {}", format, file_path.display());
-                formatter.write_to_file(results, &file_path)?;
+            // Indexed over the network rather than written to a file
+            if format == "elasticsearch" {
+                continue;
+            }
+            match self.write_format(results, base_path, format) {
+                Ok(Some(path)) => written.push(format!("{} ({})", format, path.display())),
+                Ok(None) => tracing::warn!("Unknown output format: {}", format),
+                Err(e) => failed.push((format.as_str(), e)),
+            }
+        }
+
+        match failed.len() {
+            0 => Ok(()),
+            1 if written.is_empty() => Err(failed.remove(0).1),
+            _ => Err(Error::OutputWrite {
+                written,
+                failed: failed
+                    .into_iter()
+                    .map(|(format, e)| format!("{}: {}", format, e))
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Results files from an earlier scan that writing `formats` to
+    /// `base_path` would replace
+    pub fn existing_files(&self, base_path: &Path, formats: &[String]) -> Vec<PathBuf> {
+        formats
+            .iter()
+            .flat_map(|format| {
+                if TemplateRenderer::is_template(format) {
+                    let renderer = TemplateRenderer::new(format, "");
+                    vec![base_path.with_extension(renderer.output_extension())]
+                } else if let Some(formatter) = self.get_formatter(format) {
+                    formatter.output_paths(&base_path.with_extension(format))
+                } else {
+                    Vec::new()
+                }
+            })
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    /// Write one format, returning its file; `None` for an unknown format
+    fn write_format(
+        &self,
+        results: &ScanResults,
+        base_path: &Path,
+        format: &str,
+    ) -> Result<Option<PathBuf>> {
+        let renderer;
+        let (formatter, file_path): (&dyn OutputFormatter, PathBuf) =
+            if TemplateRenderer::is_template(format) {
+                renderer = TemplateRenderer::from_file(Path::new(format))?;
+                let file_path = base_path.with_extension(renderer.output_extension());
+                (&renderer, file_path)
+            } else if let Some(formatter) = self.get_formatter(format) {
+                (formatter, base_path.with_extension(format))
             } else {
-                tracing::warn!("Unknown output format: {}", format);
+                return Ok(None);
+            };
+
+        if !self.overwrite {
+            let paths = formatter.output_paths(&file_path);
+            if let Some(existing) = paths.into_iter().find(|p| p.exists()) {
+                return Err(already_exists(&existing));
             }
         }
-        Ok(())
+
+        tracing::info!("Writing {} output to {}", format, file_path.display());
+        formatter.write_to_file(results, &file_path)?;
+        Ok(Some(file_path))
     }
 
     /// Stream results to console
-    pub fn stream_finding(&self, finding: &crate::types::Finding) {
+    pub fn stream_finding(&self, finding: &Finding) {
         use console::style;
 
         let severity_color = match finding.severity {
@@ -1209,5 +1508,248 @@ mod tests {
         let output = formatter.format(&results).unwrap();
         assert!(output.contains("<!DOCTYPE html>"));
         assert!(output.contains("CERT-X-GEN Security Scan Report"));
+
+        let mut results = create_test_results();
+        results
+            .findings
+            .push(Finding::new("example.com", "t", Severity::Low, "T", "D").with_confidence(65));
+        let output = formatter.format(&results).unwrap();
+        assert!(output
+            .contains(r#"<div class="confidence-bar"><span style="width: 65%"></span></div>"#));
+    }
+
+    #[test]
+    fn test_references_in_outputs() {
+        let mut results = create_test_results();
+        let mut finding = Finding::new(
+            "example.com",
+            "log4shell",
+            Severity::Critical,
+            "Log4Shell",
+            "JNDI lookup",
+        );
+        finding.merge_references(&[
+            "https://nvd.nist.gov/vuln/detail/CVE-2021-44228".to_string(),
+            "https://logging.apache.org/log4j/2.x/security.html".to_string(),
+        ]);
+        results.findings.push(finding);
+
+        let markdown = MarkdownFormatter::new().format(&results).unwrap();
+        assert!(markdown.contains("- <https://nvd.nist.gov/vuln/detail/CVE-2021-44228>"));
+
+        let sarif: serde_json::Value =
+            serde_json::from_str(&SarifFormatter::new().format(&results).unwrap()).unwrap();
+        let related = &sarif["runs"][0]["results"][0]["relatedLocations"];
+        assert_eq!(related.as_array().unwrap().len(), 2);
+        assert_eq!(
+            related[1]["physicalLocation"]["artifactLocation"]["uri"],
+            "https://logging.apache.org/log4j/2.x/security.html"
+        );
+
+        let csv = CsvFormatter::new().format(&results).unwrap();
+        assert!(csv.contains("CVE-2021-44228;https://logging.apache.org"));
+    }
+
+    #[test]
+    fn test_labels_in_outputs() {
+        let mut results = create_test_results();
+        let mut finding = Finding::new(
+            "example.com",
+            "git-exposed",
+            Severity::High,
+            "Git",
+            "Exposed .git",
+        );
+        finding
+            .target_labels
+            .insert("env".to_string(), "prod".to_string());
+        finding
+            .target_labels
+            .insert("owner".to_string(), "web".to_string());
+        results.findings.push(finding);
+
+        let csv = CsvFormatter::new().format(&results).unwrap();
+        assert!(csv.lines().next().unwrap().ends_with(",Labels"));
+        assert!(csv.contains("env=prod;owner=web"));
+
+        let markdown = MarkdownFormatter::new().format(&results).unwrap();
+        assert!(markdown.contains("- **Labels**: env=prod, owner=web"));
+
+        let sarif: serde_json::Value =
+            serde_json::from_str(&SarifFormatter::new().format(&results).unwrap()).unwrap();
+        assert_eq!(
+            sarif["runs"][0]["results"][0]["properties"]["labels"]["owner"],
+            "web"
+        );
+
+        let html = HtmlFormatter::new().format(&results).unwrap();
+        assert!(html.contains(r#"<span class="tag">env=prod</span>"#));
+    }
+
+    #[test]
+    fn test_screenshots_in_outputs() {
+        let png = b"\x89PNG\r\n\x1a\nfake".to_vec();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&png);
+        let mut results = create_test_results();
+        let mut finding = Finding::new(
+            "https://example.com/manager/html",
+            "tomcat-manager",
+            Severity::High,
+            "Tomcat Manager",
+            "Exposed manager",
+        );
+        finding.attach_screenshot(png.clone());
+        results.findings.push(finding.clone());
+        results.findings.push(Finding::new(
+            "10.0.0.1:6379",
+            "redis-unauth",
+            Severity::High,
+            "Redis",
+            "no auth",
+        ));
+
+        let html = HtmlFormatter::new().format(&results).unwrap();
+        assert!(html.contains(&format!(r#"src="data:image/png;base64,{}""#, encoded)));
+        assert_eq!(html.matches(r#"<img class="screenshot""#).count(), 1);
+
+        let sarif: serde_json::Value =
+            serde_json::from_str(&SarifFormatter::new().format(&results).unwrap()).unwrap();
+        let run = &sarif["runs"][0];
+        assert_eq!(run["artifacts"][0]["contents"]["binary"], encoded.as_str());
+        assert_eq!(
+            run["results"][0]["attachments"][0]["artifactLocation"]["index"],
+            0
+        );
+        assert!(run["results"][1].get("attachments").is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scan.json");
+        JsonFormatter::new(true)
+            .write_to_file(&results, &path)
+            .unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let relative = format!("screenshots/{}.png", finding.id);
+        assert_eq!(
+            json["findings"][0]["evidence"]["data"]["screenshot"],
+            relative.as_str()
+        );
+        assert!(json["findings"][1]["evidence"]["data"]
+            .get("screenshot")
+            .is_none());
+        assert_eq!(std::fs::read(dir.path().join(&relative)).unwrap(), png);
+
+        let path = dir.path().join("scan.md");
+        MarkdownFormatter::new()
+            .write_to_file(&results, &path)
+            .unwrap();
+        let markdown = std::fs::read_to_string(&path).unwrap();
+        assert!(markdown.contains(&format!(
+            "![Screenshot of https://example.com/manager/html]({})",
+            relative
+        )));
+        assert_eq!(markdown.matches("![Screenshot").count(), 1);
+    }
+    #[test]
+    fn test_write_results_creates_missing_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("reports/nightly/scan");
+        OutputManager::new()
+            .write_results(
+                &create_test_results(),
+                &base,
+                &["json".to_string(), "inventory".to_string()],
+            )
+            .unwrap();
+
+        let json = std::fs::read_to_string(base.with_extension("json")).unwrap();
+        assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok());
+        assert!(base.with_extension("inventory.txt").exists());
+        // Only the reports are left behind, no temporary files
+        let mut names: Vec<_> = std::fs::read_dir(dir.path().join("reports/nightly"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["scan.inventory.json", "scan.inventory.txt", "scan.json"]
+        );
+    }
+
+    #[test]
+    fn test_write_results_refuses_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("scan");
+        std::fs::write(base.with_extension("json"), "previous").unwrap();
+        let formats = ["json".to_string()];
+        let manager = OutputManager::new();
+
+        assert_eq!(
+            manager.existing_files(&base, &formats),
+            [base.with_extension("json")]
+        );
+        let err = manager
+            .write_results(&create_test_results(), &base, &formats)
+            .unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert_eq!(
+            std::fs::read_to_string(base.with_extension("json")).unwrap(),
+            "previous"
+        );
+
+        OutputManager::new()
+            .with_overwrite(true)
+            .write_results(&create_test_results(), &base, &formats)
+            .unwrap();
+        assert_ne!(
+            std::fs::read_to_string(base.with_extension("json")).unwrap(),
+            "previous"
+        );
+    }
+
+    #[test]
+    fn test_write_results_continues_after_failed_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("scan");
+        std::fs::write(base.with_extension("csv"), "previous").unwrap();
+        let formats = [
+            "csv".to_string(),
+            "json".to_string(),
+            dir.path().join("missing.md.j2").display().to_string(),
+        ];
+
+        let err = OutputManager::new()
+            .write_results(&create_test_results(), &base, &formats)
+            .unwrap_err();
+        let Error::OutputWrite { written, failed } = &err else {
+            panic!("unexpected error: {}", err);
+        };
+        assert_eq!(
+            written,
+            &[format!("json ({})", base.with_extension("json").display())]
+        );
+        assert_eq!(failed.len(), 2);
+        assert!(failed[0].starts_with("csv: "));
+        assert!(failed[1].contains("missing.md.j2"));
+        assert!(base.with_extension("json").exists());
+        assert!(err
+            .to_string()
+            .contains("Failed to write 2 output format(s)"));
+    }
+
+    #[test]
+    fn test_timestamped_path() {
+        let at = DateTime::parse_from_rfc3339("2024-03-01T14:25:01Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            timestamped_path(Path::new("results/scan"), at),
+            Path::new("results/scan-20240301-142501")
+        );
+        assert_eq!(
+            timestamped_path(Path::new("results.json"), at),
+            Path::new("results-20240301-142501.json")
+        );
     }
 }
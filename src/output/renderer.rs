@@ -0,0 +1,200 @@
+//! User-supplied output templates (`--output-template report.csv.j2`)
+//!
+//! Templates use MiniJinja (Jinja2) syntax and can produce any text format.
+//! Available variables: `findings`, `scan_id`, `statistics` and `metadata`
+//! (`tool`, `version`, `started_at`, `completed_at`, `duration_secs`,
+//! `errors`, `generated_at`).
+
+use super::OutputFormatter;
+use crate::error::{Error, Result};
+use crate::types::ScanResults;
+use minijinja::{context, Environment};
+use std::path::{Path, PathBuf};
+
+/// File extension that marks an output template
+pub const TEMPLATE_EXTENSION: &str = "j2";
+
+/// Extension of the rendered file when the template name has none (`wiki.j2`)
+const DEFAULT_OUTPUT_EXTENSION: &str = "txt";
+
+/// Renders scan results through a MiniJinja template
+#[derive(Debug, Clone)]
+pub struct TemplateRenderer {
+    path: PathBuf,
+    source: String,
+}
+
+impl TemplateRenderer {
+    /// Load a template file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path).map_err(|e| Error::TemplateRender {
+            template: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        Ok(Self::new(path, source))
+    }
+
+    /// Renderer for template source; `path` names it in errors and picks the output extension
+    pub fn new(path: impl Into<PathBuf>, source: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Whether `format` names an output template rather than a built-in format
+    pub fn is_template(format: &str) -> bool {
+        Path::new(format)
+            .extension()
+            .is_some_and(|ext| ext == TEMPLATE_EXTENSION)
+    }
+
+    /// Extension of the rendered file: `report.csv.j2` -> `csv`, `wiki.j2` -> `txt`
+    pub fn output_extension(&self) -> String {
+        self.path
+            .file_stem()
+            .map(Path::new)
+            .and_then(Path::extension)
+            .and_then(|ext| ext.to_str())
+            .unwrap_or(DEFAULT_OUTPUT_EXTENSION)
+            .to_string()
+    }
+
+    /// Render the results
+    pub fn render(&self, results: &ScanResults) -> Result<String> {
+        let mut env = Environment::new();
+        env.add_filter("csv_escape", csv_escape);
+
+        let duration_secs = results
+            .completed_at
+            .map(|end| (end - results.started_at).num_milliseconds() as f64 / 1000.0);
+        let ctx = context! {
+            findings => &results.findings,
+            scan_id => results.scan_id.to_string(),
+            statistics => &results.statistics,
+            metadata => context! {
+                tool => "cert-x-gen",
+                version => env!("CARGO_PKG_VERSION"),
+                started_at => results.started_at.to_rfc3339(),
+                completed_at => results.completed_at.map(|t| t.to_rfc3339()),
+                duration_secs => duration_secs,
+                errors => &results.errors,
+                generated_at => chrono::Utc::now().to_rfc3339(),
+            },
+        };
+
+        let name = self.path.to_string_lossy();
+        env.render_named_str(&name, &self.source, ctx)
+            .map_err(|e| Error::TemplateRender {
+                template: self.path.clone(),
+                reason: render_error_reason(&e),
+            })
+    }
+}
+
+impl OutputFormatter for TemplateRenderer {
+    fn name(&self) -> &str {
+        "template"
+    }
+
+    fn format(&self, results: &ScanResults) -> Result<String> {
+        self.render(results)
+    }
+}
+
+/// Quote a value for a CSV field when it contains a separator, quote or newline
+fn csv_escape(value: String) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Error message with its line, e.g. `undefined value (line 3)`
+fn render_error_reason(error: &minijinja::Error) -> String {
+    let mut reason = match error.detail() {
+        Some(detail) => format!("{}: {}", error.kind(), detail),
+        None => error.kind().to_string(),
+    };
+    if let Some(line) = error.line() {
+        reason.push_str(&format!(" (line {})", line));
+    }
+    reason
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Finding, Severity};
+    use uuid::Uuid;
+
+    fn results() -> ScanResults {
+        let mut results = ScanResults::new(Uuid::new_v4());
+        results.add_finding(Finding::new(
+            "10.0.0.1",
+            "redis-unauth",
+            Severity::High,
+            "Redis, no auth",
+            "Redis accepts commands without a password",
+        ));
+        results
+    }
+
+    #[test]
+    fn test_render_one_liner() {
+        let results = results();
+        let renderer = TemplateRenderer::new(
+            "summary.j2",
+            "{{ scan_id }}: {{ findings | length }} finding(s), first is {{ findings[0].severity }}",
+        );
+        assert_eq!(
+            renderer.render(&results).unwrap(),
+            format!("{}: 1 finding(s), first is high", results.scan_id)
+        );
+        assert_eq!(renderer.output_extension(), "txt");
+    }
+
+    #[test]
+    fn test_bundled_csv_template() {
+        let renderer = TemplateRenderer::new(
+            "findings.csv.j2",
+            include_str!("../../templates/output-templates/findings.csv.j2"),
+        );
+        let output = renderer.render(&results()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "severity,template,target,title");
+        assert_eq!(lines[1], "high,redis-unauth,10.0.0.1,\"Redis, no auth\"");
+        assert_eq!(renderer.output_extension(), "csv");
+    }
+
+    #[test]
+    fn test_bundled_templates_render() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("templates/output-templates");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if !TemplateRenderer::is_template(&path.to_string_lossy()) {
+                continue;
+            }
+            let output = TemplateRenderer::from_file(&path)
+                .unwrap()
+                .render(&results())
+                .unwrap_or_else(|e| panic!("{}", e));
+            assert!(output.contains("redis-unauth"), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn test_render_errors_name_the_template() {
+        let renderer = TemplateRenderer::new("broken.j2", "line one\n{% for f in findings %}");
+        match renderer.render(&results()) {
+            Err(Error::TemplateRender { template, reason }) => {
+                assert_eq!(template, PathBuf::from("broken.j2"));
+                assert!(reason.contains("line"), "{}", reason);
+            }
+            other => panic!("expected a render error, got {:?}", other),
+        }
+        assert!(TemplateRenderer::is_template("reports/wiki.j2"));
+        assert!(!TemplateRenderer::is_template("json"));
+    }
+}
@@ -0,0 +1,464 @@
+//! JIRA issue creation for scan findings
+//!
+//! Each finding is tracked by a `cxg-<fingerprint>` label. On scan completion
+//! the plugin searches for that label with JQL and updates the matching issue,
+//! or creates a new one, so repeated scans do not open duplicates.
+
+use super::Plugin;
+use crate::config::{Config, JiraConfig};
+use crate::error::{Error, Result};
+use crate::types::{Finding, ScanResults, Severity};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// Longest request/response excerpt put in an issue description
+const EVIDENCE_MAX_CHARS: usize = 8000;
+
+/// Issues created and updated by a sync
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JiraSyncSummary {
+    /// Keys of newly created issues
+    pub created: Vec<String>,
+    /// Keys of existing issues that were updated
+    pub updated: Vec<String>,
+}
+
+/// Creates or updates a JIRA issue per unique finding when a scan completes
+#[allow(missing_debug_implementations)]
+pub struct JiraPlugin {
+    config: Option<JiraConfig>,
+    client: Option<reqwest::Client>,
+    pending: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl JiraPlugin {
+    /// Create a JIRA plugin; settings are read from `plugins.jira` in [`Plugin::initialize`]
+    pub fn new() -> Self {
+        Self {
+            config: None,
+            client: None,
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Create or update issues for findings at or above the severity threshold
+    pub async fn sync_findings(&self, findings: &[Finding]) -> Result<JiraSyncSummary> {
+        let (Some(client), Some(config)) = (&self.client, &self.config) else {
+            return Err(Error::Plugin {
+                plugin: self.name().to_string(),
+                message: "Plugin not initialized".to_string(),
+            });
+        };
+        sync(client, config, findings).await
+    }
+
+    /// Wait for the sync started by [`Plugin::on_scan_complete`]
+    pub async fn flush(&self) {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        for handle in pending {
+            let _ = handle.await;
+        }
+    }
+}
+
+impl Default for JiraPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Plugin for JiraPlugin {
+    fn name(&self) -> &str {
+        "jira"
+    }
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    fn initialize(&mut self, config: &Config) -> Result<()> {
+        let jira = config.plugins.jira.clone().ok_or_else(|| Error::Plugin {
+            plugin: self.name().to_string(),
+            message: "Missing plugins.jira configuration".to_string(),
+        })?;
+        self.client = Some(
+            reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(30))
+                .build()
+                .map_err(|e| Error::Plugin {
+                    plugin: self.name().to_string(),
+                    message: format!("Failed to create HTTP client: {}", e),
+                })?,
+        );
+        tracing::info!("JIRA plugin initialized for project {}", jira.project);
+        self.config = Some(jira);
+        Ok(())
+    }
+
+    fn on_scan_complete(&self, results: &ScanResults) -> Result<()> {
+        let (Some(client), Some(config)) = (self.client.clone(), self.config.clone()) else {
+            return Ok(());
+        };
+        let findings = results.findings.clone();
+        let handle = tokio::spawn(async move {
+            match sync(&client, &config, &findings).await {
+                Ok(summary) => tracing::info!(
+                    "JIRA: {} issue(s) created, {} updated",
+                    summary.created.len(),
+                    summary.updated.len()
+                ),
+                Err(e) => tracing::error!("JIRA sync failed: {}", e),
+            }
+        });
+        self.pending.lock().unwrap().push(handle);
+        Ok(())
+    }
+}
+
+async fn sync(
+    client: &reqwest::Client,
+    config: &JiraConfig,
+    findings: &[Finding],
+) -> Result<JiraSyncSummary> {
+    let mut summary = JiraSyncSummary::default();
+    let mut seen = HashSet::new();
+
+    for finding in findings {
+        if finding.severity < config.min_severity || !seen.insert(finding.fingerprint()) {
+            continue;
+        }
+
+        let label = fingerprint_label(finding);
+        let fields = issue_fields(config, finding);
+        match find_issue(client, config, &label).await? {
+            Some(key) => {
+                let url = format!("{}/rest/api/2/issue/{}", base_url(config), key);
+                let request = client.put(&url).json(&json!({ "fields": fields }));
+                send(request, config).await?;
+                summary.updated.push(key);
+            }
+            None => {
+                let url = format!("{}/rest/api/2/issue", base_url(config));
+                let request = client.post(&url).json(&json!({ "fields": fields }));
+                let created = send(request, config).await?;
+                let key = created
+                    .get("key")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                summary.created.push(key.to_string());
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Key of the issue carrying `label`, if any
+async fn find_issue(
+    client: &reqwest::Client,
+    config: &JiraConfig,
+    label: &str,
+) -> Result<Option<String>> {
+    let jql = format!(
+        "project = \"{}\" AND labels = \"{}\"",
+        config.project, label
+    );
+    let request = client
+        .get(format!("{}/rest/api/2/search", base_url(config)))
+        .query(&[
+            ("jql", jql.as_str()),
+            ("fields", "key"),
+            ("maxResults", "1"),
+        ]);
+    let response = send(request, config).await?;
+
+    Ok(response
+        .get("issues")
+        .and_then(Value::as_array)
+        .and_then(|issues| issues.first())
+        .and_then(|issue| issue.get("key"))
+        .and_then(Value::as_str)
+        .map(str::to_string))
+}
+
+async fn send(request: reqwest::RequestBuilder, config: &JiraConfig) -> Result<Value> {
+    let response = request
+        .basic_auth(&config.username, Some(config.api_token.expose()))
+        .send()
+        .await?;
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(Error::Plugin {
+            plugin: "jira".to_string(),
+            message: format!("JIRA returned {}: {}", status, body.trim()),
+        });
+    }
+    // Updates answer 204 with an empty body
+    Ok(serde_json::from_str(&body).unwrap_or(Value::Null))
+}
+
+fn base_url(config: &JiraConfig) -> &str {
+    config.url.trim_end_matches('/')
+}
+
+/// Label identifying a finding across scans
+pub fn fingerprint_label(finding: &Finding) -> String {
+    format!("cxg-{}", &finding.fingerprint()[..16])
+}
+
+/// JIRA priority name for a severity
+pub fn priority_for(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "Highest",
+        Severity::High => "High",
+        Severity::Medium => "Medium",
+        Severity::Low => "Low",
+        Severity::Info => "Lowest",
+    }
+}
+
+/// Issue fields for creating or updating the issue of a finding
+pub fn issue_fields(config: &JiraConfig, finding: &Finding) -> Value {
+    // JIRA labels cannot contain spaces
+    let mut labels: Vec<String> = finding
+        .tags
+        .iter()
+        .map(|tag| tag.replace(char::is_whitespace, "-"))
+        .collect();
+    labels.push("cert-x-gen".to_string());
+    labels.push(fingerprint_label(finding));
+
+    let mut fields = Map::new();
+    fields.insert("project".into(), json!({ "key": config.project }));
+    fields.insert("issuetype".into(), json!({ "name": config.issue_type }));
+    fields.insert(
+        "summary".into(),
+        json!(format!(
+            "[CERT-X-GEN] {} on {}",
+            finding.template_id, finding.target
+        )),
+    );
+    fields.insert("description".into(), json!(wiki_description(finding)));
+    fields.insert("labels".into(), json!(labels));
+    fields.insert(
+        "priority".into(),
+        json!({ "name": priority_for(finding.severity) }),
+    );
+
+    let custom = &config.custom_fields;
+    if let (Some(field), Some(cvss)) = (&custom.cvss, finding.cvss_score) {
+        fields.insert(field.clone(), json!(cvss));
+    }
+    if let Some(field) = custom.cve.as_ref().filter(|_| !finding.cve_ids.is_empty()) {
+        fields.insert(field.clone(), json!(finding.cve_ids.join(", ")));
+    }
+    if let Some(field) = custom.cwe.as_ref().filter(|_| !finding.cwe_ids.is_empty()) {
+        fields.insert(field.clone(), json!(finding.cwe_ids.join(", ")));
+    }
+
+    Value::Object(fields)
+}
+
+/// Issue description in JIRA wiki markup
+fn wiki_description(finding: &Finding) -> String {
+    let mut out = format!(
+        "h2. {}\n\n*Severity:* {} | *Confidence:* {}% | *Template:* {}\n*Target:* {}\n",
+        finding.title, finding.severity, finding.confidence, finding.template_id, finding.target
+    );
+    if !finding.description.is_empty() {
+        out.push_str(&format!("\n{}\n", finding.description));
+    }
+    if let Some(cvss) = finding.cvss_score {
+        out.push_str(&format!("\n*CVSS:* {:.1}", cvss));
+    }
+    if !finding.cve_ids.is_empty() {
+        out.push_str(&format!("\n*CVE:* {}", finding.cve_ids.join(", ")));
+    }
+    if !finding.cwe_ids.is_empty() {
+        out.push_str(&format!("\n*CWE:* {}", finding.cwe_ids.join(", ")));
+    }
+
+    let evidence = &finding.evidence;
+    if !evidence.matched_patterns.is_empty() {
+        out.push_str("\n\nh3. Matched patterns\n");
+        for pattern in &evidence.matched_patterns {
+            out.push_str(&format!("* {{{{{}}}}}\n", pattern));
+        }
+    }
+    for (heading, body) in [
+        ("Request", &evidence.request),
+        ("Response", &evidence.response),
+    ] {
+        if let Some(body) = body {
+            out.push_str(&format!(
+                "\nh3. {}\n{{code}}\n{}\n{{code}}\n",
+                heading,
+                truncate(body)
+            ));
+        }
+    }
+    if !evidence.data.is_empty() {
+        out.push_str("\nh3. Evidence data\n||Key||Value||\n");
+        let mut data: Vec<_> = evidence.data.iter().collect();
+        data.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, value) in data {
+            out.push_str(&format!(
+                "|{}|{}|\n",
+                key,
+                value.to_string().replace('|', "\\|")
+            ));
+        }
+    }
+    if let Some(remediation) = &finding.remediation {
+        out.push_str(&format!("\nh3. Remediation\n{}\n", remediation));
+    }
+    if !finding.references.is_empty() {
+        out.push_str("\nh3. References\n");
+        for reference in &finding.references {
+            out.push_str(&format!("* {}\n", reference));
+        }
+    }
+
+    out.push_str(&format!(
+        "\n----\n_Reported by cert-x-gen at {} (fingerprint {})_",
+        finding.timestamp.to_rfc3339(),
+        finding.fingerprint()
+    ));
+    out
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= EVIDENCE_MAX_CHARS {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(EVIDENCE_MAX_CHARS).collect();
+    format!("{}\n... (truncated)", truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JiraCustomFields;
+    use wiremock::matchers::{body_partial_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config(server: &MockServer) -> JiraConfig {
+        JiraConfig {
+            url: server.uri(),
+            project: "SEC".to_string(),
+            username: "bot@example.com".to_string(),
+            api_token: "token".into(),
+            issue_type: "Bug".to_string(),
+            min_severity: Severity::High,
+            custom_fields: JiraCustomFields {
+                cvss: Some("customfield_10050".to_string()),
+                cve: Some("customfield_10051".to_string()),
+                cwe: None,
+            },
+        }
+    }
+
+    fn plugin(server: &MockServer) -> JiraPlugin {
+        let mut config = Config::default();
+        config.plugins.jira = Some(self::config(server));
+        let mut plugin = JiraPlugin::new();
+        plugin.initialize(&config).unwrap();
+        plugin
+    }
+
+    fn finding() -> Finding {
+        let mut finding = Finding::new(
+            "http://10.0.0.5:8080",
+            "log4shell",
+            Severity::Critical,
+            "Log4Shell RCE",
+            "JNDI lookup in User-Agent",
+        )
+        .with_cvss_score(10.0)
+        .add_cve("CVE-2021-44228");
+        finding.tags = vec!["rce".to_string(), "java apps".to_string()];
+        finding.evidence.response = Some("HTTP/1.1 200 OK".to_string());
+        finding
+    }
+
+    #[tokio::test]
+    async fn test_creates_issue_for_new_finding() {
+        let server = MockServer::start().await;
+        let finding = finding();
+        Mock::given(method("GET"))
+            .and(path("/rest/api/2/search"))
+            .and(query_param(
+                "jql",
+                format!(
+                    "project = \"SEC\" AND labels = \"{}\"",
+                    fingerprint_label(&finding)
+                ),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "issues": [] })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/rest/api/2/issue"))
+            .and(body_partial_json(json!({
+                "fields": {
+                    "summary": "[CERT-X-GEN] log4shell on http://10.0.0.5:8080",
+                    "priority": { "name": "Highest" },
+                    "customfield_10050": 10.0,
+                    "customfield_10051": "CVE-2021-44228",
+                }
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({ "key": "SEC-1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let low = Finding::new("host", "banner", Severity::Low, "Banner", "");
+        let summary = plugin(&server)
+            .sync_findings(&[finding.clone(), finding.clone(), low])
+            .await
+            .unwrap();
+        assert_eq!(summary.created, vec!["SEC-1".to_string()]);
+
+        let requests = server.received_requests().await.unwrap();
+        let create = requests
+            .iter()
+            .find(|r| r.method.to_string() == "POST")
+            .unwrap();
+        let fields = &create.body_json::<Value>().unwrap()["fields"];
+        let labels = fields["labels"].as_array().unwrap();
+        assert!(labels.contains(&json!("java-apps")));
+        assert!(labels.contains(&json!(fingerprint_label(&finding))));
+        let description = fields["description"].as_str().unwrap();
+        assert!(description.contains("{code}\nHTTP/1.1 200 OK\n{code}"));
+    }
+
+    #[tokio::test]
+    async fn test_updates_existing_issue() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/rest/api/2/search"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({ "issues": [{ "key": "SEC-7" }] })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/rest/api/2/issue/SEC-7"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let summary = plugin(&server).sync_findings(&[finding()]).await.unwrap();
+        assert_eq!(summary.updated, vec!["SEC-7".to_string()]);
+        assert!(summary.created.is_empty());
+    }
+}
@@ -5,6 +5,12 @@ use crate::types::{Finding, ScanResults};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+mod jira;
+mod notification;
+
+pub use jira::{JiraPlugin, JiraSyncSummary};
+pub use notification::NotificationPlugin;
+
 /// Plugin trait that all plugins must implement
 pub trait Plugin: Send + Sync {
     /// Get plugin name
@@ -0,0 +1,385 @@
+//! Slack and Microsoft Teams notifications for high-severity findings
+
+use super::Plugin;
+use crate::config::{Config, NotificationConfig};
+use crate::error::{Error, Result};
+use crate::types::Finding;
+use governor::clock::DefaultClock;
+use governor::state::{InMemoryState, NotKeyed};
+use governor::{Quota, RateLimiter};
+use serde_json::{json, Value};
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// Longest evidence snippet included in a message
+const SNIPPET_MAX_CHARS: usize = 120;
+
+type NotificationRateLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock>;
+
+/// Posts findings at or above a severity threshold to Slack and Teams webhooks
+#[allow(missing_debug_implementations)]
+pub struct NotificationPlugin {
+    config: NotificationConfig,
+    client: Option<reqwest::Client>,
+    limiter: Option<NotificationRateLimiter>,
+    pending: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl NotificationPlugin {
+    /// Create a notification plugin; webhooks are read in [`Plugin::initialize`]
+    pub fn new() -> Self {
+        Self {
+            config: NotificationConfig::default(),
+            client: None,
+            limiter: None,
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Whether a finding meets the severity threshold
+    pub fn should_notify(&self, finding: &Finding) -> bool {
+        finding.severity >= self.config.min_severity
+    }
+
+    /// Send a finding to every configured webhook
+    pub async fn notify(&self, finding: &Finding) -> Result<()> {
+        send(self.client()?, &self.config, finding).await
+    }
+
+    /// Wait for notifications sent in the background by [`Plugin::on_finding`]
+    pub async fn flush(&self) {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        for handle in pending {
+            let _ = handle.await;
+        }
+    }
+
+    /// Slack incoming-webhook payload
+    pub fn slack_message(&self, finding: &Finding) -> Value {
+        slack_message(&self.config, finding)
+    }
+
+    /// Microsoft Teams payload carrying an Adaptive Card
+    pub fn teams_message(&self, finding: &Finding) -> Value {
+        teams_message(&self.config, finding)
+    }
+
+    fn client(&self) -> Result<&reqwest::Client> {
+        self.client.as_ref().ok_or_else(|| Error::Plugin {
+            plugin: self.name().to_string(),
+            message: "Plugin not initialized".to_string(),
+        })
+    }
+
+    /// Take a slot from the rate limiter, or `false` if the budget is used up
+    fn acquire(&self) -> bool {
+        match &self.limiter {
+            Some(limiter) => limiter.check().is_ok(),
+            None => true,
+        }
+    }
+}
+
+impl Default for NotificationPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Plugin for NotificationPlugin {
+    fn name(&self) -> &str {
+        "notification"
+    }
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    fn initialize(&mut self, config: &Config) -> Result<()> {
+        self.config = config.notifications.clone();
+        self.client = Some(
+            reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .map_err(|e| Error::Plugin {
+                    plugin: self.name().to_string(),
+                    message: format!("Failed to create HTTP client: {}", e),
+                })?,
+        );
+        self.limiter = NonZeroU32::new(self.config.max_per_minute)
+            .map(|n| RateLimiter::direct(Quota::per_minute(n)));
+        Ok(())
+    }
+
+    fn on_finding(&self, finding: &Finding) -> Result<()> {
+        if !self.config.is_enabled() || !self.should_notify(finding) {
+            return Ok(());
+        }
+        if !self.acquire() {
+            tracing::warn!(
+                "Notification rate limit reached, not sending '{}' on {}",
+                finding.title,
+                finding.target
+            );
+            return Ok(());
+        }
+
+        let Some(client) = self.client.clone() else {
+            return Ok(());
+        };
+        let config = self.config.clone();
+        let finding = finding.clone();
+        let handle = tokio::spawn(async move {
+            if let Err(e) = send(&client, &config, &finding).await {
+                tracing::error!("Failed to send notification: {}", e);
+            }
+        });
+        self.pending.lock().unwrap().push(handle);
+        Ok(())
+    }
+}
+
+fn slack_message(config: &NotificationConfig, finding: &Finding) -> Value {
+    let mut fields = vec![
+        json!({ "title": "Target", "value": finding.target, "short": true }),
+        json!({ "title": "Template", "value": finding.template_id, "short": true }),
+        json!({ "title": "Severity", "value": finding.severity.to_string(), "short": true }),
+    ];
+    if let Some(snippet) = evidence_snippet(finding) {
+        fields.push(
+            json!({ "title": "Evidence", "value": format!("`{}`", snippet), "short": false }),
+        );
+    }
+
+    let mut attachment = json!({
+        "color": severity_color(finding),
+        "title": finding.title,
+        "fields": fields,
+        "footer": "cert-x-gen",
+        "ts": finding.timestamp.timestamp(),
+    });
+    if let Some(url) = &config.report_url {
+        attachment["title_link"] = json!(url);
+    }
+
+    json!({
+        "text": format!(
+            "*{}* finding on {}: {}",
+            finding.severity.to_string().to_uppercase(),
+            finding.target,
+            finding.title
+        ),
+        "attachments": [attachment],
+    })
+}
+
+fn teams_message(config: &NotificationConfig, finding: &Finding) -> Value {
+    let mut facts = vec![
+        json!({ "title": "Target", "value": finding.target }),
+        json!({ "title": "Template", "value": finding.template_id }),
+        json!({ "title": "Severity", "value": finding.severity.to_string() }),
+    ];
+    if let Some(snippet) = evidence_snippet(finding) {
+        facts.push(json!({ "title": "Evidence", "value": snippet }));
+    }
+
+    let mut card = json!({
+        "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+        "type": "AdaptiveCard",
+        "version": "1.4",
+        "body": [
+            {
+                "type": "TextBlock",
+                "text": finding.title,
+                "weight": "Bolder",
+                "size": "Medium",
+                "color": if finding.severity >= crate::types::Severity::High { "Attention" } else { "Warning" },
+                "wrap": true,
+            },
+            { "type": "FactSet", "facts": facts },
+        ],
+    });
+    if let Some(url) = &config.report_url {
+        card["actions"] = json!([
+            { "type": "Action.OpenUrl", "title": "View full report", "url": url }
+        ]);
+    }
+
+    json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "contentUrl": null,
+            "content": card,
+        }],
+    })
+}
+
+async fn send(
+    client: &reqwest::Client,
+    config: &NotificationConfig,
+    finding: &Finding,
+) -> Result<()> {
+    if let Some(slack) = &config.slack {
+        post(
+            client,
+            slack.webhook_url.expose(),
+            &slack_message(config, finding),
+        )
+        .await?;
+    }
+    if let Some(teams) = &config.teams {
+        post(
+            client,
+            teams.webhook_url.expose(),
+            &teams_message(config, finding),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+async fn post(client: &reqwest::Client, url: &str, payload: &Value) -> Result<()> {
+    let response = client.post(url).json(payload).send().await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::Network(format!(
+        "Webhook returned {}: {}",
+        status,
+        body.trim()
+    )))
+}
+
+/// One-line evidence: the first matched pattern, else the first response line
+fn evidence_snippet(finding: &Finding) -> Option<String> {
+    let evidence = &finding.evidence;
+    let line = evidence.matched_patterns.first().cloned().or_else(|| {
+        evidence
+            .response
+            .as_deref()
+            .and_then(|r| r.lines().find(|l| !l.trim().is_empty()))
+            .map(|l| l.trim().to_string())
+    })?;
+
+    if line.chars().count() > SNIPPET_MAX_CHARS {
+        let truncated: String = line.chars().take(SNIPPET_MAX_CHARS).collect();
+        Some(format!("{}…", truncated))
+    } else {
+        Some(line)
+    }
+}
+
+fn severity_color(finding: &Finding) -> &'static str {
+    use crate::types::Severity;
+    match finding.severity {
+        Severity::Critical => "#8b0000",
+        Severity::High => "#d00000",
+        Severity::Medium => "#f0a000",
+        Severity::Low => "#2b7bb9",
+        Severity::Info => "#808080",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WebhookConfig;
+    use crate::types::Severity;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn plugin(server: &MockServer, max_per_minute: u32) -> NotificationPlugin {
+        let config = Config {
+            notifications: NotificationConfig {
+                slack: Some(WebhookConfig {
+                    webhook_url: format!("{}/slack", server.uri()).into(),
+                }),
+                teams: Some(WebhookConfig {
+                    webhook_url: format!("{}/teams", server.uri()).into(),
+                }),
+                min_severity: Severity::High,
+                report_url: Some("https://reports.example.com/scan-1.html".to_string()),
+                max_per_minute,
+            },
+            ..Config::default()
+        };
+        let mut plugin = NotificationPlugin::new();
+        plugin.initialize(&config).unwrap();
+        plugin
+    }
+
+    fn finding(severity: Severity) -> Finding {
+        let mut finding = Finding::new(
+            "http://10.0.0.5:8080",
+            "spring-actuator-env",
+            severity,
+            "Spring Actuator env exposed",
+            "",
+        );
+        finding.evidence.matched_patterns = vec!["\"activeProfiles\"".to_string()];
+        finding
+    }
+
+    async fn mock_webhooks() -> MockServer {
+        let server = MockServer::start().await;
+        for route in ["/slack", "/teams"] {
+            Mock::given(method("POST"))
+                .and(path(route))
+                .respond_with(ResponseTemplate::new(200))
+                .mount(&server)
+                .await;
+        }
+        server
+    }
+
+    #[tokio::test]
+    async fn test_posts_slack_and_teams_payloads() {
+        let server = mock_webhooks().await;
+        let plugin = plugin(&server, 10);
+        plugin.notify(&finding(Severity::Critical)).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+
+        let slack: Value = requests[0].body_json().unwrap();
+        assert!(slack["text"].as_str().unwrap().contains("CRITICAL"));
+        let attachment = &slack["attachments"][0];
+        assert_eq!(
+            attachment["title_link"],
+            "https://reports.example.com/scan-1.html"
+        );
+        assert_eq!(attachment["fields"][0]["value"], "http://10.0.0.5:8080");
+        assert_eq!(attachment["fields"][3]["value"], "`\"activeProfiles\"`");
+
+        let teams: Value = requests[1].body_json().unwrap();
+        let card = &teams["attachments"][0];
+        assert_eq!(
+            card["contentType"],
+            "application/vnd.microsoft.card.adaptive"
+        );
+        assert_eq!(card["content"]["type"], "AdaptiveCard");
+        assert_eq!(
+            card["content"]["actions"][0]["url"],
+            "https://reports.example.com/scan-1.html"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_threshold_and_rate_limit() {
+        let server = mock_webhooks().await;
+        let plugin = plugin(&server, 2);
+
+        plugin.on_finding(&finding(Severity::Medium)).unwrap();
+        for _ in 0..5 {
+            plugin.on_finding(&finding(Severity::Critical)).unwrap();
+        }
+        plugin.flush().await;
+
+        // Two findings allowed through, each posted to Slack and Teams
+        assert_eq!(server.received_requests().await.unwrap().len(), 4);
+    }
+}
@@ -7,6 +7,8 @@ use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::types::{Finding, Severity};
+
 /// Progress tracker for scan operations
 #[derive(Debug)]
 pub struct ProgressTracker {
@@ -24,6 +26,8 @@ pub struct ProgressTracker {
     completed_units: AtomicU64,
     /// Findings count
     findings_count: AtomicU64,
+    /// Findings per severity, indexed by [`Severity::score`]
+    severity_counts: [AtomicU64; 5],
 }
 
 impl ProgressTracker {
@@ -37,6 +41,7 @@ impl ProgressTracker {
             total_units: AtomicU64::new(0),
             completed_units: AtomicU64::new(0),
             findings_count: AtomicU64::new(0),
+            severity_counts: Default::default(),
         }
     }
 
@@ -61,7 +66,7 @@ impl ProgressTracker {
 
         // Main progress bar style
         let main_style = ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) {msg}")
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) {prefix}{msg}")
             .unwrap()
             .progress_chars("█▓▒░  ");
 
@@ -87,6 +92,26 @@ impl ProgressTracker {
         *self.current_target.write() = Some(status_bar);
     }
 
+    /// Create a standalone bar for the template pre-compilation phase
+    pub fn compile_bar(&self, total: usize) -> Option<ProgressBar> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let style = ProgressStyle::default_bar()
+            .template(
+                "{spinner:.yellow} [{elapsed_precise}] [{bar:40.yellow/blue}] {pos}/{len} {msg}",
+            )
+            .unwrap()
+            .progress_chars("█▓▒░  ");
+
+        let bar = self.multi.add(ProgressBar::new(total as u64));
+        bar.set_style(style);
+        bar.set_message("Compiling templates");
+        bar.enable_steady_tick(Duration::from_millis(100));
+        Some(bar)
+    }
+
     /// Set current target being processed
     pub fn set_target(&self, target: &str) {
         if !self.is_enabled() {
@@ -107,6 +132,16 @@ impl ProgressTracker {
         }
     }
 
+    /// Show the number of templates adaptive concurrency lets run at once
+    pub fn set_concurrency(&self, concurrency: usize) {
+        if !self.is_enabled() {
+            return;
+        }
+        if let Some(bar) = self.main_bar.read().as_ref() {
+            bar.set_prefix(format!("[concurrency {}] ", concurrency));
+        }
+    }
+
     /// Increment completed work units
     pub fn inc(&self, delta: u64) {
         if !self.is_enabled() {
@@ -130,6 +165,35 @@ impl ProgressTracker {
         self.update_message(new_count);
     }
 
+    /// Count `findings` by severity; call before [`Self::template_done`],
+    /// which shows the counts
+    pub fn add_severities(&self, findings: &[Finding]) {
+        if !self.is_enabled() {
+            return;
+        }
+        for finding in findings {
+            self.severity_counts[finding.severity.score() as usize].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Findings so far by severity, highest first, e.g. `2 critical, 5 info`
+    fn severity_summary(&self) -> String {
+        [
+            Severity::Critical,
+            Severity::High,
+            Severity::Medium,
+            Severity::Low,
+            Severity::Info,
+        ]
+        .iter()
+        .filter_map(|severity| {
+            let count = self.severity_counts[severity.score() as usize].load(Ordering::Relaxed);
+            (count > 0).then(|| format!("{} {}", count, severity))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+
     /// Update the main bar message with findings count
     fn update_message(&self, findings: u64) {
         let completed = self.completed_units.load(Ordering::Relaxed);
@@ -137,10 +201,18 @@ impl ProgressTracker {
 
         if findings > 0 {
             if let Some(bar) = self.main_bar.read().as_ref() {
-                bar.set_message(format!(
-                    "🔍 Found {} findings ({}/{})",
-                    findings, completed, total
-                ));
+                let severities = self.severity_summary();
+                if severities.is_empty() {
+                    bar.set_message(format!(
+                        "🔍 Found {} findings ({}/{})",
+                        findings, completed, total
+                    ));
+                } else {
+                    bar.set_message(format!(
+                        "🔍 Found {} findings: {} ({}/{})",
+                        findings, severities, completed, total
+                    ));
+                }
             }
         }
     }
@@ -173,6 +245,24 @@ impl ProgressTracker {
         }
     }
 
+    /// Finish, showing how many findings made it into the report
+    pub fn finish_with_reported(&self, reported: usize) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let findings = self.findings_count.load(Ordering::Relaxed);
+        if let Some(bar) = self.main_bar.read().as_ref() {
+            bar.finish_with_message(format!(
+                "✓ Scan complete - {} findings, {} reported",
+                findings, reported
+            ));
+        }
+        if let Some(bar) = self.current_target.read().as_ref() {
+            bar.finish_and_clear();
+        }
+    }
+
     /// Finish with an error
     pub fn finish_with_error(&self, msg: &str) {
         if !self.is_enabled() {
@@ -218,3 +308,26 @@ pub fn init_progress(enabled: bool) -> &'static ProgressTracker {
 pub fn get_progress() -> Option<&'static ProgressTracker> {
     PROGRESS.get()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_severity_summary() {
+        let finding = |severity| Finding::new("a.test", "t", severity, "t", "t");
+        let progress = ProgressTracker::new(true);
+        assert_eq!(progress.severity_summary(), "");
+
+        progress.add_severities(&[
+            finding(Severity::Info),
+            finding(Severity::Critical),
+            finding(Severity::Info),
+        ]);
+        assert_eq!(progress.severity_summary(), "1 critical, 2 info");
+
+        let disabled = ProgressTracker::disabled();
+        disabled.add_severities(&[finding(Severity::High)]);
+        assert_eq!(disabled.severity_summary(), "");
+    }
+}
@@ -1,10 +1,27 @@
 //! Docker-based true sandbox environment
 
 use crate::error::{Error, Result};
+use crate::template::PathResolver;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::io::{BufRead, BufReader, Read};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Output, Stdio};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Timeout for quick daemon checks (`docker --version`, `docker ps`)
+const STATUS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of trailing output lines kept for error messages
+const OUTPUT_TAIL_LINES: usize = 20;
+
+/// Container path where the compilation cache volume is mounted
+pub const SANDBOX_CACHE_DIR: &str = "/workspace/.cache";
+
+/// Package sandbox root inside the container (`cxg sandbox init` run as root)
+pub const CONTAINER_SANDBOX_DIR: &str = "/root/.local/share/cert-x-gen/sandbox";
 
 /// Docker sandbox configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +52,22 @@ pub struct DockerConfig {
 
     /// Network mode (bridge, host, none)
     pub network_mode: String,
+
+    /// Timeout in seconds for docker commands (run, start, exec, ...)
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Timeout in seconds for image builds
+    #[serde(default = "default_build_timeout_secs")]
+    pub build_timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    300
+}
+
+fn default_build_timeout_secs() -> u64 {
+    3600
 }
 
 /// Resource limits for container
@@ -47,6 +80,23 @@ pub struct ResourceLimits {
     pub cpus: String,
 }
 
+impl DockerConfig {
+    /// Host directory backing this sandbox's compilation cache
+    pub fn host_cache_dir(&self) -> PathBuf {
+        PathResolver::cache_dir().join("sandbox").join(&self.name)
+    }
+
+    /// Mount the host compilation cache at [`SANDBOX_CACHE_DIR`]
+    ///
+    /// Compiled templates then survive container restarts and rebuilds.
+    pub fn mount_cache(&mut self) {
+        self.volumes.insert(
+            self.host_cache_dir().to_string_lossy().to_string(),
+            SANDBOX_CACHE_DIR.to_string(),
+        );
+    }
+}
+
 impl Default for DockerConfig {
     fn default() -> Self {
         Self {
@@ -71,6 +121,8 @@ impl Default for DockerConfig {
             volumes: HashMap::new(),
             environment: HashMap::new(),
             network_mode: "bridge".to_string(), // Use bridge for network access
+            timeout_secs: default_timeout_secs(),
+            build_timeout_secs: default_build_timeout_secs(),
         }
     }
 }
@@ -93,18 +145,33 @@ impl DockerSandbox {
 
     /// Load existing sandbox by name
     pub fn load(name: &str) -> Result<Self> {
+        let mut sandbox = Self::load_with_config(DockerConfig {
+            name: name.to_string(),
+            ..Default::default()
+        })?;
+
+        // Load container config
+        sandbox.load_container_config()?;
+
+        Ok(sandbox)
+    }
+
+    /// Load existing sandbox using its saved configuration
+    pub fn load_with_config(config: DockerConfig) -> Result<Self> {
+        let name = config.name.as_str();
+
         // Check if container exists
-        let output = Command::new("docker")
-            .args(&[
+        let output = run_docker(
+            &[
                 "ps",
                 "-a",
                 "--filter",
                 &format!("name={}", name),
                 "--format",
                 "{{.ID}}",
-            ])
-            .output()
-            .map_err(|e| Error::config(format!("Failed to check Docker container: {}", e)))?;
+            ],
+            STATUS_TIMEOUT,
+        )?;
 
         let container_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
 
@@ -112,43 +179,29 @@ impl DockerSandbox {
             return Err(Error::config(format!("Container '{}' not found", name)));
         }
 
-        let mut sandbox = Self {
-            config: DockerConfig {
-                name: name.to_string(),
-                ..Default::default()
-            },
+        Ok(Self {
             container_id: Some(container_id),
-        };
-
-        // Load container config
-        sandbox.load_container_config()?;
-
-        Ok(sandbox)
+            config,
+        })
     }
 
     /// Check if Docker is available
     pub fn docker_available() -> bool {
-        Command::new("docker")
-            .arg("--version")
-            .output()
+        run_docker(&["--version"], STATUS_TIMEOUT)
             .map(|out| out.status.success())
             .unwrap_or(false)
     }
 
     /// Check if Docker daemon is running
     pub fn docker_running() -> bool {
-        Command::new("docker")
-            .args(&["ps"])
-            .output()
+        run_docker(&["ps"], STATUS_TIMEOUT)
             .map(|out| out.status.success())
             .unwrap_or(false)
     }
 
     /// Get Docker version
     pub fn docker_version() -> Option<String> {
-        Command::new("docker")
-            .arg("--version")
-            .output()
+        run_docker(&["--version"], STATUS_TIMEOUT)
             .ok()
             .and_then(|out| {
                 if out.status.success() {
@@ -161,15 +214,28 @@ impl DockerSandbox {
 
     /// Check if image exists locally
     fn image_exists(&self) -> bool {
-        Command::new("docker")
-            .args(&["images", "-q", &self.config.image])
-            .output()
+        run_docker(&["images", "-q", &self.config.image], STATUS_TIMEOUT)
             .map(|out| !String::from_utf8_lossy(&out.stdout).trim().is_empty())
             .unwrap_or(false)
     }
 
+    /// Timeout for regular docker commands
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.config.timeout_secs)
+    }
+
     /// Build Docker image if needed
     pub async fn build_image(&self, dockerfile_path: Option<&Path>) -> Result<()> {
+        self.build_image_with_progress(dockerfile_path, |line| tracing::debug!("{}", line))
+            .await
+    }
+
+    /// Build Docker image, passing each line of build output to `on_line`
+    pub async fn build_image_with_progress<F: FnMut(&str)>(
+        &self,
+        dockerfile_path: Option<&Path>,
+        mut on_line: F,
+    ) -> Result<()> {
         tracing::info!("Building Docker image: {}", self.config.image);
 
         let dockerfile = dockerfile_path.map(|p| p.to_path_buf()).unwrap_or_else(|| {
@@ -213,17 +279,20 @@ impl DockerSandbox {
             }
         }
 
-        let output = Command::new("docker")
-            .args(&[
+        let build_timeout = Duration::from_secs(self.config.build_timeout_secs);
+        let dockerfile_arg = dockerfile.to_string_lossy();
+        let output = run_docker_streaming(
+            &[
                 "build",
                 "-t",
                 &self.config.image,
                 "-f",
-                dockerfile.to_str().unwrap(),
+                &dockerfile_arg,
                 ".",
-            ])
-            .output()
-            .map_err(|e| Error::command(format!("Failed to build Docker image: {}", e)))?;
+            ],
+            build_timeout,
+            &mut on_line,
+        );
 
         // Restore Docker config if we modified it
         if backup_made {
@@ -236,8 +305,9 @@ impl DockerSandbox {
             }
         }
 
+        let output = output?;
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr = output.stderr_tail.join("\n");
 
             // Check for credential helper error
             if stderr.contains("docker-credential-desktop")
@@ -247,19 +317,20 @@ impl DockerSandbox {
                 tracing::info!("Attempting workaround: using --no-cache flag");
 
                 // Try again with --no-cache and without pulling
-                let retry_output = Command::new("docker")
-                    .args(&[
+                let retry_output = run_docker_streaming(
+                    &[
                         "build",
                         "--no-cache",
                         "--pull=false",
                         "-t",
                         &self.config.image,
                         "-f",
-                        dockerfile.to_str().unwrap(),
+                        &dockerfile_arg,
                         ".",
-                    ])
-                    .output()
-                    .map_err(|e| Error::command(format!("Failed to build Docker image: {}", e)))?;
+                    ],
+                    build_timeout,
+                    &mut on_line,
+                )?;
 
                 if !retry_output.status.success() {
                     return Err(Error::command(format!(
@@ -268,7 +339,7 @@ impl DockerSandbox {
                     )));
                 }
             } else {
-                return Err(Error::command(format!("Docker build failed: {}", stderr)));
+                return Err(output.error("Docker build failed"));
             }
         }
 
@@ -424,16 +495,10 @@ impl DockerSandbox {
         args.push(self.config.image.clone());
 
         // Execute docker run
-        let output = Command::new("docker")
-            .args(&args)
-            .output()
-            .map_err(|e| Error::command(format!("Failed to create container: {}", e)))?;
-
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let output = run_docker(&args, self.timeout())?;
         if !output.status.success() {
-            return Err(Error::command(format!(
-                "Failed to create container: {}",
-                String::from_utf8_lossy(&output.stderr)
-            )));
+            return Err(command_error("Failed to create container", &output));
         }
 
         self.container_id = Some(String::from_utf8_lossy(&output.stdout).trim().to_string());
@@ -454,32 +519,28 @@ impl DockerSandbox {
 
         tracing::info!("Starting container: {}", container_id);
 
-        let output = Command::new("docker")
-            .args(&["start", container_id])
-            .output()
-            .map_err(|e| Error::command(format!("Failed to start container: {}", e)))?;
-
+        let output = run_docker(&["start", container_id], self.timeout())?;
         if !output.status.success() {
-            return Err(Error::command(format!(
-                "Failed to start container: {}",
-                String::from_utf8_lossy(&output.stderr)
-            )));
+            return Err(command_error("Failed to start container", &output));
         }
 
         // Wait a moment for container to fully start
-        std::thread::sleep(std::time::Duration::from_millis(500));
+        std::thread::sleep(Duration::from_millis(500));
 
         // Verify container is actually running
         if !self.is_running() {
             // Check container logs to see why it exited
-            let logs_output = Command::new("docker")
-                .args(&["logs", "--tail", "20", container_id])
-                .output();
-
-            let logs = logs_output
-                .ok()
-                .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
-                .unwrap_or_else(|| "Could not retrieve logs".to_string());
+            let logs = run_docker(
+                &[
+                    "logs",
+                    "--tail",
+                    &OUTPUT_TAIL_LINES.to_string(),
+                    container_id,
+                ],
+                STATUS_TIMEOUT,
+            )
+            .map(|o| combined_output(&o))
+            .unwrap_or_else(|_| "Could not retrieve logs".to_string());
 
             return Err(Error::command(format!(
                 "Container started but immediately exited.\n\nContainer logs:\n{}\n\nThis usually means the container's CMD is not keeping it alive.\nTry rebuilding: cxg sandbox delete {} --force && cxg sandbox create {}",
@@ -489,6 +550,15 @@ impl DockerSandbox {
             )));
         }
 
+        // A container can be "running" while the daemon is unable to exec into it
+        let probe = run_docker(&["exec", container_id, "true"], self.timeout())?;
+        if !probe.status.success() {
+            return Err(command_error(
+                "Container is running but not responding to exec",
+                &probe,
+            ));
+        }
+
         tracing::info!("Container started successfully");
         Ok(())
     }
@@ -502,16 +572,9 @@ impl DockerSandbox {
 
         tracing::info!("Stopping container: {}", container_id);
 
-        let output = Command::new("docker")
-            .args(&["stop", container_id])
-            .output()
-            .map_err(|e| Error::command(format!("Failed to stop container: {}", e)))?;
-
+        let output = run_docker(&["stop", container_id], self.timeout())?;
         if !output.status.success() {
-            return Err(Error::command(format!(
-                "Failed to stop container: {}",
-                String::from_utf8_lossy(&output.stderr)
-            )));
+            return Err(command_error("Failed to stop container", &output));
         }
 
         tracing::info!("Container stopped successfully");
@@ -521,11 +584,12 @@ impl DockerSandbox {
     /// Check if container is running
     pub fn is_running(&self) -> bool {
         if let Some(container_id) = &self.container_id {
-            Command::new("docker")
-                .args(&["ps", "-q", "--filter", &format!("id={}", container_id)])
-                .output()
-                .map(|out| !String::from_utf8_lossy(&out.stdout).trim().is_empty())
-                .unwrap_or(false)
+            run_docker(
+                &["ps", "-q", "--filter", &format!("id={}", container_id)],
+                STATUS_TIMEOUT,
+            )
+            .map(|out| !String::from_utf8_lossy(&out.stdout).trim().is_empty())
+            .unwrap_or(false)
         } else {
             false
         }
@@ -541,10 +605,7 @@ impl DockerSandbox {
         let mut args = vec!["exec", container_id];
         args.extend(cmd);
 
-        Command::new("docker")
-            .args(&args)
-            .output()
-            .map_err(|e| Error::command(format!("Failed to execute command in container: {}", e)))
+        run_docker(&args, self.timeout())
     }
 
     /// Execute cxg CLI command inside container
@@ -568,10 +629,10 @@ impl DockerSandbox {
             .as_ref()
             .ok_or_else(|| Error::config("Container ID not set"))?;
 
-        Command::new("docker")
-            .args(&["exec", container_id, "/bin/bash", "-c", command])
-            .output()
-            .map_err(|e| Error::command(format!("Failed to execute command in container: {}", e)))
+        run_docker(
+            &["exec", container_id, "/bin/bash", "-c", command],
+            self.timeout(),
+        )
     }
 
     /// Enter interactive shell
@@ -613,16 +674,9 @@ impl DockerSandbox {
             self.stop().await?;
         }
 
-        let output = Command::new("docker")
-            .args(&["rm", container_id])
-            .output()
-            .map_err(|e| Error::command(format!("Failed to delete container: {}", e)))?;
-
+        let output = run_docker(&["rm", container_id], self.timeout())?;
         if !output.status.success() {
-            return Err(Error::command(format!(
-                "Failed to delete container: {}",
-                String::from_utf8_lossy(&output.stderr)
-            )));
+            return Err(command_error("Failed to delete container", &output));
         }
 
         self.container_id = None;
@@ -653,6 +707,40 @@ impl DockerSandbox {
         }
     }
 
+    /// Probe the running container and each configured language runtime
+    pub async fn health_check(&self) -> SandboxHealth {
+        let responsive = match self.exec(&["true"]).await {
+            Ok(output) if output.status.success() => Ok(()),
+            Ok(output) => Err(combined_output(&output)),
+            Err(e) => Err(e.to_string()),
+        };
+
+        let mut runtimes = Vec::new();
+        if responsive.is_ok() {
+            for language in &self.config.languages {
+                let Some(command) = version_command(language) else {
+                    continue;
+                };
+                let version = match self.exec_command(command).await {
+                    Ok(output) if output.status.success() => {
+                        Ok(first_line(&combined_output(&output)))
+                    }
+                    Ok(output) => Err(first_line(&combined_output(&output))),
+                    Err(e) => Err(e.to_string()),
+                };
+                runtimes.push(RuntimeHealth {
+                    language: language.clone(),
+                    version,
+                });
+            }
+        }
+
+        SandboxHealth {
+            responsive,
+            runtimes,
+        }
+    }
+
     /// Load container configuration
     fn load_container_config(&mut self) -> Result<()> {
         // In a real implementation, we'd inspect the container
@@ -666,6 +754,127 @@ impl DockerSandbox {
     }
 }
 
+/// Routes template commands into a running sandbox container via `docker exec`
+#[derive(Debug, Clone)]
+pub struct ExecRoute {
+    container: String,
+    /// (host, container) mount pairs, longest host path first
+    mounts: Vec<(PathBuf, PathBuf)>,
+}
+
+impl ExecRoute {
+    /// Create a route for a sandbox from its configuration
+    pub fn new(config: &DockerConfig) -> Self {
+        Self::with_mounts(
+            &config.name,
+            config
+                .volumes
+                .iter()
+                .map(|(host, container)| (PathBuf::from(host), PathBuf::from(container))),
+        )
+    }
+
+    /// Create a route for a container with explicit (host, container) mounts
+    pub fn with_mounts(
+        container: &str,
+        mounts: impl IntoIterator<Item = (PathBuf, PathBuf)>,
+    ) -> Self {
+        let mut mounts: Vec<_> = mounts.into_iter().collect();
+        mounts.sort_by_key(|(host, _)| std::cmp::Reverse(host.components().count()));
+        Self {
+            container: container.to_string(),
+            mounts,
+        }
+    }
+
+    /// Container name commands are executed in
+    pub fn container(&self) -> &str {
+        &self.container
+    }
+
+    /// Translate a host path to its location inside the container
+    pub fn container_path(&self, host: &Path) -> Option<PathBuf> {
+        self.mounts.iter().find_map(|(host_dir, container_dir)| {
+            let rest = host.strip_prefix(host_dir).ok()?;
+            Some(if rest.as_os_str().is_empty() {
+                container_dir.clone()
+            } else {
+                container_dir.join(rest)
+            })
+        })
+    }
+
+    /// Host directory mounted at [`SANDBOX_CACHE_DIR`], if any
+    pub fn host_cache_dir(&self) -> Option<&Path> {
+        self.mounts
+            .iter()
+            .find(|(_, container)| container == Path::new(SANDBOX_CACHE_DIR))
+            .map(|(host, _)| host.as_path())
+    }
+
+    /// Build `docker exec` arguments for running `program` in the container
+    ///
+    /// Absolute host paths are translated to their mounted locations. A
+    /// program given by absolute host path that is not mounted is looked up
+    /// by name on the container's `PATH`. Other host paths that exist but are
+    /// not mounted are an error, since the container cannot see them.
+    pub fn exec_args(
+        &self,
+        program: &OsStr,
+        args: &[OsString],
+        env: &[(String, String)],
+        current_dir: Option<&Path>,
+    ) -> Result<Vec<OsString>> {
+        let mut exec: Vec<OsString> = vec!["exec".into()];
+        if let Some(dir) = current_dir {
+            exec.push("-w".into());
+            exec.push(self.require_container_path(dir)?.into());
+        }
+        for (key, value) in env {
+            exec.push("-e".into());
+            exec.push(format!("{}={}", key, value).into());
+        }
+        exec.push(self.container.clone().into());
+
+        let program_path = Path::new(program);
+        exec.push(match self.container_path(program_path) {
+            Some(path) => path.into(),
+            None if program_path.is_absolute() => program_path
+                .file_name()
+                .map(OsStr::to_os_string)
+                .unwrap_or_else(|| program.to_os_string()),
+            None => program.to_os_string(),
+        });
+
+        for arg in args {
+            let path = Path::new(arg);
+            if !path.is_absolute() {
+                exec.push(arg.clone());
+            } else if let Some(translated) = self.container_path(path) {
+                exec.push(translated.into());
+            } else if path.exists() {
+                return Err(self.unmounted(path));
+            } else {
+                exec.push(arg.clone());
+            }
+        }
+        Ok(exec)
+    }
+
+    fn require_container_path(&self, host: &Path) -> Result<PathBuf> {
+        self.container_path(host)
+            .ok_or_else(|| self.unmounted(host))
+    }
+
+    fn unmounted(&self, path: &Path) -> Error {
+        Error::Execution(format!(
+            "{} is not mounted in sandbox '{}'. Move it under a mounted directory or recreate the sandbox",
+            path.display(),
+            self.container
+        ))
+    }
+}
+
 /// Sandbox status information
 #[derive(Debug, Clone)]
 pub struct SandboxStatus {
@@ -681,19 +890,259 @@ pub struct SandboxStatus {
     pub languages: Vec<String>,
 }
 
+/// Result of a post-start health probe
+#[derive(Debug, Clone)]
+pub struct SandboxHealth {
+    /// Whether `docker exec true` succeeded (error output otherwise)
+    pub responsive: std::result::Result<(), String>,
+    /// Per-language runtime probes (empty if the container is unresponsive)
+    pub runtimes: Vec<RuntimeHealth>,
+}
+
+impl SandboxHealth {
+    /// Whether the container and all runtimes responded
+    pub fn is_healthy(&self) -> bool {
+        self.responsive.is_ok() && self.runtimes.iter().all(|r| r.version.is_ok())
+    }
+}
+
+/// Version probe result for one language runtime
+#[derive(Debug, Clone)]
+pub struct RuntimeHealth {
+    /// Sandbox language name
+    pub language: String,
+    /// First line of the version output, or the error output
+    pub version: std::result::Result<String, String>,
+}
+
+/// Shell command printing the version of a sandbox language runtime
+fn version_command(language: &str) -> Option<&'static str> {
+    Some(match language {
+        "python" => "python3 --version",
+        "ruby" => "ruby --version",
+        "node" => "node --version",
+        "go" => "go version",
+        "java" => "java -version 2>&1",
+        "perl" => "perl -e 'print \"perl $^V\\n\"'",
+        "php" => "php --version",
+        "rust" => "rustc --version",
+        _ => return None,
+    })
+}
+
+/// Output of a streamed docker command
+#[derive(Debug)]
+struct StreamedOutput {
+    status: ExitStatus,
+    /// Trailing lines of stdout and stderr, interleaved as received
+    tail: Vec<String>,
+    /// Trailing stderr lines, kept apart so noisy stdout cannot evict them
+    stderr_tail: Vec<String>,
+}
+
+impl StreamedOutput {
+    fn error(&self, action: &str) -> Error {
+        let lines = if self.stderr_tail.is_empty() {
+            &self.tail
+        } else {
+            &self.stderr_tail
+        };
+        Error::command(format!(
+            "{} ({}).\n\nLast docker output:\n{}",
+            action,
+            self.status,
+            lines.join("\n")
+        ))
+    }
+}
+
+/// Run a docker CLI command, failing if it does not finish within `timeout`
+fn run_docker(args: &[&str], timeout: Duration) -> Result<Output> {
+    let mut command = Command::new("docker");
+    command.args(args);
+    run_with_timeout(command, timeout)
+        .map_err(|e| Error::command(format!("docker {}: {}", args.first().unwrap_or(&""), e)))
+}
+
+/// Run a docker CLI command, passing each stdout/stderr line to `on_line`
+fn run_docker_streaming(
+    args: &[&str],
+    timeout: Duration,
+    on_line: &mut dyn FnMut(&str),
+) -> Result<StreamedOutput> {
+    let mut command = Command::new("docker");
+    command.args(args);
+    run_streaming(command, timeout, on_line)
+        .map_err(|e| Error::command(format!("docker {}: {}", args.first().unwrap_or(&""), e)))
+}
+
+fn run_with_timeout(mut command: Command, timeout: Duration) -> std::io::Result<Output> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain pipes on threads so a chatty process can't block on a full pipe
+    let read_all = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    };
+    let stdout = read_all(child.stdout.take().map(|p| Box::new(p) as _));
+    let stderr = read_all(child.stderr.take().map(|p| Box::new(p) as _));
+
+    let status = wait_with_deadline(&mut child, Instant::now() + timeout, timeout)?;
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn run_streaming(
+    mut command: Command,
+    timeout: Duration,
+    on_line: &mut dyn FnMut(&str),
+) -> std::io::Result<StreamedOutput> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let deadline = Instant::now() + timeout;
+
+    let (tx, rx) = mpsc::channel();
+    let forward =
+        |pipe: Option<Box<dyn Read + Send>>, is_stderr: bool, tx: mpsc::Sender<(bool, String)>| {
+            std::thread::spawn(move || {
+                if let Some(pipe) = pipe {
+                    for line in BufReader::new(pipe).lines().map_while(std::io::Result::ok) {
+                        if tx.send((is_stderr, line)).is_err() {
+                            break;
+                        }
+                    }
+                }
+            })
+        };
+    forward(
+        child.stdout.take().map(|p| Box::new(p) as _),
+        false,
+        tx.clone(),
+    );
+    forward(child.stderr.take().map(|p| Box::new(p) as _), true, tx);
+
+    let mut tail = VecDeque::with_capacity(OUTPUT_TAIL_LINES);
+    let mut stderr_tail = VecDeque::with_capacity(OUTPUT_TAIL_LINES);
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok((is_stderr, line)) => {
+                on_line(&line);
+                if is_stderr {
+                    push_tail(&mut stderr_tail, line.clone());
+                }
+                push_tail(&mut tail, line);
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(timed_out(timeout));
+            }
+        }
+    }
+
+    let status = wait_with_deadline(&mut child, deadline, timeout)?;
+    Ok(StreamedOutput {
+        status,
+        tail: tail.into(),
+        stderr_tail: stderr_tail.into(),
+    })
+}
+
+/// Append `line`, dropping the oldest line once the tail is full
+fn push_tail(tail: &mut VecDeque<String>, line: String) {
+    if tail.len() == OUTPUT_TAIL_LINES {
+        tail.pop_front();
+    }
+    tail.push_back(line);
+}
+
+fn wait_with_deadline(
+    child: &mut std::process::Child,
+    deadline: Instant,
+    timeout: Duration,
+) -> std::io::Result<ExitStatus> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(timed_out(timeout));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn timed_out(timeout: Duration) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::TimedOut,
+        format!(
+            "timed out after {}s (is the Docker daemon responsive?)",
+            timeout.as_secs()
+        ),
+    )
+}
+
+/// Error for a docker command that exited unsuccessfully, with its last output lines
+fn command_error(action: &str, output: &Output) -> Error {
+    Error::command(format!(
+        "{} ({}).\n\nLast docker output:\n{}",
+        action,
+        output.status,
+        combined_output(output)
+    ))
+}
+
+/// Last lines of stderr followed by stdout
+fn combined_output(output: &Output) -> String {
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stderr),
+        String::from_utf8_lossy(&output.stdout)
+    );
+    tail_lines(&text, OUTPUT_TAIL_LINES)
+}
+
+fn tail_lines(text: &str, count: usize) -> String {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(count)..].join("\n")
+}
+
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or_default().trim().to_string()
+}
+
 /// List all sandboxes
 pub fn list_sandboxes() -> Result<Vec<SandboxStatus>> {
-    let output = Command::new("docker")
-        .args(&[
+    let output = run_docker(
+        &[
             "ps",
             "-a",
             "--filter",
             "label=cert-x-gen-sandbox",
             "--format",
             "{{.Names}}",
-        ])
-        .output()
-        .map_err(|e| Error::command(format!("Failed to list containers: {}", e)))?;
+        ],
+        STATUS_TIMEOUT,
+    )?;
 
     let names: Vec<String> = String::from_utf8_lossy(&output.stdout)
         .lines()
@@ -720,3 +1169,110 @@ pub fn inside_sandbox() -> bool {
 pub fn current_sandbox_name() -> Option<String> {
     std::env::var("CERT_X_GEN_SANDBOX_NAME").ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_with_timeout_kills_hung_command() {
+        let mut command = Command::new("sleep");
+        command.arg("5");
+        let start = Instant::now();
+        let err = run_with_timeout(command, Duration::from_millis(200)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_run_streaming_forwards_lines_and_keeps_tail() {
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            "echo oops >&2; for i in $(seq 1 30); do echo line$i; done; exit 3",
+        ]);
+        let mut seen = 0;
+        let output = run_streaming(command, Duration::from_secs(10), &mut |_| seen += 1).unwrap();
+
+        assert_eq!(seen, 31);
+        assert!(!output.status.success());
+        assert_eq!(output.tail.len(), OUTPUT_TAIL_LINES);
+        assert_eq!(output.stderr_tail, vec!["oops".to_string()]);
+        assert!(output
+            .error("Docker build failed")
+            .to_string()
+            .contains("oops"));
+    }
+
+    #[test]
+    fn test_exec_route_translates_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates = dir.path().join("templates");
+        let cache = dir.path().join("cache");
+        std::fs::create_dir_all(&templates).unwrap();
+        std::fs::write(templates.join("check.py"), "").unwrap();
+
+        let route = ExecRoute::with_mounts(
+            "sbx",
+            [
+                (dir.path().to_path_buf(), PathBuf::from("/workspace/root")),
+                (templates.clone(), PathBuf::from("/workspace/templates")),
+                (cache.clone(), PathBuf::from(SANDBOX_CACHE_DIR)),
+            ],
+        );
+        assert_eq!(route.host_cache_dir(), Some(cache.as_path()));
+
+        let args = route
+            .exec_args(
+                OsStr::new("/usr/local/bin/python3"),
+                &[templates.join("check.py").into(), "-v".into()],
+                &[(
+                    "CERT_X_GEN_TARGET_HOST".to_string(),
+                    "example.com".to_string(),
+                )],
+                Some(&templates),
+            )
+            .unwrap();
+        assert_eq!(
+            args,
+            [
+                "exec",
+                "-w",
+                "/workspace/templates",
+                "-e",
+                "CERT_X_GEN_TARGET_HOST=example.com",
+                "sbx",
+                "python3",
+                "/workspace/templates/check.py",
+                "-v",
+            ]
+            .map(OsString::from)
+        );
+
+        // Not-yet-existing outputs under a mount are translated too
+        let binary = route
+            .exec_args(OsStr::new("gcc"), &[cache.join("c/x").into()], &[], None)
+            .unwrap();
+        assert_eq!(binary.last().unwrap(), "/workspace/.cache/c/x");
+
+        let outside = tempfile::tempdir().unwrap();
+        assert!(route
+            .exec_args(OsStr::new("python3"), &[outside.path().into()], &[], None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_tail_lines_and_old_config() {
+        assert_eq!(tail_lines("a\n\nb\nc\n", 2), "b\nc");
+        assert_eq!(version_command("go"), Some("go version"));
+        assert_eq!(version_command("cobol"), None);
+
+        // Configs saved before timeouts existed still load
+        let config: DockerConfig = serde_yaml::from_str(
+            "name: s\nimage: i\nlanguages: []\npersist: true\nauto_start: true\nresources: {memory: 1g, cpus: '1'}\nvolumes: {}\nenvironment: {}\nnetwork_mode: bridge\n",
+        )
+        .unwrap();
+        assert_eq!(config.timeout_secs, 300);
+        assert_eq!(config.build_timeout_secs, 3600);
+    }
+}
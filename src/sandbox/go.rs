@@ -1,11 +1,11 @@
 //! Go sandbox environment
 
 use crate::error::{Error, Result};
-use crate::sandbox::Sandbox;
+use crate::sandbox::{InitOutcome, Sandbox};
 use std::process::Command;
 
 /// Initialize Go environment
-pub async fn init_environment(_sandbox: &Sandbox) -> Result<()> {
+pub async fn init_environment(_sandbox: &Sandbox) -> Result<InitOutcome> {
     tracing::info!("Initializing Go sandbox environment");
 
     // Check if Go is available, attempt installation if missing
@@ -16,11 +16,11 @@ pub async fn init_environment(_sandbox: &Sandbox) -> Result<()> {
     if !go_available {
         tracing::warn!("Go not available and automatic installation failed");
         tracing::warn!("Skipping Go sandbox initialization");
-        return Ok(());
+        return Ok(InitOutcome::Skipped("Go not available".into()));
     }
 
     tracing::info!("Go sandbox environment initialized successfully");
-    Ok(())
+    Ok(InitOutcome::Ready)
 }
 
 /// Compile and execute Go template
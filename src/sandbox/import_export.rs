@@ -1,7 +1,7 @@
 //! Sandbox import/export functionality for "bring your own sandbox"
 
 use crate::error::{Error, Result};
-use crate::sandbox::{Sandbox, SandboxConfig};
+use crate::sandbox::{python, Sandbox, SandboxConfig};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
@@ -107,7 +107,7 @@ impl SandboxExport {
 
     /// Read installed Python packages
     fn read_python_packages(sandbox: &Sandbox) -> Result<Vec<String>> {
-        let pip_list = sandbox.root_dir().join("python/venv/bin/pip");
+        let pip_list = python::venv_executable(&python::venv_dir(sandbox.root_dir()), "pip");
         if !pip_list.exists() {
             return Ok(Vec::new());
         }
@@ -212,7 +212,7 @@ impl SandboxExport {
                 self.python_packages.len()
             );
             let packages: Vec<&str> = self.python_packages.iter().map(|s| s.as_str()).collect();
-            crate::sandbox::python::install_packages(sandbox, &packages).await?;
+            python::install_packages(sandbox, &packages).await?;
         }
 
         if !self.javascript_packages.is_empty() {
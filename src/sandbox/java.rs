@@ -1,11 +1,11 @@
 //! Java sandbox environment
 
 use crate::error::{Error, Result};
-use crate::sandbox::Sandbox;
+use crate::sandbox::{InitOutcome, Sandbox};
 use std::process::Command;
 
 /// Initialize Java environment
-pub async fn init_environment(_sandbox: &Sandbox) -> Result<()> {
+pub async fn init_environment(_sandbox: &Sandbox) -> Result<InitOutcome> {
     tracing::info!("Initializing Java sandbox environment");
 
     // Check if Java is available, attempt installation if missing
@@ -17,11 +17,11 @@ pub async fn init_environment(_sandbox: &Sandbox) -> Result<()> {
     if !java_available {
         tracing::warn!("Java not available and automatic installation failed");
         tracing::warn!("Skipping Java sandbox initialization");
-        return Ok(());
+        return Ok(InitOutcome::Skipped("Java not available".into()));
     }
 
     tracing::info!("Java sandbox environment initialized successfully");
-    Ok(())
+    Ok(InitOutcome::Ready)
 }
 
 /// Compile and execute Java template
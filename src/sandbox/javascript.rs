@@ -1,12 +1,12 @@
 //! JavaScript/Node.js sandbox environment
 
 use crate::error::{Error, Result};
-use crate::sandbox::Sandbox;
+use crate::sandbox::{InitOutcome, Sandbox};
 use std::fs;
 use std::process::Command;
 
 /// Initialize Node.js environment
-pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
+pub async fn init_environment(sandbox: &Sandbox) -> Result<InitOutcome> {
     tracing::info!("Initializing JavaScript/Node.js sandbox environment");
 
     // Check if Node.js is available, attempt installation if missing
@@ -17,7 +17,7 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
     if !node_available {
         tracing::warn!("Node.js not available and automatic installation failed");
         tracing::warn!("Skipping JavaScript sandbox initialization");
-        return Ok(());
+        return Ok(InitOutcome::Skipped("Node.js not available".into()));
     }
 
     // Verify npm is also available
@@ -53,7 +53,7 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
     install_packages(sandbox, &package_refs).await?;
 
     tracing::info!("JavaScript sandbox environment initialized successfully");
-    Ok(())
+    Ok(InitOutcome::Ready)
 }
 
 /// Install npm packages
@@ -22,6 +22,8 @@ pub mod python;
 pub mod ruby;
 pub mod runtime_installer;
 pub mod rust;
+pub mod syscall;
+pub mod update;
 
 /// Get the active Docker sandbox for transparent execution
 pub fn get_active_docker_sandbox() -> Option<docker::DockerSandbox> {
@@ -34,10 +36,55 @@ pub fn get_active_docker_sandbox() -> Option<docker::DockerSandbox> {
 
     // Load config and get default sandbox
     let cfg = SandboxConfigFile::load().ok()?;
-    let (name, _config) = cfg.get_default_sandbox()?;
+    let (_name, config) = cfg.get_default_sandbox()?;
 
     // Load the sandbox
-    docker::DockerSandbox::load(name).ok()
+    docker::DockerSandbox::load_with_config(config.clone()).ok()
+}
+
+/// Route for running template commands in the default Docker sandbox
+///
+/// Resolved once per process: `None` when no default sandbox is configured,
+/// it is not running, or we are already inside a sandbox.
+pub fn active_exec_route() -> Option<&'static docker::ExecRoute> {
+    static ROUTE: std::sync::OnceLock<Option<docker::ExecRoute>> = std::sync::OnceLock::new();
+
+    ROUTE
+        .get_or_init(|| {
+            let sandbox = get_active_docker_sandbox()?;
+            if !sandbox.is_running() {
+                return None;
+            }
+            tracing::info!(
+                "Routing template execution through sandbox '{}'",
+                sandbox.config().name
+            );
+            Some(docker::ExecRoute::new(sandbox.config()))
+        })
+        .as_ref()
+}
+
+/// Local package sandbox whose libraries script templates can import
+///
+/// Resolved once per process: `None` when `cxg sandbox init` has not been
+/// run, or when templates are routed through a Docker sandbox (the container
+/// carries its own environment).
+pub fn active_local_sandbox() -> Option<&'static Sandbox> {
+    static SANDBOX: std::sync::OnceLock<Option<Sandbox>> = std::sync::OnceLock::new();
+
+    SANDBOX
+        .get_or_init(|| {
+            if active_exec_route().is_some() {
+                return None;
+            }
+            let sandbox = Sandbox::open(&SandboxConfig::default_sandbox_dir())?;
+            tracing::debug!(
+                "Using sandbox libraries from {}",
+                sandbox.root_dir().display()
+            );
+            Some(sandbox)
+        })
+        .as_ref()
 }
 
 /// Sandbox configuration
@@ -134,6 +181,18 @@ impl Sandbox {
         }
     }
 
+    /// Open a sandbox previously initialized at `root_dir`
+    ///
+    /// Returns `None` when `root_dir` holds no readable `config.yaml`.
+    pub fn open(root_dir: &Path) -> Option<Self> {
+        let mut config = SandboxConfig::load(&root_dir.join("config.yaml")).ok()?;
+        config.root_dir = root_dir.to_path_buf();
+        Some(Self {
+            config,
+            initialized: true,
+        })
+    }
+
     /// Get sandbox root directory
     pub fn root_dir(&self) -> &Path {
         &self.config.root_dir
@@ -150,7 +209,10 @@ impl Sandbox {
     }
 
     /// Initialize the sandbox environment
-    pub async fn init(&mut self) -> Result<()> {
+    ///
+    /// Language environments are set up concurrently; a failing language is
+    /// recorded in the returned report rather than aborting the others.
+    pub async fn init(&mut self) -> Result<InitReport> {
         tracing::info!("Initializing sandbox at: {}", self.root_dir().display());
 
         // Create root directory
@@ -161,39 +223,100 @@ impl Sandbox {
         self.create_directory_structure()?;
 
         // Initialize language environments
-        if self.config.enable_python {
-            python::init_environment(self).await?;
-        }
-        if self.config.enable_javascript {
-            javascript::init_environment(self).await?;
-        }
-        if self.config.enable_ruby {
-            ruby::init_environment(self).await?;
-        }
-        if self.config.enable_perl {
-            perl::init_environment(self).await?;
-        }
-        if self.config.enable_php {
-            php::init_environment(self).await?;
-        }
-        if self.config.enable_rust {
-            rust::init_environment(self).await?;
-        }
-        if self.config.enable_go {
-            go::init_environment(self).await?;
-        }
-        if self.config.enable_java {
-            java::init_environment(self).await?;
-        }
+        let report = self.init_languages().await;
 
         // Save configuration
         let config_path = self.root_dir().join("config.yaml");
         self.config.save(&config_path)?;
 
         self.initialized = true;
-        tracing::info!("Sandbox initialized successfully");
+        tracing::info!(
+            "Sandbox initialized: {} succeeded, {} skipped, {} failed",
+            report.succeeded().len(),
+            report.skipped().len(),
+            report.failed().len()
+        );
+
+        Ok(report)
+    }
 
-        Ok(())
+    /// Languages enabled in the configuration, in initialization order
+    pub fn enabled_languages(&self) -> Vec<&'static str> {
+        let config = &self.config;
+        [
+            ("python", config.enable_python),
+            ("javascript", config.enable_javascript),
+            ("ruby", config.enable_ruby),
+            ("perl", config.enable_perl),
+            ("php", config.enable_php),
+            ("rust", config.enable_rust),
+            ("go", config.enable_go),
+            ("java", config.enable_java),
+        ]
+        .into_iter()
+        .filter_map(|(language, enabled)| enabled.then_some(language))
+        .collect()
+    }
+
+    /// Run each enabled language's `init_environment`, at most
+    /// [`INIT_PARALLELISM`] at a time
+    async fn init_languages(&self) -> InitReport {
+        use tracing::Instrument;
+
+        let languages = self.enabled_languages();
+        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(INIT_PARALLELISM));
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut task_languages = std::collections::HashMap::new();
+
+        for (index, language) in languages.iter().copied().enumerate() {
+            let permit = semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("init semaphore is never closed");
+            let sandbox = Sandbox::with_config(self.config.clone());
+            let runtime = tokio::runtime::Handle::current();
+            // The installers shell out with blocking std::process calls
+            let handle = tasks.spawn_blocking(move || {
+                let _permit = permit;
+                let span = tracing::info_span!("sandbox", language);
+                let result = runtime.block_on(init_language(&sandbox, language).instrument(span));
+                (index, result)
+            });
+            task_languages.insert(handle.id(), (index, language));
+        }
+
+        let mut results = Vec::with_capacity(languages.len());
+        while let Some(joined) = tasks.join_next_with_id().await {
+            let (index, result) = match joined {
+                Ok((_, (index, result))) => (index, result),
+                Err(e) => {
+                    let (index, language) = task_languages[&e.id()];
+                    (
+                        index,
+                        Err(Error::config(format!(
+                            "{} initialization task failed: {}",
+                            language, e
+                        ))),
+                    )
+                }
+            };
+            if let Err(e) = &result {
+                tracing::error!("[{}] initialization failed: {}", languages[index], e);
+            }
+            results.push((index, result));
+        }
+
+        results.sort_by_key(|(index, _)| *index);
+        InitReport {
+            languages: results
+                .into_iter()
+                .map(|(index, result)| LanguageInit {
+                    language: languages[index],
+                    result,
+                })
+                .collect(),
+        }
     }
 
     /// Create sandbox directory structure
@@ -253,11 +376,18 @@ impl Sandbox {
 
         // Python
         if self.config.enable_python {
-            let python_path = self.root_dir().join("python/venv");
+            let python_path = python::venv_dir(self.root_dir());
             env_vars.push((
                 "VIRTUAL_ENV".to_string(),
                 python_path.to_string_lossy().to_string(),
             ));
+            // Activate the venv: its executables come first on PATH
+            let inherited = std::env::var_os("PATH").unwrap_or_default();
+            let paths = std::iter::once(python::venv_bin_dir(&python_path))
+                .chain(std::env::split_paths(&inherited));
+            if let Ok(path) = std::env::join_paths(paths) {
+                env_vars.push(("PATH".to_string(), path.to_string_lossy().to_string()));
+            }
             env_vars.push((
                 "PYTHONUSERBASE".to_string(),
                 self.root_dir()
@@ -280,31 +410,49 @@ impl Sandbox {
 
         // Ruby
         if self.config.enable_ruby {
-            env_vars.push((
-                "GEM_HOME".to_string(),
-                self.root_dir()
-                    .join("ruby/gems")
-                    .to_string_lossy()
-                    .to_string(),
-            ));
+            let gems = self.root_dir().join("ruby/gems");
+            env_vars.push(("GEM_HOME".to_string(), gems.to_string_lossy().to_string()));
+            env_vars.push(("GEM_PATH".to_string(), gems.to_string_lossy().to_string()));
         }
 
         // Perl
         if self.config.enable_perl {
+            let local_lib = self.root_dir().join("perl/local");
             env_vars.push((
                 "PERL_LOCAL_LIB_ROOT".to_string(),
-                self.root_dir()
-                    .join("perl/local")
-                    .to_string_lossy()
-                    .to_string(),
+                local_lib.to_string_lossy().to_string(),
             ));
+            // `cpanm -L` installs modules under lib/perl5
+            let inherited = std::env::var_os("PERL5LIB").unwrap_or_default();
+            let paths = std::iter::once(local_lib.join("lib").join("perl5"))
+                .chain(std::env::split_paths(&inherited));
+            if let Ok(perl5lib) = std::env::join_paths(paths) {
+                env_vars.push((
+                    "PERL5LIB".to_string(),
+                    perl5lib.to_string_lossy().to_string(),
+                ));
+            }
         }
 
         // PHP
         if self.config.enable_php {
+            let php_dir = self.root_dir().join("php");
             env_vars.push((
                 "PHP_USER_INI".to_string(),
-                self.root_dir().join("php").to_string_lossy().to_string(),
+                php_dir.to_string_lossy().to_string(),
+            ));
+            env_vars.push((
+                "COMPOSER_VENDOR_DIR".to_string(),
+                php_dir.join("vendor").to_string_lossy().to_string(),
+            ));
+            // Templates `require getenv('CERT_X_GEN_COMPOSER_AUTOLOAD')`
+            env_vars.push((
+                "CERT_X_GEN_COMPOSER_AUTOLOAD".to_string(),
+                php_dir
+                    .join("vendor")
+                    .join("autoload.php")
+                    .to_string_lossy()
+                    .to_string(),
             ));
         }
 
@@ -360,7 +508,7 @@ impl Sandbox {
         SandboxStatus {
             initialized: self.is_initialized(),
             root_dir: self.root_dir().to_path_buf(),
-            python_ready: self.config.enable_python && self.root_dir().join("python/venv").exists(),
+            python_ready: self.config.enable_python && python::venv_dir(self.root_dir()).exists(),
             javascript_ready: self.config.enable_javascript
                 && self.root_dir().join("javascript/node_modules").exists(),
             ruby_ready: self.config.enable_ruby && self.root_dir().join("ruby/gems").exists(),
@@ -379,6 +527,114 @@ impl Default for Sandbox {
     }
 }
 
+/// Shell opened by `cxg sandbox shell`
+///
+/// `SHELL` wins when set, which includes Git Bash and MSYS on Windows.
+/// Otherwise Unix falls back to `/bin/bash`, and Windows to PowerShell, or to
+/// `COMSPEC` (normally `cmd.exe`) when PowerShell is not installed.
+pub fn interactive_shell() -> PathBuf {
+    interactive_shell_from(|key| std::env::var_os(key))
+}
+
+fn interactive_shell_from(env: impl Fn(&str) -> Option<std::ffi::OsString>) -> PathBuf {
+    if let Some(shell) = env("SHELL").filter(|s| !s.is_empty()) {
+        return PathBuf::from(shell);
+    }
+
+    if cfg!(windows) {
+        let powershell = env("SystemRoot").map(|root| {
+            PathBuf::from(root)
+                .join("System32")
+                .join("WindowsPowerShell")
+                .join("v1.0")
+                .join("powershell.exe")
+        });
+        match powershell {
+            Some(path) if path.exists() => path,
+            _ => env("COMSPEC")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("cmd.exe")),
+        }
+    } else {
+        PathBuf::from("/bin/bash")
+    }
+}
+
+/// Maximum number of language environments initialized at once
+pub const INIT_PARALLELISM: usize = 3;
+
+/// Outcome of a language's `init_environment` that did not error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InitOutcome {
+    /// Environment set up
+    Ready,
+    /// Environment not set up, with the reason (e.g. runtime missing)
+    Skipped(String),
+}
+
+/// Initialization result for a single language
+#[derive(Debug)]
+pub struct LanguageInit {
+    /// Language name
+    pub language: &'static str,
+    /// Outcome, or the error that stopped this language
+    pub result: Result<InitOutcome>,
+}
+
+/// Per-language results of [`Sandbox::init`]
+#[derive(Debug, Default)]
+pub struct InitReport {
+    /// Results in initialization order
+    pub languages: Vec<LanguageInit>,
+}
+
+impl InitReport {
+    /// Languages whose environment is ready
+    pub fn succeeded(&self) -> Vec<&'static str> {
+        self.languages
+            .iter()
+            .filter(|l| matches!(l.result, Ok(InitOutcome::Ready)))
+            .map(|l| l.language)
+            .collect()
+    }
+
+    /// Skipped languages with the reason
+    pub fn skipped(&self) -> Vec<(&'static str, &str)> {
+        self.languages
+            .iter()
+            .filter_map(|l| match &l.result {
+                Ok(InitOutcome::Skipped(reason)) => Some((l.language, reason.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Failed languages with the error
+    pub fn failed(&self) -> Vec<(&'static str, &Error)> {
+        self.languages
+            .iter()
+            .filter_map(|l| l.result.as_ref().err().map(|e| (l.language, e)))
+            .collect()
+    }
+}
+
+async fn init_language(sandbox: &Sandbox, language: &str) -> Result<InitOutcome> {
+    match language {
+        "python" => python::init_environment(sandbox).await,
+        "javascript" => javascript::init_environment(sandbox).await,
+        "ruby" => ruby::init_environment(sandbox).await,
+        "perl" => perl::init_environment(sandbox).await,
+        "php" => php::init_environment(sandbox).await,
+        "rust" => rust::init_environment(sandbox).await,
+        "go" => go::init_environment(sandbox).await,
+        "java" => java::init_environment(sandbox).await,
+        other => Err(Error::config(format!(
+            "Unknown sandbox language: {}",
+            other
+        ))),
+    }
+}
+
 /// Sandbox status information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SandboxStatus {
@@ -470,3 +726,128 @@ impl SandboxStatus {
         langs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(root: &Path) -> SandboxConfig {
+        SandboxConfig {
+            root_dir: root.to_path_buf(),
+            enable_python: false,
+            enable_javascript: false,
+            enable_ruby: false,
+            enable_perl: false,
+            enable_php: false,
+            enable_rust: false,
+            enable_go: false,
+            enable_java: false,
+            auto_init: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_init_only_runs_enabled_languages() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sandbox = Sandbox::with_config(config(dir.path()));
+        assert!(sandbox.enabled_languages().is_empty());
+
+        let report = sandbox.init().await.unwrap();
+        assert!(report.languages.is_empty());
+        assert!(dir.path().join("config.yaml").exists());
+
+        let mut partial = config(dir.path());
+        partial.enable_python = true;
+        partial.enable_go = true;
+        assert_eq!(
+            Sandbox::with_config(partial).enabled_languages(),
+            vec!["python", "go"]
+        );
+    }
+
+    #[test]
+    fn test_env_vars_put_venv_executables_on_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut python = config(dir.path());
+        python.enable_python = true;
+
+        let env_vars = Sandbox::with_config(python).get_env_vars();
+        let path = &env_vars.iter().find(|(key, _)| key == "PATH").unwrap().1;
+        let first = std::env::split_paths(path).next().unwrap();
+        let expected = if cfg!(windows) { "Scripts" } else { "bin" };
+        assert_eq!(first, dir.path().join("python").join("venv").join(expected));
+    }
+
+    #[test]
+    fn test_open_reads_initialized_sandbox() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Sandbox::open(dir.path()).is_none());
+
+        let mut perl = config(dir.path());
+        perl.enable_perl = true;
+        perl.save(&dir.path().join("config.yaml")).unwrap();
+
+        let sandbox = Sandbox::open(dir.path()).unwrap();
+        assert!(sandbox.is_initialized());
+        assert_eq!(sandbox.enabled_languages(), vec!["perl"]);
+        let env_vars = sandbox.get_env_vars();
+        let perl5lib = &env_vars
+            .iter()
+            .find(|(key, _)| key == "PERL5LIB")
+            .unwrap()
+            .1;
+        assert_eq!(
+            std::env::split_paths(perl5lib).next().unwrap(),
+            dir.path().join("perl/local").join("lib").join("perl5")
+        );
+    }
+
+    #[test]
+    fn test_interactive_shell_prefers_shell_env() {
+        let shell = interactive_shell_from(|key| (key == "SHELL").then(|| "/usr/bin/zsh".into()));
+        assert_eq!(shell, PathBuf::from("/usr/bin/zsh"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_interactive_shell_defaults_to_bash() {
+        assert_eq!(interactive_shell_from(|_| None), PathBuf::from("/bin/bash"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_interactive_shell_falls_back_to_comspec() {
+        let shell = interactive_shell_from(|key| match key {
+            "SystemRoot" => Some("Z:\\missing".into()),
+            "COMSPEC" => Some("C:\\Windows\\system32\\cmd.exe".into()),
+            _ => None,
+        });
+        assert_eq!(shell, PathBuf::from("C:\\Windows\\system32\\cmd.exe"));
+    }
+
+    #[test]
+    fn test_init_report_groups_outcomes() {
+        let report = InitReport {
+            languages: vec![
+                LanguageInit {
+                    language: "python",
+                    result: Ok(InitOutcome::Ready),
+                },
+                LanguageInit {
+                    language: "ruby",
+                    result: Ok(InitOutcome::Skipped("gem not found".into())),
+                },
+                LanguageInit {
+                    language: "php",
+                    result: Err(Error::config("composer install failed")),
+                },
+            ],
+        };
+
+        assert_eq!(report.succeeded(), vec!["python"]);
+        assert_eq!(report.skipped(), vec![("ruby", "gem not found")]);
+        let failed = report.failed();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, "php");
+    }
+}
@@ -1,11 +1,11 @@
 //! Perl sandbox environment
 
 use crate::error::{Error, Result};
-use crate::sandbox::Sandbox;
+use crate::sandbox::{InitOutcome, Sandbox};
 use std::process::Command;
 
 /// Initialize Perl environment
-pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
+pub async fn init_environment(sandbox: &Sandbox) -> Result<InitOutcome> {
     tracing::info!("Initializing Perl sandbox environment");
 
     // Check if Perl is available, attempt installation if missing
@@ -16,7 +16,7 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
     if !perl_available {
         tracing::warn!("Perl not available and automatic installation failed");
         tracing::warn!("Skipping Perl sandbox initialization");
-        return Ok(());
+        return Ok(InitOutcome::Skipped("Perl not available".into()));
     }
 
     // Check if cpanm is available, install if needed
@@ -35,7 +35,9 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
             );
             tracing::warn!("Or install via system package manager: brew install perl (macOS) or apt-get install perl (Linux)");
             tracing::warn!("Skipping Perl module installation. Perl scripts may still work if modules are already installed.");
-            return Ok(()); // Don't fail, just skip Perl
+            return Ok(InitOutcome::Skipped(
+                "curl not found, cannot install cpanm".into(),
+            ));
         }
 
         // Store cpanm in sandbox directory for reuse
@@ -45,7 +47,9 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
         // Create perl directory if it doesn't exist
         if let Err(e) = std::fs::create_dir_all(&cpanm_dir) {
             tracing::warn!("Failed to create Perl sandbox directory: {}", e);
-            return Ok(());
+            return Ok(InitOutcome::Skipped(
+                "failed to create Perl sandbox directory".into(),
+            ));
         }
 
         // Download cpanm to sandbox directory
@@ -61,7 +65,7 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
         if download_result.is_err() || !download_result.as_ref().unwrap().status.success() {
             tracing::warn!("Failed to download cpanm. Skipping Perl module installation.");
             tracing::warn!("You can install cpanm manually: curl -L https://cpanmin.us | perl - App::cpanminus");
-            return Ok(()); // Don't fail, just skip Perl
+            return Ok(InitOutcome::Skipped("failed to download cpanm".into()));
         }
 
         // Make cpanm executable
@@ -71,7 +75,9 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
 
         if chmod_result.is_err() {
             tracing::warn!("Failed to make cpanm executable. Skipping Perl module installation.");
-            return Ok(());
+            return Ok(InitOutcome::Skipped(
+                "failed to make cpanm executable".into(),
+            ));
         }
 
         // Try to install cpanm system-wide (preferred)
@@ -113,7 +119,7 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
     install_modules(sandbox, &module_refs).await?;
 
     tracing::info!("Perl sandbox environment initialized successfully");
-    Ok(())
+    Ok(InitOutcome::Ready)
 }
 
 /// Install Perl modules
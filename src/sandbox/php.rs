@@ -1,12 +1,12 @@
 //! PHP sandbox environment
 
 use crate::error::{Error, Result};
-use crate::sandbox::Sandbox;
+use crate::sandbox::{InitOutcome, Sandbox};
 use std::fs;
 use std::process::Command;
 
 /// Initialize PHP environment
-pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
+pub async fn init_environment(sandbox: &Sandbox) -> Result<InitOutcome> {
     tracing::info!("Initializing PHP sandbox environment");
 
     // Check if PHP is available, attempt installation if missing
@@ -17,7 +17,7 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
     if !php_available {
         tracing::warn!("PHP not available and automatic installation failed");
         tracing::warn!("Skipping PHP sandbox initialization");
-        return Ok(());
+        return Ok(InitOutcome::Skipped("PHP not available".into()));
     }
 
     let php_dir = sandbox.root_dir().join("php");
@@ -51,7 +51,7 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
     }
 
     tracing::info!("PHP sandbox environment initialized successfully");
-    Ok(())
+    Ok(InitOutcome::Ready)
 }
 
 /// Install Composer packages
@@ -1,14 +1,34 @@
 //! Python sandbox environment
 
 use crate::error::{Error, Result};
-use crate::sandbox::Sandbox;
+use crate::sandbox::{InitOutcome, Sandbox};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Virtual environment directory under the sandbox root
+pub fn venv_dir(root: &Path) -> PathBuf {
+    root.join("python").join("venv")
+}
+
+/// Directory holding the venv's executables (`Scripts` on Windows, `bin` elsewhere)
+pub fn venv_bin_dir(venv: &Path) -> PathBuf {
+    if cfg!(windows) {
+        venv.join("Scripts")
+    } else {
+        venv.join("bin")
+    }
+}
+
+/// Executable `name` inside the venv, with the platform's executable suffix
+pub fn venv_executable(venv: &Path, name: &str) -> PathBuf {
+    venv_bin_dir(venv).join(format!("{}{}", name, std::env::consts::EXE_SUFFIX))
+}
+
 /// Initialize Python virtual environment
-pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
+pub async fn init_environment(sandbox: &Sandbox) -> Result<InitOutcome> {
     tracing::info!("Initializing Python sandbox environment");
 
-    let venv_path = sandbox.root_dir().join("python/venv");
+    let venv_path = venv_dir(sandbox.root_dir());
 
     // Check if Python is available, attempt installation if missing
     use crate::sandbox::runtime_installer::ensure_runtime_available;
@@ -18,7 +38,7 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
     if !python_available {
         tracing::warn!("Python3 not available and automatic installation failed");
         tracing::warn!("Skipping Python sandbox initialization");
-        return Ok(());
+        return Ok(InitOutcome::Skipped("Python 3 not available".into()));
     }
 
     // Create virtual environment
@@ -50,7 +70,7 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
     install_packages(sandbox, &package_refs).await?;
 
     tracing::info!("Python sandbox environment initialized successfully");
-    Ok(())
+    Ok(InitOutcome::Ready)
 }
 
 /// Install Python packages
@@ -61,7 +81,7 @@ pub async fn install_packages(sandbox: &Sandbox, packages: &[&str]) -> Result<()
 
     tracing::info!("Installing Python packages: {:?}", packages);
 
-    let pip_path = sandbox.root_dir().join("python/venv/bin/pip");
+    let pip_path = venv_executable(&venv_dir(sandbox.root_dir()), "pip");
 
     let mut successful = 0;
     let mut failed = 0;
@@ -198,14 +218,14 @@ fn is_builtin_python_module(module: &str) -> bool {
 }
 
 /// Get Python executable path in sandbox
-pub fn get_python_path(sandbox: &Sandbox) -> std::path::PathBuf {
-    sandbox.root_dir().join("python/venv/bin/python")
+pub fn get_python_path(sandbox: &Sandbox) -> PathBuf {
+    venv_executable(&venv_dir(sandbox.root_dir()), "python")
 }
 
 /// Execute Python script in sandbox
 pub async fn execute_script(
     sandbox: &Sandbox,
-    script_path: &std::path::Path,
+    script_path: &Path,
     args: &[&str],
 ) -> Result<std::process::Output> {
     let python_path = get_python_path(sandbox);
@@ -222,3 +242,28 @@ pub async fn execute_script(
     cmd.output()
         .map_err(|e| Error::command(format!("Failed to execute Python script: {}", e)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_venv_executable_unix() {
+        let venv = venv_dir(Path::new("/sandbox"));
+        assert_eq!(
+            venv_executable(&venv, "pip"),
+            PathBuf::from("/sandbox/python/venv/bin/pip")
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_venv_executable_windows() {
+        let venv = venv_dir(Path::new("C:\\sandbox"));
+        assert_eq!(
+            venv_executable(&venv, "pip"),
+            PathBuf::from("C:\\sandbox\\python\\venv\\Scripts\\pip.exe")
+        );
+    }
+}
@@ -1,11 +1,11 @@
 //! Ruby sandbox environment
 
 use crate::error::{Error, Result};
-use crate::sandbox::Sandbox;
+use crate::sandbox::{InitOutcome, Sandbox};
 use std::process::Command;
 
 /// Initialize Ruby environment
-pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
+pub async fn init_environment(sandbox: &Sandbox) -> Result<InitOutcome> {
     tracing::info!("Initializing Ruby sandbox environment");
 
     // Check if Ruby is available, attempt installation if missing
@@ -16,7 +16,7 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
     if !ruby_available {
         tracing::warn!("Ruby not available and automatic installation failed");
         tracing::warn!("Skipping Ruby sandbox initialization");
-        return Ok(());
+        return Ok(InitOutcome::Skipped("Ruby not available".into()));
     }
 
     // Check Ruby version
@@ -56,7 +56,7 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
 
     if gem_check.is_err() {
         tracing::warn!("Ruby/gem not found, skipping Ruby sandbox initialization");
-        return Ok(());
+        return Ok(InitOutcome::Skipped("gem not found".into()));
     }
 
     // Install comprehensive gems from manifest
@@ -73,7 +73,7 @@ pub async fn init_environment(sandbox: &Sandbox) -> Result<()> {
     install_gems(sandbox, &gem_refs).await?;
 
     tracing::info!("Ruby sandbox environment initialized successfully");
-    Ok(())
+    Ok(InitOutcome::Ready)
 }
 
 /// Install Ruby gems
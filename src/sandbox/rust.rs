@@ -1,11 +1,11 @@
 //! Rust sandbox environment
 
 use crate::error::{Error, Result};
-use crate::sandbox::Sandbox;
+use crate::sandbox::{InitOutcome, Sandbox};
 use std::process::Command;
 
 /// Initialize Rust environment
-pub async fn init_environment(_sandbox: &Sandbox) -> Result<()> {
+pub async fn init_environment(_sandbox: &Sandbox) -> Result<InitOutcome> {
     tracing::info!("Initializing Rust sandbox environment");
 
     // Check if Rust (cargo/rustc) is available, attempt installation if missing
@@ -18,11 +18,11 @@ pub async fn init_environment(_sandbox: &Sandbox) -> Result<()> {
         tracing::warn!("Rust/Cargo not available and automatic installation failed");
         tracing::warn!("Skipping Rust sandbox initialization");
         tracing::info!("You can install Rust manually: curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs | sh");
-        return Ok(());
+        return Ok(InitOutcome::Skipped("Rust/Cargo not available".into()));
     }
 
     tracing::info!("Rust sandbox environment initialized successfully");
-    Ok(())
+    Ok(InitOutcome::Ready)
 }
 
 /// Compile and execute Rust template
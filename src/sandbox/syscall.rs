@@ -0,0 +1,112 @@
+//! Syscall filtering for template processes (`sandbox.syscall_filtering`)
+//!
+//! Compiled and interpreted templates run as child processes. With filtering
+//! on, each one is confined before its program starts:
+//!
+//! - Linux (x86_64, aarch64): a seccomp filter allows the syscalls runtimes
+//!   need for files, sockets, memory, time, signals and threads. Anything
+//!   else, including starting a new process (`fork`, `vfork`, or `clone`
+//!   without `CLONE_THREAD`) or any `execve` after the one starting the
+//!   template, kills the template's process group and the run fails with
+//!   [`Error::SandboxViolation`] naming the syscall.
+//! - macOS: a Seatbelt profile denies `process-fork`. The call fails with
+//!   `EPERM` inside the template; no violation is reported.
+//!
+//! On macOS a template can still replace itself with another program. Commands
+//! routed into a Docker sandbox are left to the container's own seccomp profile.
+//!
+//! [`Error::SandboxViolation`]: crate::error::Error::SandboxViolation
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(target_os = "macos")]
+mod seatbelt;
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod seccomp;
+
+#[cfg(target_os = "macos")]
+pub use seatbelt::confine;
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+pub use seccomp::{confine, Monitor, Watch};
+
+/// Whether template processes can be confined on this platform
+pub const SUPPORTED: bool = cfg!(any(
+    all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ),
+    target_os = "macos"
+));
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turn filtering of template processes on or off for this process
+pub fn set_enabled(enabled: bool) {
+    if enabled && !SUPPORTED {
+        tracing::warn!(
+            "Syscall filtering is not supported on this platform; templates run unfiltered"
+        );
+    }
+    ENABLED.store(enabled && SUPPORTED, Ordering::Relaxed);
+}
+
+/// Whether template processes are confined
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Confines nothing where violations are not reported
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+#[derive(Debug)]
+pub struct Monitor;
+
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+impl Monitor {
+    /// Start watching the spawned process
+    pub fn watch(self, _pid: Option<u32>) -> Watch {
+        Watch
+    }
+}
+
+/// Reports nothing where violations are not reported
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+#[derive(Debug)]
+pub struct Watch;
+
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+impl Watch {
+    /// Forbidden syscall that stopped the process, if any
+    pub fn finish(self) -> Option<String> {
+        None
+    }
+}
+
+/// Leave `command` unconfined on platforms without filtering
+#[cfg(not(any(
+    all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ),
+    target_os = "macos"
+)))]
+pub fn confine(_command: &mut tokio::process::Command) -> std::io::Result<Monitor> {
+    Ok(Monitor)
+}
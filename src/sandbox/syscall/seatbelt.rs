@@ -0,0 +1,38 @@
+//! Seatbelt profile for macOS
+//!
+//! The profile is applied in the forked child just before `exec` and is
+//! inherited by the template's program. Forbidden operations fail with
+//! `EPERM`; Seatbelt does not tell the parent, so nothing is reported.
+
+// The profile is applied between fork and exec
+#![allow(unsafe_code)]
+
+use super::Monitor;
+use std::io;
+use std::os::raw::{c_char, c_int};
+use tokio::process::Command;
+
+/// Allow everything except starting new processes
+const PROFILE: &[u8] = b"(version 1)(allow default)(deny process-fork)\0";
+
+extern "C" {
+    fn sandbox_init(profile: *const c_char, flags: u64, errorbuf: *mut *mut c_char) -> c_int;
+    fn sandbox_free_error(errorbuf: *mut c_char);
+}
+
+/// Confine `command` with the Seatbelt profile
+pub fn confine(command: &mut Command) -> io::Result<Monitor> {
+    // SAFETY: `PROFILE` is NUL-terminated and static; a returned error
+    // buffer is freed before returning
+    unsafe {
+        command.pre_exec(|| {
+            let mut error = std::ptr::null_mut();
+            if sandbox_init(PROFILE.as_ptr().cast(), 0, &mut error) != 0 {
+                sandbox_free_error(error);
+                return Err(io::Error::from(io::ErrorKind::PermissionDenied));
+            }
+            Ok(())
+        });
+    }
+    Ok(Monitor)
+}
@@ -0,0 +1,582 @@
+//! seccomp-bpf filter for Linux
+//!
+//! The BPF is compiled by `seccompiler` before the fork. Two filters are
+//! installed in the forked child just before `exec`: one that answers the
+//! [`UNAVAILABLE`] syscalls with `ENOSYS`, then the allowlist, whose other
+//! syscalls raise a user notification. The kernel applies the strictest
+//! result, so `ENOSYS` wins over the notification.
+//!
+//! The allowlist is installed with `SECCOMP_FILTER_FLAG_NEW_LISTENER`, and
+//! the child hands the listener to us over a socket pair; a watcher thread,
+//! started before the spawn, receives the notification for the first
+//! forbidden syscall, kills the process group and records the syscall.
+//! Everything the child does after installing the allowlist (`sendmsg`,
+//! `close`, `execve`) is on it. `execve` is not on the allowlist: the watcher
+//! lets the child's first one, which starts the template, continue and treats
+//! any later one as a violation.
+
+// seccompiler installs a filter but cannot create a notification listener,
+// and nix allocates when sending descriptors, which the forked child must
+// not do. Installing the listener, passing it on and the notification
+// ioctls stay raw syscalls.
+#![allow(unsafe_code)]
+
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::sys::socket::{
+    recvmsg, socketpair, AddressFamily, ControlMessageOwned, MsgFlags, SockFlag, SockType,
+};
+use seccompiler::{
+    BpfProgram, SeccompAction, SeccompCmpArgLen, SeccompCmpOp, SeccompCondition, SeccompFilter,
+    SeccompRule, TargetArch,
+};
+use std::collections::BTreeMap;
+use std::io::{self, IoSliceMut};
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use tokio::process::Command;
+
+/// `SECCOMP_IOCTL_NOTIF_RECV` and `SECCOMP_IOCTL_NOTIF_SEND`
+const NOTIF_RECV: u64 = ioctl_rw(0, size_of::<libc::seccomp_notif>());
+const NOTIF_SEND: u64 = ioctl_rw(1, size_of::<libc::seccomp_notif_resp>());
+
+/// Stand-in for `SECCOMP_RET_USER_NOTIF`, which seccompiler has no action for
+const NOTIFY_PLACEHOLDER: SeccompAction = SeccompAction::Trace(0);
+
+/// How often the watcher checks whether the run is over without a `POLLHUP`
+const POLL_INTERVAL_MS: libc::c_int = 100;
+
+macro_rules! syscalls {
+    ($($name:ident),* $(,)?) => {
+        &[$((stringify!($name), libc::$name)),*]
+    };
+}
+
+/// Syscalls templates may make
+#[rustfmt::skip]
+const ALLOWED: &[(&str, libc::c_long)] = syscalls![
+    // Files
+    SYS_read, SYS_write, SYS_readv, SYS_writev, SYS_pread64, SYS_pwrite64,
+    SYS_preadv, SYS_pwritev, SYS_openat, SYS_close, SYS_close_range, SYS_fstat,
+    SYS_newfstatat, SYS_statx, SYS_fstatfs, SYS_statfs, SYS_lseek, SYS_ioctl,
+    SYS_fcntl, SYS_dup, SYS_dup3, SYS_pipe2, SYS_getcwd, SYS_chdir, SYS_fchdir,
+    SYS_faccessat, SYS_faccessat2, SYS_readlinkat, SYS_getdents64, SYS_mkdirat,
+    SYS_unlinkat, SYS_renameat2, SYS_ftruncate, SYS_fsync, SYS_fdatasync,
+    SYS_flock, SYS_umask, SYS_utimensat, SYS_fchmod, SYS_fchmodat,
+    SYS_copy_file_range, SYS_memfd_create,
+    // Sockets
+    SYS_socket, SYS_socketpair, SYS_connect, SYS_bind, SYS_listen, SYS_accept4,
+    SYS_getsockname, SYS_getpeername, SYS_sendto, SYS_recvfrom, SYS_sendmsg,
+    SYS_recvmsg, SYS_sendmmsg, SYS_recvmmsg, SYS_setsockopt, SYS_getsockopt,
+    SYS_shutdown,
+    // Waiting on descriptors
+    SYS_epoll_create1, SYS_epoll_ctl, SYS_epoll_pwait, SYS_epoll_pwait2,
+    SYS_ppoll, SYS_pselect6, SYS_eventfd2, SYS_timerfd_create,
+    SYS_timerfd_settime, SYS_timerfd_gettime, SYS_signalfd4,
+    // Memory
+    SYS_mmap, SYS_mprotect, SYS_munmap, SYS_mremap, SYS_madvise, SYS_mincore,
+    SYS_msync, SYS_brk, SYS_membarrier,
+    // Signals
+    SYS_rt_sigaction, SYS_rt_sigprocmask, SYS_rt_sigreturn, SYS_rt_sigtimedwait,
+    SYS_rt_sigsuspend, SYS_sigaltstack, SYS_tgkill, SYS_tkill,
+    // Time
+    SYS_nanosleep, SYS_clock_nanosleep, SYS_clock_gettime, SYS_clock_getres,
+    SYS_gettimeofday, SYS_getitimer, SYS_setitimer, SYS_times,
+    // Threads and process state
+    SYS_futex, SYS_futex_waitv, SYS_set_tid_address, SYS_set_robust_list,
+    SYS_get_robust_list, SYS_rseq, SYS_sched_yield, SYS_sched_getaffinity,
+    SYS_sched_setaffinity, SYS_sched_getparam, SYS_sched_getscheduler,
+    SYS_sched_get_priority_max, SYS_sched_get_priority_min, SYS_getcpu,
+    SYS_getpid, SYS_gettid, SYS_getppid, SYS_getuid, SYS_geteuid, SYS_getgid,
+    SYS_getegid, SYS_getgroups, SYS_getpgid, SYS_getsid, SYS_getresuid,
+    SYS_getresgid, SYS_getpriority, SYS_prlimit64, SYS_getrusage, SYS_capget,
+    SYS_sysinfo, SYS_uname, SYS_prctl, SYS_getrandom, SYS_wait4, SYS_waitid,
+    SYS_restart_syscall, SYS_exit, SYS_exit_group,
+];
+
+/// Legacy forms of allowed syscalls that only x86_64 has
+#[cfg(target_arch = "x86_64")]
+#[rustfmt::skip]
+const ALLOWED_LEGACY: &[(&str, libc::c_long)] = syscalls![
+    SYS_open, SYS_stat, SYS_lstat, SYS_access, SYS_readlink, SYS_mkdir,
+    SYS_unlink, SYS_rename, SYS_chmod, SYS_getdents, SYS_pipe, SYS_dup2,
+    SYS_poll, SYS_select, SYS_epoll_create, SYS_epoll_wait, SYS_eventfd,
+    SYS_signalfd, SYS_sendfile, SYS_fadvise64, SYS_alarm, SYS_pause, SYS_time,
+    SYS_getpgrp, SYS_getrlimit, SYS_arch_prctl,
+];
+#[cfg(target_arch = "aarch64")]
+const ALLOWED_LEGACY: &[(&str, libc::c_long)] = &[];
+
+/// Refused with `ENOSYS` so runtimes fall back to allowed syscalls
+///
+/// glibc creates threads with `clone3` and retries with `clone`, whose flags
+/// the filter can inspect; libuv probes `io_uring` and V8 probes memory
+/// protection keys, and both carry on without them.
+const UNAVAILABLE: &[(&str, libc::c_long)] =
+    syscalls![SYS_clone3, SYS_io_uring_setup, SYS_pkey_alloc];
+
+/// Forbidden syscalls worth naming in a violation
+#[rustfmt::skip]
+const FORBIDDEN: &[(&str, libc::c_long)] = syscalls![
+    SYS_clone, SYS_execve, SYS_execveat, SYS_kill, SYS_ptrace, SYS_process_vm_readv,
+    SYS_process_vm_writev, SYS_mount, SYS_umount2, SYS_chroot, SYS_unshare,
+    SYS_setns, SYS_setuid, SYS_setgid, SYS_personality, SYS_keyctl, SYS_bpf,
+    SYS_init_module, SYS_finit_module, SYS_reboot,
+];
+#[cfg(target_arch = "x86_64")]
+const FORBIDDEN_LEGACY: &[(&str, libc::c_long)] = syscalls![SYS_fork, SYS_vfork];
+#[cfg(target_arch = "aarch64")]
+const FORBIDDEN_LEGACY: &[(&str, libc::c_long)] = &[];
+
+const fn ioctl_rw(nr: u64, size: usize) -> u64 {
+    (3 << 30) | ((size as u64) << 16) | ((b'!' as u64) << 8) | nr
+}
+
+/// Filters installed in the child, compiled before the fork
+#[derive(Debug)]
+struct Filters {
+    /// `ENOSYS` for [`UNAVAILABLE`], everything else allowed
+    unavailable: BpfProgram,
+    /// [`ALLOWED`] and thread `clone`s allowed, everything else notified
+    allowlist: BpfProgram,
+}
+
+fn compile_filters() -> seccompiler::Result<Filters> {
+    let arch = TargetArch::try_from(std::env::consts::ARCH)?;
+
+    let unavailable = SeccompFilter::new(
+        UNAVAILABLE
+            .iter()
+            .map(|(_, nr)| (*nr, Vec::new()))
+            .collect(),
+        SeccompAction::Allow,
+        SeccompAction::Errno(libc::ENOSYS as u32),
+        arch,
+    )?;
+
+    let mut rules: BTreeMap<i64, Vec<SeccompRule>> = ALLOWED
+        .iter()
+        .chain(ALLOWED_LEGACY)
+        .map(|(_, nr)| (*nr, Vec::new()))
+        .collect();
+    // New threads are fine; a `clone` without CLONE_THREAD is a new process
+    let clone_thread = libc::CLONE_THREAD as u64;
+    rules.insert(
+        libc::SYS_clone,
+        vec![SeccompRule::new(vec![SeccompCondition::new(
+            0,
+            SeccompCmpArgLen::Qword,
+            SeccompCmpOp::MaskedEq(clone_thread),
+            clone_thread,
+        )?])?],
+    );
+    let allowlist = SeccompFilter::new(rules, NOTIFY_PLACEHOLDER, SeccompAction::Allow, arch)?;
+
+    let mut allowlist: BpfProgram = allowlist.try_into()?;
+    let placeholder = u32::from(NOTIFY_PLACEHOLDER);
+    for instruction in &mut allowlist {
+        if instruction.code == (libc::BPF_RET | libc::BPF_K) as u16 && instruction.k == placeholder
+        {
+            instruction.k = libc::SECCOMP_RET_USER_NOTIF;
+        }
+    }
+
+    Ok(Filters {
+        unavailable: unavailable.try_into()?,
+        allowlist,
+    })
+}
+
+/// Name of syscall `nr`, e.g. `clone`
+fn syscall_name(nr: i32) -> String {
+    ALLOWED
+        .iter()
+        .chain(ALLOWED_LEGACY)
+        .chain(UNAVAILABLE)
+        .chain(FORBIDDEN)
+        .chain(FORBIDDEN_LEGACY)
+        .find(|(_, number)| *number == nr as libc::c_long)
+        .map(|(name, _)| name.trim_start_matches("SYS_").to_string())
+        .unwrap_or_else(|| format!("syscall {}", nr))
+}
+
+/// Confine `command` to the allowlist; call [`Monitor::watch`] once it is spawned
+///
+/// `command`'s program should be a path: every `execve` after the first one,
+/// including those of a `PATH` search, is a violation.
+pub fn confine(command: &mut Command) -> io::Result<Monitor> {
+    let (socket, child) = socketpair(
+        AddressFamily::Unix,
+        SockType::SeqPacket,
+        None,
+        SockFlag::SOCK_CLOEXEC,
+    )?;
+
+    let filters = compile_filters().map_err(io::Error::other)?;
+    let child_socket = child.as_raw_fd();
+    // SAFETY: the closure runs between fork and exec and only makes raw
+    // syscalls on memory allocated before the fork
+    unsafe {
+        command.pre_exec(move || install(&filters, child_socket));
+    }
+
+    // Spawning waits for the `execve` the watcher has to let through
+    let pid = Arc::new(AtomicU32::new(0));
+    let done = Arc::new(AtomicBool::new(false));
+    let handle = {
+        let (pid, done) = (pid.clone(), done.clone());
+        std::thread::spawn(move || watch_listener(socket, &pid, &done))
+    };
+    Ok(Monitor {
+        child,
+        pid,
+        watch: Watch {
+            done,
+            handle: Some(handle),
+        },
+    })
+}
+
+/// Install the filters in the child and send the listener to the parent
+fn install(filters: &Filters, socket: RawFd) -> io::Result<()> {
+    // Also sets no_new_privs, which the listener's filter relies on
+    seccompiler::apply_filter(&filters.unavailable).map_err(|_| io::Error::last_os_error())?;
+
+    let prog = libc::sock_fprog {
+        len: filters.allowlist.len() as libc::c_ushort,
+        // seccompiler's `sock_filter` is the kernel's layout
+        filter: filters.allowlist.as_ptr() as *mut libc::sock_filter,
+    };
+    // SAFETY: `prog` points at the compiled program, which outlives the call
+    let listener = unsafe {
+        libc::syscall(
+            libc::SYS_seccomp,
+            libc::SECCOMP_SET_MODE_FILTER,
+            libc::SECCOMP_FILTER_FLAG_NEW_LISTENER,
+            &prog as *const libc::sock_fprog,
+        )
+    };
+    if listener < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let sent = send_fd(socket, listener as RawFd);
+    // SAFETY: the listener was just created and nothing else refers to it
+    unsafe { libc::close(listener as RawFd) };
+    sent
+}
+
+/// Control message buffer with room for one descriptor
+#[repr(C)]
+union Control {
+    buf: [u8; unsafe { libc::CMSG_SPACE(size_of::<RawFd>() as u32) } as usize],
+    _align: libc::cmsghdr,
+}
+
+/// Send `fd` over `socket` without allocating, as the forked child must
+fn send_fd(socket: RawFd, fd: RawFd) -> io::Result<()> {
+    let mut byte = 0u8;
+    let mut iov = libc::iovec {
+        iov_base: (&mut byte as *mut u8).cast(),
+        iov_len: 1,
+    };
+    // SAFETY: the header and control buffer live on this stack frame and
+    // CMSG_FIRSTHDR points into `control`, which has room for one descriptor
+    unsafe {
+        let mut control: Control = mem::zeroed();
+        let mut msg: libc::msghdr = mem::zeroed();
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.buf.as_mut_ptr().cast();
+        msg.msg_controllen = size_of::<Control>() as _;
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(size_of::<RawFd>() as u32) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<RawFd>(), fd);
+        if libc::sendmsg(socket, &msg, 0) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Receive the listener, or `None` if the child closed its end without one
+fn recv_fd(socket: RawFd) -> io::Result<Option<OwnedFd>> {
+    let mut byte = [0u8; 1];
+    let mut iov = [IoSliceMut::new(&mut byte)];
+    let mut control = nix::cmsg_space!(RawFd);
+    let msg = recvmsg::<()>(
+        socket,
+        &mut iov,
+        Some(&mut control),
+        MsgFlags::MSG_CMSG_CLOEXEC,
+    )?;
+    let fd = msg.cmsgs().find_map(|cmsg| match cmsg {
+        ControlMessageOwned::ScmRights(fds) => fds.first().copied(),
+        _ => None,
+    });
+    // SAFETY: a descriptor received with SCM_RIGHTS is new and owned by us
+    Ok(fd.map(|fd| unsafe { OwnedFd::from_raw_fd(fd) }))
+}
+
+/// A confined command that has not been spawned yet
+#[derive(Debug)]
+pub struct Monitor {
+    child: OwnedFd,
+    pid: Arc<AtomicU32>,
+    watch: Watch,
+}
+
+impl Monitor {
+    /// Watch the spawned process (rooted at `pid`) for violations
+    pub fn watch(self, pid: Option<u32>) -> Watch {
+        let Monitor {
+            child,
+            pid: root,
+            watch,
+        } = self;
+        root.store(pid.unwrap_or(0), Ordering::Relaxed);
+        // Only the child needs its end; with ours closed a failed child reads as EOF
+        drop(child);
+        watch
+    }
+}
+
+/// Watches a running confined process
+#[derive(Debug)]
+pub struct Watch {
+    done: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Option<i32>>>,
+}
+
+impl Watch {
+    /// Forbidden syscall that stopped the process, if any
+    ///
+    /// Call once the process has exited.
+    pub fn finish(mut self) -> Option<String> {
+        self.done.store(true, Ordering::Relaxed);
+        let nr = self.handle.take()?.join().ok().flatten()?;
+        Some(syscall_name(nr))
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::Relaxed);
+    }
+}
+
+fn watch_listener(socket: OwnedFd, pid: &AtomicU32, done: &AtomicBool) -> Option<i32> {
+    let listener = match recv_fd(socket.as_raw_fd()) {
+        Ok(listener) => listener?,
+        Err(e) => {
+            tracing::debug!("No seccomp listener from template process: {}", e);
+            return None;
+        }
+    };
+
+    let mut exec_started = false;
+    loop {
+        let mut pollfds = [PollFd::new(&listener, PollFlags::POLLIN)];
+        let ready = poll(&mut pollfds, POLL_INTERVAL_MS).unwrap_or(0);
+        let revents = pollfds[0].revents().unwrap_or(PollFlags::empty());
+        if ready > 0 && revents.contains(PollFlags::POLLIN) {
+            if let Some(nr) = take_violation(&listener, pid, &mut exec_started) {
+                return Some(nr);
+            }
+        } else if ready > 0 || done.load(Ordering::Relaxed) {
+            // POLLHUP: every process using the filter has exited
+            return None;
+        }
+    }
+}
+
+/// Receive a notification; let the first `execve` continue, otherwise stop
+/// the process tree and fail the syscall
+fn take_violation(listener: &OwnedFd, pid: &AtomicU32, exec_started: &mut bool) -> Option<i32> {
+    // SAFETY: the kernel fills the zeroed notification and reads the response
+    unsafe {
+        let mut notif: libc::seccomp_notif = mem::zeroed();
+        if libc::ioctl(listener.as_raw_fd(), NOTIF_RECV as _, &mut notif) != 0 {
+            // The process died before we got to the notification
+            return None;
+        }
+        let mut resp = libc::seccomp_notif_resp {
+            id: notif.id,
+            val: 0,
+            error: -libc::EPERM,
+            flags: 0,
+        };
+        // The child is still single-threaded, so the checked call is the one that runs
+        if notif.data.nr == libc::SYS_execve as i32 && !*exec_started {
+            *exec_started = true;
+            resp.error = 0;
+            resp.flags = libc::SECCOMP_USER_NOTIF_FLAG_CONTINUE as u32;
+            libc::ioctl(listener.as_raw_fd(), NOTIF_SEND as _, &mut resp);
+            return None;
+        }
+        // Before `watch` learns the root, the caller is the child itself
+        let root = match pid.load(Ordering::Relaxed) {
+            0 => notif.pid,
+            root => root,
+        };
+        crate::engine::process::kill_tree(root);
+        libc::ioctl(listener.as_raw_fd(), NOTIF_SEND as _, &mut resp);
+        Some(notif.data.nr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::common::ToolCommand;
+    use crate::error::Error;
+
+    /// Compile `source` with the system C compiler, or `None` if there is none
+    fn compile(dir: &std::path::Path, source: &str) -> Option<std::path::PathBuf> {
+        let source_path = dir.join("template.c");
+        let binary = dir.join("template");
+        std::fs::write(&source_path, source).unwrap();
+        let status = std::process::Command::new("cc")
+            .arg(&source_path)
+            .arg("-pthread")
+            .arg("-o")
+            .arg(&binary)
+            .status()
+            .ok()?;
+        status.success().then_some(binary)
+    }
+
+    #[test]
+    fn test_filters_compile_and_name_syscalls() {
+        let filters = compile_filters().unwrap();
+        assert!(filters.allowlist.len() < 4096);
+        let placeholder = u32::from(NOTIFY_PLACEHOLDER);
+        assert!(filters.allowlist.iter().all(|i| i.k != placeholder));
+        assert!(filters
+            .allowlist
+            .iter()
+            .any(|i| i.k == libc::SECCOMP_RET_USER_NOTIF));
+        assert_eq!(syscall_name(libc::SYS_clone as i32), "clone");
+        assert_eq!(syscall_name(libc::SYS_connect as i32), "connect");
+        assert_eq!(syscall_name(100_000), "syscall 100000");
+    }
+
+    #[tokio::test]
+    async fn test_forbidden_syscall_stops_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(binary) = compile(
+            dir.path(),
+            r#"
+#include <stdio.h>
+#include <unistd.h>
+int main(void) {
+    puts("before");
+    fflush(stdout);
+    pid_t pid = fork();
+    printf("forked %d\n", (int)pid);
+    return 0;
+}
+"#,
+        ) else {
+            eprintln!("no C compiler; skipping");
+            return;
+        };
+
+        let err = ToolCommand::new(&binary)
+            .syscall_filter(true)
+            .output()
+            .await
+            .unwrap_err();
+        match err {
+            Error::SandboxViolation { syscall, .. } => assert_eq!(syscall, "clone"),
+            other => panic!("expected a sandbox violation, got {}", other),
+        }
+
+        // Unfiltered, the same program runs to completion
+        let output = ToolCommand::new(&binary).output().await.unwrap();
+        assert!(String::from_utf8_lossy(&output.stdout).contains("forked"));
+    }
+
+    #[tokio::test]
+    async fn test_second_exec_stops_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(binary) = compile(
+            dir.path(),
+            r#"
+#include <stdio.h>
+#include <unistd.h>
+int main(void) {
+    char *argv[] = {"sh", "-c", "echo replaced", NULL};
+    puts("before");
+    fflush(stdout);
+    execv("/bin/sh", argv);
+    puts("exec failed");
+    return 0;
+}
+"#,
+        ) else {
+            eprintln!("no C compiler; skipping");
+            return;
+        };
+
+        let err = ToolCommand::new(&binary)
+            .syscall_filter(true)
+            .output()
+            .await
+            .unwrap_err();
+        match err {
+            Error::SandboxViolation { syscall, .. } => assert_eq!(syscall, "execve"),
+            other => panic!("expected a sandbox violation, got {}", other),
+        }
+
+        // Unfiltered, the program replaces itself
+        let output = ToolCommand::new(&binary).output().await.unwrap();
+        assert!(String::from_utf8_lossy(&output.stdout).contains("replaced"));
+    }
+
+    #[tokio::test]
+    async fn test_allowed_syscalls_and_threads_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(binary) = compile(
+            dir.path(),
+            r#"
+#include <pthread.h>
+#include <stdio.h>
+static void *work(void *arg) { return arg; }
+int main(void) {
+    pthread_t thread;
+    void *result = NULL;
+    if (pthread_create(&thread, NULL, work, "ok") != 0) return 1;
+    pthread_join(thread, &result);
+    FILE *f = fopen("/proc/self/status", "r");
+    if (!f) return 2;
+    fclose(f);
+    printf("%s\n", (char *)result);
+    return 0;
+}
+"#,
+        ) else {
+            eprintln!("no C compiler; skipping");
+            return;
+        };
+
+        let output = ToolCommand::new(&binary)
+            .syscall_filter(true)
+            .output()
+            .await
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+
+        // A bare name is looked up in PATH before the one exec
+        let output = ToolCommand::new("true")
+            .syscall_filter(true)
+            .output()
+            .await
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    }
+}
@@ -0,0 +1,588 @@
+//! Package updates for sandbox language environments
+//!
+//! Each language is updated with its own package manager and reported as a
+//! list of version changes. Commands run on the host against the sandbox
+//! directories, or inside a Docker sandbox container when one is given.
+
+use crate::engine::common::ToolCommand;
+use crate::error::{Error, Result};
+use crate::sandbox::docker::{ExecRoute, CONTAINER_SANDBOX_DIR};
+use crate::sandbox::{packages, Sandbox, SandboxConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+/// Languages with a package manager that can be updated
+pub const UPDATABLE_LANGUAGES: &[&str] = &["python", "javascript", "ruby", "perl", "php"];
+
+/// Map a language name or alias (`py`, `js`, `rb`, ...) to its canonical name
+pub fn normalize_language(language: &str) -> Option<&'static str> {
+    match language.to_lowercase().as_str() {
+        "python" | "py" => Some("python"),
+        "javascript" | "js" | "node" => Some("javascript"),
+        "ruby" | "rb" => Some("ruby"),
+        "perl" | "pl" => Some("perl"),
+        "php" => Some("php"),
+        _ => None,
+    }
+}
+
+/// Updatable languages enabled in a sandbox configuration
+pub fn enabled_languages(config: &SandboxConfig) -> Vec<&'static str> {
+    [
+        ("python", config.enable_python),
+        ("javascript", config.enable_javascript),
+        ("ruby", config.enable_ruby),
+        ("perl", config.enable_perl),
+        ("php", config.enable_php),
+    ]
+    .into_iter()
+    .filter_map(|(language, enabled)| enabled.then_some(language))
+    .collect()
+}
+
+/// Version change of a single package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageChange {
+    /// Package name
+    pub name: String,
+    /// Version before the update (`None` if newly installed or unknown)
+    pub before: Option<String>,
+    /// Version after the update, or the available version in a dry run
+    pub after: Option<String>,
+}
+
+impl PackageChange {
+    fn new(name: &str, before: Option<&str>, after: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            before: before.map(str::to_string),
+            after: after.map(str::to_string),
+        }
+    }
+}
+
+/// Update result for one language
+#[derive(Debug)]
+pub struct LanguageUpdate {
+    /// Canonical language name
+    pub language: String,
+    /// Changed (or, in a dry run, updatable) packages
+    pub result: Result<Vec<PackageChange>>,
+}
+
+/// Updates sandbox packages per language
+#[derive(Debug)]
+pub struct PackageUpdater {
+    root: PathBuf,
+    route: Option<ExecRoute>,
+    dry_run: bool,
+}
+
+impl PackageUpdater {
+    /// Update the host package sandbox
+    pub fn new(sandbox: &Sandbox) -> Self {
+        Self {
+            root: sandbox.root_dir().to_path_buf(),
+            route: None,
+            dry_run: false,
+        }
+    }
+
+    /// Update the package sandbox inside a running Docker sandbox container
+    pub fn in_container(sandbox: &Sandbox, container: &str) -> Self {
+        let root = sandbox.root_dir().to_path_buf();
+        let route = ExecRoute::with_mounts(
+            container,
+            [(root.clone(), PathBuf::from(CONTAINER_SANDBOX_DIR))],
+        );
+        Self {
+            root,
+            route: Some(route),
+            dry_run: false,
+        }
+    }
+
+    /// Only list available updates instead of applying them
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Update each language, continuing past failures
+    pub async fn update_all(&self, languages: &[&str]) -> Vec<LanguageUpdate> {
+        let mut updates = Vec::with_capacity(languages.len());
+        for language in languages {
+            updates.push(self.update(language).await);
+        }
+        updates
+    }
+
+    /// Update a single language
+    pub async fn update(&self, language: &str) -> LanguageUpdate {
+        let result = match normalize_language(language) {
+            Some("python") => self.update_python().await,
+            Some("javascript") => self.update_javascript().await,
+            Some("ruby") => self.update_ruby().await,
+            Some("perl") => self.update_perl().await,
+            Some("php") => self.update_php().await,
+            _ => Err(Error::config(format!(
+                "Updating packages is not supported for '{}' (supported: {})",
+                language,
+                UPDATABLE_LANGUAGES.join(", ")
+            ))),
+        };
+        LanguageUpdate {
+            language: normalize_language(language).unwrap_or(language).to_string(),
+            result,
+        }
+    }
+
+    async fn update_python(&self) -> Result<Vec<PackageChange>> {
+        let venv = super::python::venv_dir(&self.root);
+        // Sandbox containers are Linux even on a Windows host
+        let pip = match self.route {
+            Some(_) => venv.join("bin").join("pip"),
+            None => super::python::venv_executable(&venv, "pip"),
+        };
+        let outdated = self
+            .run_json(pip_command(&pip, &["list", "--outdated", "--format=json"]))
+            .await?;
+        let outdated = parse_pip_outdated(&outdated);
+        if self.dry_run || outdated.is_empty() {
+            return Ok(outdated);
+        }
+
+        let list = || pip_command(&pip, &["list", "--format=json"]);
+        let before = parse_pip_list(&self.run_json(list()).await?);
+        let mut upgrade = pip_command(&pip, &["install", "--upgrade"]);
+        upgrade.args(outdated.iter().map(|c| c.name.as_str()));
+        self.run_checked(&upgrade).await?;
+        let after = parse_pip_list(&self.run_json(list()).await?);
+        Ok(diff_versions(&before, &after))
+    }
+
+    async fn update_javascript(&self) -> Result<Vec<PackageChange>> {
+        let dir = self.root.join("javascript");
+        let npm = |args: &[&str]| {
+            let mut cmd = ToolCommand::new("npm");
+            cmd.args(args).current_dir(&dir);
+            cmd
+        };
+
+        if self.dry_run {
+            // `npm outdated` exits 1 when anything is outdated
+            let output = self.run(&npm(&["outdated", "--json"])).await?;
+            return Ok(parse_npm_outdated(&stdout_json(&output)?));
+        }
+
+        let list = || npm(&["ls", "--json", "--depth=0"]);
+        let before = parse_npm_list(&stdout_json(&self.run(&list()).await?)?);
+        self.run_checked(&npm(&["update", "--no-audit", "--no-fund"]))
+            .await?;
+        let after = parse_npm_list(&stdout_json(&self.run(&list()).await?)?);
+        Ok(diff_versions(&before, &after))
+    }
+
+    async fn update_ruby(&self) -> Result<Vec<PackageChange>> {
+        // Environment values are not path-translated by the exec route
+        let gem_home = self.env_path(&self.root.join("ruby/gems"));
+        let gem = |args: &[&str]| {
+            let mut cmd = ToolCommand::new("gem");
+            cmd.args(args)
+                .env("GEM_HOME", gem_home.as_str())
+                .env("GEM_PATH", gem_home.as_str());
+            cmd
+        };
+
+        if self.dry_run {
+            let output = self.run_checked(&gem(&["outdated"])).await?;
+            return Ok(parse_gem_outdated(&String::from_utf8_lossy(&output.stdout)));
+        }
+
+        let list = || gem(&["list", "--local"]);
+        let before = parse_gem_list(&String::from_utf8_lossy(
+            &self.run_checked(&list()).await?.stdout,
+        ));
+        self.run_checked(&gem(&["update", "--no-document"])).await?;
+        let after = parse_gem_list(&String::from_utf8_lossy(
+            &self.run_checked(&list()).await?.stdout,
+        ));
+        Ok(diff_versions(&before, &after))
+    }
+
+    async fn update_perl(&self) -> Result<Vec<PackageChange>> {
+        let local_lib = self.root.join("perl/local");
+
+        if self.dry_run {
+            let mut cmd = ToolCommand::new("cpan-outdated");
+            cmd.arg("--local-lib").arg(&local_lib);
+            let output = self.run_checked(&cmd).await?;
+            return Ok(parse_cpan_outdated(&String::from_utf8_lossy(
+                &output.stdout,
+            )));
+        }
+
+        let cpanm = self.cpanm();
+        let mut self_upgrade = ToolCommand::new(&cpanm);
+        self_upgrade.arg("--self-upgrade");
+        if let Err(e) = self.run_checked(&self_upgrade).await {
+            tracing::warn!("cpanm self-upgrade failed: {}", e);
+        }
+
+        // cpanm skips modules that are already up to date
+        let mut install = ToolCommand::new(&cpanm);
+        install
+            .arg("-L")
+            .arg(&local_lib)
+            .args(packages::perl_manifest().all_packages());
+        let output = self.run(&install).await?;
+        let changes = parse_cpanm_installed(&String::from_utf8_lossy(&output.stdout));
+        if !output.status.success() && changes.is_empty() {
+            return Err(command_failed("cpanm", &output));
+        }
+        Ok(changes)
+    }
+
+    async fn update_php(&self) -> Result<Vec<PackageChange>> {
+        let dir = self.root.join("php");
+        let composer = |args: &[&str]| {
+            let mut cmd = ToolCommand::new("composer");
+            cmd.args(args).arg("--no-interaction").current_dir(&dir);
+            cmd
+        };
+
+        if self.dry_run {
+            let outdated = self
+                .run_json(composer(&["outdated", "--direct", "--format=json"]))
+                .await?;
+            return Ok(parse_composer_packages(&outdated, "latest"));
+        }
+
+        let list = || composer(&["show", "--direct", "--format=json"]);
+        let before = composer_versions(&self.run_json(list()).await?);
+        self.run_checked(&composer(&["update"])).await?;
+        let after = composer_versions(&self.run_json(list()).await?);
+        Ok(diff_versions(&before, &after))
+    }
+
+    /// cpanm binary: the sandbox-local copy if `sandbox init` downloaded one
+    fn cpanm(&self) -> PathBuf {
+        let local = self.root.join("perl/cpanm");
+        if local.exists() {
+            local
+        } else {
+            PathBuf::from("cpanm")
+        }
+    }
+
+    /// Path as seen by the package manager, for use in environment variables
+    fn env_path(&self, host: &Path) -> String {
+        self.route
+            .as_ref()
+            .and_then(|route| route.container_path(host))
+            .unwrap_or_else(|| host.to_path_buf())
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    async fn run(&self, cmd: &ToolCommand) -> Result<Output> {
+        cmd.output_via(self.route.as_ref()).await
+    }
+
+    async fn run_checked(&self, cmd: &ToolCommand) -> Result<Output> {
+        let output = self.run(cmd).await?;
+        if output.status.success() {
+            Ok(output)
+        } else {
+            Err(command_failed(&cmd.program_name(), &output))
+        }
+    }
+
+    async fn run_json(&self, cmd: ToolCommand) -> Result<Value> {
+        stdout_json(&self.run_checked(&cmd).await?)
+    }
+}
+
+fn pip_command(pip: &Path, args: &[&str]) -> ToolCommand {
+    let mut cmd = ToolCommand::new(pip);
+    cmd.args(args).arg("--disable-pip-version-check");
+    cmd
+}
+
+fn command_failed(program: &str, output: &Output) -> Error {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = stderr
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or("no output");
+    Error::command(format!(
+        "{} exited with {}: {}",
+        program, output.status, message
+    ))
+}
+
+fn stdout_json(output: &Output) -> Result<Value> {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(&stdout).map_err(|e| {
+        Error::Parse(format!(
+            "Unexpected package manager output ({}): {}",
+            e,
+            stdout.lines().next().unwrap_or_default()
+        ))
+    })
+}
+
+/// Packages whose version changed or that were newly installed
+fn diff_versions(
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, String>,
+) -> Vec<PackageChange> {
+    after
+        .iter()
+        .filter(|(name, version)| before.get(*name) != Some(version))
+        .map(|(name, version)| {
+            PackageChange::new(name, before.get(name).map(String::as_str), Some(version))
+        })
+        .collect()
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(Value::as_str)
+}
+
+fn parse_pip_list(json: &Value) -> BTreeMap<String, String> {
+    json.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|p| {
+            Some((
+                str_field(p, "name")?.into(),
+                str_field(p, "version")?.into(),
+            ))
+        })
+        .collect()
+}
+
+fn parse_pip_outdated(json: &Value) -> Vec<PackageChange> {
+    json.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|p| {
+            Some(PackageChange::new(
+                str_field(p, "name")?,
+                str_field(p, "version"),
+                str_field(p, "latest_version"),
+            ))
+        })
+        .collect()
+}
+
+fn parse_npm_list(json: &Value) -> BTreeMap<String, String> {
+    json.get("dependencies")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, dep)| Some((name.clone(), str_field(dep, "version")?.into())))
+        .collect()
+}
+
+fn parse_npm_outdated(json: &Value) -> Vec<PackageChange> {
+    json.as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, dep)| {
+            // `npm update` installs the highest version allowed by package.json
+            PackageChange::new(name, str_field(dep, "current"), str_field(dep, "wanted"))
+        })
+        .collect()
+}
+
+/// Parse `gem list` lines such as `nokogiri (1.15.4, default: 1.13.0)`
+fn parse_gem_list(output: &str) -> BTreeMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, versions) = line.trim().split_once(" (")?;
+            let latest = versions.trim_end_matches(')').split(", ").next()?;
+            Some((
+                name.to_string(),
+                latest.trim_start_matches("default: ").to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Parse `gem outdated` lines such as `rack (2.2.7 < 3.0.8)`
+fn parse_gem_outdated(output: &str) -> Vec<PackageChange> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, versions) = line.trim().split_once(" (")?;
+            let (current, latest) = versions.trim_end_matches(')').split_once(" < ")?;
+            Some(PackageChange::new(name, Some(current), Some(latest)))
+        })
+        .collect()
+}
+
+/// Split a CPAN distribution name such as `Mojolicious-9.35` into name and version
+fn split_dist(dist: &str) -> (&str, Option<&str>) {
+    match dist.rsplit_once('-') {
+        Some((name, version)) if version.starts_with(|c: char| c.is_ascii_digit()) => {
+            (name, Some(version))
+        }
+        _ => (dist, None),
+    }
+}
+
+/// Parse `cpan-outdated` distribution paths such as `S/SR/SRI/Mojolicious-9.35.tar.gz`
+fn parse_cpan_outdated(output: &str) -> Vec<PackageChange> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let file = line.trim().rsplit('/').next()?;
+            let dist = [".tar.gz", ".tgz", ".zip", ".tar.bz2"]
+                .iter()
+                .find_map(|ext| file.strip_suffix(ext))?;
+            let (name, version) = split_dist(dist);
+            Some(PackageChange::new(name, None, version))
+        })
+        .collect()
+}
+
+/// Parse cpanm lines such as `Successfully installed Mojolicious-9.35 (upgraded from 9.34)`
+fn parse_cpanm_installed(output: &str) -> Vec<PackageChange> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("Successfully installed ")?;
+            let (dist, previous) = match rest.split_once(" (upgraded from ") {
+                Some((dist, previous)) => (dist, Some(previous.trim_end_matches(')'))),
+                None => (rest, None),
+            };
+            let (name, version) = split_dist(dist);
+            Some(PackageChange::new(name, previous, version))
+        })
+        .collect()
+}
+
+fn composer_versions(json: &Value) -> BTreeMap<String, String> {
+    parse_composer_packages(json, "version")
+        .into_iter()
+        .filter_map(|c| Some((c.name, c.after?)))
+        .collect()
+}
+
+/// Parse `composer show|outdated --format=json`, taking the target version from `field`
+fn parse_composer_packages(json: &Value, field: &str) -> Vec<PackageChange> {
+    json.get("installed")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|p| {
+            Some(PackageChange::new(
+                str_field(p, "name")?,
+                str_field(p, "version"),
+                str_field(p, field),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_package_manager_output() {
+        let pip = parse_pip_outdated(&json!([
+            {"name": "requests", "version": "2.28.0", "latest_version": "2.31.0", "latest_filetype": "wheel"}
+        ]));
+        assert_eq!(
+            pip,
+            vec![PackageChange::new(
+                "requests",
+                Some("2.28.0"),
+                Some("2.31.0")
+            )]
+        );
+
+        let npm = parse_npm_outdated(&json!({
+            "axios": {"current": "1.4.0", "wanted": "1.6.2", "latest": "1.6.2"}
+        }));
+        assert_eq!(npm[0].after.as_deref(), Some("1.6.2"));
+
+        let gems = parse_gem_outdated("rack (2.2.7 < 3.0.8)\nnot a gem line\n");
+        assert_eq!(
+            gems,
+            vec![PackageChange::new("rack", Some("2.2.7"), Some("3.0.8"))]
+        );
+        let installed = parse_gem_list("json (2.7.1, default: 2.6.3)\nrake (13.1.0)\n");
+        assert_eq!(installed["json"], "2.7.1");
+
+        let cpanm = parse_cpanm_installed(
+            "--> Working on Mojolicious\nSuccessfully installed Mojolicious-9.35 (upgraded from 9.34)\nSuccessfully installed Net-DNS-1.40\n",
+        );
+        assert_eq!(
+            cpanm,
+            vec![
+                PackageChange::new("Mojolicious", Some("9.34"), Some("9.35")),
+                PackageChange::new("Net-DNS", None, Some("1.40")),
+            ]
+        );
+        assert_eq!(
+            parse_cpan_outdated("S/SR/SRI/Mojolicious-9.35.tar.gz\n")[0].name,
+            "Mojolicious"
+        );
+
+        let composer = parse_composer_packages(
+            &json!({"installed": [{"name": "guzzlehttp/guzzle", "version": "7.5.0", "latest": "7.8.1"}]}),
+            "latest",
+        );
+        assert_eq!(composer[0].after.as_deref(), Some("7.8.1"));
+    }
+
+    #[test]
+    fn test_diff_versions() {
+        let before = BTreeMap::from([
+            ("a".to_string(), "1.0".to_string()),
+            ("b".to_string(), "2.0".to_string()),
+        ]);
+        let after = BTreeMap::from([
+            ("a".to_string(), "1.1".to_string()),
+            ("b".to_string(), "2.0".to_string()),
+            ("c".to_string(), "0.1".to_string()),
+        ]);
+        assert_eq!(
+            diff_versions(&before, &after),
+            vec![
+                PackageChange::new("a", Some("1.0"), Some("1.1")),
+                PackageChange::new("c", None, Some("0.1")),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_language_fails_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = Sandbox::with_config(SandboxConfig {
+            root_dir: dir.path().to_path_buf(),
+            ..SandboxConfig::default()
+        });
+        let updates = PackageUpdater::new(&sandbox)
+            .dry_run(true)
+            .update_all(&["go", "py"])
+            .await;
+
+        assert_eq!(updates.len(), 2);
+        assert!(updates[0].result.is_err());
+        assert_eq!(updates[1].language, "python");
+    }
+}
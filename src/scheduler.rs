@@ -4,10 +4,16 @@ use crate::config::Config;
 use crate::core::ScanJob;
 use crate::error::{Error, Result};
 use crate::template::Template;
+use crate::types::{Severity, TemplateMetadata};
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 use std::sync::Arc;
 
+pub mod queue;
+
+pub use queue::{JobControl, JobStatus, ScanJobHandle, ScanQueue};
+
 /// Scheduler for managing template execution order
 #[derive(Debug)]
 pub struct Scheduler {
@@ -57,6 +63,252 @@ impl Scheduler {
     }
 }
 
+/// Seed for a scan's randomized orders: `seed` when given, otherwise a random one
+///
+/// `None` when nothing is randomized. The seed is logged so the run can be
+/// replayed with `--seed`.
+pub fn scan_seed(randomized: bool, seed: Option<u64>) -> Option<u64> {
+    if !randomized {
+        if seed.is_some() {
+            tracing::warn!(
+                "--seed has no effect without --randomize-targets or --randomize-templates"
+            );
+        }
+        return None;
+    }
+    let seed = seed.unwrap_or_else(|| fastrand::u64(..));
+    tracing::info!(
+        "Randomizing scan order with seed {} (replay with --seed {})",
+        seed,
+        seed
+    );
+    Some(seed)
+}
+
+/// Shuffle `items` with a `fastrand` generator seeded from `seed`
+///
+/// The same seed and length always give the same order, across platforms.
+pub fn shuffle_seeded<T>(items: &mut [T], seed: u64) {
+    fastrand::Rng::with_seed(seed).shuffle(items);
+}
+
+/// Order of `template_count` template indexes for the target at `target_idx`
+///
+/// In job order without a seed; otherwise shuffled from `seed` and the target's
+/// index, so each target gets its own but reproducible order.
+pub fn template_order(template_count: usize, seed: Option<u64>, target_idx: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..template_count).collect();
+    if let Some(seed) = seed {
+        shuffle_seeded(&mut order, seed.wrapping_add(target_idx as u64));
+    }
+    order
+}
+
+/// Order in which a target's templates start (`--priority`)
+///
+/// Templates matching an earlier boost term start first; within a term, and
+/// among templates matching none, higher severity starts first. `none` keeps
+/// load order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TemplatePriority {
+    /// Sort by severity, highest first, after the boosts
+    by_severity: bool,
+    /// Boosted template sets, highest priority first
+    boosts: Vec<PriorityTerm>,
+}
+
+/// One boosted set of a [`TemplatePriority`], e.g. `tags:rce,xss`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PriorityTerm {
+    /// Templates with any of these tags
+    Tags(Vec<String>),
+    /// Templates of any of these severities
+    Severity(Vec<Severity>),
+    /// Templates with any of these IDs
+    Ids(Vec<String>),
+}
+
+impl PriorityTerm {
+    fn matches(&self, metadata: &TemplateMetadata) -> bool {
+        match self {
+            Self::Tags(tags) => metadata
+                .tags
+                .iter()
+                .any(|tag| tags.iter().any(|t| t.eq_ignore_ascii_case(tag))),
+            Self::Severity(severities) => severities.contains(&metadata.severity),
+            Self::Ids(ids) => ids.contains(&metadata.id),
+        }
+    }
+
+    fn push(&mut self, value: &str) -> Result<()> {
+        match self {
+            Self::Tags(values) | Self::Ids(values) => values.push(value.to_string()),
+            Self::Severity(values) => values.push(parse_severity(value)?),
+        }
+        Ok(())
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            Self::Tags(_) => "tags",
+            Self::Severity(_) => "severity",
+            Self::Ids(_) => "id",
+        }
+    }
+
+    fn values(&self) -> Vec<String> {
+        match self {
+            Self::Tags(values) | Self::Ids(values) => values.clone(),
+            Self::Severity(values) => values.iter().map(Severity::to_string).collect(),
+        }
+    }
+}
+
+impl Default for TemplatePriority {
+    fn default() -> Self {
+        Self {
+            by_severity: true,
+            boosts: Vec::new(),
+        }
+    }
+}
+
+impl TemplatePriority {
+    /// Load order, as with `--priority none`
+    pub fn none() -> Self {
+        Self {
+            by_severity: false,
+            boosts: Vec::new(),
+        }
+    }
+
+    /// Whether templates keep their load order
+    pub fn is_none(&self) -> bool {
+        !self.by_severity && self.boosts.is_empty()
+    }
+
+    /// Sort key of a template; lower keys start first
+    fn rank(&self, metadata: &TemplateMetadata) -> (usize, u8) {
+        let boost = self
+            .boosts
+            .iter()
+            .position(|term| term.matches(metadata))
+            .unwrap_or(self.boosts.len());
+        let severity = if self.by_severity {
+            Severity::Critical.score() - metadata.severity.score()
+        } else {
+            0
+        };
+        (boost, severity)
+    }
+
+    /// Stable-sort `order`, a list of indexes into `templates`, by priority
+    pub fn sort(&self, order: &mut [usize], templates: &[Arc<dyn Template>]) {
+        if self.is_none() {
+            return;
+        }
+        order.sort_by_key(|&idx| self.rank(templates[idx].metadata()));
+    }
+}
+
+impl std::str::FromStr for TemplatePriority {
+    type Err = Error;
+
+    /// Parse `none`, `severity`, or comma-separated `key:value` boosts
+    ///
+    /// A value without a key extends the previous term, so
+    /// `tags:rce,sqli,severity:critical` boosts templates tagged `rce` or
+    /// `sqli`, then critical ones. Keys are `tags` (`tag`), `severity` and `id`.
+    fn from_str(expression: &str) -> Result<Self> {
+        let expression = expression.trim();
+        if expression.eq_ignore_ascii_case("none") {
+            return Ok(Self::none());
+        }
+        let mut priority = Self::default();
+        if expression.is_empty() || expression.eq_ignore_ascii_case("severity") {
+            return Ok(priority);
+        }
+
+        for part in expression.split(',').map(str::trim) {
+            let (key, value) = match part.split_once(':') {
+                Some((key, value)) => (Some(key.trim()), value.trim()),
+                None => (None, part),
+            };
+            if value.is_empty() {
+                return Err(Error::config(format!(
+                    "Empty value in priority expression '{}'",
+                    expression
+                )));
+            }
+            match key.map(str::to_ascii_lowercase).as_deref() {
+                Some("tags") | Some("tag") => priority.boosts.push(PriorityTerm::Tags(Vec::new())),
+                Some("severity") => priority.boosts.push(PriorityTerm::Severity(Vec::new())),
+                Some("id") => priority.boosts.push(PriorityTerm::Ids(Vec::new())),
+                Some(other) => {
+                    return Err(Error::config(format!(
+                        "Unknown priority key '{}' (expected tags, severity or id)",
+                        other
+                    )))
+                }
+                None => {}
+            }
+            match priority.boosts.last_mut() {
+                Some(term) => term.push(value)?,
+                None => {
+                    return Err(Error::config(format!(
+                        "Priority value '{}' needs a key, e.g. tags:{}",
+                        value, value
+                    )))
+                }
+            }
+        }
+        Ok(priority)
+    }
+}
+
+fn parse_severity(value: &str) -> Result<Severity> {
+    match value.to_ascii_lowercase().as_str() {
+        "critical" => Ok(Severity::Critical),
+        "high" => Ok(Severity::High),
+        "medium" => Ok(Severity::Medium),
+        "low" => Ok(Severity::Low),
+        "info" => Ok(Severity::Info),
+        _ => Err(Error::config(format!("Unknown severity '{}'", value))),
+    }
+}
+
+impl std::fmt::Display for TemplatePriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_none() {
+            return write!(f, "none");
+        }
+        if self.boosts.is_empty() {
+            return write!(f, "severity");
+        }
+        let terms: Vec<String> = self
+            .boosts
+            .iter()
+            .map(|term| format!("{}:{}", term.key(), term.values().join(",")))
+            .collect();
+        write!(f, "{}", terms.join(","))
+    }
+}
+
+impl TryFrom<String> for TemplatePriority {
+    type Error = Error;
+
+    fn try_from(expression: String) -> Result<Self> {
+        expression.parse()
+    }
+}
+
+impl From<TemplatePriority> for String {
+    fn from(priority: TemplatePriority) -> Self {
+        priority.to_string()
+    }
+}
+
 /// Template with priority information
 #[derive(Debug, Clone)]
 pub struct PrioritizedTemplate {
@@ -196,7 +448,7 @@ impl ResourceManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{AuthorInfo, Severity, TemplateLanguage, TemplateMetadata};
+    use crate::types::{AuthorInfo, TemplateLanguage};
     use async_trait::async_trait;
     use chrono::Utc;
     use std::path::PathBuf;
@@ -234,6 +486,7 @@ mod tests {
                 description: "Test template".to_string(),
                 cve_ids: Vec::new(),
                 cwe_ids: Vec::new(),
+                references: Vec::new(),
                 cvss_score: None,
                 tags: Vec::new(),
                 language: TemplateLanguage::Yaml,
@@ -242,6 +495,12 @@ mod tests {
                 updated: Utc::now(),
                 version: "1.0".to_string(),
                 confidence: None,
+                rate_limit: None,
+                max_requests: None,
+                deprecated: false,
+                superseded_by: None,
+                created_date: None,
+                depends_on: Vec::new(),
             },
         }
     }
@@ -256,6 +515,86 @@ mod tests {
         assert!(high > medium);
     }
 
+    #[test]
+    fn test_template_priority() {
+        let tagged = |id: &str, severity, tags: &[&str]| -> Arc<dyn Template> {
+            let mut template = create_mock_template(id, severity);
+            template.metadata.tags = tags.iter().map(|t| t.to_string()).collect();
+            Arc::new(template)
+        };
+        let templates = vec![
+            tagged("fingerprint", Severity::Info, &[]),
+            tagged("xss", Severity::Medium, &["xss"]),
+            tagged("log4shell", Severity::Critical, &["rce"]),
+            tagged("exposed-git", Severity::High, &[]),
+            tagged("cmd-injection", Severity::High, &["RCE"]),
+            tagged("banner", Severity::Info, &["tech"]),
+        ];
+        let ids = |priority: &str| {
+            let priority: TemplatePriority = priority.parse().unwrap();
+            let mut order: Vec<usize> = (0..templates.len()).collect();
+            priority.sort(&mut order, &templates);
+            order
+                .into_iter()
+                .map(|i| templates[i].id())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ids("severity"),
+            [
+                "log4shell",
+                "exposed-git",
+                "cmd-injection",
+                "xss",
+                "fingerprint",
+                "banner"
+            ]
+        );
+        assert_eq!(
+            ids("none"),
+            [
+                "fingerprint",
+                "xss",
+                "log4shell",
+                "exposed-git",
+                "cmd-injection",
+                "banner"
+            ]
+        );
+        assert_eq!(
+            ids("tags:tech,xss,severity:high"),
+            [
+                "xss",
+                "banner",
+                "exposed-git",
+                "cmd-injection",
+                "log4shell",
+                "fingerprint"
+            ]
+        );
+        assert_eq!(
+            ids("tags:rce,id:fingerprint"),
+            [
+                "log4shell",
+                "cmd-injection",
+                "fingerprint",
+                "exposed-git",
+                "xss",
+                "banner"
+            ]
+        );
+
+        let priority: TemplatePriority = "tags:rce,sqli,severity:critical".parse().unwrap();
+        assert_eq!(priority.to_string(), "tags:rce,sqli,severity:critical");
+        assert_eq!(TemplatePriority::default().to_string(), "severity");
+        assert!("none".parse::<TemplatePriority>().unwrap().is_none());
+        assert!("rce".parse::<TemplatePriority>().is_err());
+        assert!("owner:bob".parse::<TemplatePriority>().is_err());
+        assert!("severity:urgent".parse::<TemplatePriority>().is_err());
+        assert!("tags:".parse::<TemplatePriority>().is_err());
+    }
+
     #[test]
     fn test_resource_manager() {
         let config = Config::default();
@@ -268,4 +607,31 @@ mod tests {
         manager.release(100 * 1024 * 1024);
         assert_eq!(manager.current_memory_mb(), 0);
     }
+
+    #[test]
+    fn test_seeded_shuffles_repeat() {
+        let shuffled = |seed| {
+            let mut items: Vec<u32> = (0..50).collect();
+            shuffle_seeded(&mut items, seed);
+            items
+        };
+        assert_eq!(shuffled(42), shuffled(42));
+        assert_ne!(shuffled(42), shuffled(43));
+        let mut sorted = shuffled(42);
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..50).collect::<Vec<u32>>());
+
+        assert_eq!(template_order(4, None, 3), vec![0, 1, 2, 3]);
+        assert_eq!(
+            template_order(20, Some(7), 1),
+            template_order(20, Some(7), 1)
+        );
+        assert_ne!(
+            template_order(20, Some(7), 0),
+            template_order(20, Some(7), 1)
+        );
+        assert_eq!(scan_seed(true, Some(9)), Some(9));
+        assert!(scan_seed(true, None).is_some());
+        assert_eq!(scan_seed(false, Some(9)), None);
+    }
 }
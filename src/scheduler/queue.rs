@@ -0,0 +1,614 @@
+//! Priority queue of scan jobs for the API server
+//!
+//! [`ScanQueue::submit`] queues a job and returns a [`ScanJobHandle`] at
+//! once. At most `workers` jobs run at a time, highest `priority` first
+//! (0 = low, 255 = urgent) and in submission order within a priority.
+//!
+//! When every worker is busy, a job outranking the lowest-priority running
+//! job preempts it: the running job stops starting templates (those already
+//! running finish, so no request is cut off) and goes back in the queue,
+//! resuming where it left off once a worker is free again.
+
+use crate::core::{self, ScanJob};
+use crate::error::Result;
+use crate::executor::Executor;
+use crate::types::ScanResults;
+use parking_lot::Mutex;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// How long [`ScanJobHandle::cancel`] waits for a running job to stop
+pub const CANCEL_GRACE: Duration = Duration::from_secs(5);
+
+/// Pause and cancellation signal of a job, checked between templates
+#[derive(Debug, Clone)]
+pub struct JobControl(Arc<watch::Sender<Signal>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Signal {
+    Run,
+    Pause,
+    Cancel,
+}
+
+impl Default for JobControl {
+    fn default() -> Self {
+        Self(Arc::new(watch::Sender::new(Signal::Run)))
+    }
+}
+
+impl JobControl {
+    /// Hold the job's next template until [`resume`](Self::resume)
+    pub fn pause(&self) {
+        self.signal(Signal::Pause);
+    }
+
+    /// Let a paused job start templates again
+    pub fn resume(&self) {
+        self.signal(Signal::Run);
+    }
+
+    /// Start no more templates; a paused job is released to wind down
+    pub fn cancel(&self) {
+        self.0.send_replace(Signal::Cancel);
+    }
+
+    /// Whether the job was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        *self.0.borrow() == Signal::Cancel
+    }
+
+    /// Wait while the job is paused; `false` once it is cancelled
+    pub async fn checkpoint(&self) -> bool {
+        let mut signal = self.0.subscribe();
+        let run = match signal.wait_for(|s| *s != Signal::Pause).await {
+            Ok(s) => *s == Signal::Run,
+            Err(_) => true,
+        };
+        run
+    }
+
+    fn signal(&self, signal: Signal) {
+        // Cancellation is final
+        self.0.send_if_modified(|current| {
+            let changed = *current != Signal::Cancel && *current != signal;
+            if changed {
+                *current = signal;
+            }
+            changed
+        });
+    }
+}
+
+/// Where a queued job is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Waiting for a worker
+    Queued,
+    /// Running templates
+    Running,
+    /// Paused for a higher-priority job; back in the queue
+    Preempted,
+    /// Finished; results are available
+    Completed,
+    /// Stopped with an error
+    Failed(String),
+    /// Cancelled before it finished
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job will not run again
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed(_) | JobStatus::Cancelled
+        )
+    }
+}
+
+/// State of one submitted job, shared by the queue and its handles
+#[derive(Debug)]
+struct Submitted {
+    id: Uuid,
+    priority: u8,
+    seq: u64,
+    control: JobControl,
+    status: watch::Sender<JobStatus>,
+    results: Mutex<Option<ScanResults>>,
+    task: Mutex<Option<tokio::task::AbortHandle>>,
+}
+
+/// A job waiting for a worker, or a preempted one waiting to resume
+struct Pending {
+    submitted: Arc<Submitted>,
+    /// `None` once the job has started
+    job: Option<ScanJob>,
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Higher priority first, then earlier submission
+        self.submitted
+            .priority
+            .cmp(&other.submitted.priority)
+            .then_with(|| other.submitted.seq.cmp(&self.submitted.seq))
+    }
+}
+
+#[derive(Default)]
+struct State {
+    pending: BinaryHeap<Pending>,
+    /// Jobs holding a worker
+    running: Vec<Arc<Submitted>>,
+    next_seq: u64,
+}
+
+struct Inner {
+    executor: Arc<Executor>,
+    workers: usize,
+    state: Mutex<State>,
+}
+
+/// Priority queue running scan jobs on a fixed number of workers
+#[derive(Clone)]
+pub struct ScanQueue {
+    inner: Arc<Inner>,
+}
+
+impl std::fmt::Debug for ScanQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.inner.state.lock();
+        f.debug_struct("ScanQueue")
+            .field("workers", &self.inner.workers)
+            .field("pending", &state.pending.len())
+            .field("running", &state.running.len())
+            .finish()
+    }
+}
+
+impl ScanQueue {
+    /// Queue running up to `workers` jobs at once on `executor`
+    pub fn new(executor: Arc<Executor>, workers: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                executor,
+                workers: workers.max(1),
+                state: Mutex::new(State::default()),
+            }),
+        }
+    }
+
+    /// Queue `job`, returning a handle to follow it
+    ///
+    /// Must be called within a Tokio runtime.
+    pub fn submit(&self, mut job: ScanJob, priority: u8) -> ScanJobHandle {
+        let control = JobControl::default();
+        job.control = control.clone();
+
+        let mut state = self.inner.state.lock();
+        let submitted = Arc::new(Submitted {
+            id: job.id,
+            priority,
+            seq: state.next_seq,
+            control,
+            status: watch::Sender::new(JobStatus::Queued),
+            results: Mutex::new(None),
+            task: Mutex::new(None),
+        });
+        state.next_seq += 1;
+        tracing::debug!("Queued scan job {} at priority {}", job.id, priority);
+        state.pending.push(Pending {
+            submitted: submitted.clone(),
+            job: Some(job),
+        });
+        self.inner.dispatch(&mut state);
+
+        ScanJobHandle {
+            submitted,
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Number of workers
+    pub fn workers(&self) -> usize {
+        self.inner.workers
+    }
+
+    /// Jobs waiting for a worker, including preempted ones
+    pub fn pending_count(&self) -> usize {
+        self.inner.state.lock().pending.len()
+    }
+
+    /// Jobs holding a worker
+    pub fn running_count(&self) -> usize {
+        self.inner.state.lock().running.len()
+    }
+}
+
+impl Inner {
+    /// Start or resume pending jobs while workers are free, preempting
+    /// lower-priority jobs for higher-priority ones
+    fn dispatch(self: &Arc<Self>, state: &mut State) {
+        while let Some(top) = state.pending.peek() {
+            if top.submitted.control.is_cancelled() {
+                state.pending.pop();
+                continue;
+            }
+
+            if state.running.len() < self.workers {
+                let Some(pending) = state.pending.pop() else {
+                    break;
+                };
+                self.start(state, pending);
+                continue;
+            }
+
+            // Lowest priority, most recently submitted first
+            let victim = state
+                .running
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.priority.cmp(&b.priority).then(b.seq.cmp(&a.seq)))
+                .filter(|(_, job)| job.priority < top.submitted.priority)
+                .map(|(index, _)| index);
+            let Some(index) = victim else {
+                break;
+            };
+            let preempted = state.running.swap_remove(index);
+            tracing::info!(
+                "Preempting scan job {} (priority {}) for a priority {} job",
+                preempted.id,
+                preempted.priority,
+                top.submitted.priority
+            );
+            preempted.control.pause();
+            preempted.status.send_replace(JobStatus::Preempted);
+            state.pending.push(Pending {
+                submitted: preempted,
+                job: None,
+            });
+        }
+    }
+
+    fn start(self: &Arc<Self>, state: &mut State, pending: Pending) {
+        let submitted = pending.submitted;
+        state.running.push(submitted.clone());
+        submitted.status.send_replace(JobStatus::Running);
+
+        let Some(job) = pending.job else {
+            tracing::info!("Resuming scan job {}", submitted.id);
+            submitted.control.resume();
+            return;
+        };
+
+        let inner = self.clone();
+        let job_state = submitted.clone();
+        let task = tokio::spawn(async move {
+            let results = inner.run(job).await;
+            inner.finish(&job_state, results);
+        });
+        *submitted.task.lock() = Some(task.abort_handle());
+    }
+
+    async fn run(&self, mut job: ScanJob) -> Result<ScanResults> {
+        let results = ScanResults::new(job.id);
+        let workspace = job.workspace();
+        let executed = self.executor.execute_with_usage(&job).await;
+        job.release_workspace(&workspace);
+        let (findings, usage) = executed?;
+        Ok(core::finish_results(
+            results,
+            &job,
+            findings,
+            usage,
+            self.executor.network_client().requests(),
+            self.executor
+                .network_client()
+                .probe_recorder()
+                .map(Arc::as_ref),
+        ))
+    }
+
+    /// Record how a job ended and hand its worker to the next job
+    fn finish(self: &Arc<Self>, submitted: &Arc<Submitted>, results: Result<ScanResults>) {
+        let status = match results {
+            _ if submitted.control.is_cancelled() => JobStatus::Cancelled,
+            Ok(results) => {
+                *submitted.results.lock() = Some(results);
+                JobStatus::Completed
+            }
+            Err(e) => JobStatus::Failed(e.to_string()),
+        };
+        tracing::debug!("Scan job {} finished: {:?}", submitted.id, status);
+
+        let mut state = self.state.lock();
+        state.running.retain(|job| !Arc::ptr_eq(job, submitted));
+        submitted.status.send_replace(status);
+        self.dispatch(&mut state);
+    }
+}
+
+/// Handle to a job submitted to a [`ScanQueue`]
+#[derive(Clone)]
+pub struct ScanJobHandle {
+    submitted: Arc<Submitted>,
+    inner: Arc<Inner>,
+}
+
+impl std::fmt::Debug for ScanJobHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScanJobHandle")
+            .field("id", &self.submitted.id)
+            .field("priority", &self.submitted.priority)
+            .field("status", &*self.submitted.status.borrow())
+            .finish()
+    }
+}
+
+impl ScanJobHandle {
+    /// ID of the job
+    pub fn id(&self) -> Uuid {
+        self.submitted.id
+    }
+
+    /// Priority the job was submitted with
+    pub fn priority(&self) -> u8 {
+        self.submitted.priority
+    }
+
+    /// Current status
+    pub fn status(&self) -> JobStatus {
+        self.submitted.status.borrow().clone()
+    }
+
+    /// Results of a completed job, taken out of the handle
+    pub fn take_results(&self) -> Option<ScanResults> {
+        self.submitted.results.lock().take()
+    }
+
+    /// Wait until the job has finished and return its final status
+    pub async fn wait(&self) -> JobStatus {
+        let mut status = self.submitted.status.subscribe();
+        let finished = status.wait_for(JobStatus::is_finished).await;
+        match finished {
+            Ok(status) => status.clone(),
+            Err(_) => self.status(),
+        }
+    }
+
+    /// Cancel the job, waiting up to [`CANCEL_GRACE`] for it to stop
+    ///
+    /// A queued job is dropped at once. A running job starts no more templates
+    /// and is aborted if the templates it is running outlast the grace period.
+    /// Returns `false` only when the job had to be aborted.
+    pub async fn cancel(&self) -> bool {
+        {
+            let mut state = self.inner.state.lock();
+            if self.status().is_finished() {
+                return true;
+            }
+            self.submitted.control.cancel();
+            state
+                .pending
+                .retain(|pending| !Arc::ptr_eq(&pending.submitted, &self.submitted));
+            if self.status() == JobStatus::Queued {
+                self.submitted.status.send_replace(JobStatus::Cancelled);
+                return true;
+            }
+        }
+
+        let mut status = self.submitted.status.subscribe();
+        let stopped = tokio::time::timeout(CANCEL_GRACE, status.wait_for(JobStatus::is_finished));
+        if stopped.await.is_ok() {
+            return true;
+        }
+
+        tracing::warn!(
+            "Scan job {} did not stop within {:?}; aborting it",
+            self.submitted.id,
+            CANCEL_GRACE
+        );
+        if let Some(task) = self.submitted.task.lock().take() {
+            task.abort();
+        }
+        let mut state = self.inner.state.lock();
+        state
+            .running
+            .retain(|job| !Arc::ptr_eq(job, &self.submitted));
+        self.submitted.status.send_replace(JobStatus::Cancelled);
+        self.inner.dispatch(&mut state);
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::template::Template;
+    use crate::types::{
+        AuthorInfo, Context, Finding, Protocol, Severity, Target, TemplateLanguage,
+        TemplateMetadata,
+    };
+    use async_trait::async_trait;
+
+    /// Appends `<job>:<template>` to a shared log when it runs
+    struct LoggedTemplate {
+        metadata: TemplateMetadata,
+        log: Arc<Mutex<Vec<String>>>,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl Template for LoggedTemplate {
+        fn metadata(&self) -> &TemplateMetadata {
+            &self.metadata
+        }
+
+        async fn execute(&self, target: &Target, _context: &Context) -> Result<Vec<Finding>> {
+            self.log.lock().push(self.metadata.id.clone());
+            tokio::time::sleep(self.delay).await;
+            Ok(vec![Finding::new(
+                target.address.as_str(),
+                self.metadata.id.as_str(),
+                Severity::Info,
+                "ran",
+                "ran",
+            )])
+        }
+    }
+
+    fn job(
+        config: &Arc<Config>,
+        name: &str,
+        templates: usize,
+        delay: Duration,
+        log: &Arc<Mutex<Vec<String>>>,
+    ) -> ScanJob {
+        let templates = (0..templates)
+            .map(|i| {
+                let id = format!("{}:{}", name, i);
+                Box::new(LoggedTemplate {
+                    metadata: TemplateMetadata {
+                        id: id.clone(),
+                        name: id,
+                        author: AuthorInfo {
+                            name: "Test".to_string(),
+                            email: None,
+                            github: None,
+                        },
+                        severity: Severity::Info,
+                        description: String::new(),
+                        cve_ids: Vec::new(),
+                        cwe_ids: Vec::new(),
+                        references: Vec::new(),
+                        cvss_score: None,
+                        tags: Vec::new(),
+                        language: TemplateLanguage::Yaml,
+                        file_path: std::path::PathBuf::from("test.yaml"),
+                        created: chrono::Utc::now(),
+                        updated: chrono::Utc::now(),
+                        version: "1.0".to_string(),
+                        confidence: None,
+                        rate_limit: None,
+                        max_requests: None,
+                        deprecated: false,
+                        superseded_by: None,
+                        created_date: None,
+                        depends_on: Vec::new(),
+                    },
+                    log: log.clone(),
+                    delay,
+                }) as Box<dyn Template>
+            })
+            .collect();
+        let target = Target::new(format!("{}.test", name), Protocol::Https);
+        ScanJob::new(vec![target], templates, config.clone())
+    }
+
+    async fn queue(workers: usize) -> (ScanQueue, Arc<Config>) {
+        let mut config = Config::default();
+        // One template at a time, so boundaries are easy to observe
+        config.execution.parallel_targets = 1;
+        config.execution.parallel_templates = 1;
+        let config = Arc::new(config);
+        let executor = Arc::new(Executor::new(config.clone()).await.unwrap());
+        (ScanQueue::new(executor, workers), config)
+    }
+
+    async fn wait_until(mut done: impl FnMut() -> bool) {
+        for _ in 0..500 {
+            if done() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition not reached");
+    }
+
+    #[tokio::test]
+    async fn test_urgent_job_preempts_at_template_boundary() {
+        let (queue, config) = queue(1).await;
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let low = queue.submit(job(&config, "low", 6, Duration::from_millis(40), &log), 0);
+        wait_until(|| !log.lock().is_empty()).await;
+        let urgent = queue.submit(
+            job(&config, "urgent", 2, Duration::from_millis(5), &log),
+            255,
+        );
+
+        assert_eq!(urgent.wait().await, JobStatus::Completed);
+        assert!(!low.status().is_finished());
+        assert_eq!(low.wait().await, JobStatus::Completed);
+
+        // The low job was resumed, not restarted, and the urgent job ran in between
+        let log = log.lock().clone();
+        let position = |id: &str| log.iter().position(|l| l == id).unwrap();
+        assert_eq!(log.len(), 8);
+        assert!(position("urgent:1") < position("low:5"));
+        assert!(position("low:0") < position("urgent:0"));
+        assert_eq!(urgent.take_results().unwrap().findings.len(), 2);
+        assert_eq!(low.take_results().unwrap().findings.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_equal_priority_runs_in_submission_order() {
+        let (queue, config) = queue(1).await;
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let first = queue.submit(job(&config, "a", 2, Duration::from_millis(10), &log), 10);
+        let second = queue.submit(job(&config, "b", 1, Duration::ZERO, &log), 10);
+        let third = queue.submit(job(&config, "c", 1, Duration::ZERO, &log), 10);
+        assert_eq!(queue.pending_count(), 2);
+
+        for handle in [&first, &second, &third] {
+            assert_eq!(handle.wait().await, JobStatus::Completed);
+        }
+        assert_eq!(*log.lock(), ["a:0", "a:1", "b:0", "c:0"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_running_and_queued_jobs() {
+        let (queue, config) = queue(1).await;
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let running = queue.submit(job(&config, "run", 20, Duration::from_millis(20), &log), 5);
+        let queued = queue.submit(job(&config, "wait", 1, Duration::ZERO, &log), 1);
+        wait_until(|| !log.lock().is_empty()).await;
+
+        assert!(queued.cancel().await);
+        assert_eq!(queued.status(), JobStatus::Cancelled);
+
+        let started = std::time::Instant::now();
+        assert!(running.cancel().await);
+        assert!(started.elapsed() < CANCEL_GRACE);
+        assert_eq!(running.status(), JobStatus::Cancelled);
+        assert!(running.take_results().is_none());
+
+        let log = log.lock().clone();
+        assert!(log.len() < 20);
+        assert!(!log.iter().any(|id| id.starts_with("wait")));
+        assert_eq!(queue.running_count(), 0);
+        assert_eq!(queue.pending_count(), 0);
+    }
+}
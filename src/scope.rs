@@ -0,0 +1,770 @@
+//! Scope entries and importers for `--scope-from`
+//!
+//! Converts third-party exports into plain scope entries (`host`,
+//! `host:port`, `udp://host:port` or CIDR blocks) that go through the same
+//! expansion and deduplication as `--scope` values ([`ScopeEntries`]).
+//! `--scope -` reads entries from stdin instead, one per line.
+
+pub mod openapi;
+pub mod ports;
+pub mod targets;
+pub mod zone_transfer;
+
+pub use ports::{parse_port_entries, parse_ports};
+pub use targets::{expand_targets_for_ports, parse_target, ScopeEntries};
+
+use crate::error::{Error, Result};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+use tokio::sync::OnceCell;
+
+/// Public endpoint used to resolve ASN announced prefixes
+pub const RIPESTAT_ANNOUNCED_PREFIXES: &str =
+    "https://stat.ripe.net/data/announced-prefixes/data.json";
+
+/// A `--scope-from` source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScopeSource {
+    /// Shodan JSON export (`shodan:results.json`, optionally gzipped)
+    Shodan(PathBuf),
+    /// Autonomous system number (`asn:AS13335`)
+    Asn(u32),
+    /// CSV file, the column holding hosts and label columns
+    /// (`csv:hosts.csv?column=ip&labels=owner,env`)
+    Csv {
+        /// CSV file path
+        path: PathBuf,
+        /// Header name or zero-based index; first column when unset
+        column: Option<String>,
+        /// Columns whose values become target labels, keyed by column name
+        labels: Vec<String>,
+    },
+}
+
+/// A scope entry from an importer, with the labels its source attached
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedEntry {
+    /// Scope entry, expanded like a `--scope` value
+    pub entry: String,
+    /// Labels for every target the entry expands to
+    pub labels: HashMap<String, String>,
+}
+
+impl ImportedEntry {
+    /// Entry without labels
+    pub fn new(entry: impl Into<String>) -> Self {
+        Self {
+            entry: entry.into(),
+            labels: HashMap::new(),
+        }
+    }
+}
+
+impl ScopeSource {
+    /// Parse a `kind:value` source specification
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (kind, value) = spec.split_once(':').ok_or_else(|| {
+            Error::config(format!(
+                "Invalid --scope-from '{}': expected shodan:<file>, asn:<ASN> or csv:<file>",
+                spec
+            ))
+        })?;
+
+        match kind.to_lowercase().as_str() {
+            "shodan" => Ok(Self::Shodan(PathBuf::from(value))),
+            "asn" => {
+                let digits = value
+                    .strip_prefix("AS")
+                    .or_else(|| value.strip_prefix("as"))
+                    .unwrap_or(value);
+                digits
+                    .parse()
+                    .map(Self::Asn)
+                    .map_err(|_| Error::config(format!("Invalid ASN '{}'", value)))
+            }
+            "csv" => {
+                let (path, query) = value.split_once('?').unwrap_or((value, ""));
+                let mut column = None;
+                let mut labels = Vec::new();
+                for pair in query.split('&').filter(|p| !p.is_empty()) {
+                    match pair.split_once('=') {
+                        Some(("column", name)) if !name.is_empty() => {
+                            column = Some(name.to_string())
+                        }
+                        Some(("labels", names)) if !names.is_empty() => labels.extend(
+                            names
+                                .split(',')
+                                .map(str::trim)
+                                .filter(|n| !n.is_empty())
+                                .map(str::to_string),
+                        ),
+                        _ => {
+                            return Err(Error::config(format!(
+                                "Invalid CSV option '{}' in '{}' (expected column=<name> or labels=<a,b>)",
+                                pair, spec
+                            )))
+                        }
+                    }
+                }
+                Ok(Self::Csv {
+                    path: PathBuf::from(path),
+                    column,
+                    labels,
+                })
+            }
+            other => Err(Error::config(format!(
+                "Unknown --scope-from importer '{}' (expected shodan, asn or csv)",
+                other
+            ))),
+        }
+    }
+
+    /// Whether importing the source fetches it from the network
+    pub fn fetches(&self) -> bool {
+        matches!(self, Self::Asn(_))
+    }
+}
+
+impl std::fmt::Display for ScopeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Shodan(path) => write!(f, "shodan:{}", path.display()),
+            Self::Asn(asn) => write!(f, "asn:AS{}", asn),
+            Self::Csv { path, .. } => write!(f, "csv:{}", path.display()),
+        }
+    }
+}
+
+/// Import every source, dropping entries already produced by an earlier one
+///
+/// A dropped duplicate's labels are added to the kept entry. With `offline`
+/// set, sources that need network access fail instead of reaching out.
+pub async fn import_all(sources: &[ScopeSource], offline: bool) -> Result<Vec<ImportedEntry>> {
+    let mut entries = Vec::new();
+    for source in sources {
+        let imported = match source {
+            ScopeSource::Shodan(path) => unlabeled(import_shodan(path)?),
+            ScopeSource::Csv {
+                path,
+                column,
+                labels,
+            } => import_csv(path, column.as_deref(), labels)?,
+            ScopeSource::Asn(asn) => {
+                if offline {
+                    return Err(Error::config(format!(
+                        "Cannot resolve AS{} prefixes with --offline",
+                        asn
+                    )));
+                }
+                unlabeled(import_asn(*asn, RIPESTAT_ANNOUNCED_PREFIXES).await?)
+            }
+        };
+        tracing::info!(
+            "Imported {} scope entries from {:?}",
+            imported.len(),
+            source
+        );
+        merge_entries(&mut entries, imported);
+    }
+    Ok(entries)
+}
+
+fn unlabeled(entries: Vec<String>) -> Vec<ImportedEntry> {
+    entries.into_iter().map(ImportedEntry::new).collect()
+}
+
+/// Append `imported` to `entries`, folding duplicates' labels into the first
+fn merge_entries(entries: &mut Vec<ImportedEntry>, imported: Vec<ImportedEntry>) {
+    for item in imported {
+        match entries.iter_mut().find(|e| e.entry == item.entry) {
+            Some(existing) => {
+                for (key, value) in item.labels {
+                    existing.labels.entry(key).or_insert(value);
+                }
+            }
+            None => entries.push(item),
+        }
+    }
+}
+
+/// Parse a `--label key=value` flag
+pub fn parse_label(label: &str) -> Result<(String, String)> {
+    match label.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(Error::config(format!(
+            "Invalid label '{}': expected key=value",
+            label
+        ))),
+    }
+}
+
+/// Trailing annotations on a scope file line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScopeAnnotations {
+    /// Template IDs to run on the entry, from `# templates:a,b`
+    pub templates: Option<Vec<String>>,
+    /// Headers sent with every request to the entry, from `# header:Name=Value` (repeatable)
+    pub headers: Vec<(String, String)>,
+}
+
+impl ScopeAnnotations {
+    /// Whether the line had no annotations
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_none() && self.headers.is_empty()
+    }
+}
+
+/// Split a scope file line into its entry and the annotations that follow it
+///
+/// Each `# ` starts one annotation: `templates:a,b` or `header:Name=Value`.
+/// Other trailing `# comments` are dropped. A `#` only starts a comment after
+/// whitespace, so URL fragments are kept.
+pub fn split_annotations(line: &str) -> Result<(&str, ScopeAnnotations)> {
+    let starts: Vec<usize> = line
+        .char_indices()
+        .filter(|&(i, c)| c == '#' && line[..i].ends_with(char::is_whitespace))
+        .map(|(i, _)| i)
+        .collect();
+    let Some(&first) = starts.first() else {
+        return Ok((line.trim(), ScopeAnnotations::default()));
+    };
+
+    let mut annotations = ScopeAnnotations::default();
+    let ends = starts[1..].iter().copied().chain([line.len()]);
+    for (start, end) in starts.iter().zip(ends) {
+        let annotation = line[start + 1..end].trim();
+        if let Some(ids) = annotation.strip_prefix("templates:") {
+            let ids: Vec<String> = ids
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .collect();
+            if !ids.is_empty() {
+                annotations
+                    .templates
+                    .get_or_insert_with(Vec::new)
+                    .extend(ids);
+            }
+        } else if let Some(header) = annotation.strip_prefix("header:") {
+            annotations.headers.push(parse_header_annotation(header)?);
+        }
+    }
+    Ok((line[..first].trim(), annotations))
+}
+
+/// `Name=Value` from a `# header:` annotation
+fn parse_header_annotation(header: &str) -> Result<(String, String)> {
+    let invalid = || {
+        Error::config(format!(
+            "Invalid header annotation '{}': expected header:Name=Value",
+            header
+        ))
+    };
+    let (name, value) = header.split_once('=').ok_or_else(invalid)?;
+    let (name, value) = (name.trim(), value.trim());
+    if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err()
+        || reqwest::header::HeaderValue::from_str(value).is_err()
+    {
+        return Err(invalid());
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Split a `@file`, `file://file` or `file:file` reference into the path and
+/// `true`; any other entry is returned as is with `false`
+pub(crate) fn strip_file_prefix(entry: &str) -> (&str, bool) {
+    if let Some(rest) = entry.strip_prefix('@') {
+        (rest, true)
+    } else if let Some(rest) = entry.strip_prefix("file://") {
+        (rest, true)
+    } else if let Some(rest) = entry.strip_prefix("file:") {
+        (rest, true)
+    } else {
+        (entry, false)
+    }
+}
+
+/// Whether a `--scope` value reads targets from stdin (`-` or `@-`)
+pub fn is_stdin_entry(entry: &str) -> bool {
+    matches!(entry.trim(), "-" | "@-")
+}
+
+/// Read newline-delimited scope entries, skipping blank lines and `#` comments
+pub async fn read_entries<R: AsyncBufRead + Unpin>(reader: R) -> Result<Vec<String>> {
+    let mut lines = reader.lines();
+    let mut entries = Vec::new();
+    while let Some(line) = lines.next_line().await? {
+        let line = line.trim();
+        if !line.is_empty() && !line.starts_with('#') {
+            entries.push(line.to_string());
+        }
+    }
+    Ok(entries)
+}
+
+/// Read scope entries piped on stdin (`cxg scan --scope -`)
+///
+/// Stdin is consumed once; later calls (e.g. several `--input` jobs using
+/// `-`) get the same entries. An interactive terminal is prompted first.
+pub async fn read_stdin() -> Result<Vec<String>> {
+    static STDIN_ENTRIES: OnceCell<Vec<String>> = OnceCell::const_new();
+    STDIN_ENTRIES
+        .get_or_try_init(|| async {
+            if std::io::stdin().is_terminal() {
+                let prompt = || eprintln!("Enter targets (one per line, Ctrl-D to finish):");
+                match crate::progress::get_progress() {
+                    Some(progress) => progress.suspend(prompt),
+                    None => prompt(),
+                }
+            }
+            let entries = read_entries(BufReader::new(tokio::io::stdin())).await?;
+            tracing::info!("Read {} scope entries from stdin", entries.len());
+            Ok(entries)
+        })
+        .await
+        .cloned()
+}
+
+#[derive(Debug, Deserialize)]
+struct ShodanBanner {
+    ip_str: Option<String>,
+    ip: Option<serde_json::Value>,
+    port: Option<u16>,
+    transport: Option<String>,
+}
+
+impl ShodanBanner {
+    fn host(&self) -> Option<String> {
+        if let Some(ip) = &self.ip_str {
+            return Some(ip.clone());
+        }
+        match self.ip.as_ref()? {
+            serde_json::Value::String(ip) => Some(ip.clone()),
+            serde_json::Value::Number(n) => {
+                let ip = u32::try_from(n.as_u64()?).ok()?;
+                Some(std::net::Ipv4Addr::from(ip).to_string())
+            }
+            _ => None,
+        }
+    }
+
+    fn entry(&self) -> Option<String> {
+        let host = self.host()?;
+        let host = if host.contains(':') {
+            format!("[{}]", host)
+        } else {
+            host
+        };
+        Some(match (self.port, self.transport.as_deref()) {
+            (Some(port), Some("udp")) => format!("udp://{}:{}", host, port),
+            (Some(port), _) => format!("{}:{}", host, port),
+            (None, _) => host,
+        })
+    }
+}
+
+/// Read a Shodan export: JSON lines (`shodan download`) or a JSON array
+pub fn import_shodan(path: &Path) -> Result<Vec<String>> {
+    let content = read_maybe_gzipped(path)?;
+    let banners: Vec<(usize, ShodanBanner)> = if content.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<ShodanBanner>>(&content)
+            .map_err(|e| malformed(path, format!("invalid JSON array: {}", e)))?
+            .into_iter()
+            .enumerate()
+            .map(|(i, banner)| (i + 1, banner))
+            .collect()
+    } else {
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .map(|banner| (i + 1, banner))
+                    .map_err(|e| malformed(path, format!("line {}: {}", i + 1, e)))
+            })
+            .collect::<Result<_>>()?
+    };
+
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    for (record, banner) in banners {
+        let entry = banner
+            .entry()
+            .ok_or_else(|| malformed(path, format!("record {} has no ip or ip_str", record)))?;
+        if seen.insert(entry.clone()) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Read hosts from one column of a CSV file with a header row
+///
+/// Non-empty values of the `labels` columns become labels of the host on
+/// the same row.
+pub fn import_csv(
+    path: &Path,
+    column: Option<&str>,
+    labels: &[String],
+) -> Result<Vec<ImportedEntry>> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| csv_error(path, e))?;
+    let headers = reader.headers().map_err(|e| csv_error(path, e))?.clone();
+    let find_column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+            .or_else(|| name.parse().ok().filter(|i| *i < headers.len()))
+            .ok_or_else(|| {
+                malformed(
+                    path,
+                    format!(
+                        "no column '{}' (columns: {})",
+                        name,
+                        headers.iter().collect::<Vec<_>>().join(", ")
+                    ),
+                )
+            })
+    };
+
+    let index = match column {
+        None => 0,
+        Some(name) => find_column(name)?,
+    };
+    let label_columns = labels
+        .iter()
+        .map(|name| Ok((name.as_str(), find_column(name)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut entries = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| csv_error(path, e))?;
+        let Some(value) = record.get(index).map(str::trim) else {
+            continue;
+        };
+        if value.is_empty() {
+            continue;
+        }
+        let mut entry = ImportedEntry::new(value);
+        for (name, label_index) in &label_columns {
+            if let Some(label) = record.get(*label_index).map(str::trim) {
+                if !label.is_empty() {
+                    entry.labels.insert(name.to_string(), label.to_string());
+                }
+            }
+        }
+        merge_entries(&mut entries, vec![entry]);
+    }
+    Ok(entries)
+}
+
+/// Resolve the IPv4 prefixes an ASN announces
+///
+/// IPv6 prefixes are skipped since they cannot be expanded host by host.
+pub async fn import_asn(asn: u32, endpoint: &str) -> Result<Vec<String>> {
+    #[derive(Deserialize)]
+    struct Response {
+        data: Data,
+    }
+    #[derive(Deserialize)]
+    struct Data {
+        prefixes: Vec<Prefix>,
+    }
+    #[derive(Deserialize)]
+    struct Prefix {
+        prefix: String,
+    }
+
+    let response = reqwest::Client::new()
+        .get(endpoint)
+        .query(&[("resource", format!("AS{}", asn))])
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| Error::Network(format!("Failed to resolve AS{} prefixes: {}", asn, e)))?;
+    let body: Response = response
+        .json()
+        .await
+        .map_err(|e| Error::Network(format!("Unexpected response for AS{}: {}", asn, e)))?;
+
+    let mut prefixes = Vec::new();
+    for prefix in body.data.prefixes {
+        if prefix.prefix.contains(':') {
+            tracing::debug!("Skipping IPv6 prefix {} for AS{}", prefix.prefix, asn);
+        } else if !prefixes.contains(&prefix.prefix) {
+            prefixes.push(prefix.prefix);
+        }
+    }
+    if prefixes.is_empty() {
+        return Err(Error::config(format!(
+            "AS{} announces no IPv4 prefixes",
+            asn
+        )));
+    }
+    Ok(prefixes)
+}
+
+fn read_maybe_gzipped(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| Error::config(format!("Failed to read '{}': {}", path.display(), e)))?;
+    if !bytes.starts_with(&[0x1f, 0x8b]) {
+        return String::from_utf8(bytes).map_err(|_| malformed(path, "not UTF-8 text"));
+    }
+    let mut content = String::new();
+    GzDecoder::new(bytes.as_slice())
+        .read_to_string(&mut content)
+        .map_err(|e| malformed(path, format!("invalid gzip data: {}", e)))?;
+    Ok(content)
+}
+
+fn malformed(path: &Path, reason: impl std::fmt::Display) -> Error {
+    Error::config(format!(
+        "Malformed scope file '{}': {}",
+        path.display(),
+        reason
+    ))
+}
+
+fn csv_error(path: &Path, error: csv::Error) -> Error {
+    match error.kind() {
+        csv::ErrorKind::Io(e) => {
+            Error::config(format!("Failed to read '{}': {}", path.display(), e))
+        }
+        _ => malformed(path, error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_parse_sources() {
+        assert_eq!(
+            ScopeSource::parse("asn:AS13335").unwrap(),
+            ScopeSource::Asn(13335)
+        );
+        assert_eq!(
+            ScopeSource::parse("csv:hosts.csv?column=ip").unwrap(),
+            ScopeSource::Csv {
+                path: PathBuf::from("hosts.csv"),
+                column: Some("ip".to_string()),
+                labels: Vec::new(),
+            }
+        );
+        assert_eq!(
+            ScopeSource::parse("csv:hosts.csv?column=ip&labels=owner, env").unwrap(),
+            ScopeSource::Csv {
+                path: PathBuf::from("hosts.csv"),
+                column: Some("ip".to_string()),
+                labels: vec!["owner".to_string(), "env".to_string()],
+            }
+        );
+        assert_eq!(
+            parse_label("env=prod").unwrap(),
+            ("env".to_string(), "prod".to_string())
+        );
+        assert!(parse_label("prod").is_err() && parse_label("=prod").is_err());
+        assert!(ScopeSource::parse("csv:hosts.csv?col=ip").is_err());
+        assert!(ScopeSource::parse("asn:cloudflare").is_err());
+        assert!(ScopeSource::parse("censys:hosts.json").is_err());
+    }
+
+    #[test]
+    fn test_split_template_annotation() {
+        let split_template_annotation = |line| {
+            let (entry, annotations) = split_annotations(line).unwrap();
+            (entry, annotations.templates)
+        };
+        assert_eq!(
+            split_template_annotation("https://api.example.com #templates:api-auth, api-injection"),
+            (
+                "https://api.example.com",
+                Some(vec!["api-auth".to_string(), "api-injection".to_string()])
+            )
+        );
+        assert_eq!(
+            split_template_annotation("10.0.0.0/30\t# templates:redis-unauth"),
+            ("10.0.0.0/30", Some(vec!["redis-unauth".to_string()]))
+        );
+        assert_eq!(
+            split_template_annotation("example.com  # staging box"),
+            ("example.com", None)
+        );
+        assert_eq!(
+            split_template_annotation("https://app.example.com/#/login"),
+            ("https://app.example.com/#/login", None)
+        );
+        assert_eq!(
+            split_template_annotation("example.com # templates:"),
+            ("example.com", None)
+        );
+    }
+
+    #[test]
+    fn test_split_header_annotations() {
+        let (entry, annotations) = split_annotations(
+            "https://api.internal.example.com #header:Authorization=Bearer xyz123 \
+             #header: X-Tenant = blue #templates:api-auth",
+        )
+        .unwrap();
+        assert_eq!(entry, "https://api.internal.example.com");
+        assert_eq!(
+            annotations.headers,
+            [
+                ("Authorization".to_string(), "Bearer xyz123".to_string()),
+                ("X-Tenant".to_string(), "blue".to_string()),
+            ]
+        );
+        assert_eq!(annotations.templates, Some(vec!["api-auth".to_string()]));
+
+        // Values may contain `=`
+        let (_, annotations) = split_annotations("example.com # header:Cookie=a=1; b=2").unwrap();
+        assert_eq!(annotations.headers[0].1, "a=1; b=2");
+
+        assert!(split_annotations("example.com # header:Authorization").is_err());
+        assert!(split_annotations("example.com # header:Bad Name=x").is_err());
+        assert!(split_annotations("example.com # staging")
+            .unwrap()
+            .1
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_piped_entries() {
+        let piped: &[u8] =
+            b"https://a.example/login\n\n  b.example:8443\r\n# staging\nhttp://10.0.0.5:8080\nc.example";
+        let entries = read_entries(piped).await.unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                "https://a.example/login",
+                "b.example:8443",
+                "http://10.0.0.5:8080",
+                "c.example",
+            ]
+        );
+
+        assert!(is_stdin_entry("-") && is_stdin_entry(" @- "));
+        assert!(!is_stdin_entry("@targets.txt") && !is_stdin_entry("--"));
+    }
+
+    #[tokio::test]
+    async fn test_piped_entries_expand_like_scope_values() {
+        let piped: &[u8] = b"# from subfinder\n\
+            10.0.0.0/31 # templates:redis-unauth\n\
+            b.example:8443\n\
+            https://a.example/login # header:X-Tenant=blue\n\
+            10.0.0.1\n";
+        let mut entries = ScopeEntries::new();
+        for line in read_entries(piped).await.unwrap() {
+            entries.expand(&line).unwrap();
+        }
+        let (targets, overrides) = entries.into_targets();
+
+        let urls: Vec<String> = targets.iter().map(|t| t.url()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://10.0.0.0",
+                "https://10.0.0.1",
+                "https://b.example:8443",
+                "https://a.example",
+            ]
+        );
+        assert_eq!(targets[2], parse_target("b.example:8443"));
+        let pinned = |target| overrides.get(target).map(<[String]>::to_vec);
+        assert_eq!(pinned(&targets[1]), Some(vec!["redis-unauth".to_string()]));
+        assert_eq!(pinned(&targets[2]), None);
+        assert_eq!(
+            targets[3].custom_headers,
+            [("X-Tenant".to_string(), "blue".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_importers_dedupe_across_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let shodan = dir.path().join("shodan.json");
+        std::fs::write(
+            &shodan,
+            concat!(
+                r#"{"ip_str": "192.0.2.10", "port": 443, "transport": "tcp"}"#,
+                "\n",
+                r#"{"ip": 3221225994, "port": 53, "transport": "udp"}"#,
+                "\n",
+                r#"{"ip_str": "192.0.2.10", "port": 443, "transport": "tcp"}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        let csv = dir.path().join("hosts.csv");
+        std::fs::write(
+            &csv,
+            "name,ip,owner\nweb,192.0.2.10:443,web-team\nmail,198.51.100.7,\n",
+        )
+        .unwrap();
+
+        let entries = import_all(
+            &[
+                ScopeSource::Shodan(shodan.clone()),
+                ScopeSource::Csv {
+                    path: csv.clone(),
+                    column: Some("ip".to_string()),
+                    labels: vec!["owner".to_string()],
+                },
+            ],
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.entry.as_str()).collect::<Vec<_>>(),
+            vec!["192.0.2.10:443", "udp://192.0.2.10:53", "198.51.100.7"]
+        );
+        // The CSV row's labels land on the entry Shodan imported first
+        assert_eq!(entries[0].labels["owner"], "web-team");
+        assert!(entries[2].labels.is_empty());
+
+        let err = import_csv(&csv, Some("ip"), &["team".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("no column 'team'"));
+        let err = import_csv(&csv, Some("address"), &[]).unwrap_err();
+        assert!(err.to_string().contains("no column 'address'"));
+        std::fs::write(&shodan, "{\"port\": 80}\nnot json\n").unwrap();
+        let err = import_shodan(&shodan).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[tokio::test]
+    async fn test_asn_prefixes() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(query_param("resource", "AS64500"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {"prefixes": [
+                    {"prefix": "203.0.113.0/24"},
+                    {"prefix": "2001:db8::/32"},
+                    {"prefix": "203.0.113.0/24"}
+                ]}
+            })))
+            .mount(&server)
+            .await;
+
+        let prefixes = import_asn(64500, &server.uri()).await.unwrap();
+        assert_eq!(prefixes, vec!["203.0.113.0/24"]);
+
+        let err = import_all(&[ScopeSource::Asn(64500)], true)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("--offline"));
+    }
+}
@@ -0,0 +1,479 @@
+//! OpenAPI/Swagger ingestion for `--openapi`
+//!
+//! Turns an OpenAPI 2 or 3 document into one sub-target per API path, so HTTP
+//! templates request every endpoint instead of only the host root. Path
+//! parameters are filled with example or primitive values, and each target
+//! carries its operation details as metadata:
+//!
+//! - `openapi_path`: path as written in the spec (`/users/{id}`)
+//! - `openapi_methods`: comma-separated HTTP methods
+//! - `openapi_operation_ids`: comma-separated `operationId`s
+//! - `openapi_auth`: comma-separated security scheme names (empty if public)
+
+use crate::error::{Error, Result};
+use crate::types::{Protocol, Target};
+use serde_yaml::Value;
+use std::collections::BTreeSet;
+
+/// Default cap on endpoints taken from one spec
+pub const DEFAULT_MAX_ENDPOINTS: usize = 500;
+
+const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// A parsed OpenAPI document
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiSpec {
+    /// Absolute server URLs declared by the spec
+    pub servers: Vec<String>,
+    /// Prefix shared by every path (`basePath` or the first server's path)
+    pub base_path: String,
+    /// Endpoints in spec order
+    pub endpoints: Vec<Endpoint>,
+}
+
+/// One API path and the operations it supports
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    /// Path as written in the spec
+    pub template: String,
+    /// Path with parameters substituted
+    pub path: String,
+    /// Operations on this path
+    pub operations: Vec<Operation>,
+}
+
+/// One method on an endpoint
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    /// Uppercase HTTP method
+    pub method: String,
+    /// `operationId`, if set
+    pub operation_id: Option<String>,
+    /// Names of the security schemes that can authorize the call
+    pub security: Vec<String>,
+}
+
+impl ApiSpec {
+    /// Read a spec from a file or an `http(s)://` URL
+    pub async fn load(source: &str, offline: bool) -> Result<Self> {
+        let content = if source.starts_with("http://") || source.starts_with("https://") {
+            if offline {
+                return Err(Error::config(format!(
+                    "Cannot fetch OpenAPI spec {} with --offline",
+                    source
+                )));
+            }
+            reqwest::get(source)
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| Error::Network(format!("Failed to fetch {}: {}", source, e)))?
+                .text()
+                .await
+                .map_err(|e| Error::Network(format!("Failed to read {}: {}", source, e)))?
+        } else {
+            std::fs::read_to_string(source)
+                .map_err(|e| Error::config(format!("Failed to read '{}': {}", source, e)))?
+        };
+        Self::parse(&content)
+    }
+
+    /// Parse a JSON or YAML OpenAPI 2/3 document
+    pub fn parse(content: &str) -> Result<Self> {
+        // YAML is a superset of JSON, and its mappings keep the spec's path order
+        let doc: Value = serde_yaml::from_str(content)
+            .map_err(|e| Error::Parse(format!("Invalid OpenAPI document: {}", e)))?;
+        let (servers, base_path) = if doc.get("swagger").is_some() {
+            swagger_servers(&doc)
+        } else if doc.get("openapi").is_some() {
+            openapi_servers(&doc)
+        } else {
+            return Err(Error::Parse(
+                "Not an OpenAPI document: missing 'openapi' or 'swagger' version".to_string(),
+            ));
+        };
+
+        let default_security = security_names(doc.get("security"));
+        let mut endpoints = Vec::new();
+        for (template, item) in doc
+            .get("paths")
+            .and_then(Value::as_mapping)
+            .into_iter()
+            .flatten()
+        {
+            let Some(template) = template.as_str() else {
+                continue;
+            };
+            let shared = parameters(&doc, item.get("parameters"));
+            let mut path = template.to_string();
+            let mut operations = Vec::new();
+            for method in METHODS {
+                let Some(op) = item.get(*method) else {
+                    continue;
+                };
+                for param in shared.iter().chain(&parameters(&doc, op.get("parameters"))) {
+                    if param.get("in").and_then(Value::as_str) == Some("path") {
+                        if let Some(name) = param.get("name").and_then(Value::as_str) {
+                            path = path.replace(&format!("{{{}}}", name), &sample_value(param));
+                        }
+                    }
+                }
+                operations.push(Operation {
+                    method: method.to_uppercase(),
+                    operation_id: op
+                        .get("operationId")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    security: match op.get("security") {
+                        Some(security) => security_names(Some(security)),
+                        None => default_security.clone(),
+                    },
+                });
+            }
+            if operations.is_empty() {
+                continue;
+            }
+            endpoints.push(Endpoint {
+                template: template.to_string(),
+                path: fill_unknown_parameters(&path),
+                operations,
+            });
+        }
+
+        Ok(Self {
+            servers,
+            base_path,
+            endpoints,
+        })
+    }
+
+    /// One target per endpoint for every HTTP(S) target, capped at `max_endpoints` endpoints
+    ///
+    /// Targets for other protocols are kept as they are.
+    pub fn endpoint_targets(&self, targets: &[Target], max_endpoints: usize) -> Vec<Target> {
+        if self.endpoints.len() > max_endpoints {
+            tracing::warn!(
+                "OpenAPI spec has {} endpoints; only the first {} are scanned (see --max-endpoints)",
+                self.endpoints.len(),
+                max_endpoints
+            );
+        }
+        let endpoints = &self.endpoints[..self.endpoints.len().min(max_endpoints)];
+
+        let mut expanded = Vec::new();
+        for target in targets {
+            if !matches!(target.protocol, Protocol::Http | Protocol::Https) {
+                expanded.push(target.clone());
+                continue;
+            }
+            for endpoint in endpoints {
+                let mut sub_target = target.clone();
+                sub_target.id = uuid::Uuid::new_v4();
+                sub_target.base_path = Some(format!("{}{}", self.base_path, endpoint.path));
+                sub_target.metadata.extend(endpoint.metadata());
+                expanded.push(sub_target);
+            }
+        }
+        expanded
+    }
+}
+
+impl Endpoint {
+    /// Metadata attached to this endpoint's targets
+    pub fn metadata(&self) -> Vec<(String, String)> {
+        let join = |values: Vec<&str>| values.join(",");
+        let auth: BTreeSet<&str> = self
+            .operations
+            .iter()
+            .flat_map(|op| op.security.iter().map(String::as_str))
+            .collect();
+        vec![
+            ("openapi_path".to_string(), self.template.clone()),
+            (
+                "openapi_methods".to_string(),
+                join(
+                    self.operations
+                        .iter()
+                        .map(|op| op.method.as_str())
+                        .collect(),
+                ),
+            ),
+            (
+                "openapi_operation_ids".to_string(),
+                join(
+                    self.operations
+                        .iter()
+                        .filter_map(|op| op.operation_id.as_deref())
+                        .collect(),
+                ),
+            ),
+            ("openapi_auth".to_string(), join(auth.into_iter().collect())),
+        ]
+    }
+}
+
+/// Servers and base path of a Swagger 2 document (`host`, `basePath`, `schemes`)
+fn swagger_servers(doc: &Value) -> (Vec<String>, String) {
+    let base_path = normalize_base_path(doc.get("basePath").and_then(Value::as_str).unwrap_or(""));
+    let servers = match doc.get("host").and_then(Value::as_str) {
+        Some(host) => {
+            let schemes: Vec<&str> = doc
+                .get("schemes")
+                .and_then(Value::as_sequence)
+                .map(|s| s.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_else(|| vec!["https"]);
+            schemes
+                .iter()
+                .map(|scheme| format!("{}://{}", scheme, host))
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    (servers, base_path)
+}
+
+/// Servers and base path of an OpenAPI 3 document (`servers[].url`)
+fn openapi_servers(doc: &Value) -> (Vec<String>, String) {
+    let urls: Vec<&str> = doc
+        .get("servers")
+        .and_then(Value::as_sequence)
+        .map(|s| {
+            s.iter()
+                .filter_map(|server| server.get("url").and_then(Value::as_str))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut servers = Vec::new();
+    let mut base_path = None;
+    for raw in urls {
+        match url::Url::parse(raw) {
+            Ok(url) if url.has_host() => {
+                base_path.get_or_insert_with(|| normalize_base_path(url.path()));
+                servers.push(format!(
+                    "{}://{}",
+                    url.scheme(),
+                    &url[url::Position::BeforeHost..url::Position::AfterPort]
+                ));
+            }
+            // Relative server URLs only contribute a path prefix
+            _ => {
+                base_path.get_or_insert_with(|| normalize_base_path(raw));
+            }
+        }
+    }
+    (servers, base_path.unwrap_or_default())
+}
+
+fn normalize_base_path(path: &str) -> String {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() || trimmed.starts_with('/') {
+        trimmed.to_string()
+    } else {
+        format!("/{}", trimmed)
+    }
+}
+
+/// Parameter objects with local `$ref`s resolved
+fn parameters(doc: &Value, params: Option<&Value>) -> Vec<Value> {
+    params
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .filter_map(|param| match param.get("$ref").and_then(Value::as_str) {
+            Some(reference) => resolve_ref(doc, reference).cloned(),
+            None => Some(param.clone()),
+        })
+        .collect()
+}
+
+/// Follow a local `#/a/b` reference
+fn resolve_ref<'a>(doc: &'a Value, reference: &str) -> Option<&'a Value> {
+    reference
+        .strip_prefix("#/")?
+        .split('/')
+        .map(|part| part.replace("~1", "/").replace("~0", "~"))
+        .try_fold(doc, |value, part| value.get(part.as_str()))
+}
+
+/// Example, default or first enum value of a parameter, else a value of its type
+fn sample_value(param: &Value) -> String {
+    // OpenAPI 3 keeps type information under `schema`, Swagger 2 on the parameter
+    let schema = param.get("schema").unwrap_or(param);
+    let explicit = param
+        .get("example")
+        .or_else(|| param.get("x-example"))
+        .or_else(|| schema.get("example"))
+        .or_else(|| schema.get("default"))
+        .or_else(|| schema.get("enum").and_then(|e| e.get(0)));
+    if let Some(value) = explicit {
+        match value {
+            Value::String(s) => return s.clone(),
+            Value::Number(n) => return n.to_string(),
+            Value::Bool(b) => return b.to_string(),
+            _ => {}
+        }
+    }
+
+    match (
+        schema.get("type").and_then(Value::as_str),
+        schema.get("format").and_then(Value::as_str),
+    ) {
+        (Some("integer" | "number"), _) => "1".to_string(),
+        (Some("boolean"), _) => "true".to_string(),
+        (_, Some("uuid")) => "00000000-0000-0000-0000-000000000001".to_string(),
+        (_, Some("date")) => "2024-01-01".to_string(),
+        (_, Some("date-time")) => "2024-01-01T00:00:00Z".to_string(),
+        _ => "test".to_string(),
+    }
+}
+
+/// Replace `{name}` placeholders that no parameter declared
+fn fill_unknown_parameters(path: &str) -> String {
+    let mut filled = String::with_capacity(path.len());
+    let mut rest = path;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        filled.push_str(&rest[..start]);
+        filled.push('1');
+        rest = &rest[start + end + 1..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// Scheme names from a `security` requirement list
+fn security_names(security: Option<&Value>) -> Vec<String> {
+    let mut names: Vec<String> = security
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_mapping)
+        .flat_map(|requirement| requirement.keys().filter_map(Value::as_str))
+        .map(str::to_string)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPENAPI3: &str = r#"
+openapi: 3.0.1
+servers:
+  - url: https://api.example.com:8443/v1/
+security:
+  - bearerAuth: []
+components:
+  parameters:
+    UserId:
+      name: userId
+      in: path
+      required: true
+      schema: { type: integer }
+paths:
+  /users/{userId}:
+    parameters:
+      - $ref: '#/components/parameters/UserId'
+    get:
+      operationId: getUser
+    delete:
+      operationId: deleteUser
+      security:
+        - apiKey: []
+  /orders/{orderId}/items/{sku}:
+    get:
+      parameters:
+        - name: orderId
+          in: path
+          schema: { type: string, format: uuid }
+        - name: sku
+          in: path
+          example: ABC-1
+  /health:
+    get:
+      operationId: health
+      security: []
+"#;
+
+    #[test]
+    fn test_openapi3_endpoints_and_metadata() {
+        let spec = ApiSpec::parse(OPENAPI3).unwrap();
+        assert_eq!(
+            spec.servers,
+            vec!["https://api.example.com:8443".to_string()]
+        );
+        assert_eq!(spec.base_path, "/v1");
+
+        let paths: Vec<&str> = spec.endpoints.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/users/1",
+                "/orders/00000000-0000-0000-0000-000000000001/items/ABC-1",
+                "/health"
+            ]
+        );
+
+        let metadata: std::collections::HashMap<String, String> =
+            spec.endpoints[0].metadata().into_iter().collect();
+        assert_eq!(metadata["openapi_path"], "/users/{userId}");
+        assert_eq!(metadata["openapi_methods"], "GET,DELETE");
+        assert_eq!(metadata["openapi_operation_ids"], "getUser,deleteUser");
+        assert_eq!(metadata["openapi_auth"], "apiKey,bearerAuth");
+        assert_eq!(
+            spec.endpoints[2].operations[0].security,
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_swagger2_and_endpoint_targets() {
+        let spec = ApiSpec::parse(
+            r#"{"swagger": "2.0", "host": "petstore.example.com", "basePath": "/api",
+                "schemes": ["http"],
+                "paths": {
+                  "/pets/{petId}": {"get": {"operationId": "getPet", "parameters": [
+                      {"name": "petId", "in": "path", "type": "integer", "x-example": 42}]}},
+                  "/pets": {"post": {}, "get": {}},
+                  "/store/{unknown}": {"get": {}}
+                }}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            spec.servers,
+            vec!["http://petstore.example.com".to_string()]
+        );
+
+        let targets = spec.endpoint_targets(
+            &[
+                Target::new("petstore.example.com", Protocol::Http),
+                Target::with_port("petstore.example.com", 22, Protocol::Ssh),
+            ],
+            2,
+        );
+        let urls: Vec<String> = targets.iter().map(|t| t.url()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "http://petstore.example.com/api/pets/42",
+                "http://petstore.example.com/api/pets",
+                "petstore.example.com:22",
+            ]
+        );
+        assert_eq!(targets[0].metadata["openapi_operation_ids"], "getPet");
+        assert_eq!(targets[1].metadata["openapi_methods"], "GET,POST");
+    }
+
+    #[test]
+    fn test_rejects_non_openapi_documents() {
+        assert!(ApiSpec::parse("id: not-a-spec").is_err());
+        assert_eq!(fill_unknown_parameters("/a/{x}/b/{y}"), "/a/1/b/1");
+    }
+}
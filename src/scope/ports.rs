@@ -0,0 +1,170 @@
+//! Port specifications for `--ports` and `--override-ports`
+//!
+//! A specification lists ports and ranges (`80,443,8000-9000`); `--ports`
+//! entries may also name files (`@ports.txt`) holding more entries, one per
+//! line.
+
+use crate::error::{Error, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Parse port specification string (supports individual ports and ranges)
+/// Examples: "80,443,8000-9000" -> [80, 443, 8000, 8001, ..., 9000]
+pub fn parse_ports(port_spec: &str) -> Result<Vec<u16>> {
+    let mut ports = Vec::new();
+
+    for part in port_spec.split(',') {
+        let part = part.trim();
+
+        if part.contains('-') {
+            // Handle range (e.g., "8000-9000")
+            let range_parts: Vec<&str> = part.split('-').collect();
+            if range_parts.len() != 2 {
+                return Err(Error::config(format!("Invalid port range: {}", part)));
+            }
+
+            let start: u16 = range_parts[0]
+                .trim()
+                .parse()
+                .map_err(|_| Error::config(format!("Invalid port number: {}", range_parts[0])))?;
+            let end: u16 = range_parts[1]
+                .trim()
+                .parse()
+                .map_err(|_| Error::config(format!("Invalid port number: {}", range_parts[1])))?;
+
+            if start > end {
+                return Err(Error::config(format!(
+                    "Invalid port range: {} > {}",
+                    start, end
+                )));
+            }
+
+            for port in start..=end {
+                ports.push(port);
+            }
+        } else {
+            // Handle single port
+            let port: u16 = part
+                .parse()
+                .map_err(|_| Error::config(format!("Invalid port number: {}", part)))?;
+            ports.push(port);
+        }
+    }
+
+    // Remove duplicates and sort
+    ports.sort_unstable();
+    ports.dedup();
+
+    Ok(ports)
+}
+
+/// Parse `--ports` entries: specifications or `@file` references, sorted and deduplicated
+pub fn parse_port_entries(entries: &[String]) -> Result<Vec<u16>> {
+    let mut collected = Vec::new();
+    let mut file_stack = HashSet::new();
+
+    for entry in entries {
+        expand_port_entry(entry, &mut collected, &mut file_stack)?;
+    }
+
+    collected.sort_unstable();
+    collected.dedup();
+    Ok(collected)
+}
+
+fn expand_port_entry(
+    entry: &str,
+    acc: &mut Vec<u16>,
+    file_stack: &mut HashSet<PathBuf>,
+) -> Result<()> {
+    let trimmed = entry.trim();
+    if trimmed.is_empty() {
+        return Ok(());
+    }
+
+    if trimmed.contains(',') {
+        for part in trimmed.split(',') {
+            expand_port_entry(part, acc, file_stack)?;
+        }
+        return Ok(());
+    }
+
+    let (candidate, forced_file) = super::strip_file_prefix(trimmed);
+
+    let path = Path::new(candidate);
+    if forced_file || path.exists() {
+        if !path.exists() {
+            return Err(Error::config(format!("Port file not found: {}", candidate)));
+        }
+
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        if !file_stack.insert(canonical.clone()) {
+            return Err(Error::config(format!(
+                "Recursive port file reference detected: {}",
+                canonical.display()
+            )));
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| {
+            Error::config(format!("Failed to read port file '{}': {}", candidate, e))
+        })?;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            expand_port_entry(line, acc, file_stack)?;
+        }
+
+        file_stack.remove(&canonical);
+        return Ok(());
+    }
+
+    acc.extend(parse_ports(trimmed)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ports() {
+        assert_eq!(
+            parse_ports("443, 80,8000-8002").unwrap(),
+            [80, 443, 8000, 8001, 8002]
+        );
+        assert_eq!(parse_ports("80,80").unwrap(), [80]);
+        assert!(parse_ports("9000-8000").is_err());
+        assert!(parse_ports("1-2-3").is_err());
+        assert!(parse_ports("http").is_err());
+        assert!(parse_ports("70000").is_err());
+    }
+
+    #[test]
+    fn test_parse_port_entries_reads_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("more.txt");
+        fs::write(&nested, "9000-9001\n").unwrap();
+        let file = dir.path().join("ports.txt");
+        fs::write(
+            &file,
+            format!("# web\n8080\n8443,8000\n@{}\n", nested.display()),
+        )
+        .unwrap();
+
+        let entries = vec![format!("@{}", file.display()), "80".to_string()];
+        assert_eq!(
+            parse_port_entries(&entries).unwrap(),
+            [80, 8000, 8080, 8443, 9000, 9001]
+        );
+
+        let looped = dir.path().join("loop.txt");
+        fs::write(&looped, format!("@{}\n", looped.display())).unwrap();
+        assert!(parse_port_entries(&[format!("@{}", looped.display())]).is_err());
+        assert!(parse_port_entries(&["@missing-ports.txt".to_string()]).is_err());
+    }
+}
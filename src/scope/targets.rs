@@ -0,0 +1,410 @@
+//! Turning scope entries into targets
+//!
+//! An entry is a host, `host:port`, URL or CIDR block, a comma-separated list
+//! of entries, or a file of entries (`@targets.txt`, `file:`), one per line
+//! with optional `# templates:` and `# header:` annotations.
+
+use crate::core::PerTargetOverrides;
+use crate::error::{Error, Result};
+use crate::types::{Protocol, SchemePorts, SchemeSource, Target};
+use crate::utils;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Parse a single target string (supports host:port format)
+pub fn parse_target(target_str: &str) -> Target {
+    if let Ok(url) = url::Url::parse(target_str) {
+        if let Some(host) = url.host_str() {
+            let protocol = match url.scheme().to_lowercase().as_str() {
+                "http" => Protocol::Http,
+                "https" => Protocol::Https,
+                "tcp" => Protocol::Tcp,
+                "udp" => Protocol::Udp,
+                other => Protocol::Custom(other.to_string()),
+            };
+
+            let mut target = Target::new(host, protocol);
+            if let Some(port) = url.port() {
+                target.port = Some(port);
+            }
+            if matches!(target.protocol, Protocol::Http | Protocol::Https) {
+                target.scheme_source = Some(SchemeSource::Explicit);
+            }
+            return target;
+        }
+    }
+
+    match utils::parse_target(target_str) {
+        Ok((host, port)) => {
+            if let Some(port) = port {
+                // A first guess; `network::settle_schemes` settles it
+                let protocol = SchemePorts::default()
+                    .scheme_for(port)
+                    .unwrap_or(Protocol::Https);
+                Target::with_port(host, port, protocol)
+            } else {
+                Target::new(host, Protocol::Https)
+            }
+        }
+        Err(_) => Target::new(target_str, Protocol::Https),
+    }
+}
+
+/// Expand targets to create one target per port
+/// This enables testing multiple ports on the same host
+pub fn expand_targets_for_ports(targets: Vec<Target>, ports: &[u16]) -> Vec<Target> {
+    if ports.is_empty() {
+        return targets;
+    }
+
+    let mut expanded = Vec::new();
+
+    for target in targets {
+        // If target already has a port, keep it and add additional ports
+        if target.port.is_some() {
+            expanded.push(target.clone());
+        }
+
+        // Create a target for each additional port
+        for &port in ports {
+            // Skip if this port is already the target's port
+            if target.port == Some(port) {
+                continue;
+            }
+
+            let mut new_target = target.clone();
+            new_target.port = Some(port);
+            // The scope entry's scheme says nothing about another port
+            new_target.scheme_source = None;
+            expanded.push(new_target);
+        }
+    }
+
+    expanded
+}
+
+/// Scope entries expanded from `--scope` values
+#[derive(Debug, Default)]
+pub struct ScopeEntries {
+    entries: Vec<String>,
+    /// Template IDs from `# templates:` annotations, by expanded entry
+    templates: HashMap<String, Vec<String>>,
+    /// Labels from structured `--scope-from` sources, by expanded entry
+    labels: HashMap<String, HashMap<String, String>>,
+    /// Headers from `# header:` annotations, by expanded entry
+    headers: HashMap<String, Vec<(String, String)>>,
+    /// Scope files being read, to catch files that include themselves
+    in_progress_files: HashSet<PathBuf>,
+}
+
+impl ScopeEntries {
+    /// No entries yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expand `entry` (files, lists and CIDR blocks) and add what it names
+    pub fn expand(&mut self, entry: &str) -> Result<()> {
+        let trimmed = entry.trim();
+        if trimmed.is_empty() {
+            return Ok(());
+        }
+
+        // `target # templates:a,b` runs only those templates on what `target` expands to,
+        // and `# header:Name=Value` adds a header to every request sent to it
+        let (trimmed, annotations) = super::split_annotations(trimmed)?;
+        if !annotations.is_empty() {
+            let start = self.entries.len();
+            self.expand(trimmed)?;
+            for expanded in &self.entries[start..] {
+                if let Some(ids) = &annotations.templates {
+                    self.templates
+                        .entry(expanded.clone())
+                        .or_default()
+                        .extend(ids.iter().cloned());
+                }
+                if !annotations.headers.is_empty() {
+                    self.headers
+                        .entry(expanded.clone())
+                        .or_default()
+                        .extend(annotations.headers.iter().cloned());
+                }
+            }
+            return Ok(());
+        }
+        if trimmed.is_empty() {
+            return Ok(());
+        }
+
+        // Allow comma-separated lists inside scope values (e.g., from files)
+        if trimmed.contains(',') {
+            for part in trimmed.split(',') {
+                self.expand(part)?;
+            }
+            return Ok(());
+        }
+
+        let (candidate, forced_file) = super::strip_file_prefix(trimmed);
+
+        let path = Path::new(candidate);
+        if forced_file || path.exists() {
+            if !path.exists() {
+                return Err(Error::config(format!(
+                    "Scope file not found: {}",
+                    candidate
+                )));
+            }
+
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+            if !self.in_progress_files.insert(canonical.clone()) {
+                return Err(Error::config(format!(
+                    "Recursive scope file reference detected: {}",
+                    canonical.display()
+                )));
+            }
+
+            let content = fs::read_to_string(path).map_err(|e| {
+                Error::config(format!("Failed to read scope file '{}': {}", candidate, e))
+            })?;
+
+            for line in content.lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                self.expand(line)?;
+            }
+
+            self.in_progress_files.remove(&canonical);
+            return Ok(());
+        }
+
+        if trimmed.contains('/') && !trimmed.contains("://") {
+            if let Ok(addresses) = utils::parse_cidr(trimmed) {
+                for ip in addresses {
+                    self.entries.push(ip.to_string());
+                }
+                return Ok(());
+            }
+        }
+
+        self.entries.push(trimmed.to_string());
+        Ok(())
+    }
+
+    /// Expand `entry` and label every target it names, keeping labels set before
+    pub fn expand_labeled(&mut self, entry: &str, labels: &HashMap<String, String>) -> Result<()> {
+        let start = self.entries.len();
+        self.expand(entry)?;
+        for entry in &self.entries[start..] {
+            let entry_labels = self.labels.entry(entry.clone()).or_default();
+            for (key, value) in labels {
+                entry_labels
+                    .entry(key.clone())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a single host or URL as is
+    pub fn push(&mut self, entry: impl Into<String>) {
+        self.entries.push(entry.into());
+    }
+
+    /// Whether no entry was added
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// One target per distinct entry, in order, with the templates scope file
+    /// annotations pin to some of them
+    pub fn into_targets(self) -> (Vec<Target>, PerTargetOverrides) {
+        let mut seen = HashSet::new();
+        let mut targets = Vec::new();
+        let mut overrides = PerTargetOverrides::new();
+
+        for raw in &self.entries {
+            let trimmed = raw.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            if !seen.insert(trimmed.to_string()) {
+                continue;
+            }
+
+            let mut target = parse_target(trimmed);
+            if let Some(labels) = self.labels.get(raw) {
+                target.labels = labels.clone();
+            }
+            if let Some(headers) = self.headers.get(raw) {
+                target.custom_headers = headers.clone();
+            }
+            if let Some(ids) = self.templates.get(raw) {
+                overrides.insert(target.clone(), ids.clone());
+            }
+            targets.push(target);
+        }
+        (targets, overrides)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets_from(entry: &str) -> Result<(Vec<Target>, PerTargetOverrides)> {
+        let mut scope = ScopeEntries::new();
+        scope.expand(entry)?;
+        Ok(scope.into_targets())
+    }
+
+    #[test]
+    fn test_parse_target() {
+        let target = parse_target("http://example.com:8080");
+        assert_eq!(target.protocol, Protocol::Http);
+        assert_eq!(target.port, Some(8080));
+        assert_eq!(target.scheme_source, Some(SchemeSource::Explicit));
+
+        let target = parse_target("example.com:8000");
+        assert_eq!(target.protocol, Protocol::Http);
+        assert_eq!(target.scheme_source, None);
+
+        let target = parse_target("udp://10.0.0.1:161");
+        assert_eq!(target.protocol, Protocol::Udp);
+        assert_eq!(target.scheme_source, None);
+
+        assert_eq!(parse_target("example.com").url(), "https://example.com");
+    }
+
+    #[test]
+    fn test_expand_targets_for_ports() {
+        let mut explicit = parse_target("https://a.example.com:8443");
+        explicit
+            .labels
+            .insert("env".to_string(), "prod".to_string());
+        let targets = vec![explicit, parse_target("b.example.com")];
+
+        let expanded = expand_targets_for_ports(targets, &[80, 8443]);
+        let urls: Vec<String> = expanded.iter().map(Target::url).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://a.example.com:8443",
+                "https://a.example.com:80",
+                "https://b.example.com:80",
+                "https://b.example.com:8443",
+            ]
+        );
+        assert_eq!(expanded[0].scheme_source, Some(SchemeSource::Explicit));
+        assert_eq!(expanded[1].scheme_source, None);
+        assert_eq!(expanded[1].labels["env"], "prod");
+        let unchanged = expand_targets_for_ports(expanded, &[]);
+        assert_eq!(unchanged.iter().map(Target::url).collect::<Vec<_>>(), urls);
+    }
+
+    #[test]
+    fn test_scope_file_template_annotations() {
+        let dir = tempfile::tempdir().unwrap();
+        let scope_file = dir.path().join("targets.txt");
+        fs::write(
+            &scope_file,
+            "# engagement scope\n\
+             https://api.example.com #templates:api-auth,api-injection\n\
+             https://www.example.com\n\
+             10.0.0.0/31 # templates:redis-unauth\n\
+             https://app.example.com # owner: web team\n",
+        )
+        .unwrap();
+
+        let (targets, overrides) = targets_from(&format!("@{}", scope_file.display())).unwrap();
+        let urls: Vec<String> = targets.iter().map(Target::url).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://api.example.com",
+                "https://www.example.com",
+                "https://10.0.0.0",
+                "https://10.0.0.1",
+                "https://app.example.com",
+            ]
+        );
+
+        let pinned = |target: &Target| overrides.get(target).map(<[String]>::to_vec);
+        assert_eq!(
+            pinned(&targets[0]),
+            Some(vec!["api-auth".to_string(), "api-injection".to_string()])
+        );
+        assert_eq!(pinned(&targets[1]), None);
+        assert_eq!(pinned(&targets[2]), Some(vec!["redis-unauth".to_string()]));
+        assert_eq!(pinned(&targets[3]), Some(vec!["redis-unauth".to_string()]));
+        assert_eq!(pinned(&targets[4]), None);
+    }
+
+    #[test]
+    fn test_scope_file_header_annotations() {
+        let dir = tempfile::tempdir().unwrap();
+        let scope_file = dir.path().join("targets.txt");
+        fs::write(
+            &scope_file,
+            "https://api.internal.example.com #header:Authorization=Bearer xyz123 \
+             #header:X-Tenant=blue\n\
+             https://www.example.com\n\
+             10.0.0.0/31 # header:Cookie=session=abc # templates:redis-unauth\n",
+        )
+        .unwrap();
+        let scope = format!("@{}", scope_file.display());
+
+        let (targets, overrides) = targets_from(&scope).unwrap();
+        let headers = |target: &Target| target.custom_headers.clone();
+        assert_eq!(
+            headers(&targets[0]),
+            [
+                ("Authorization".to_string(), "Bearer xyz123".to_string()),
+                ("X-Tenant".to_string(), "blue".to_string()),
+            ]
+        );
+        assert!(headers(&targets[1]).is_empty());
+        for target in &targets[2..] {
+            assert_eq!(
+                headers(target),
+                [("Cookie".to_string(), "session=abc".to_string())]
+            );
+            assert_eq!(
+                overrides.get(target).map(<[String]>::to_vec).unwrap().len(),
+                1
+            );
+        }
+
+        fs::write(
+            &scope_file,
+            "https://api.example.com # header:Authorization\n",
+        )
+        .unwrap();
+        assert!(targets_from(&scope).is_err());
+    }
+
+    #[test]
+    fn test_scope_entries_dedup_and_labels() {
+        let dir = tempfile::tempdir().unwrap();
+        let looped = dir.path().join("loop.txt");
+        fs::write(&looped, format!("a.example.com\n@{}\n", looped.display())).unwrap();
+        assert!(targets_from(&format!("@{}", looped.display())).is_err());
+        assert!(targets_from("@missing-scope.txt").is_err());
+
+        let mut scope = ScopeEntries::new();
+        assert!(scope.is_empty());
+        scope.expand("a.example.com, b.example.com").unwrap();
+        let labels = HashMap::from([("owner".to_string(), "web".to_string())]);
+        scope.expand_labeled("b.example.com", &labels).unwrap();
+        scope.push("a.example.com");
+        let (targets, overrides) = scope.into_targets();
+        let hosts: Vec<&str> = targets.iter().map(|t| t.address.as_str()).collect();
+        assert_eq!(hosts, ["a.example.com", "b.example.com"]);
+        assert_eq!(targets[1].labels["owner"], "web");
+        assert!(overrides.is_empty());
+    }
+}
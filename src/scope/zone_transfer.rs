@@ -0,0 +1,426 @@
+//! DNS zone transfers for `cxg scan --zone-transfer <domain>`
+//!
+//! The domain's authoritative nameservers (its NS records) are asked for the
+//! whole zone over TCP (AXFR). The first one that sends it reveals every host
+//! name with an A, AAAA or CNAME record, and those names are added to the
+//! scope. Most nameservers refuse; the attempt is then reported as an `Info`
+//! finding, and an allowed transfer as a `Medium` one, since it exposes the
+//! zone to anyone.
+
+use crate::error::{Error, Result};
+use crate::types::{Evidence, Finding, Severity};
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use trust_dns_proto::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use trust_dns_proto::rr::{Name, RData, Record, RecordType};
+
+/// Longest wait for each nameserver's transfer
+pub const TRANSFER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Template ID of zone transfer findings
+pub const ZONE_TRANSFER_TEMPLATE_ID: &str = "dns-zone-transfer";
+
+/// AXFR request for one domain
+#[derive(Debug, Clone)]
+pub struct ZoneTransfer {
+    domain: Name,
+    nameservers: Option<Vec<SocketAddr>>,
+    timeout: Duration,
+}
+
+/// What a zone transfer attempt found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneTransferResult {
+    /// Domain whose zone was requested
+    pub domain: String,
+    /// Nameserver that sent the zone, if any did
+    pub transferred_by: Option<SocketAddr>,
+    /// Names with A, AAAA or CNAME records in the zone, without wildcards
+    pub hosts: Vec<String>,
+    /// Why each nameserver asked before (or instead of) a transfer did not send the zone
+    pub refusals: Vec<(SocketAddr, String)>,
+}
+
+impl ZoneTransfer {
+    /// Transfer of `domain`'s zone from its authoritative nameservers
+    pub fn new(domain: &str) -> Result<Self> {
+        let mut domain = Name::from_ascii(domain.trim().trim_end_matches('.')).map_err(|e| {
+            Error::config(format!("Invalid zone transfer domain {}: {}", domain, e))
+        })?;
+        domain.set_fqdn(true);
+        Ok(Self {
+            domain,
+            nameservers: None,
+            timeout: TRANSFER_TIMEOUT,
+        })
+    }
+
+    /// Ask these nameservers instead of the domain's NS records
+    pub fn with_nameservers(mut self, nameservers: Vec<SocketAddr>) -> Self {
+        self.nameservers = Some(nameservers);
+        self
+    }
+
+    /// Wait at most `timeout` for each nameserver
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Ask each nameserver in turn until one sends the zone
+    ///
+    /// Fails only when the nameservers cannot be found; refusals are part of
+    /// the result.
+    pub async fn run(&self) -> Result<ZoneTransferResult> {
+        let nameservers = match &self.nameservers {
+            Some(nameservers) => nameservers.clone(),
+            None => self.authoritative_nameservers().await?,
+        };
+
+        let mut result = ZoneTransferResult {
+            domain: self.domain_str(),
+            transferred_by: None,
+            hosts: Vec::new(),
+            refusals: Vec::new(),
+        };
+        for nameserver in nameservers {
+            let attempt = tokio::time::timeout(self.timeout, self.transfer_from(nameserver)).await;
+            match attempt {
+                Ok(Ok(records)) => {
+                    result.hosts = hosts_in(&records);
+                    result.transferred_by = Some(nameserver);
+                    tracing::info!(
+                        "Zone transfer of {} from {} revealed {} host(s)",
+                        result.domain,
+                        nameserver,
+                        result.hosts.len()
+                    );
+                    break;
+                }
+                Ok(Err(e)) => {
+                    tracing::debug!("Zone transfer from {} failed: {}", nameserver, e);
+                    result.refusals.push((nameserver, e.to_string()));
+                }
+                Err(_) => {
+                    tracing::debug!("Zone transfer from {} timed out", nameserver);
+                    result.refusals.push((nameserver, "timed out".to_string()));
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn domain_str(&self) -> String {
+        self.domain.to_ascii().trim_end_matches('.').to_string()
+    }
+
+    /// Addresses of the domain's NS hosts, on port 53
+    async fn authoritative_nameservers(&self) -> Result<Vec<SocketAddr>> {
+        let resolver = trust_dns_resolver::TokioAsyncResolver::tokio(
+            trust_dns_resolver::config::ResolverConfig::default(),
+            trust_dns_resolver::config::ResolverOpts::default(),
+        );
+        let lookup_error = |e: trust_dns_resolver::error::ResolveError| Error::DnsResolution {
+            hostname: self.domain_str(),
+            error: e.to_string(),
+        };
+
+        let ns = resolver
+            .ns_lookup(self.domain.clone())
+            .await
+            .map_err(lookup_error)?;
+        let mut addresses = Vec::new();
+        for host in ns.iter() {
+            match resolver.lookup_ip(host.0.clone()).await {
+                Ok(ips) => addresses.extend(ips.iter().map(|ip| SocketAddr::new(ip, 53))),
+                Err(e) => tracing::debug!("Cannot resolve nameserver {}: {}", host.0, e),
+            }
+        }
+        if addresses.is_empty() {
+            return Err(Error::DnsResolution {
+                hostname: self.domain_str(),
+                error: "no reachable NS records".to_string(),
+            });
+        }
+        Ok(addresses)
+    }
+
+    /// Every record of the zone, from the opening SOA to the closing one
+    async fn transfer_from(&self, nameserver: SocketAddr) -> Result<Vec<Record>> {
+        let mut query = Message::new();
+        let id = fastrand::u16(..);
+        query
+            .set_id(id)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .add_query(Query::query(self.domain.clone(), RecordType::AXFR));
+        let query = query.to_vec().map_err(dns_error)?;
+
+        let mut stream = TcpStream::connect(nameserver).await?;
+        stream.write_u16(query.len() as u16).await?;
+        stream.write_all(&query).await?;
+
+        let mut records: Vec<Record> = Vec::new();
+        loop {
+            let len = stream.read_u16().await.map_err(|_| {
+                Error::Network("connection closed before the transfer completed".to_string())
+            })?;
+            let mut buffer = vec![0u8; len as usize];
+            stream.read_exact(&mut buffer).await?;
+            let mut response = Message::from_vec(&buffer).map_err(dns_error)?;
+            if response.id() != id {
+                return Err(Error::Network("response to another query".to_string()));
+            }
+            if response.response_code() != ResponseCode::NoError {
+                return Err(Error::Network(format!(
+                    "nameserver answered {}",
+                    response.response_code()
+                )));
+            }
+
+            let answers = response.take_answers();
+            if records.is_empty() && answers.is_empty() {
+                return Err(Error::Network("nameserver sent no records".to_string()));
+            }
+            if records.is_empty() && answers[0].record_type() != RecordType::SOA {
+                return Err(Error::Network(
+                    "transfer did not start with SOA".to_string(),
+                ));
+            }
+            records.extend(answers);
+            let soas = records
+                .iter()
+                .filter(|r| r.record_type() == RecordType::SOA)
+                .count();
+            if soas >= 2 {
+                return Ok(records);
+            }
+        }
+    }
+}
+
+impl ZoneTransferResult {
+    /// The attempt as a finding: `Medium` when the zone was sent, `Info` when refused
+    pub fn finding(&self) -> Finding {
+        let mut evidence = Evidence::new();
+        evidence
+            .data
+            .insert("domain".to_string(), serde_json::json!(self.domain));
+        let refusals: Vec<serde_json::Value> = self
+            .refusals
+            .iter()
+            .map(|(nameserver, reason)| {
+                serde_json::json!({ "nameserver": nameserver.to_string(), "reason": reason })
+            })
+            .collect();
+        evidence
+            .data
+            .insert("refusals".to_string(), serde_json::json!(refusals));
+
+        let mut finding = match self.transferred_by {
+            Some(nameserver) => {
+                evidence
+                    .data
+                    .insert("nameserver".to_string(), serde_json::json!(nameserver.to_string()));
+                evidence
+                    .data
+                    .insert("hosts".to_string(), serde_json::json!(self.hosts));
+                let mut finding = Finding::new(
+                    self.domain.clone(),
+                    ZONE_TRANSFER_TEMPLATE_ID.to_string(),
+                    Severity::Medium,
+                    "DNS zone transfer allowed".to_string(),
+                    format!(
+                        "Nameserver {} sent the whole {} zone ({} hosts) to an unauthenticated AXFR request",
+                        nameserver,
+                        self.domain,
+                        self.hosts.len()
+                    ),
+                );
+                finding.cwe_ids.push("CWE-200".to_string());
+                finding.remediation = Some(
+                    "Allow AXFR only from secondary nameservers, e.g. with allow-transfer or TSIG"
+                        .to_string(),
+                );
+                finding
+            }
+            None => Finding::new(
+                self.domain.clone(),
+                ZONE_TRANSFER_TEMPLATE_ID.to_string(),
+                Severity::Info,
+                "DNS zone transfer refused".to_string(),
+                format!(
+                    "A zone transfer (AXFR) of {} was attempted and none of its {} nameserver(s) sent the zone; this is the expected configuration",
+                    self.domain,
+                    self.refusals.len()
+                ),
+            ),
+        };
+        finding.evidence = evidence;
+        finding.tags = vec!["dns".to_string(), "axfr".to_string()];
+        finding
+    }
+}
+
+/// Names with A, AAAA or CNAME records, sorted, without wildcards
+fn hosts_in(records: &[Record]) -> Vec<String> {
+    records
+        .iter()
+        .filter(|record| {
+            matches!(
+                record.data(),
+                Some(RData::A(_)) | Some(RData::AAAA(_)) | Some(RData::CNAME(_))
+            )
+        })
+        .map(|record| {
+            record
+                .name()
+                .to_ascii()
+                .trim_end_matches('.')
+                .to_lowercase()
+        })
+        .filter(|name| !name.starts_with('*'))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn dns_error(e: trust_dns_proto::error::ProtoError) -> Error {
+    Error::Network(format!("DNS message error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use tokio::net::TcpListener;
+    use trust_dns_proto::rr::rdata::{A, AAAA, CNAME, SOA, TXT};
+
+    fn record(name: &str, data: RData) -> Record {
+        Record::from_rdata(Name::from_str(name).unwrap(), 300, data)
+    }
+
+    fn soa() -> Record {
+        record(
+            "example.com.",
+            RData::SOA(SOA::new(
+                Name::from_str("ns1.example.com.").unwrap(),
+                Name::from_str("admin.example.com.").unwrap(),
+                1,
+                3600,
+                600,
+                86400,
+                300,
+            )),
+        )
+    }
+
+    /// Authoritative nameserver answering one AXFR with `batches` of
+    /// records, one message each, or with `code` when it is not `NoError`
+    async fn nameserver(code: ResponseCode, batches: Vec<Vec<Record>>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let len = stream.read_u16().await.unwrap();
+            let mut buffer = vec![0u8; len as usize];
+            stream.read_exact(&mut buffer).await.unwrap();
+            let query = Message::from_vec(&buffer).unwrap();
+            assert_eq!(query.queries()[0].query_type(), RecordType::AXFR);
+
+            let batches = if code == ResponseCode::NoError {
+                batches
+            } else {
+                vec![Vec::new()]
+            };
+            for batch in batches {
+                let mut response = Message::new();
+                response
+                    .set_id(query.id())
+                    .set_message_type(MessageType::Response)
+                    .set_response_code(code)
+                    .add_query(query.queries()[0].clone())
+                    .insert_answers(batch);
+                let bytes = response.to_vec().unwrap();
+                stream.write_u16(bytes.len() as u16).await.unwrap();
+                stream.write_all(&bytes).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_zone_transfer_reveals_hosts() {
+        let addr = nameserver(
+            ResponseCode::NoError,
+            vec![
+                vec![
+                    soa(),
+                    record("www.example.com.", RData::A(A::new(192, 0, 2, 10))),
+                    record("*.dev.example.com.", RData::A(A::new(192, 0, 2, 11))),
+                    record(
+                        "api.example.com.",
+                        RData::AAAA(AAAA::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+                    ),
+                ],
+                vec![
+                    record(
+                        "shop.example.com.",
+                        RData::CNAME(CNAME(Name::from_str("shops.vendor.net.").unwrap())),
+                    ),
+                    record("example.com.", RData::TXT(TXT::new(vec!["v=spf1".into()]))),
+                    soa(),
+                ],
+            ],
+        )
+        .await;
+
+        let result = ZoneTransfer::new("example.com")
+            .unwrap()
+            .with_nameservers(vec![addr])
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(result.transferred_by, Some(addr));
+        assert_eq!(
+            result.hosts,
+            vec!["api.example.com", "shop.example.com", "www.example.com"]
+        );
+
+        let finding = result.finding();
+        assert_eq!(finding.severity, Severity::Medium);
+        assert_eq!(finding.target, "example.com");
+        assert_eq!(finding.evidence.data["hosts"].as_array().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_refused_zone_transfer_is_info() {
+        let refusing = nameserver(ResponseCode::Refused, Vec::new()).await;
+        // Closes the connection halfway through the zone
+        let truncated = nameserver(ResponseCode::NoError, vec![vec![soa()]]).await;
+
+        let result = ZoneTransfer::new("example.com.")
+            .unwrap()
+            .with_nameservers(vec![refusing, truncated])
+            .with_timeout(Duration::from_secs(2))
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(result.transferred_by, None);
+        assert!(result.hosts.is_empty());
+        assert_eq!(result.refusals.len(), 2);
+        assert!(
+            result.refusals[0].1.contains("Refused"),
+            "{:?}",
+            result.refusals
+        );
+
+        let finding = result.finding();
+        assert_eq!(finding.severity, Severity::Info);
+        assert_eq!(finding.title, "DNS zone transfer refused");
+        assert_eq!(finding.template_id, ZONE_TRANSFER_TEMPLATE_ID);
+    }
+}
@@ -2,13 +2,24 @@
 
 // Note: CLI types are defined in main.rs, not in the library
 // We'll define the search types here instead
+use crate::template::UsageStore;
 use crate::types::{Severity, TemplateLanguage};
-use regex::Regex;
+use regex::RegexBuilder;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::time::SystemTime;
 
+mod index;
+mod semantic;
+mod stats;
+
+pub use index::{file_stamps, fts_query, FileStamp, SearchIndex};
+#[cfg(feature = "semantic")]
+pub use semantic::LocalEmbedder;
+pub use semantic::{default_embedder, template_text, ProviderEmbedder, TextEmbedder};
+pub use stats::{RankedCount, TemplateStatsReport};
+
 /// Search arguments structure
 #[derive(Debug, Clone)]
 pub struct SearchArgs {
@@ -26,12 +37,16 @@ pub struct SearchArgs {
 
     /// CWE identifier if applicable
     pub cwe: Option<String>,
+    /// Only templates that list reference URLs
+    pub has_references: bool,
     /// Search in template content
     pub content: bool,
     /// Case-sensitive search
     pub case_sensitive: bool,
     /// Use regex for search
     pub regex: bool,
+    /// Rank by embedding similarity instead of matching text
+    pub semantic: bool,
     /// Maximum number of results
     pub limit: usize,
     /// Output format
@@ -80,7 +95,7 @@ pub enum SearchSort {
     Author,
     /// Sort by last modified date
     Date,
-    /// Sort by popularity/usage
+    /// Sort by local usage (findings produced, then runs)
     Popularity,
 }
 
@@ -105,14 +120,39 @@ pub struct SearchResult {
 
     /// CWE identifier if applicable
     pub cwe: Option<String>,
+    /// Advisory and PoC URLs
+    #[serde(default)]
+    pub references: Vec<String>,
     /// File system path to template
     pub file_path: String,
     /// Search relevance score (0.0-1.0)
     pub relevance_score: f64,
     /// Fields that matched the search
     pub match_fields: Vec<String>,
-    /// Preview of matching content
+    /// Matching source lines with context, for `--content` searches
     pub content_preview: Option<String>,
+    /// Template is deprecated
+    #[serde(default)]
+    pub deprecated: bool,
+    /// ID of the template that replaces it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub superseded_by: Option<String>,
+    /// Date the template was added, from its `created-date` metadata
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_date: Option<chrono::NaiveDate>,
+}
+
+impl SearchResult {
+    /// `deprecated` or `deprecated, use <id>`, for deprecated templates
+    pub fn deprecation_note(&self) -> Option<String> {
+        if !self.deprecated {
+            return None;
+        }
+        Some(match &self.superseded_by {
+            Some(replacement) => format!("deprecated, use {}", replacement),
+            None => "deprecated".to_string(),
+        })
+    }
 }
 
 /// Search statistics
@@ -128,6 +168,12 @@ pub struct SearchStats {
     pub severities: HashMap<Severity, usize>,
     /// Search execution time in milliseconds
     pub search_time_ms: u64,
+    /// Template count by author, most templates first
+    #[serde(default)]
+    pub authors: Vec<RankedCount>,
+    /// Templates added per month (`YYYY-MM`), counting those with a `created-date`
+    #[serde(default)]
+    pub created_per_month: BTreeMap<String, usize>,
 }
 
 /// Template search engine
@@ -138,14 +184,23 @@ pub struct TemplateSearchEngine {
     index: HashMap<String, Vec<usize>>, // word -> template indices
     #[allow(dead_code)]
     content_index: HashMap<String, Vec<usize>>, // word -> template indices (content)
+    sources: HashMap<String, String>, // file path -> template source
+    usage: UsageStore,
+    fts: Option<SearchIndex>,
+    stamps: Vec<FileStamp>,
+    embedder: Option<Box<dyn TextEmbedder>>,
 }
 
+/// Lines of context shown on each side of a content match
+const SNIPPET_CONTEXT_LINES: usize = 2;
+
 impl TemplateSearchEngine {
     /// Create a new search engine from loaded templates
     pub fn new(templates: Vec<Box<dyn crate::template::Template>>) -> Self {
         let mut search_results = Vec::new();
         let mut index = HashMap::new();
         let mut content_index = HashMap::new();
+        let mut sources = HashMap::new();
 
         for (i, template) in templates.iter().enumerate() {
             let metadata = template.metadata();
@@ -160,18 +215,23 @@ impl TemplateSearchEngine {
                 author: metadata.author.name.clone(),
                 tags: metadata.tags.clone(),
                 cwe: metadata.cwe_ids.first().cloned(),
+                references: metadata.references.clone(),
                 file_path: metadata.file_path.to_string_lossy().to_string(),
                 relevance_score: 0.0,
                 match_fields: Vec::new(),
                 content_preview: None,
+                deprecated: metadata.deprecated,
+                superseded_by: metadata.superseded_by.clone(),
+                created_date: metadata.created_date,
             };
 
             // Build search index
             Self::index_template(&search_result, i, &mut index);
 
-            // Build content index if available
+            // Build content index and keep the source for `--content` searches
             if let Ok(content) = fs::read_to_string(&metadata.file_path) {
                 Self::index_content(&content, i, &mut content_index);
+                sources.insert(search_result.file_path.clone(), content);
             }
 
             search_results.push(search_result);
@@ -181,7 +241,178 @@ impl TemplateSearchEngine {
             templates: search_results,
             index,
             content_index,
+            sources,
+            usage: UsageStore::default(),
+            fts: None,
+            stamps: Vec::new(),
+            embedder: None,
+        }
+    }
+
+    /// Narrow queries through a full-text index, rebuilt whenever any
+    /// template file was added, removed or modified
+    pub fn with_index(mut self, index: SearchIndex) -> Self {
+        self.stamps = file_stamps(self.templates.iter().map(|t| t.file_path.as_str()));
+        self.fts = Some(index);
+        self
+    }
+
+    /// Embed templates and queries with `embedder` for semantic search
+    pub fn with_embedder(mut self, embedder: Box<dyn TextEmbedder>) -> Self {
+        self.embedder = Some(embedder);
+        self
+    }
+
+    /// The `limit` templates most similar in meaning to `query`
+    ///
+    /// Template embeddings are computed on first use and cached in the
+    /// attached index until the template file changes.
+    pub fn semantic_search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> crate::error::Result<Vec<SearchResult>> {
+        let mut results = self.apply_semantic_query(self.templates.clone(), query)?;
+        results.truncate(limit);
+        Ok(results)
+    }
+
+    /// Score `results` by cosine similarity to `query`, most similar first
+    fn apply_semantic_query(
+        &self,
+        mut results: Vec<SearchResult>,
+        query: &str,
+    ) -> crate::error::Result<Vec<SearchResult>> {
+        let similarities = self.similarities(query)?;
+        for result in &mut results {
+            result.relevance_score = similarities
+                .get(&result.file_path)
+                .copied()
+                .unwrap_or_default() as f64;
+            result.match_fields = vec!["semantic".to_string()];
         }
+        results.retain(|result| result.relevance_score > 0.0);
+        results.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+        Ok(results)
+    }
+
+    /// Similarity of every template to `query`, by file path
+    fn similarities(&self, query: &str) -> crate::error::Result<HashMap<String, f32>> {
+        use crate::ai::embedder::cosine_similarity;
+
+        let embedder = self.embedder.as_ref().ok_or_else(|| {
+            crate::error::Error::config("Semantic search needs an embedding backend")
+        })?;
+        let model = embedder.model();
+
+        let mut vectors = match &self.fts {
+            Some(index) => index
+                .cached_embeddings(model, &self.stamps)
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Ignoring cached embeddings: {}", e);
+                    HashMap::new()
+                }),
+            None => HashMap::new(),
+        };
+
+        let missing: Vec<&SearchResult> = self
+            .templates
+            .iter()
+            .filter(|template| !vectors.contains_key(&template.file_path))
+            .collect();
+        if !missing.is_empty() {
+            tracing::info!("Embedding {} templates with {}", missing.len(), model);
+            let texts: Vec<String> = missing.iter().map(|t| template_text(t)).collect();
+            let embedded = embedder.embed(&texts)?;
+
+            if let Some(index) = &self.fts {
+                let stamps: HashMap<&str, i64> = self
+                    .stamps
+                    .iter()
+                    .map(|(path, modified)| (path.as_str(), *modified))
+                    .collect();
+                let entries: Vec<(FileStamp, Vec<f32>)> = missing
+                    .iter()
+                    .zip(&embedded)
+                    .filter_map(|(template, vector)| {
+                        let modified = *stamps.get(template.file_path.as_str())?;
+                        Some(((template.file_path.clone(), modified), vector.clone()))
+                    })
+                    .collect();
+                if let Err(e) = index.store_embeddings(model, &entries) {
+                    tracing::warn!("Failed to cache embeddings: {}", e);
+                }
+            }
+
+            for (template, vector) in missing.into_iter().zip(embedded) {
+                vectors.insert(template.file_path.clone(), vector);
+            }
+        }
+
+        let query_vector = embedder
+            .embed(&[query.to_string()])?
+            .pop()
+            .unwrap_or_default();
+        Ok(vectors
+            .into_iter()
+            .map(|(path, vector)| {
+                let similarity = cosine_similarity(&query_vector, &vector);
+                (path, similarity)
+            })
+            .collect())
+    }
+
+    /// Whether the attached index no longer matches the loaded templates
+    pub fn index_is_stale(&self) -> bool {
+        let Some(index) = &self.fts else {
+            return false;
+        };
+        match index.is_fresh(&self.stamps) {
+            Ok(fresh) => !fresh,
+            Err(e) => {
+                tracing::warn!("{}", e);
+                true
+            }
+        }
+    }
+
+    /// Rebuild the attached index from the loaded templates
+    pub fn rebuild_index(&self) -> crate::error::Result<()> {
+        let Some(index) = &self.fts else {
+            return Ok(());
+        };
+        let start_time = SystemTime::now();
+        index.rebuild(
+            &self.templates,
+            |path| self.sources.get(path).map(String::as_str),
+            &self.stamps,
+        )?;
+        tracing::info!(
+            "Rebuilt search index for {} templates in {}ms",
+            self.templates.len(),
+            start_time.elapsed().unwrap_or_default().as_millis()
+        );
+        Ok(())
+    }
+
+    /// File paths the index matches for `query`, or `None` when the query
+    /// has to be checked against every template
+    fn index_candidates(&self, query: &str, args: &SearchArgs) -> Option<HashSet<String>> {
+        if args.regex {
+            return None;
+        }
+        let index = self.fts.as_ref()?;
+        let fts_query = fts_query(query, args.content)?;
+        index
+            .query(&fts_query)
+            .map_err(|e| tracing::warn!("Falling back to a full scan: {}", e))
+            .ok()
+    }
+
+    /// Rank `SearchSort::Popularity` by these usage counters
+    pub fn with_usage(mut self, usage: UsageStore) -> Self {
+        self.usage = usage;
+        self
     }
 
     /// Index a template for search
@@ -229,6 +460,12 @@ impl TemplateSearchEngine {
     pub fn search(&self, args: &SearchArgs) -> (Vec<SearchResult>, SearchStats) {
         let start_time = SystemTime::now();
 
+        if self.index_is_stale() {
+            if let Err(e) = self.rebuild_index() {
+                tracing::warn!("Searching without the index: {}", e);
+            }
+        }
+
         let mut results = self.templates.clone();
         let total_templates = results.len();
 
@@ -237,7 +474,17 @@ impl TemplateSearchEngine {
 
         // Apply search query
         if let Some(query) = &args.query {
-            results = self.apply_search_query(results, query, args);
+            results = if args.semantic {
+                match self.apply_semantic_query(results.clone(), query) {
+                    Ok(ranked) => ranked,
+                    Err(e) => {
+                        tracing::warn!("Falling back to text search: {}", e);
+                        self.apply_search_query(results, query, args)
+                    }
+                }
+            } else {
+                self.apply_search_query(results, query, args)
+            };
         }
 
         // Sort results
@@ -255,6 +502,18 @@ impl TemplateSearchEngine {
         (results, stats)
     }
 
+    /// Statistics over every loaded template, for `template stats`
+    pub fn stats_report(&self) -> TemplateStatsReport {
+        let start_time = SystemTime::now();
+        let total_templates = self.templates.len();
+        let stats = self.calculate_stats(
+            &self.templates,
+            total_templates,
+            start_time.elapsed().unwrap_or_default().as_millis() as u64,
+        );
+        TemplateStatsReport::new(stats, &self.templates)
+    }
+
     /// Apply filters to search results
     fn apply_filters(
         &self,
@@ -294,6 +553,10 @@ impl TemplateSearchEngine {
             results.retain(|template| template.cwe.as_ref().map_or(false, |t| t.contains(cwe)));
         }
 
+        if args.has_references {
+            results.retain(|template| !template.references.is_empty());
+        }
+
         results
     }
 
@@ -304,15 +567,36 @@ impl TemplateSearchEngine {
         query: &str,
         args: &SearchArgs,
     ) -> Vec<SearchResult> {
+        // The trigram index finds the same substrings scored below, except for
+        // queries spanning two tags, which the index stores comma-joined
+        if let Some(candidates) = self.index_candidates(query, args) {
+            results.retain(|result| candidates.contains(&result.file_path));
+        }
+
         let query_lower = if args.case_sensitive {
             query.to_string()
         } else {
             query.to_lowercase()
         };
 
-        // Create regex if requested
+        // Create regex if requested; an invalid pattern falls back to substring matching
         let regex = if args.regex {
-            Regex::new(&query_lower).ok()
+            RegexBuilder::new(query)
+                .case_insensitive(!args.case_sensitive)
+                .build()
+                .ok()
+        } else {
+            None
+        };
+
+        // Template sources are matched with the query itself, escaped unless it is a regex
+        let content_regex = if args.content {
+            regex.clone().or_else(|| {
+                RegexBuilder::new(&regex::escape(query))
+                    .case_insensitive(!args.case_sensitive)
+                    .build()
+                    .ok()
+            })
         } else {
             None
         };
@@ -355,35 +639,16 @@ impl TemplateSearchEngine {
             }
 
             // Search in content if requested
-            if args.content {
-                if let Ok(content) = fs::read_to_string(&result.file_path) {
-                    let content_lower = if args.case_sensitive {
-                        content.clone()
-                    } else {
-                        content.to_lowercase()
-                    };
-
-                    if let Some(regex) = &regex {
-                        if regex.is_match(&content_lower) {
-                            score += 2.0;
-                            match_fields.push("content".to_string());
-
-                            // Add content preview
-                            if result.content_preview.is_none() {
-                                result.content_preview =
-                                    Some(self.extract_preview(&content, &query_lower));
-                            }
-                        }
-                    } else if content_lower.contains(&query_lower) {
-                        score += 1.0;
-                        match_fields.push("content".to_string());
-
-                        // Add content preview
-                        if result.content_preview.is_none() {
-                            result.content_preview =
-                                Some(self.extract_preview(&content, &query_lower));
-                        }
-                    }
+            let content_match = content_regex.as_ref().and_then(|content_regex| {
+                let source = self.sources.get(&result.file_path)?;
+                let found = content_regex.find(source)?;
+                Some(Self::content_snippet(source, found.start(), found.end()))
+            });
+            if let Some(snippet) = content_match {
+                score += if regex.is_some() { 2.0 } else { 1.0 };
+                match_fields.push("content".to_string());
+                if result.content_preview.is_none() {
+                    result.content_preview = Some(snippet);
                 }
             }
 
@@ -397,23 +662,33 @@ impl TemplateSearchEngine {
         results
     }
 
-    /// Extract a preview of content around the search term
-    fn extract_preview(&self, content: &str, query: &str) -> String {
-        const PREVIEW_LENGTH: usize = 200;
-
-        if let Some(pos) = content.to_lowercase().find(&query.to_lowercase()) {
-            let start = pos.saturating_sub(PREVIEW_LENGTH / 2);
-            let end = (pos + query.len() + PREVIEW_LENGTH / 2).min(content.len());
-            let preview = &content[start..end];
-
-            if start > 0 {
-                format!("...{}...", preview)
-            } else {
-                format!("{}...", preview)
-            }
-        } else {
-            content.chars().take(PREVIEW_LENGTH).collect::<String>() + "..."
-        }
+    /// The lines of `source` holding `start..end`, with line numbers and
+    /// [`SNIPPET_CONTEXT_LINES`] lines either side; matched lines are marked `>`
+    fn content_snippet(source: &str, start: usize, end: usize) -> String {
+        let first = source[..start].matches('\n').count();
+        let last = first
+            + source[start..end]
+                .trim_end_matches('\n')
+                .matches('\n')
+                .count();
+        let from = first.saturating_sub(SNIPPET_CONTEXT_LINES);
+        let width = (last + SNIPPET_CONTEXT_LINES + 1).to_string().len();
+
+        source
+            .lines()
+            .enumerate()
+            .skip(from)
+            .take(last + SNIPPET_CONTEXT_LINES + 1 - from)
+            .map(|(n, line)| {
+                let marker = if (first..=last).contains(&n) {
+                    '>'
+                } else {
+                    ' '
+                };
+                format!("{} {:>width$} | {}", marker, n + 1, line, width = width)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 
     /// Sort results based on the specified criteria
@@ -465,8 +740,17 @@ impl TemplateSearchEngine {
                 });
             }
             SearchSort::Popularity => {
-                // For now, sort by relevance score as a proxy for popularity
-                results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
+                // Never-run templates go last, ordered by relevance
+                let unused = Default::default();
+                results.sort_by(|a, b| {
+                    let a_usage = self.usage.get(&a.id).unwrap_or(&unused);
+                    let b_usage = self.usage.get(&b.id).unwrap_or(&unused);
+                    b_usage.cmp_popularity(a_usage).then(
+                        b.relevance_score
+                            .partial_cmp(&a.relevance_score)
+                            .unwrap_or(std::cmp::Ordering::Equal),
+                    )
+                });
             }
         }
 
@@ -486,10 +770,18 @@ impl TemplateSearchEngine {
     ) -> SearchStats {
         let mut languages = HashMap::new();
         let mut severities = HashMap::new();
+        let mut authors = HashMap::new();
+        let mut created_per_month = BTreeMap::new();
 
         for result in results {
             *languages.entry(result.language).or_insert(0) += 1;
             *severities.entry(result.severity).or_insert(0) += 1;
+            *authors.entry(result.author.as_str()).or_insert(0) += 1;
+            if let Some(date) = result.created_date {
+                *created_per_month
+                    .entry(date.format("%Y-%m").to_string())
+                    .or_insert(0) += 1;
+            }
         }
 
         SearchStats {
@@ -498,6 +790,8 @@ impl TemplateSearchEngine {
             languages,
             severities,
             search_time_ms,
+            authors: RankedCount::ranked(authors),
+            created_per_month,
         }
     }
 }
@@ -561,6 +855,9 @@ impl SearchResultFormatter {
                     result.tags.join(", "),
                     result.relevance_score
                 ));
+                if let Some(note) = result.deprecation_note() {
+                    output.push_str(&format!("│ Status: {:<70} │\n", note));
+                }
                 if !result.match_fields.is_empty() {
                     output.push_str(&format!(
                         "│ Matched in: {:<66} │\n",
@@ -568,10 +865,13 @@ impl SearchResultFormatter {
                     ));
                 }
                 if let Some(preview) = &result.content_preview {
-                    output.push_str(&format!(
-                        "│ Preview: {:<68} │\n",
-                        preview.chars().take(70).collect::<String>()
-                    ));
+                    output.push_str(&format!("│ Preview: {:<68} │\n", ""));
+                    for line in preview.lines() {
+                        output.push_str(&format!(
+                            "│   {:<74} │\n",
+                            line.chars().take(74).collect::<String>()
+                        ));
+                    }
                 }
                 output.push_str("├─────────────────────────────────────────────────────────────────────────────────────────────────┤\n");
             }
@@ -583,10 +883,15 @@ impl SearchResultFormatter {
             output.push_str("├─────────────────────────────────────────────────────────────────────────────────────────────────┤\n");
 
             for result in results {
+                let name = if result.deprecated {
+                    format!("[deprecated] {}", result.name)
+                } else {
+                    result.name.clone()
+                };
                 output.push_str(&format!(
                     "│ {:<20} │ {:<28} │ {:<10} │ {:<8} │ {:<5.2} │\n",
                     result.id,
-                    result.name.chars().take(28).collect::<String>(),
+                    name.chars().take(28).collect::<String>(),
                     format!("{:?}", result.language),
                     format!("{:?}", result.severity),
                     result.relevance_score
@@ -653,9 +958,13 @@ impl SearchResultFormatter {
         } else {
             for result in results {
                 output.push_str(&format!(
-                    "{} - {} ({:?}, {:?})\n",
+                    "{} - {} ({:?}, {:?})",
                     result.id, result.name, result.language, result.severity
                 ));
+                if let Some(note) = result.deprecation_note() {
+                    output.push_str(&format!(" [{}]", note));
+                }
+                output.push('\n');
             }
         }
 
@@ -724,8 +1033,12 @@ impl SearchResultFormatter {
                 result.match_fields.join(", ")
             ));
 
+            if let Some(note) = result.deprecation_note() {
+                output.push_str(&format!("Status: {}\n", note));
+            }
+
             if let Some(preview) = &result.content_preview {
-                output.push_str(&format!("Content Preview: {}\n", preview));
+                output.push_str(&format!("Content Preview:\n{}\n", preview));
             }
 
             output.push_str("\n");
@@ -747,6 +1060,11 @@ mod tests {
             templates: Vec::new(),
             index: HashMap::new(),
             content_index: HashMap::new(),
+            sources: HashMap::new(),
+            usage: UsageStore::default(),
+            fts: None,
+            stamps: Vec::new(),
+            embedder: None,
         };
         assert_eq!(engine.templates.len(), 0);
     }
@@ -762,18 +1080,417 @@ mod tests {
     }
 
     #[test]
-    fn test_content_preview_extraction() {
+    fn test_content_snippet_shows_surrounding_lines() {
+        let source = "one\ntwo\nthree\nfour\nfive\nsix\nseven\n";
+        let start = source.find("four").unwrap();
+        assert_eq!(
+            TemplateSearchEngine::content_snippet(source, start, start + 4),
+            "  2 | two\n  3 | three\n> 4 | four\n  5 | five\n  6 | six"
+        );
+        assert_eq!(
+            TemplateSearchEngine::content_snippet(source, 0, 7),
+            "> 1 | one\n> 2 | two\n  3 | three\n  4 | four"
+        );
+    }
+
+    #[test]
+    fn test_list_format_shows_deprecation() {
+        let result = |id: &str, superseded_by: Option<&str>| SearchResult {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            language: TemplateLanguage::Yaml,
+            severity: Severity::Info,
+            author: String::new(),
+            tags: Vec::new(),
+            cwe: None,
+            references: Vec::new(),
+            file_path: String::new(),
+            relevance_score: 0.0,
+            match_fields: Vec::new(),
+            content_preview: None,
+            deprecated: superseded_by.is_some(),
+            superseded_by: superseded_by.map(str::to_string),
+            created_date: None,
+        };
+        let results = vec![
+            result("old-check", Some("new-check")),
+            result("new-check", None),
+        ];
+        let stats = SearchStats {
+            total_templates: 2,
+            matching_templates: 2,
+            languages: HashMap::new(),
+            severities: HashMap::new(),
+            search_time_ms: 0,
+            authors: Vec::new(),
+            created_per_month: BTreeMap::new(),
+        };
+
+        let output = SearchResultFormatter::format_results(
+            &results,
+            &stats,
+            SearchFormat::List,
+            false,
+            false,
+        );
+        assert_eq!(
+            output,
+            "old-check - old-check (Yaml, Info) [deprecated, use new-check]\n\
+             new-check - new-check (Yaml, Info)\n"
+        );
+    }
+
+    #[test]
+    fn test_popularity_sort_uses_usage_counters() {
+        let result = |id: &str| SearchResult {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            language: TemplateLanguage::Yaml,
+            severity: Severity::Info,
+            author: String::new(),
+            tags: Vec::new(),
+            cwe: None,
+            references: Vec::new(),
+            file_path: String::new(),
+            relevance_score: 0.0,
+            match_fields: Vec::new(),
+            content_preview: None,
+            deprecated: false,
+            superseded_by: None,
+            created_date: None,
+        };
+        let mut usage = UsageStore::default();
+        usage.templates.insert(
+            "noisy".to_string(),
+            crate::template::TemplateUsage {
+                executions: 50,
+                findings: 1,
+                last_used: None,
+            },
+        );
+        usage.templates.insert(
+            "productive".to_string(),
+            crate::template::TemplateUsage {
+                executions: 10,
+                findings: 8,
+                last_used: None,
+            },
+        );
         let engine = TemplateSearchEngine {
-            templates: Vec::new(),
+            templates: vec![result("never-run"), result("noisy"), result("productive")],
             index: HashMap::new(),
             content_index: HashMap::new(),
+            sources: HashMap::new(),
+            usage: UsageStore::default(),
+            fts: None,
+            stamps: Vec::new(),
+            embedder: None,
+        }
+        .with_usage(usage);
+
+        let args = SearchArgs {
+            query: None,
+            language: None,
+            severity: None,
+            tags: None,
+            author: None,
+            cwe: None,
+            has_references: false,
+            content: false,
+            case_sensitive: false,
+            regex: false,
+            semantic: false,
+            limit: 10,
+            format: SearchFormat::List,
+            detailed: false,
+            sort: SearchSort::Popularity,
+            reverse: false,
+            ids_only: true,
+            stats: false,
         };
+        let (results, _) = engine.search(&args);
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["productive", "noisy", "never-run"]);
+    }
+
+    fn source_result(id: &str) -> SearchResult {
+        SearchResult {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            language: TemplateLanguage::Python,
+            severity: Severity::Info,
+            author: String::new(),
+            tags: Vec::new(),
+            cwe: None,
+            references: Vec::new(),
+            file_path: format!("templates/{}.py", id),
+            relevance_score: 0.0,
+            match_fields: Vec::new(),
+            content_preview: None,
+            deprecated: false,
+            superseded_by: None,
+            created_date: None,
+        }
+    }
+
+    fn content_args(query: &str, regex: bool, case_sensitive: bool) -> SearchArgs {
+        SearchArgs {
+            query: Some(query.to_string()),
+            language: None,
+            severity: None,
+            tags: None,
+            author: None,
+            cwe: None,
+            has_references: false,
+            content: true,
+            case_sensitive,
+            regex,
+            semantic: false,
+            limit: 2000,
+            format: SearchFormat::List,
+            detailed: false,
+            sort: SearchSort::Name,
+            reverse: false,
+            ids_only: true,
+            stats: false,
+        }
+    }
+
+    /// 1000 templates; every tenth checks `X-Frame-Options`, the rest call `requests.get`
+    fn content_fixture() -> TemplateSearchEngine {
+        let mut templates = Vec::new();
+        let mut sources = HashMap::new();
+        for i in 0..1000 {
+            let result = source_result(&format!("check-{:04}", i));
+            let check = if i % 10 == 0 {
+                "    if 'X-Frame-Options' not in resp.headers:"
+            } else {
+                "    resp = requests.get(url, timeout=10)"
+            };
+            let mut source = String::from("import requests\n\n");
+            for line in 0..40 {
+                source.push_str(&format!("# filler line {} of the template body\n", line));
+            }
+            source.push_str(&format!(
+                "def check(url):\n{}\n        return True\n",
+                check
+            ));
+            sources.insert(result.file_path.clone(), source);
+            templates.push(result);
+        }
+        TemplateSearchEngine {
+            templates,
+            index: HashMap::new(),
+            content_index: HashMap::new(),
+            sources,
+            usage: UsageStore::default(),
+            fts: None,
+            stamps: Vec::new(),
+            embedder: None,
+        }
+    }
 
-        let content = "This is a long content with the word injection in the middle of the text and more content after it";
-        let query = "injection";
-        let preview = engine.extract_preview(content, query);
+    #[test]
+    fn test_content_search_matches_source_code() {
+        let engine = content_fixture();
+
+        let (results, _) = engine.search(&content_args("x-frame-options", false, false));
+        assert_eq!(results.len(), 100);
+        assert_eq!(results[0].match_fields, vec!["content"]);
+        assert_eq!(
+            results[0].content_preview.as_deref(),
+            Some(
+                "  42 | # filler line 39 of the template body\n  \
+                   43 | def check(url):\n> \
+                   44 |     if 'X-Frame-Options' not in resp.headers:\n  \
+                   45 |         return True"
+            )
+        );
+
+        let (results, _) = engine.search(&content_args("x-frame-options", false, true));
+        assert!(results.is_empty());
+
+        let (results, _) = engine.search(&content_args(r"requests\.get\(url", true, true));
+        assert_eq!(results.len(), 900);
+
+        // Metadata matches still count without --content
+        let mut args = content_args("requests.get", false, false);
+        args.content = false;
+        assert!(engine.search(&args).0.is_empty());
+    }
+
+    #[test]
+    fn test_content_search_reads_no_template_files() {
+        // None of the fixture's files exist, so every match comes from the loaded sources
+        let engine = content_fixture();
+        assert!(engine
+            .templates
+            .iter()
+            .all(|t| !std::path::Path::new(&t.file_path).exists()));
+
+        for (mut args, expected) in [
+            (content_args("X-Frame-Options", false, false), 100),
+            (content_args("requests.get", false, true), 900),
+            (content_args(r"requests\.(get|post)\(", true, false), 900),
+        ] {
+            args.limit = 1000;
+            let (results, _) = engine.search(&args);
+            assert_eq!(results.len(), expected, "query {:?}", args.query);
+            assert!(results.iter().all(|r| r.content_preview.is_some()));
+        }
+    }
+
+    #[test]
+    #[ignore] // Only run with --ignored to time content search against a pre-built index
+    fn test_content_search_of_1000_templates_is_fast() {
+        let engine = content_fixture().with_index(SearchIndex::open_in_memory().unwrap());
+        engine.rebuild_index().unwrap();
+        for args in [
+            content_args("X-Frame-Options", false, false),
+            content_args("requests.get", false, true),
+            content_args(r"requests\.(get|post)\(", true, false),
+        ] {
+            let start = std::time::Instant::now();
+            let (results, _) = engine.search(&args);
+            assert!(!results.is_empty());
+            assert!(
+                start.elapsed() < std::time::Duration::from_secs(2),
+                "content search took {:?}",
+                start.elapsed()
+            );
+        }
+    }
+
+    #[test]
+    fn test_indexed_search_matches_full_scan() {
+        let scan = content_fixture();
+        let indexed = content_fixture().with_index(SearchIndex::open_in_memory().unwrap());
+        assert!(indexed.index_is_stale());
+
+        for args in [
+            content_args("x-frame-options", false, false),
+            content_args("X-Frame-Options", false, true),
+            content_args("requests.get", false, false),
+            content_args(r"requests\.get\(url", true, true),
+            content_args("ch", false, false),
+        ] {
+            let ids = |engine: &TemplateSearchEngine| {
+                let mut ids: Vec<_> = engine.search(&args).0.into_iter().map(|r| r.id).collect();
+                ids.sort();
+                ids
+            };
+            assert_eq!(ids(&indexed), ids(&scan), "query {:?}", args.query);
+        }
+        assert!(!indexed.index_is_stale());
+    }
+
+    /// Maps words onto a few concepts, standing in for a real model
+    #[derive(Debug, Default)]
+    struct ConceptEmbedder {
+        embedded: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
 
-        assert!(preview.contains("injection"));
-        assert!(preview.len() <= 200 + 6); // 200 chars + "..."
+    impl TextEmbedder for ConceptEmbedder {
+        fn model(&self) -> &str {
+            "concepts"
+        }
+
+        fn embed(&self, texts: &[String]) -> crate::error::Result<Vec<Vec<f32>>> {
+            const CONCEPTS: [&[&str]; 3] = [
+                &["sql", "sqli", "injection", "query"],
+                &["redis", "cache", "unauthenticated"],
+                &["login", "auth", "bypass"],
+            ];
+            self.embedded
+                .fetch_add(texts.len(), std::sync::atomic::Ordering::SeqCst);
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let words = TemplateSearchEngine::extract_words(text);
+                    CONCEPTS
+                        .iter()
+                        .map(|concept| {
+                            words
+                                .iter()
+                                .filter(|word| concept.contains(&word.as_str()))
+                                .count() as f32
+                        })
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    fn semantic_fixture() -> TemplateSearchEngine {
+        let mut sqli = source_result("sqli-login-bypass");
+        sqli.name = "SQLi login bypass".to_string();
+        sqli.tags = vec!["sqli".to_string(), "auth".to_string()];
+        let mut redis = source_result("redis-open");
+        redis.name = "Redis unauthenticated".to_string();
+        redis.tags = vec!["redis".to_string(), "cache".to_string()];
+
+        TemplateSearchEngine {
+            templates: vec![sqli, redis],
+            index: HashMap::new(),
+            content_index: HashMap::new(),
+            sources: HashMap::new(),
+            usage: UsageStore::default(),
+            fts: None,
+            stamps: Vec::new(),
+            embedder: None,
+        }
+    }
+
+    #[test]
+    fn test_semantic_search_finds_what_text_search_misses() {
+        let embedder = ConceptEmbedder::default();
+        let embedded = embedder.embedded.clone();
+        let engine = semantic_fixture()
+            .with_index(SearchIndex::open_in_memory().unwrap())
+            .with_embedder(Box::new(embedder));
+
+        let mut args = content_args("SQL injection", false, false);
+        args.content = false;
+        assert!(engine.search(&args).0.is_empty());
+
+        let results = engine.semantic_search("SQL injection", 5).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "sqli-login-bypass");
+        assert_eq!(results[0].match_fields, vec!["semantic"]);
+
+        args.semantic = true;
+        let (results, _) = engine.search(&args);
+        assert_eq!(results[0].id, "sqli-login-bypass");
+
+        // Template vectors come from the index after the first search
+        assert_eq!(
+            embedded.load(std::sync::atomic::Ordering::SeqCst),
+            2 + 1 + 1
+        );
+    }
+
+    #[test]
+    fn test_semantic_search_without_embedder_falls_back_to_text() {
+        let engine = semantic_fixture();
+        assert!(engine.semantic_search("redis", 5).is_err());
+
+        let mut args = content_args("redis", false, false);
+        args.semantic = true;
+        let (results, _) = engine.search(&args);
+        assert_eq!(results[0].id, "redis-open");
+    }
+
+    /// Downloads the local model on first run
+    #[cfg(feature = "semantic")]
+    #[test]
+    #[ignore]
+    fn test_local_model_relates_sqli_and_sql_injection() {
+        let engine = semantic_fixture().with_embedder(Box::new(LocalEmbedder::new().unwrap()));
+        let results = engine.semantic_search("SQL injection", 2).unwrap();
+        assert_eq!(results[0].id, "sqli-login-bypass");
+        assert!(results[0].relevance_score > results[1].relevance_score);
     }
 }
@@ -0,0 +1,393 @@
+//! SQLite FTS5 index over template metadata and source
+//!
+//! The index lives in `~/.cert-x-gen/cache/search-index.db` and records the
+//! modification time of every template file it was built from, so a search
+//! can tell when any template changed and rebuild before querying. Template
+//! embeddings for semantic search are cached alongside, per model and file
+//! modification time.
+
+use super::SearchResult;
+use crate::error::{Error, Result};
+use crate::template::PathResolver;
+use rusqlite::{params, Connection};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Bumped whenever the table layout changes, forcing a rebuild
+const SCHEMA_VERSION: i64 = 2;
+
+/// Shortest query the trigram tokenizer can match
+const MIN_QUERY_CHARS: usize = 3;
+
+/// A template file path and its modification time in nanoseconds
+pub type FileStamp = (String, i64);
+
+/// Full-text index of the template library
+#[derive(Debug)]
+pub struct SearchIndex {
+    conn: Connection,
+}
+
+impl SearchIndex {
+    /// Default index location, `~/.cert-x-gen/cache/search-index.db`
+    pub fn default_path() -> PathBuf {
+        PathResolver::cache_dir().join("search-index.db")
+    }
+
+    /// Open the index at the default location
+    pub fn open_default() -> Result<Self> {
+        Self::open(&Self::default_path())
+    }
+
+    /// Open or create the index at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path).map_err(|e| {
+            Error::Cache(format!(
+                "Failed to open search index {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::with_connection(conn)
+    }
+
+    /// An index that lives only as long as the returned value
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| Error::Cache(format!("Failed to open search index: {}", e)))?;
+        Self::with_connection(conn)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        let version: i64 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(cache_error)?;
+        if version != SCHEMA_VERSION {
+            conn.execute_batch(&format!(
+                "DROP TABLE IF EXISTS templates;
+                 DROP TABLE IF EXISTS files;
+                 DROP TABLE IF EXISTS embeddings;
+                 CREATE VIRTUAL TABLE templates USING fts5(
+                     file_path UNINDEXED, id, name, description, tags, author,
+                     language, severity, content, tokenize = 'trigram'
+                 );
+                 CREATE TABLE files (path TEXT PRIMARY KEY, modified INTEGER NOT NULL);
+                 CREATE TABLE embeddings (
+                     path TEXT NOT NULL, model TEXT NOT NULL, modified INTEGER NOT NULL,
+                     vector BLOB NOT NULL, PRIMARY KEY (path, model)
+                 );
+                 PRAGMA user_version = {};",
+                SCHEMA_VERSION
+            ))
+            .map_err(cache_error)?;
+        }
+        Ok(Self { conn })
+    }
+
+    /// Whether the index was built from exactly these files at these times
+    pub fn is_fresh(&self, files: &[FileStamp]) -> Result<bool> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, modified FROM files")
+            .map_err(cache_error)?;
+        let indexed = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(cache_error)?
+            .collect::<std::result::Result<HashSet<FileStamp>, _>>()
+            .map_err(cache_error)?;
+
+        Ok(indexed.len() == files.len() && files.iter().all(|file| indexed.contains(file)))
+    }
+
+    /// Replace the index contents with `templates`
+    ///
+    /// `sources` looks up a template's source by file path; `files` is
+    /// recorded for later [`is_fresh`](Self::is_fresh) checks.
+    pub fn rebuild<'a>(
+        &self,
+        templates: &[SearchResult],
+        sources: impl Fn(&str) -> Option<&'a str>,
+        files: &[FileStamp],
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction().map_err(cache_error)?;
+        tx.execute_batch("DELETE FROM templates; DELETE FROM files;")
+            .map_err(cache_error)?;
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO templates
+                     (file_path, id, name, description, tags, author, language, severity, content)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                )
+                .map_err(cache_error)?;
+            for template in templates {
+                insert
+                    .execute(params![
+                        template.file_path,
+                        template.id,
+                        template.name,
+                        template.description,
+                        template.tags.join(","),
+                        template.author,
+                        template.language.to_string(),
+                        template.severity.to_string(),
+                        sources(&template.file_path).unwrap_or_default(),
+                    ])
+                    .map_err(cache_error)?;
+            }
+
+            let mut stamp = tx
+                .prepare("INSERT OR REPLACE INTO files (path, modified) VALUES (?1, ?2)")
+                .map_err(cache_error)?;
+            for (path, modified) in files {
+                stamp
+                    .execute(params![path, modified])
+                    .map_err(cache_error)?;
+            }
+        }
+        tx.commit().map_err(cache_error)
+    }
+
+    /// File paths of the templates matching an FTS5 `MATCH` expression
+    pub fn query(&self, fts_query: &str) -> Result<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT file_path FROM templates WHERE templates MATCH ?1")
+            .map_err(cache_error)?;
+        let paths = stmt
+            .query_map([fts_query], |row| row.get(0))
+            .map_err(cache_error)?
+            .collect::<std::result::Result<HashSet<String>, _>>()
+            .map_err(cache_error)?;
+        Ok(paths)
+    }
+
+    /// Cached `model` embeddings of the files whose stamp still matches
+    pub fn cached_embeddings(
+        &self,
+        model: &str,
+        files: &[FileStamp],
+    ) -> Result<HashMap<String, Vec<f32>>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, modified, vector FROM embeddings WHERE model = ?1")
+            .map_err(cache_error)?;
+        let rows = stmt
+            .query_map([model], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, Vec<u8>>(2)?,
+                ))
+            })
+            .map_err(cache_error)?;
+
+        let current: HashMap<&str, i64> = files
+            .iter()
+            .map(|(path, modified)| (path.as_str(), *modified))
+            .collect();
+        let mut cached = HashMap::new();
+        for row in rows {
+            let (path, modified, blob) = row.map_err(cache_error)?;
+            if current.get(path.as_str()) == Some(&modified) {
+                cached.insert(path, decode_vector(&blob));
+            }
+        }
+        Ok(cached)
+    }
+
+    /// Cache `model` embeddings, replacing older ones for the same files
+    pub fn store_embeddings(
+        &self,
+        model: &str,
+        embeddings: &[(FileStamp, Vec<f32>)],
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction().map_err(cache_error)?;
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT OR REPLACE INTO embeddings (path, model, modified, vector)
+                     VALUES (?1, ?2, ?3, ?4)",
+                )
+                .map_err(cache_error)?;
+            for ((path, modified), vector) in embeddings {
+                insert
+                    .execute(params![path, model, modified, encode_vector(vector)])
+                    .map_err(cache_error)?;
+            }
+        }
+        tx.commit().map_err(cache_error)
+    }
+}
+
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+fn decode_vector(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+/// Modification stamps for template files; unreadable files stamp as `-1`
+pub fn file_stamps<'a>(paths: impl IntoIterator<Item = &'a str>) -> Vec<FileStamp> {
+    paths
+        .into_iter()
+        .map(|path| {
+            let modified = std::fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(-1, |age| age.as_nanos() as i64);
+            (path.to_string(), modified)
+        })
+        .collect()
+}
+
+/// Translate a plain search query into an FTS5 `MATCH` expression
+///
+/// The query becomes a single quoted phrase, which the trigram tokenizer
+/// matches as a case-insensitive substring, restricted to the metadata
+/// columns plus `content` when searching source. Queries shorter than three
+/// characters cannot be matched by trigrams and yield `None`.
+pub fn fts_query(query: &str, content: bool) -> Option<String> {
+    if query.chars().count() < MIN_QUERY_CHARS {
+        return None;
+    }
+    let columns = if content {
+        "id name description tags author content"
+    } else {
+        "id name description tags author"
+    };
+    Some(format!(
+        "{{{}}} : \"{}\"",
+        columns,
+        query.replace('"', "\"\"")
+    ))
+}
+
+fn cache_error(e: rusqlite::Error) -> Error {
+    Error::Cache(format!("Search index error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Severity, TemplateLanguage};
+
+    fn result(id: &str, name: &str, path: &str) -> SearchResult {
+        SearchResult {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: String::new(),
+            language: TemplateLanguage::Yaml,
+            severity: Severity::High,
+            author: "tester".to_string(),
+            tags: vec!["redis".to_string(), "database".to_string()],
+            cwe: None,
+            references: Vec::new(),
+            file_path: path.to_string(),
+            relevance_score: 0.0,
+            match_fields: Vec::new(),
+            content_preview: None,
+            deprecated: false,
+            superseded_by: None,
+            created_date: None,
+        }
+    }
+
+    #[test]
+    fn test_fts_query_translation() {
+        assert_eq!(
+            fts_query("redis", false).unwrap(),
+            "{id name description tags author} : \"redis\""
+        );
+        assert_eq!(
+            fts_query("say \"hi\"", true).unwrap(),
+            "{id name description tags author content} : \"say \"\"hi\"\"\""
+        );
+        assert!(fts_query("ab", false).is_none());
+    }
+
+    #[test]
+    fn test_query_matches_substrings_case_insensitively() {
+        let index = SearchIndex::open_in_memory().unwrap();
+        let templates = [
+            result("redis-unauth", "Redis Unauthenticated", "a.yaml"),
+            result("mysql-default", "MySQL Default Login", "b.yaml"),
+        ];
+        let source = |path: &str| (path == "b.yaml").then_some("password = 'root'");
+        index.rebuild(&templates, source, &[]).unwrap();
+
+        let hits = index.query(&fts_query("sql", false).unwrap()).unwrap();
+        assert_eq!(hits, HashSet::from(["b.yaml".to_string()]));
+        let hits = index.query(&fts_query("UNAUTH", false).unwrap()).unwrap();
+        assert_eq!(hits, HashSet::from(["a.yaml".to_string()]));
+
+        // Source only matches when content is searched
+        assert!(index
+            .query(&fts_query("password", false).unwrap())
+            .unwrap()
+            .is_empty());
+        let hits = index.query(&fts_query("password", true).unwrap()).unwrap();
+        assert_eq!(hits, HashSet::from(["b.yaml".to_string()]));
+    }
+
+    #[test]
+    fn test_freshness_tracks_modification_times() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("check.yaml");
+        std::fs::write(&path, "id: check\n").unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let index = SearchIndex::open(&dir.path().join("index.db")).unwrap();
+        let stamps = file_stamps([path.as_str()]);
+        assert!(!index.is_fresh(&stamps).unwrap());
+
+        index
+            .rebuild(&[result("check", "Check", &path)], |_| None, &stamps)
+            .unwrap();
+        assert!(index.is_fresh(&stamps).unwrap());
+
+        // Reopening keeps the recorded stamps
+        let index = SearchIndex::open(&dir.path().join("index.db")).unwrap();
+        assert!(index.is_fresh(&file_stamps([path.as_str()])).unwrap());
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(UNIX_EPOCH + std::time::Duration::from_secs(1_000))
+            .unwrap();
+        assert!(!index.is_fresh(&file_stamps([path.as_str()])).unwrap());
+
+        // Added or removed templates also invalidate the index
+        assert!(!index.is_fresh(&[]).unwrap());
+    }
+
+    #[test]
+    fn test_embeddings_cached_per_model_and_stamp() {
+        let index = SearchIndex::open_in_memory().unwrap();
+        let stamp = ("a.yaml".to_string(), 10);
+        index
+            .store_embeddings("mini", &[(stamp.clone(), vec![0.5, -1.0, 2.0])])
+            .unwrap();
+
+        let cached = index.cached_embeddings("mini", &[stamp]).unwrap();
+        assert_eq!(cached["a.yaml"], vec![0.5, -1.0, 2.0]);
+        assert!(index
+            .cached_embeddings("other", &[("a.yaml".to_string(), 10)])
+            .unwrap()
+            .is_empty());
+        // A modified template is embedded again
+        assert!(index
+            .cached_embeddings("mini", &[("a.yaml".to_string(), 11)])
+            .unwrap()
+            .is_empty());
+    }
+}
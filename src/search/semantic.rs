@@ -0,0 +1,142 @@
+//! Embedding backends for `cxg search --semantic`
+//!
+//! Templates are embedded from their name, description and tags. Vectors
+//! come from a local `fastembed` model (384 dimensions, built with the
+//! `semantic` feature) or from the configured AI provider's embeddings API,
+//! and are cached per model in the [`SearchIndex`](super::SearchIndex).
+
+use super::SearchResult;
+use crate::ai::AIManager;
+use crate::error::{Error, Result};
+use std::fmt;
+
+/// Turns text into embedding vectors
+pub trait TextEmbedder: fmt::Debug + Send + Sync {
+    /// Model name; cached vectors are only reused for the same model
+    fn model(&self) -> &str;
+
+    /// Embed each text, in order
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Text embedded for a template
+pub fn template_text(template: &SearchResult) -> String {
+    format!(
+        "{}\n{}\n{}",
+        template.name,
+        template.description,
+        template.tags.join(", ")
+    )
+}
+
+/// Offline embeddings from a local all-MiniLM-L6-v2 model
+///
+/// The model is downloaded to `~/.cert-x-gen/cache/models` on first use.
+#[cfg(feature = "semantic")]
+pub struct LocalEmbedder {
+    model: std::sync::Mutex<fastembed::TextEmbedding>,
+}
+
+#[cfg(feature = "semantic")]
+impl LocalEmbedder {
+    /// Load (downloading if needed) the local embedding model
+    pub fn new() -> Result<Self> {
+        use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+
+        let options = InitOptions::new(EmbeddingModel::AllMiniLML6V2)
+            .with_cache_dir(crate::template::PathResolver::cache_dir().join("models"));
+        let model = TextEmbedding::try_new(options)
+            .map_err(|e| Error::Ai(format!("Failed to load embedding model: {}", e)))?;
+        Ok(Self {
+            model: std::sync::Mutex::new(model),
+        })
+    }
+}
+
+#[cfg(feature = "semantic")]
+impl fmt::Debug for LocalEmbedder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalEmbedder").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "semantic")]
+impl TextEmbedder for LocalEmbedder {
+    fn model(&self) -> &str {
+        "all-minilm-l6-v2"
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let model = self
+            .model
+            .lock()
+            .map_err(|_| Error::Internal("embedding model lock poisoned".to_string()))?;
+        model
+            .embed(texts.to_vec(), None)
+            .map_err(|e| Error::Ai(format!("Failed to embed text: {}", e)))
+    }
+}
+
+/// Embeddings from the AI manager's default provider (ollama or openai)
+#[derive(Debug)]
+pub struct ProviderEmbedder {
+    manager: AIManager,
+    model: String,
+}
+
+impl ProviderEmbedder {
+    /// Embed with `manager`'s default provider
+    pub fn new(manager: AIManager) -> Self {
+        let config = manager.config();
+        let provider = config.default_provider_name();
+        let model = match config.get_provider(provider) {
+            Some(provider_config) => format!("{}/{}", provider, provider_config.model),
+            None => provider.to_string(),
+        };
+        Self { manager, model }
+    }
+}
+
+impl TextEmbedder for ProviderEmbedder {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        // Search runs synchronously inside the multi-threaded CLI runtime
+        tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(async {
+                let mut vectors = Vec::with_capacity(texts.len());
+                for text in texts {
+                    let vector = self
+                        .manager
+                        .embed_text(text)
+                        .await
+                        .map_err(|e| Error::Ai(format!("Failed to embed text: {}", e)))?;
+                    vectors.push(vector);
+                }
+                Ok(vectors)
+            })
+        })
+    }
+}
+
+/// The local model when built with the `semantic` feature, otherwise the
+/// configured AI provider
+pub fn default_embedder() -> Result<Box<dyn TextEmbedder>> {
+    #[cfg(feature = "semantic")]
+    {
+        Ok(Box::new(LocalEmbedder::new()?))
+    }
+
+    #[cfg(not(feature = "semantic"))]
+    {
+        let manager = AIManager::new().map_err(|e| {
+            Error::Ai(format!(
+                "Semantic search needs the `semantic` build feature or an AI provider with embeddings: {}",
+                e
+            ))
+        })?;
+        Ok(Box::new(ProviderEmbedder::new(manager)))
+    }
+}
@@ -0,0 +1,381 @@
+//! Template collection statistics (`template stats`)
+//!
+//! Extends [`SearchStats`] over the whole collection with tag frequency and
+//! CWE coverage, and renders it as tables or ASCII bar charts.
+
+use super::{SearchResult, SearchStats};
+use crate::types::Severity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Width of the longest bar in charts
+const CHART_WIDTH: usize = 40;
+
+/// A name and how many templates it applies to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankedCount {
+    /// Author or tag
+    pub name: String,
+    /// Number of templates
+    pub templates: usize,
+}
+
+impl RankedCount {
+    /// Most templates first, ties by name
+    pub fn ranked<S: Into<String>>(counts: HashMap<S, usize>) -> Vec<Self> {
+        let mut ranked: Vec<Self> = counts
+            .into_iter()
+            .map(|(name, templates)| Self {
+                name: name.into(),
+                templates,
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.templates.cmp(&a.templates).then(a.name.cmp(&b.name)));
+        ranked
+    }
+}
+
+/// Statistics over a template collection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateStatsReport {
+    /// Counts by language, severity, author and month added
+    #[serde(flatten)]
+    pub stats: SearchStats,
+    /// Tag frequency, most used first; a template's own language tag is not counted
+    pub tags: Vec<RankedCount>,
+    /// Templates mapped to at least one CWE
+    pub with_cwe: usize,
+    /// Templates without a CWE mapping
+    pub without_cwe: usize,
+    /// Templates without a `created-date`
+    pub undated: usize,
+}
+
+impl TemplateStatsReport {
+    /// Complete `stats`, computed over `templates`, with tag and CWE counts
+    pub fn new(stats: SearchStats, templates: &[SearchResult]) -> Self {
+        let mut tags: HashMap<&str, usize> = HashMap::new();
+        for template in templates {
+            let language_tag = template.language.to_string().to_lowercase();
+            for tag in template.tags.iter().filter(|tag| **tag != language_tag) {
+                *tags.entry(tag.as_str()).or_insert(0) += 1;
+            }
+        }
+        let with_cwe = templates.iter().filter(|t| t.cwe.is_some()).count();
+        let undated = templates
+            .iter()
+            .filter(|t| t.created_date.is_none())
+            .count();
+
+        Self {
+            stats,
+            tags: RankedCount::ranked(tags),
+            with_cwe,
+            without_cwe: templates.len() - with_cwe,
+            undated,
+        }
+    }
+
+    /// Plain counts, listing at most `top` tags and authors
+    pub fn render_table(&self, top: usize) -> String {
+        self.render(top, |rows| {
+            let width = label_width(rows);
+            rows.iter()
+                .map(|(label, count)| {
+                    format!(
+                        "  {:<width$} {:>6}  {:>5.1}%\n",
+                        label,
+                        count,
+                        self.percent(*count),
+                        width = width
+                    )
+                })
+                .collect()
+        })
+    }
+
+    /// ASCII bar charts, listing at most `top` tags and authors
+    pub fn render_chart(&self, top: usize) -> String {
+        self.render(top, |rows| {
+            let width = label_width(rows);
+            let max = rows.iter().map(|(_, count)| *count).max().unwrap_or(0);
+            rows.iter()
+                .map(|(label, count)| {
+                    format!(
+                        "  {:<width$} │{:<bar_width$} {}\n",
+                        label,
+                        bar(*count, max),
+                        count,
+                        width = width,
+                        bar_width = CHART_WIDTH
+                    )
+                })
+                .collect()
+        })
+    }
+
+    /// Lay out every section, drawing rows with `rows_fn`
+    fn render(&self, top: usize, rows_fn: impl Fn(&[(String, usize)]) -> String) -> String {
+        let mut output = format!("Templates: {}\n", self.stats.total_templates);
+
+        let mut languages: Vec<(String, usize)> = self
+            .stats
+            .languages
+            .iter()
+            .map(|(language, count)| (language.to_string(), *count))
+            .collect();
+        languages.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        section(&mut output, "By language", rows_fn(&languages));
+
+        let severities: Vec<(String, usize)> = [
+            Severity::Critical,
+            Severity::High,
+            Severity::Medium,
+            Severity::Low,
+            Severity::Info,
+        ]
+        .iter()
+        .map(|severity| {
+            let count = self.stats.severities.get(severity).copied().unwrap_or(0);
+            (severity.to_string(), count)
+        })
+        .collect();
+        section(&mut output, "By severity", rows_fn(&severities));
+
+        let cwe = [
+            ("with CWE".to_string(), self.with_cwe),
+            ("without CWE".to_string(), self.without_cwe),
+        ];
+        section(&mut output, "CWE mapping", rows_fn(&cwe));
+
+        let tags = top_rows(&self.tags, top);
+        section(
+            &mut output,
+            &format!("Top tags ({} distinct)", self.tags.len()),
+            rows_fn(&tags),
+        );
+
+        let authors = top_rows(&self.stats.authors, top);
+        section(
+            &mut output,
+            &format!("Authors ({} total)", self.stats.authors.len()),
+            rows_fn(&authors),
+        );
+
+        let months: Vec<(String, usize)> = self
+            .stats
+            .created_per_month
+            .iter()
+            .map(|(month, count)| (month.clone(), *count))
+            .collect();
+        let mut added = rows_fn(&months);
+        if self.undated > 0 {
+            added.push_str(&format!(
+                "  ({} template(s) without a created-date)\n",
+                self.undated
+            ));
+        }
+        section(&mut output, "Added per month", added);
+
+        output
+    }
+
+    /// Share of the collection, in percent
+    fn percent(&self, count: usize) -> f64 {
+        if self.stats.total_templates == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / self.stats.total_templates as f64
+        }
+    }
+}
+
+fn section(output: &mut String, title: &str, rows: String) {
+    output.push_str(&format!("\n{}:\n", title));
+    if rows.is_empty() {
+        output.push_str("  (none)\n");
+    } else {
+        output.push_str(&rows);
+    }
+}
+
+fn top_rows(counts: &[RankedCount], top: usize) -> Vec<(String, usize)> {
+    counts
+        .iter()
+        .take(top)
+        .map(|count| (count.name.clone(), count.templates))
+        .collect()
+}
+
+fn label_width(rows: &[(String, usize)]) -> usize {
+    rows.iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Bar scaled so `max` fills [`CHART_WIDTH`]; non-zero counts get at least one block
+fn bar(count: usize, max: usize) -> String {
+    if max == 0 || count == 0 {
+        return String::new();
+    }
+    "█".repeat((count * CHART_WIDTH / max).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::TemplateSearchEngine;
+    use crate::template::UsageStore;
+    use crate::types::TemplateLanguage;
+    use chrono::NaiveDate;
+
+    fn template(
+        id: &str,
+        language: TemplateLanguage,
+        severity: Severity,
+        author: &str,
+        tags: &[&str],
+        cwe: Option<&str>,
+        created: Option<&str>,
+    ) -> SearchResult {
+        SearchResult {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            language,
+            severity,
+            author: author.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            cwe: cwe.map(str::to_string),
+            references: Vec::new(),
+            file_path: String::new(),
+            relevance_score: 0.0,
+            match_fields: Vec::new(),
+            content_preview: None,
+            deprecated: false,
+            superseded_by: None,
+            created_date: created.map(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap()),
+        }
+    }
+
+    fn fixture() -> TemplateSearchEngine {
+        use TemplateLanguage::{Python, Yaml};
+        TemplateSearchEngine {
+            templates: vec![
+                template(
+                    "redis-unauth",
+                    Python,
+                    Severity::High,
+                    "alice",
+                    &["redis", "database", "python"],
+                    Some("CWE-306"),
+                    Some("2024-01-15"),
+                ),
+                template(
+                    "mongo-unauth",
+                    Python,
+                    Severity::High,
+                    "alice",
+                    &["mongodb", "database", "python"],
+                    Some("CWE-306"),
+                    Some("2024-01-30"),
+                ),
+                template(
+                    "git-exposed",
+                    Yaml,
+                    Severity::Medium,
+                    "bob",
+                    &["exposure", "yaml"],
+                    None,
+                    Some("2024-03-02"),
+                ),
+                template(
+                    "log4shell",
+                    Yaml,
+                    Severity::Critical,
+                    "carol",
+                    &["rce", "yaml"],
+                    Some("CWE-502"),
+                    None,
+                ),
+            ],
+            index: HashMap::new(),
+            content_index: HashMap::new(),
+            sources: HashMap::new(),
+            usage: UsageStore::default(),
+            fts: None,
+            stamps: Vec::new(),
+            embedder: None,
+        }
+    }
+
+    #[test]
+    fn test_report_counts_match_fixture() {
+        let report = fixture().stats_report();
+
+        assert_eq!(report.stats.total_templates, 4);
+        assert_eq!(report.stats.languages[&TemplateLanguage::Python], 2);
+        assert_eq!(report.stats.languages[&TemplateLanguage::Yaml], 2);
+        assert_eq!(report.stats.severities[&Severity::High], 2);
+        assert_eq!(report.stats.severities.get(&Severity::Low), None);
+        assert_eq!((report.with_cwe, report.without_cwe), (3, 1));
+
+        // Language tags are left out of tag frequency
+        assert_eq!(
+            report.tags[0],
+            RankedCount {
+                name: "database".to_string(),
+                templates: 2
+            }
+        );
+        assert_eq!(report.tags.len(), 5);
+        assert!(report.tags.iter().all(|t| t.name != "yaml"));
+
+        let authors: Vec<(&str, usize)> = report
+            .stats
+            .authors
+            .iter()
+            .map(|a| (a.name.as_str(), a.templates))
+            .collect();
+        assert_eq!(authors, [("alice", 2), ("bob", 1), ("carol", 1)]);
+
+        assert_eq!(report.stats.created_per_month.len(), 2);
+        assert_eq!(report.stats.created_per_month["2024-01"], 2);
+        assert_eq!(report.stats.created_per_month["2024-03"], 1);
+        assert_eq!(report.undated, 1);
+    }
+
+    #[test]
+    fn test_render_table_and_chart() {
+        let report = fixture().stats_report();
+
+        let table = report.render_table(1);
+        assert!(table.starts_with("Templates: 4\n"));
+        assert!(table.contains("  python      2   50.0%\n"));
+        assert!(table.contains("Top tags (5 distinct):\n  database      2   50.0%\n\nAuthors"));
+        assert!(table.contains("(1 template(s) without a created-date)"));
+
+        let chart = report.render_chart(20);
+        let full = "█".repeat(CHART_WIDTH);
+        assert!(chart.contains(&format!("  alice │{} 2\n", full)));
+        let half = "█".repeat(CHART_WIDTH / 2);
+        assert!(chart.contains(&format!(
+            "  bob   │{:<width$} 1\n",
+            half,
+            width = CHART_WIDTH
+        )));
+        // Empty severities get no bar
+        assert!(chart.contains(&format!("  low      │{} 0\n", " ".repeat(CHART_WIDTH))));
+    }
+
+    #[test]
+    fn test_report_json_is_flat() {
+        let report = fixture().stats_report();
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["total_templates"], 4);
+        assert_eq!(json["authors"][0]["name"], "alice");
+        assert_eq!(json["created_per_month"]["2024-01"], 2);
+        assert_eq!(json["without_cwe"], 1);
+    }
+}
@@ -734,10 +734,63 @@ impl Default for SessionManager {
     }
 }
 
+/// Credentials of one user in a multi-account scan
+///
+/// Templates send a request as this user with `session: <name>`, so checks
+/// for horizontal privilege escalation can compare what two users reach.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SessionState {
+    /// Name templates refer to (`user_a`, `user_b`)
+    pub name: String,
+    /// Cookies sent instead of the scan's own
+    #[serde(default)]
+    pub cookies: Vec<(String, String)>,
+    /// Headers sent on top of the scan's own, replacing those of the same name
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+}
+
+impl SessionState {
+    /// Create a session without credentials
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    /// The session called `name` in `sessions`, added if missing
+    pub fn named<'a>(sessions: &'a mut Vec<SessionState>, name: &str) -> &'a mut SessionState {
+        match sessions.iter().position(|session| session.name == name) {
+            Some(index) => &mut sessions[index],
+            None => {
+                sessions.push(Self::new(name));
+                sessions.last_mut().expect("session was just added")
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_named_session() {
+        let mut sessions = Vec::new();
+        SessionState::named(&mut sessions, "user_a")
+            .cookies
+            .push(("sid".to_string(), "a".to_string()));
+        SessionState::named(&mut sessions, "user_b");
+        SessionState::named(&mut sessions, "user_a")
+            .headers
+            .push(("X-Tenant".to_string(), "1".to_string()));
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].cookies.len(), 1);
+        assert_eq!(sessions[0].headers.len(), 1);
+        assert_eq!(sessions[1], SessionState::new("user_b"));
+    }
+
     #[test]
     fn test_cookie_creation() {
         let cookie = Cookie::new("session", "abc123", "example.com");
@@ -211,6 +211,7 @@ impl AutoUpdater {
                 "shell" => "🐚",
                 "perl" => "🐪",
                 "php" => "🐘",
+                "rhai" => "📜",
                 _ => "📁",
             };
             let line = format!("│     {} {}: {}", icon, lang, count);
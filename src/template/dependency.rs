@@ -0,0 +1,207 @@
+//! Template dependencies
+//!
+//! A template listing `depends-on: [<id>, ...]` runs on a target only after
+//! each of those templates has run there and reported a finding, e.g. an
+//! endpoint check that only makes sense once a fingerprint template has
+//! confirmed the framework. Dependencies on templates that are not part of
+//! the scan are ignored, so a dependent picked on its own still runs.
+
+use crate::template::Template;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Order in which a job's templates run so dependencies come first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyPlan {
+    /// Template indexes per wave; a wave only depends on earlier ones
+    waves: Vec<Vec<usize>>,
+    /// Indexes of the templates each template waits for
+    requires: Vec<Vec<usize>>,
+}
+
+impl DependencyPlan {
+    /// Plan `templates`
+    ///
+    /// Templates caught in a dependency cycle go in a last wave and only wait
+    /// for the dependencies that could be ordered before them.
+    pub fn new(templates: &[Arc<dyn Template>]) -> Self {
+        let declared: Vec<Vec<usize>> = templates
+            .iter()
+            .map(|template| {
+                template
+                    .metadata()
+                    .depends_on
+                    .iter()
+                    .filter_map(|id| templates.iter().position(|t| t.id() == id))
+                    .collect()
+            })
+            .collect();
+
+        let mut waves = Vec::new();
+        let mut placed: HashSet<usize> = HashSet::new();
+        while placed.len() < templates.len() {
+            let wave: Vec<usize> = (0..templates.len())
+                .filter(|idx| !placed.contains(idx))
+                .filter(|&idx| declared[idx].iter().all(|dep| placed.contains(dep)))
+                .collect();
+            if wave.is_empty() {
+                let rest: Vec<usize> = (0..templates.len())
+                    .filter(|idx| !placed.contains(idx))
+                    .collect();
+                for &idx in &rest {
+                    tracing::warn!(
+                        "Template '{}' is part of a depends-on cycle; it only waits for dependencies outside it",
+                        templates[idx].id()
+                    );
+                }
+                placed.extend(&rest);
+                waves.push(rest);
+                break;
+            }
+            placed.extend(&wave);
+            waves.push(wave);
+        }
+
+        // Only dependencies from earlier waves can gate a template
+        let wave_of = |idx: usize| waves.iter().position(|wave| wave.contains(&idx));
+        let requires = declared
+            .iter()
+            .enumerate()
+            .map(|(idx, deps)| {
+                deps.iter()
+                    .copied()
+                    .filter(|&dep| wave_of(dep) < wave_of(idx))
+                    .collect()
+            })
+            .collect();
+
+        Self { waves, requires }
+    }
+
+    /// Template indexes per wave
+    pub fn waves(&self) -> &[Vec<usize>] {
+        &self.waves
+    }
+
+    /// Indexes of the templates `templates[idx]` waits for
+    pub fn requires(&self, idx: usize) -> &[usize] {
+        self.requires.get(idx).map_or(&[], Vec::as_slice)
+    }
+
+    /// `order` with each template moved up to its most urgent dependent
+    ///
+    /// A critical check waiting on an info-level fingerprint then does not
+    /// wait for every other info template too.
+    pub fn prioritize(&self, order: &[usize]) -> Vec<usize> {
+        let mut rank = vec![usize::MAX; self.requires.len()];
+        for (position, &idx) in order.iter().enumerate() {
+            let mut raise = vec![idx];
+            while let Some(idx) = raise.pop() {
+                if rank[idx] <= position {
+                    continue;
+                }
+                rank[idx] = position;
+                raise.extend_from_slice(self.requires(idx));
+            }
+        }
+        let mut prioritized = order.to_vec();
+        prioritized.sort_by_key(|&idx| rank[idx]);
+        prioritized
+    }
+}
+
+/// `depends-on` references to IDs outside `templates`, as `(template ID, missing dependency)`
+pub fn missing_dependencies(templates: &[Arc<dyn Template>]) -> Vec<(&str, &str)> {
+    let ids: HashSet<&str> = templates.iter().map(|t| t.id()).collect();
+    templates
+        .iter()
+        .flat_map(|template| {
+            template
+                .metadata()
+                .depends_on
+                .iter()
+                .filter(|dep| !ids.contains(dep.as_str()))
+                .map(|dep| (template.id(), dep.as_str()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use crate::types::{Context, Finding, Target, TemplateLanguage};
+
+    struct StubTemplate {
+        metadata: crate::types::TemplateMetadata,
+    }
+
+    #[async_trait::async_trait]
+    impl Template for StubTemplate {
+        fn metadata(&self) -> &crate::types::TemplateMetadata {
+            &self.metadata
+        }
+
+        async fn execute(&self, _target: &Target, _context: &Context) -> Result<Vec<Finding>> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn template(id: &str, depends_on: &[&str]) -> Arc<dyn Template> {
+        let mut metadata = crate::engine::common::create_metadata(
+            std::path::Path::new("test.yaml"),
+            TemplateLanguage::Yaml,
+        );
+        metadata.id = id.to_string();
+        metadata.depends_on = depends_on.iter().map(|d| d.to_string()).collect();
+        Arc::new(StubTemplate { metadata })
+    }
+
+    #[test]
+    fn test_dependencies_run_first() {
+        let templates = vec![
+            template("env", &["detect"]),
+            template("detect", &[]),
+            template("heapdump", &["env", "detect"]),
+            template("other", &["not-selected"]),
+        ];
+        let plan = DependencyPlan::new(&templates);
+        assert_eq!(plan.waves(), [vec![1, 3], vec![0], vec![2]]);
+        assert_eq!(plan.requires(0), [1]);
+        assert_eq!(plan.requires(2), [0, 1]);
+        assert!(plan.requires(3).is_empty());
+        assert_eq!(
+            missing_dependencies(&templates),
+            [("other", "not-selected")]
+        );
+    }
+
+    #[test]
+    fn test_cycles_run_last() {
+        let templates = vec![
+            template("a", &["b"]),
+            template("b", &["a"]),
+            template("c", &[]),
+        ];
+        let plan = DependencyPlan::new(&templates);
+        assert_eq!(plan.waves(), [vec![2], vec![0, 1]]);
+        assert!(plan.requires(0).is_empty());
+        assert!(plan.requires(1).is_empty());
+
+        let plan = DependencyPlan::new(&[template("a", &[]), template("b", &[])]);
+        assert_eq!(plan.waves(), [vec![0, 1]]);
+    }
+
+    #[test]
+    fn test_dependencies_take_their_dependents_priority() {
+        let templates = vec![
+            template("fingerprint", &[]),
+            template("detect", &[]),
+            template("heapdump", &["detect"]),
+            template("banner", &[]),
+        ];
+        let plan = DependencyPlan::new(&templates);
+        assert_eq!(plan.prioritize(&[2, 0, 1, 3]), [2, 1, 0, 3]);
+        assert_eq!(plan.prioritize(&[0, 1, 2, 3]), [0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,172 @@
+//! Template deprecation
+//!
+//! A template marked `deprecated: true` stays loadable, so `--templates`
+//! selections naming it keep working (with a warning), but scans over all
+//! templates skip it unless `--include-deprecated` is given.
+//! `superseded-by: <id>` names its replacement, which `--follow-deprecations`
+//! runs in its place.
+
+use crate::error::{Error, Result};
+use crate::template::Template;
+use std::collections::HashSet;
+
+/// Replace deprecated templates in `selected` with the templates superseding them
+///
+/// Replacements are looked up by ID in `available` and followed until a
+/// template that is not deprecated is reached. A replacement that is already
+/// selected is not added twice; deprecated templates without a replacement are
+/// kept. Fails when a replacement is not in `available` or the chain loops.
+pub fn follow_deprecations(
+    selected: Vec<Box<dyn Template>>,
+    mut available: Vec<Box<dyn Template>>,
+) -> Result<Vec<Box<dyn Template>>> {
+    let mut chosen: Vec<Box<dyn Template>> = Vec::new();
+    let mut chosen_ids: HashSet<String> = HashSet::new();
+
+    'selected: for template in selected {
+        let mut current = template;
+        let mut visited = HashSet::new();
+        while current.metadata().deprecated {
+            let Some(replacement) = current.metadata().superseded_by.clone() else {
+                tracing::warn!(
+                    "Template '{}' is deprecated and names no replacement",
+                    current.id()
+                );
+                break;
+            };
+            if !visited.insert(current.id().to_string()) {
+                return Err(Error::config(format!(
+                    "Deprecated template '{}' is superseded by itself through '{}'",
+                    current.id(),
+                    replacement
+                )));
+            }
+            tracing::info!(
+                "Running '{}' in place of deprecated template '{}'",
+                replacement,
+                current.id()
+            );
+            if chosen_ids.contains(&replacement) {
+                continue 'selected;
+            }
+            let index = available
+                .iter()
+                .position(|t| t.id() == replacement)
+                .ok_or_else(|| {
+                    Error::config(format!(
+                        "Template '{}' is superseded by '{}', which was not found",
+                        current.id(),
+                        replacement
+                    ))
+                })?;
+            current = available.swap_remove(index);
+        }
+        if chosen_ids.insert(current.id().to_string()) {
+            chosen.push(current);
+        }
+    }
+
+    Ok(chosen)
+}
+
+/// `superseded-by` references to IDs outside the set, as `(template ID, missing replacement)`
+pub fn missing_replacements(templates: &[(String, Option<String>)]) -> Vec<(&str, &str)> {
+    let ids: HashSet<&str> = templates.iter().map(|(id, _)| id.as_str()).collect();
+    templates
+        .iter()
+        .filter_map(|(id, replacement)| {
+            let replacement = replacement.as_deref()?;
+            (!ids.contains(replacement)).then_some((id.as_str(), replacement))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Context, Finding, Target, TemplateLanguage, TemplateMetadata};
+
+    struct StubTemplate {
+        metadata: TemplateMetadata,
+    }
+
+    #[async_trait::async_trait]
+    impl Template for StubTemplate {
+        fn metadata(&self) -> &TemplateMetadata {
+            &self.metadata
+        }
+
+        async fn execute(&self, _target: &Target, _context: &Context) -> Result<Vec<Finding>> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn template(id: &str, superseded_by: Option<&str>) -> Box<dyn Template> {
+        let path = std::path::Path::new("test.yaml");
+        let mut metadata = crate::engine::common::create_metadata(path, TemplateLanguage::Yaml);
+        metadata.id = id.to_string();
+        metadata.deprecated = superseded_by.is_some();
+        metadata.superseded_by = superseded_by.map(str::to_string);
+        Box::new(StubTemplate { metadata })
+    }
+
+    fn ids(templates: &[Box<dyn Template>]) -> Vec<&str> {
+        templates.iter().map(|t| t.id()).collect()
+    }
+
+    #[test]
+    fn test_follow_deprecations_substitutes_replacement_chain() {
+        let available = || {
+            vec![
+                template("old-check", Some("mid-check")),
+                template("mid-check", Some("new-check")),
+                template("new-check", None),
+                template("other-check", None),
+            ]
+        };
+
+        let selected = vec![
+            template("old-check", Some("mid-check")),
+            template("other-check", None),
+        ];
+        let chosen = follow_deprecations(selected, available()).unwrap();
+        assert_eq!(ids(&chosen), vec!["new-check", "other-check"]);
+
+        // Replacements already selected are not run twice
+        let selected = vec![
+            template("new-check", None),
+            template("mid-check", Some("new-check")),
+        ];
+        let chosen = follow_deprecations(selected, available()).unwrap();
+        assert_eq!(ids(&chosen), vec!["new-check"]);
+    }
+
+    #[test]
+    fn test_follow_deprecations_errors_on_missing_or_looping_replacement() {
+        let selected = vec![template("old-check", Some("gone-check"))];
+        let err = follow_deprecations(selected, Vec::new())
+            .err()
+            .expect("missing replacement is an error");
+        assert!(err.to_string().contains("gone-check"));
+
+        let selected = vec![template("a-check", Some("b-check"))];
+        let available = vec![
+            template("a-check", Some("b-check")),
+            template("b-check", Some("a-check")),
+        ];
+        assert!(follow_deprecations(selected, available).is_err());
+    }
+
+    #[test]
+    fn test_missing_replacements() {
+        let templates = vec![
+            ("old-check".to_string(), Some("new-check".to_string())),
+            ("new-check".to_string(), None),
+            ("stale-check".to_string(), Some("renamed-check".to_string())),
+        ];
+        assert_eq!(
+            missing_replacements(&templates),
+            vec![("stale-check", "renamed-check")]
+        );
+    }
+}
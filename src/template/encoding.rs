@@ -0,0 +1,169 @@
+//! Text encoding of template files
+//!
+//! Editors on Windows may save templates as UTF-16 or as UTF-8 with a byte
+//! order mark. Loaders read template files through [`read_source`], which
+//! transcodes both to plain UTF-8, so a BOM never ends up in front of the
+//! first YAML key or metadata comment.
+
+use crate::error::{Error, Result};
+use std::fmt;
+use std::path::Path;
+
+/// UTF-8 byte order mark
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Encoding a template file was saved in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceEncoding {
+    /// Plain UTF-8
+    Utf8,
+    /// UTF-8 starting with a byte order mark
+    Utf8Bom,
+    /// UTF-16, little-endian
+    Utf16Le,
+    /// UTF-16, big-endian
+    Utf16Be,
+}
+
+impl SourceEncoding {
+    /// Whether the file was transcoded rather than read as it is
+    pub fn is_transcoded(&self) -> bool {
+        *self != SourceEncoding::Utf8
+    }
+}
+
+impl fmt::Display for SourceEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceEncoding::Utf8 => write!(f, "UTF-8"),
+            SourceEncoding::Utf8Bom => write!(f, "UTF-8 with BOM"),
+            SourceEncoding::Utf16Le => write!(f, "UTF-16LE"),
+            SourceEncoding::Utf16Be => write!(f, "UTF-16BE"),
+        }
+    }
+}
+
+/// Template text and the encoding it was read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedSource {
+    /// Text as UTF-8, without a BOM
+    pub text: String,
+    /// Encoding of the file
+    pub encoding: SourceEncoding,
+}
+
+/// Decode template file contents
+///
+/// UTF-16 is recognised by its BOM, or without one by a NUL in the first
+/// code unit (templates start with ASCII).
+pub fn decode_source(bytes: &[u8]) -> std::result::Result<DecodedSource, String> {
+    let utf16 = match bytes {
+        [0xFF, 0xFE, rest @ ..] => Some((SourceEncoding::Utf16Le, rest)),
+        [0xFE, 0xFF, rest @ ..] => Some((SourceEncoding::Utf16Be, rest)),
+        [first, 0, ..] if *first != 0 => Some((SourceEncoding::Utf16Le, bytes)),
+        [0, second, ..] if *second != 0 => Some((SourceEncoding::Utf16Be, bytes)),
+        _ => None,
+    };
+    if let Some((encoding, rest)) = utf16 {
+        let codec = match encoding {
+            SourceEncoding::Utf16Be => encoding_rs::UTF_16BE,
+            _ => encoding_rs::UTF_16LE,
+        };
+        let text = codec
+            .decode_without_bom_handling_and_without_replacement(rest)
+            .ok_or_else(|| format!("invalid {} (truncated or unpaired surrogate)", encoding))?;
+        return Ok(DecodedSource {
+            text: text.into_owned(),
+            encoding,
+        });
+    }
+
+    let (encoding, rest) = match bytes.strip_prefix(UTF8_BOM) {
+        Some(rest) => (SourceEncoding::Utf8Bom, rest),
+        None => (SourceEncoding::Utf8, bytes),
+    };
+    match std::str::from_utf8(rest) {
+        Ok(text) => Ok(DecodedSource {
+            text: text.to_string(),
+            encoding,
+        }),
+        Err(e) => {
+            let offset = e.valid_up_to();
+            let line = rest[..offset].iter().filter(|&&b| b == b'\n').count() + 1;
+            Err(format!(
+                "not UTF-8 or UTF-16: invalid byte 0x{:02X} on line {}; save the file as UTF-8",
+                rest[offset], line
+            ))
+        }
+    }
+}
+
+/// Read a template file, transcoding UTF-16 and BOM-prefixed UTF-8
+pub fn read_source(path: &Path) -> Result<DecodedSource> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        Error::template(
+            path.display().to_string(),
+            format!("Failed to read template: {}", e),
+        )
+    })?;
+    let source = decode_source(&bytes).map_err(|e| {
+        Error::template(
+            path.display().to_string(),
+            format!("Unsupported encoding: {}", e),
+        )
+    })?;
+    if source.encoding.is_transcoded() {
+        tracing::debug!("Read {} as {}", path.display(), source.encoding);
+    }
+    Ok(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool, bom: bool) -> Vec<u8> {
+        let units = bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16());
+        units
+            .flat_map(|unit| {
+                if big_endian {
+                    unit.to_be_bytes()
+                } else {
+                    unit.to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_boms_and_utf16_are_transcoded() {
+        let text = "id: café\nname: Test\n";
+        let cases = [
+            (text.as_bytes().to_vec(), SourceEncoding::Utf8),
+            (
+                [UTF8_BOM, text.as_bytes()].concat(),
+                SourceEncoding::Utf8Bom,
+            ),
+            (utf16(text, false, true), SourceEncoding::Utf16Le),
+            (utf16(text, true, true), SourceEncoding::Utf16Be),
+            (utf16(text, false, false), SourceEncoding::Utf16Le),
+            (utf16(text, true, false), SourceEncoding::Utf16Be),
+        ];
+        for (bytes, encoding) in cases {
+            let source = decode_source(&bytes).unwrap();
+            assert_eq!(source.encoding, encoding);
+            assert_eq!(source.text, text, "{}", encoding);
+        }
+    }
+
+    #[test]
+    fn test_undecodable_bytes_are_reported() {
+        // Latin-1 `é` on the second line
+        let err = decode_source(b"id: test\nname: caf\xE9\n").unwrap_err();
+        assert!(err.contains("0xE9 on line 2"), "{}", err);
+
+        let mut odd = utf16("id: x", false, true);
+        odd.pop();
+        assert!(decode_source(&odd).unwrap_err().contains("UTF-16LE"));
+    }
+}
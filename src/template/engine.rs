@@ -5,7 +5,14 @@
 use crate::error::{Error, Result};
 use crate::types::{Context, Finding, Protocol, Target, TemplateMetadata};
 use async_trait::async_trait;
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Boxed future resolving to templates loaded from a directory tree
+pub type TemplatesFuture<'a> = std::pin::Pin<
+    Box<dyn std::future::Future<Output = Result<Vec<Box<dyn Template>>>> + Send + 'a>,
+>;
 
 /// Template trait that all templates must implement
 #[async_trait]
@@ -21,11 +28,26 @@ pub trait Template: Send + Sync {
         Ok(())
     }
 
+    /// Prepare the template for execution (e.g. compile it ahead of time)
+    async fn prepare(&self) -> Result<()> {
+        Ok(())
+    }
+
     /// Get supported protocols
     fn supported_protocols(&self) -> Vec<Protocol> {
         vec![Protocol::Http, Protocol::Https]
     }
 
+    /// Other files the template was built from (e.g. included YAML snippets)
+    fn dependencies(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Rough number of requests sent to each target, shown in scan plans
+    fn estimated_requests(&self) -> usize {
+        1
+    }
+
     /// Get template name
     fn name(&self) -> &str {
         &self.metadata().name
@@ -62,12 +84,67 @@ pub trait TemplateEngine: Send + Sync {
 
     /// Check if engine supports a file
     fn supports_file(&self, path: &Path) -> bool;
+
+    /// Relative cost of running one template
+    fn cost_hint(&self) -> ExecutionCost {
+        ExecutionCost::Light
+    }
+
+    /// Most templates of this engine that may run at once (`None` = unlimited)
+    fn max_concurrency(&self) -> Option<usize> {
+        self.cost_hint().default_concurrency()
+    }
+}
+
+/// How heavy a single template execution is for an engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionCost {
+    /// In-process work such as YAML HTTP requests
+    Light,
+    /// An interpreter process per execution
+    Medium,
+    /// A compile step and/or a JVM per execution
+    Heavy,
 }
 
+impl ExecutionCost {
+    /// Default concurrency limit: unlimited, 2x CPU count, or CPU count
+    pub fn default_concurrency(self) -> Option<usize> {
+        match self {
+            ExecutionCost::Light => None,
+            ExecutionCost::Medium => Some(2 * num_cpus::get()),
+            ExecutionCost::Heavy => Some(num_cpus::get()),
+        }
+    }
+}
+
+impl std::fmt::Display for ExecutionCost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutionCost::Light => write!(f, "light"),
+            ExecutionCost::Medium => write!(f, "medium"),
+            ExecutionCost::Heavy => write!(f, "heavy"),
+        }
+    }
+}
+
+/// Directories never searched for templates (build output, disabled and skeleton templates)
+pub(crate) const EXCLUDED_TEMPLATE_DIRS: &[&str] = &[
+    "target",
+    "node_modules",
+    ".git",
+    "__pycache__",
+    "_disabled",
+    "skeleton",
+];
+
 /// Template loader for managing multiple template engines
 #[allow(missing_debug_implementations)]
 pub struct TemplateLoader {
     engines: Vec<Box<dyn TemplateEngine>>,
+    /// Template files that failed to load from a directory
+    skipped: AtomicUsize,
 }
 
 impl TemplateLoader {
@@ -75,6 +152,7 @@ impl TemplateLoader {
     pub fn new() -> Self {
         Self {
             engines: Vec::new(),
+            skipped: AtomicUsize::new(0),
         }
     }
 
@@ -87,7 +165,12 @@ impl TemplateLoader {
     pub async fn load_template(&self, path: &Path) -> Result<Box<dyn Template>> {
         for engine in &self.engines {
             if engine.supports_file(path) {
-                return engine.load_template(path).await;
+                let started = std::time::Instant::now();
+                let template = engine.load_template(path).await?;
+                if let Some(profiler) = crate::metrics::profiler::get_profiler() {
+                    profiler.record_load(template.id(), started.elapsed());
+                }
+                return Ok(template);
             }
         }
 
@@ -95,7 +178,10 @@ impl TemplateLoader {
     }
 
     /// Check if a file has a valid template extension
-    fn is_valid_template_file(path: &Path) -> bool {
+    pub(crate) fn is_valid_template_file(path: &Path) -> bool {
+        if crate::engine::common::is_metadata_sidecar(path) {
+            return false;
+        }
         if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
             matches!(
                 ext,
@@ -110,6 +196,7 @@ impl TemplateLoader {
                 "rb" |            // Ruby
                 "pl" |            // Perl
                 "php" |           // PHP
+                "rhai" |          // Rhai
                 "sh" | "bash" // Shell
             )
         } else {
@@ -118,11 +205,7 @@ impl TemplateLoader {
     }
 
     /// Load all templates from a directory
-    pub fn load_templates_from_dir<'a>(
-        &'a self,
-        dir: &'a Path,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<Vec<Box<dyn Template>>>> + 'a>>
-    {
+    pub fn load_templates_from_dir<'a>(&'a self, dir: &'a Path) -> TemplatesFuture<'a> {
         Box::pin(async move {
             let mut templates = Vec::new();
 
@@ -148,21 +231,14 @@ impl TemplateLoader {
                     match self.load_template(&path).await {
                         Ok(template) => templates.push(template),
                         Err(e) => {
+                            self.skipped.fetch_add(1, Ordering::Relaxed);
                             tracing::warn!("Failed to load template {}: {}", path.display(), e);
                         }
                     }
                 } else if path.is_dir() {
                     // Skip build artifact directories, disabled templates, and skeleton templates
                     if let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) {
-                        if matches!(
-                            dir_name,
-                            "target"
-                                | "node_modules"
-                                | ".git"
-                                | "__pycache__"
-                                | "_disabled"
-                                | "skeleton"
-                        ) {
+                        if EXCLUDED_TEMPLATE_DIRS.contains(&dir_name) {
                             tracing::trace!("Skipping excluded directory: {}", path.display());
                             continue;
                         }
@@ -190,6 +266,11 @@ impl TemplateLoader {
     pub fn engines(&self) -> &[Box<dyn TemplateEngine>] {
         &self.engines
     }
+
+    /// Template files skipped by directory loads so far because they failed to load
+    pub fn skipped_count(&self) -> usize {
+        self.skipped.load(Ordering::Relaxed)
+    }
 }
 
 impl Default for TemplateLoader {
@@ -340,6 +421,7 @@ mod tests {
                 description: "Test template".to_string(),
                 cve_ids: Vec::new(),
                 cwe_ids: Vec::new(),
+                references: Vec::new(),
                 cvss_score: None,
                 tags,
                 language: TemplateLanguage::Yaml,
@@ -348,6 +430,12 @@ mod tests {
                 updated: Utc::now(),
                 version: "1.0".to_string(),
                 confidence: None,
+                rate_limit: None,
+                max_requests: None,
+                deprecated: false,
+                superseded_by: None,
+                created_date: None,
+                depends_on: Vec::new(),
             },
         })
     }
@@ -1,6 +1,7 @@
 //! Template management system with multi-source discovery
 
 use crate::error::{Error, Result};
+use crate::template::pack::PackRegistry;
 use crate::template::paths::PathResolver;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -36,6 +37,8 @@ pub struct TemplateLocation {
     pub path: PathBuf,
     /// Source location type
     pub source: TemplateSource,
+    /// Installed pack the template came from
+    pub pack: Option<String>,
 }
 
 #[derive(Debug)]
@@ -44,6 +47,7 @@ pub struct TemplateManager {
     system_dir: PathBuf,
     user_dir: PathBuf,
     local_dir: PathBuf,
+    packs_file: PathBuf,
     cache: Arc<RwLock<HashMap<String, TemplateLocation>>>,
 }
 
@@ -54,6 +58,7 @@ impl TemplateManager {
             system_dir: PathResolver::system_template_dir(),
             user_dir: PathResolver::user_template_dir(),
             local_dir: PathResolver::local_template_dir(),
+            packs_file: PackRegistry::default_path(),
             cache: Arc::new(RwLock::new(HashMap::new())),
         }
     }
@@ -79,13 +84,26 @@ impl TemplateManager {
 
     /// Discover all templates from all sources
     pub async fn discover_all(&self) -> Result<()> {
+        let packs = PackRegistry::load(&self.packs_file).unwrap_or_else(|e| {
+            tracing::warn!(
+                "Ignoring pack registry {}: {}",
+                self.packs_file.display(),
+                e
+            );
+            PackRegistry::default()
+        });
+
         let mut cache = self.cache.write().await;
         cache.clear();
 
         // Discover in reverse priority order so higher priority overrides
-        self.discover_from_dir(&self.system_dir, TemplateSource::System, &mut cache)?;
-        self.discover_from_dir(&self.user_dir, TemplateSource::User, &mut cache)?;
-        self.discover_from_dir(&self.local_dir, TemplateSource::Local, &mut cache)?;
+        for (dir, source) in [
+            (&self.system_dir, TemplateSource::System),
+            (&self.user_dir, TemplateSource::User),
+            (&self.local_dir, TemplateSource::Local),
+        ] {
+            self.discover_from_dir(dir, source, &packs, &mut cache)?;
+        }
 
         tracing::info!("Discovered {} templates", cache.len());
         Ok(())
@@ -96,6 +114,7 @@ impl TemplateManager {
         &self,
         dir: &Path,
         source: TemplateSource,
+        packs: &PackRegistry,
         cache: &mut HashMap<String, TemplateLocation>,
     ) -> Result<()> {
         if !dir.exists() {
@@ -115,6 +134,7 @@ impl TemplateManager {
 
             let path = entry.path();
             if let Some(template_id) = self.extract_template_id(path) {
+                let pack = packs.pack_for(path).map(str::to_string);
                 // Higher priority sources override lower priority
                 cache
                     .entry(template_id.clone())
@@ -122,12 +142,14 @@ impl TemplateManager {
                         if source.priority() > loc.source.priority() {
                             loc.path = path.to_path_buf();
                             loc.source = source;
+                            loc.pack = pack.clone();
                         }
                     })
                     .or_insert_with(|| TemplateLocation {
                         template_id: template_id.clone(),
                         path: path.to_path_buf(),
                         source,
+                        pack,
                     });
                 count += 1;
             }
@@ -166,6 +188,18 @@ impl TemplateManager {
         cache.get(id).cloned()
     }
 
+    /// IDs of discovered templates that came from `pack`
+    pub async fn templates_in_pack(&self, pack: &str) -> Vec<String> {
+        let cache = self.cache.read().await;
+        let mut ids: Vec<String> = cache
+            .values()
+            .filter(|loc| loc.pack.as_deref() == Some(pack))
+            .map(|loc| loc.template_id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+
     /// Get all discovered template IDs
     pub async fn get_all_template_ids(&self) -> Vec<String> {
         let cache = self.cache.read().await;
@@ -210,4 +244,33 @@ mod tests {
         let loc = manager.get_template_location("test-template").await;
         assert!(loc.is_some());
     }
+
+    #[tokio::test]
+    async fn test_templates_are_attributed_to_their_pack() {
+        use crate::template::{PackInstaller, PackManifest};
+
+        let source = TempDir::new().unwrap();
+        std::fs::write(source.path().join("acme-login.yaml"), "id: acme-login").unwrap();
+        let work = TempDir::new().unwrap();
+        let archive = work.path().join("acme.cxgpack");
+        PackManifest::from_dir("acme", "1.0.0", "ACME", "", source.path())
+            .unwrap()
+            .write_archive(source.path(), &archive)
+            .unwrap();
+
+        let mut manager = TemplateManager::new();
+        manager.system_dir = work.path().join("none");
+        manager.local_dir = work.path().join("none");
+        manager.user_dir = work.path().join("templates");
+        manager.packs_file = work.path().join("packs.json");
+        PackInstaller::new(manager.user_dir.clone(), manager.packs_file.clone())
+            .install(archive.to_str().unwrap())
+            .await
+            .unwrap();
+
+        manager.discover_all().await.unwrap();
+        let loc = manager.get_template_location("acme-login").await.unwrap();
+        assert_eq!(loc.pack.as_deref(), Some("acme"));
+        assert_eq!(manager.templates_in_pack("acme").await, vec!["acme-login"]);
+    }
 }
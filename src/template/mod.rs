@@ -4,24 +4,44 @@
 
 // Module declarations
 mod auto_update;
+mod dependency;
+mod deprecation;
+mod encoding;
 mod engine;
 mod git;
 mod manager;
+mod pack;
 mod paths;
+mod registry;
 mod repository;
 mod repository_config;
+mod scaffold;
+mod selection;
 mod stats;
+mod usage;
 mod version;
+mod watcher;
 
 // Re-export all template engine types (backward compatibility)
 pub use engine::*;
 
 // Export new template management types
 pub use auto_update::AutoUpdater;
+pub use dependency::{missing_dependencies, DependencyPlan};
+pub use deprecation::{follow_deprecations, missing_replacements};
+pub use encoding::{decode_source, read_source, DecodedSource, SourceEncoding};
 pub use git::GitClient;
 pub use manager::{TemplateLocation, TemplateManager, TemplateSource};
+pub use pack::{
+    InstalledPack, PackEntry, PackInstaller, PackManifest, PackRegistry, PACK_EXTENSION,
+};
 pub use paths::PathResolver;
+pub use registry::TemplateRegistry;
 pub use repository::RepositoryManager;
 pub use repository_config::{Repository, RepositoryConfig};
+pub use scaffold::ProjectScaffold;
+pub use selection::{is_template_source_file, parse_template_entries, separate_template_entries};
 pub use stats::TemplateStats;
+pub use usage::{TemplateUsage, UsageStore};
 pub use version::TemplateVersion;
+pub use watcher::{TemplateEvent, TemplateWatcher};
@@ -0,0 +1,589 @@
+//! Template packs: curated template collections distributed as one archive
+//!
+//! A `.cxgpack` file is a gzipped tar holding a `pack.yaml` manifest and the
+//! pack's files under `templates/`. The manifest records a SHA-256 checksum
+//! for every file; installation refuses archives whose contents do not
+//! match it. Installed packs are recorded in a [`PackRegistry`] so the
+//! [`TemplateManager`](super::TemplateManager) can tell which pack a
+//! template came from.
+
+use crate::error::{Error, Result};
+use crate::template::engine::{TemplateLoader, EXCLUDED_TEMPLATE_DIRS};
+use crate::template::paths::PathResolver;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// File extension of pack archives
+pub const PACK_EXTENSION: &str = "cxgpack";
+
+/// Manifest file name inside a pack archive
+pub const MANIFEST_FILE: &str = "pack.yaml";
+
+/// Directory holding the pack's files inside the archive
+const ARCHIVE_TEMPLATE_DIR: &str = "templates";
+
+/// Directory under the templates directory that packs are installed into
+const INSTALL_DIR: &str = "packs";
+
+/// Largest pack archive accepted, uncompressed
+const MAX_UNPACKED_SIZE: u64 = 256 * 1024 * 1024;
+
+/// `pack.yaml`: what a pack contains and who published it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackManifest {
+    /// Pack name, also the directory it is installed into
+    pub name: String,
+    /// Pack version
+    pub version: String,
+    /// Pack author
+    #[serde(default)]
+    pub author: String,
+    /// What the pack is for
+    #[serde(default)]
+    pub description: String,
+    /// When the pack was created
+    pub created_at: DateTime<Utc>,
+    /// Template files
+    pub templates: Vec<PackEntry>,
+    /// Supporting files (snippets, fixtures, metadata sidecars)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assets: Vec<PackEntry>,
+}
+
+/// One file in a pack
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackEntry {
+    /// Template ID; empty for assets
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    /// Path relative to the pack root, `/`-separated
+    pub path: String,
+    /// SHA-256 of the file contents, hex encoded
+    pub sha256: String,
+}
+
+impl PackManifest {
+    /// Manifest for every file under `dir`, skipping excluded and hidden directories
+    pub fn from_dir(
+        name: &str,
+        version: &str,
+        author: &str,
+        description: &str,
+        dir: &Path,
+    ) -> Result<Self> {
+        validate_pack_name(name)?;
+        if !dir.is_dir() {
+            return Err(Error::config(format!(
+                "Template directory not found: {}",
+                dir.display()
+            )));
+        }
+
+        let mut templates = Vec::new();
+        let mut assets = Vec::new();
+        for file in pack_files(dir) {
+            let relative = relative_path(dir, &file)?;
+            let content = std::fs::read(&file)?;
+            let id = if TemplateLoader::is_valid_template_file(&file) {
+                template_id(&file)
+            } else {
+                String::new()
+            };
+            let entry = PackEntry {
+                id,
+                path: relative,
+                sha256: sha256_hex(&content),
+            };
+            if entry.id.is_empty() {
+                assets.push(entry);
+            } else {
+                templates.push(entry);
+            }
+        }
+
+        if templates.is_empty() {
+            return Err(Error::config(format!(
+                "No templates found in {}",
+                dir.display()
+            )));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            author: author.to_string(),
+            description: description.to_string(),
+            created_at: Utc::now(),
+            templates,
+            assets,
+        })
+    }
+
+    /// Template and asset entries
+    pub fn entries(&self) -> impl Iterator<Item = &PackEntry> {
+        self.templates.iter().chain(self.assets.iter())
+    }
+
+    /// Write the manifest and the files it lists from `dir` to a `.cxgpack` archive
+    pub fn write_archive(&self, dir: &Path, output: &Path) -> Result<()> {
+        if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::File::create(output)?;
+        let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+        let manifest = serde_yaml::to_string(self)?;
+        append_bytes(&mut archive, MANIFEST_FILE, manifest.as_bytes())?;
+        for entry in self.entries() {
+            let content = std::fs::read(dir.join(&entry.path))?;
+            if sha256_hex(&content) != entry.sha256 {
+                return Err(Error::config(format!(
+                    "{} changed while the pack was being created",
+                    entry.path
+                )));
+            }
+            let archive_path = format!("{}/{}", ARCHIVE_TEMPLATE_DIR, entry.path);
+            append_bytes(&mut archive, &archive_path, &content)?;
+        }
+
+        archive.into_inner()?.finish()?;
+        Ok(())
+    }
+}
+
+/// A pack recorded as installed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledPack {
+    /// Manifest the pack was installed from
+    pub manifest: PackManifest,
+    /// Directory the pack's files were extracted into
+    pub path: PathBuf,
+    /// Where the pack was installed from (file path or URL)
+    pub source: String,
+    /// When the pack was installed
+    pub installed_at: DateTime<Utc>,
+}
+
+impl InstalledPack {
+    /// Number of templates in the pack
+    pub fn template_count(&self) -> usize {
+        self.manifest.templates.len()
+    }
+}
+
+/// Installed packs, stored in `~/.cert-x-gen/packs.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackRegistry {
+    /// Installed packs, keyed by pack name
+    pub packs: BTreeMap<String, InstalledPack>,
+}
+
+impl PackRegistry {
+    /// Default registry location
+    pub fn default_path() -> PathBuf {
+        PathResolver::user_config_dir().join("packs.json")
+    }
+
+    /// Load the registry, empty if the file does not exist yet
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Save the registry
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Installed packs in name order
+    pub fn list(&self) -> impl Iterator<Item = &InstalledPack> {
+        self.packs.values()
+    }
+
+    /// Installed pack by name
+    pub fn get(&self, name: &str) -> Option<&InstalledPack> {
+        self.packs.get(name)
+    }
+
+    /// Name of the installed pack a file belongs to
+    pub fn pack_for(&self, path: &Path) -> Option<&str> {
+        self.packs
+            .values()
+            .find(|pack| path.starts_with(&pack.path))
+            .map(|pack| pack.manifest.name.as_str())
+    }
+
+    fn register(&mut self, pack: InstalledPack) {
+        self.packs.insert(pack.manifest.name.clone(), pack);
+    }
+}
+
+/// Installs `.cxgpack` archives into a templates directory
+#[derive(Debug, Clone)]
+pub struct PackInstaller {
+    templates_dir: PathBuf,
+    registry_path: PathBuf,
+}
+
+impl PackInstaller {
+    /// Installer extracting into `templates_dir/packs/<name>` and recording packs at `registry_path`
+    pub fn new(templates_dir: PathBuf, registry_path: PathBuf) -> Self {
+        Self {
+            templates_dir,
+            registry_path,
+        }
+    }
+
+    /// Installer for the user template directory and default registry
+    pub fn for_user() -> Self {
+        Self::new(
+            PathResolver::user_template_dir(),
+            PackRegistry::default_path(),
+        )
+    }
+
+    /// Registry of packs installed so far
+    pub fn registry(&self) -> Result<PackRegistry> {
+        PackRegistry::load(&self.registry_path)
+    }
+
+    /// Install a pack from a file path or an `http(s)://` URL
+    ///
+    /// Reinstalling a pack with the same name replaces it.
+    pub async fn install(&self, source: &str) -> Result<InstalledPack> {
+        let archive = if source.starts_with("http://") || source.starts_with("https://") {
+            reqwest::get(source)
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| Error::Network(format!("Failed to fetch {}: {}", source, e)))?
+                .bytes()
+                .await
+                .map_err(|e| Error::Network(format!("Failed to read {}: {}", source, e)))?
+                .to_vec()
+        } else {
+            std::fs::read(source)
+                .map_err(|e| Error::config(format!("Failed to read '{}': {}", source, e)))?
+        };
+        self.install_bytes(&archive, source)
+    }
+
+    /// Install a pack archive already read into memory
+    pub fn install_bytes(&self, archive: &[u8], source: &str) -> Result<InstalledPack> {
+        let (manifest, files) = read_archive(archive)?;
+        verify(&manifest, &files)?;
+
+        let pack_dir = self.templates_dir.join(INSTALL_DIR).join(&manifest.name);
+        if pack_dir.exists() {
+            std::fs::remove_dir_all(&pack_dir)?;
+        }
+        for entry in manifest.entries() {
+            let path = pack_dir.join(&entry.path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, &files[&entry.path])?;
+        }
+
+        let installed = InstalledPack {
+            manifest,
+            path: pack_dir,
+            source: source.to_string(),
+            installed_at: Utc::now(),
+        };
+        let mut registry = self.registry()?;
+        registry.register(installed.clone());
+        registry.save(&self.registry_path)?;
+
+        tracing::info!(
+            "Installed pack {} {} ({} templates) into {}",
+            installed.manifest.name,
+            installed.manifest.version,
+            installed.template_count(),
+            installed.path.display()
+        );
+        Ok(installed)
+    }
+}
+
+/// Manifest and file contents (keyed by manifest path) of a pack archive
+fn read_archive(archive: &[u8]) -> Result<(PackManifest, BTreeMap<String, Vec<u8>>)> {
+    let invalid = |e: std::io::Error| Error::config(format!("Invalid pack archive: {}", e));
+
+    let mut manifest = None;
+    let mut files = BTreeMap::new();
+    let mut unpacked = 0u64;
+    let mut tar = tar::Archive::new(GzDecoder::new(archive));
+    for entry in tar.entries().map_err(invalid)? {
+        let mut entry = entry.map_err(invalid)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path().map_err(invalid)?.into_owned();
+        let path = safe_relative_path(&path)
+            .ok_or_else(|| Error::config(format!("Unsafe path in pack: {}", path.display())))?;
+
+        unpacked += entry.header().size().map_err(invalid)?;
+        if unpacked > MAX_UNPACKED_SIZE {
+            return Err(Error::config("Pack archive is too large"));
+        }
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).map_err(invalid)?;
+
+        if path == MANIFEST_FILE {
+            manifest = Some(serde_yaml::from_slice::<PackManifest>(&content)?);
+        } else if let Some(relative) = path
+            .strip_prefix(ARCHIVE_TEMPLATE_DIR)
+            .and_then(|p| p.strip_prefix('/'))
+        {
+            files.insert(relative.to_string(), content);
+        } else {
+            return Err(Error::config(format!("Unexpected file in pack: {}", path)));
+        }
+    }
+
+    let manifest =
+        manifest.ok_or_else(|| Error::config(format!("Pack has no {}", MANIFEST_FILE)))?;
+    validate_pack_name(&manifest.name)?;
+    Ok((manifest, files))
+}
+
+/// Check that the archive holds exactly the manifest's files with matching checksums
+fn verify(manifest: &PackManifest, files: &BTreeMap<String, Vec<u8>>) -> Result<()> {
+    for entry in manifest.entries() {
+        if safe_relative_path(Path::new(&entry.path)).is_none() {
+            return Err(Error::config(format!(
+                "Unsafe path in pack manifest: {}",
+                entry.path
+            )));
+        }
+        let content = files.get(&entry.path).ok_or_else(|| {
+            Error::config(format!("Pack {} is missing {}", manifest.name, entry.path))
+        })?;
+        if sha256_hex(content) != entry.sha256 {
+            return Err(Error::config(format!(
+                "Checksum mismatch for {} in pack {}",
+                entry.path, manifest.name
+            )));
+        }
+    }
+    if let Some(extra) = files
+        .keys()
+        .find(|path| !manifest.entries().any(|e| &e.path == *path))
+    {
+        return Err(Error::config(format!(
+            "Pack {} contains {} which is not in its manifest",
+            manifest.name, extra
+        )));
+    }
+    Ok(())
+}
+
+/// Regular files to bundle from `dir`, in a stable order
+fn pack_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(dir)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            e.depth() == 0
+                || !e.file_name().to_str().is_some_and(|name| {
+                    name.starts_with('.') || EXCLUDED_TEMPLATE_DIRS.contains(&name)
+                })
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Template ID from its file name, as the template manager derives it
+fn template_id(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn relative_path(dir: &Path, file: &Path) -> Result<String> {
+    let relative = file
+        .strip_prefix(dir)
+        .map_err(|_| Error::config(format!("{} is outside {}", file.display(), dir.display())))?;
+    let parts: Vec<&str> = relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect();
+    Ok(parts.join("/"))
+}
+
+/// `/`-joined path if it stays inside the directory it is extracted into
+fn safe_relative_path(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn validate_pack_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::config(format!(
+            "Invalid pack name '{}': use letters, digits, '-', '_' and '.'",
+            name
+        )))
+    }
+}
+
+fn append_bytes<W: std::io::Write>(
+    archive: &mut tar::Builder<W>,
+    path: &str,
+    content: &[u8],
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, path, content)?;
+    Ok(())
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn source_dir() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("web/fixtures")).unwrap();
+        std::fs::write(dir.path().join("redis-open.py"), "# redis").unwrap();
+        std::fs::write(dir.path().join("web/admin-panel.yaml"), "id: admin-panel").unwrap();
+        std::fs::write(dir.path().join("web/fixtures/login.http"), "HTTP/1.1 200").unwrap();
+        std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+        std::fs::write(dir.path().join(".git/HEAD"), "ref").unwrap();
+        dir
+    }
+
+    fn create_pack(source: &Path, output: &Path) -> PackManifest {
+        let manifest =
+            PackManifest::from_dir("acme-corp-pack", "1.2.0", "ACME", "ACME checks", source)
+                .unwrap();
+        manifest.write_archive(source, output).unwrap();
+        manifest
+    }
+
+    #[tokio::test]
+    async fn test_create_install_list_round_trip() {
+        let source = source_dir();
+        let work = TempDir::new().unwrap();
+        let archive = work.path().join("acme-corp-pack.cxgpack");
+        let manifest = create_pack(source.path(), &archive);
+
+        let ids: Vec<&str> = manifest.templates.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["redis-open", "admin-panel"]);
+        assert_eq!(manifest.assets.len(), 1);
+        assert_eq!(manifest.assets[0].path, "web/fixtures/login.http");
+
+        let installer = PackInstaller::new(
+            work.path().join("templates"),
+            work.path().join("packs.json"),
+        );
+        let installed = installer.install(archive.to_str().unwrap()).await.unwrap();
+        assert_eq!(installed.manifest, manifest);
+        assert_eq!(
+            std::fs::read_to_string(installed.path.join("web/admin-panel.yaml")).unwrap(),
+            "id: admin-panel"
+        );
+        assert!(!installed.path.join(MANIFEST_FILE).exists());
+
+        let registry = installer.registry().unwrap();
+        let packs: Vec<&InstalledPack> = registry.list().collect();
+        assert_eq!(packs.len(), 1);
+        assert_eq!(packs[0].template_count(), 2);
+        assert_eq!(
+            registry.pack_for(&installed.path.join("redis-open.py")),
+            Some("acme-corp-pack")
+        );
+
+        // Reinstalling replaces the pack instead of adding a second entry
+        installer.install(archive.to_str().unwrap()).await.unwrap();
+        assert_eq!(installer.registry().unwrap().packs.len(), 1);
+    }
+
+    #[test]
+    fn test_install_rejects_checksum_mismatch() {
+        let source = source_dir();
+        let work = TempDir::new().unwrap();
+        let archive = work.path().join("pack.cxgpack");
+        let mut manifest = create_pack(source.path(), &archive);
+
+        // Rebuild the archive with a manifest that no longer matches one file
+        manifest.templates[0].sha256 = sha256_hex(b"something else");
+        let file = std::fs::File::create(&archive).unwrap();
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let yaml = serde_yaml::to_string(&manifest).unwrap();
+        append_bytes(&mut builder, MANIFEST_FILE, yaml.as_bytes()).unwrap();
+        for entry in manifest.entries() {
+            let content = std::fs::read(source.path().join(&entry.path)).unwrap();
+            append_bytes(&mut builder, &format!("templates/{}", entry.path), &content).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let installer = PackInstaller::new(
+            work.path().join("templates"),
+            work.path().join("packs.json"),
+        );
+        let err = installer
+            .install_bytes(&std::fs::read(&archive).unwrap(), "pack.cxgpack")
+            .unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"));
+        assert!(installer.registry().unwrap().packs.is_empty());
+        assert!(!work.path().join("templates/packs/acme-corp-pack").exists());
+    }
+
+    #[test]
+    fn test_unsafe_paths_and_names_are_rejected() {
+        assert_eq!(
+            safe_relative_path(Path::new("a/./b.yaml")),
+            Some("a/b.yaml".into())
+        );
+        assert_eq!(safe_relative_path(Path::new("../escape.yaml")), None);
+        assert_eq!(safe_relative_path(Path::new("/etc/passwd")), None);
+        assert!(validate_pack_name("acme-corp-pack").is_ok());
+        assert!(validate_pack_name("../acme").is_err());
+        assert!(validate_pack_name(".hidden").is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! In-memory template set kept current from directory watch events
+
+use super::{Template, TemplateEvent};
+use crate::error::{Error, Result};
+use crate::types::{Context, Finding, Protocol, Target, TemplateMetadata};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Templates available to new scans, keyed by template ID
+///
+/// Scans run on a [`snapshot`](Self::snapshot), so removing or replacing a
+/// template only affects scans started afterwards; scans already running
+/// keep the version they started with until they complete.
+#[derive(Default)]
+pub struct TemplateRegistry {
+    templates: RwLock<HashMap<String, Arc<dyn Template>>>,
+}
+
+impl std::fmt::Debug for TemplateRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TemplateRegistry")
+            .field("templates", &self.len())
+            .finish()
+    }
+}
+
+impl TemplateRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry from already loaded templates (first ID wins)
+    pub fn from_templates(templates: Vec<Box<dyn Template>>) -> Self {
+        let mut map: HashMap<String, Arc<dyn Template>> = HashMap::new();
+        for template in templates {
+            map.entry(template.id().to_string())
+                .or_insert_with(|| Arc::from(template));
+        }
+        Self {
+            templates: RwLock::new(map),
+        }
+    }
+
+    /// Number of registered templates
+    pub fn len(&self) -> usize {
+        self.templates.read().unwrap().len()
+    }
+
+    /// Whether no templates are registered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether a template ID is registered
+    pub fn contains(&self, id: &str) -> bool {
+        self.templates.read().unwrap().contains_key(id)
+    }
+
+    /// Templates for a new scan
+    pub fn snapshot(&self) -> Vec<Box<dyn Template>> {
+        self.templates
+            .read()
+            .unwrap()
+            .values()
+            .map(|t| Box::new(SharedTemplate(t.clone())) as Box<dyn Template>)
+            .collect()
+    }
+
+    /// Apply a watch event; added and modified templates are validated first
+    pub fn apply(&self, event: TemplateEvent) -> Result<()> {
+        match event {
+            TemplateEvent::Added(template) | TemplateEvent::Modified(template) => {
+                validate(template.as_ref())?;
+                let id = template.id().to_string();
+                tracing::info!("Registered template {}", id);
+                self.templates
+                    .write()
+                    .unwrap()
+                    .insert(id, Arc::from(template));
+            }
+            TemplateEvent::Removed(id) => {
+                if self.templates.write().unwrap().remove(&id).is_some() {
+                    tracing::info!("Unregistered template {}", id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply events from [`TemplateLoader::watch_directory`](super::TemplateLoader::watch_directory)
+    /// until the channel closes
+    pub fn listen(self: Arc<Self>, mut events: mpsc::Receiver<TemplateEvent>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                if let Err(e) = self.apply(event) {
+                    tracing::warn!("Rejected template: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Reject templates that fail the same checks as `cxg template validate`
+fn validate(template: &dyn Template) -> Result<()> {
+    let metadata = template.metadata();
+    let rejected = |reason: String| Error::TemplateValidation {
+        template: metadata.id.clone(),
+        reason,
+    };
+
+    template.validate().map_err(|e| rejected(e.to_string()))?;
+    let code = std::fs::read_to_string(&metadata.file_path)?;
+    crate::ai::validator::TemplateValidator::new()
+        .validate(&code, metadata.language)
+        .map_err(|e| rejected(e.to_string()))
+}
+
+/// Registry entry handed to a scan, keeping the template alive until it finishes
+struct SharedTemplate(Arc<dyn Template>);
+
+#[async_trait]
+impl Template for SharedTemplate {
+    fn metadata(&self) -> &TemplateMetadata {
+        self.0.metadata()
+    }
+
+    async fn execute(&self, target: &Target, context: &Context) -> Result<Vec<Finding>> {
+        self.0.execute(target, context).await
+    }
+
+    fn validate(&self) -> Result<()> {
+        self.0.validate()
+    }
+
+    async fn prepare(&self) -> Result<()> {
+        self.0.prepare().await
+    }
+
+    fn supported_protocols(&self) -> Vec<Protocol> {
+        self.0.supported_protocols()
+    }
+
+    fn dependencies(&self) -> Vec<PathBuf> {
+        self.0.dependencies()
+    }
+
+    fn estimated_requests(&self) -> usize {
+        self.0.estimated_requests()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::YamlTemplateEngine;
+    use crate::template::TemplateEngine;
+
+    async fn load(dir: &std::path::Path, name: &str, content: &str) -> Box<dyn Template> {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        YamlTemplateEngine::new()
+            .load_template(&path)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_invalid_templates_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = TemplateRegistry::new();
+
+        // Parses, but has no http/network/flows section to run
+        let template = load(
+            dir.path(),
+            "empty.yaml",
+            "id: empty\nname: Empty\nauthor:\n  name: t\nseverity: info\n\
+             description: nothing to run\nlanguage: yaml\n",
+        )
+        .await;
+        let err = registry.apply(TemplateEvent::Added(template)).unwrap_err();
+        assert!(matches!(err, Error::TemplateValidation { .. }));
+        assert!(registry.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_removal_does_not_affect_running_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = load(
+            dir.path(),
+            "probe.yaml",
+            "id: probe\nname: Probe\nauthor:\n  name: t\nseverity: info\n\
+             description: probe\nlanguage: yaml\nhttp:\n  - path: [\"/\"]\n",
+        )
+        .await;
+        let registry = TemplateRegistry::from_templates(vec![template]);
+
+        let in_progress = registry.snapshot();
+        registry
+            .apply(TemplateEvent::Removed("probe".to_string()))
+            .unwrap();
+
+        assert!(registry.snapshot().is_empty());
+        assert_eq!(in_progress.len(), 1);
+        assert_eq!(in_progress[0].id(), "probe");
+        assert_eq!(
+            in_progress[0].metadata().file_path,
+            dir.path().join("probe.yaml")
+        );
+    }
+}
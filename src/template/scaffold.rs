@@ -0,0 +1,514 @@
+//! Project scaffolds for compiled-language templates
+//!
+//! `cxg template create` copies a single skeleton file, which is enough for
+//! interpreted languages. Compiled templates that need dependencies or a
+//! package layout are generated as a small project instead: entry source
+//! file, build manifest with pinned dependencies, metadata sidecar and a
+//! README.
+
+use crate::engine::common::PROJECT_METADATA_FILE;
+use crate::error::{Error, Result};
+use crate::types::TemplateLanguage;
+use std::path::{Path, PathBuf};
+
+/// Java package used for scaffolded Java templates
+const JAVA_PACKAGE: &str = "certxgen.templates";
+
+/// A generated template project, not yet written to disk
+#[derive(Debug, Clone)]
+pub struct ProjectScaffold {
+    /// Template ID
+    pub id: String,
+    /// Template language
+    pub language: TemplateLanguage,
+    /// Project files as (relative path, content)
+    pub files: Vec<(PathBuf, String)>,
+    entry: PathBuf,
+}
+
+impl ProjectScaffold {
+    /// Languages that `init-project` can scaffold
+    pub const SUPPORTED: &'static [TemplateLanguage] = &[
+        TemplateLanguage::Rust,
+        TemplateLanguage::Go,
+        TemplateLanguage::Java,
+    ];
+
+    /// Build the project files for a template
+    pub fn new(language: TemplateLanguage, id: &str, name: &str) -> Result<Self> {
+        if id.is_empty()
+            || !id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            || id.starts_with(|c: char| !c.is_ascii_lowercase())
+        {
+            return Err(Error::config(format!(
+                "Invalid template id '{}': use lowercase letters, digits and '-', starting with a letter",
+                id
+            )));
+        }
+
+        // The name lands in Rust, Go and Java string literals and the YAML
+        // sidecar; without these characters it needs no per-language escaping
+        if name.contains(['"', '\\']) || name.chars().any(char::is_control) {
+            return Err(Error::config(format!(
+                "Invalid template name {:?}: quotes, backslashes and control characters are not allowed",
+                name
+            )));
+        }
+
+        let class_name = pascal_case(id);
+        let vars = [
+            ("{{id}}", id),
+            ("{{name}}", name),
+            ("{{class}}", class_name.as_str()),
+            ("{{package}}", JAVA_PACKAGE),
+        ];
+        let render = |template: &str| {
+            vars.iter().fold(template.to_string(), |acc, (key, value)| {
+                acc.replace(key, value)
+            })
+        };
+
+        let (entry, sources): (PathBuf, Vec<(PathBuf, &str)>) = match language {
+            TemplateLanguage::Rust => {
+                let entry = PathBuf::from(format!("{}.rs", id));
+                (
+                    entry.clone(),
+                    vec![
+                        (PathBuf::from("Cargo.toml"), RUST_MANIFEST),
+                        (entry, RUST_MAIN),
+                    ],
+                )
+            }
+            TemplateLanguage::Go => {
+                let entry = PathBuf::from(format!("{}.go", id));
+                (
+                    entry.clone(),
+                    vec![(PathBuf::from("go.mod"), GO_MODULE), (entry, GO_MAIN)],
+                )
+            }
+            TemplateLanguage::Java => {
+                let entry = Path::new("src/main/java")
+                    .join(JAVA_PACKAGE.replace('.', "/"))
+                    .join(format!("{}.java", class_name));
+                (
+                    entry.clone(),
+                    vec![(PathBuf::from("pom.xml"), JAVA_POM), (entry, JAVA_MAIN)],
+                )
+            }
+            other => {
+                return Err(Error::config(format!(
+                    "init-project does not support {} templates (supported: rust, go, java); use 'cxg template create' instead",
+                    other
+                )))
+            }
+        };
+
+        let mut files: Vec<(PathBuf, String)> = sources
+            .into_iter()
+            .map(|(path, content)| (path, render(content)))
+            .collect();
+        files.push((
+            PathBuf::from(PROJECT_METADATA_FILE),
+            render(METADATA_SIDECAR),
+        ));
+        files.push((
+            PathBuf::from("README.md"),
+            render(README).replace("{{entry}}", &entry.to_string_lossy()),
+        ));
+
+        Ok(Self {
+            id: id.to_string(),
+            language,
+            files,
+            entry,
+        })
+    }
+
+    /// Path of the template entry point, relative to the project directory
+    pub fn entry(&self) -> &Path {
+        &self.entry
+    }
+
+    /// Write the project into `dir`, refusing to overwrite existing files
+    pub fn write(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        if let Some((existing, _)) = self.files.iter().find(|(p, _)| dir.join(p).exists()) {
+            return Err(Error::config(format!(
+                "Refusing to overwrite existing file: {}",
+                dir.join(existing).display()
+            )));
+        }
+
+        let mut written = Vec::with_capacity(self.files.len());
+        for (relative, content) in &self.files {
+            let path = dir.join(relative);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, content)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+/// Convert a kebab-case id into a PascalCase class name
+fn pascal_case(id: &str) -> String {
+    id.split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(c) => c.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+const METADATA_SIDECAR: &str = r#"# Template metadata, read by CERT-X-GEN when loading the template
+id: {{id}}
+name: "{{name}}"
+author: Your Name <you@example.com>
+severity: info
+description: Reports whether the target web server responds on HTTP
+tags:
+  - http
+  - {{id}}
+# Advisory, PoC, vendor bulletin or NVD URLs shown with each finding
+# references:
+#   - https://nvd.nist.gov/vuln/detail/CVE-YYYY-NNNNN
+"#;
+
+const README: &str = r#"# {{name}}
+
+CERT-X-GEN template project `{{id}}`.
+
+- Entry point: `{{entry}}`
+- Metadata: `template.meta.yaml` (id, severity, tags, description)
+
+```bash
+cxg template validate {{entry}}
+cxg scan --scope example.com --templates {{entry}}
+```
+
+When run by CERT-X-GEN the target is passed via `CERT_X_GEN_TARGET_HOST` and
+`CERT_X_GEN_TARGET_PORT`; findings must be printed to stdout as JSON.
+"#;
+
+const RUST_MANIFEST: &str = r#"[package]
+name = "{{id}}"
+version = "0.1.0"
+edition = "2021"
+publish = false
+
+[[bin]]
+name = "{{id}}"
+path = "{{id}}.rs"
+
+[dependencies]
+reqwest = { version = "=0.11.27", default-features = false, features = ["blocking", "rustls-tls"] }
+serde = { version = "=1.0.228", features = ["derive"] }
+serde_json = "=1.0.145"
+"#;
+
+const RUST_MAIN: &str = r#"//! {{name}} - CERT-X-GEN template
+
+use serde::Serialize;
+use std::env;
+use std::time::Duration;
+
+const TEMPLATE_ID: &str = "{{id}}";
+const TEMPLATE_NAME: &str = "{{name}}";
+
+#[derive(Serialize)]
+struct Evidence {
+    request: String,
+    response: String,
+}
+
+#[derive(Serialize)]
+struct Finding {
+    template_id: &'static str,
+    template_name: &'static str,
+    severity: &'static str,
+    confidence: u8,
+    title: String,
+    description: String,
+    host: String,
+    matched_at: String,
+    evidence: Evidence,
+}
+
+#[derive(Serialize)]
+struct Output {
+    findings: Vec<Finding>,
+}
+
+fn check(host: &str, port: u16) -> Option<Finding> {
+    let url = format!("http://{}:{}/", host, port);
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .ok()?;
+    let response = client.get(&url).send().ok()?;
+    let status = response.status();
+    let body: String = response.text().ok()?.chars().take(512).collect();
+
+    Some(Finding {
+        template_id: TEMPLATE_ID,
+        template_name: TEMPLATE_NAME,
+        severity: "info",
+        confidence: 90,
+        title: format!("HTTP service responded with {}", status),
+        description: format!("{} answered an HTTP GET request", url),
+        host: host.to_string(),
+        matched_at: url.clone(),
+        evidence: Evidence {
+            request: format!("GET {}", url),
+            response: body,
+        },
+    })
+}
+
+fn main() {
+    let host = env::var("CERT_X_GEN_TARGET_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
+    let port = env::var("CERT_X_GEN_TARGET_PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(80);
+
+    let output = Output {
+        findings: check(&host, port).into_iter().collect(),
+    };
+    println!("{}", serde_json::to_string(&output).expect("findings serialize"));
+}
+"#;
+
+const GO_MODULE: &str = r#"module {{id}}
+
+go 1.21
+"#;
+
+const GO_MAIN: &str = r#"// {{name}} - CERT-X-GEN template
+package main
+
+import (
+	"encoding/json"
+	"fmt"
+	"io"
+	"net/http"
+	"os"
+	"time"
+)
+
+const templateID = "{{id}}"
+const templateName = "{{name}}"
+
+type Evidence struct {
+	Request  string `json:"request"`
+	Response string `json:"response"`
+}
+
+type Finding struct {
+	TemplateID   string   `json:"template_id"`
+	TemplateName string   `json:"template_name"`
+	Severity     string   `json:"severity"`
+	Confidence   int      `json:"confidence"`
+	Title        string   `json:"title"`
+	Description  string   `json:"description"`
+	Host         string   `json:"host"`
+	MatchedAt    string   `json:"matched_at"`
+	Evidence     Evidence `json:"evidence"`
+}
+
+func check(host, port string) []Finding {
+	url := fmt.Sprintf("http://%s:%s/", host, port)
+	client := &http.Client{Timeout: 10 * time.Second}
+	resp, err := client.Get(url)
+	if err != nil {
+		return []Finding{}
+	}
+	defer resp.Body.Close()
+	body, _ := io.ReadAll(io.LimitReader(resp.Body, 512))
+
+	return []Finding{{
+		TemplateID:   templateID,
+		TemplateName: templateName,
+		Severity:     "info",
+		Confidence:   90,
+		Title:        fmt.Sprintf("HTTP service responded with %s", resp.Status),
+		Description:  fmt.Sprintf("%s answered an HTTP GET request", url),
+		Host:         host,
+		MatchedAt:    url,
+		Evidence:     Evidence{Request: "GET " + url, Response: string(body)},
+	}}
+}
+
+func main() {
+	host := os.Getenv("CERT_X_GEN_TARGET_HOST")
+	if host == "" {
+		host = "127.0.0.1"
+	}
+	port := os.Getenv("CERT_X_GEN_TARGET_PORT")
+	if port == "" {
+		port = "80"
+	}
+
+	output, _ := json.Marshal(map[string][]Finding{"findings": check(host, port)})
+	fmt.Println(string(output))
+}
+"#;
+
+const JAVA_POM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0"
+         xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+         xsi:schemaLocation="http://maven.apache.org/POM/4.0.0 http://maven.apache.org/xsd/maven-4.0.0.xsd">
+  <modelVersion>4.0.0</modelVersion>
+
+  <groupId>{{package}}</groupId>
+  <artifactId>{{id}}</artifactId>
+  <version>0.1.0</version>
+
+  <properties>
+    <maven.compiler.release>11</maven.compiler.release>
+    <project.build.sourceEncoding>UTF-8</project.build.sourceEncoding>
+  </properties>
+</project>
+"#;
+
+const JAVA_MAIN: &str = r#"// {{name}} - CERT-X-GEN template
+package {{package}};
+
+import java.net.URI;
+import java.net.http.HttpClient;
+import java.net.http.HttpRequest;
+import java.net.http.HttpResponse;
+import java.time.Duration;
+
+public class {{class}} {
+    static final String TEMPLATE_ID = "{{id}}";
+    static final String TEMPLATE_NAME = "{{name}}";
+
+    static String quote(String value) {
+        StringBuilder sb = new StringBuilder("\"");
+        for (char c : value.toCharArray()) {
+            switch (c) {
+                case '"': sb.append("\\\""); break;
+                case '\\': sb.append("\\\\"); break;
+                case '\n': sb.append("\\n"); break;
+                case '\r': sb.append("\\r"); break;
+                case '\t': sb.append("\\t"); break;
+                default:
+                    if (c < 0x20) {
+                        sb.append(String.format("\\u%04x", (int) c));
+                    } else {
+                        sb.append(c);
+                    }
+            }
+        }
+        return sb.append('"').toString();
+    }
+
+    static String check(String host, String port) {
+        String url = "http://" + host + ":" + port + "/";
+        try {
+            HttpClient client = HttpClient.newBuilder().connectTimeout(Duration.ofSeconds(10)).build();
+            HttpRequest request = HttpRequest.newBuilder(URI.create(url)).timeout(Duration.ofSeconds(10)).GET().build();
+            HttpResponse<String> response = client.send(request, HttpResponse.BodyHandlers.ofString());
+            String body = response.body();
+            if (body.length() > 512) {
+                body = body.substring(0, 512);
+            }
+
+            return "{"
+                + "\"template_id\":" + quote(TEMPLATE_ID) + ","
+                + "\"template_name\":" + quote(TEMPLATE_NAME) + ","
+                + "\"severity\":\"info\","
+                + "\"confidence\":90,"
+                + "\"title\":" + quote("HTTP service responded with " + response.statusCode()) + ","
+                + "\"description\":" + quote(url + " answered an HTTP GET request") + ","
+                + "\"host\":" + quote(host) + ","
+                + "\"matched_at\":" + quote(url) + ","
+                + "\"evidence\":{\"request\":" + quote("GET " + url) + ",\"response\":" + quote(body) + "}"
+                + "}";
+        } catch (Exception e) {
+            return null;
+        }
+    }
+
+    public static void main(String[] args) {
+        String host = System.getenv().getOrDefault("CERT_X_GEN_TARGET_HOST", "127.0.0.1");
+        String port = System.getenv().getOrDefault("CERT_X_GEN_TARGET_PORT", "80");
+
+        String finding = check(host, port);
+        System.out.println("{\"findings\":[" + (finding == null ? "" : finding) + "]}");
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaffold_files() {
+        let rust = ProjectScaffold::new(TemplateLanguage::Rust, "my-check", "My Check").unwrap();
+        assert_eq!(rust.entry(), Path::new("my-check.rs"));
+        let manifest = &rust
+            .files
+            .iter()
+            .find(|(p, _)| p == Path::new("Cargo.toml"))
+            .unwrap()
+            .1;
+        assert!(manifest.contains("name = \"my-check\""));
+        assert!(manifest.contains("reqwest = { version = \"=0.11.27\""));
+
+        let java = ProjectScaffold::new(TemplateLanguage::Java, "my-check", "My Check").unwrap();
+        assert_eq!(
+            java.entry(),
+            Path::new("src/main/java/certxgen/templates/MyCheck.java")
+        );
+        assert!(java
+            .files
+            .iter()
+            .all(|(_, content)| !content.contains("{{")));
+    }
+
+    #[test]
+    fn test_scaffold_rejects_invalid_input() {
+        assert!(ProjectScaffold::new(TemplateLanguage::Python, "my-check", "x").is_err());
+        assert!(ProjectScaffold::new(TemplateLanguage::Rust, "My Check", "x").is_err());
+        assert!(ProjectScaffold::new(TemplateLanguage::Go, "1check", "x").is_err());
+        assert!(ProjectScaffold::new(TemplateLanguage::Rust, "my-check", "My \"Check\"").is_err());
+        assert!(ProjectScaffold::new(TemplateLanguage::Go, "my-check", "C:\\check").is_err());
+        assert!(ProjectScaffold::new(TemplateLanguage::Java, "my-check", "My\nCheck").is_err());
+    }
+
+    #[test]
+    fn test_scaffold_metadata_keeps_yaml_special_names() {
+        let name = "Admin: panel # exposed";
+        let scaffold = ProjectScaffold::new(TemplateLanguage::Go, "my-check", name).unwrap();
+        let sidecar = &scaffold
+            .files
+            .iter()
+            .find(|(p, _)| p == Path::new(PROJECT_METADATA_FILE))
+            .unwrap()
+            .1;
+        let parsed: serde_yaml::Value = serde_yaml::from_str(sidecar).unwrap();
+        assert_eq!(parsed["name"].as_str(), Some(name));
+    }
+
+    #[test]
+    fn test_scaffold_write_refuses_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let scaffold = ProjectScaffold::new(TemplateLanguage::Go, "my-check", "My Check").unwrap();
+
+        let written = scaffold.write(dir.path()).unwrap();
+        assert_eq!(written.len(), 4);
+        assert!(dir.path().join("go.mod").is_file());
+        assert!(dir.path().join(PROJECT_METADATA_FILE).is_file());
+
+        assert!(scaffold.write(dir.path()).is_err());
+    }
+}
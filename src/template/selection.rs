@@ -0,0 +1,290 @@
+//! Template selections given on the command line (`--templates`)
+//!
+//! An entry is a template file, a path relative to a template directory, a
+//! template ID, a comma-separated list of entries, or a list file
+//! (`@templates.txt`, or any `.txt`/`.list`/`.lst`/`.cfg` file) with one entry
+//! per line.
+
+use crate::error::{Error, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Check if a path is a template source file (actual template, not a list file)
+pub fn is_template_source_file(path: &Path) -> bool {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => matches!(
+            ext.to_ascii_lowercase().as_str(),
+            "yaml" | "yml" |  // YAML templates
+            "py" |            // Python
+            "js" |            // JavaScript
+            "rs" |            // Rust
+            "c" |             // C
+            "cpp" | "cc" | "cxx" | // C++
+            "java" |          // Java
+            "go" |            // Go
+            "rb" |            // Ruby
+            "pl" |            // Perl
+            "php" |           // PHP
+            "rhai" |          // Rhai
+            "sh" | "bash" // Shell
+        ),
+        None => false,
+    }
+}
+
+/// Separate template entries into direct file paths and filter IDs
+/// Returns (direct_template_paths, filter_ids)
+///
+/// When a relative path is provided, it will be resolved against the template directories.
+pub fn separate_template_entries(
+    entries: &[String],
+    template_dirs: &[PathBuf],
+) -> Result<(Vec<PathBuf>, Vec<String>)> {
+    let mut direct_paths = Vec::new();
+    let mut filter_ids = Vec::new();
+
+    tracing::debug!(
+        "Separating {} template entries (template_dirs: {:?})",
+        entries.len(),
+        template_dirs
+    );
+
+    for entry in entries {
+        let trimmed = entry.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        // Handle comma-separated entries
+        if trimmed.contains(',') {
+            for part in trimmed.split(',') {
+                let (mut paths, mut ids) =
+                    separate_template_entries(&[part.to_string()], template_dirs)?;
+                direct_paths.append(&mut paths);
+                filter_ids.append(&mut ids);
+            }
+            continue;
+        }
+
+        let path = Path::new(trimmed);
+        let exists = path.exists();
+        let is_file = path.is_file();
+        let is_template = is_template_source_file(path);
+        let is_abs = path.is_absolute();
+
+        tracing::debug!(
+            "Entry '{}': exists={}, is_file={}, is_template={}, is_absolute={}",
+            trimmed,
+            exists,
+            is_file,
+            is_template,
+            is_abs
+        );
+
+        // Check if it's a direct path to a template file (absolute or relative that exists)
+        if exists && is_file && is_template {
+            tracing::info!("Detected direct template path: {}", trimmed);
+            direct_paths.push(path.to_path_buf());
+        } else if is_abs && is_template && !exists {
+            // Absolute path that looks like a template but doesn't exist - report error
+            return Err(Error::config(format!(
+                "Template file not found: {}",
+                trimmed
+            )));
+        } else if !is_abs && (trimmed.contains('/') || trimmed.contains('\\')) && is_template {
+            // Relative path that looks like a template - try resolving against template directories
+            let mut found = false;
+
+            for template_dir in template_dirs {
+                let resolved_path = template_dir.join(trimmed);
+                tracing::debug!(
+                    "Trying to resolve relative path: {} -> {}",
+                    trimmed,
+                    resolved_path.display()
+                );
+
+                if resolved_path.exists() && resolved_path.is_file() {
+                    tracing::info!(
+                        "Resolved relative template path: {} -> {}",
+                        trimmed,
+                        resolved_path.display()
+                    );
+                    direct_paths.push(resolved_path);
+                    found = true;
+                    break;
+                }
+            }
+
+            if !found {
+                // Couldn't find the template in any directory
+                let searched_dirs: Vec<String> = template_dirs
+                    .iter()
+                    .map(|d| d.display().to_string())
+                    .collect();
+                return Err(Error::config(format!(
+                    "Template file not found: {} (searched in: {})",
+                    trimmed,
+                    if searched_dirs.is_empty() {
+                        "current directory".to_string()
+                    } else {
+                        searched_dirs.join(", ")
+                    }
+                )));
+            }
+        } else {
+            // Treat as template ID for filtering
+            tracing::debug!("Treating as filter ID: {}", trimmed);
+            filter_ids.push(trimmed.to_string());
+        }
+    }
+
+    tracing::info!(
+        "Separated template entries: {} direct paths, {} filter IDs",
+        direct_paths.len(),
+        filter_ids.len()
+    );
+
+    Ok((direct_paths, filter_ids))
+}
+
+/// Expand template entries, reading list files, into unique IDs and paths in order
+pub fn parse_template_entries(entries: &[String]) -> Result<Vec<String>> {
+    let mut collected = Vec::new();
+    let mut file_stack = HashSet::new();
+
+    for entry in entries {
+        expand_template_entry(entry, &mut collected, &mut file_stack)?;
+    }
+
+    let mut seen = HashSet::new();
+    let mut unique = Vec::new();
+
+    for entry in collected {
+        if seen.insert(entry.clone()) {
+            unique.push(entry);
+        }
+    }
+
+    Ok(unique)
+}
+
+fn expand_template_entry(
+    entry: &str,
+    acc: &mut Vec<String>,
+    file_stack: &mut HashSet<PathBuf>,
+) -> Result<()> {
+    let trimmed = entry.trim();
+    if trimmed.is_empty() {
+        return Ok(());
+    }
+
+    if trimmed.contains(',') {
+        for part in trimmed.split(',') {
+            expand_template_entry(part, acc, file_stack)?;
+        }
+        return Ok(());
+    }
+
+    let (candidate, forced_file) = crate::scope::strip_file_prefix(trimmed);
+
+    let path = Path::new(candidate);
+
+    if forced_file || (path.exists() && path.is_file() && is_template_list_file(path)) {
+        if !path.exists() {
+            return Err(Error::config(format!(
+                "Template list file not found: {}",
+                candidate
+            )));
+        }
+
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        if !file_stack.insert(canonical.clone()) {
+            return Err(Error::config(format!(
+                "Recursive template file reference detected: {}",
+                canonical.display()
+            )));
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| {
+            Error::config(format!(
+                "Failed to read template list '{}': {}",
+                candidate, e
+            ))
+        })?;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            expand_template_entry(line, acc, file_stack)?;
+        }
+
+        file_stack.remove(&canonical);
+        return Ok(());
+    }
+
+    acc.push(trimmed.to_string());
+    Ok(())
+}
+
+fn is_template_list_file(path: &Path) -> bool {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => matches!(
+            ext.to_ascii_lowercase().as_str(),
+            "txt" | "list" | "lst" | "cfg"
+        ),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_separate_template_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let direct = dir.path().join("direct.yaml");
+        fs::write(&direct, "id: direct\n").unwrap();
+        fs::create_dir(dir.path().join("web")).unwrap();
+        fs::write(dir.path().join("web/relative.py"), "").unwrap();
+
+        let entries = vec![
+            format!("{},redis-unauth", direct.display()),
+            "web/relative.py".to_string(),
+            " git-config ".to_string(),
+        ];
+        let dirs = vec![dir.path().to_path_buf()];
+        let (paths, ids) = separate_template_entries(&entries, &dirs).unwrap();
+        assert_eq!(paths, [direct, dir.path().join("web/relative.py")]);
+        assert_eq!(ids, ["redis-unauth", "git-config"]);
+
+        let missing = vec!["web/missing.yaml".to_string()];
+        assert!(separate_template_entries(&missing, &dirs).is_err());
+        let missing = vec![dir.path().join("gone.yaml").display().to_string()];
+        assert!(separate_template_entries(&missing, &dirs).is_err());
+    }
+
+    #[test]
+    fn test_parse_template_entries_reads_lists() {
+        let dir = tempfile::tempdir().unwrap();
+        let list = dir.path().join("web.txt");
+        fs::write(&list, "# web checks\nxss\nsqli, xss\n").unwrap();
+
+        let entries = vec![
+            "redis-unauth".to_string(),
+            list.display().to_string(),
+            format!("@{}", list.display()),
+        ];
+        assert_eq!(
+            parse_template_entries(&entries).unwrap(),
+            ["redis-unauth", "xss", "sqli"]
+        );
+        assert!(parse_template_entries(&["@missing.txt".to_string()]).is_err());
+        assert!(is_template_source_file(Path::new("check.RHAI")));
+        assert!(!is_template_source_file(Path::new("web.txt")));
+    }
+}
@@ -52,6 +52,7 @@ impl TemplateStats {
                     "rb" => "ruby",
                     "pl" => "perl",
                     "php" => "php",
+                    "rhai" => "rhai",
                     "sh" | "bash" => "shell",
                     "yaml" | "yml" => "yaml",
                     _ => continue,
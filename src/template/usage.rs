@@ -0,0 +1,165 @@
+//! Local template usage tracking
+//!
+//! Every scan adds to per-template counters (executions, findings produced,
+//! last used) kept in `~/.cert-x-gen/stats/usage.json`. Nothing leaves the
+//! machine; `templates.usage_tracking: false` or `--no-usage-tracking` turns
+//! recording off. Search ranks `--sort popularity` by these counters and
+//! `template list --usage`, `template info` and `template stats --usage` show them.
+
+use crate::error::Result;
+use crate::template::paths::PathResolver;
+use crate::types::ScanResults;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Counters for one template
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TemplateUsage {
+    /// Times the template ran against a target
+    pub executions: u64,
+    /// Findings it produced
+    pub findings: u64,
+    /// Last scan that ran it
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+impl TemplateUsage {
+    /// Findings per execution (0.0 when never run)
+    pub fn hit_rate(&self) -> f64 {
+        if self.executions == 0 {
+            0.0
+        } else {
+            self.findings as f64 / self.executions as f64
+        }
+    }
+
+    /// Most productive first: more findings, then more runs
+    pub fn cmp_popularity(&self, other: &Self) -> Ordering {
+        self.findings
+            .cmp(&other.findings)
+            .then(self.executions.cmp(&other.executions))
+    }
+}
+
+/// Usage counters of all templates, keyed by template ID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageStore {
+    /// Counters per template
+    pub templates: BTreeMap<String, TemplateUsage>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl UsageStore {
+    /// Default store location, `~/.cert-x-gen/stats/usage.json`
+    pub fn default_path() -> PathBuf {
+        PathResolver::user_config_dir()
+            .join("stats")
+            .join("usage.json")
+    }
+
+    /// Load the store at the default location
+    pub fn load_default() -> Result<Self> {
+        Self::load(&Self::default_path())
+    }
+
+    /// Load a store; a missing file is an empty store
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut store: Self = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(e.into()),
+        };
+        store.path = path.to_path_buf();
+        Ok(store)
+    }
+
+    /// Write the store back to where it was loaded from
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Count a scan: each template ran once per target
+    pub fn record_scan<'a>(
+        &mut self,
+        template_ids: impl IntoIterator<Item = &'a str>,
+        targets: usize,
+        results: &ScanResults,
+    ) {
+        let mut findings: HashMap<&str, u64> = HashMap::new();
+        for finding in &results.findings {
+            *findings.entry(finding.template_id.as_str()).or_default() += 1;
+        }
+        let now = results.completed_at.unwrap_or_else(Utc::now);
+        for id in template_ids {
+            let usage = self.templates.entry(id.to_string()).or_default();
+            usage.executions += targets as u64;
+            usage.findings += findings.get(id).copied().unwrap_or_default();
+            usage.last_used = Some(now);
+        }
+    }
+
+    /// Counters for a template, if it was ever run
+    pub fn get(&self, template_id: &str) -> Option<&TemplateUsage> {
+        self.templates.get(template_id)
+    }
+
+    /// Templates ordered from most to least productive
+    pub fn ranked(&self) -> Vec<(&str, &TemplateUsage)> {
+        let mut ranked: Vec<_> = self
+            .templates
+            .iter()
+            .map(|(id, usage)| (id.as_str(), usage))
+            .collect();
+        ranked.sort_by(|(a_id, a), (b_id, b)| b.cmp_popularity(a).then(a_id.cmp(b_id)));
+        ranked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Finding, Severity};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_record_scan_and_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats").join("usage.json");
+        let mut store = UsageStore::load(&path).unwrap();
+        assert!(store.templates.is_empty());
+
+        let mut results = ScanResults::new(Uuid::new_v4());
+        for target in ["10.0.0.1", "10.0.0.2"] {
+            results.add_finding(Finding::new(
+                target,
+                "redis-unauth",
+                Severity::High,
+                "Redis",
+                "no auth",
+            ));
+        }
+        results.complete();
+        store.record_scan(["redis-unauth", "mongo-unauth"], 3, &results);
+        store.record_scan(["mongo-unauth"], 1, &ScanResults::new(Uuid::new_v4()));
+        store.save().unwrap();
+
+        let store = UsageStore::load(&path).unwrap();
+        let redis = store.get("redis-unauth").unwrap();
+        assert_eq!((redis.executions, redis.findings), (3, 2));
+        assert!((redis.hit_rate() - 2.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(redis.last_used, results.completed_at);
+        let mongo = store.get("mongo-unauth").unwrap();
+        assert_eq!((mongo.executions, mongo.findings), (4, 0));
+
+        let ranked: Vec<&str> = store.ranked().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ranked, vec!["redis-unauth", "mongo-unauth"]);
+    }
+}
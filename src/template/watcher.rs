@@ -0,0 +1,385 @@
+//! Template file watcher for hot-reloading during template development
+//! and for picking up templates dropped into a directory at runtime
+
+use super::{Template, TemplateLoader, EXCLUDED_TEMPLATE_DIRS};
+use crate::error::{Error, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Debounce used by [`TemplateLoader::watch_directory`]
+const DIRECTORY_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Change to the set of templates in a watched directory
+pub enum TemplateEvent {
+    /// A new template file appeared
+    Added(Box<dyn Template>),
+    /// An existing template file was rewritten
+    Modified(Box<dyn Template>),
+    /// A template file was deleted (carries the template ID)
+    Removed(String),
+}
+
+impl std::fmt::Debug for TemplateEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added(t) => f.debug_tuple("Added").field(&t.id()).finish(),
+            Self::Modified(t) => f.debug_tuple("Modified").field(&t.id()).finish(),
+            Self::Removed(id) => f.debug_tuple("Removed").field(id).finish(),
+        }
+    }
+}
+
+/// Watches template files and directories and reports debounced changes
+#[allow(missing_debug_implementations)]
+pub struct TemplateWatcher {
+    _watcher: RecommendedWatcher,
+    rx: mpsc::UnboundedReceiver<PathBuf>,
+    debounce: Duration,
+}
+
+impl TemplateWatcher {
+    /// Start watching the given template files and directories
+    ///
+    /// Files are watched through their parent directory so editors that save
+    /// via rename-over-original are still picked up.
+    pub fn new(paths: &[PathBuf], debounce: Duration) -> Result<Self> {
+        let mut files = HashSet::new();
+        let mut dirs = Vec::new();
+        for path in paths {
+            let canonical = path
+                .canonicalize()
+                .map_err(|_| Error::FileNotFound(path.clone()))?;
+            if canonical.is_dir() {
+                dirs.push(canonical);
+            } else {
+                files.insert(canonical);
+            }
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let watched_files = files.clone();
+        let watched_dirs = dirs.clone();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let event = match res {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::warn!("Template watcher error: {}", e);
+                    return;
+                }
+            };
+            if !matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                return;
+            }
+            for path in event.paths {
+                if is_watched(&path, &watched_files, &watched_dirs) {
+                    let _ = tx.send(path);
+                }
+            }
+        })
+        .map_err(|e| Error::config(format!("Failed to start template watcher: {}", e)))?;
+
+        let parents: BTreeSet<&Path> = files.iter().filter_map(|f| f.parent()).collect();
+        for parent in parents {
+            watcher
+                .watch(parent, RecursiveMode::NonRecursive)
+                .map_err(|e| {
+                    Error::config(format!("Failed to watch {}: {}", parent.display(), e))
+                })?;
+        }
+        for dir in &dirs {
+            watcher
+                .watch(dir, RecursiveMode::Recursive)
+                .map_err(|e| Error::config(format!("Failed to watch {}: {}", dir.display(), e)))?;
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+            debounce,
+        })
+    }
+
+    /// Wait for the next batch of changed template files
+    ///
+    /// Rapid successive saves are coalesced: the batch is returned once no new
+    /// change has been seen for the debounce interval. Returns `None` if the
+    /// watcher has stopped.
+    pub async fn next_changes(&mut self) -> Option<Vec<PathBuf>> {
+        let changed = self.next_batch().await?;
+        Some(changed.into_iter().filter(|p| p.is_file()).collect())
+    }
+
+    /// Like [`next_changes`](Self::next_changes), but also reports deleted paths
+    async fn next_batch(&mut self) -> Option<BTreeSet<PathBuf>> {
+        let mut changed = BTreeSet::new();
+        changed.insert(self.rx.recv().await?);
+
+        while let Ok(Some(path)) = tokio::time::timeout(self.debounce, self.rx.recv()).await {
+            changed.insert(path);
+        }
+        Some(changed)
+    }
+}
+
+impl TemplateLoader {
+    /// Watch a template directory and report templates added, rewritten or deleted
+    ///
+    /// Templates already in the directory are indexed but not reported. When a
+    /// file another template depends on changes (such as an included YAML
+    /// snippet inside the directory), each dependent template is reloaded and
+    /// reported as modified. The background task stops when the receiver is
+    /// dropped.
+    pub fn watch_directory(self: Arc<Self>, dir: &Path) -> Result<mpsc::Receiver<TemplateEvent>> {
+        let dir = dir
+            .canonicalize()
+            .map_err(|_| Error::FileNotFound(dir.to_path_buf()))?;
+        let mut watcher = TemplateWatcher::new(std::slice::from_ref(&dir), DIRECTORY_DEBOUNCE)?;
+        let (tx, rx) = mpsc::channel(64);
+
+        tokio::spawn(async move {
+            let mut index = WatchIndex::default();
+            match self.load_templates_from_dir(&dir).await {
+                Ok(templates) => {
+                    for template in &templates {
+                        index.record(template.as_ref());
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to index templates in {}: {}", dir.display(), e);
+                }
+            }
+
+            while let Some(changed) = watcher.next_batch().await {
+                let mut reload = BTreeSet::new();
+                for path in changed {
+                    let dependents = index.dependents(&path);
+                    if !dependents.is_empty() {
+                        reload.extend(dependents);
+                    } else if !is_excluded(&path, &dir) {
+                        reload.insert(path);
+                    }
+                }
+                for path in reload {
+                    for event in self.change_events(&path, &mut index).await {
+                        tracing::debug!("Template change in {}: {:?}", path.display(), event);
+                        if tx.send(event).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+    /// Translate one changed path into events, updating the index
+    async fn change_events(&self, path: &Path, index: &mut WatchIndex) -> Vec<TemplateEvent> {
+        if !path.is_file() {
+            index.dependencies.remove(path);
+            return index
+                .ids
+                .remove(path)
+                .map(TemplateEvent::Removed)
+                .into_iter()
+                .collect();
+        }
+
+        let template = match self.load_template(path).await {
+            Ok(template) => template,
+            Err(e) => {
+                tracing::warn!("Failed to load template {}: {}", path.display(), e);
+                return Vec::new();
+            }
+        };
+        let id = template.id().to_string();
+        match index.record_path(path, template.as_ref()) {
+            Some(previous) if previous == id => vec![TemplateEvent::Modified(template)],
+            // The file now declares a different ID: retire the old one
+            Some(previous) => vec![
+                TemplateEvent::Removed(previous),
+                TemplateEvent::Added(template),
+            ],
+            None => vec![TemplateEvent::Added(template)],
+        }
+    }
+}
+
+/// What the directory watcher remembers about each template file
+///
+/// Deleted files can no longer be parsed, so each path's template ID is kept,
+/// along with the files it was built from.
+#[derive(Default)]
+struct WatchIndex {
+    ids: HashMap<PathBuf, String>,
+    dependencies: HashMap<PathBuf, Vec<PathBuf>>,
+}
+
+impl WatchIndex {
+    fn record(&mut self, template: &dyn Template) {
+        let path = template.metadata().file_path.clone();
+        self.record_path(&path, template);
+    }
+
+    /// Index `template` under `path`, returning the ID previously there
+    fn record_path(&mut self, path: &Path, template: &dyn Template) -> Option<String> {
+        let dependencies = template.dependencies();
+        if dependencies.is_empty() {
+            self.dependencies.remove(path);
+        } else {
+            self.dependencies.insert(path.to_path_buf(), dependencies);
+        }
+        self.ids
+            .insert(path.to_path_buf(), template.id().to_string())
+    }
+
+    /// Template files built from `path`
+    fn dependents(&self, path: &Path) -> Vec<PathBuf> {
+        self.dependencies
+            .iter()
+            .filter(|(_, deps)| deps.iter().any(|d| d == path))
+            .map(|(template, _)| template.clone())
+            .collect()
+    }
+}
+
+/// Whether `path` lies in a directory the loader skips (e.g. `skeleton/`)
+fn is_excluded(path: &Path, root: &Path) -> bool {
+    path.strip_prefix(root)
+        .map(|relative| {
+            relative.parent().is_some_and(|parent| {
+                parent.components().any(|c| {
+                    EXCLUDED_TEMPLATE_DIRS.contains(&c.as_os_str().to_string_lossy().as_ref())
+                })
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Check whether a changed path belongs to the watched templates
+fn is_watched(path: &Path, files: &HashSet<PathBuf>, dirs: &[PathBuf]) -> bool {
+    if files.contains(path) {
+        return true;
+    }
+    dirs.iter().any(|d| path.starts_with(d)) && TemplateLoader::is_valid_template_file(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_watched() {
+        let files: HashSet<PathBuf> = [PathBuf::from("/t/single.py")].into_iter().collect();
+        let dirs = vec![PathBuf::from("/t/dir")];
+
+        assert!(is_watched(Path::new("/t/single.py"), &files, &dirs));
+        assert!(!is_watched(Path::new("/t/other.py"), &files, &dirs));
+        assert!(is_watched(Path::new("/t/dir/sub/check.go"), &files, &dirs));
+        assert!(!is_watched(Path::new("/t/dir/notes.txt"), &files, &dirs));
+    }
+
+    #[tokio::test]
+    async fn test_watcher_debounces_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = dir.path().join("check.yaml");
+        std::fs::write(&template, "id: check\n").unwrap();
+
+        let mut watcher =
+            TemplateWatcher::new(std::slice::from_ref(&template), Duration::from_millis(200))
+                .unwrap();
+
+        for i in 0..3 {
+            std::fs::write(&template, format!("id: check\n# edit {}\n", i)).unwrap();
+        }
+
+        let changes = tokio::time::timeout(Duration::from_secs(5), watcher.next_changes())
+            .await
+            .expect("no change detected")
+            .unwrap();
+        assert_eq!(changes, vec![template.canonicalize().unwrap()]);
+    }
+
+    fn yaml_template(id: &str, severity: &str) -> String {
+        format!(
+            "id: {id}\nname: {id}\nauthor:\n  name: tester\nseverity: {severity}\n\
+             description: watch test\nlanguage: yaml\nhttp:\n  - method: GET\n    path: [\"/\"]\n"
+        )
+    }
+
+    async fn next_event(rx: &mut mpsc::Receiver<TemplateEvent>) -> TemplateEvent {
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no template event")
+            .expect("watcher stopped")
+    }
+
+    #[tokio::test]
+    async fn test_watch_directory_events() {
+        use crate::template::TemplateRegistry;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("existing.yaml"),
+            yaml_template("existing", "low"),
+        )
+        .unwrap();
+
+        let mut loader = TemplateLoader::new();
+        loader.register_engine(Box::new(crate::engine::YamlTemplateEngine::new()));
+        let mut rx = Arc::new(loader).watch_directory(dir.path()).unwrap();
+        let registry = TemplateRegistry::new();
+        // Give the watcher time to index the existing template
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let added = dir.path().join("added.yaml");
+        std::fs::write(&added, yaml_template("added", "info")).unwrap();
+        let event = next_event(&mut rx).await;
+        assert!(matches!(&event, TemplateEvent::Added(t) if t.id() == "added"));
+        registry.apply(event).unwrap();
+        assert!(registry.contains("added"));
+
+        std::fs::write(&added, yaml_template("added", "high")).unwrap();
+        let event = next_event(&mut rx).await;
+        assert!(matches!(
+            &event,
+            TemplateEvent::Modified(t) if t.metadata().severity == crate::types::Severity::High
+        ));
+        registry.apply(event).unwrap();
+
+        std::fs::remove_file(dir.path().join("existing.yaml")).unwrap();
+        let event = next_event(&mut rx).await;
+        assert!(matches!(&event, TemplateEvent::Removed(id) if id == "existing"));
+    }
+
+    #[tokio::test]
+    async fn test_snippet_change_reloads_dependents() {
+        let dir = tempfile::tempdir().unwrap();
+        let snippet = dir.path().join("common.yaml");
+        std::fs::write(&snippet, "variables:\n  probe: /v1\n").unwrap();
+        std::fs::write(
+            dir.path().join("uses-common.yaml"),
+            format!(
+                "{}include: [common.yaml]\n",
+                yaml_template("uses-common", "low")
+            ),
+        )
+        .unwrap();
+
+        let mut loader = TemplateLoader::new();
+        loader.register_engine(Box::new(crate::engine::YamlTemplateEngine::new()));
+        let mut rx = Arc::new(loader).watch_directory(dir.path()).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        std::fs::write(&snippet, "variables:\n  probe: /v2\n").unwrap();
+        let event = next_event(&mut rx).await;
+        assert!(matches!(&event, TemplateEvent::Modified(t) if t.id() == "uses-common"));
+    }
+}
@@ -1,13 +1,18 @@
 //! Core type definitions for CERT-X-GEN
 
-use chrono::{DateTime, Utc};
+use crate::matcher::LatencyBaselines;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 use uuid::Uuid;
 
+pub mod normalize;
+pub use normalize::TargetNormalizer;
+
 /// Severity levels for findings
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -77,6 +82,22 @@ pub enum TemplateLanguage {
     Perl,
     /// PHP interpreted templates
     Php,
+    /// Rhai scripts run by the embedded interpreter
+    Rhai,
+}
+
+impl TemplateLanguage {
+    /// Whether templates in this language are compiled before execution
+    pub fn is_compiled(&self) -> bool {
+        matches!(
+            self,
+            TemplateLanguage::Rust
+                | TemplateLanguage::C
+                | TemplateLanguage::Cpp
+                | TemplateLanguage::Java
+                | TemplateLanguage::Go
+        )
+    }
 }
 
 impl std::fmt::Display for TemplateLanguage {
@@ -94,6 +115,7 @@ impl std::fmt::Display for TemplateLanguage {
             TemplateLanguage::Ruby => write!(f, "ruby"),
             TemplateLanguage::Perl => write!(f, "perl"),
             TemplateLanguage::Php => write!(f, "php"),
+            TemplateLanguage::Rhai => write!(f, "rhai"),
         }
     }
 }
@@ -144,6 +166,50 @@ impl std::fmt::Display for Protocol {
     }
 }
 
+/// How the scheme of an HTTP target was settled while preparing the scan's targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SchemeSource {
+    /// Written in the scope entry (`https://host:8080`)
+    Explicit,
+    /// A TLS handshake or plaintext request to the port (`--detect-scheme`)
+    Probe,
+    /// The `network.scheme_ports` table
+    PortTable,
+}
+
+/// Ports assumed to serve plain HTTP or HTTPS when nothing else tells
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SchemePorts {
+    /// Plain HTTP ports
+    pub http: Vec<u16>,
+    /// HTTPS ports
+    pub https: Vec<u16>,
+}
+
+impl Default for SchemePorts {
+    fn default() -> Self {
+        Self {
+            http: vec![80, 8000, 8080],
+            https: vec![443, 8443],
+        }
+    }
+}
+
+impl SchemePorts {
+    /// Scheme the table gives `port`; HTTPS if it is listed under both
+    pub fn scheme_for(&self, port: u16) -> Option<Protocol> {
+        if self.https.contains(&port) {
+            Some(Protocol::Https)
+        } else if self.http.contains(&port) {
+            Some(Protocol::Http)
+        } else {
+            None
+        }
+    }
+}
+
 /// Scan target specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Target {
@@ -155,8 +221,43 @@ pub struct Target {
     pub port: Option<u16>,
     /// Protocol to use
     pub protocol: Protocol,
-    /// Additional metadata
+    /// Path prefix for HTTP targets (e.g. one API endpoint)
+    #[serde(default)]
+    pub base_path: Option<String>,
+    /// Additional metadata, exposed to templates as context variables
     pub metadata: HashMap<String, String>,
+    /// Grouping labels (`env=prod`, `owner=web`) copied onto the target's findings
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+    /// Headers sent with every HTTP request to this target (`# header:` scope annotations)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_headers: Vec<(String, String)>,
+    /// How `protocol` was chosen for an HTTP target; `None` leaves the first
+    /// scheme to the default port table (see [`Target::infer_scheme`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheme_source: Option<SchemeSource>,
+}
+
+/// Targets are equal when they address the same endpoint: protocol, address,
+/// port and base path. The ID, metadata, labels and custom headers are not compared.
+impl PartialEq for Target {
+    fn eq(&self, other: &Self) -> bool {
+        self.protocol == other.protocol
+            && self.address == other.address
+            && self.port == other.port
+            && self.base_path == other.base_path
+    }
+}
+
+impl Eq for Target {}
+
+impl std::hash::Hash for Target {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.protocol.hash(state);
+        self.address.hash(state);
+        self.port.hash(state);
+        self.base_path.hash(state);
+    }
 }
 
 impl Target {
@@ -167,7 +268,11 @@ impl Target {
             address: address.into(),
             port: None,
             protocol,
+            base_path: None,
             metadata: HashMap::new(),
+            labels: HashMap::new(),
+            custom_headers: Vec::new(),
+            scheme_source: None,
         }
     }
 
@@ -178,7 +283,21 @@ impl Target {
             address: address.into(),
             port: Some(port),
             protocol,
+            base_path: None,
             metadata: HashMap::new(),
+            labels: HashMap::new(),
+            custom_headers: Vec::new(),
+            scheme_source: None,
+        }
+    }
+
+    /// Add this target's custom headers to a request's, keeping any header
+    /// (compared case-insensitively) the request already sets
+    pub fn merge_custom_headers(&self, headers: &mut HashMap<String, String>) {
+        for (name, value) in &self.custom_headers {
+            if !headers.keys().any(|key| key.eq_ignore_ascii_case(name)) {
+                headers.insert(name.clone(), value.clone());
+            }
         }
     }
 
@@ -191,10 +310,11 @@ impl Target {
                 } else {
                     "http"
                 };
+                let base_path = self.base_path.as_deref().unwrap_or("");
                 if let Some(port) = self.port {
-                    format!("{}://{}:{}", scheme, self.address, port)
+                    format!("{}://{}:{}{}", scheme, self.address, port, base_path)
                 } else {
-                    format!("{}://{}", scheme, self.address)
+                    format!("{}://{}{}", scheme, self.address, base_path)
                 }
             }
             _ => {
@@ -207,6 +327,15 @@ impl Target {
         }
     }
 
+    /// URL of `path` on this target; `/` on a target with a base path is the base path itself
+    pub fn url_with_path(&self, path: &str) -> String {
+        if self.base_path.is_some() && path == "/" {
+            self.url()
+        } else {
+            format!("{}{}", self.url(), path)
+        }
+    }
+
     /// Get socket address if possible
     pub fn socket_addr(&self) -> Option<SocketAddr> {
         if let Ok(ip) = self.address.parse::<IpAddr>() {
@@ -235,13 +364,20 @@ impl Target {
         }
     }
 
-    /// Smart scheme selection based on port
+    /// Scheme to try first: `protocol` once target preparation settled it,
+    /// otherwise the default port table's, falling back to `protocol`
     pub fn infer_scheme(&self) -> Protocol {
-        match self.port {
-            Some(443) | Some(8443) => Protocol::Https,
-            Some(80) | Some(8080) | Some(8000) => Protocol::Http,
-            _ => self.protocol.clone(),
+        if self.scheme_source.is_some() {
+            return self.protocol.clone();
         }
+        self.port
+            .and_then(|port| SchemePorts::default().scheme_for(port))
+            .unwrap_or_else(|| self.protocol.clone())
+    }
+
+    /// Whether a probe confirmed the scheme, so the other one need not be tried
+    pub fn scheme_is_probed(&self) -> bool {
+        self.scheme_source == Some(SchemeSource::Probe)
     }
 }
 
@@ -276,6 +412,15 @@ pub struct Context {
     /// Cookies for authenticated scans
     #[serde(default)]
     pub cookies: Vec<(String, String)>,
+    /// Per-target latency baselines for `latency` matchers, shared by clones
+    #[serde(skip)]
+    pub latency_baselines: Arc<LatencyBaselines>,
+    /// Working directories of script and compiled template runs
+    #[serde(skip)]
+    pub workspace: Option<Arc<crate::workspace::ScanWorkspace>>,
+    /// Named user sessions templates send requests as (`session: user_a`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sessions: Vec<crate::session::SessionState>,
 }
 
 impl Default for Context {
@@ -294,10 +439,35 @@ impl Default for Context {
             override_ports: None,
             headers: Vec::new(),
             cookies: Vec::new(),
+            latency_baselines: Arc::default(),
+            workspace: None,
+            sessions: Vec::new(),
         }
     }
 }
 
+impl Context {
+    /// The session called `name`, if the scan defines it
+    pub fn session(&self, name: &str) -> Option<&crate::session::SessionState> {
+        self.sessions.iter().find(|session| session.name == name)
+    }
+
+    /// This context with `session`'s credentials: its cookies instead of
+    /// the scan's, its headers over the scan's
+    pub fn as_session(&self, session: &crate::session::SessionState) -> Context {
+        let mut context = self.clone();
+        context.cookies = session.cookies.clone();
+        context.headers.retain(|(name, _)| {
+            !session
+                .headers
+                .iter()
+                .any(|(own, _)| own.eq_ignore_ascii_case(name))
+        });
+        context.headers.extend(session.headers.iter().cloned());
+        context
+    }
+}
+
 /// Evidence for a finding
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Evidence {
@@ -342,6 +512,76 @@ impl Default for Evidence {
     }
 }
 
+/// Product identified on a target, collected by the `inventory` output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Technology {
+    /// Product name
+    pub product: String,
+    /// Vendor, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<String>,
+    /// Detected version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// CPE 2.3 name given by the template; see [`Technology::cpe`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpe: Option<String>,
+}
+
+impl Technology {
+    /// Technology with only a product name
+    pub fn new<S: Into<String>>(product: S) -> Self {
+        Self {
+            product: product.into(),
+            vendor: None,
+            version: None,
+            cpe: None,
+        }
+    }
+
+    /// CPE 2.3 name: the template's, or a best-effort application CPE built
+    /// from vendor (the product when unknown), product and version
+    ///
+    /// Without a version no CPE is generated, since it would match every release.
+    pub fn cpe(&self) -> Option<String> {
+        if let Some(cpe) = &self.cpe {
+            return Some(cpe.clone());
+        }
+        let version = self.version.as_deref().filter(|v| !v.trim().is_empty())?;
+        let product = cpe_component(&self.product);
+        let vendor = self
+            .vendor
+            .as_deref()
+            .map(cpe_component)
+            .unwrap_or_else(|| product.clone());
+        Some(format!(
+            "cpe:2.3:a:{}:{}:{}:*:*:*:*:*:*:*",
+            vendor,
+            product,
+            cpe_component(version)
+        ))
+    }
+}
+
+/// One CPE 2.3 formatted-string component: lowercase, spaces as underscores,
+/// punctuation other than `-`, `.` and `_` escaped with a backslash
+fn cpe_component(value: &str) -> String {
+    let mut component = String::with_capacity(value.len());
+    for c in value.trim().to_lowercase().chars() {
+        match c {
+            ' ' => component.push('_'),
+            c if c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_') => component.push(c),
+            c if c.is_ascii_punctuation() => {
+                component.push('\\');
+                component.push(c);
+            }
+            // Non-ASCII characters cannot appear in a CPE name
+            _ => {}
+        }
+    }
+    component
+}
+
 /// Security finding
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Finding {
@@ -373,10 +613,31 @@ pub struct Finding {
     pub references: Vec<String>,
     /// Tags
     pub tags: Vec<String>,
+    /// Product the finding identified, for the technology inventory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub technology: Option<Technology>,
     /// Timestamp
     pub timestamp: DateTime<Utc>,
+    /// PNG of the finding's page (`--screenshot`); each report format stores it its own way
+    #[serde(skip)]
+    pub screenshot: Option<Vec<u8>>,
+    /// Labels of the target the finding was produced for; not part of its identity
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub target_labels: BTreeMap<String, String>,
+    /// Response-similarity cluster (`--cluster`), e.g. `git-exposed#2`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster_id: Option<String>,
+    /// Findings folded into this one by `--collapse-clusters`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicates: Option<usize>,
+    /// Targets of every finding in a collapsed cluster, this one's included
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub affected_targets: Vec<String>,
 }
 
+/// Directory, next to file reports, that finding screenshots are written to
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
 impl Finding {
     /// Create a new finding
     pub fn new<S: Into<String>>(
@@ -401,7 +662,13 @@ impl Finding {
             remediation: None,
             references: Vec::new(),
             tags: Vec::new(),
+            technology: None,
             timestamp: Utc::now(),
+            screenshot: None,
+            target_labels: BTreeMap::new(),
+            cluster_id: None,
+            duplicates: None,
+            affected_targets: Vec::new(),
         }
     }
 
@@ -434,6 +701,72 @@ impl Finding {
         self.cvss_score = Some(score);
         self
     }
+
+    /// Set the identified technology
+    pub fn with_technology(mut self, technology: Technology) -> Self {
+        self.technology = Some(technology);
+        self
+    }
+
+    /// Path of the finding's screenshot relative to file reports
+    /// (`screenshots/<id>.png`)
+    pub fn screenshot_path(&self) -> String {
+        format!("{}/{}.png", SCREENSHOT_DIR, self.id)
+    }
+
+    /// Attach a PNG screenshot and reference its report path from the evidence
+    pub fn attach_screenshot(&mut self, png: Vec<u8>) {
+        self.evidence
+            .add_data("screenshot", serde_json::json!(self.screenshot_path()));
+        self.screenshot = Some(png);
+    }
+
+    /// Append reference URLs the finding does not already carry
+    pub fn merge_references(&mut self, references: &[String]) {
+        for reference in references {
+            if !self.references.contains(reference) {
+                self.references.push(reference.clone());
+            }
+        }
+    }
+
+    /// Target labels as `key=value` pairs joined by `sep`, in key order
+    pub fn labels_display(&self, sep: &str) -> String {
+        self.target_labels
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(sep)
+    }
+
+    /// Deterministic identifier for the same issue across scans
+    ///
+    /// Unlike [`id`](Self::id), which is random per finding, this hashes the
+    /// template, target, title and matched patterns, so re-reporting a finding
+    /// yields the same value. HTTP findings target the URL they were served
+    /// from, so the same issue over `http://` and `https://` stays apart.
+    pub fn fingerprint(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        let mut patterns: Vec<&str> = self
+            .evidence
+            .matched_patterns
+            .iter()
+            .map(String::as_str)
+            .collect();
+        patterns.sort_unstable();
+
+        let mut hasher = Sha256::new();
+        for part in [&self.template_id, &self.target, &self.title] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        for pattern in patterns {
+            hasher.update(pattern.as_bytes());
+            hasher.update([0]);
+        }
+        hex::encode(hasher.finalize())
+    }
 }
 
 /// Scan statistics
@@ -445,14 +778,70 @@ pub struct ScanStatistics {
     pub templates_executed: usize,
     /// Findings by severity
     pub findings_by_severity: HashMap<Severity, usize>,
+    /// Findings produced by the scan
+    #[serde(default)]
+    pub total_findings: usize,
+    /// Findings left after the report severity threshold
+    #[serde(default)]
+    pub reported_findings: usize,
     /// Total network requests
     pub network_requests: usize,
+    /// Network requests issued to each host
+    #[serde(default)]
+    pub requests_per_host: BTreeMap<String, u64>,
+    /// Template/target pairs skipped because a scan request budget was spent
+    #[serde(default)]
+    pub skipped_work_units: usize,
     /// Total data transferred (bytes)
     pub data_transferred: u64,
     /// Scan duration
     pub duration: Duration,
     /// Success rate
     pub success_rate: f64,
+    /// Peak number of templates running at once, per engine
+    #[serde(default)]
+    pub engine_peak_concurrency: BTreeMap<String, usize>,
+    /// Seed of a randomized target or template order (`--seed` replays it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Limits set by `--adaptive-concurrency`, starting with the initial one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub concurrency_timeline: Vec<ConcurrencyChange>,
+}
+
+/// A change of the adaptive concurrency limit
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConcurrencyChange {
+    /// Milliseconds into the scan
+    pub elapsed_ms: u64,
+    /// Templates allowed to run at once from then on
+    pub concurrency: usize,
+    /// Share of failed requests in the window that led to the change
+    pub error_rate: f64,
+}
+
+/// Liveness of one target, gathered from the requests the scan made (`--probe-summary`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetProbe {
+    /// Target as given to the scan
+    pub target: String,
+    /// Whether any connection to the target succeeded
+    pub reachable: bool,
+    /// Lowest HTTP status code received
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lowest_status: Option<u16>,
+    /// `Server` response header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// `<title>` of the first HTML page received
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Common name of the TLS certificate the target presented
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_cn: Option<String>,
+    /// Last connection error, for targets that were never reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 /// Scan results
@@ -470,6 +859,9 @@ pub struct ScanResults {
     pub statistics: ScanStatistics,
     /// Errors encountered
     pub errors: Vec<String>,
+    /// Per-target liveness, when `--probe-summary` is set
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<TargetProbe>,
 }
 
 impl ScanResults {
@@ -482,6 +874,7 @@ impl ScanResults {
             findings: Vec::new(),
             statistics: ScanStatistics::default(),
             errors: Vec::new(),
+            targets: Vec::new(),
         }
     }
 
@@ -492,9 +885,31 @@ impl ScanResults {
             .findings_by_severity
             .entry(finding.severity)
             .or_insert(0) += 1;
+        self.statistics.total_findings += 1;
+        self.statistics.reported_findings += 1;
         self.findings.push(finding);
     }
 
+    /// Drop findings below `min` from the report; the scan totals are kept
+    pub fn retain_min_severity(&mut self, min: Severity) {
+        self.findings.retain(|f| f.severity >= min);
+        self.statistics
+            .findings_by_severity
+            .retain(|severity, _| *severity >= min);
+        self.statistics.reported_findings = self.findings.len();
+    }
+
+    /// Drop findings with a confidence below `min` from the report; the scan totals are kept
+    pub fn retain_min_confidence(&mut self, min: u8) {
+        self.findings.retain(|f| f.confidence >= min);
+        let mut by_severity = HashMap::new();
+        for finding in &self.findings {
+            *by_severity.entry(finding.severity).or_insert(0) += 1;
+        }
+        self.statistics.findings_by_severity = by_severity;
+        self.statistics.reported_findings = self.findings.len();
+    }
+
     /// Mark scan as complete
     pub fn complete(&mut self) {
         self.completed_at = Some(Utc::now());
@@ -505,6 +920,17 @@ impl ScanResults {
         }
     }
 
+    /// Finding counts per value of the target label `key`; `None` counts findings without it
+    pub fn findings_by_label(&self, key: &str) -> BTreeMap<Option<String>, usize> {
+        let mut counts = BTreeMap::new();
+        for finding in &self.findings {
+            *counts
+                .entry(finding.target_labels.get(key).cloned())
+                .or_insert(0) += 1;
+        }
+        counts
+    }
+
     /// Get critical findings
     pub fn critical_findings(&self) -> Vec<&Finding> {
         self.findings
@@ -543,6 +969,9 @@ pub struct TemplateMetadata {
     pub cwe_ids: Vec<String>,
     /// CVSS score
     pub cvss_score: Option<f32>,
+    /// Advisory, PoC, vendor bulletin or NVD URLs
+    #[serde(default)]
+    pub references: Vec<String>,
     /// Tags
     #[serde(default)]
     pub tags: Vec<String>,
@@ -562,6 +991,44 @@ pub struct TemplateMetadata {
     pub version: String,
     /// Confidence (0-100)
     pub confidence: Option<u8>,
+    /// Requests per second for this template, overriding looser global limits
+    #[serde(default)]
+    pub rate_limit: Option<u32>,
+    /// Most requests one execution against a target may send
+    #[serde(
+        default,
+        rename = "max-requests",
+        alias = "max_requests",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_requests: Option<u64>,
+    /// Kept for existing `--templates` selections; full scans skip it
+    #[serde(default)]
+    pub deprecated: bool,
+    /// ID of the template that replaces this one
+    #[serde(
+        default,
+        rename = "superseded-by",
+        alias = "superseded_by",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub superseded_by: Option<String>,
+    /// Date the template was added to the collection, when it records one
+    #[serde(
+        default,
+        rename = "created-date",
+        alias = "created_date",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub created_date: Option<NaiveDate>,
+    /// IDs of templates that must report a finding on a target before this one runs there
+    #[serde(
+        default,
+        rename = "depends-on",
+        alias = "depends_on",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub depends_on: Vec<String>,
 }
 
 /// Author information
@@ -588,6 +1055,60 @@ fn default_version() -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_finding_fingerprint_is_deterministic() {
+        let make = || {
+            let mut finding = Finding::new("host:80", "tpl", Severity::Low, "Title", "desc");
+            finding.evidence.matched_patterns = vec!["b".to_string(), "a".to_string()];
+            finding
+        };
+        let (a, mut b) = (make(), make());
+        assert_ne!(a.id, b.id);
+        b.evidence.matched_patterns.reverse();
+        assert_eq!(a.fingerprint(), b.fingerprint());
+
+        // Labels are metadata, not identity
+        b.target_labels
+            .insert("owner".to_string(), "web".to_string());
+        assert_eq!(a.fingerprint(), b.fingerprint());
+
+        b.target = "host:8080".to_string();
+        assert_ne!(a.fingerprint(), b.fingerprint());
+
+        let (mut http, mut https) = (make(), make());
+        http.target = "http://host".to_string();
+        https.target = "https://host".to_string();
+        assert_ne!(http.fingerprint(), https.fingerprint());
+    }
+
+    #[test]
+    fn test_technology_cpe() {
+        let mut nginx = Technology::new("nginx");
+        assert_eq!(nginx.cpe(), None);
+
+        nginx.version = Some("1.18.0".to_string());
+        assert_eq!(
+            nginx.cpe().unwrap(),
+            "cpe:2.3:a:nginx:nginx:1.18.0:*:*:*:*:*:*:*"
+        );
+
+        let tomcat = Technology {
+            vendor: Some("Apache".to_string()),
+            version: Some("9.0.1 (RC:1)".to_string()),
+            ..Technology::new("Tomcat Server")
+        };
+        assert_eq!(
+            tomcat.cpe().unwrap(),
+            r"cpe:2.3:a:apache:tomcat_server:9.0.1_\(rc\:1\):*:*:*:*:*:*:*"
+        );
+
+        let given = Technology {
+            cpe: Some("cpe:2.3:a:f5:nginx:1.18.0:*:*:*:*:*:*:*".to_string()),
+            ..nginx
+        };
+        assert_eq!(given.cpe(), given.cpe);
+    }
+
     #[test]
     fn test_severity_ordering() {
         assert!(Severity::Critical > Severity::High);
@@ -605,6 +1126,95 @@ mod tests {
         assert_eq!(target.url(), "http://example.com");
     }
 
+    #[test]
+    fn test_target_custom_headers() {
+        let mut target = Target::new("api.example.com", Protocol::Https);
+        // Targets without custom headers serialize as before
+        let json = serde_json::to_value(&target).unwrap();
+        assert!(json.get("custom_headers").is_none());
+
+        target.custom_headers = vec![("Authorization".to_string(), "Bearer xyz".to_string())];
+        let restored: Target =
+            serde_json::from_str(&serde_json::to_string(&target).unwrap()).unwrap();
+        assert_eq!(restored.custom_headers, target.custom_headers);
+
+        let mut headers = HashMap::from([("authorization".to_string(), "Basic a".to_string())]);
+        restored.merge_custom_headers(&mut headers);
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["authorization"], "Basic a");
+        let mut headers = HashMap::new();
+        restored.merge_custom_headers(&mut headers);
+        assert_eq!(headers["Authorization"], "Bearer xyz");
+    }
+
+    #[test]
+    fn test_context_as_session() {
+        let mut user_a = crate::session::SessionState::new("user_a");
+        user_a.cookies = vec![("sid".to_string(), "a".to_string())];
+        user_a.headers = vec![("authorization".to_string(), "Bearer a".to_string())];
+        let context = Context {
+            headers: vec![
+                ("Authorization".to_string(), "Bearer admin".to_string()),
+                ("X-Scan".to_string(), "1".to_string()),
+            ],
+            cookies: vec![("sid".to_string(), "admin".to_string())],
+            sessions: vec![user_a.clone()],
+            ..Context::default()
+        };
+        assert_eq!(context.session("user_a"), Some(&user_a));
+        assert!(context.session("user_b").is_none());
+
+        let scoped = context.as_session(&user_a);
+        assert_eq!(scoped.cookies, user_a.cookies);
+        assert_eq!(
+            scoped.headers,
+            vec![
+                ("X-Scan".to_string(), "1".to_string()),
+                ("authorization".to_string(), "Bearer a".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_target_scheme_source() {
+        let mut target = Target::with_port("example.com", 8443, Protocol::Http);
+        assert_eq!(target.infer_scheme(), Protocol::Https);
+        assert!(serde_json::to_value(&target)
+            .unwrap()
+            .get("scheme_source")
+            .is_none());
+
+        // A probed scheme beats the port table
+        target.scheme_source = Some(SchemeSource::Probe);
+        assert_eq!(target.infer_scheme(), Protocol::Http);
+        assert!(target.scheme_is_probed());
+        let json = serde_json::to_value(&target).unwrap();
+        assert_eq!(json["scheme_source"], "probe");
+
+        let ports = SchemePorts {
+            http: vec![8443],
+            https: vec![],
+        };
+        assert_eq!(ports.scheme_for(8443), Some(Protocol::Http));
+        assert_eq!(ports.scheme_for(9000), None);
+        assert_eq!(
+            SchemePorts::default().scheme_for(443),
+            Some(Protocol::Https)
+        );
+    }
+
+    #[test]
+    fn test_target_base_path() {
+        let mut target = Target::with_port("api.example.com", 8080, Protocol::Http);
+        target.base_path = Some("/v1/users/1".to_string());
+        assert_eq!(target.url(), "http://api.example.com:8080/v1/users/1");
+        assert_eq!(target.url_with_path("/"), target.url());
+        assert_eq!(
+            target.url_with_path("/.git/config"),
+            "http://api.example.com:8080/v1/users/1/.git/config"
+        );
+    }
+
     #[test]
     fn test_finding_creation() {
         let finding = Finding::new(
@@ -617,4 +1227,94 @@ mod tests {
         assert_eq!(finding.severity, Severity::Critical);
         assert_eq!(finding.confidence, 90);
     }
+
+    fn results_with(severities: &[Severity]) -> ScanResults {
+        let mut results = ScanResults::new(Uuid::new_v4());
+        for severity in severities {
+            results.add_finding(Finding::new("t", "id", *severity, "title", "desc"));
+        }
+        results
+    }
+
+    #[test]
+    fn test_min_severity_keeps_findings_at_threshold() {
+        let all = [
+            Severity::Info,
+            Severity::Low,
+            Severity::Medium,
+            Severity::High,
+            Severity::Critical,
+        ];
+
+        let mut results = results_with(&all);
+        results.retain_min_severity(Severity::Medium);
+        let kept: Vec<Severity> = results.findings.iter().map(|f| f.severity).collect();
+        assert_eq!(
+            kept,
+            vec![Severity::Medium, Severity::High, Severity::Critical]
+        );
+        assert_eq!(results.statistics.total_findings, 5);
+        assert_eq!(results.statistics.reported_findings, 3);
+        assert_eq!(
+            results.statistics.findings_by_severity.get(&Severity::Low),
+            None
+        );
+        assert_eq!(
+            results.statistics.findings_by_severity[&Severity::Medium],
+            1
+        );
+
+        let mut results = results_with(&all);
+        results.retain_min_severity(Severity::Info);
+        assert_eq!(results.statistics.reported_findings, 5);
+
+        let mut results = results_with(&all);
+        results.retain_min_severity(Severity::Critical);
+        assert_eq!(results.statistics.reported_findings, 1);
+    }
+
+    #[test]
+    fn test_min_confidence_keeps_findings_at_threshold() {
+        let mut results = results_with(&[Severity::High, Severity::High, Severity::Low]);
+        for (finding, confidence) in results.findings.iter_mut().zip([95, 30, 60]) {
+            finding.confidence = confidence;
+        }
+
+        results.retain_min_confidence(60);
+        let kept: Vec<u8> = results.findings.iter().map(|f| f.confidence).collect();
+        assert_eq!(kept, vec![95, 60]);
+        assert_eq!(results.statistics.total_findings, 3);
+        assert_eq!(results.statistics.reported_findings, 2);
+        assert_eq!(results.statistics.findings_by_severity[&Severity::High], 1);
+        assert_eq!(results.statistics.findings_by_severity[&Severity::Low], 1);
+
+        results.retain_min_confidence(100);
+        assert!(results.findings.is_empty());
+        assert!(results.statistics.findings_by_severity.is_empty());
+    }
+
+    #[test]
+    fn test_findings_by_label() {
+        let mut results = results_with(&[Severity::High, Severity::Low, Severity::Info]);
+        for (finding, owner) in results.findings.iter_mut().zip(["web", "web"]) {
+            finding
+                .target_labels
+                .insert("owner".to_string(), owner.to_string());
+        }
+
+        let counts = results.findings_by_label("owner");
+        assert_eq!(counts.get(&Some("web".to_string())), Some(&2));
+        assert_eq!(counts.get(&None), Some(&1));
+        assert_eq!(results.findings_by_label("env").get(&None), Some(&3));
+        assert_eq!(results.findings[0].labels_display(","), "owner=web");
+    }
+
+    #[test]
+    fn test_min_severity_on_empty_results() {
+        let mut results = results_with(&[Severity::Low, Severity::Low]);
+        results.retain_min_severity(Severity::High);
+        assert!(results.findings.is_empty());
+        assert_eq!(results.statistics.total_findings, 2);
+        assert_eq!(results.statistics.reported_findings, 0);
+    }
 }
@@ -0,0 +1,200 @@
+//! Target normalization
+//!
+//! `https://example.com`, `https://example.com:443/` and `HTTPS://Example.COM`
+//! are the same endpoint. Normalizing targets before deduplication keeps a
+//! scan from hitting it several times.
+
+use super::{Protocol, Target};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+
+/// Canonicalizes targets and drops duplicates
+#[derive(Debug, Clone, Default)]
+pub struct TargetNormalizer {
+    resolve_aliases: bool,
+}
+
+impl TargetNormalizer {
+    /// Normalizer that compares targets by their canonical URL only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also drop hostnames that resolve to an IPv4 address already in scope
+    pub fn with_alias_resolution(mut self, enabled: bool) -> Self {
+        self.resolve_aliases = enabled;
+        self
+    }
+
+    /// Lowercase the host, drop the protocol's default port and a root path
+    pub fn normalize(&self, mut target: Target) -> Target {
+        target.address = target.address.to_lowercase();
+        if target.port.is_some() && target.port == default_port(&target.protocol) {
+            target.port = None;
+        }
+        target.base_path = target
+            .base_path
+            .map(|path| path.trim_end_matches('/').to_string())
+            .filter(|path| !path.is_empty());
+        target
+    }
+
+    /// Normalize `targets` and keep the first of each equivalent group, in order
+    ///
+    /// The kept target also gets labels only its dropped duplicates carried.
+    pub async fn dedup(&self, targets: Vec<Target>) -> Vec<Target> {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut unique: Vec<Target> = Vec::new();
+        for target in targets.into_iter().map(|target| self.normalize(target)) {
+            match seen.get(&target.url()) {
+                Some(&index) => {
+                    for (key, value) in target.labels {
+                        unique[index].labels.entry(key).or_insert(value);
+                    }
+                }
+                None => {
+                    seen.insert(target.url(), unique.len());
+                    unique.push(target);
+                }
+            }
+        }
+        if !self.resolve_aliases {
+            return unique;
+        }
+
+        let in_scope: HashSet<(IpAddr, Option<u16>, String)> = unique
+            .iter()
+            .filter_map(|t| {
+                Some((
+                    t.address.parse::<IpAddr>().ok()?,
+                    t.port,
+                    t.protocol.to_string(),
+                ))
+            })
+            .collect();
+        if in_scope.is_empty() {
+            return unique;
+        }
+
+        let mut aliases = HashSet::new();
+        for target in &unique {
+            if target.address.parse::<IpAddr>().is_ok() || target.base_path.is_some() {
+                continue;
+            }
+            let resolved = tokio::net::lookup_host((target.address.as_str(), 0)).await;
+            let alias = resolved.into_iter().flatten().find(|addr| {
+                addr.is_ipv4()
+                    && in_scope.contains(&(addr.ip(), target.port, target.protocol.to_string()))
+            });
+            if let Some(addr) = alias {
+                tracing::info!(
+                    "Skipping {}: resolves to {}, which is already in scope",
+                    target.url(),
+                    addr.ip()
+                );
+                aliases.insert(target.id);
+            }
+        }
+        unique.retain(|target| !aliases.contains(&target.id));
+        unique
+    }
+}
+
+fn default_port(protocol: &Protocol) -> Option<u16> {
+    match protocol {
+        Protocol::Http => Some(80),
+        Protocol::Https => Some(443),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn https(address: &str, port: Option<u16>, base_path: Option<&str>) -> Target {
+        let mut target = Target::new(address, Protocol::Https);
+        target.port = port;
+        target.base_path = base_path.map(str::to_string);
+        target
+    }
+
+    #[test]
+    fn test_equivalent_urls_normalize_to_one() {
+        let normalizer = TargetNormalizer::new();
+        let variants = [
+            https("example.com", None, None),
+            https("example.com", Some(443), None),
+            https("example.com", None, Some("/")),
+            https("Example.COM", None, None),
+        ];
+        for target in variants {
+            assert_eq!(normalizer.normalize(target).url(), "https://example.com");
+        }
+
+        let http = Target::with_port("example.com", 80, Protocol::Http);
+        assert_eq!(normalizer.normalize(http).url(), "http://example.com");
+
+        // Non-default ports, other protocols and real paths are kept
+        let alt = https("example.com", Some(8443), Some("/api/"));
+        assert_eq!(
+            normalizer.normalize(alt).url(),
+            "https://example.com:8443/api"
+        );
+        let tcp = Target::with_port("DB.example.com", 443, Protocol::Tcp);
+        assert_eq!(normalizer.normalize(tcp).url(), "db.example.com:443");
+    }
+
+    #[test]
+    fn test_normalized_url_round_trips() {
+        let normalizer = TargetNormalizer::new();
+        for target in [
+            https("Example.COM", Some(443), Some("/")),
+            https("example.com", Some(8443), Some("/v1")),
+            Target::with_port("10.0.0.1", 80, Protocol::Http),
+        ] {
+            let normalized = normalizer.normalize(target);
+            let url = url::Url::parse(&normalized.url()).unwrap();
+            let mut reparsed = Target::new(
+                url.host_str().unwrap(),
+                if url.scheme() == "http" {
+                    Protocol::Http
+                } else {
+                    Protocol::Https
+                },
+            );
+            reparsed.port = url.port();
+            reparsed.base_path = Some(url.path().to_string());
+            assert_eq!(normalizer.normalize(reparsed).url(), normalized.url());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dedup_keeps_first_and_drops_ip_aliases() {
+        let targets = vec![
+            https("localhost", None, None),
+            https("LOCALHOST", Some(443), None),
+            https("127.0.0.1", None, None),
+            https("localhost", Some(8443), None),
+        ];
+
+        let unique = TargetNormalizer::new().dedup(targets.clone()).await;
+        assert_eq!(unique.len(), 3);
+        assert_eq!(unique[0].id, targets[0].id);
+
+        let mut labeled = targets.clone();
+        labeled[0].labels.insert("env".into(), "prod".into());
+        labeled[1].labels.insert("env".into(), "staging".into());
+        labeled[1].labels.insert("owner".into(), "web".into());
+        let unique = TargetNormalizer::new().dedup(labeled).await;
+        assert_eq!(unique[0].labels["env"], "prod");
+        assert_eq!(unique[0].labels["owner"], "web");
+
+        let unique = TargetNormalizer::new()
+            .with_alias_resolution(true)
+            .dedup(targets)
+            .await;
+        let urls: Vec<String> = unique.iter().map(|t| t.url()).collect();
+        assert_eq!(urls, vec!["https://127.0.0.1", "https://localhost:8443"]);
+    }
+}
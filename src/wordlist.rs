@@ -0,0 +1,328 @@
+//! Wordlists shipped with the binary
+//!
+//! A few curated lists (admin panel paths, sensitive file names, default
+//! credential pairs, SSRF parameter names) are compiled in from
+//! `src/data/wordlists/`. Templates reference them from `payloads:` with a
+//! `wordlist:` value:
+//!
+//! ```yaml
+//! payloads:
+//!   path: "wordlist:admin-panels"
+//!   param: [url, "wordlist:ssrf-params"]   # mixed with inline values
+//! ```
+//!
+//! A `<name>.txt` file in `~/.cert-x-gen/wordlists/` replaces the embedded
+//! list of that name, or adds a new one. Lists are only read and split into
+//! entries when a template or `cxg wordlist` asks for them. One entry per
+//! line; blank lines and lines starting with `#` are skipped.
+
+use crate::error::{Error, Result};
+use crate::template::PathResolver;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Prefix of a payload value that names a wordlist
+pub const WORDLIST_PREFIX: &str = "wordlist:";
+
+/// A wordlist compiled into the binary
+struct EmbeddedWordlist {
+    name: &'static str,
+    version: u32,
+    contents: &'static str,
+}
+
+const EMBEDDED: &[EmbeddedWordlist] = &[
+    EmbeddedWordlist {
+        name: "admin-panels",
+        version: 1,
+        contents: include_str!("data/wordlists/admin-panels.txt"),
+    },
+    EmbeddedWordlist {
+        name: "default-credentials",
+        version: 1,
+        contents: include_str!("data/wordlists/default-credentials.txt"),
+    },
+    EmbeddedWordlist {
+        name: "sensitive-files",
+        version: 1,
+        contents: include_str!("data/wordlists/sensitive-files.txt"),
+    },
+    EmbeddedWordlist {
+        name: "ssrf-params",
+        version: 1,
+        contents: include_str!("data/wordlists/ssrf-params.txt"),
+    },
+];
+
+/// Where a wordlist was loaded from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase", tag = "kind", content = "path")]
+pub enum WordlistSource {
+    /// Compiled into the binary
+    Embedded,
+    /// A file in the user wordlist directory
+    User(PathBuf),
+}
+
+impl fmt::Display for WordlistSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WordlistSource::Embedded => write!(f, "embedded"),
+            WordlistSource::User(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A resolved wordlist
+#[derive(Debug, Clone, Serialize)]
+pub struct Wordlist {
+    /// Name used in `wordlist:<name>` references
+    pub name: String,
+    /// Version of the embedded list; `None` for user files
+    pub version: Option<u32>,
+    /// First comment line of the file
+    pub description: Option<String>,
+    /// Where the list came from
+    pub source: WordlistSource,
+    /// Raw file contents, comments included
+    #[serde(skip)]
+    pub contents: String,
+}
+
+impl Wordlist {
+    /// The list's entries, without blank and comment lines
+    pub fn entries(&self) -> Vec<String> {
+        parse_entries(&self.contents)
+    }
+}
+
+/// Entries of a wordlist file: one per line, skipping blanks and `#` comments
+pub fn parse_entries(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+/// Name referenced by a `wordlist:<name>` payload value
+pub fn reference_name(value: &str) -> Option<&str> {
+    value.strip_prefix(WORDLIST_PREFIX).map(str::trim)
+}
+
+/// Resolves wordlist names: user files first, then the embedded lists
+#[derive(Debug, Clone)]
+pub struct WordlistStore {
+    user_dir: Option<PathBuf>,
+}
+
+impl Default for WordlistStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WordlistStore {
+    /// Store with overrides from `~/.cert-x-gen/wordlists/`
+    pub fn new() -> Self {
+        Self {
+            user_dir: Some(Self::default_user_dir()),
+        }
+    }
+
+    /// Store with overrides from `dir`
+    pub fn with_user_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            user_dir: Some(dir.into()),
+        }
+    }
+
+    /// Store with the embedded lists only
+    pub fn embedded_only() -> Self {
+        Self { user_dir: None }
+    }
+
+    /// Default user wordlist directory
+    pub fn default_user_dir() -> PathBuf {
+        PathResolver::user_config_dir().join("wordlists")
+    }
+
+    /// Load the wordlist called `name`
+    pub fn load(&self, name: &str) -> Result<Wordlist> {
+        if let Some(path) = self.user_file(name) {
+            let contents = std::fs::read_to_string(&path).map_err(|e| {
+                Error::config(format!("Failed to read wordlist {}: {}", path.display(), e))
+            })?;
+            return Ok(Wordlist {
+                name: name.to_string(),
+                version: None,
+                description: description(&contents),
+                source: WordlistSource::User(path),
+                contents,
+            });
+        }
+        let embedded = EMBEDDED.iter().find(|w| w.name == name).ok_or_else(|| {
+            Error::Validation(format!(
+                "Unknown wordlist '{}' (run 'cxg wordlist list' to see the available lists)",
+                name
+            ))
+        })?;
+        Ok(Wordlist {
+            name: name.to_string(),
+            version: Some(embedded.version),
+            description: description(embedded.contents),
+            source: WordlistSource::Embedded,
+            contents: embedded.contents.to_string(),
+        })
+    }
+
+    /// Whether `name` resolves, without reading it
+    pub fn contains(&self, name: &str) -> bool {
+        self.user_file(name).is_some() || EMBEDDED.iter().any(|w| w.name == name)
+    }
+
+    /// Every available wordlist, by name; user files replace embedded lists
+    pub fn list(&self) -> Result<Vec<Wordlist>> {
+        let mut names: Vec<String> = EMBEDDED.iter().map(|w| w.name.to_string()).collect();
+        if let Some(dir) = self.user_dir.as_deref().filter(|d| d.is_dir()) {
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "txt") {
+                    if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                        names.push(stem.to_string());
+                    }
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
+        names.iter().map(|name| self.load(name)).collect()
+    }
+
+    /// Replace every `wordlist:<name>` value in `payloads` with the list's entries
+    pub fn expand_payloads(&self, payloads: &mut BTreeMap<String, Vec<String>>) -> Result<()> {
+        for (payload, values) in payloads.iter_mut() {
+            if !values.iter().any(|v| reference_name(v).is_some()) {
+                continue;
+            }
+            let mut expanded = Vec::new();
+            for value in values.iter() {
+                match reference_name(value) {
+                    Some(name) => expanded.extend(
+                        self.load(name)
+                            .map_err(|e| {
+                                let reason = match e {
+                                    Error::Validation(reason) => reason,
+                                    e => e.message(),
+                                };
+                                Error::Validation(format!("Payload '{}': {}", payload, reason))
+                            })?
+                            .entries(),
+                    ),
+                    None => expanded.push(value.clone()),
+                }
+            }
+            *values = expanded;
+        }
+        Ok(())
+    }
+
+    fn user_file(&self, name: &str) -> Option<PathBuf> {
+        // Names are plain file stems; anything else could escape the directory
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return None;
+        }
+        let path = self.user_dir.as_deref()?.join(format!("{}.txt", name));
+        path.is_file().then_some(path)
+    }
+}
+
+/// First `#` comment line of a wordlist, as its description
+fn description(contents: &str) -> Option<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .and_then(|line| line.strip_prefix('#'))
+        .map(|line| line.trim().to_string())
+}
+
+/// Write a wordlist's raw contents to `path`
+pub fn export(wordlist: &Wordlist, path: &Path) -> Result<()> {
+    crate::output::write_atomic(path, &wordlist.contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_lists_load() {
+        let store = WordlistStore::embedded_only();
+        let names: Vec<String> = store.list().unwrap().into_iter().map(|w| w.name).collect();
+        assert_eq!(
+            names,
+            [
+                "admin-panels",
+                "default-credentials",
+                "sensitive-files",
+                "ssrf-params"
+            ]
+        );
+        for wordlist in store.list().unwrap() {
+            assert_eq!(wordlist.source, WordlistSource::Embedded);
+            assert!(wordlist.description.is_some(), "{}", wordlist.name);
+            let entries = wordlist.entries();
+            assert!(!entries.is_empty());
+            assert!(entries.iter().all(|e| !e.starts_with('#')));
+        }
+        assert!(store.load("nope").is_err());
+    }
+
+    #[test]
+    fn test_user_file_overrides_embedded_list() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("admin-panels.txt"),
+            "# Ours\n/staff\n\n/ops\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("extra.txt"), "/x\n").unwrap();
+        let store = WordlistStore::with_user_dir(dir.path());
+
+        let admin = store.load("admin-panels").unwrap();
+        assert_eq!(admin.entries(), ["/staff", "/ops"]);
+        assert_eq!(admin.version, None);
+        assert!(matches!(admin.source, WordlistSource::User(_)));
+        assert!(store.contains("extra"));
+        assert_eq!(store.list().unwrap().len(), 5);
+        assert!(!store.contains("../admin-panels"));
+    }
+
+    #[test]
+    fn test_expand_payloads() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("short.txt"), "a\nb\n").unwrap();
+        let store = WordlistStore::with_user_dir(dir.path());
+
+        let mut payloads = BTreeMap::from([
+            (
+                "param".to_string(),
+                vec!["first".to_string(), "wordlist:short".to_string()],
+            ),
+            ("plain".to_string(), vec!["x".to_string()]),
+        ]);
+        store.expand_payloads(&mut payloads).unwrap();
+        assert_eq!(payloads["param"], ["first", "a", "b"]);
+        assert_eq!(payloads["plain"], ["x"]);
+
+        let mut missing = BTreeMap::from([("p".to_string(), vec!["wordlist:gone".to_string()])]);
+        let err = store.expand_payloads(&mut missing).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Payload 'p': Unknown wordlist 'gone'"));
+    }
+}
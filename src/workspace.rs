@@ -0,0 +1,308 @@
+//! Per-scan working directories for script and compiled templates
+//!
+//! Every (template, target) execution of an external template gets its own
+//! scratch directory under the scan's workspace:
+//!
+//! ```text
+//! ~/.cert-x-gen/work/<scan_id>/<template_id>/<target>/
+//! ```
+//!
+//! The directory is the process's working directory and is passed in
+//! `CXG_WORKDIR`. A finding can point at files the template left there with
+//! an `artifacts` evidence entry (a path or a list of paths, relative to the
+//! working directory):
+//!
+//! ```json
+//! {"evidence": {"data": {"artifacts": ["dump.bin", "loot/config.xml"]}}}
+//! ```
+//!
+//! The workspace is removed when the scan ends unless artifacts are kept
+//! (`cxg scan --keep-artifacts`), in which case referenced artifacts are also
+//! copied to `artifacts/<finding id>/` next to the results. Workspaces older
+//! than the retention period are pruned when a scan starts.
+
+use crate::error::Result;
+use crate::template::PathResolver;
+use crate::types::{Finding, ScanResults, Target};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+/// Environment variable holding an execution's working directory
+pub const WORKDIR_ENV: &str = "CXG_WORKDIR";
+
+/// Evidence data key listing a finding's artifact files
+pub const ARTIFACTS_KEY: &str = "artifacts";
+
+/// Directory, next to file reports, that kept artifacts are copied to
+pub const ARTIFACT_DIR: &str = "artifacts";
+
+/// Working directories of one scan
+#[derive(Debug, Clone)]
+pub struct ScanWorkspace {
+    dir: PathBuf,
+}
+
+impl ScanWorkspace {
+    /// Workspace of `scan_id` under `base`; nothing is created until an
+    /// execution asks for its directory
+    pub fn new(base: impl AsRef<Path>, scan_id: Uuid) -> Self {
+        Self {
+            dir: base.as_ref().join(scan_id.to_string()),
+        }
+    }
+
+    /// Default directory holding every scan's workspace
+    pub fn default_base() -> PathBuf {
+        PathResolver::user_config_dir().join("work")
+    }
+
+    /// `<base>/<scan_id>`
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Create and return the directory for running `template_id` on `target`
+    pub fn execution_dir(&self, template_id: &str, target: &Target) -> Result<PathBuf> {
+        let target_name = match target.port {
+            Some(port) => format!("{}_{}", target.address, port),
+            None => target.address.clone(),
+        };
+        let dir = self
+            .dir
+            .join(crate::network::trace::path_component(template_id))
+            .join(crate::network::trace::path_component(&target_name));
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Delete the workspace and everything in it
+    pub fn remove(&self) -> Result<()> {
+        match std::fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Copy the artifacts `results` reference into `artifacts/<finding id>/`
+    /// beside `report` and point the evidence at the copies
+    ///
+    /// Only files inside this workspace are copied; other references are
+    /// left as they are. Returns the number of files copied.
+    pub fn collect_artifacts(&self, results: &mut ScanResults, report: &Path) -> Result<usize> {
+        let Ok(workspace) = self.dir.canonicalize() else {
+            return Ok(0);
+        };
+        let report_dir = report.parent().unwrap_or_else(|| Path::new(""));
+        let mut copied = 0;
+        for finding in &mut results.findings {
+            let Some(paths) = artifact_paths(finding) else {
+                continue;
+            };
+            let mut kept = Vec::with_capacity(paths.len());
+            let mut names = HashSet::new();
+            for path in paths {
+                let source = match Path::new(&path).canonicalize() {
+                    Ok(source) if source.starts_with(&workspace) && source.is_file() => source,
+                    _ => {
+                        tracing::warn!(
+                            "Artifact {} of finding {} is not a file in the scan workspace; not copied",
+                            path,
+                            finding.id
+                        );
+                        kept.push(path);
+                        continue;
+                    }
+                };
+                let name = source
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                // Two artifacts of a finding may share a file name
+                let name = std::iter::once(name.clone())
+                    .chain((1..).map(|n| format!("{}-{}", n, name)))
+                    .find(|candidate| names.insert(candidate.clone()))
+                    .unwrap_or(name);
+                let relative = format!("{}/{}/{}", ARTIFACT_DIR, finding.id, name);
+                let destination = report_dir.join(&relative);
+                if let Some(parent) = destination.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::copy(&source, &destination)?;
+                copied += 1;
+                kept.push(relative);
+            }
+            finding
+                .evidence
+                .add_data(ARTIFACTS_KEY, serde_json::json!(kept));
+        }
+        Ok(copied)
+    }
+}
+
+/// Make a finding's relative artifact paths absolute under `workdir`
+///
+/// Also normalizes a single path to a one-element list.
+pub fn resolve_artifacts(findings: &mut [Finding], workdir: Option<&Path>) {
+    let Some(workdir) = workdir else {
+        return;
+    };
+    for finding in findings {
+        let Some(paths) = artifact_paths(finding) else {
+            continue;
+        };
+        let resolved: Vec<String> = paths
+            .into_iter()
+            .map(|path| workdir.join(path).to_string_lossy().into_owned())
+            .collect();
+        finding
+            .evidence
+            .add_data(ARTIFACTS_KEY, serde_json::json!(resolved));
+    }
+}
+
+/// The `artifacts` evidence entry as a list of paths
+fn artifact_paths(finding: &Finding) -> Option<Vec<String>> {
+    match finding.evidence.data.get(ARTIFACTS_KEY)? {
+        serde_json::Value::String(path) => Some(vec![path.clone()]),
+        serde_json::Value::Array(paths) => Some(
+            paths
+                .iter()
+                .filter_map(|p| p.as_str().map(String::from))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Remove scan workspaces under `base` last modified more than `max_age` ago
+///
+/// Returns the number of workspaces removed. A missing `base` is not an error.
+pub fn prune(base: &Path, max_age: Duration) -> Result<usize> {
+    if !base.is_dir() {
+        return Ok(0);
+    }
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for entry in std::fs::read_dir(base)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok());
+        if metadata.is_dir() && age.is_some_and(|age| age > max_age) {
+            std::fs::remove_dir_all(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Protocol, Severity};
+
+    fn finding(artifacts: serde_json::Value) -> Finding {
+        let mut finding = Finding::new("10.0.0.1:8080", "dump", Severity::High, "Dump", "");
+        finding.evidence.add_data(ARTIFACTS_KEY, artifacts);
+        finding
+    }
+
+    #[test]
+    fn test_execution_dirs() {
+        let base = tempfile::tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let workspace = ScanWorkspace::new(base.path(), id);
+        assert!(!workspace.dir().exists());
+
+        let target = Target::with_port("10.0.0.1", 8080, Protocol::Http);
+        let dir = workspace.execution_dir("../escape", &target).unwrap();
+        assert!(dir.is_dir());
+        assert_eq!(
+            dir,
+            base.path()
+                .join(id.to_string())
+                .join(".._escape")
+                .join("10.0.0.1_8080")
+        );
+
+        workspace.remove().unwrap();
+        assert!(!workspace.dir().exists());
+        workspace.remove().unwrap();
+    }
+
+    #[test]
+    fn test_collect_artifacts() {
+        let base = tempfile::tempdir().unwrap();
+        let workspace = ScanWorkspace::new(base.path(), Uuid::new_v4());
+        let target = Target::with_port("10.0.0.1", 8080, Protocol::Http);
+        let workdir = workspace.execution_dir("dump", &target).unwrap();
+        std::fs::create_dir(workdir.join("loot")).unwrap();
+        std::fs::write(workdir.join("dump.bin"), b"dump").unwrap();
+        std::fs::write(workdir.join("loot/dump.bin"), b"second").unwrap();
+        let outside = tempfile::NamedTempFile::new().unwrap();
+
+        let mut findings = vec![
+            finding(serde_json::json!("dump.bin")),
+            finding(serde_json::json!([
+                "dump.bin",
+                "loot/dump.bin",
+                "missing.txt",
+                outside.path().to_str().unwrap()
+            ])),
+        ];
+        resolve_artifacts(&mut findings, Some(&workdir));
+        assert_eq!(
+            findings[0].evidence.data[ARTIFACTS_KEY],
+            serde_json::json!([workdir.join("dump.bin").to_str().unwrap()])
+        );
+
+        let mut results = ScanResults::new(Uuid::new_v4());
+        results.findings = findings;
+        let reports = tempfile::tempdir().unwrap();
+        let copied = workspace
+            .collect_artifacts(&mut results, &reports.path().join("scan.json"))
+            .unwrap();
+        assert_eq!(copied, 3);
+
+        let second = &results.findings[1];
+        let paths = second.evidence.data[ARTIFACTS_KEY].as_array().unwrap();
+        let first_copy = format!("artifacts/{}/dump.bin", second.id);
+        let second_copy = format!("artifacts/{}/1-dump.bin", second.id);
+        assert_eq!(paths[0], first_copy.as_str());
+        assert_eq!(paths[1], second_copy.as_str());
+        assert!(paths[2].as_str().unwrap().ends_with("missing.txt"));
+        assert_eq!(paths[3], outside.path().to_str().unwrap());
+        assert_eq!(
+            std::fs::read(reports.path().join(&first_copy)).unwrap(),
+            b"dump"
+        );
+        assert_eq!(
+            std::fs::read(reports.path().join(&second_copy)).unwrap(),
+            b"second"
+        );
+    }
+
+    #[test]
+    fn test_prune_old_workspaces() {
+        let base = tempfile::tempdir().unwrap();
+        let old = ScanWorkspace::new(base.path(), Uuid::new_v4());
+        std::fs::create_dir_all(old.dir()).unwrap();
+        let week_ago = SystemTime::now() - Duration::from_secs(7 * 24 * 3600);
+        std::fs::File::open(old.dir())
+            .unwrap()
+            .set_modified(week_ago)
+            .unwrap();
+        let fresh = ScanWorkspace::new(base.path(), Uuid::new_v4());
+        std::fs::create_dir_all(fresh.dir()).unwrap();
+
+        let removed = prune(base.path(), Duration::from_secs(24 * 3600)).unwrap();
+        assert_eq!(removed, 1);
+        assert!(!old.dir().exists());
+        assert!(fresh.dir().exists());
+        assert_eq!(prune(&base.path().join("none"), Duration::ZERO).unwrap(), 0);
+    }
+}
@@ -12,6 +12,7 @@
 #   Severity: high
 #   Tags: skeleton, example
 #   Language: perl
+#   References: advisory, PoC, vendor bulletin or NVD URLs (comma-separated)
 # 
 # Execution:
 #   perl template.pl --target example.com --json
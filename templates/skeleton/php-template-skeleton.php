@@ -12,6 +12,7 @@
  *   Severity: high
  *   Tags: skeleton, example
  *   Language: php
+ *   References: advisory, PoC, vendor bulletin or NVD URLs (comma-separated)
  * 
  * Execution:
  *   php template.php --target example.com --json